@@ -0,0 +1,4 @@
+use criterion::{criterion_group, criterion_main};
+
+criterion_group!(engine, potatodb::bench::benches);
+criterion_main!(engine);
@@ -0,0 +1,106 @@
+#ifndef POTATODB_H
+#define POTATODB_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct potatodb_t potatodb_t;
+
+typedef struct potatodb_result_t potatodb_result_t;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Opens the database stored at `path`, or an empty database when nothing
+// is stored there yet. Returns NULL if `path` is NULL or not UTF-8, or if
+// the file cannot be read.
+//
+// # Safety
+// `path` must be NULL or a valid NUL-terminated string.
+struct potatodb_t *potatodb_open(const char *path);
+
+// Opens an empty database that is not stored anywhere until
+// `potatodb_save` writes it.
+struct potatodb_t *potatodb_open_memory(void);
+
+// Releases a database returned by `potatodb_open` or `potatodb_open_memory`.
+// Unsaved changes are lost.
+//
+// # Safety
+// `db` must be NULL or a pointer returned by `potatodb_open` or
+// `potatodb_open_memory` that has not been closed.
+void potatodb_close(struct potatodb_t *db);
+
+// Writes the database to `path`. Returns 0 on success and -1 on failure.
+//
+// # Safety
+// `db` must be a live handle and `path` a valid NUL-terminated string.
+int potatodb_save(const struct potatodb_t *db, const char *path);
+
+// Creates an empty table. Returns 0 on success and -1 on failure.
+//
+// # Safety
+// `db` must be a live handle and `name` a valid NUL-terminated string.
+int potatodb_create_table(struct potatodb_t *db, const char *name);
+
+// Runs a SQL statement. Always returns a result that must be released with
+// `potatodb_free_result`; check `potatodb_result_error` for failures.
+//
+// # Safety
+// `db` must be a live handle and `sql` a valid NUL-terminated string.
+struct potatodb_result_t *potatodb_execute_sql(struct potatodb_t *db, const char *sql);
+
+// Releases a result returned by `potatodb_execute_sql`, including every
+// string obtained from it.
+//
+// # Safety
+// `result` must be NULL or a pointer returned by `potatodb_execute_sql` that has not been freed.
+void potatodb_free_result(struct potatodb_result_t *result);
+
+// Returns the error message of a failed statement, or NULL on success.
+//
+// # Safety
+// `result` must be a live result.
+const char *potatodb_result_error(const struct potatodb_result_t *result);
+
+// # Safety
+// `result` must be a live result.
+size_t potatodb_result_row_count(const struct potatodb_result_t *result);
+
+// Returns the record id of a row, or 0 if `row` is out of range.
+//
+// # Safety
+// `result` must be a live result.
+uint64_t potatodb_result_row_id(const struct potatodb_result_t *result, size_t row);
+
+// # Safety
+// `result` must be a live result.
+size_t potatodb_result_column_count(const struct potatodb_result_t *result, size_t row);
+
+// Returns the name of a column in a row, or NULL if out of range. The string
+// is owned by the result.
+//
+// # Safety
+// `result` must be a live result.
+const char *potatodb_result_column_name(const struct potatodb_result_t *result,
+                                        size_t row,
+                                        size_t column);
+
+// Returns the value of a column in a row, or NULL if out of range. The string
+// is owned by the result.
+//
+// # Safety
+// `result` must be a live result.
+const char *potatodb_result_value(const struct potatodb_result_t *result,
+                                  size_t row,
+                                  size_t column);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* POTATODB_H */
@@ -0,0 +1,229 @@
+use std::any::Any;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+
+use potatodb::{Database, Record};
+
+pub struct Handle {
+    db: Database,
+}
+
+pub struct QueryResult {
+    error: Option<CString>,
+    rows: Vec<Row>,
+}
+
+struct Row {
+    id: u64,
+    // sorted by column name so indexes are stable across calls
+    cells: Vec<(CString, CString)>,
+}
+
+// Values with interior NUL bytes cannot be represented as C strings.
+fn c_string(s: &str) -> Result<CString, String> {
+    CString::new(s).map_err(|_| format!("Value {:?} contains a NUL byte and cannot be returned through the C API", s))
+}
+
+fn error_string(e: &str) -> CString {
+    CString::new(e.replace('\0', "\\0")).unwrap()
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    let message = payload.downcast_ref::<&str>().copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic");
+    format!("Internal error: {}", message)
+}
+
+// A panic must never unwind across `extern "C"`; every entry point runs
+// through this and reports `fallback` instead.
+fn guard<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+unsafe fn str_arg<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        None
+    } else {
+        CStr::from_ptr(s).to_str().ok()
+    }
+}
+
+impl Row {
+    fn from_record(record: &Record) -> Result<Self, String> {
+        let mut cells: Vec<(CString, CString)> = record.data().iter()
+            .map(|(k, v)| Ok((c_string(k)?, c_string(v)?)))
+            .collect::<Result<_, String>>()?;
+        cells.sort();
+        Ok(Row { id: record.id(), cells })
+    }
+}
+
+/// Opens the database stored at `path`, or an empty database when nothing
+/// is stored there yet. Returns NULL if `path` is NULL or not UTF-8, or if
+/// the file cannot be read.
+///
+/// # Safety
+/// `path` must be NULL or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn potatodb_open(path: *const c_char) -> *mut Handle {
+    guard(ptr::null_mut(), || {
+        let db = match str_arg(path) {
+            Some(path) if Path::new(path).exists() => match Database::load(path) {
+                Ok(db) => db,
+                Err(_) => return ptr::null_mut(),
+            },
+            Some(_) => Database::new(),
+            None => return ptr::null_mut(),
+        };
+        Box::into_raw(Box::new(Handle { db }))
+    })
+}
+
+/// Opens an empty database that is not stored anywhere until
+/// `potatodb_save` writes it.
+#[no_mangle]
+pub extern "C" fn potatodb_open_memory() -> *mut Handle {
+    guard(ptr::null_mut(), || Box::into_raw(Box::new(Handle { db: Database::new() })))
+}
+
+/// Releases a database returned by `potatodb_open` or `potatodb_open_memory`.
+/// Unsaved changes are lost.
+///
+/// # Safety
+/// `db` must be NULL or a pointer returned by `potatodb_open` or
+/// `potatodb_open_memory` that has not been closed.
+#[no_mangle]
+pub unsafe extern "C" fn potatodb_close(db: *mut Handle) {
+    guard((), || {
+        if !db.is_null() {
+            drop(Box::from_raw(db));
+        }
+    })
+}
+
+/// Writes the database to `path`. Returns 0 on success and -1 on failure.
+///
+/// # Safety
+/// `db` must be a live handle and `path` a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn potatodb_save(db: *const Handle, path: *const c_char) -> c_int {
+    guard(-1, || match (db.as_ref(), str_arg(path)) {
+        (Some(handle), Some(path)) if handle.db.save(path).is_ok() => 0,
+        _ => -1,
+    })
+}
+
+/// Creates an empty table. Returns 0 on success and -1 on failure.
+///
+/// # Safety
+/// `db` must be a live handle and `name` a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn potatodb_create_table(db: *mut Handle, name: *const c_char) -> c_int {
+    guard(-1, || match (db.as_mut(), str_arg(name)) {
+        (Some(handle), Some(name)) => match handle.db.create_table(name.to_string()) {
+            Ok(()) => 0,
+            Err(_) => -1,
+        },
+        _ => -1,
+    })
+}
+
+/// Runs a SQL statement. Always returns a result that must be released with
+/// `potatodb_free_result`; check `potatodb_result_error` for failures.
+///
+/// # Safety
+/// `db` must be a live handle and `sql` a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn potatodb_execute_sql(db: *mut Handle, sql: *const c_char) -> *mut QueryResult {
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let records = match (db.as_mut(), str_arg(sql)) {
+            (Some(handle), Some(sql)) => handle.db.execute_sql(sql)?,
+            (None, _) => return Err("Invalid database handle".to_string()),
+            (_, None) => return Err("Invalid SQL string".to_string()),
+        };
+        records.iter().map(Row::from_record).collect::<Result<Vec<_>, _>>()
+    }))
+    .unwrap_or_else(|payload| Err(panic_message(payload.as_ref())));
+    let result = match result {
+        Ok(rows) => QueryResult { error: None, rows },
+        Err(e) => QueryResult { error: Some(error_string(&e)), rows: Vec::new() },
+    };
+    Box::into_raw(Box::new(result))
+}
+
+/// Releases a result returned by `potatodb_execute_sql`, including every
+/// string obtained from it.
+///
+/// # Safety
+/// `result` must be NULL or a pointer returned by `potatodb_execute_sql` that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn potatodb_free_result(result: *mut QueryResult) {
+    guard((), || {
+        if !result.is_null() {
+            drop(Box::from_raw(result));
+        }
+    })
+}
+
+/// Returns the error message of a failed statement, or NULL on success.
+///
+/// # Safety
+/// `result` must be a live result.
+#[no_mangle]
+pub unsafe extern "C" fn potatodb_result_error(result: *const QueryResult) -> *const c_char {
+    guard(ptr::null(), || {
+        result.as_ref()
+            .and_then(|r| r.error.as_ref())
+            .map_or(ptr::null(), |e| e.as_ptr())
+    })
+}
+
+/// # Safety
+/// `result` must be a live result.
+#[no_mangle]
+pub unsafe extern "C" fn potatodb_result_row_count(result: *const QueryResult) -> usize {
+    guard(0, || result.as_ref().map_or(0, |r| r.rows.len()))
+}
+
+/// Returns the record id of a row, or 0 if `row` is out of range.
+///
+/// # Safety
+/// `result` must be a live result.
+#[no_mangle]
+pub unsafe extern "C" fn potatodb_result_row_id(result: *const QueryResult, row: usize) -> u64 {
+    guard(0, || result.as_ref().and_then(|r| r.rows.get(row)).map_or(0, |r| r.id))
+}
+
+/// # Safety
+/// `result` must be a live result.
+#[no_mangle]
+pub unsafe extern "C" fn potatodb_result_column_count(result: *const QueryResult, row: usize) -> usize {
+    guard(0, || result.as_ref().and_then(|r| r.rows.get(row)).map_or(0, |r| r.cells.len()))
+}
+
+/// Returns the name of a column in a row, or NULL if out of range. The string
+/// is owned by the result.
+///
+/// # Safety
+/// `result` must be a live result.
+#[no_mangle]
+pub unsafe extern "C" fn potatodb_result_column_name(result: *const QueryResult, row: usize, column: usize) -> *const c_char {
+    guard(ptr::null(), || cell(result, row, column).map_or(ptr::null(), |(name, _)| name.as_ptr()))
+}
+
+/// Returns the value of a column in a row, or NULL if out of range. The string
+/// is owned by the result.
+///
+/// # Safety
+/// `result` must be a live result.
+#[no_mangle]
+pub unsafe extern "C" fn potatodb_result_value(result: *const QueryResult, row: usize, column: usize) -> *const c_char {
+    guard(ptr::null(), || cell(result, row, column).map_or(ptr::null(), |(_, value)| value.as_ptr()))
+}
+
+unsafe fn cell<'a>(result: *const QueryResult, row: usize, column: usize) -> Option<&'a (CString, CString)> {
+    result.as_ref()?.rows.get(row)?.cells.get(column)
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+use potatodb::Database;
+use potatodb_ffi::*;
+
+fn sql(s: &str) -> CString {
+    CString::new(s).unwrap()
+}
+
+unsafe fn text<'a>(p: *const std::ffi::c_char) -> &'a str {
+    CStr::from_ptr(p).to_str().unwrap()
+}
+
+#[test]
+fn execute_and_read_rows() {
+    unsafe {
+        let db = potatodb_open_memory();
+        assert!(!db.is_null());
+        assert_eq!(potatodb_create_table(db, sql("users").as_ptr()), 0);
+        assert_eq!(potatodb_create_table(db, sql("users").as_ptr()), -1);
+
+        let insert = potatodb_execute_sql(db, sql("INSERT INTO users (name, age) VALUES (Alice, 30)").as_ptr());
+        assert!(potatodb_result_error(insert).is_null());
+        potatodb_free_result(insert);
+
+        let result = potatodb_execute_sql(db, sql("SELECT * FROM users").as_ptr());
+        assert!(potatodb_result_error(result).is_null());
+        assert_eq!(potatodb_result_row_count(result), 1);
+        assert_eq!(potatodb_result_row_id(result, 0), 1);
+        assert_eq!(potatodb_result_column_count(result, 0), 2);
+        assert_eq!(text(potatodb_result_column_name(result, 0, 0)), "age");
+        assert_eq!(text(potatodb_result_value(result, 0, 0)), "30");
+        assert_eq!(text(potatodb_result_column_name(result, 0, 1)), "name");
+        assert_eq!(text(potatodb_result_value(result, 0, 1)), "Alice");
+        assert!(potatodb_result_value(result, 0, 2).is_null());
+        assert!(potatodb_result_value(result, 1, 0).is_null());
+        potatodb_free_result(result);
+
+        potatodb_close(db);
+    }
+}
+
+#[test]
+fn errors_are_reported_in_the_result() {
+    unsafe {
+        let db = potatodb_open_memory();
+        let result = potatodb_execute_sql(db, sql("SELECT * FROM missing").as_ptr());
+        assert_eq!(text(potatodb_result_error(result)), "Table not found");
+        assert_eq!(potatodb_result_row_count(result), 0);
+        potatodb_free_result(result);
+
+        let result = potatodb_execute_sql(db, ptr::null());
+        assert!(!potatodb_result_error(result).is_null());
+        potatodb_free_result(result);
+        potatodb_close(db);
+    }
+}
+
+#[test]
+fn malformed_sql_does_not_unwind_into_the_caller() {
+    unsafe {
+        let db = potatodb_open_memory();
+        for statement in ["", "   ", "SELECT * FROM"] {
+            let result = potatodb_execute_sql(db, sql(statement).as_ptr());
+            assert!(!potatodb_result_error(result).is_null(), "{:?} should fail", statement);
+            potatodb_free_result(result);
+        }
+        potatodb_close(db);
+    }
+}
+
+#[test]
+fn values_with_nul_bytes_are_an_error() {
+    let path = std::env::temp_dir().join(format!("potatodb-ffi-nul-{}.bin", std::process::id()));
+    let mut db = Database::new();
+    db.create_table("t".to_string()).unwrap();
+    db.insert("t", 1, HashMap::from([("a".to_string(), "x\0y".to_string())])).unwrap();
+    db.save(path.to_str().unwrap()).unwrap();
+
+    unsafe {
+        let handle = potatodb_open(sql(path.to_str().unwrap()).as_ptr());
+        assert!(!handle.is_null());
+        let result = potatodb_execute_sql(handle, sql("SELECT * FROM t").as_ptr());
+        assert!(text(potatodb_result_error(result)).contains("NUL byte"));
+        potatodb_free_result(result);
+        potatodb_close(handle);
+    }
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn invalid_paths_open_nothing() {
+    unsafe {
+        assert!(potatodb_open(ptr::null()).is_null());
+        let not_utf8 = CString::new(vec![b'd', 0xff, b'b']).unwrap();
+        assert!(potatodb_open(not_utf8.as_ptr()).is_null());
+
+        // a path with nothing stored yet opens empty
+        let path = std::env::temp_dir().join(format!("potatodb-ffi-new-{}.bin", std::process::id()));
+        let handle = potatodb_open(sql(path.to_str().unwrap()).as_ptr());
+        assert!(!handle.is_null());
+        potatodb_close(handle);
+    }
+}
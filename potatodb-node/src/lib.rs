@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use napi::bindgen_prelude::{AsyncTask, BigInt, Buffer};
+use napi::{Env, Error, Result, Task};
+use napi_derive::napi;
+use potatodb::Database;
+
+fn to_napi_err(e: impl ToString) -> Error {
+    Error::from_reason(e.to_string())
+}
+
+// Cell values are strings because that is all the engine stores; Buffer
+// support currently covers whole-database images (fromBuffer/toBuffer).
+#[napi(object)]
+pub struct Row {
+    pub id: BigInt,
+    pub data: HashMap<String, String>,
+}
+
+// Shared with the libuv worker threads that run the async methods.
+type SharedDatabase = Arc<Mutex<Database>>;
+
+fn lock(db: &SharedDatabase) -> Result<std::sync::MutexGuard<'_, Database>> {
+    db.lock().map_err(|_| to_napi_err("Database lock poisoned"))
+}
+
+#[napi(js_name = "Database")]
+pub struct JsDatabase {
+    db: SharedDatabase,
+    path: Option<String>,
+}
+
+#[napi]
+impl JsDatabase {
+    #[napi(constructor)]
+    pub fn new(path: Option<String>) -> Result<Self> {
+        let db = match &path {
+            Some(path) if Path::new(path).exists() => Database::load(path).map_err(to_napi_err)?,
+            _ => Database::new(),
+        };
+        Ok(JsDatabase { db: Arc::new(Mutex::new(db)), path })
+    }
+
+    #[napi(factory)]
+    pub fn from_buffer(buffer: Buffer) -> Result<Self> {
+        let db = Database::from_bytes(&buffer).map_err(to_napi_err)?;
+        Ok(JsDatabase { db: Arc::new(Mutex::new(db)), path: None })
+    }
+
+    #[napi]
+    pub fn to_buffer(&self) -> Result<Buffer> {
+        let bytes = lock(&self.db)?.to_bytes().map_err(to_napi_err)?;
+        Ok(bytes.into())
+    }
+
+    #[napi]
+    pub fn create_table(&self, name: String) -> Result<()> {
+        lock(&self.db)?.create_table(name).map_err(to_napi_err)
+    }
+
+    #[napi]
+    pub fn tables(&self) -> Result<Vec<String>> {
+        let mut tables: Vec<String> = lock(&self.db)?.list_tables().into_iter().map(String::from).collect();
+        tables.sort();
+        Ok(tables)
+    }
+
+    #[napi]
+    pub fn execute_sync(&self, sql: String) -> Result<Vec<Row>> {
+        execute(&self.db, &sql)
+    }
+
+    #[napi(ts_return_type = "Promise<Row[]>")]
+    pub fn execute(&self, sql: String) -> AsyncTask<Execute> {
+        AsyncTask::new(Execute { db: self.db.clone(), sql })
+    }
+
+    #[napi(ts_return_type = "Promise<void>")]
+    pub fn save(&self, path: Option<String>) -> Result<AsyncTask<Save>> {
+        let path = path.or_else(|| self.path.clone())
+            .ok_or_else(|| to_napi_err("No path given and the database was not opened from a file"))?;
+        Ok(AsyncTask::new(Save { db: self.db.clone(), path }))
+    }
+}
+
+fn execute(db: &SharedDatabase, sql: &str) -> Result<Vec<Row>> {
+    let mut db = lock(db)?;
+    // catch parser panics here so they cannot poison the mutex for every later call
+    let records = catch_unwind(AssertUnwindSafe(|| db.execute_sql(sql)))
+        .unwrap_or_else(|_| Err(format!("Invalid SQL statement: {:?}", sql)))
+        .map_err(to_napi_err)?;
+    Ok(records.into_iter()
+        .map(|record| Row { id: BigInt::from(record.id()), data: record.data().to_map() })
+        .collect())
+}
+
+pub struct Execute {
+    db: SharedDatabase,
+    sql: String,
+}
+
+impl Task for Execute {
+    type Output = Vec<Row>;
+    type JsValue = Vec<Row>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        execute(&self.db, &self.sql)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+pub struct Save {
+    db: SharedDatabase,
+    path: String,
+}
+
+impl Task for Save {
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        // serialize under the lock, write the file without holding it
+        let bytes = lock(&self.db)?.to_bytes().map_err(to_napi_err)?;
+        std::fs::write(&self.path, bytes).map_err(to_napi_err)
+    }
+
+    fn resolve(&mut self, _env: Env, _output: Self::Output) -> Result<Self::JsValue> {
+        Ok(())
+    }
+}
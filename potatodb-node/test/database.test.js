@@ -0,0 +1,27 @@
+const test = require('node:test');
+const assert = require('node:assert');
+const { Database } = require('..');
+
+function makeDb() {
+  const db = new Database();
+  db.createTable('users');
+  db.executeSync('INSERT INTO users (name, age) VALUES (Alice, 30)');
+  return db;
+}
+
+test('execute resolves rows with bigint ids', async () => {
+  const rows = await makeDb().execute('SELECT * FROM users');
+  assert.deepStrictEqual(rows, [{ id: 1n, data: { name: 'Alice', age: '30' } }]);
+});
+
+test('malformed SQL rejects without breaking later calls', async () => {
+  const db = makeDb();
+  await assert.rejects(db.execute(''));
+  await assert.rejects(db.execute('SELECT * FROM'));
+  assert.strictEqual((await db.execute('SELECT * FROM users')).length, 1);
+});
+
+test('buffers round-trip the whole database', () => {
+  const copy = Database.fromBuffer(makeDb().toBuffer());
+  assert.deepStrictEqual(copy.tables(), ['users']);
+});
@@ -0,0 +1,112 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyType};
+
+create_exception!(potatodb, Error, PyException);
+
+fn to_py_err(e: impl ToString) -> PyErr {
+    Error::new_err(e.to_string())
+}
+
+#[pyclass(name = "Database", module = "potatodb")]
+struct PyDatabase {
+    db: potatodb::Database,
+    path: Option<String>,
+}
+
+#[pymethods]
+impl PyDatabase {
+    #[new]
+    #[pyo3(signature = (path=None))]
+    fn new(path: Option<String>) -> PyResult<Self> {
+        let db = match &path {
+            Some(path) if Path::new(path).exists() => potatodb::Database::load(path).map_err(to_py_err)?,
+            _ => potatodb::Database::new(),
+        };
+        Ok(PyDatabase { db, path })
+    }
+
+    fn execute<'py>(&mut self, py: Python<'py>, sql: &str) -> PyResult<Bound<'py, PyList>> {
+        // a parser panic would otherwise surface as PanicException, which
+        // derives from BaseException and slips past `except Exception`
+        let records = catch_unwind(AssertUnwindSafe(|| self.db.execute_sql(sql)))
+            .unwrap_or_else(|_| Err(format!("Invalid SQL statement: {:?}", sql)))
+            .map_err(to_py_err)?;
+        let rows = PyList::empty(py);
+        for record in records {
+            let row = PyDict::new(py);
+            row.set_item("id", record.id())?;
+            for (column, value) in record.data() {
+                row.set_item(column, value)?;
+            }
+            rows.append(row)?;
+        }
+        Ok(rows)
+    }
+
+    fn create_table(&mut self, name: String) -> PyResult<()> {
+        self.db.create_table(name).map_err(to_py_err)
+    }
+
+    fn tables(&self) -> Vec<String> {
+        let mut tables: Vec<String> = self.db.list_tables().into_iter().map(String::from).collect();
+        tables.sort();
+        tables
+    }
+
+    #[pyo3(signature = (path=None))]
+    fn save(&self, path: Option<String>) -> PyResult<()> {
+        let path = path.or_else(|| self.path.clone())
+            .ok_or_else(|| to_py_err("No path given and the database was not opened from a file"))?;
+        self.db.save(&path).map_err(to_py_err)
+    }
+
+    /// Returns a context manager that rolls back every change made inside the
+    /// `with` block if it raises.
+    fn transaction(slf: Py<Self>) -> Transaction {
+        Transaction { db: slf, snapshot: None }
+    }
+}
+
+#[pyclass(module = "potatodb")]
+struct Transaction {
+    db: Py<PyDatabase>,
+    snapshot: Option<potatodb::Database>,
+}
+
+#[pymethods]
+impl Transaction {
+    fn __enter__(mut slf: PyRefMut<'_, Self>) -> PyResult<Py<PyDatabase>> {
+        let py = slf.py();
+        let snapshot = slf.db.borrow(py).db.clone();
+        slf.snapshot = Some(snapshot);
+        Ok(slf.db.clone_ref(py))
+    }
+
+    fn __exit__(
+        &mut self,
+        py: Python<'_>,
+        exc_type: Option<Bound<'_, PyType>>,
+        _exc_value: Option<Bound<'_, PyAny>>,
+        _traceback: Option<Bound<'_, PyAny>>,
+    ) -> bool {
+        if let Some(snapshot) = self.snapshot.take() {
+            if exc_type.is_some() {
+                self.db.borrow_mut(py).db = snapshot;
+            }
+        }
+        false
+    }
+}
+
+#[pymodule(name = "potatodb")]
+fn potatodb_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyDatabase>()?;
+    m.add_class::<Transaction>()?;
+    m.add("Error", m.py().get_type::<Error>())?;
+    Ok(())
+}
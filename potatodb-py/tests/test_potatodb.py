@@ -0,0 +1,45 @@
+import pytest
+
+import potatodb
+
+
+def make_db():
+    db = potatodb.Database()
+    db.create_table("users")
+    db.execute("INSERT INTO users (name, age) VALUES (Alice, 30)")
+    return db
+
+
+def test_execute_returns_dicts():
+    db = make_db()
+    assert db.execute("SELECT * FROM users") == [{"id": 1, "name": "Alice", "age": "30"}]
+    assert db.tables() == ["users"]
+
+
+def test_invalid_sql_raises_error():
+    db = make_db()
+    for sql in ["", "SELECT * FROM", "SELECT * FROM missing"]:
+        with pytest.raises(potatodb.Error):
+            db.execute(sql)
+
+
+def test_transaction_rolls_back_on_exception():
+    db = make_db()
+    with pytest.raises(ValueError):
+        with db.transaction():
+            db.execute("INSERT INTO users (name, age) VALUES (Bob, 25)")
+            raise ValueError
+    assert len(db.execute("SELECT * FROM users")) == 1
+
+
+def test_transaction_keeps_changes_on_success():
+    db = make_db()
+    with db.transaction():
+        db.execute("INSERT INTO users (name, age) VALUES (Bob, 25)")
+    assert len(db.execute("SELECT * FROM users")) == 2
+
+
+def test_save_and_reopen(tmp_path):
+    path = str(tmp_path / "db.bin")
+    make_db().save(path)
+    assert potatodb.Database(path).execute("SELECT * FROM users")[0]["name"] == "Alice"
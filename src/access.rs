@@ -0,0 +1,157 @@
+use core::fmt;
+
+use crate::prelude::*;
+use crate::Database;
+#[cfg(feature = "sql")]
+use crate::SqlStatement;
+
+/// Grants on this table name apply to every table.
+pub const ALL_TABLES: &str = "*";
+
+/// What a role may do to a table. `Ddl` covers `CREATE TABLE`, `CREATE
+/// SCHEMA`, `DROP SCHEMA`, `PRAGMA`, `SET` and `ANALYZE`; `Unmask` shows
+/// masked columns as stored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Privilege {
+    Select,
+    Insert,
+    Update,
+    Delete,
+    Ddl,
+    Unmask,
+}
+
+impl fmt::Display for Privilege {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Privilege::Select => "SELECT",
+            Privilege::Insert => "INSERT",
+            Privilege::Update => "UPDATE",
+            Privilege::Delete => "DELETE",
+            Privilege::Ddl => "DDL",
+            Privilege::Unmask => "UNMASK",
+        })
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct AccessControl {
+    // role -> table -> privileges
+    roles: HashMap<String, HashMap<String, HashSet<Privilege>>>,
+    // user -> roles
+    users: HashMap<String, HashSet<String>>,
+    // statements run unchecked without a session user
+    session: Option<String>,
+}
+
+impl AccessControl {
+    fn allows(&self, user: &str, table: &str, privilege: Privilege) -> bool {
+        self.users.get(user).into_iter().flatten()
+            .filter_map(|role| self.roles.get(role))
+            .flat_map(|grants| [grants.get(table), grants.get(ALL_TABLES)])
+            .flatten()
+            .any(|privileges| privileges.contains(&privilege))
+    }
+
+    // Swaps in the session user, returning the one it replaces.
+    pub(crate) fn replace_session(&mut self, user: Option<String>) -> Option<String> {
+        core::mem::replace(&mut self.session, user)
+    }
+
+    fn role_mut(&mut self, role: &str) -> Result<&mut HashMap<String, HashSet<Privilege>>, String> {
+        self.roles.get_mut(role).ok_or(format!("Role '{}' not found", role))
+    }
+}
+
+#[cfg(feature = "sql")]
+fn required(statement: &SqlStatement) -> (Privilege, &str) {
+    match statement {
+        SqlStatement::Select { table, .. } | SqlStatement::Graph { table, .. } => (Privilege::Select, table),
+        SqlStatement::Insert { table, .. } => (Privilege::Insert, table),
+        SqlStatement::Update { table, .. } | SqlStatement::Increment { table, .. } => (Privilege::Update, table),
+        SqlStatement::Delete { table, .. } => (Privilege::Delete, table),
+        SqlStatement::Explain { statement, .. } => required(statement),
+        SqlStatement::CreateTable { table, .. } | SqlStatement::Analyze(table) => (Privilege::Ddl, table),
+        SqlStatement::CreateSchema(_) | SqlStatement::DropSchema(_) | SqlStatement::Pragma(_) | SqlStatement::Set(..) => (Privilege::Ddl, ALL_TABLES),
+    }
+}
+
+impl Database {
+    pub fn create_role(&mut self, role: &str) -> Result<(), String> {
+        if self.access.roles.contains_key(role) {
+            return Err(format!("Role '{}' already exists", role));
+        }
+        self.access.roles.insert(role.to_string(), HashMap::new());
+        Ok(())
+    }
+
+    /// Lets `role` use `privileges` on `table`, or on every table if `table`
+    /// is [`ALL_TABLES`].
+    pub fn grant(&mut self, role: &str, table: &str, privileges: &[Privilege]) -> Result<(), String> {
+        self.access.role_mut(role)?.entry(table.to_string()).or_default().extend(privileges);
+        Ok(())
+    }
+
+    /// Takes back privileges given with [`grant`](Self::grant) on the same
+    /// table name.
+    pub fn revoke(&mut self, role: &str, table: &str, privileges: &[Privilege]) -> Result<(), String> {
+        if let Some(granted) = self.access.role_mut(role)?.get_mut(table) {
+            granted.retain(|p| !privileges.contains(p));
+        }
+        Ok(())
+    }
+
+    /// Adds a user holding `roles`, or replaces the roles of an existing one.
+    pub fn create_user(&mut self, user: &str, roles: &[&str]) -> Result<(), String> {
+        if let Some(missing) = roles.iter().find(|r| !self.access.roles.contains_key(**r)) {
+            return Err(format!("Role '{}' not found", missing));
+        }
+        self.access.users.insert(user.to_string(), roles.iter().map(|r| r.to_string()).collect());
+        Ok(())
+    }
+
+    /// Runs later SQL statements as `user`, who needs a privilege on each
+    /// table a statement touches. `None` runs them unchecked, as the
+    /// application itself. The Rust API is never checked, and roles and
+    /// users are not saved with the database. Switching to another user
+    /// ends the session, dropping its temporary tables.
+    pub fn set_session_user(&mut self, user: Option<&str>) -> Result<(), String> {
+        if let Some(user) = user.filter(|u| !self.has_user(u)) {
+            return Err(format!("User '{}' not found", user));
+        }
+        if self.access.replace_session(user.map(String::from)).as_deref() != user {
+            self.end_session();
+        }
+        Ok(())
+    }
+
+    pub(crate) fn has_user(&self, user: &str) -> bool {
+        self.access.users.contains_key(user)
+    }
+
+    pub fn session_user(&self) -> Option<&str> {
+        self.access.session.as_deref()
+    }
+
+    // Statements run without a session user are always allowed.
+    pub(crate) fn session_allows(&self, table: &str, privilege: Privilege) -> bool {
+        self.access.session.as_ref().map_or(true, |user| self.access.allows(user, table, privilege))
+    }
+
+    #[cfg(feature = "sql")]
+    pub(crate) fn authorize(&self, statement: &SqlStatement) -> Result<(), String> {
+        let (privilege, table) = required(statement);
+        self.check_privilege(table, privilege)
+    }
+
+    // Fails unless the session user, if any, holds `privilege` on `table`.
+    #[cfg(feature = "sql")]
+    pub(crate) fn check_privilege(&self, table: &str, privilege: Privilege) -> Result<(), String> {
+        match &self.access.session {
+            Some(user) if !self.session_allows(table, privilege) => {
+                Err(format!("Permission denied: user '{}' lacks {} on '{}'", user, privilege, table))
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,422 @@
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+
+use crate::arena::{self, ArenaVec};
+use crate::generated::number_text;
+use crate::hyperloglog::HyperLogLog;
+use crate::prelude::*;
+use crate::value;
+#[cfg(feature = "sql")]
+use crate::Condition;
+use crate::{Database, Record, Row};
+
+/// The direction of a [`Pipeline::sort`] stage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+#[derive(Clone, Debug)]
+enum Stage {
+    #[cfg(feature = "sql")]
+    Match(String),
+    // group key (None for one group of every row), then (output, accumulator)
+    Group(Option<String>, Vec<(String, String)>),
+    // row key, column key, accumulator
+    Pivot(String, String, String),
+    Sort(String, SortOrder),
+    Limit(usize),
+}
+
+pub(crate) enum Accumulator {
+    Count,
+    Sum(String),
+    Avg(String),
+    Min(String),
+    Max(String),
+    ApproxCountDistinct(String),
+    // column, fraction in 0..=1
+    PercentileCont(String, f64),
+    // column, separator, and the column and direction values are joined in
+    StringAgg(String, String, Option<(String, SortOrder)>),
+}
+
+impl Accumulator {
+    // `count`, `sum(column)`, `avg(column)`, `min(column)`, `max(column)`,
+    // `approx_count_distinct(column)`, `percentile_cont(column, fraction)`,
+    // `median(column)`, or `string_agg(column, 'separator' ORDER BY column)`
+    // or its alias `group_concat`
+    pub(crate) fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text.eq_ignore_ascii_case("count") || text.eq_ignore_ascii_case("count(*)") {
+            return Ok(Accumulator::Count);
+        }
+        let (function, column) = text.strip_suffix(')').and_then(|t| t.split_once('('))
+            .ok_or(format!("Invalid accumulator '{}'", text))?;
+        let column = column.trim().to_string();
+        let without_column = column.is_empty();
+        let accumulator = match function.trim().to_lowercase().as_str() {
+            "sum" => Ok(Accumulator::Sum(column)),
+            "avg" => Ok(Accumulator::Avg(column)),
+            "min" => Ok(Accumulator::Min(column)),
+            "max" => Ok(Accumulator::Max(column)),
+            "approx_count_distinct" => Ok(Accumulator::ApproxCountDistinct(column)),
+            "median" => Ok(Accumulator::PercentileCont(column, 0.5)),
+            "string_agg" | "group_concat" => string_agg(&column),
+            "percentile_cont" => {
+                let (column, fraction) = column.split_once(',').ok_or(format!("Invalid accumulator '{}': expected percentile_cont(column, fraction)", text))?;
+                match fraction.trim().parse::<f64>() {
+                    Ok(fraction) if (0.0..=1.0).contains(&fraction) => Ok(Accumulator::PercentileCont(column.trim().to_string(), fraction)),
+                    _ => Err(format!("Percentiles must be fractions between 0 and 1, not '{}'", fraction.trim())),
+                }
+            }
+            _ => Err(format!("Unknown accumulator '{}'", function.trim())),
+        }?;
+        match without_column {
+            true => Err(format!("Invalid accumulator '{}': {}() takes a column", text, function.trim())),
+            false => Ok(accumulator),
+        }
+    }
+
+    // `None` when no row has a value to work with.
+    pub(crate) fn apply(&self, rows: &(impl Rows + ?Sized)) -> Option<String> {
+        match self {
+            Accumulator::Count => Some(rows.count().to_string()),
+            Accumulator::Sum(column) => Some(number_text(rows.numbers(column).iter().sum())),
+            Accumulator::Avg(column) => {
+                let numbers = rows.numbers(column);
+                (!numbers.is_empty()).then(|| number_text(numbers.iter().sum::<f64>() / numbers.len() as f64))
+            }
+            Accumulator::Min(column) => rows.values(column).min_by(|a, b| compare(a, b)).cloned(),
+            Accumulator::Max(column) => rows.values(column).max_by(|a, b| compare(a, b)).cloned(),
+            Accumulator::ApproxCountDistinct(column) => {
+                let mut sketch = HyperLogLog::new();
+                rows.values(column).for_each(|v| sketch.add(v));
+                Some(sketch.estimate().to_string())
+            }
+            Accumulator::PercentileCont(column, fraction) => {
+                let mut numbers = rows.numbers(column);
+                numbers.sort_by(f64::total_cmp);
+                percentile(&numbers, *fraction).map(number_text)
+            }
+            Accumulator::StringAgg(column, separator, order) => {
+                let mut values = rows.values_by(column, order.as_ref().map_or(column, |(by, _)| by));
+                if let Some((_, direction)) = order {
+                    values.sort_by(|(_, a), (_, b)| {
+                        let ordering = match (a, b) {
+                            (Some(a), Some(b)) => compare(a, b),
+                            (a, b) => a.is_some().cmp(&b.is_some()),
+                        };
+                        if *direction == SortOrder::Descending { ordering.reverse() } else { ordering }
+                    });
+                }
+                let values: Vec<&str> = values.into_iter().map(|(value, _)| value.as_str()).collect();
+                (!values.is_empty()).then(|| values.join(separator))
+            }
+        }
+    }
+}
+
+// `column`, then optionally a quoted separator (a comma by default), then
+// optionally `ORDER BY column`, ascending unless followed by `DESC`.
+fn string_agg(arguments: &str) -> Result<Accumulator, String> {
+    let invalid = || format!("Invalid accumulator 'string_agg({})': expected string_agg(column, 'separator' ORDER BY column)", arguments);
+    let end = arguments.find(|c: char| c == ',' || c.is_whitespace()).unwrap_or(arguments.len());
+    let (column, mut rest) = (arguments[..end].to_string(), arguments[end..].trim_start());
+    let mut separator = ",".to_string();
+    if let Some(quoted) = rest.strip_prefix(',').map(str::trim_start) {
+        let quote = quoted.chars().next().filter(|&c| c == '\'' || c == '"').ok_or_else(invalid)?;
+        let (text, after) = quoted[1..].split_once(quote).ok_or_else(invalid)?;
+        (separator, rest) = (text.to_string(), after.trim_start());
+    }
+    let order = match rest.split_whitespace().collect::<Vec<_>>()[..] {
+        [] => None,
+        [order, by, column, ref direction @ ..] if order.eq_ignore_ascii_case("ORDER") && by.eq_ignore_ascii_case("BY") => {
+            let direction = match direction {
+                [] => SortOrder::Ascending,
+                [d] if d.eq_ignore_ascii_case("ASC") => SortOrder::Ascending,
+                [d] if d.eq_ignore_ascii_case("DESC") => SortOrder::Descending,
+                _ => return Err(invalid()),
+            };
+            Some((column.to_string(), direction))
+        }
+        _ => return Err(invalid()),
+    };
+    if column.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Accumulator::StringAgg(column, separator, order))
+}
+
+// Interpolates linearly between the two closest values, as in SQL's
+// PERCENTILE_CONT.
+fn percentile(sorted: &[f64], fraction: f64) -> Option<f64> {
+    let last = sorted.len().checked_sub(1)?;
+    let rank = fraction * last as f64;
+    let (below, above) = (rank.floor() as usize, rank.ceil() as usize);
+    Some(sorted[below] + (sorted[above] - sorted[below]) * (rank - below as f64))
+}
+
+// What accumulators read: the rows of a group, or the columns of a
+// columnar table for them.
+pub(crate) trait Rows {
+    fn count(&self) -> usize;
+
+    fn values<'a>(&'a self, column: &'a str) -> Box<dyn Iterator<Item = &'a String> + 'a>;
+
+    fn numbers(&self, column: &str) -> Vec<f64> {
+        self.values(column).filter_map(|v| v.parse().ok()).collect()
+    }
+
+    // The values of `column`, each with the same row's value of `by`.
+    fn values_by<'a>(&'a self, column: &'a str, by: &'a str) -> Vec<(&'a String, Option<&'a String>)>;
+}
+
+impl<R: Borrow<Record>> Rows for [R] {
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    fn values<'a>(&'a self, column: &'a str) -> Box<dyn Iterator<Item = &'a String> + 'a> {
+        Box::new(self.iter().filter_map(move |r| r.borrow().data.get(column)))
+    }
+
+    fn values_by<'a>(&'a self, column: &'a str, by: &'a str) -> Vec<(&'a String, Option<&'a String>)> {
+        self.iter().filter_map(|r| Some((r.borrow().data.get(column)?, r.borrow().data.get(by)))).collect()
+    }
+}
+
+// Numbers compare as numbers, booleans as booleans and text as text; the
+// kinds sort apart, numbers before text.
+pub(crate) fn compare(a: &str, b: &str) -> Ordering {
+    value::sort(a, b)
+}
+
+/// Stages run over a table in order, built with [`Database::aggregate`].
+/// The leading [`match_`](Self::match_) runs as the WHERE clause of a SQL
+/// SELECT, so access control, row policies and masks apply as they do to
+/// SQL; the later stages work on its rows. A leading group over a
+/// [columnar](Database::set_columnar) table reads the table's columns
+/// instead, when none of those apply to the session.
+pub struct Pipeline<'a> {
+    db: &'a Database,
+    table: String,
+    stages: Vec<Stage>,
+}
+
+impl Pipeline<'_> {
+    /// Keeps the rows matching a WHERE predicate such as `status = paid`.
+    #[cfg(feature = "sql")]
+    pub fn match_(mut self, predicate: &str) -> Self {
+        self.stages.push(Stage::Match(predicate.to_string()));
+        self
+    }
+
+    /// One row per distinct value of `key`, holding the key and each named
+    /// accumulator: `count`, `sum(column)`, `avg(column)`, `min(column)`,
+    /// `max(column)`, `approx_count_distinct(column)` (a HyperLogLog
+    /// estimate, within about 2%), `percentile_cont(column, fraction)`,
+    /// `median(column)` or `string_agg(column, ', ' ORDER BY column DESC)`
+    /// (also called `group_concat`; the separator defaults to a comma and
+    /// values are joined in row order without ORDER BY). Rows without the
+    /// key are left out.
+    pub fn group(mut self, key: &str, accumulators: &[(&str, &str)]) -> Self {
+        self.stages.push(Stage::Group(Some(key.to_string()), owned(accumulators)));
+        self
+    }
+
+    /// One row holding each named accumulator over every row.
+    pub fn group_all(mut self, accumulators: &[(&str, &str)]) -> Self {
+        self.stages.push(Stage::Group(None, owned(accumulators)));
+        self
+    }
+
+    /// A crosstab: one row per distinct value of `rows`, holding that value
+    /// and one column per distinct value of `columns`, named after it, with
+    /// the accumulator over the rows having both. Combinations without rows
+    /// leave the cell out. Turns `region, quarter, amount` rows into
+    /// `region, Q1, Q2, ...` with `pivot("region", "quarter", "sum(amount)")`.
+    pub fn pivot(mut self, rows: &str, columns: &str, accumulator: &str) -> Self {
+        self.stages.push(Stage::Pivot(rows.to_string(), columns.to_string(), accumulator.to_string()));
+        self
+    }
+
+    /// Orders the rows by a column, numerically where both values are
+    /// numbers. Rows without the column sort before every value, unless the
+    /// `nulls` [setting](Database::set_setting) puts them first or last.
+    pub fn sort(mut self, column: &str, order: SortOrder) -> Self {
+        self.stages.push(Stage::Sort(column.to_string(), order));
+        self
+    }
+
+    pub fn limit(mut self, n: usize) -> Self {
+        self.stages.push(Stage::Limit(n));
+        self
+    }
+
+    pub fn run(self) -> Result<Vec<Record>, String> {
+        let (mut rows, stages) = match self.stages.split_first() {
+            #[cfg(feature = "sql")]
+            Some((Stage::Match(predicate), rest)) => {
+                self.predicate(predicate)?;
+                (self.db.query_sql(&format!("SELECT * FROM {} WHERE {}", self.table, predicate))?, rest)
+            }
+            Some((Stage::Group(key, accumulators), rest)) => match self.db.group_columns(&self.table, key.as_deref(), accumulators)? {
+                Some(rows) => (rows, rest),
+                None => (self.all_rows()?, self.stages.as_slice()),
+            },
+            _ => (self.all_rows()?, self.stages.as_slice()),
+        };
+        for stage in stages {
+            rows = match stage {
+                #[cfg(feature = "sql")]
+                Stage::Match(predicate) => {
+                    let condition = Some(self.predicate(predicate)?);
+                    rows.into_iter().filter(|r| self.db.evaluate_condition(r, &condition)).collect()
+                }
+                Stage::Group(key, accumulators) => group(rows, key.as_deref(), accumulators)?,
+                Stage::Pivot(row_key, column_key, accumulator) => pivot(rows, row_key, column_key, accumulator)?,
+                Stage::Sort(column, order) => {
+                    let compare_as = self.db.tables.get(&self.table).and_then(|t| t.compare.get(column)).copied();
+                    let nulls_last = self.db.settings.nulls_last;
+                    rows.sort_by(|a, b| {
+                        let (a, b) = (a.data.get(column), b.data.get(column));
+                        let ordering = match (a, b) {
+                            (Some(a), Some(b)) => compare_as.map_or_else(|| compare(a, b), |c| c.sort(a, b)),
+                            _ => a.is_some().cmp(&b.is_some()),
+                        };
+                        match nulls_last {
+                            Some(last) if a.is_none() != b.is_none() => (a.is_some() ^ last).cmp(&(b.is_some() ^ last)),
+                            _ if *order == SortOrder::Descending => ordering.reverse(),
+                            _ => ordering,
+                        }
+                    });
+                    rows
+                }
+                Stage::Limit(n) => {
+                    rows.truncate(*n);
+                    rows
+                }
+            };
+        }
+        Ok(rows)
+    }
+
+    #[cfg(feature = "sql")]
+    fn all_rows(&self) -> Result<Vec<Record>, String> {
+        self.db.query_sql(&format!("SELECT * FROM {}", self.table))
+    }
+
+    // As `SELECT *` reads them, without the policies and masks only SQL has.
+    #[cfg(not(feature = "sql"))]
+    fn all_rows(&self) -> Result<Vec<Record>, String> {
+        let table = self.db.tables.get(&self.table).ok_or("Table not found")?;
+        let mut rows = table.records.clone();
+        self.db.keys.reveal(table, &mut rows);
+        Ok(rows)
+    }
+
+    #[cfg(feature = "sql")]
+    fn predicate(&self, predicate: &str) -> Result<Condition, String> {
+        let tokens: Vec<&str> = core::iter::once("WHERE").chain(crate::identifier::split(predicate)).collect();
+        self.db.parse_where_clause(&tokens).ok().flatten().ok_or(format!("Invalid match predicate '{}'", predicate))
+    }
+}
+
+fn owned(accumulators: &[(&str, &str)]) -> Vec<(String, String)> {
+    accumulators.iter().map(|(name, a)| (name.to_string(), a.to_string())).collect()
+}
+
+// Groups keep the order their first row came in. Groups and their cells
+// are built in an arena, pointing into `rows`.
+fn group(rows: Vec<Record>, key: Option<&str>, accumulators: &[(String, String)]) -> Result<Vec<Record>, String> {
+    let accumulators = accumulators.iter()
+        .map(|(name, a)| Ok((name, Accumulator::parse(a)?)))
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(arena::scoped(|arena| {
+        let mut groups: ArenaVec<(Option<&str>, ArenaVec<&Record>)> = ArenaVec::new_in(arena);
+        let mut positions: HashMap<Option<&str>, usize> = HashMap::new();
+        if key.is_none() {
+            groups.push((None, ArenaVec::new_in(arena)));
+            positions.insert(None, 0);
+        }
+        for row in &rows {
+            let value = match key {
+                Some(key) => match row.data.get(key) {
+                    Some(value) => Some(value.as_str()),
+                    None => continue,
+                },
+                None => None,
+            };
+            let position = *positions.entry(value).or_insert_with(|| {
+                groups.push((value, ArenaVec::new_in(arena)));
+                groups.len() - 1
+            });
+            groups[position].1.push(row);
+        }
+        (1..).zip(groups)
+            .map(|(id, (value, rows))| {
+                let mut data: Row = accumulators.iter()
+                    .filter_map(|(name, accumulator)| Some((name.to_string(), accumulator.apply(&rows[..])?)))
+                    .collect();
+                if let (Some(key), Some(value)) = (key, value) {
+                    data.insert(key.to_string(), value.to_string());
+                }
+                Record { id, data }
+            })
+            .collect()
+    }))
+}
+
+// A pivoted row's key and its cells: column value -> the rows in the cell.
+type PivotRow<'a> = (&'a str, ArenaVec<'a, (&'a str, ArenaVec<'a, &'a Record>)>);
+
+// Rows keep the order their first row came in; rows without either key
+// are left out.
+fn pivot(rows: Vec<Record>, row_key: &str, column_key: &str, accumulator: &str) -> Result<Vec<Record>, String> {
+    let accumulator = Accumulator::parse(accumulator)?;
+    arena::scoped(|arena| {
+        let mut groups: ArenaVec<PivotRow> = ArenaVec::new_in(arena);
+        let mut positions: HashMap<&str, usize> = HashMap::new();
+        for row in &rows {
+            let (Some(key), Some(column)) = (row.data.get(row_key), row.data.get(column_key)) else {
+                continue;
+            };
+            if column == row_key {
+                return Err(format!("Cannot pivot: the value '{}' of '{}' would replace the row key", column, column_key));
+            }
+            let position = *positions.entry(key).or_insert_with(|| {
+                groups.push((key, ArenaVec::new_in(arena)));
+                groups.len() - 1
+            });
+            let cells = &mut groups[position].1;
+            match cells.iter().position(|(c, _)| c == column) {
+                Some(position) => cells[position].1.push(row),
+                None => {
+                    let mut cell = ArenaVec::new_in(arena);
+                    cell.push(row);
+                    cells.push((column, cell));
+                }
+            }
+        }
+        Ok((1..).zip(groups)
+            .map(|(id, (key, cells))| {
+                let mut data: Row = cells.into_iter()
+                    .filter_map(|(column, rows)| Some((column.to_string(), accumulator.apply(&rows[..])?)))
+                    .collect();
+                data.insert(row_key.to_string(), key.to_string());
+                Record { id, data }
+            })
+            .collect())
+    })
+}
+
+impl Database {
+    /// Starts an aggregation pipeline over a table, for composing stages
+    /// instead of writing SQL:
+    /// `db.aggregate("orders").match_("status = paid").group("user", &[("spent", "sum(total)")]).run()`.
+    pub fn aggregate(&self, table: &str) -> Pipeline<'_> {
+        Pipeline { db: self, table: table.to_string(), stages: Vec::new() }
+    }
+}
@@ -0,0 +1,291 @@
+#[cfg(feature = "sql")]
+use alloc::collections::BTreeMap;
+use alloc::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::expiry::Expiry;
+use crate::generated::{self, number_text};
+use crate::partition::Partitions;
+use crate::prelude::*;
+use crate::{CompareAs, Database, PartitionScheme, ProtoType, Row, Table, Value};
+
+/// The type [`Database::change_column_type`] converts a column's values to.
+/// Once a column has a type, from that or from `CREATE TABLE t (age
+/// INTEGER)`, every value written to it is stored converted, or the write
+/// fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColumnType {
+    /// Whole numbers; `3.0` converts to `3`, but `3.5` fails.
+    Integer,
+    /// Any finite number.
+    Real,
+    Text,
+    /// `true` or `false`, in any case.
+    Boolean,
+    /// Bytes, written as a hex literal such as `x'cafe'`.
+    Blob,
+}
+
+/// What [`Database::change_column_type`] did, or would have done.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConversionReport {
+    converted: usize,
+    failures: Vec<(u64, String)>,
+}
+
+impl ConversionReport {
+    /// Rows whose value was rewritten, such as `1.50` becoming `1.5`.
+    pub fn converted(&self) -> usize {
+        self.converted
+    }
+
+    /// The id and value of each row that could not be converted. When there
+    /// are any, the column is left as it was.
+    pub fn failures(&self) -> &[(u64, String)] {
+        &self.failures
+    }
+
+    pub fn is_applied(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl ColumnType {
+    pub(crate) fn convert(self, value: &str) -> Option<String> {
+        let number = || value.trim().parse::<f64>().ok().filter(|n| n.is_finite());
+        match self {
+            ColumnType::Integer => match value.trim().parse::<i64>() {
+                Ok(n) => Some(n.to_string()),
+                Err(_) => number().filter(|n| n.fract() == 0.0 && n.abs() < 1e15).map(number_text),
+            },
+            ColumnType::Real => number().map(number_text),
+            ColumnType::Text => Some(value.to_string()),
+            ColumnType::Boolean => value.trim().parse::<bool>().or_else(|_| value.trim().to_lowercase().parse()).ok().map(|b| b.to_string()),
+            ColumnType::Blob => match Value::parse(value.trim()) {
+                blob @ Value::Blob(_) => Some(blob.to_string()),
+                _ => None,
+            },
+        }
+    }
+
+    fn proto_type(self) -> ProtoType {
+        match self {
+            ColumnType::Integer => ProtoType::Int64,
+            ColumnType::Real => ProtoType::Double,
+            ColumnType::Text | ColumnType::Blob => ProtoType::String,
+            ColumnType::Boolean => ProtoType::Bool,
+        }
+    }
+
+    // The type a CREATE TABLE column definition names.
+    #[cfg(feature = "sql")]
+    fn named(name: &str) -> Option<ColumnType> {
+        match name.to_uppercase().as_str() {
+            "INTEGER" | "INT" | "BIGINT" | "SMALLINT" => Some(ColumnType::Integer),
+            "REAL" | "FLOAT" | "DOUBLE" | "NUMERIC" | "DECIMAL" => Some(ColumnType::Real),
+            "TEXT" | "VARCHAR" | "CHAR" | "STRING" => Some(ColumnType::Text),
+            "BOOLEAN" | "BOOL" => Some(ColumnType::Boolean),
+            "BLOB" | "BYTES" => Some(ColumnType::Blob),
+            _ => None,
+        }
+    }
+}
+
+// The typed column definitions in the body of a CREATE TABLE, such as `age
+// INTEGER` or `name VARCHAR(40)`. Generated columns are worked out rather
+// than written, and other type names, such as an enum's, are left untyped.
+#[cfg(feature = "sql")]
+pub(crate) fn parse_definitions(body: &str) -> BTreeMap<String, ColumnType> {
+    generated::split_definitions(body).into_iter()
+        .filter(|definition| !definition.to_uppercase().contains("GENERATED"))
+        .filter_map(|definition| {
+            let mut words = definition.split_whitespace();
+            let column = words.next()?;
+            let name = words.next()?;
+            let ty = ColumnType::named(name.split('(').next().unwrap_or(name))?;
+            Some((crate::identifier::unquote(column), ty))
+        })
+        .collect()
+}
+
+impl Table {
+    // Converts the values `data` gives typed columns to their types, as
+    // `change_column_type` converts them.
+    pub(crate) fn convert_types(&self, data: &mut Row) -> Result<(), String> {
+        for (column, ty) in &self.types {
+            if let Some(value) = data.get_mut(column) {
+                *value = self.convert_value(column, *ty, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    // A value written to `column`, converted if the column has a type.
+    pub(crate) fn convert_column(&self, column: &str, value: &str) -> Result<String, String> {
+        match self.types.get(column) {
+            Some(&ty) => self.convert_value(column, ty, value),
+            None => Ok(value.to_string()),
+        }
+    }
+
+    fn convert_value(&self, column: &str, ty: ColumnType, value: &str) -> Result<String, String> {
+        ty.convert(value).ok_or_else(|| format!("Cannot convert '{}' in column '{}' to {:?}", value, column, ty))
+    }
+
+    // How a typed column compares when no comparison is set for it: numbers
+    // as numbers and text as text.
+    pub(crate) fn declared_compare(&self, column: &str) -> Option<CompareAs> {
+        match self.types.get(column)? {
+            ColumnType::Integer | ColumnType::Real => Some(CompareAs::Numeric),
+            ColumnType::Text => Some(CompareAs::String),
+            ColumnType::Boolean | ColumnType::Blob => None,
+        }
+    }
+
+    // Every column the table's rows, message, generated columns or enums
+    // name.
+    fn known_columns(&self) -> BTreeSet<String> {
+        let mut columns: BTreeSet<String> = self.records.iter().flat_map(|r| r.data.keys().cloned()).collect();
+        columns.extend(self.proto.iter().flat_map(|p| p.columns()));
+        columns.extend(self.generated.iter().map(|g| g.name().to_string()));
+        columns.extend(self.enums.keys().cloned());
+        columns
+    }
+}
+
+impl Database {
+    /// Renames a column, rewriting every row that has it along with what
+    /// refers to it: the table's message field, generated column
+    /// expressions, enum and comparison settings, partitioning, expiry,
+    /// relations naming it as a foreign key, edge declarations, masks and
+    /// row policies. Encrypted columns can't be renamed, as their values
+    /// are bound to the column name.
+    pub fn rename_column(&mut self, table_name: &str, from: &str, to: &str) -> Result<(), String> {
+        crate::identifier::check("column", to)?;
+        let table = self.tables.get(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        let columns = table.known_columns();
+        if !columns.contains(from) {
+            return Err(format!("Column '{}' not found in table '{}'", from, table_name));
+        }
+        if columns.contains(to) {
+            return Err(format!("Table '{}' already has a column '{}'", table_name, to));
+        }
+        if table.encrypted.contains(from) {
+            return Err(format!("Column '{}' of '{}' is encrypted and can't be renamed", from, table_name));
+        }
+        let rows: Vec<(u64, Row)> = table.records.iter()
+            .filter(|r| r.data.contains_key(from))
+            .map(|r| {
+                let mut data = r.data.clone();
+                let value = data.remove(from).unwrap_or_default();
+                data.insert(to.to_string(), value);
+                (r.id, data)
+            })
+            .collect();
+        self.replace_rows(table_name, rows);
+
+        let table = self.tables.get_mut(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        if let Some(proto) = &mut table.proto {
+            proto.rename_field(from, to);
+        }
+        table.generated.iter_mut().for_each(|g| g.rename_column(from, to));
+        if let Some(values) = table.enums.remove(from) {
+            table.enums.insert(to.to_string(), values);
+        }
+        if let Some(compare) = table.compare.remove(from) {
+            table.compare.insert(to.to_string(), compare);
+        }
+        if let Some(ty) = table.types.remove(from) {
+            table.types.insert(to.to_string(), ty);
+        }
+        // partitions are picked by the column, so they are rebuilt under its
+        // new name
+        if let Some(partitions) = &table.partitions {
+            let mut scheme = partitions.scheme().clone();
+            if scheme.column() == from {
+                match &mut scheme {
+                    PartitionScheme::Hash { column, .. } | PartitionScheme::Range { column, .. } => *column = to.to_string(),
+                }
+                table.partitions = Some(Partitions::new(scheme, &table.records));
+            }
+        }
+        if table.expiry.as_ref().is_some_and(|e| e.column() == from) {
+            table.expiry = Some(Expiry::new(to, &table.records));
+        }
+        self.relations.rename_column(table_name, from, to);
+        self.edge_tables.rename_column(table_name, from, to);
+        #[cfg(feature = "sql")]
+        {
+            self.masks.rename_column(table_name, from, to);
+            self.policies.rename_column(table_name, from, to);
+        }
+        Ok(())
+    }
+
+    /// Converts a column's values to `ty`, row by row, and from then on
+    /// stores what is written to it as `ty` and compares its values as that
+    /// type: numeric types compare as numbers, text as text, and the field
+    /// of a table with a message becomes an `int64`, `double`, `bool` or
+    /// `string`. If any value can't be converted, nothing changes and the
+    /// report lists the rows that failed. Stored generated columns are
+    /// recomputed from the converted values.
+    pub fn change_column_type(&mut self, table_name: &str, column: &str, ty: ColumnType) -> Result<ConversionReport, String> {
+        let table = self.tables.get(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        if !table.known_columns().contains(column) {
+            return Err(format!("Column '{}' not found in table '{}'", column, table_name));
+        }
+        if table.encrypted.contains(column) {
+            return Err(format!("Column '{}' of '{}' is encrypted and can't change type", column, table_name));
+        }
+        if table.generated.iter().any(|g| g.name() == column) {
+            return Err(format!("Column '{}' of '{}' is generated and can't change type", column, table_name));
+        }
+        if table.enums.contains_key(column) {
+            return Err(format!("Column '{}' of '{}' is an enum and can't change type", column, table_name));
+        }
+        if ty != ColumnType::Text && table.partitions.as_ref().is_some_and(|p| p.scheme().column() == column) {
+            return Err(format!("Column '{}' partitions table '{}', so it compares as text", column, table_name));
+        }
+
+        let mut report = ConversionReport { converted: 0, failures: Vec::new() };
+        let mut rows = Vec::new();
+        for record in &table.records {
+            let Some(value) = record.data.get(column) else { continue };
+            match ty.convert(value) {
+                Some(converted) if converted == *value => {}
+                Some(converted) => {
+                    let mut data = record.data.clone();
+                    data.insert(column.to_string(), converted);
+                    generated::fill(&table.generated, &mut data);
+                    rows.push((record.id, data));
+                }
+                None => report.failures.push((record.id, value.clone())),
+            }
+        }
+        if !report.is_applied() {
+            return Ok(report);
+        }
+        report.converted = rows.len();
+        self.replace_rows(table_name, rows);
+
+        let table = self.tables.get_mut(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        table.types.insert(column.to_string(), ty);
+        match &mut table.proto {
+            Some(proto) if proto.type_of(column).is_some() => proto.retype_field(column, ty.proto_type()),
+            _ => {
+                match table.declared_compare(column) {
+                    Some(compare) => table.compare.insert(column.to_string(), compare),
+                    None => table.compare.remove(column),
+                };
+            }
+        }
+        Ok(report)
+    }
+
+    /// The type a column stores its values as, or None if it takes any.
+    pub fn column_type(&self, table_name: &str, column: &str) -> Result<Option<ColumnType>, String> {
+        let table = self.tables.get(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        Ok(table.types.get(column).copied())
+    }
+}
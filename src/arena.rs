@@ -0,0 +1,36 @@
+#[cfg(feature = "std")]
+use std::cell::RefCell;
+
+use bumpalo::Bump;
+
+pub(crate) use bumpalo::collections::Vec as ArenaVec;
+
+// Arenas that grew past this are freed after use instead of kept.
+#[cfg(feature = "std")]
+const KEEP_BYTES: usize = 1 << 20;
+
+#[cfg(feature = "std")]
+thread_local! {
+    // arenas of finished statements, kept so the next ones don't allocate
+    static POOL: RefCell<Vec<Bump>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Runs one step of a statement with a bump arena for its intermediate
+/// rows, all released at once when it returns. Arenas are pooled per
+/// thread, so a statement usually reuses the memory of the previous one.
+#[cfg(feature = "std")]
+pub(crate) fn scoped<R>(step: impl FnOnce(&Bump) -> R) -> R {
+    let mut arena = POOL.with(|pool| pool.borrow_mut().pop()).unwrap_or_default();
+    let result = step(&arena);
+    arena.reset();
+    if arena.allocated_bytes() <= KEEP_BYTES {
+        POOL.with(|pool| pool.borrow_mut().push(arena));
+    }
+    result
+}
+
+// Without threads there is no pool; each step gets a fresh arena.
+#[cfg(not(feature = "std"))]
+pub(crate) fn scoped<R>(step: impl FnOnce(&Bump) -> R) -> R {
+    step(&Bump::new())
+}
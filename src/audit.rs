@@ -0,0 +1,290 @@
+use alloc::collections::VecDeque;
+#[cfg(feature = "sql")]
+use alloc::collections::{BTreeMap, BTreeSet};
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+use alloc::sync::Arc;
+use core::time::Duration;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+use std::fs::{File, OpenOptions};
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+use std::io::Write;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+use std::path::{Path, PathBuf};
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock::unix_millis;
+use crate::prelude::*;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+use crate::StorageBackend;
+use crate::{ChangeKind, Database, Row};
+#[cfg(feature = "sql")]
+use crate::{Record, Table};
+
+/// The read-only table audited changes are kept in when auditing to
+/// [`AuditSink::Table`], with the columns `session`, `timestamp`
+/// (milliseconds since the Unix epoch), `table`, `record`, `operation` and,
+/// where they apply, `before` and `after`.
+pub const AUDIT_TABLE: &str = "_audit";
+
+/// Where [`Database::enable_audit`] sends audited changes.
+#[derive(Clone, Debug)]
+pub enum AuditSink {
+    /// The in-memory [`AUDIT_TABLE`], trimmed by the retention settings. It
+    /// is not saved with the database.
+    Table,
+    /// An append-only file of length-prefixed bincode entries, read back with
+    /// [`Database::read_audit_file`]. Retention does not apply to files.
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    File(PathBuf),
+    /// The same entries appended to a blob of a storage backend, read back
+    /// with [`Database::read_audit_log`].
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    Backend(Arc<dyn StorageBackend>, String),
+}
+
+/// One audited insert, update or delete.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    session: String,
+    timestamp: u64,
+    table: String,
+    record: u64,
+    kind: ChangeKind,
+    before: Option<Row>,
+    after: Option<Row>,
+}
+
+impl AuditEntry {
+    /// The tag set with [`Database::set_audit_session`] when the change was made.
+    pub fn session(&self) -> &str {
+        &self.session
+    }
+
+    /// Milliseconds since the Unix epoch.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    pub fn record(&self) -> u64 {
+        self.record
+    }
+
+    pub fn kind(&self) -> ChangeKind {
+        self.kind
+    }
+
+    /// The row before an update or delete.
+    pub fn before(&self) -> Option<&Row> {
+        self.before.as_ref()
+    }
+
+    /// The row after an insert or update.
+    pub fn after(&self) -> Option<&Row> {
+        self.after.as_ref()
+    }
+}
+
+#[derive(Clone)]
+enum Target {
+    Table,
+    // shared by clones of the database so entries stay in one file
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    File(Arc<Mutex<File>>),
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    Backend(Arc<dyn StorageBackend>, String),
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct AuditLog {
+    target: Option<Target>,
+    session: String,
+    entries: VecDeque<AuditEntry>,
+    max_entries: Option<usize>,
+    max_age: Option<Duration>,
+    error: Option<String>,
+}
+
+// Rows in the audit table show a row as `column=value` pairs sorted by column.
+#[cfg(feature = "sql")]
+fn describe(data: &Row) -> String {
+    let mut pairs: Vec<_> = data.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    pairs.sort();
+    pairs.join(", ")
+}
+
+impl AuditLog {
+    pub(crate) fn record(
+        &mut self,
+        table: &str,
+        id: u64,
+        kind: ChangeKind,
+        before: Option<&Row>,
+        after: Option<&Row>,
+    ) {
+        let Some(target) = &self.target else { return };
+        let entry = AuditEntry {
+            session: self.session.clone(),
+            timestamp: unix_millis(),
+            table: table.to_string(),
+            record: id,
+            kind,
+            before: before.cloned(),
+            after: after.cloned(),
+        };
+        match target {
+            Target::Table => {
+                self.entries.push_back(entry);
+                self.trim();
+            }
+            #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+            Target::File(file) => {
+                // the change is already made, so a failed write is kept for take_audit_error
+                if let Err(e) = append(file, &entry) {
+                    self.error.get_or_insert(e.to_string());
+                }
+            }
+            #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+            Target::Backend(backend, name) => {
+                let mut frame = Vec::new();
+                let appended = crate::replication::write_frame(&mut frame, &entry).and_then(|()| backend.append(name, &frame));
+                if let Err(e) = appended {
+                    self.error.get_or_insert(e.to_string());
+                }
+            }
+        }
+    }
+
+    // Entries older than this have expired.
+    fn cutoff(&self) -> u64 {
+        self.max_age.map_or(0, |max_age| unix_millis().saturating_sub(max_age.as_millis() as u64))
+    }
+
+    fn trim(&mut self) {
+        if let Some(max) = self.max_entries {
+            let excess = self.entries.len().saturating_sub(max);
+            self.entries.drain(..excess);
+        }
+        let cutoff = self.cutoff();
+        while self.entries.front().is_some_and(|e| e.timestamp < cutoff) {
+            self.entries.pop_front();
+        }
+    }
+
+    #[cfg(feature = "sql")]
+    pub(crate) fn table(&self) -> Table {
+        let cutoff = self.cutoff();
+        let records: Vec<Record> = self.entries.iter().zip(1..)
+            .filter(|(entry, _)| entry.timestamp >= cutoff)
+            .map(|(entry, id)| {
+                let mut data = Row::from([
+                    ("session".to_string(), entry.session.clone()),
+                    ("timestamp".to_string(), entry.timestamp.to_string()),
+                    ("table".to_string(), entry.table.clone()),
+                    ("record".to_string(), entry.record.to_string()),
+                    ("operation".to_string(), format!("{:?}", entry.kind).to_lowercase()),
+                ]);
+                data.extend(entry.before.as_ref().map(|b| ("before".to_string(), describe(b))));
+                data.extend(entry.after.as_ref().map(|a| ("after".to_string(), describe(a))));
+                Record { id, data }
+            })
+            .collect();
+        Table {
+            name: AUDIT_TABLE.to_string(),
+            index: records.iter().enumerate().map(|(i, r)| (r.id, i)).collect(),
+            records,
+            proto: None,
+            partitions: None,
+            history: None,
+            encrypted: BTreeSet::new(),
+            generated: Vec::new(),
+            enums: BTreeMap::new(),
+            series: None,
+            queue: None,
+            max_rows: None,
+            temporary: false,
+            columnar: None,
+            compare: BTreeMap::new(),
+            expiry: None,
+            timestamps: false,
+            types: BTreeMap::new(),
+        }
+    }
+}
+
+// The entries of an audit log. A last entry cut short, as by a crash while
+// it was written, is left out.
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+fn read_entries(mut input: &[u8]) -> std::io::Result<Vec<AuditEntry>> {
+    let mut entries = Vec::new();
+    while !input.is_empty() {
+        match crate::replication::read_frame(&mut input) {
+            Ok(entry) => entries.push(entry),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+fn append(file: &Mutex<File>, entry: &AuditEntry) -> std::io::Result<()> {
+    let mut file = file.lock().map_err(|_| std::io::Error::other("Audit file lock poisoned"))?;
+    crate::replication::write_frame(&mut *file, entry)?;
+    file.flush()
+}
+
+impl Database {
+    /// Records every later insert, update and delete, with the session tag,
+    /// time and the row before and after, to `sink`.
+    pub fn enable_audit(&mut self, sink: AuditSink) -> Result<(), Box<dyn core::error::Error>> {
+        self.audit.target = Some(match sink {
+            AuditSink::Table => Target::Table,
+            #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+            AuditSink::File(path) => Target::File(Arc::new(Mutex::new(OpenOptions::new().create(true).append(true).open(path)?))),
+            #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+            AuditSink::Backend(backend, name) => Target::Backend(backend, name),
+        });
+        Ok(())
+    }
+
+    /// Stops auditing. Entries already in the audit table stay.
+    pub fn disable_audit(&mut self) {
+        self.audit.target = None;
+    }
+
+    /// Tags later audited changes with who or what made them.
+    pub fn set_audit_session(&mut self, session: &str) {
+        self.audit.session = session.to_string();
+    }
+
+    /// Limits the audit table to the newest `max_entries` and to entries
+    /// younger than `max_age`. `None` leaves that limit off.
+    pub fn set_audit_retention(&mut self, max_entries: Option<usize>, max_age: Option<Duration>) {
+        self.audit.max_entries = max_entries;
+        self.audit.max_age = max_age;
+        self.audit.trim();
+    }
+
+    /// The first error writing to an audit file since the last call, if any.
+    pub fn take_audit_error(&mut self) -> Option<String> {
+        self.audit.error.take()
+    }
+
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub fn read_audit_file(path: impl AsRef<Path>) -> Result<Vec<AuditEntry>, Box<dyn std::error::Error>> {
+        Ok(read_entries(&std::fs::read(path)?)?)
+    }
+
+    /// The entries of an [`AuditSink::Backend`] log.
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub fn read_audit_log(backend: &dyn StorageBackend, name: &str) -> Result<Vec<AuditEntry>, Box<dyn std::error::Error>> {
+        Ok(read_entries(&backend.read(name)?)?)
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::hash::BuildHasher;
+use std::path::Path;
+use std::time::SystemTime;
+
+use serde_json::{json, Value as Json};
+
+use crate::{Database, Row};
+
+const MAGIC: &[u8; 4] = b"Obj\x01";
+const BLOCK_SIZE: usize = 1000;
+
+// The subset of Avro schemas that maps onto flat records of scalar columns.
+enum Schema {
+    Null,
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    Bytes,
+    String,
+    Fixed(usize),
+    Enum(Vec<String>),
+    Union(Vec<Schema>),
+}
+
+impl Database {
+    /// Writes a table to an Avro object container file. `id` becomes a `long`
+    /// field and every column a nullable `string`. Columns whose Avro names
+    /// would clash are rejected. Returns the number of records written.
+    pub fn export_avro(&self, table_name: &str, path: impl AsRef<Path>) -> Result<usize, Box<dyn std::error::Error>> {
+        let records = self.get_all(table_name)?;
+        let columns = self.columns(table_name)?;
+        let mut names: HashMap<String, &str> = HashMap::from([("id".to_string(), "id")]);
+        for column in &columns {
+            if let Some(other) = names.insert(avro_name(column), column) {
+                return Err(format!("Columns '{}' and '{}' both map to Avro field '{}'", other, column, avro_name(column)).into());
+            }
+        }
+        let fields: Vec<Json> = std::iter::once(json!({ "name": "id", "type": "long" }))
+            .chain(columns.iter().map(|c| json!({ "name": avro_name(c), "type": ["null", "string"], "default": null })))
+            .collect();
+        let schema = json!({ "type": "record", "name": avro_name(table_name), "fields": fields });
+
+        let sync = sync_marker();
+        let mut out = MAGIC.to_vec();
+        put_long(&mut out, 2);
+        put_bytes(&mut out, b"avro.schema");
+        put_bytes(&mut out, schema.to_string().as_bytes());
+        put_bytes(&mut out, b"avro.codec");
+        put_bytes(&mut out, b"null");
+        put_long(&mut out, 0);
+        out.extend_from_slice(&sync);
+
+        for chunk in records.chunks(BLOCK_SIZE) {
+            let mut block = Vec::new();
+            for record in chunk {
+                let id = i64::try_from(record.id).map_err(|_| format!("Record id {} does not fit in an Avro long", record.id))?;
+                put_long(&mut block, id);
+                for column in &columns {
+                    match record.data.get(column) {
+                        Some(value) => {
+                            put_long(&mut block, 1);
+                            put_bytes(&mut block, value.as_bytes());
+                        }
+                        None => put_long(&mut block, 0),
+                    }
+                }
+            }
+            put_long(&mut out, chunk.len() as i64);
+            put_long(&mut out, block.len() as i64);
+            out.extend_from_slice(&block);
+            out.extend_from_slice(&sync);
+        }
+
+        fs::write(path, out)?;
+        Ok(records.len())
+    }
+
+    /// Loads the records of an Avro object container file into a table,
+    /// creating it if needed. A top-level `id` field of type int or long
+    /// (optionally nullable) is used as the record id; otherwise ids continue
+    /// after the largest existing one. Scalar fields are converted to their
+    /// string form and nulls are left out. The whole file is decoded and
+    /// checked before anything is inserted. Returns the number of records imported.
+    pub fn import_avro(&mut self, table_name: &str, path: impl AsRef<Path>) -> Result<usize, Box<dyn std::error::Error>> {
+        let bytes = fs::read(path)?;
+        let mut input = bytes.as_slice();
+        if take(&mut input, 4)? != MAGIC {
+            return Err("Not an Avro object container file".into());
+        }
+
+        let mut metadata = HashMap::new();
+        loop {
+            let mut count = take_long(&mut input)?;
+            if count == 0 {
+                break;
+            }
+            if count < 0 {
+                count = -count;
+                take_long(&mut input)?;
+            }
+            for _ in 0..count {
+                let key = String::from_utf8(take_bytes(&mut input)?.to_vec())?;
+                metadata.insert(key, take_bytes(&mut input)?.to_vec());
+            }
+        }
+        match metadata.get("avro.codec").map(Vec::as_slice) {
+            None | Some(b"null") => {}
+            Some(codec) => return Err(format!("Unsupported Avro codec '{}'", String::from_utf8_lossy(codec)).into()),
+        }
+        let schema: Json = serde_json::from_slice(metadata.get("avro.schema").ok_or("Avro file has no schema")?)?;
+        let fields = record_fields(&schema)?;
+        let sync = take(&mut input, 16)?.to_vec();
+
+        let mut rows = Vec::new();
+        while !input.is_empty() {
+            let count = take_long(&mut input)?;
+            take_long(&mut input)?;
+            for _ in 0..count {
+                let mut id = None;
+                let mut data = HashMap::new();
+                for (name, schema) in &fields {
+                    let value = read_value(&mut input, schema)?;
+                    match value {
+                        Some(value) if name == "id" && is_integer(schema) => {
+                            id = Some(value.parse::<u64>().map_err(|_| format!("Invalid record id {}", value))?);
+                        }
+                        Some(value) => {
+                            data.insert(name.clone(), value);
+                        }
+                        None => {}
+                    }
+                }
+                rows.push((id, data));
+            }
+            if take(&mut input, 16)? != sync.as_slice() {
+                return Err("Avro sync marker mismatch".into());
+            }
+        }
+
+        let table = self.tables.get(table_name);
+        let mut ids: HashSet<u64> = table.map(|t| t.index.keys().copied().collect()).unwrap_or_default();
+        let mut next_id = ids.iter().max().map_or(1, |id| id.saturating_add(1));
+        let mut records = Vec::with_capacity(rows.len());
+        for (id, data) in rows {
+            let mut data = Row::from(data);
+            let id = id.unwrap_or(next_id);
+            if !ids.insert(id) {
+                return Err(format!("Record with id {} already exists", id).into());
+            }
+            if let Some(table) = table {
+                table.convert_types(&mut data)?;
+            }
+            if let Some(proto) = table.and_then(|t| t.proto.as_ref()) {
+                proto.validate(&data)?;
+            }
+            next_id = next_id.max(id.saturating_add(1));
+            records.push((id, data));
+        }
+
+        if table.is_none() {
+            self.create_table(table_name.to_string())?;
+        }
+        let imported = records.len();
+        for (id, data) in records {
+            self.insert(table_name, id, data)?;
+        }
+        Ok(imported)
+    }
+}
+
+fn avro_name(name: &str) -> String {
+    let mut name: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        name.insert(0, '_');
+    }
+    name
+}
+
+fn is_integer(schema: &Schema) -> bool {
+    match schema {
+        Schema::Int | Schema::Long => true,
+        Schema::Union(branches) => {
+            branches.iter().any(is_integer) && branches.iter().all(|b| matches!(b, Schema::Null | Schema::Int | Schema::Long))
+        }
+        _ => false,
+    }
+}
+
+fn sync_marker() -> [u8; 16] {
+    let seed = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_nanos();
+    let state = RandomState::new();
+    let mut marker = [0u8; 16];
+    marker[..8].copy_from_slice(&state.hash_one(seed).to_le_bytes());
+    marker[8..].copy_from_slice(&state.hash_one(seed.wrapping_add(1)).to_le_bytes());
+    marker
+}
+
+fn record_fields(schema: &Json) -> Result<Vec<(String, Schema)>, String> {
+    if schema["type"] != "record" {
+        return Err("Only record schemas can be imported".to_string());
+    }
+    schema["fields"].as_array().ok_or("Record schema has no fields")?
+        .iter()
+        .map(|field| {
+            let name = field["name"].as_str().ok_or("Field without a name")?;
+            Ok((name.to_string(), parse_schema(&field["type"])?))
+        })
+        .collect()
+}
+
+fn parse_schema(schema: &Json) -> Result<Schema, String> {
+    match schema {
+        Json::String(name) => match name.as_str() {
+            "null" => Ok(Schema::Null),
+            "boolean" => Ok(Schema::Boolean),
+            "int" => Ok(Schema::Int),
+            "long" => Ok(Schema::Long),
+            "float" => Ok(Schema::Float),
+            "double" => Ok(Schema::Double),
+            "bytes" => Ok(Schema::Bytes),
+            "string" => Ok(Schema::String),
+            other => Err(format!("Unsupported Avro type '{}'", other)),
+        },
+        Json::Array(branches) => Ok(Schema::Union(branches.iter().map(parse_schema).collect::<Result<_, _>>()?)),
+        Json::Object(object) => match object.get("type").and_then(Json::as_str) {
+            Some("enum") => {
+                let symbols = object.get("symbols").and_then(Json::as_array).ok_or("Enum without symbols")?;
+                Ok(Schema::Enum(symbols.iter().filter_map(|s| s.as_str().map(String::from)).collect()))
+            }
+            Some("fixed") => Ok(Schema::Fixed(object.get("size").and_then(Json::as_u64).ok_or("Fixed without size")? as usize)),
+            // logical types such as timestamp-millis annotate a primitive
+            Some(_) => parse_schema(&object["type"]),
+            None => Err("Schema object without a type".to_string()),
+        },
+        _ => Err(format!("Invalid Avro schema {}", schema)),
+    }
+}
+
+fn read_value(input: &mut &[u8], schema: &Schema) -> Result<Option<String>, String> {
+    Ok(Some(match schema {
+        Schema::Null => return Ok(None),
+        Schema::Boolean => (take(input, 1)?[0] != 0).to_string(),
+        Schema::Int | Schema::Long => take_long(input)?.to_string(),
+        Schema::Float => f32::from_le_bytes(take(input, 4)?.try_into().unwrap()).to_string(),
+        Schema::Double => f64::from_le_bytes(take(input, 8)?.try_into().unwrap()).to_string(),
+        Schema::String => String::from_utf8(take_bytes(input)?.to_vec()).map_err(|_| "Invalid UTF-8 in string field")?,
+        Schema::Bytes => hex(take_bytes(input)?),
+        Schema::Fixed(size) => hex(take(input, *size)?),
+        Schema::Enum(symbols) => {
+            let index = take_long(input)? as usize;
+            symbols.get(index).ok_or("Enum index out of range")?.clone()
+        }
+        Schema::Union(branches) => {
+            let index = take_long(input)? as usize;
+            return read_value(input, branches.get(index).ok_or("Union index out of range")?);
+        }
+    }))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn put_long(out: &mut Vec<u8>, n: i64) {
+    let mut n = ((n << 1) ^ (n >> 63)) as u64;
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_long(out, bytes.len() as i64);
+    out.extend_from_slice(bytes);
+}
+
+fn take_long(input: &mut &[u8]) -> Result<i64, String> {
+    let mut n = 0u64;
+    for shift in (0..64).step_by(7) {
+        let b = take(input, 1)?[0];
+        n |= ((b & 0x7f) as u64) << shift;
+        if b & 0x80 == 0 {
+            return Ok((n >> 1) as i64 ^ -((n & 1) as i64));
+        }
+    }
+    Err("Invalid Avro varint".to_string())
+}
+
+fn take_bytes<'a>(input: &mut &'a [u8]) -> Result<&'a [u8], String> {
+    let len = take_long(input)?;
+    take(input, usize::try_from(len).map_err(|_| "Negative Avro length")?)
+}
+
+fn take<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8], String> {
+    if input.len() < len {
+        return Err("Truncated Avro file".to_string());
+    }
+    let (head, rest) = input.split_at(len);
+    *input = rest;
+    Ok(head)
+}
@@ -0,0 +1,53 @@
+use std::error::Error;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::{Database, FileStorage, StorageBackend};
+
+/// A save running on a background thread, from
+/// [`Database::save_async`] or [`Database::save_to_async`].
+pub struct SaveHandle {
+    thread: JoinHandle<Result<usize, String>>,
+}
+
+impl SaveHandle {
+    /// Whether the save has finished, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Waits for the save to finish. Returns the number of bytes written.
+    pub fn wait(self) -> Result<usize, Box<dyn Error>> {
+        match self.thread.join() {
+            Ok(result) => Ok(result?),
+            Err(_) => Err("The background save panicked".into()),
+        }
+    }
+}
+
+impl Database {
+    /// Saves to a file like [`save`](Self::save), but serializes and writes
+    /// on a background thread. The file holds the database as it was at the
+    /// call, whatever is written after it: tables and key-value namespaces
+    /// are shared with the save, not copied, and a write to one during the
+    /// save copies that table or namespace alone.
+    pub fn save_async(&self, filename: &str) -> SaveHandle {
+        self.save_to_async(Arc::new(FileStorage), filename)
+    }
+
+    /// Like [`save_async`](Self::save_async), to `name` in `storage`.
+    pub fn save_to_async(&self, storage: Arc<dyn StorageBackend>, name: &str) -> SaveHandle {
+        // only shares tables and namespaces, whatever their size; temporary
+        // tables are left out when it is serialized
+        let copy = Database {
+            tables: self.tables.clone(),
+            kv: self.kv.clone(),
+            schemas: self.schemas.clone(),
+            codec: self.codec.clone(),
+            ..Database::new()
+        };
+        let name = name.to_string();
+        let thread = thread::spawn(move || copy.write_to(&*storage, &name).map_err(|e| e.to_string()));
+        SaveHandle { thread }
+    }
+}
@@ -0,0 +1,134 @@
+//! Workloads and generated datasets for measuring the engine with
+//! criterion. `cargo bench --features bench -- --save-baseline main` records
+//! a baseline; `-- --baseline main` then reports how a change moves each
+//! workload against it.
+
+use criterion::{black_box, BatchSize, Criterion};
+
+use crate::sample::Random;
+use crate::{Database, ProtoMessage, ProtoType, Row};
+
+const CITIES: [&str; 8] = ["Oslo", "Lima", "Pune", "Kyiv", "Accra", "Quito", "Hanoi", "Perth"];
+const STATUSES: [&str; 4] = ["new", "paid", "shipped", "returned"];
+
+/// The size and seed of a generated dataset. The same dataset always
+/// generates the same rows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dataset {
+    pub users: u64,
+    pub orders: u64,
+    pub seed: u64,
+}
+
+impl Default for Dataset {
+    fn default() -> Self {
+        Dataset { users: 1_000, orders: 20_000, seed: 0x5eed }
+    }
+}
+
+impl Dataset {
+    /// Rows of `users`, keyed 1 up: `name`, `city` and `age`.
+    pub fn users(&self) -> Vec<(u64, Row)> {
+        let mut random = Random::seeded(self.seed);
+        (1..=self.users)
+            .map(|id| {
+                let data = Row::from([
+                    ("name".to_string(), format!("user{}", id)),
+                    ("city".to_string(), CITIES[random.below(CITIES.len())].to_string()),
+                    ("age".to_string(), (18 + random.below(60)).to_string()),
+                ]);
+                (id, data)
+            })
+            .collect()
+    }
+
+    /// Rows of `orders`, keyed 1 up: `user` (a user's key), `total` and
+    /// `status`.
+    pub fn orders(&self) -> Vec<(u64, Row)> {
+        let mut random = Random::seeded(self.seed.rotate_left(32));
+        (1..=self.orders)
+            .map(|id| {
+                let data = Row::from([
+                    ("user".to_string(), (1 + random.below(self.users.max(1) as usize)).to_string()),
+                    ("total".to_string(), format!("{}.{:02}", random.below(1_000), random.below(100))),
+                    ("status".to_string(), STATUSES[random.below(STATUSES.len())].to_string()),
+                ]);
+                (id, data)
+            })
+            .collect()
+    }
+
+    /// A database with the `users` and `orders` tables filled. Orders are
+    /// typed by a protobuf message, so filters on `total` compare numbers.
+    pub fn database(&self) -> Result<Database, String> {
+        let mut db = Database::new();
+        db.create_table("users".to_string())?;
+        for (id, data) in self.users() {
+            db.insert("users", id, data)?;
+        }
+        insert_orders(&mut db, self.orders())?;
+        Ok(db)
+    }
+}
+
+fn order_message() -> Result<ProtoMessage, String> {
+    ProtoMessage::new("Order")
+        .field("user", 1, ProtoType::Uint64)?
+        .field("total", 2, ProtoType::Double)?
+        .field("status", 3, ProtoType::String)
+}
+
+fn insert_orders(db: &mut Database, rows: Vec<(u64, Row)>) -> Result<(), String> {
+    db.create_table("orders".to_string())?;
+    db.set_table_proto("orders", order_message()?)?;
+    rows.into_iter().try_for_each(|(id, data)| db.insert("orders", id, data))
+}
+
+/// Inserts `rows` one by one into the `orders` table of a new database.
+pub fn bulk_insert(rows: Vec<(u64, Row)>) -> Result<Database, String> {
+    let mut db = Database::new();
+    insert_orders(&mut db, rows)?;
+    Ok(db)
+}
+
+/// Reads each of `ids` from `orders`, returning how many exist.
+pub fn point_lookup(db: &Database, ids: &[u64]) -> Result<usize, String> {
+    ids.iter().try_fold(0, |found, &id| Ok(found + db.get("orders", id)?.is_some() as usize))
+}
+
+/// The shipped orders over 500, by SQL.
+pub fn scan_filter(db: &Database) -> Result<usize, String> {
+    Ok(db.query_sql("SELECT user, total FROM orders WHERE status = shipped AND total > 500")?.len())
+}
+
+/// The shipped orders of users in Oslo. There is no SQL join, so this
+/// looks each order's user up by key, as an application would.
+pub fn join(db: &Database) -> Result<usize, String> {
+    let shipped = db.query_sql("SELECT user FROM orders WHERE status = shipped")?;
+    let mut joined = 0;
+    for order in &shipped {
+        let user = order.data.get("user").and_then(|id| id.parse().ok()).ok_or("Order has no user")?;
+        if db.get("users", user)?.is_some_and(|user| user.data.get("city").is_some_and(|city| city == "Oslo")) {
+            joined += 1;
+        }
+    }
+    Ok(joined)
+}
+
+/// Registers every workload on the default dataset.
+pub fn benches(c: &mut Criterion) {
+    let dataset = Dataset::default();
+    let orders = dataset.orders();
+    let db = dataset.database().expect("generated data is valid");
+    let lookups: Vec<u64> = {
+        let mut random = Random::seeded(dataset.seed);
+        (0..1_000).map(|_| 1 + random.below(dataset.orders as usize) as u64).collect()
+    };
+
+    c.bench_function("bulk_insert", |b| {
+        b.iter_batched(|| orders.clone(), |rows| bulk_insert(black_box(rows)).unwrap(), BatchSize::LargeInput)
+    });
+    c.bench_function("point_lookup", |b| b.iter(|| point_lookup(black_box(&db), black_box(&lookups)).unwrap()));
+    c.bench_function("scan_filter", |b| b.iter(|| scan_filter(black_box(&db)).unwrap()));
+    c.bench_function("join", |b| b.iter(|| join(black_box(&db)).unwrap()));
+}
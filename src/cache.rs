@@ -0,0 +1,110 @@
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+
+use crate::{Database, Record};
+use crate::prelude::*;
+
+type Loader = Arc<dyn Fn(u64) -> Option<HashMap<String, String>> + Send + Sync>;
+
+// Recency of a cache table's records: each use takes the next tick.
+#[derive(Clone)]
+struct Cache {
+    capacity: usize,
+    loader: Option<Loader>,
+    clock: u64,
+    // id -> tick of its last use, and the reverse, oldest first
+    used: HashMap<u64, u64>,
+    order: BTreeMap<u64, u64>,
+}
+
+impl Cache {
+    fn touch(&mut self, id: u64) {
+        if let Some(tick) = self.used.remove(&id) {
+            self.order.remove(&tick);
+        }
+        self.clock += 1;
+        self.used.insert(id, self.clock);
+        self.order.insert(self.clock, id);
+    }
+
+    fn pop_least_recent(&mut self) -> Option<u64> {
+        let (_, id) = self.order.pop_first()?;
+        self.used.remove(&id);
+        Some(id)
+    }
+}
+
+// cache table -> its recency; not saved, as loaders are closures
+#[derive(Clone, Default)]
+pub(crate) struct Caches {
+    tables: HashMap<String, Cache>,
+}
+
+impl Database {
+    /// Makes a table a cache of at most `capacity` records: inserting past
+    /// that evicts the least recently used record, where a use is an insert
+    /// or a [`cache_get`](Self::cache_get). Writes go straight to the table,
+    /// so cached records are saved like any other. Cache mode, and the
+    /// loader, are not saved; call this again after loading.
+    pub fn enable_cache(&mut self, table_name: &str, capacity: usize) -> Result<(), String> {
+        if capacity == 0 {
+            return Err("Cache tables must hold at least one record".to_string());
+        }
+        let table = self.tables.get(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        let mut cache = Cache { capacity, loader: None, clock: 0, used: HashMap::new(), order: BTreeMap::new() };
+        // existing records count as used in table order
+        table.records.iter().for_each(|r| cache.touch(r.id));
+        self.caches.tables.insert(table_name.to_string(), cache);
+        self.evict_least_recent(table_name);
+        Ok(())
+    }
+
+    /// Sets the function a [`cache_get`](Self::cache_get) miss reads
+    /// through to, such as a slower database or service. A record it returns
+    /// is inserted into the cache table.
+    pub fn set_cache_loader(&mut self, table_name: &str, loader: impl Fn(u64) -> Option<HashMap<String, String>> + Send + Sync + 'static) -> Result<(), String> {
+        let cache = self.caches.tables.get_mut(table_name).ok_or(format!("Table '{}' is not a cache", table_name))?;
+        cache.loader = Some(Arc::new(loader));
+        Ok(())
+    }
+
+    /// Reads a record from a cache table, marking it recently used. On a
+    /// miss the loader, if any, is asked for it and its answer is cached.
+    pub fn cache_get(&mut self, table_name: &str, id: u64) -> Result<Option<&Record>, String> {
+        let cache = self.caches.tables.get_mut(table_name).ok_or(format!("Table '{}' is not a cache", table_name))?;
+        if self.tables.get(table_name).is_some_and(|t| t.index.contains_key(&id)) {
+            cache.touch(id);
+        } else {
+            let Some(data) = cache.loader.clone().and_then(|load| load(id)) else {
+                return Ok(None);
+            };
+            self.insert(table_name, id, data)?;
+        }
+        self.get(table_name, id)
+    }
+
+    // Called after each insert.
+    pub(crate) fn cache_inserted(&mut self, table_name: &str, id: u64) {
+        if let Some(cache) = self.caches.tables.get_mut(table_name) {
+            cache.touch(id);
+            self.evict_least_recent(table_name);
+        }
+    }
+
+    // Evicts records through `delete`, so evictions are changes like any
+    // other. Records deleted directly are skipped.
+    fn evict_least_recent(&mut self, table_name: &str) {
+        let Some(table) = self.tables.get(table_name) else {
+            return;
+        };
+        let mut over = table.records.len().saturating_sub(self.caches.tables[table_name].capacity);
+        while over > 0 {
+            let Some(id) = self.caches.tables.get_mut(table_name).and_then(Cache::pop_least_recent) else {
+                return;
+            };
+            if self.delete(table_name, id).is_ok() {
+                over -= 1;
+            }
+        }
+    }
+}
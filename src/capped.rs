@@ -0,0 +1,50 @@
+use crate::Database;
+use crate::prelude::*;
+
+// Splits a trailing `MAX ROWS n` off the rest of a CREATE TABLE.
+#[cfg(feature = "sql")]
+pub(crate) fn split_max_rows(definition: &str) -> Result<(&str, Option<usize>), String> {
+    let tokens: Vec<&str> = definition.split_whitespace().collect();
+    let [.., max, rows, n] = tokens[..] else {
+        return Ok((definition, None));
+    };
+    if !max.eq_ignore_ascii_case("MAX") || !rows.eq_ignore_ascii_case("ROWS") {
+        return Ok((definition, None));
+    }
+    let n = n.parse().map_err(|_| format!("Invalid MAX ROWS '{}'", n))?;
+    let end = definition.rfind(max).unwrap_or_default();
+    Ok((definition[..end].trim_end(), Some(check_cap(n)?)))
+}
+
+fn check_cap(max_rows: usize) -> Result<usize, String> {
+    if max_rows == 0 {
+        return Err("Capped tables must keep at least one row".to_string());
+    }
+    Ok(max_rows)
+}
+
+impl Database {
+    /// Creates a table holding at most `max_rows` records, as
+    /// `CREATE TABLE t MAX ROWS n` does: inserting past the cap evicts the
+    /// oldest records first, like a ring buffer. SQL INSERTs number rows
+    /// after the newest one. Evictions are recorded as deletes in the
+    /// change log and history.
+    pub fn create_capped_table(&mut self, name: &str, max_rows: usize) -> Result<(), String> {
+        let max_rows = check_cap(max_rows)?;
+        self.create_table(name.to_string())?;
+        if let Some(table) = self.tables.get_mut(name) {
+            table.max_rows = Some(max_rows);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn evict_over_cap(&mut self, table_name: &str) {
+        let Some(table) = self.tables.get(table_name) else {
+            return;
+        };
+        if let Some(max_rows) = table.max_rows {
+            let over = table.records.len().saturating_sub(max_rows);
+            self.drop_oldest(table_name, over);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use alloc::collections::BTreeSet;
+
+use crate::{Database, Record, Row, Table};
+use crate::prelude::*;
+
+/// The read-only catalog table listing every table, with the columns
+/// `name`, `kind` (`table`, `temporary`, `series`, `queue` or `virtual`)
+/// and `rows`, counting the rows the session's row policies let it see,
+/// which virtual tables leave out.
+pub const TABLES_CATALOG: &str = "__tables";
+
+/// The read-only catalog table listing the columns of every table, with
+/// the columns `table`, `column`, `position` (from 1), `type` (the
+/// protobuf type of a typed table's field, and `text` otherwise) and, for
+/// generated columns, `generated` (`stored` or `virtual`). Columns of
+/// untyped tables are the ones their rows hold, in name order.
+pub const COLUMNS_CATALOG: &str = "__columns";
+
+/// The read-only catalog table listing the indexes of every table, with
+/// the columns `table`, `column` and `kind`: `primary` for the id every
+/// table is keyed by, `hash` or `range` for a partitioning, and `expiry`
+/// for the column rows expire by.
+pub const INDEXES_CATALOG: &str = "__indexes";
+
+// Table names starting with this are kept for the catalog.
+pub(crate) const RESERVED_PREFIX: &str = "__";
+
+pub(crate) fn is_catalog(name: &str) -> bool {
+    [TABLES_CATALOG, COLUMNS_CATALOG, INDEXES_CATALOG].contains(&name)
+}
+
+fn row(pairs: &[(&str, String)]) -> Row {
+    pairs.iter().map(|(column, value)| (column.to_string(), value.clone())).collect()
+}
+
+impl Database {
+    // The catalog table `name` as it is now, if it is one.
+    pub(crate) fn catalog(&self, name: &str) -> Option<Table> {
+        let rows = match name {
+            TABLES_CATALOG => self.catalog_tables(),
+            COLUMNS_CATALOG => self.catalog_columns(),
+            INDEXES_CATALOG => self.catalog_indexes(),
+            _ => return None,
+        };
+        let records = rows.into_iter().zip(1..).map(|(data, id)| Record { id, data }).collect();
+        Some(Table::transient(name, records))
+    }
+
+    // Stored and virtual tables, by name.
+    fn catalog_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.tables.keys().chain(self.virtual_tables.keys()).map(AsRef::as_ref).collect();
+        names.sort();
+        names
+    }
+
+    fn catalog_tables(&self) -> Vec<Row> {
+        self.catalog_names().into_iter()
+            .map(|name| match self.tables.get(name) {
+                Some(table) => {
+                    let kind = match table {
+                        t if t.temporary => "temporary",
+                        t if t.series.is_some() => "series",
+                        t if t.queue.is_some() => "queue",
+                        _ => "table",
+                    };
+                    let mut data = row(&[("name", name.to_string()), ("kind", kind.to_string())]);
+                    // only the rows the session's policies let it see,
+                    // and none if they name an attribute that isn't set
+                    let rows = match self.policies.condition(name) {
+                        Ok(None) => Some(table.records.len()),
+                        Ok(Some(policy)) => Some(table.records.iter().filter(|r| table.matches(r, &policy)).count()),
+                        Err(_) => None,
+                    };
+                    if let Some(rows) = rows {
+                        data.insert("rows".to_string(), rows.to_string());
+                    }
+                    data
+                }
+                None => row(&[("name", name.to_string()), ("kind", "virtual".to_string())]),
+            })
+            .collect()
+    }
+
+    fn catalog_columns(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        for name in self.catalog_names() {
+            let (proto, columns) = match self.tables.get(name) {
+                Some(table) => {
+                    let mut columns: BTreeSet<String> = table.records.iter().flat_map(|r| r.data.keys().cloned()).collect();
+                    columns.extend(table.generated.iter().map(|g| g.name().to_string()));
+                    columns.extend(table.enums.keys().cloned());
+                    (table.proto.clone(), columns)
+                }
+                None => (self.virtual_tables[name].schema(), BTreeSet::new()),
+            };
+            let columns = match &proto {
+                Some(proto) => proto.columns(),
+                None => columns.into_iter().collect(),
+            };
+            let generated = self.tables.get(name).map_or(&[][..], |t| &t.generated);
+            for (column, position) in columns.into_iter().zip(1..) {
+                let ty = proto.as_ref().and_then(|p| p.type_of(&column)).map_or("text".to_string(), |ty| format!("{:?}", ty).to_lowercase());
+                let mut data = row(&[("table", name.to_string()), ("column", column.clone()), ("position", position.to_string()), ("type", ty)]);
+                if let Some(g) = generated.iter().find(|g| g.name() == column) {
+                    data.insert("generated".to_string(), if g.is_stored() { "stored" } else { "virtual" }.to_string());
+                }
+                rows.push(data);
+            }
+        }
+        rows
+    }
+
+    fn catalog_indexes(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        for (name, table) in self.catalog_names().into_iter().filter_map(|name| Some((name, self.tables.get(name)?))) {
+            rows.push(row(&[("table", name.to_string()), ("column", "id".to_string()), ("kind", "primary".to_string())]));
+            if let Some(partitions) = &table.partitions {
+                let kind = match partitions.scheme() {
+                    crate::PartitionScheme::Hash { .. } => "hash",
+                    crate::PartitionScheme::Range { .. } => "range",
+                };
+                rows.push(row(&[("table", name.to_string()), ("column", partitions.scheme().column().to_string()), ("kind", kind.to_string())]));
+            }
+            if let Some(expiry) = &table.expiry {
+                rows.push(row(&[("table", name.to_string()), ("column", expiry.column().to_string()), ("kind", "expiry".to_string())]));
+            }
+        }
+        rows
+    }
+}
@@ -0,0 +1,194 @@
+use alloc::collections::VecDeque;
+#[cfg(feature = "std")]
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+#[cfg(feature = "std")]
+use crate::schema::schema_of;
+use crate::{Database, Record};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangeKind {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// A committed mutation. `record` is the row after an insert or update and
+/// the removed row for a delete.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChangeEvent {
+    seq: u64,
+    table: String,
+    kind: ChangeKind,
+    record: Record,
+}
+
+impl ChangeEvent {
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    pub fn kind(&self) -> ChangeKind {
+        self.kind
+    }
+
+    pub fn record(&self) -> &Record {
+        &self.record
+    }
+}
+
+#[cfg(feature = "std")]
+type Filter = Arc<dyn Fn(&ChangeEvent) -> bool + Send + Sync>;
+
+#[cfg(feature = "std")]
+#[derive(Clone)]
+struct Subscriber {
+    table: String,
+    filter: Filter,
+    sender: Sender<ChangeEvent>,
+}
+
+// How many changes are kept unless `Database::set_change_retention` says
+// otherwise.
+const MAX_CHANGES: usize = 10_000;
+
+// Kept in memory only, apart from the latest sequence number, which is
+// saved so that numbering carries on after a database is loaded.
+#[derive(Clone)]
+pub(crate) struct ChangeLog {
+    last_seq: u64,
+    events: VecDeque<ChangeEvent>,
+    max_changes: Option<usize>,
+    // rows deleted from each table since it was last compacted
+    pub(crate) tombstones: HashMap<String, usize>,
+    #[cfg(feature = "std")]
+    subscribers: Vec<Subscriber>,
+}
+
+impl Default for ChangeLog {
+    fn default() -> Self {
+        ChangeLog {
+            last_seq: 0,
+            events: VecDeque::new(),
+            max_changes: Some(MAX_CHANGES),
+            tombstones: HashMap::new(),
+            #[cfg(feature = "std")]
+            subscribers: Vec::new(),
+        }
+    }
+}
+
+impl ChangeLog {
+    pub(crate) fn push(&mut self, table: &str, kind: ChangeKind, record: Record) {
+        self.last_seq += 1;
+        if kind == ChangeKind::Delete {
+            *self.tombstones.entry(table.to_string()).or_default() += 1;
+        }
+        let event = ChangeEvent { seq: self.last_seq, table: table.to_string(), kind, record };
+        // subscribers whose receiver was dropped are forgotten here
+        #[cfg(feature = "std")]
+        self.subscribers.retain(|s| s.table != table || !(s.filter)(&event) || s.sender.send(event.clone()).is_ok());
+        self.events.push_back(event);
+        self.trim();
+    }
+
+    // Drops the oldest changes past `max_changes`.
+    fn trim(&mut self) {
+        if let Some(max) = self.max_changes {
+            let excess = self.events.len().saturating_sub(max);
+            self.events.drain(..excess);
+        }
+    }
+
+    // How many changes are logged.
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub(crate) fn len(&self) -> usize {
+        self.events.len()
+    }
+}
+
+// Saves a change log as its latest sequence number.
+pub(crate) fn persistent_seq<S: serde::Serializer>(log: &ChangeLog, serializer: S) -> Result<S::Ok, S::Error> {
+    log.last_seq.serialize(serializer)
+}
+
+// Starts an empty change log that numbers on from a saved one.
+pub(crate) fn restored_log<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<ChangeLog, D::Error> {
+    Ok(ChangeLog { last_seq: u64::deserialize(deserializer)?, ..ChangeLog::default() })
+}
+
+impl Database {
+    /// Changes with a sequence number greater than `seq`, oldest first.
+    /// Pass 0 to read the whole log. Changes the log no longer keeps are
+    /// skipped; [`changes_complete_after`](Self::changes_complete_after)
+    /// says whether any were.
+    pub fn changes_since(&self, seq: u64) -> impl Iterator<Item = ChangeEvent> + '_ {
+        let start = self.changes.events.partition_point(|e| e.seq <= seq);
+        self.changes.events.range(start..).cloned()
+    }
+
+    /// The sequence number of the latest change, or 0 if nothing has changed.
+    pub fn last_change_seq(&self) -> u64 {
+        self.changes.last_seq
+    }
+
+    /// Whether the log still holds every change after `seq`. Once it
+    /// doesn't, [`changes_since`](Self::changes_since) leaves out the
+    /// discarded ones and a reader has to start again from a snapshot.
+    pub fn changes_complete_after(&self, seq: u64) -> bool {
+        seq == self.changes.last_seq || self.changes.events.front().is_some_and(|e| e.seq <= seq + 1)
+    }
+
+    // Replays a change from another database. Tables created there after
+    // the last sync appear with their first row, along with their schema,
+    // and replaying a change that is already applied is harmless.
+    #[cfg(feature = "std")]
+    pub(crate) fn apply_change(&mut self, table: &str, kind: ChangeKind, record: &Record) -> Result<(), String> {
+        if !self.tables.contains_key(table) {
+            self.schemas.extend(schema_of(table).map(String::from));
+            self.create_table(table.to_string())?;
+        }
+        let exists = self.get(table, record.id)?.is_some();
+        match (kind, exists) {
+            (ChangeKind::Delete, false) => Ok(()),
+            (ChangeKind::Delete, true) => self.delete(table, record.id),
+            (_, true) => self.update(table, record.id, record.data.clone()),
+            (_, false) => self.insert(table, record.id, record.data.clone()),
+        }
+    }
+
+    /// Delivers every later change to `table` that matches `filter` on the
+    /// returned channel. Dropping the receiver ends the subscription.
+    #[cfg(feature = "std")]
+    pub fn subscribe(&mut self, table: &str, filter: impl Fn(&ChangeEvent) -> bool + Send + Sync + 'static) -> Receiver<ChangeEvent> {
+        let (sender, receiver) = channel();
+        self.changes.subscribers.push(Subscriber { table: table.to_string(), filter: Arc::new(filter), sender });
+        receiver
+    }
+
+    /// Keeps only the newest `max_changes` logged changes, 10,000 unless
+    /// set; `None` keeps every change until
+    /// [`discard_changes_through`](Self::discard_changes_through). Readers
+    /// further behind than that, such as replication followers, catch up
+    /// from a snapshot instead.
+    pub fn set_change_retention(&mut self, max_changes: Option<usize>) {
+        self.changes.max_changes = max_changes;
+        self.changes.trim();
+    }
+
+    /// Drops logged changes up to and including `seq` once every consumer
+    /// has read them. Sequence numbers keep increasing.
+    pub fn discard_changes_through(&mut self, seq: u64) {
+        let end = self.changes.events.partition_point(|e| e.seq <= seq);
+        self.changes.events.drain(..end);
+    }
+}
@@ -0,0 +1,37 @@
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+// Instant::now panics on wasm32-unknown-unknown and builds without std have
+// no clock, so timers read zero there.
+pub(crate) struct Timer {
+    #[cfg(feature = "std")]
+    start: Option<Instant>,
+}
+
+impl Timer {
+    pub(crate) fn start() -> Self {
+        Timer {
+            #[cfg(feature = "std")]
+            start: if cfg!(target_arch = "wasm32") { None } else { Some(Instant::now()) },
+        }
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        #[cfg(feature = "std")]
+        if let Some(start) = self.start {
+            return start.elapsed();
+        }
+        Duration::ZERO
+    }
+}
+
+// SystemTime::now panics on wasm32-unknown-unknown too, so this reads 0
+// there, as it does without std.
+pub(crate) fn unix_millis() -> u64 {
+    #[cfg(feature = "std")]
+    if !cfg!(target_arch = "wasm32") {
+        return SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64);
+    }
+    0
+}
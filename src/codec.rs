@@ -0,0 +1,159 @@
+use std::sync::Arc;
+
+use bincode::Options;
+use serde::de::DeserializeOwned;
+
+use crate::Database;
+
+/// How the body of a saved database is encoded. Each file names its
+/// codec in its header, so it loads with the codec it was saved with
+/// whichever the loading database prefers.
+///
+/// Codecs work through [`erased_serde`], so one can wrap any serde data
+/// format, or another codec:
+///
+/// ```
+/// use potatodb::{erased_serde, BincodeCodec, Codec, Database};
+///
+/// // bincode, with every byte inverted
+/// struct Inverted;
+///
+/// impl Codec for Inverted {
+///     fn name(&self) -> &str {
+///         "inverted"
+///     }
+///
+///     fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>, String> {
+///         Ok(BincodeCodec.encode(value)?.iter().map(|b| !b).collect())
+///     }
+///
+///     fn decode(&self, bytes: &[u8], read: &mut dyn FnMut(&mut dyn erased_serde::Deserializer) -> Result<(), erased_serde::Error>) -> Result<(), String> {
+///         let bytes: Vec<u8> = bytes.iter().map(|b| !b).collect();
+///         BincodeCodec.decode(&bytes, read)
+///     }
+/// }
+///
+/// let mut db = Database::new();
+/// db.set_codec(Inverted).unwrap();
+/// let bytes = db.to_bytes().unwrap();
+/// assert!(Database::from_bytes(&bytes).is_err());
+/// assert_eq!(Database::from_bytes_with_codec(&bytes, Inverted).unwrap().codec_name(), "inverted");
+/// ```
+pub trait Codec: Send + Sync {
+    /// Identifies the codec in file headers; at most 255 bytes.
+    fn name(&self) -> &str;
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>, String>;
+    /// Passes `read` a deserializer over `bytes`.
+    fn decode(&self, bytes: &[u8], read: &mut dyn FnMut(&mut dyn erased_serde::Deserializer) -> Result<(), erased_serde::Error>) -> Result<(), String>;
+}
+
+/// Compact binary bincode, and what files were written in before codecs
+/// could be chosen. The default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BincodeCodec;
+
+// the options of bincode::serialize and bincode::deserialize
+fn bincode_options() -> impl Options {
+    bincode::DefaultOptions::new().with_fixint_encoding().allow_trailing_bytes()
+}
+
+impl Codec for BincodeCodec {
+    fn name(&self) -> &str {
+        "bincode"
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>, String> {
+        bincode_options().serialize(value).map_err(|e| e.to_string())
+    }
+
+    fn decode(&self, bytes: &[u8], read: &mut dyn FnMut(&mut dyn erased_serde::Deserializer) -> Result<(), erased_serde::Error>) -> Result<(), String> {
+        let mut bincode = bincode::Deserializer::from_slice(bytes, bincode_options());
+        read(&mut <dyn erased_serde::Deserializer>::erase(&mut bincode)).map_err(|e| e.to_string())
+    }
+}
+
+/// CBOR (RFC 8949), which other tools can read without knowing the layout.
+#[cfg(feature = "cbor")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CborCodec;
+
+#[cfg(feature = "cbor")]
+impl Codec for CborCodec {
+    fn name(&self) -> &str {
+        "cbor"
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>, String> {
+        serde_cbor::to_vec(&value).map_err(|e| e.to_string())
+    }
+
+    fn decode(&self, bytes: &[u8], read: &mut dyn FnMut(&mut dyn erased_serde::Deserializer) -> Result<(), erased_serde::Error>) -> Result<(), String> {
+        let mut cbor = serde_cbor::Deserializer::from_slice(bytes);
+        read(&mut <dyn erased_serde::Deserializer>::erase(&mut cbor)).map_err(|e| e.to_string())
+    }
+}
+
+/// MessagePack.
+#[cfg(feature = "msgpack")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MessagePackCodec;
+
+#[cfg(feature = "msgpack")]
+impl Codec for MessagePackCodec {
+    fn name(&self) -> &str {
+        "msgpack"
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>, String> {
+        rmp_serde::to_vec(value).map_err(|e| e.to_string())
+    }
+
+    fn decode(&self, bytes: &[u8], read: &mut dyn FnMut(&mut dyn erased_serde::Deserializer) -> Result<(), erased_serde::Error>) -> Result<(), String> {
+        let mut msgpack = rmp_serde::Deserializer::new(bytes);
+        read(&mut <dyn erased_serde::Deserializer>::erase(&mut msgpack)).map_err(|e| e.to_string())
+    }
+}
+
+// The built-in codec called `name`, if this build has it.
+pub(crate) fn built_in(name: &str) -> Option<Arc<dyn Codec>> {
+    match name {
+        "bincode" => Some(Arc::new(BincodeCodec)),
+        #[cfg(feature = "cbor")]
+        "cbor" => Some(Arc::new(CborCodec)),
+        #[cfg(feature = "msgpack")]
+        "msgpack" => Some(Arc::new(MessagePackCodec)),
+        _ => None,
+    }
+}
+
+// Decodes a database body, of this version or an older one, with `codec`.
+pub(crate) fn decode<T: DeserializeOwned>(codec: &dyn Codec, body: &[u8]) -> Result<T, String> {
+    let mut db = None;
+    codec.decode(body, &mut |de| {
+        db = Some(erased_serde::deserialize::<T>(de)?);
+        Ok(())
+    })?;
+    db.ok_or_else(|| format!("Codec '{}' decoded nothing", codec.name()))
+}
+
+impl Database {
+    /// Saves later copies of the database with `codec`.
+    pub fn set_codec(&mut self, codec: impl Codec + 'static) -> Result<(), String> {
+        if codec.name().len() > u8::MAX as usize {
+            return Err(format!("Codec name '{}' is longer than 255 bytes", codec.name()));
+        }
+        self.codec = Some(Arc::new(codec));
+        Ok(())
+    }
+
+    /// The name of the codec the database is saved with.
+    pub fn codec_name(&self) -> &str {
+        self.codec.as_deref().map_or("bincode", |codec| codec.name())
+    }
+
+    /// Like [`Database::from_bytes`], for files saved with a codec that is
+    /// not built in. The loaded database keeps saving with it.
+    pub fn from_bytes_with_codec(bytes: &[u8], codec: impl Codec + 'static) -> Result<Self, Box<dyn std::error::Error>> {
+        crate::format::decode_with(bytes, Some(Arc::new(codec)))
+    }
+}
@@ -0,0 +1,198 @@
+
+use crate::aggregate::{Accumulator, Rows};
+use crate::arena::{self, ArenaVec};
+use crate::{interrupt, Database, Privilege, Record, Row, Table};
+use crate::prelude::*;
+
+// A column of a columnar table: each record's value in table order, and
+// the value as a number where it is one.
+struct Column {
+    text: Vec<Option<String>>,
+    numbers: Vec<Option<f64>>,
+}
+
+// The records of a table column by column, virtual columns included.
+struct ColumnStore {
+    ids: Vec<u64>,
+    columns: HashMap<String, Column>,
+}
+
+impl ColumnStore {
+    fn build(table: &Table) -> Self {
+        let len = table.records.len();
+        let mut columns: HashMap<String, Column> = HashMap::new();
+        for (position, record) in table.records.iter().enumerate() {
+            for (name, value) in &table.with_virtual(record).data {
+                let column = columns.entry(name.clone())
+                    .or_insert_with(|| Column { text: vec![None; len], numbers: vec![None; len] });
+                column.numbers[position] = value.parse().ok();
+                column.text[position] = Some(value.clone());
+            }
+        }
+        ColumnStore { ids: table.records.iter().map(|r| r.id).collect(), columns }
+    }
+}
+
+/// The column copy of a column-oriented table, built on the first read
+/// after a write.
+#[derive(Default)]
+pub(crate) struct Columnar {
+    store: OnceLock<ColumnStore>,
+}
+
+// Clones rebuild their columns when first read.
+impl Clone for Columnar {
+    fn clone(&self) -> Self {
+        Columnar::default()
+    }
+}
+
+// The rows of one group, or of the whole table, read from its columns.
+struct Group<'a> {
+    store: &'a ColumnStore,
+    rows: Option<&'a [usize]>,
+}
+
+impl Rows for Group<'_> {
+    fn count(&self) -> usize {
+        self.rows.map_or(self.store.ids.len(), <[usize]>::len)
+    }
+
+    fn values<'a>(&'a self, column: &'a str) -> Box<dyn Iterator<Item = &'a String> + 'a> {
+        let Some(column) = self.store.columns.get(column) else {
+            return Box::new(core::iter::empty());
+        };
+        match self.rows {
+            Some(rows) => Box::new(rows.iter().filter_map(|&row| column.text[row].as_ref())),
+            None => Box::new(column.text.iter().flatten()),
+        }
+    }
+
+    fn numbers(&self, column: &str) -> Vec<f64> {
+        let Some(column) = self.store.columns.get(column) else {
+            return Vec::new();
+        };
+        match self.rows {
+            Some(rows) => rows.iter().filter_map(|&row| column.numbers[row]).collect(),
+            None => column.numbers.iter().flatten().copied().collect(),
+        }
+    }
+
+    fn values_by<'a>(&'a self, column: &'a str, by: &'a str) -> Vec<(&'a String, Option<&'a String>)> {
+        let Some(values) = self.store.columns.get(column) else {
+            return Vec::new();
+        };
+        let by = self.store.columns.get(by);
+        let value_at = |row: usize| Some((values.text[row].as_ref()?, by.and_then(|by| by.text[row].as_ref())));
+        match self.rows {
+            Some(rows) => rows.iter().filter_map(|&row| value_at(row)).collect(),
+            None => (0..self.store.ids.len()).filter_map(value_at).collect(),
+        }
+    }
+}
+
+impl Table {
+    // Called by every write to the table.
+    pub(crate) fn invalidate_columns(&mut self) {
+        if let Some(columnar) = &mut self.columnar {
+            columnar.store = OnceLock::new();
+        }
+    }
+
+    fn columns(&self) -> Option<&ColumnStore> {
+        let columnar = self.columnar.as_ref()?;
+        Some(columnar.store.get_or_init(|| ColumnStore::build(self)))
+    }
+
+    // The named columns of every record, or None unless the table is
+    // columnar and no column is `*`.
+    #[cfg(feature = "sql")]
+    pub(crate) fn select_columns(&self, columns: &[String]) -> Option<Vec<Record>> {
+        if columns.iter().any(|c| c == "*") {
+            return None;
+        }
+        let store = self.columns()?;
+        let selected: Vec<(&String, &Column)> = columns.iter()
+            .filter_map(|name| Some((name, store.columns.get(name)?)))
+            .collect();
+        Some(store.ids.iter().enumerate()
+            .take_while(|_| interrupt::running())
+            .map(|(position, &id)| {
+                let data = selected.iter()
+                    .filter_map(|(name, column)| Some((name.to_string(), column.text[position].clone()?)))
+                    .collect();
+                Record { id, data }
+            })
+            .inspect(interrupt::keep)
+            .collect())
+    }
+}
+
+impl Database {
+    /// Makes a table column-oriented, or row-oriented again. A columnar
+    /// table also keeps each column as one contiguous list, rebuilt on the
+    /// first read after a write, which aggregation pipelines starting with
+    /// a group and SELECTs of named columns without WHERE read instead of
+    /// the records. Suits tables read far more than written, at about
+    /// twice the memory. The setting is saved with the table.
+    pub fn set_columnar(&mut self, table_name: &str, columnar: bool) -> Result<(), String> {
+        let table = self.tables.get_mut(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        table.columnar = columnar.then(Columnar::default);
+        Ok(())
+    }
+
+    // A pipeline's leading group stage, read from the columns. None unless
+    // the table is columnar and the session reads it unfiltered, unmasked
+    // and without decrypting, which only SQL does.
+    pub(crate) fn group_columns(&self, table_name: &str, key: Option<&str>, accumulators: &[(String, String)]) -> Result<Option<Vec<Record>>, String> {
+        let Some(table) = self.tables.get(table_name) else {
+            return Ok(None);
+        };
+        if !table.encrypted.is_empty() || !self.session_allows(table_name, Privilege::Select) {
+            return Ok(None);
+        }
+        #[cfg(feature = "sql")]
+        if self.policies.restricts(table_name) || self.masks_apply(table_name) {
+            return Ok(None);
+        }
+        let Some(store) = table.columns() else {
+            return Ok(None);
+        };
+        let accumulators = accumulators.iter()
+            .map(|(name, a)| Ok((name, Accumulator::parse(a)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        let _statement = interrupt::begin(&self.limits, self.settings, None);
+        arena::scoped(|arena| {
+            // groups keep the order their first row came in
+            let mut groups: ArenaVec<(Option<&String>, Option<ArenaVec<usize>>)> = ArenaVec::new_in(arena);
+            match key {
+                None => groups.push((None, None)),
+                Some(key) => {
+                    let mut positions: HashMap<&String, usize> = HashMap::new();
+                    let values = store.columns.get(key).map_or(&[][..], |c| c.text.as_slice());
+                    for (row, value) in values.iter().enumerate().take_while(|_| interrupt::running()) {
+                        let Some(value) = value else { continue };
+                        let position = *positions.entry(value).or_insert_with(|| {
+                            groups.push((Some(value), Some(ArenaVec::new_in(arena))));
+                            groups.len() - 1
+                        });
+                        groups[position].1.get_or_insert_with(|| ArenaVec::new_in(arena)).push(row);
+                    }
+                    interrupt::check()?;
+                }
+            }
+            Ok(Some((1..).zip(groups)
+                .map(|(id, (value, rows))| {
+                    let rows = Group { store, rows: rows.as_deref() };
+                    let mut data: Row = accumulators.iter()
+                        .filter_map(|(name, accumulator)| Some((name.to_string(), accumulator.apply(&rows)?)))
+                        .collect();
+                    if let (Some(key), Some(value)) = (key, value) {
+                        data.insert(key.to_string(), value.clone());
+                    }
+                    Record { id, data }
+                })
+                .collect()))
+        })
+    }
+}
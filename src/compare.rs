@@ -0,0 +1,111 @@
+use core::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::history::parse_timestamp;
+use crate::prelude::*;
+use crate::value;
+use crate::Database;
+
+/// How the values of a column compare in WHERE clauses and
+/// [`Pipeline::sort`](crate::Pipeline::sort), so the text columns of an
+/// untyped database order correctly without moving to a typed schema. Set
+/// with [`Database::set_compare_as`]. Values that can't be read as numbers
+/// or dates match no comparison but `!=`, and sort after those that can.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompareAs {
+    /// Byte by byte, as text.
+    String,
+    /// As numbers, so `9` comes before `10` and `1.0` equals `1`.
+    Numeric,
+    /// As UTC dates and times: `2024-01-31`, `2024/01/31`,
+    /// `2024-01-31 12:30:00`, `2024-01-31T12:30:00Z` or milliseconds since
+    /// the Unix epoch.
+    Date,
+    /// As text with runs of digits compared as numbers, so `file2` comes
+    /// before `file10`.
+    Natural,
+    /// As [`Value`](crate::Value)s: numbers as numbers, booleans as
+    /// booleans and text as text. Values of different kinds, such as `30`
+    /// and `unknown`, match no comparison but `!=`; they sort booleans
+    /// first, then numbers, text and bytes.
+    #[default]
+    Value,
+}
+
+impl CompareAs {
+    // None when either value can't be read this way.
+    pub(crate) fn compare(self, a: &str, b: &str) -> Option<Ordering> {
+        match self {
+            CompareAs::String => Some(a.cmp(b)),
+            CompareAs::Numeric => Some(number(a)?.total_cmp(&number(b)?)),
+            CompareAs::Date => Some(date(a)?.cmp(&date(b)?)),
+            CompareAs::Natural => Some(natural(a, b).then_with(|| a.cmp(b))),
+            CompareAs::Value => value::order(a, b, str::cmp),
+        }
+    }
+
+    // Orders any two values, those that can't be read this way last.
+    pub(crate) fn sort(self, a: &str, b: &str) -> Ordering {
+        if self == CompareAs::Value {
+            return value::sort(a, b);
+        }
+        self.compare(a, b).unwrap_or_else(|| {
+            let (a_read, b_read) = (self.compare(a, a).is_some(), self.compare(b, b).is_some());
+            b_read.cmp(&a_read).then_with(|| a.cmp(b))
+        })
+    }
+}
+
+fn number(value: &str) -> Option<f64> {
+    value.trim().parse().ok().filter(|n: &f64| !n.is_nan())
+}
+
+fn date(value: &str) -> Option<u64> {
+    let value = value.trim().replace('/', "-");
+    parse_timestamp(&value.replacen(' ', "T", 1)).ok()
+}
+
+fn natural(mut a: &str, mut b: &str) -> Ordering {
+    loop {
+        let (Some(x), Some(y)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+        if x.is_ascii_digit() && y.is_ascii_digit() {
+            let (a_digits, a_rest) = a.split_at(a.find(|c: char| !c.is_ascii_digit()).unwrap_or(a.len()));
+            let (b_digits, b_rest) = b.split_at(b.find(|c: char| !c.is_ascii_digit()).unwrap_or(b.len()));
+            let (a_digits, b_digits) = (a_digits.trim_start_matches('0'), b_digits.trim_start_matches('0'));
+            match a_digits.len().cmp(&b_digits.len()).then_with(|| a_digits.cmp(b_digits)) {
+                Ordering::Equal => (a, b) = (a_rest, b_rest),
+                ordering => return ordering,
+            }
+        } else if x != y {
+            return x.cmp(&y);
+        } else {
+            (a, b) = (&a[x.len_utf8()..], &b[y.len_utf8()..]);
+        }
+    }
+}
+
+impl Database {
+    /// Sets how a column's values compare. [`CompareAs::Value`], the
+    /// default, compares them by type, so numbers compare as numbers. The
+    /// partition column of a table always compares as plain text, as its
+    /// partitions are picked by it.
+    pub fn set_compare_as(&mut self, table_name: &str, column: &str, compare: CompareAs) -> Result<(), String> {
+        let table = self.tables.get_mut(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        if !matches!(compare, CompareAs::Value | CompareAs::String) && table.partitions.as_ref().is_some_and(|p| p.scheme().column() == column) {
+            return Err(format!("Column '{}' partitions table '{}', so it compares as text", column, table_name));
+        }
+        match compare {
+            CompareAs::Value => table.compare.remove(column),
+            _ => table.compare.insert(column.to_string(), compare),
+        };
+        Ok(())
+    }
+
+    pub fn compare_as(&self, table_name: &str, column: &str) -> Result<CompareAs, String> {
+        let table = self.tables.get(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        Ok(table.compare.get(column).copied().or_else(|| table.declared_compare(column)).unwrap_or_default())
+    }
+}
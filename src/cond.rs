@@ -0,0 +1,182 @@
+use core::fmt;
+
+use crate::clock::Timer;
+use crate::hints::Hints;
+use crate::interrupt;
+use crate::page::Page;
+use crate::prelude::*;
+use crate::{Condition, Database, Record, SqlStatement, Value};
+
+/// A WHERE clause built in code, for [`Database::query_where`],
+/// [`Database::update_where`] and [`Database::delete_where`]. It runs as
+/// the same clause written in SQL would: values compare as [`Value`]s,
+/// [`ID`](crate::ID) is the record id, and permissions and row policies
+/// apply.
+///
+/// ```
+/// use potatodb::Cond;
+///
+/// let cond = Cond::eq("age", 30).and(Cond::like("name", "A%"));
+/// assert_eq!(cond.to_string(), "(age = 30 AND name LIKE A%)");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Cond(pub(crate) Condition);
+
+impl Cond {
+    /// [`Value::Null`] matches rows without the column, as `IS NULL`.
+    pub fn eq(column: &str, value: impl Into<Value>) -> Cond {
+        match value.into() {
+            Value::Null => Cond(Condition::IsNull(column.to_string())),
+            value => Cond(Condition::Equals(column.to_string(), value.to_string())),
+        }
+    }
+
+    /// Also matches rows without the column. [`Value::Null`] matches rows
+    /// with it, as `IS NOT NULL`.
+    pub fn ne(column: &str, value: impl Into<Value>) -> Cond {
+        match value.into() {
+            Value::Null => Cond(Condition::IsNotNull(column.to_string())),
+            value => Cond(Condition::NotEquals(column.to_string(), value.to_string())),
+        }
+    }
+
+    /// Nothing is greater than [`Value::Null`], so it matches no rows.
+    pub fn gt(column: &str, value: impl Into<Value>) -> Cond {
+        match value.into() {
+            Value::Null => Cond::nothing(column),
+            value => Cond(Condition::GreaterThan(column.to_string(), value.to_string())),
+        }
+    }
+
+    /// Nothing is less than [`Value::Null`], so it matches no rows.
+    pub fn lt(column: &str, value: impl Into<Value>) -> Cond {
+        match value.into() {
+            Value::Null => Cond::nothing(column),
+            value => Cond(Condition::LessThan(column.to_string(), value.to_string())),
+        }
+    }
+
+    /// Both ends included; a [`Value::Null`] end matches no rows.
+    pub fn between(column: &str, low: impl Into<Value>, high: impl Into<Value>) -> Cond {
+        match (low.into(), high.into()) {
+            (Value::Null, _) | (_, Value::Null) => Cond::nothing(column),
+            (low, high) => Cond(Condition::Between(column.to_string(), low.to_string(), high.to_string())),
+        }
+    }
+
+    // A comparison with NULL, which no row satisfies.
+    fn nothing(column: &str) -> Cond {
+        Cond(Condition::IsNull(column.to_string())).and(Cond(Condition::IsNotNull(column.to_string())))
+    }
+
+    /// `%` in `pattern` matches any run of characters, `_` any one.
+    pub fn like(column: &str, pattern: &str) -> Cond {
+        Cond(Condition::Like(column.to_string(), pattern.to_string()))
+    }
+
+    pub fn and(self, other: Cond) -> Cond {
+        Cond(Condition::And(Box::new(self.0), Box::new(other.0)))
+    }
+
+    pub fn or(self, other: Cond) -> Cond {
+        Cond(Condition::Or(Box::new(self.0), Box::new(other.0)))
+    }
+}
+
+/// The clause as it would be written after WHERE, which is also what the
+/// query log and observers are given.
+impl fmt::Display for Cond {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+// Whether `text` matches a LIKE `pattern`. A `%` first takes no
+// characters, then one more each time the rest of the pattern fails.
+pub(crate) fn like(text: &str, pattern: &str) -> bool {
+    let (text, pattern): (Vec<char>, Vec<char>) = (text.chars().collect(), pattern.chars().collect());
+    let (mut t, mut p) = (0, 0);
+    // the pattern position after the last `%`, and where in the text it
+    // took up to
+    let mut wildcard = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('%') => {
+                p += 1;
+                wildcard = Some((p, t));
+            }
+            Some(&c) if c == '_' || c == text[t] => {
+                t += 1;
+                p += 1;
+            }
+            _ => match wildcard {
+                Some((after, taken)) => {
+                    wildcard = Some((after, taken + 1));
+                    (p, t) = (after, taken + 1);
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '%')
+}
+
+impl Database {
+    /// The rows of a table matching `condition`, as
+    /// `SELECT * FROM table WHERE condition` returns them.
+    pub fn query_where(&self, table_name: &str, condition: &Cond) -> Result<Vec<Record>, String> {
+        let timer = Timer::start();
+        let _statement = interrupt::begin(&self.limits, self.settings, None);
+        let sql = format!("SELECT * FROM {} WHERE {}", table_name, condition);
+        let select = SqlStatement::Select {
+            table: table_name.to_string(),
+            columns: vec!["*".to_string()],
+            condition: Some(condition.0.clone()),
+            as_of: None,
+            sample: None,
+            hints: Hints::default(),
+            order_by: Vec::new(),
+            page: Page::default(),
+            group_by: None,
+        };
+        let result = self.authorize(&select)
+            .and_then(|()| self.apply_policies(select))
+            .and_then(|select| self.execute_select(select));
+        self.finish_query(timer, "select", &sql, &result);
+        result
+    }
+
+    /// Sets `column` to `value` in the rows matching `condition` that have
+    /// it, as `UPDATE` does, and returns those rows.
+    pub fn update_where(&mut self, table_name: &str, condition: &Cond, column: &str, value: impl Into<Value>) -> Result<Vec<Record>, String> {
+        let value = value.into().to_string();
+        let sql = format!("UPDATE {} SET {} = {} WHERE {}", table_name, column, value, condition);
+        let update = SqlStatement::Update {
+            table: table_name.to_string(),
+            column: column.to_string(),
+            value,
+            condition: Some(condition.0.clone()),
+        };
+        self.execute_built(&sql, update)
+    }
+
+    /// Deletes the rows matching `condition` and returns them.
+    pub fn delete_where(&mut self, table_name: &str, condition: &Cond) -> Result<Vec<Record>, String> {
+        let sql = format!("DELETE FROM {} WHERE {}", table_name, condition);
+        let delete = SqlStatement::Delete { table: table_name.to_string(), condition: Some(condition.0.clone()) };
+        self.execute_built(&sql, delete)
+    }
+
+    // Runs a statement built in code as `execute_sql` runs parsed ones,
+    // logged as `sql`.
+    fn execute_built(&mut self, sql: &str, statement: SqlStatement) -> Result<Vec<Record>, String> {
+        let timer = Timer::start();
+        let _statement = interrupt::begin(&self.limits, self.settings, None);
+        let kind = statement.kind();
+        let result = self.authorize(&statement)
+            .and_then(|()| self.apply_policies(statement))
+            .and_then(|statement| self.execute_statement(statement));
+        self.finish_query(timer, kind, sql, &result);
+        result
+    }
+}
@@ -0,0 +1,182 @@
+use std::error::Error;
+use std::io::{BufRead, BufReader, Read};
+
+use crate::{generated, ChangeKind, Database, Record, Row, Table};
+use crate::prelude::*;
+
+/// How [`Database::copy_in`] reads rows, after PostgreSQL's COPY formats.
+/// Either way the first line names the columns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CopyFormat {
+    /// Tab-separated values, one row per line. `\N` leaves a column out, and
+    /// `\\`, `\t`, `\n` and `\r` stand for a backslash, tab, newline and
+    /// carriage return.
+    Text,
+    /// Comma-separated values. Fields may be double-quoted, with `""` for a
+    /// quote inside them; an unquoted empty field leaves the column out.
+    Csv,
+}
+
+// A row's fields, None for the columns it leaves out.
+type Fields = Vec<Option<String>>;
+
+impl CopyFormat {
+    fn read_row(self, reader: &mut impl BufRead) -> Result<Option<Fields>, Box<dyn Error>> {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if self == CopyFormat::Csv {
+            // a quoted field can run over several lines
+            while line.matches('"').count() % 2 == 1 {
+                if reader.read_line(&mut line)? == 0 {
+                    return Err("Unterminated quoted field".into());
+                }
+            }
+        }
+        let line = line.strip_suffix('\n').unwrap_or(&line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        Ok(Some(match self {
+            CopyFormat::Text => line.split('\t').map(unescape).collect::<Result<_, _>>()?,
+            CopyFormat::Csv => split_csv(line),
+        }))
+    }
+}
+
+fn unescape(field: &str) -> Result<Option<String>, String> {
+    if field == "\\N" {
+        return Ok(None);
+    }
+    let mut value = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        value.push(match c {
+            '\\' => match chars.next() {
+                Some('\\') => '\\',
+                Some('t') => '\t',
+                Some('n') => '\n',
+                Some('r') => '\r',
+                other => return Err(format!("Invalid escape '\\{}'", other.map(String::from).unwrap_or_default())),
+            },
+            c => c,
+        });
+    }
+    Ok(Some(value))
+}
+
+fn split_csv(line: &str) -> Fields {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let (mut quoted, mut in_quotes) = (false, false);
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => {
+                in_quotes = !in_quotes;
+                quoted = true;
+            }
+            ',' if !in_quotes => {
+                fields.push(Some(core::mem::take(&mut field)).filter(|f| quoted || !f.is_empty()));
+                quoted = false;
+            }
+            c => field.push(c),
+        }
+    }
+    fields.push(Some(field).filter(|f| quoted || !f.is_empty()));
+    fields
+}
+
+// The column names on the first line and the rows after it.
+pub(crate) fn read_rows(reader: impl Read, format: CopyFormat) -> Result<(Vec<String>, Vec<Row>), Box<dyn Error>> {
+    let mut reader = BufReader::new(reader);
+    let Some(header) = format.read_row(&mut reader)? else {
+        return Ok((Vec::new(), Vec::new()));
+    };
+    let columns: Vec<String> = header.into_iter().map(Option::unwrap_or_default).collect();
+    let mut rows = Vec::new();
+    while let Some(fields) = format.read_row(&mut reader)? {
+        if fields.len() != columns.len() {
+            return Err(format!("Row {} has {} values for {} columns", rows.len() + 1, fields.len(), columns.len()).into());
+        }
+        rows.push(columns.iter().zip(fields).filter_map(|(column, value)| Some((column.clone(), value?))).collect::<Row>());
+    }
+    Ok((columns, rows))
+}
+
+impl Database {
+    /// Bulk-loads rows into a table from `reader`, as `COPY table FROM`
+    /// does. The rows skip SQL parsing and are checked together once all of
+    /// them are read, so a bad row leaves the table unchanged, and then added
+    /// in one batch. Ids continue from the highest in the table. Returns the
+    /// number of rows loaded.
+    pub fn copy_in(&mut self, table_name: &str, reader: impl Read, format: CopyFormat) -> Result<usize, Box<dyn Error>> {
+        let table = self.tables.get(table_name).ok_or_else(|| format!("Table '{}' not found", table_name))?;
+        let (columns, mut rows) = read_rows(reader, format)?;
+        generated::check_writable(&table.generated, &columns)?;
+        for data in &mut rows {
+            self.prepare_row(table, data)?;
+        }
+        self.load_rows(table_name, rows)
+    }
+
+    // Fills in a row's timestamps, typed values and stored generated
+    // columns, then checks and seals it as an insert would.
+    pub(crate) fn prepare_row(&self, table: &Table, data: &mut Row) -> Result<(), String> {
+        table.stamp(None, data);
+        table.convert_types(data)?;
+        generated::fill(&table.generated, data);
+        table.check_enums(data)?;
+        self.keys.seal_row(table, data)?;
+        match &table.proto {
+            Some(proto) => proto.validate(data),
+            None => Ok(()),
+        }
+    }
+
+    // Adds prepared rows to a table in one batch, with ids continuing from
+    // the highest in it.
+    pub(crate) fn load_rows(&mut self, table_name: &str, rows: Vec<Row>) -> Result<usize, Box<dyn Error>> {
+        let table = self.tables.get(table_name).ok_or_else(|| format!("Table '{}' not found", table_name))?;
+        let first_id = table.index.keys().max().map_or(Some(1), |id| id.checked_add(1))
+            .map(|id| id.max(self.sequences.floor(table_name)))
+            .filter(|id| id.checked_add(rows.len() as u64).is_some())
+            .ok_or("No record ids left in the table")?;
+
+        let loaded = rows.len();
+        let table = self.tables.get_mut(table_name).expect("the table was found above");
+        table.records.reserve(loaded);
+        table.index.reserve(loaded);
+        for (id, mut data) in (first_id..).zip(rows) {
+            if let Some(partitions) = &mut table.partitions {
+                partitions.place(id, &data);
+            }
+            if let Some(expiry) = &mut table.expiry {
+                expiry.place(id, &data);
+            }
+            if let Some(history) = &mut table.history {
+                history.record(id, Some(&data));
+            }
+            self.audit.record(table_name, id, ChangeKind::Insert, None, Some(&data));
+            if let Some(newest) = table.records.last() {
+                data.share_names(&newest.data);
+            }
+            let record = Record { id, data };
+            table.index.insert(id, table.records.len());
+            table.records.push(record.clone());
+            self.changes.push(table_name, ChangeKind::Insert, record);
+        }
+        table.invalidate_columns();
+        if table.series.is_some() {
+            self.apply_retention(table_name)?;
+        }
+        self.evict_over_cap(table_name);
+        for id in first_id..first_id + loaded as u64 {
+            self.cache_inserted(table_name, id);
+        }
+        Ok(loaded)
+    }
+}
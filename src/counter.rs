@@ -0,0 +1,69 @@
+use crate::generated;
+use crate::prelude::*;
+use crate::{Database, Table, Value};
+#[cfg(feature = "sql")]
+use crate::{interrupt, Condition, Record};
+
+// `current + delta`, in whole numbers when both are whole.
+fn add(current: &str, delta: f64) -> Result<String, String> {
+    let sum = match Value::parse(current) {
+        Value::Int(current) if delta.fract() == 0.0 => current.checked_add(delta as i64).map(Value::Int).ok_or("Counter overflowed")?,
+        current => current.checked_add(&Value::Float(delta)).ok_or(format!("Cannot increment '{}': it is not a number", current))?,
+    };
+    Ok(sum.to_string())
+}
+
+impl Table {
+    // The value `column` of the record at `index` moves to, checked and
+    // ready to store.
+    pub(crate) fn incremented(&self, index: usize, column: &str, delta: f64, keys: &crate::encryption::Keys) -> Result<String, String> {
+        let current = self.records[index].data.get(column).map_or("0", String::as_str);
+        let value = self.convert_column(column, &add(current, delta)?)?;
+        if let Some(proto) = &self.proto {
+            proto.validate_value(column, &value)?;
+        }
+        self.check_enum_value(column, &value)?;
+        keys.seal(self, column, &value)
+    }
+
+    pub(crate) fn check_incrementable(&self, column: &str) -> Result<(), String> {
+        generated::check_writable(&self.generated, &[column.to_string()])?;
+        if self.encrypted.contains(column) {
+            return Err(format!("Cannot increment encrypted column '{}'", column));
+        }
+        Ok(())
+    }
+}
+
+impl Database {
+    /// Adds `delta` to a numeric column of a record in one step and returns
+    /// the new value, so counters need no read-modify-write by the caller.
+    /// A record without the column counts from 0. Whole numbers stay whole.
+    pub fn increment(&mut self, table_name: &str, id: u64, column: &str, delta: f64) -> Result<String, String> {
+        let table = self.tables.get(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        let index = *table.index.get(&id).ok_or(format!("Record with id {} not found in table '{}'", id, table_name))?;
+        table.check_incrementable(column)?;
+        let value = table.incremented(index, column, delta, &self.keys)?;
+        self.write_column(table_name, column, vec![(id, value.clone())])?;
+        Ok(value)
+    }
+
+    // `UPDATE t SET column = column + delta`: every matching row that has
+    // the column is checked before any is written.
+    #[cfg(feature = "sql")]
+    pub(crate) fn execute_increment(&mut self, table_name: &str, column: &str, delta: f64, condition: Option<Condition>) -> Result<Vec<Record>, String> {
+        let table = self.tables.get(table_name).ok_or("Table not found")?;
+        table.check_incrementable(column)?;
+        let mut ids = Vec::new();
+        table.filter(table.scan(&condition), &condition, |record| {
+            if record.data.contains_key(column) {
+                ids.push(record.id);
+            }
+        });
+        let writes = ids.into_iter()
+            .map(|id| Ok((id, table.incremented(table.index[&id], column, delta, &self.keys)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        interrupt::check()?;
+        self.write_column(table_name, column, writes)
+    }
+}
@@ -0,0 +1,347 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Database, Record};
+
+pub type ReplicaId = u64;
+
+/// The local table conflicting writes are kept in, with the columns
+/// `table`, `record`, `column`, `replica` and, unless the losing write
+/// removed the column, `value`.
+pub const CONFLICTS_TABLE: &str = "_conflicts";
+
+/// A hybrid logical clock reading. Readings order by wall time, then by the
+/// logical counter, then by replica, so any two of them compare the same way
+/// on every replica.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Timestamp {
+    millis: u64,
+    counter: u32,
+    replica: ReplicaId,
+}
+
+impl Timestamp {
+    pub fn replica(&self) -> ReplicaId {
+        self.replica
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Change {
+    CreateTable,
+    /// Sets the given columns; `None` removes a column.
+    Put(HashMap<String, Option<String>>),
+    Delete,
+}
+
+/// One recorded write. Ops are what replicas exchange when they sync.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Op {
+    ts: Timestamp,
+    // the latest write to the row the writer had seen
+    base: Option<Timestamp>,
+    table: String,
+    id: u64,
+    change: Change,
+}
+
+impl Op {
+    pub fn timestamp(&self) -> Timestamp {
+        self.ts
+    }
+}
+
+/// Two writes to the same field of a record, made without either replica
+/// seeing the other's.
+pub struct Conflict<'a> {
+    table: &'a str,
+    id: u64,
+    column: &'a str,
+    earlier: &'a Option<String>,
+    later: &'a Option<String>,
+}
+
+impl Conflict<'_> {
+    pub fn table(&self) -> &str {
+        self.table
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn column(&self) -> &str {
+        self.column
+    }
+
+    /// The value with the older timestamp; `None` if that write removed the column.
+    pub fn earlier(&self) -> Option<&str> {
+        self.earlier.as_deref()
+    }
+
+    pub fn later(&self) -> Option<&str> {
+        self.later.as_deref()
+    }
+}
+
+type Resolver = Box<dyn Fn(&Conflict) -> Option<String> + Send + Sync>;
+
+#[derive(Clone, Default)]
+struct RowState {
+    written: Option<Timestamp>,
+    deleted: Option<Timestamp>,
+    fields: HashMap<String, (Timestamp, Option<String>)>,
+}
+
+impl RowState {
+    // The row exists if its latest write is newer than its latest delete,
+    // and only fields written after that delete survive it.
+    fn visible(&self) -> Option<HashMap<String, String>> {
+        if self.written <= self.deleted {
+            return None;
+        }
+        Some(self.fields.iter()
+            .filter(|(_, (ts, _))| Some(*ts) > self.deleted)
+            .filter_map(|(column, (_, value))| Some((column.clone(), value.clone()?)))
+            .collect())
+    }
+}
+
+/// An offline-first copy of a database. Every local write is recorded as an
+/// [`Op`]; [`sync_with`](Self::sync_with) exchanges the ops each side is
+/// missing and both end up with the same data. Concurrent writes to the same
+/// field resolve last-writer-wins by [`Timestamp`].
+pub struct Replica {
+    id: ReplicaId,
+    clock: Timestamp,
+    ops: Vec<Op>,
+    seen: BTreeMap<ReplicaId, Timestamp>,
+    rows: HashMap<(String, u64), RowState>,
+    tables: HashMap<String, Timestamp>,
+    resolvers: HashMap<String, Resolver>,
+    db: Database,
+}
+
+impl Replica {
+    pub fn new(id: ReplicaId) -> Self {
+        Replica {
+            id,
+            clock: Timestamp { millis: 0, counter: 0, replica: id },
+            ops: Vec::new(),
+            seen: BTreeMap::new(),
+            rows: HashMap::new(),
+            tables: HashMap::new(),
+            resolvers: HashMap::new(),
+            db: Database::new(),
+        }
+    }
+
+    pub fn id(&self) -> ReplicaId {
+        self.id
+    }
+
+    /// The merged data. Write through the replica so changes are recorded.
+    pub fn database(&self) -> &Database {
+        &self.db
+    }
+
+    pub fn create_table(&mut self, name: &str) -> Result<(), String> {
+        if self.tables.contains_key(name) || name == CONFLICTS_TABLE {
+            return Err(format!("Table '{}' already exists", name));
+        }
+        self.record(name, 0, Change::CreateTable)
+    }
+
+    pub fn insert(&mut self, table: &str, id: u64, data: HashMap<String, String>) -> Result<(), String> {
+        if self.db.get(table, id)?.is_some() {
+            return Err(format!("Record with id {} already exists in table '{}'", id, table));
+        }
+        self.record(table, id, Change::Put(data.into_iter().map(|(k, v)| (k, Some(v))).collect()))
+    }
+
+    /// Replaces a record's data; columns left out are removed. Only columns
+    /// that actually change are recorded, so concurrent edits to different
+    /// columns of a record both survive a sync.
+    pub fn update(&mut self, table: &str, id: u64, mut data: HashMap<String, String>) -> Result<(), String> {
+        let record = self.db.get(table, id)?.ok_or(format!("Record with id {} not found in table '{}'", id, table))?;
+        let mut fields: HashMap<String, Option<String>> = HashMap::new();
+        for (column, value) in record.data() {
+            match data.remove(column) {
+                Some(new) if new == *value => {}
+                new => {
+                    fields.insert(column.clone(), new);
+                }
+            }
+        }
+        fields.extend(data.into_iter().map(|(k, v)| (k, Some(v))));
+        self.record(table, id, Change::Put(fields))
+    }
+
+    /// Decides concurrent writes to the same field of a `table` record. The
+    /// resolver returns the value to keep, or `None` to remove the column;
+    /// without one the later write wins. Every replica must register the
+    /// same resolvers, and resolvers must be deterministic, for replicas to
+    /// converge. Values that lose are kept in [`CONFLICTS_TABLE`].
+    pub fn set_resolver(&mut self, table: &str, resolver: impl Fn(&Conflict) -> Option<String> + Send + Sync + 'static) {
+        self.resolvers.insert(table.to_string(), Box::new(resolver));
+    }
+
+    pub fn delete(&mut self, table: &str, id: u64) -> Result<(), String> {
+        if self.db.get(table, id)?.is_none() {
+            return Err(format!("Record with id {} not found in table '{}'", id, table));
+        }
+        self.record(table, id, Change::Delete)
+    }
+
+    /// The latest op seen from each replica, which is what a peer needs to
+    /// work out what to send.
+    pub fn version(&self) -> BTreeMap<ReplicaId, Timestamp> {
+        self.seen.clone()
+    }
+
+    /// Every op not covered by `version`, oldest first.
+    pub fn ops_since(&self, version: &BTreeMap<ReplicaId, Timestamp>) -> Vec<Op> {
+        self.ops.iter()
+            .filter(|op| version.get(&op.ts.replica).map_or(true, |seen| op.ts > *seen))
+            .cloned()
+            .collect()
+    }
+
+    /// Merges ops from another replica. Ops already seen are skipped, so
+    /// applying the same batch twice is harmless.
+    pub fn apply_ops(&mut self, ops: Vec<Op>) -> Result<(), String> {
+        for op in ops {
+            if self.seen.get(&op.ts.replica).is_some_and(|seen| op.ts <= *seen) {
+                continue;
+            }
+            self.tick(Some(op.ts));
+            self.apply(op)?;
+        }
+        Ok(())
+    }
+
+    /// Brings this replica and `peer` to the same state.
+    pub fn sync_with(&mut self, peer: &mut Replica) -> Result<(), String> {
+        let theirs = peer.ops_since(&self.version());
+        let ours = self.ops_since(&peer.version());
+        self.apply_ops(theirs)?;
+        peer.apply_ops(ours)
+    }
+
+    pub fn query_sql(&self, sql: &str) -> Result<Vec<Record>, String> {
+        self.db.query_sql(sql)
+    }
+
+    fn record(&mut self, table: &str, id: u64, change: Change) -> Result<(), String> {
+        if !matches!(change, Change::CreateTable) && !self.tables.contains_key(table) {
+            return Err(format!("Table '{}' not found", table));
+        }
+        self.tick(None);
+        let base = self.rows.get(&(table.to_string(), id)).and_then(|row| row.written.max(row.deleted));
+        self.apply(Op { ts: self.clock, base, table: table.to_string(), id, change })
+    }
+
+    // Advances the clock past the local wall time and, when merging, past the
+    // remote timestamp so later local writes order after what was received.
+    fn tick(&mut self, remote: Option<Timestamp>) {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64);
+        let last = remote.map_or(self.clock, |r| self.clock.max(Timestamp { replica: self.id, ..r }));
+        self.clock = if now > last.millis {
+            Timestamp { millis: now, counter: 0, replica: self.id }
+        } else {
+            Timestamp { millis: last.millis, counter: last.counter + 1, replica: self.id }
+        };
+    }
+
+    fn apply(&mut self, op: Op) -> Result<(), String> {
+        let ts = op.ts;
+        match &op.change {
+            Change::CreateTable => {
+                // two replicas creating the same table is not a conflict
+                if !self.tables.contains_key(&op.table) {
+                    self.db.create_table(op.table.clone())?;
+                    self.materialize_table(&op.table)?;
+                }
+                let created = self.tables.entry(op.table.clone()).or_insert(ts);
+                *created = (*created).min(ts);
+            }
+            Change::Put(fields) => {
+                let row = self.rows.entry((op.table.clone(), op.id)).or_default();
+                row.written = row.written.max(Some(ts));
+                let mut losers = Vec::new();
+                for (column, value) in fields {
+                    let slot = row.fields.entry(column.clone()).or_insert((ts, value.clone()));
+                    // the writer had not seen the value it replaces
+                    if slot.0 != ts && Some(slot.0) > op.base && slot.1 != *value {
+                        let ((earlier_ts, earlier), (later_ts, later)) = if slot.0 < ts {
+                            ((slot.0, &slot.1), (ts, value))
+                        } else {
+                            ((ts, value), (slot.0, &slot.1))
+                        };
+                        let conflict = Conflict { table: &op.table, id: op.id, column, earlier, later };
+                        let kept = match self.resolvers.get(&op.table) {
+                            Some(resolve) => resolve(&conflict),
+                            None => later.clone(),
+                        };
+                        losers.extend([(earlier_ts, earlier), (later_ts, later)].into_iter()
+                            .filter(|(_, v)| **v != kept)
+                            .map(|(t, v)| (column.clone(), t.replica, v.clone())));
+                        *slot = (later_ts, kept);
+                    } else if ts >= slot.0 {
+                        *slot = (ts, value.clone());
+                    }
+                }
+                self.materialize(&op.table, op.id)?;
+                for (column, replica, value) in losers {
+                    self.record_conflict(&op.table, op.id, column, replica, value)?;
+                }
+            }
+            Change::Delete => {
+                let row = self.rows.entry((op.table.clone(), op.id)).or_default();
+                row.deleted = row.deleted.max(Some(ts));
+                self.materialize(&op.table, op.id)?;
+            }
+        }
+        let seen = self.seen.entry(ts.replica).or_insert(ts);
+        *seen = (*seen).max(ts);
+        self.ops.push(op);
+        Ok(())
+    }
+
+    fn record_conflict(&mut self, table: &str, id: u64, column: String, replica: ReplicaId, value: Option<String>) -> Result<(), String> {
+        if !self.db.tables.contains_key(CONFLICTS_TABLE) {
+            self.db.create_table(CONFLICTS_TABLE.to_string())?;
+        }
+        let mut data = HashMap::from([
+            ("table".to_string(), table.to_string()),
+            ("record".to_string(), id.to_string()),
+            ("column".to_string(), column),
+            ("replica".to_string(), replica.to_string()),
+        ]);
+        data.extend(value.map(|v| ("value".to_string(), v)));
+        let conflict_id = self.db.tables[CONFLICTS_TABLE].records.len() as u64 + 1;
+        self.db.insert(CONFLICTS_TABLE, conflict_id, data)
+    }
+
+    // Rows can arrive before the op that creates their table.
+    fn materialize_table(&mut self, table: &str) -> Result<(), String> {
+        let ids: Vec<u64> = self.rows.keys().filter(|(t, _)| t == table).map(|(_, id)| *id).collect();
+        ids.into_iter().try_for_each(|id| self.materialize(table, id))
+    }
+
+    fn materialize(&mut self, table: &str, id: u64) -> Result<(), String> {
+        if !self.db.tables.contains_key(table) {
+            return Ok(());
+        }
+        let visible = self.rows.get(&(table.to_string(), id)).and_then(RowState::visible);
+        let exists = self.db.get(table, id)?.is_some();
+        match (visible, exists) {
+            (Some(data), true) => self.db.update(table, id, data),
+            (Some(data), false) => self.db.insert(table, id, data),
+            (None, true) => self.db.delete(table, id),
+            (None, false) => Ok(()),
+        }
+    }
+}
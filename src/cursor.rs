@@ -0,0 +1,42 @@
+use crate::prelude::*;
+use crate::{Database, Record};
+
+/// A position in a table that holds no borrow of the database, so rows can
+/// be written between steps. It visits the rows the table had when the
+/// cursor was opened, in the order it had them, each as it is when reached:
+/// changes made since are seen, rows deleted since are skipped, and rows
+/// inserted since are not visited. Open one with [`Database::cursor`].
+#[derive(Clone, Debug)]
+pub struct TableCursor {
+    table: String,
+    ids: Vec<u64>,
+    position: usize,
+}
+
+impl TableCursor {
+    /// The next row still in the table, or `None` once every row has been
+    /// visited. Fails if the table is gone.
+    pub fn next(&mut self, db: &Database) -> Result<Option<Record>, String> {
+        while let Some(&id) = self.ids.get(self.position) {
+            self.position += 1;
+            if let Some(record) = db.get(&self.table, id)? {
+                return Ok(Some(record.clone()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// How many rows are left to visit, counting any since deleted.
+    pub fn remaining(&self) -> usize {
+        self.ids.len() - self.position
+    }
+}
+
+impl Database {
+    /// A cursor over the rows of a table as they are now; see
+    /// [`TableCursor`].
+    pub fn cursor(&self, table_name: &str) -> Result<TableCursor, String> {
+        let table = self.tables.get(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        Ok(TableCursor { table: table_name.to_string(), ids: table.records.iter().map(|r| r.id).collect(), position: 0 })
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use bincode::{deserialize, serialize};
+use serde::{Deserialize, Serialize};
+
+use crate::{ChangeKind, Database, Record};
+
+const MAGIC: &[u8; 8] = b"POTADLTA";
+
+#[derive(Serialize, Deserialize)]
+struct Delta {
+    seq: u64,
+    changes: Vec<(String, ChangeKind, Record)>,
+}
+
+impl Database {
+    /// Bundles the changes made after `since_seq` for [`apply_delta`](Self::apply_delta).
+    /// Several changes to one record collapse into its final state, so the
+    /// bundle grows with the records touched rather than the writes made.
+    /// Fails if the change log no longer holds every change after `since_seq`.
+    pub fn export_delta(&self, since_seq: u64) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        if since_seq > self.last_change_seq() || !self.changes_complete_after(since_seq) {
+            return Err(format!("Changes after {} are no longer available; send a full copy instead", since_seq).into());
+        }
+        // per record: (first kind, latest kind, latest row, latest seq)
+        let mut net: HashMap<(String, u64), (ChangeKind, ChangeKind, Record, u64)> = HashMap::new();
+        for event in self.changes_since(since_seq) {
+            let key = (event.table().to_string(), event.record().id);
+            let entry = net.entry(key).or_insert((event.kind(), event.kind(), event.record().clone(), event.seq()));
+            (entry.1, entry.2, entry.3) = (event.kind(), event.record().clone(), event.seq());
+        }
+        let mut changes: Vec<_> = net.into_iter()
+            .filter_map(|((table, _), (first, last, record, seq))| {
+                let kind = match (first, last) {
+                    // created and removed again since the last sync
+                    (ChangeKind::Insert, ChangeKind::Delete) => return None,
+                    (ChangeKind::Insert, _) => ChangeKind::Insert,
+                    (_, ChangeKind::Delete) => ChangeKind::Delete,
+                    _ => ChangeKind::Update,
+                };
+                Some((seq, (table, kind, record)))
+            })
+            .collect();
+        changes.sort_unstable_by_key(|(seq, _)| *seq);
+        let delta = Delta { seq: self.last_change_seq(), changes: changes.into_iter().map(|(_, c)| c).collect() };
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(serialize(&delta)?);
+        Ok(bytes)
+    }
+
+    /// Applies a bundle from [`export_delta`](Self::export_delta) and returns
+    /// the source sequence number it brings this copy up to, which is the
+    /// `since_seq` to ask for next time. Applying a bundle twice is harmless.
+    pub fn apply_delta(&mut self, bytes: &[u8]) -> Result<u64, Box<dyn std::error::Error>> {
+        let body = bytes.strip_prefix(MAGIC).ok_or("Not a potatodb delta")?;
+        let delta: Delta = deserialize(body)?;
+        // try the changes on a copy first so a failing one leaves this
+        // database and its subscribers untouched; the copy keeps the column
+        // keys so encrypted columns are written as they will be here
+        let mut check = Database { tables: self.tables.clone(), keys: self.keys.clone(), ..Database::new() };
+        for (table, kind, record) in &delta.changes {
+            check.apply_change(table, *kind, record)?;
+        }
+        for (table, kind, record) in &delta.changes {
+            self.apply_change(table, *kind, record)?;
+        }
+        Ok(delta.seq)
+    }
+}
@@ -0,0 +1,61 @@
+use alloc::collections::BTreeMap;
+
+use crate::Record;
+use crate::prelude::*;
+
+// Marks a row without the column at rest.
+const ABSENT: u16 = u16::MAX;
+
+// The distinct values of a column in first-seen order, and how many records
+// have the column.
+#[derive(Default)]
+struct Distinct<'a> {
+    values: Vec<&'a str>,
+    positions: HashMap<&'a str, u16>,
+    used: usize,
+}
+
+/// A column saved once per distinct value: the values, then each record's
+/// index into them.
+pub(crate) type DictionaryColumn = (String, Vec<String>, Vec<u16>);
+
+// Splits the columns whose values repeat, each used at least twice on
+// average, out of records as indexes into their distinct values, for
+// saving. Status flags, country codes and enum columns end up here;
+// columns of mostly unique values stay as text.
+pub(crate) fn encode(records: &[Record]) -> (Vec<Record>, Vec<DictionaryColumn>) {
+    let mut dictionaries: BTreeMap<&str, Distinct> = BTreeMap::new();
+    for (column, value) in records.iter().flat_map(|r| &r.data) {
+        let distinct = dictionaries.entry(column).or_default();
+        distinct.used += 1;
+        if distinct.values.len() < ABSENT as usize && !distinct.positions.contains_key(value.as_str()) {
+            distinct.positions.insert(value, distinct.values.len() as u16);
+            distinct.values.push(value);
+        }
+    }
+    dictionaries.retain(|_, d| d.values.len() < ABSENT as usize && d.used >= d.values.len() * 2);
+
+    let mut records = records.to_vec();
+    let columns = dictionaries.into_iter()
+        .map(|(column, distinct)| {
+            let indexes = records.iter_mut()
+                .map(|record| match record.data.remove(column) {
+                    Some(value) => distinct.positions[value.as_str()],
+                    None => ABSENT,
+                })
+                .collect();
+            (column.to_string(), distinct.values.into_iter().map(String::from).collect(), indexes)
+        })
+        .collect();
+    (records, columns)
+}
+
+pub(crate) fn decode(records: &mut [Record], columns: Vec<DictionaryColumn>) -> Result<(), String> {
+    for (column, values, indexes) in columns {
+        for (record, index) in records.iter_mut().zip(indexes).filter(|(_, i)| *i != ABSENT) {
+            let value = values.get(index as usize).ok_or(format!("Invalid stored value for column '{}'", column))?;
+            record.data.insert(column.clone(), value.clone());
+        }
+    }
+    Ok(())
+}
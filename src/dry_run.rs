@@ -0,0 +1,63 @@
+use crate::prelude::*;
+use crate::{interrupt, Database, Record, SqlStatement};
+
+/// What an UPDATE or DELETE would do, worked out by
+/// [`Database::dry_run`] without doing it.
+#[derive(Clone, Debug)]
+pub struct DryRun {
+    plan: String,
+    rows: Vec<Record>,
+}
+
+impl DryRun {
+    /// How the statement would find its rows, as in the `plan` column of
+    /// [`SLOW_QUERIES_TABLE`](crate::SLOW_QUERIES_TABLE).
+    pub fn plan(&self) -> &str {
+        &self.plan
+    }
+
+    /// The rows the statement would change or delete, as they are now.
+    pub fn rows(&self) -> &[Record] {
+        &self.rows
+    }
+}
+
+impl Database {
+    /// Finds the rows an `UPDATE` or `DELETE` would change or delete, under
+    /// the same permissions, row policies and query observers' rewrites,
+    /// without writing anything.
+    /// Fails where running the statement would fail before writing, such as
+    /// on a value its column doesn't allow. Rows come back as a SELECT
+    /// returns them, with encrypted columns revealed and masks applied.
+    pub fn dry_run(&self, sql: &str) -> Result<DryRun, String> {
+        let _statement = interrupt::begin(&self.limits, self.settings, None);
+        let (_, statement) = self.prepare(sql);
+        let statement = statement?;
+        let (table_name, condition, column) = match &statement {
+            SqlStatement::Update { table, column, condition, .. } | SqlStatement::Increment { table, column, condition, .. } => (table, condition, Some(column)),
+            SqlStatement::Delete { table, condition } => (table, condition, None),
+            _ => return Err("Only UPDATE and DELETE statements can be dry run".to_string()),
+        };
+        self.check_writable(table_name)?;
+        let table = self.tables.get(table_name).ok_or("Table not found")?;
+        match &statement {
+            SqlStatement::Update { column, value, .. } => self.updated_value(table_name, column, value).map(drop)?,
+            SqlStatement::Increment { column, .. } => table.check_incrementable(column)?,
+            _ => {}
+        }
+        let mut ids = Vec::new();
+        table.filter(table.scan(condition), condition, |record| ids.push(record.id));
+        interrupt::check()?;
+        // writes skip rows without the column they set
+        let mut rows: Vec<Record> = ids.into_iter()
+            .map(|id| table.records[table.index[&id]].clone())
+            .filter(|record| column.map_or(true, |column| record.data.contains_key(column)))
+            .collect();
+        if let SqlStatement::Increment { column, delta, .. } = &statement {
+            rows.iter().try_for_each(|record| table.incremented(table.index[&record.id], column, *delta, &self.keys).map(drop))?;
+        }
+        self.keys.reveal(table, &mut rows);
+        self.apply_masks(table_name, &mut rows);
+        Ok(DryRun { plan: self.describe_statement(&statement), rows })
+    }
+}
@@ -0,0 +1,151 @@
+
+#[cfg(feature = "encryption")]
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+#[cfg(feature = "encryption")]
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+
+use crate::{Record, Row, Table};
+#[cfg(feature = "encryption")]
+use crate::{partition::Partitions, ColumnType, Database};
+use crate::prelude::*;
+
+/// A 256-bit ChaCha20-Poly1305 key for an encrypted column.
+pub type ColumnKey = [u8; 32];
+
+#[cfg(feature = "encryption")]
+const NONCE_LEN: usize = 12;
+
+// Keys of encrypted columns by (table, column). Never saved.
+#[derive(Clone, Default)]
+pub(crate) struct Keys(HashMap<(String, String), ColumnKey>);
+
+impl Keys {
+    fn get(&self, table: &str, column: &str) -> Option<&ColumnKey> {
+        self.0.get(&(table.to_string(), column.to_string()))
+    }
+
+    // The value to store for `column`: ciphertext if the column is encrypted.
+    pub(crate) fn seal(&self, table: &Table, column: &str, value: &str) -> Result<String, String> {
+        if !table.encrypted.contains(column) {
+            return Ok(value.to_string());
+        }
+        let key = self.get(&table.name, column)
+            .ok_or(format!("Column '{}' of '{}' is encrypted and its key is not set", column, table.name))?;
+        encrypt(key, &table.name, column, value)
+    }
+
+    // Seals the encrypted columns of a row being written. Values that are
+    // already sealed, as `Database::get` returns them, are kept, so a row
+    // read, changed and written back isn't encrypted twice.
+    pub(crate) fn seal_row(&self, table: &Table, data: &mut Row) -> Result<(), String> {
+        for (column, value) in data.iter_mut().filter(|(c, _)| table.encrypted.contains(*c)) {
+            if self.get(&table.name, column).is_some_and(|key| decrypt(key, &table.name, column, value).is_ok()) {
+                continue;
+            }
+            *value = self.seal(table, column, value)?;
+        }
+        Ok(())
+    }
+
+    // Decrypts the encrypted columns of `records` whose keys are set.
+    pub(crate) fn reveal(&self, table: &Table, records: &mut [Record]) {
+        for column in &table.encrypted {
+            let Some(key) = self.get(&table.name, column) else { continue };
+            for value in records.iter_mut().filter_map(|r| r.data.get_mut(column)) {
+                if let Ok(plain) = decrypt(key, &table.name, column, value) {
+                    *value = plain;
+                }
+            }
+        }
+    }
+}
+
+// Binds the ciphertext to its column so values cannot be moved between columns.
+#[cfg(feature = "encryption")]
+fn associated_data(table: &str, column: &str) -> String {
+    format!("{}.{}", table, column)
+}
+
+// Stored as hex of a random nonce followed by the ciphertext.
+#[cfg(feature = "encryption")]
+fn encrypt(key: &ColumnKey, table: &str, column: &str, value: &str) -> Result<String, String> {
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = ChaCha20Poly1305::new(key.into())
+        .encrypt(&nonce, Payload { msg: value.as_bytes(), aad: associated_data(table, column).as_bytes() })
+        .map_err(|_| format!("Could not encrypt column '{}' of '{}'", column, table))?;
+    Ok(nonce.iter().chain(&ciphertext).map(|b| format!("{:02x}", b)).collect())
+}
+
+#[cfg(feature = "encryption")]
+fn decrypt(key: &ColumnKey, table: &str, column: &str, value: &str) -> Result<String, String> {
+    let invalid = || format!("Could not decrypt column '{}' of '{}'", column, table);
+    let bytes = (0..value.len()).step_by(2)
+        .map(|i| value.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok()))
+        .collect::<Option<Vec<u8>>>()
+        .filter(|b| b.len() >= NONCE_LEN)
+        .ok_or_else(invalid)?;
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    let plain = ChaCha20Poly1305::new(key.into())
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: associated_data(table, column).as_bytes() })
+        .map_err(|_| invalid())?;
+    String::from_utf8(plain).map_err(|_| invalid())
+}
+
+#[cfg(not(feature = "encryption"))]
+fn encrypt(_: &ColumnKey, table: &str, column: &str, _: &str) -> Result<String, String> {
+    Err(format!("Column '{}' of '{}' is encrypted; writing it needs the `encryption` feature", column, table))
+}
+
+#[cfg(not(feature = "encryption"))]
+fn decrypt(_: &ColumnKey, _: &str, _: &str, _: &str) -> Result<String, String> {
+    Err("Decrypting needs the `encryption` feature".to_string())
+}
+
+#[cfg(feature = "encryption")]
+impl Database {
+    /// Encrypts a column's values with `key` and keeps them encrypted in
+    /// memory and on disk. SQL writes encrypt with the key set for the column
+    /// and SELECTs decrypt with it; without one, writes to the column fail and
+    /// SELECTs return ciphertext. WHERE clauses cannot match encrypted values,
+    /// and earlier values kept by history or the audit log stay as written.
+    pub fn encrypt_column(&mut self, table_name: &str, column: &str, key: ColumnKey) -> Result<(), String> {
+        let table = self.tables.get_mut(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        if table.proto.is_some() {
+            return Err(format!("Columns of protobuf table '{}' cannot be encrypted", table_name));
+        }
+        if table.types.get(column).is_some_and(|&ty| ty != ColumnType::Text) {
+            return Err(format!("Column '{}' of '{}' has a type and can't be encrypted", column, table_name));
+        }
+        if !table.encrypted.insert(column.to_string()) {
+            return Err(format!("Column '{}' of '{}' is already encrypted", column, table_name));
+        }
+        for value in table.records.iter_mut().filter_map(|r| r.data.get_mut(column)) {
+            *value = encrypt(&key, table_name, column, value)?;
+        }
+        table.invalidate_columns();
+        if let Some(partitions) = &table.partitions {
+            table.partitions = Some(Partitions::new(partitions.scheme().clone(), &table.records));
+        }
+        self.keys.0.insert((table_name.to_string(), column.to_string()), key);
+        Ok(())
+    }
+
+    /// Sets the key of an encrypted column, for instance after loading the
+    /// database. Fails if the key does not decrypt the column's values.
+    pub fn set_column_key(&mut self, table_name: &str, column: &str, key: ColumnKey) -> Result<(), String> {
+        let table = self.tables.get(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        if !table.encrypted.contains(column) {
+            return Err(format!("Column '{}' of '{}' is not encrypted", column, table_name));
+        }
+        if let Some(value) = table.records.iter().find_map(|r| r.data.get(column)) {
+            decrypt(&key, table_name, column, value).map_err(|_| format!("Wrong key for column '{}' of '{}'", column, table_name))?;
+        }
+        self.keys.0.insert((table_name.to_string(), column.to_string()), key);
+        Ok(())
+    }
+
+    /// Forgets every column key, leaving encrypted columns unreadable.
+    pub fn clear_column_keys(&mut self) {
+        self.keys.0.clear();
+    }
+}
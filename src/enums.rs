@@ -0,0 +1,86 @@
+#[cfg(feature = "sql")]
+use alloc::collections::BTreeMap;
+
+use crate::{Database, Row, Table};
+use crate::prelude::*;
+
+// Enum columns take fewer values than this.
+const ABSENT: u16 = u16::MAX;
+
+// `name ENUM('a', 'b', ...)`, or `None` for any other column definition.
+#[cfg(feature = "sql")]
+fn parse_definition(definition: &str) -> Result<Option<(String, Vec<String>)>, String> {
+    let Some((name, rest)) = definition.split_once(char::is_whitespace) else {
+        return Ok(None);
+    };
+    let rest = rest.trim_start();
+    if !rest.get(..4).is_some_and(|keyword| keyword.eq_ignore_ascii_case("ENUM"))
+        || rest[4..].starts_with(|c: char| c.is_alphanumeric() || c == '_')
+    {
+        return Ok(None);
+    }
+    let values = rest[4..].trim().strip_prefix('(').and_then(|r| r.strip_suffix(')'))
+        .ok_or(format!("Invalid enum column definition '{}'", definition))?;
+    let values: Vec<String> = match values.trim() {
+        "" => Vec::new(),
+        values => values.split(',').map(|v| v.trim().trim_matches('\'').to_string()).collect(),
+    };
+    check_values(name, &values)?;
+    Ok(Some((name.to_string(), values)))
+}
+
+// The enum column definitions in the body of a CREATE TABLE.
+#[cfg(feature = "sql")]
+pub(crate) fn parse_definitions(body: &str) -> Result<BTreeMap<String, Vec<String>>, String> {
+    let mut enums = BTreeMap::new();
+    for definition in crate::generated::split_definitions(body) {
+        if let Some((column, values)) = parse_definition(definition)? {
+            enums.insert(column, values);
+        }
+    }
+    Ok(enums)
+}
+
+fn check_values(column: &str, values: &[String]) -> Result<(), String> {
+    if values.is_empty() || values.len() >= ABSENT as usize {
+        return Err(format!("Enum column '{}' needs between 1 and {} values", column, ABSENT - 1));
+    }
+    if let Some(duplicate) = values.iter().enumerate().find(|(i, v)| values[..*i].contains(v)) {
+        return Err(format!("Enum column '{}' lists '{}' twice", column, duplicate.1));
+    }
+    Ok(())
+}
+
+impl Table {
+    pub(crate) fn check_enum_value(&self, column: &str, value: &str) -> Result<(), String> {
+        match self.enums.get(column) {
+            Some(values) if !values.iter().any(|v| v == value) => {
+                Err(format!("'{}' is not one of {} allowed in column '{}'", value, values.join(", "), column))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn check_enums(&self, data: &Row) -> Result<(), String> {
+        data.iter().try_for_each(|(column, value)| self.check_enum_value(column, value))
+    }
+}
+
+impl Database {
+    /// Limits a column to `values`: writes with any other value fail. Enum
+    /// values are saved as small integers. Fails if a record already holds
+    /// another value.
+    pub fn set_column_enum(&mut self, table_name: &str, column: &str, values: &[&str]) -> Result<(), String> {
+        let table = self.tables.get_mut(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        check_values(column, &values)?;
+        // encrypted values can't be checked, so they are left alone
+        if let Some(record) = table.records.iter().filter(|_| !table.encrypted.contains(column))
+            .find(|r| r.data.get(column).is_some_and(|v| !values.contains(v)))
+        {
+            return Err(format!("Record {} of '{}' has '{}' in column '{}', which is not allowed", record.id, table_name, record.data[column], column));
+        }
+        table.enums.insert(column.to_string(), values);
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use alloc::collections::BTreeSet;
+use core::time::Duration;
+
+use crate::clock::unix_millis;
+use crate::history::parse_timestamp;
+use crate::prelude::*;
+use crate::{Database, Record, Row};
+
+// The rows of a table ordered by when they expire, read from a column
+// holding a timestamp, so sweeps visit only the rows that are due. Rows
+// without the column, or with a value that isn't a timestamp, never expire.
+// Like partition segments, the order is rebuilt from the records when a
+// table loads.
+#[derive(Clone)]
+pub(crate) struct Expiry {
+    column: String,
+    // (expiry in milliseconds since the Unix epoch, id), soonest first
+    due: BTreeSet<(u64, u64)>,
+    expires: HashMap<u64, u64>,
+}
+
+impl Expiry {
+    pub(crate) fn new(column: &str, records: &[Record]) -> Self {
+        let mut expiry = Expiry { column: column.to_string(), due: BTreeSet::new(), expires: HashMap::new() };
+        for record in records {
+            expiry.place(record.id, &record.data);
+        }
+        expiry
+    }
+
+    pub(crate) fn column(&self) -> &str {
+        &self.column
+    }
+
+    pub(crate) fn place(&mut self, id: u64, data: &Row) {
+        self.remove(id);
+        if let Some(at) = data.get(&self.column).and_then(|v| parse_timestamp(v).ok()) {
+            self.due.insert((at, id));
+            self.expires.insert(id, at);
+        }
+    }
+
+    pub(crate) fn remove(&mut self, id: u64) {
+        if let Some(at) = self.expires.remove(&id) {
+            self.due.remove(&(at, id));
+        }
+    }
+
+    fn due_by(&self, now: u64) -> impl Iterator<Item = u64> + '_ {
+        self.due.iter().take_while(move |(at, _)| *at <= now).map(|(_, id)| *id)
+    }
+
+    fn next(&self) -> Option<u64> {
+        self.due.first().map(|(at, _)| *at)
+    }
+}
+
+impl Database {
+    /// Makes the rows of a table expire at the timestamp in `column`:
+    /// milliseconds since the Unix epoch, `2024-01-31` or
+    /// `2024-01-31T12:30:00Z`, in UTC. Rows without a timestamp there never
+    /// expire. Expired rows stay readable until
+    /// [`purge_expired`](Self::purge_expired) deletes them.
+    pub fn set_expiry_column(&mut self, table_name: &str, column: &str) -> Result<(), String> {
+        let table = self.tables.get_mut(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        table.expiry = Some(Expiry::new(column, &table.records));
+        Ok(())
+    }
+
+    /// Stops the rows of a table expiring.
+    pub fn clear_expiry_column(&mut self, table_name: &str) -> Result<(), String> {
+        let table = self.tables.get_mut(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        table.expiry = None;
+        Ok(())
+    }
+
+    /// Inserts a row that expires `ttl` from now, by setting the table's
+    /// expiry column.
+    pub fn insert_with_ttl(&mut self, table_name: &str, id: u64, data: impl Into<Row>, ttl: Duration) -> Result<(), String> {
+        let table = self.tables.get(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        let column = table.expiry.as_ref().ok_or(format!("Table '{}' has no expiry column", table_name))?.column().to_string();
+        let mut data = data.into();
+        data.insert(column, (unix_millis() + ttl.as_millis() as u64).to_string());
+        self.insert(table_name, id, data)
+    }
+
+    /// Deletes the rows of every table whose expiry has passed, visiting
+    /// only those rows, and returns how many were deleted. Each is deleted
+    /// as by [`delete`](Self::delete), so it is audited and logged.
+    pub fn purge_expired(&mut self) -> Result<usize, String> {
+        let now = unix_millis();
+        let due: Vec<(String, u64)> = self.tables.iter()
+            .filter_map(|(name, table)| Some((name, table.expiry.as_ref()?)))
+            .flat_map(|(name, expiry)| expiry.due_by(now).map(move |id| (name.clone(), id)))
+            .collect();
+        for (table, id) in &due {
+            self.delete(table, *id)?;
+        }
+        Ok(due.len())
+    }
+
+    /// When the next row of any table expires, in milliseconds since the
+    /// Unix epoch, to schedule the next [`purge_expired`](Self::purge_expired)
+    /// for. It may already have passed.
+    pub fn next_expiry(&self) -> Option<u64> {
+        self.tables.values().filter_map(|t| t.expiry.as_ref()?.next()).min()
+    }
+}
@@ -0,0 +1,176 @@
+use alloc::borrow::Cow;
+use core::fmt;
+
+use crate::arena::{self, ArenaVec};
+use crate::interrupt;
+use crate::clock::Timer;
+use crate::{calls, is_count, project, pseudo, Condition, Database, Record, Row, SqlStatement};
+use crate::prelude::*;
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Equals(c, v) => write!(f, "{} = {}", c, v),
+            Condition::NotEquals(c, v) => write!(f, "{} != {}", c, v),
+            Condition::GreaterThan(c, v) => write!(f, "{} > {}", c, v),
+            Condition::LessThan(c, v) => write!(f, "{} < {}", c, v),
+            Condition::Between(c, low, high) => write!(f, "{} BETWEEN {} AND {}", c, low, high),
+            Condition::Like(c, pattern) => write!(f, "{} LIKE {}", c, pattern),
+            Condition::IsNull(c) => write!(f, "{} IS NULL", c),
+            Condition::IsNotNull(c) => write!(f, "{} IS NOT NULL", c),
+            Condition::And(l, r) => write!(f, "({} AND {})", l, r),
+            Condition::Or(l, r) => write!(f, "({} OR {})", l, r),
+        }
+    }
+}
+
+struct Node {
+    name: &'static str,
+    detail: String,
+    estimated_rows: usize,
+    // actual rows and elapsed milliseconds, for EXPLAIN ANALYZE
+    actual: Option<(usize, f64)>,
+}
+
+impl Node {
+    fn into_record(self, id: u64) -> Record {
+        let mut data = Row::from([
+            ("node".to_string(), self.name.to_string()),
+            ("detail".to_string(), self.detail),
+            ("estimated_rows".to_string(), self.estimated_rows.to_string()),
+        ]);
+        if let Some((rows, millis)) = self.actual {
+            data.insert("actual_rows".to_string(), rows.to_string());
+            data.insert("loops".to_string(), "1".to_string());
+            data.insert("time_ms".to_string(), format!("{:.3}", millis));
+        }
+        Record { id, data }
+    }
+}
+
+fn millis(timer: &Timer) -> f64 {
+    timer.elapsed().as_secs_f64() * 1000.0
+}
+
+impl Database {
+    // One row per plan node, in execution order: the scan, the sample if
+    // there is one, the WHERE filter if there is one, the GROUP BY, the
+    // ORDER BY sort and the LIMIT if there are, then the projection, or the
+    // count of a COUNT(*). An
+    // unfiltered COUNT(*) is one count node.
+    // The filter's estimate comes from the histograms of the columns it
+    // compares, when the table has been analyzed; otherwise every scanned
+    // row is assumed to pass.
+    pub(crate) fn execute_explain(&self, statement: SqlStatement, analyze: bool) -> Result<Vec<Record>, String> {
+        let SqlStatement::Select { table, columns, condition, as_of, sample, hints, order_by, page, group_by } = statement else {
+            return Err("Only SELECT statements can be explained".to_string());
+        };
+        let table = self.select_source(&table, as_of, &condition)?;
+        let count = is_count(&columns) && group_by.is_none();
+        if count && condition.is_none() && sample.is_none() {
+            let mut node = Node { name: "count", detail: format!("row count of {}", table.name), estimated_rows: 1, actual: None };
+            if analyze {
+                node.actual = Some((1, 0.0));
+            }
+            return Ok(vec![node.into_record(1)]);
+        }
+        // grouped columns are keys and aggregates, not calls
+        let calls = match group_by {
+            Some(_) => Vec::new(),
+            None => calls(&columns)?,
+        };
+        if let Some(proto) = table.proto.as_ref().filter(|_| !count) {
+            columns.iter().filter(|c| *c != "*" && !c.contains('(') && !pseudo::is_pseudo(c)).try_for_each(|c| proto.check_column(c))?;
+        }
+        // samples are drawn from the whole table
+        let scanned_condition = if sample.is_some() { None } else { hints.scanned(&condition).clone() };
+        let mut estimate = table.scan_estimate(&scanned_condition);
+        let detail = match (&condition, &sample, &table.columnar) {
+            (None, None, Some(_)) if calls.is_empty() && group_by.is_none() && !columns.iter().any(|c| c == "*") => format!("column scan of {} ({} rows)", table.name, table.records.len()),
+            _ => table.describe_scan(&scanned_condition),
+        };
+        let mut nodes = vec![Node { name: "scan", detail, estimated_rows: estimate, actual: None }];
+        if let Some(sample) = &sample {
+            estimate = sample.size(estimate);
+            nodes.push(Node { name: "sample", detail: sample.to_string(), estimated_rows: estimate, actual: None });
+        }
+        let filter_node = nodes.len();
+        if let Some(condition) = &condition {
+            estimate = (estimate as f64 * self.histograms.selectivity(&table.name, condition)).round() as usize;
+            nodes.push(Node { name: "filter", detail: condition.to_string(), estimated_rows: estimate, actual: None });
+        }
+        // every row may be a group of its own
+        let group_node = nodes.len();
+        if let Some(group_by) = &group_by {
+            nodes.push(Node { name: "group", detail: group_by.to_string(), estimated_rows: estimate, actual: None });
+        }
+        let sort_node = nodes.len();
+        let sorted = !count && !order_by.is_empty();
+        if sorted {
+            let detail = order_by.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ");
+            nodes.push(Node { name: "sort", detail, estimated_rows: estimate, actual: None });
+        }
+        let limit_node = nodes.len();
+        let paged = !count && !page.is_all();
+        if paged {
+            estimate = page.size(estimate);
+            nodes.push(Node { name: "limit", detail: page.to_string(), estimated_rows: estimate, actual: None });
+        }
+        nodes.push(match count {
+            true => Node { name: "count", detail: columns.join(", "), estimated_rows: 1, actual: None },
+            false => Node { name: "project", detail: columns.join(", "), estimated_rows: estimate, actual: None },
+        });
+
+        if analyze {
+            let timer = Timer::start();
+            let mut scanned = table.scan(&scanned_condition);
+            nodes[0].actual = Some((scanned.len(), millis(&timer)));
+
+            if let Some(sample) = &sample {
+                let timer = Timer::start();
+                scanned = sample.draw(scanned);
+                nodes[1].actual = Some((scanned.len(), millis(&timer)));
+            }
+
+            arena::scoped(|arena| {
+                let timer = Timer::start();
+                let mut filtered = ArenaVec::new_in(arena);
+                table.filter(scanned, &condition, |record| filtered.push(record));
+                interrupt::check()?;
+                if condition.is_some() {
+                    nodes[filter_node].actual = Some((filtered.len(), millis(&timer)));
+                }
+
+                if let Some(group_by) = &group_by {
+                    let timer = Timer::start();
+                    let groups = self.group_rows(group_by, &columns, &filtered)?;
+                    filtered.clear();
+                    filtered.extend(groups.into_iter().map(Cow::Owned));
+                    nodes[group_node].actual = Some((filtered.len(), millis(&timer)));
+                }
+
+                if sorted {
+                    let timer = Timer::start();
+                    self.sort_rows(&table, &order_by, &mut filtered)?;
+                    nodes[sort_node].actual = Some((filtered.len(), millis(&timer)));
+                }
+
+                let mut kept = filtered.len();
+                if paged {
+                    let timer = Timer::start();
+                    kept = page.size(kept);
+                    nodes[limit_node].actual = Some((kept, millis(&timer)));
+                }
+
+                let timer = Timer::start();
+                let rows = match count {
+                    true => 1,
+                    false => filtered.iter().skip(page.offset).take(kept).map(|record| project(&columns, &calls, record)).collect::<Vec<Record>>().len(),
+                };
+                nodes.last_mut().unwrap().actual = Some((rows, millis(&timer)));
+                Ok::<_, String>(())
+            })?;
+        }
+        Ok(nodes.into_iter().zip(1..).map(|(node, id)| node.into_record(id)).collect())
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value as Json;
+
+use crate::{Database, Row};
+
+// A fixture maps table names to lists of rows:
+//
+//     [[users]]
+//     id = 1
+//     name = "Alice"
+//
+// or `{"users": [{"id": 1, "name": "Alice"}]}`. Rows without an `id` are
+// numbered from 1 by position. Strings keep their text, other scalars their
+// plain text form, arrays and objects their JSON text; nulls are left out.
+fn column_value(value: Json) -> Option<String> {
+    match value {
+        Json::Null => None,
+        Json::String(s) => Some(s),
+        other => Some(other.to_string()),
+    }
+}
+
+impl Database {
+    /// Builds a database from a `.toml` or `.json` fixture file, for tests
+    /// that need the same data every run.
+    pub fn from_fixture(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        let fixture: HashMap<String, Vec<HashMap<String, Json>>> = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(&text)?,
+            Some("json") => serde_json::from_str(&text)?,
+            _ => return Err(format!("Fixture '{}' is neither .toml nor .json", path.display()).into()),
+        };
+        let mut db = Database::new();
+        for (table, rows) in fixture {
+            db.create_table(table.clone())?;
+            for (position, mut row) in (1..).zip(rows) {
+                let id = match row.remove("id") {
+                    Some(id) => id.as_u64().ok_or(format!("Row {} of '{}' has an id that is not a positive integer", position, table))?,
+                    None => position,
+                };
+                let data: Row = row.into_iter().filter_map(|(column, value)| Some((column, column_value(value)?))).collect();
+                db.insert(&table, id, data)?;
+            }
+        }
+        db.fixture = Some(db.tables.clone());
+        Ok(db)
+    }
+
+    /// Puts every table back as [`from_fixture`](Self::from_fixture) built
+    /// it, dropping tables created since.
+    pub fn reset_to_fixture(&mut self) -> Result<(), String> {
+        let fixture = self.fixture.as_ref().ok_or("Database was not built from a fixture")?;
+        self.tables = fixture.clone();
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use std::fs::File;
+use std::path::PathBuf;
+
+#[cfg(feature = "json")]
+use serde_json::Value as Json;
+
+use crate::copy::{read_rows, CopyFormat};
+use crate::{Row, VirtualTable};
+
+/// A CSV file read as a [`VirtualTable`]: the first line names the
+/// columns, and fields are quoted as for [`CopyFormat::Csv`].
+pub struct CsvSource {
+    path: PathBuf,
+}
+
+impl CsvSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        CsvSource { path: path.into() }
+    }
+}
+
+impl VirtualTable for CsvSource {
+    fn scan(&self) -> Result<Vec<Row>, String> {
+        let file = File::open(&self.path).map_err(|e| format!("Cannot read '{}': {}", self.path.display(), e))?;
+        let (_, rows) = read_rows(file, CopyFormat::Csv).map_err(|e| format!("Cannot read '{}': {}", self.path.display(), e))?;
+        Ok(rows)
+    }
+}
+
+/// A JSON file read as a [`VirtualTable`], holding an array of objects or
+/// one object per line. Strings keep their text, other scalars their plain
+/// text form, arrays and objects their JSON text; nulls are left out.
+#[cfg(feature = "json")]
+pub struct JsonSource {
+    path: PathBuf,
+}
+
+#[cfg(feature = "json")]
+impl JsonSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        JsonSource { path: path.into() }
+    }
+}
+
+#[cfg(feature = "json")]
+impl VirtualTable for JsonSource {
+    fn scan(&self) -> Result<Vec<Row>, String> {
+        let error = |e: &dyn std::fmt::Display| format!("Cannot read '{}': {}", self.path.display(), e);
+        let text = std::fs::read_to_string(&self.path).map_err(|e| error(&e))?;
+        let objects: Vec<serde_json::Map<String, Json>> = match text.trim_start().starts_with('[') {
+            true => serde_json::from_str(&text).map_err(|e| error(&e))?,
+            false => text.lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<Result<_, _>>()
+                .map_err(|e| error(&e))?,
+        };
+        Ok(objects.into_iter()
+            .map(|object| object.into_iter().filter_map(|(column, value)| Some((column, column_value(value)?))).collect())
+            .collect())
+    }
+}
+
+// A JSON value as a column value, as `JsonSource` reads it.
+#[cfg(feature = "json")]
+pub(crate) fn column_value(value: Json) -> Option<String> {
+    match value {
+        Json::Null => None,
+        Json::String(s) => Some(s),
+        other => Some(other.to_string()),
+    }
+}
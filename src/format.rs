@@ -0,0 +1,115 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use bincode::deserialize;
+
+use crate::codec::{self, BincodeCodec, Codec};
+use crate::row::SharedNames;
+use crate::{Database, Table};
+
+// Files start with MAGIC, a little-endian u32 version and a little-endian
+// CRC-32 of the version and the body. The body begins with the name of its
+// codec, a length byte and then the name, and the database encoded with
+// it follows. Files written before the header existed (version 0) are plain
+// bincode of the original layout and are converted on load.
+const MAGIC: &[u8; 8] = b"POTATODB";
+const FORMAT_VERSION: u32 = 1;
+
+pub(crate) fn encode(db: &Database) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let codec = db.codec.as_deref().unwrap_or(&BincodeCodec);
+    let mut body = vec![codec.name().len() as u8];
+    body.extend_from_slice(codec.name().as_bytes());
+    body.extend(codec.encode(db)?);
+    let version = FORMAT_VERSION.to_le_bytes();
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&version);
+    bytes.extend_from_slice(&crc32(crc32(0, &version), &body).to_le_bytes());
+    bytes.extend_from_slice(&body);
+    Ok(bytes)
+}
+
+// CRC-32 (IEEE), bit by bit; files are checksummed once per save or load.
+// Pass 0 to start, or the result of the bytes before to continue.
+fn crc32(crc: u32, bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!crc, |crc, &b| {
+        (0..8).fold(crc ^ b as u32, |crc, _| if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 })
+    })
+}
+
+// Whether the body of a file matches its checksum. Files from before the
+// header have no checksum and always pass.
+pub(crate) fn checksum_matches(bytes: &[u8]) -> Result<bool, String> {
+    let Some(rest) = bytes.strip_prefix(MAGIC.as_slice()) else {
+        return Ok(true);
+    };
+    let header = rest.get(..8).ok_or("Truncated database header")?;
+    let crc = crc32(crc32(0, &header[..4]), &rest[8..]);
+    Ok(u32::from_le_bytes(header[4..].try_into().unwrap()) == crc)
+}
+
+pub(crate) fn decode(bytes: &[u8]) -> Result<Database, Box<dyn std::error::Error>> {
+    decode_with(bytes, None)
+}
+
+// Decodes a file, with `custom` if the file names it rather than a
+// built-in codec.
+pub(crate) fn decode_with(bytes: &[u8], custom: Option<Arc<dyn Codec>>) -> Result<Database, Box<dyn std::error::Error>> {
+    let Some(rest) = bytes.strip_prefix(MAGIC.as_slice()) else {
+        let db: v0::Database = deserialize(bytes)?;
+        return Ok(db.into());
+    };
+    let version = rest.get(..4).ok_or("Truncated database header")?;
+    match u32::from_le_bytes(version.try_into().unwrap()) {
+        FORMAT_VERSION => {
+            if !checksum_matches(bytes)? {
+                return Err("Database file is corrupt: checksum mismatch".into());
+            }
+            let body = &rest[8..];
+            let (&len, rest) = body.split_first().ok_or("Truncated database header")?;
+            let name = rest.get(..len as usize).ok_or("Truncated database header")?;
+            let name = std::str::from_utf8(name)?;
+            let codec = match custom.filter(|codec| codec.name() == name) {
+                Some(codec) => codec,
+                None => codec::built_in(name).ok_or(format!("Database was saved with codec '{}', which is not available", name))?,
+            };
+            let mut db: Database = codec::decode(codec.as_ref(), &rest[len as usize..])?;
+            // bincode is the default, so it is not remembered
+            db.codec = (name != BincodeCodec.name()).then_some(codec);
+            Ok(db)
+        }
+        version => Err(format!("Database format version {} is newer than the supported version {}", version, FORMAT_VERSION).into()),
+    }
+}
+
+mod v0 {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use crate::Record;
+
+    #[derive(Deserialize)]
+    pub struct Table {
+        pub name: String,
+        pub records: Vec<Record>,
+        pub index: HashMap<u64, usize>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Database {
+        pub tables: HashMap<String, Table>,
+    }
+}
+
+impl From<v0::Database> for Database {
+    fn from(db: v0::Database) -> Self {
+        let tables = db.tables.into_iter()
+            .map(|(key, mut t)| {
+                let mut names = SharedNames::default();
+                t.records.iter_mut().for_each(|r| names.share(&mut r.data));
+                (key, Table { name: t.name, records: t.records, index: t.index, proto: None, partitions: None, history: None, encrypted: BTreeSet::new(), generated: Vec::new(), enums: BTreeMap::new(), series: None, queue: None, max_rows: None, temporary: false, columnar: None, compare: BTreeMap::new(), expiry: None, timestamps: false, types: BTreeMap::new() })
+            })
+            .collect();
+        Database { tables, ..Database::new() }
+    }
+}
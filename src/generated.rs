@@ -0,0 +1,512 @@
+use alloc::borrow::Cow;
+#[cfg(feature = "sql")]
+use alloc::collections::BTreeMap;
+use core::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Database, Record, Row, Table, Value};
+use crate::row::SharedNames;
+#[cfg(feature = "sql")]
+use crate::ColumnType;
+use crate::prelude::*;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Concat,
+}
+
+// The scalar functions, all about missing values.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+enum Function {
+    // the first argument with a value
+    Coalesce,
+    // the first argument, unless it equals the second
+    Nullif,
+    // COALESCE of two arguments
+    Ifnull,
+}
+
+impl Function {
+    fn named(name: &str) -> Option<Function> {
+        match name.to_uppercase().as_str() {
+            "COALESCE" => Some(Function::Coalesce),
+            "NULLIF" => Some(Function::Nullif),
+            "IFNULL" => Some(Function::Ifnull),
+            _ => None,
+        }
+    }
+
+    fn check_arity(self, name: &str, arguments: usize) -> Result<(), String> {
+        match self {
+            Function::Coalesce if arguments < 2 => Err(format!("{} takes at least two arguments", name)),
+            Function::Nullif | Function::Ifnull if arguments != 2 => Err(format!("{} takes two arguments", name)),
+            _ => Ok(()),
+        }
+    }
+}
+
+// The types CAST converts to.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+enum Type {
+    Integer,
+    Real,
+    Text,
+}
+
+impl Type {
+    fn named(name: &str) -> Option<Type> {
+        match name.to_uppercase().as_str() {
+            "INTEGER" | "INT" | "BIGINT" => Some(Type::Integer),
+            "REAL" | "FLOAT" | "DOUBLE" | "NUMERIC" => Some(Type::Real),
+            "TEXT" | "VARCHAR" | "STRING" => Some(Type::Text),
+            _ => None,
+        }
+    }
+
+    // `None` for text that is not a number, and for numbers that are not
+    // finite.
+    fn convert(self, value: Value) -> Option<Value> {
+        let number = match value {
+            _ if matches!(self, Type::Text) => return Some(Value::Text(value.to_string())),
+            Value::Int(n) => n as f64,
+            Value::Float(n) => n,
+            Value::Text(text) => text.trim().parse().ok()?,
+            _ => return None,
+        };
+        match self {
+            _ if !number.is_finite() => None,
+            Type::Integer if number.abs() < i64::MAX as f64 => Some(Value::Int(number.trunc() as i64)),
+            Type::Integer => Some(Value::Float(number.trunc())),
+            _ => Some(Value::Float(number)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum Expr {
+    Column(String),
+    Number(f64),
+    Text(String),
+    Binary(Box<Expr>, Op, Box<Expr>),
+    Call(Function, Vec<Expr>),
+    Cast(Box<Expr>, Type),
+}
+
+// Whole numbers print without a fraction, as users write them.
+pub(crate) fn number_text(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        (n as i64).to_string()
+    } else {
+        n.to_string()
+    }
+}
+
+impl Expr {
+    fn rename_column(&mut self, from: &str, to: &str) {
+        match self {
+            Expr::Column(c) if c == from => *c = to.to_string(),
+            Expr::Binary(left, _, right) => {
+                left.rename_column(from, to);
+                right.rename_column(from, to);
+            }
+            Expr::Call(_, arguments) => arguments.iter_mut().for_each(|a| a.rename_column(from, to)),
+            Expr::Cast(expr, _) => expr.rename_column(from, to),
+            _ => {}
+        }
+    }
+
+    // `None` when a column is missing or arithmetic meets text, which the
+    // functions take as NULL.
+    fn eval(&self, data: &Row) -> Option<Value> {
+        match self {
+            Expr::Cast(expr, to) => to.convert(expr.eval(data)?),
+            Expr::Call(Function::Coalesce | Function::Ifnull, arguments) => arguments.iter().find_map(|a| a.eval(data)),
+            Expr::Call(Function::Nullif, arguments) => {
+                let value = arguments[0].eval(data)?;
+                match arguments[1].eval(data) {
+                    Some(other) if value.compare(&other) == Some(Ordering::Equal) => None,
+                    _ => Some(value),
+                }
+            }
+            Expr::Column(c) => data.get(c).map(|v| Value::parse(v)),
+            Expr::Number(n) => Some(Value::Float(*n)),
+            Expr::Text(s) => Some(Value::Text(s.clone())),
+            Expr::Binary(l, Op::Concat, r) => Some(Value::Text(l.eval(data)?.to_string() + &r.eval(data)?.to_string())),
+            Expr::Binary(l, op, r) => {
+                let (l, r) = (l.eval(data)?, r.eval(data)?);
+                match op {
+                    Op::Add => l.checked_add(&r),
+                    Op::Sub => l.checked_sub(&r),
+                    Op::Mul => l.checked_mul(&r),
+                    Op::Div => l.checked_div(&r),
+                    Op::Concat => unreachable!(),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Number(f64),
+    Text(String),
+    Symbol(&'static str),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut number = String::new();
+            while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit() || **d == '.') {
+                number.push(d);
+                chars.next();
+            }
+            tokens.push(Token::Number(number.parse().map_err(|_| format!("Invalid number '{}'", number))?));
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut word = String::new();
+            while let Some(&d) = chars.peek().filter(|d| d.is_alphanumeric() || **d == '_') {
+                word.push(d);
+                chars.next();
+            }
+            tokens.push(Token::Word(word));
+        } else if c == '\'' {
+            chars.next();
+            let text: String = chars.by_ref().take_while(|&d| d != '\'').collect();
+            tokens.push(Token::Text(text));
+        } else {
+            chars.next();
+            let symbol = match c {
+                '+' => "+",
+                '-' => "-",
+                '*' => "*",
+                '/' => "/",
+                '(' => "(",
+                ')' => ")",
+                ',' => ",",
+                '|' if chars.next_if_eq(&'|').is_some() => "||",
+                _ => return Err(format!("Unexpected '{}' in expression", c)),
+            };
+            tokens.push(Token::Symbol(symbol));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        self.position += 1;
+        self.tokens.get(self.position - 1)
+    }
+
+    fn symbol(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol);
+        self.position += found as usize;
+        found
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword));
+        self.position += found as usize;
+        found
+    }
+
+    fn expect(&mut self, keyword: &str) -> Result<(), String> {
+        self.keyword(keyword).then_some(()).ok_or(format!("Expected {} in column definition", keyword))
+    }
+
+    // concatenation binds loosest, then + and -, then * and /
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut left = self.sum()?;
+        while self.symbol("||") {
+            left = Expr::Binary(Box::new(left), Op::Concat, Box::new(self.sum()?));
+        }
+        Ok(left)
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut left = self.product()?;
+        loop {
+            let op = if self.symbol("+") { Op::Add } else if self.symbol("-") { Op::Sub } else { return Ok(left) };
+            left = Expr::Binary(Box::new(left), op, Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut left = self.atom()?;
+        loop {
+            let op = if self.symbol("*") { Op::Mul } else if self.symbol("/") { Op::Div } else { return Ok(left) };
+            left = Expr::Binary(Box::new(left), op, Box::new(self.atom()?));
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        if self.symbol("(") {
+            let expr = self.expr()?;
+            return self.symbol(")").then_some(expr).ok_or("Expected ) in expression".to_string());
+        }
+        if self.symbol("-") {
+            return Ok(Expr::Binary(Box::new(Expr::Number(0.0)), Op::Sub, Box::new(self.atom()?)));
+        }
+        if matches!(self.peek(), Some(Token::Word(w)) if w.eq_ignore_ascii_case("CAST")) && self.tokens.get(self.position + 1) == Some(&Token::Symbol("(")) {
+            self.position += 2;
+            let expr = self.expr()?;
+            self.keyword("AS").then_some(()).ok_or("Expected AS in CAST")?;
+            let Some(Token::Word(name)) = self.next() else {
+                return Err("Expected a type in CAST".to_string());
+            };
+            let to = Type::named(name).ok_or(format!("Cannot CAST to '{}'", name))?;
+            if !self.symbol(")") {
+                return Err("Expected ) after CAST".to_string());
+            }
+            return Ok(Expr::Cast(Box::new(expr), to));
+        }
+        if let (Some(Token::Word(name)), Some(Token::Symbol("("))) = (self.peek(), self.tokens.get(self.position + 1)) {
+            let name = name.clone();
+            let function = Function::named(&name).ok_or(format!("Unknown function '{}'", name))?;
+            self.position += 2;
+            let mut arguments = vec![self.expr()?];
+            while self.symbol(",") {
+                arguments.push(self.expr()?);
+            }
+            if !self.symbol(")") {
+                return Err(format!("Expected ) after the arguments of {}", name));
+            }
+            function.check_arity(&name, arguments.len())?;
+            return Ok(Expr::Call(function, arguments));
+        }
+        match self.next() {
+            Some(Token::Word(w)) => Ok(Expr::Column(w.clone())),
+            Some(Token::Number(n)) => Ok(Expr::Number(*n)),
+            Some(Token::Text(s)) => Ok(Expr::Text(s.clone())),
+            _ => Err("Expected a column, number or string in expression".to_string()),
+        }
+    }
+}
+
+/// A column computed from the others: kept up to date on every write if
+/// stored, or worked out in each SELECT if virtual.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct GeneratedColumn {
+    name: String,
+    expr: Expr,
+    // INTEGER columns round their value
+    integer: bool,
+    stored: bool,
+}
+
+impl GeneratedColumn {
+    // `name [type] GENERATED ALWAYS AS (expr) [STORED | VIRTUAL]`, or
+    // `name [type] DEFAULT expr`
+    pub(crate) fn parse(definition: &str) -> Result<Self, String> {
+        let mut parser = Parser { tokens: tokenize(definition)?, position: 0 };
+        let Some(Token::Word(name)) = parser.next() else {
+            return Err(format!("Invalid column definition '{}'", definition));
+        };
+        let name = name.clone();
+        let integer = parser.keyword("INTEGER") || parser.keyword("INT");
+        while matches!(parser.peek(), Some(Token::Word(w)) if !w.eq_ignore_ascii_case("GENERATED") && !w.eq_ignore_ascii_case("DEFAULT")) {
+            parser.next();
+        }
+        // a default is stored as COALESCE of the column and the default, so
+        // values written to the column win
+        if parser.keyword("DEFAULT") {
+            let expr = Expr::Call(Function::Coalesce, vec![Expr::Column(name.clone()), parser.expr()?]);
+            if parser.peek().is_some() {
+                return Err(format!("Unexpected text at the end of column definition '{}'", definition));
+            }
+            return Ok(GeneratedColumn { name, expr, integer, stored: true });
+        }
+        parser.expect("GENERATED")?;
+        parser.expect("ALWAYS")?;
+        parser.expect("AS")?;
+        if !parser.symbol("(") {
+            return Err("Expected ( after AS in column definition".to_string());
+        }
+        let expr = parser.expr()?;
+        if !parser.symbol(")") {
+            return Err("Expected ) after the generating expression".to_string());
+        }
+        let stored = parser.keyword("STORED");
+        if !stored {
+            parser.keyword("VIRTUAL");
+        }
+        if parser.peek().is_some() {
+            return Err(format!("Unexpected text at the end of column definition '{}'", definition));
+        }
+        Ok(GeneratedColumn { name, expr, integer, stored })
+    }
+
+    // A function call in a SELECT list or WHERE clause, such as
+    // `COALESCE(nick, name)`, worked out like a virtual column named as
+    // written.
+    #[cfg(feature = "sql")]
+    pub(crate) fn call(text: &str) -> Result<Self, String> {
+        let mut parser = Parser { tokens: tokenize(text)?, position: 0 };
+        let expr = parser.expr()?;
+        if !matches!(expr, Expr::Call(..) | Expr::Cast(..)) || parser.peek().is_some() {
+            return Err(format!("Invalid function call '{}'", text));
+        }
+        Ok(GeneratedColumn { name: text.to_string(), expr, integer: false, stored: false })
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[cfg(feature = "sql")]
+    pub(crate) fn is_stored(&self) -> bool {
+        self.stored
+    }
+
+    // Whether the column is a number CAST, which WHERE compares as numbers.
+    #[cfg(feature = "sql")]
+    pub(crate) fn is_numeric(&self) -> bool {
+        matches!(self.expr, Expr::Cast(_, Type::Integer | Type::Real))
+    }
+
+    fn is_default(&self) -> bool {
+        matches!(&self.expr, Expr::Call(Function::Coalesce, arguments) if matches!(arguments.first(), Some(Expr::Column(c)) if *c == self.name))
+    }
+
+    // Follows a column renamed from `from` to `to`, in its name and its
+    // expression.
+    pub(crate) fn rename_column(&mut self, from: &str, to: &str) {
+        if self.name == from {
+            self.name = to.to_string();
+        }
+        self.expr.rename_column(from, to);
+    }
+
+    pub(crate) fn value(&self, data: &Row) -> Option<String> {
+        match self.expr.eval(data)? {
+            Value::Float(n) if self.integer => Some((n.round() as i64).to_string()),
+            value => Some(value.to_string()),
+        }
+    }
+}
+
+// Splits the body of a CREATE TABLE into column definitions.
+#[cfg(feature = "sql")]
+pub(crate) fn split_definitions(body: &str) -> Vec<&str> {
+    let mut definitions = Vec::new();
+    let (mut depth, mut start) = (0, 0);
+    for (i, c) in body.char_indices().chain([(body.len(), ',')]) {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                definitions.push(body[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    definitions
+}
+
+// The generated and defaulted column definitions in the body of a CREATE
+// TABLE; plain column definitions are ignored, as tables take any columns.
+#[cfg(feature = "sql")]
+pub(crate) fn parse_definitions(body: &str) -> Result<Vec<GeneratedColumn>, String> {
+    split_definitions(body).into_iter()
+        .filter(|definition| {
+            let definition = definition.to_uppercase();
+            definition.contains("GENERATED") || definition.split_whitespace().any(|w| w == "DEFAULT")
+        })
+        .map(GeneratedColumn::parse)
+        .collect()
+}
+
+// Sets the stored generated columns of a row being written and drops any
+// virtual ones, so values given for generated columns are replaced.
+pub(crate) fn fill(columns: &[GeneratedColumn], data: &mut Row) {
+    for column in columns {
+        match column.value(data).filter(|_| column.stored) {
+            Some(value) => data.insert(column.name.clone(), value),
+            None => data.remove(&column.name),
+        };
+    }
+}
+
+pub(crate) fn check_writable(columns: &[GeneratedColumn], written: &[String]) -> Result<(), String> {
+    match columns.iter().find(|c| !c.is_default() && written.contains(&c.name)) {
+        Some(column) => Err(format!("Cannot write generated column '{}'", column.name)),
+        None => Ok(()),
+    }
+}
+
+impl Table {
+    // The record with its virtual columns worked out.
+    pub(crate) fn with_virtual<'a>(&self, record: &'a Record) -> Cow<'a, Record> {
+        let mut virtual_columns = self.generated.iter().filter(|c| !c.stored).peekable();
+        if virtual_columns.peek().is_none() {
+            return Cow::Borrowed(record);
+        }
+        let mut record = record.clone();
+        for column in virtual_columns {
+            if let Some(value) = column.value(&record.data) {
+                record.data.insert(column.name.clone(), value);
+            }
+        }
+        Cow::Owned(record)
+    }
+}
+
+impl Database {
+    #[cfg(feature = "sql")]
+    pub(crate) fn execute_create_table(&mut self, table_name: String, generated: Vec<GeneratedColumn>, enums: BTreeMap<String, Vec<String>>, types: BTreeMap<String, ColumnType>, temporary: bool, max_rows: Option<usize>) -> Result<Vec<Record>, String> {
+        self.create_table(table_name.clone())?;
+        if let Some(table) = self.tables.get_mut(&table_name) {
+            table.generated = generated;
+            table.enums = enums;
+            table.types = types;
+            table.temporary = temporary;
+            table.max_rows = max_rows;
+        }
+        Ok(Vec::new())
+    }
+
+    /// Adds a column computed from the others, declared as in SQL:
+    /// `price_cents INTEGER GENERATED ALWAYS AS (price * 100) STORED`.
+    /// Expressions combine columns, numbers and `'text'` with `+ - * /`,
+    /// `||`, `COALESCE`, `NULLIF`, `IFNULL` and `CAST(expr AS type)`. Stored columns are written with each row and can partition a
+    /// table; virtual ones (the default) are worked out in each SELECT.
+    /// Either kind can be used in WHERE clauses.
+    pub fn add_generated_column(&mut self, table_name: &str, definition: &str) -> Result<(), String> {
+        let column = GeneratedColumn::parse(definition)?;
+        let table = self.tables.get_mut(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        if table.generated.iter().any(|c| c.name == column.name) {
+            return Err(format!("Column '{}' of '{}' is already generated", column.name, table_name));
+        }
+        table.generated.push(column);
+        table.invalidate_columns();
+        let mut names = SharedNames::default();
+        for record in &mut table.records {
+            fill(&table.generated, &mut record.data);
+            names.share(&mut record.data);
+        }
+        if let Some(partitions) = &table.partitions {
+            table.partitions = Some(crate::partition::Partitions::new(partitions.scheme().clone(), &table.records));
+        }
+        Ok(())
+    }
+}
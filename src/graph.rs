@@ -0,0 +1,200 @@
+use alloc::collections::VecDeque;
+
+use crate::prelude::*;
+use crate::{Database, Record, Table};
+#[cfg(feature = "sql")]
+use crate::{interrupt, Condition, Row};
+
+/// The order [`Database::traverse`] visits nodes in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Traversal {
+    BreadthFirst,
+    DepthFirst,
+}
+
+// What a GRAPH statement asks of an edge table.
+#[cfg(feature = "sql")]
+#[derive(Clone, Debug)]
+pub(crate) enum GraphQuery {
+    Neighbors(String),
+    Traverse(Traversal, String, usize),
+    Path(String, String),
+}
+
+#[cfg(feature = "sql")]
+impl GraphQuery {
+    // `NEIGHBORS node`, `BFS node depth`, `DFS node depth` or `PATH from to`
+    pub(crate) fn parse(tokens: &[&str]) -> Result<(Self, usize), String> {
+        let depth = |token: &str| token.parse().map_err(|_| format!("Invalid traversal depth '{}'", token));
+        match tokens {
+            [kind, node, ..] if kind.eq_ignore_ascii_case("NEIGHBORS") => Ok((GraphQuery::Neighbors(node.to_string()), 2)),
+            [kind, node, n, ..] if kind.eq_ignore_ascii_case("BFS") => Ok((GraphQuery::Traverse(Traversal::BreadthFirst, node.to_string(), depth(n)?), 3)),
+            [kind, node, n, ..] if kind.eq_ignore_ascii_case("DFS") => Ok((GraphQuery::Traverse(Traversal::DepthFirst, node.to_string(), depth(n)?), 3)),
+            [kind, from, to, ..] if kind.eq_ignore_ascii_case("PATH") => Ok((GraphQuery::Path(from.to_string(), to.to_string()), 3)),
+            _ => Err("Invalid GRAPH statement".to_string()),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct EdgeColumns {
+    source: String,
+    target: String,
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct EdgeTables {
+    tables: HashMap<String, EdgeColumns>,
+}
+
+impl EdgeTables {
+    pub(crate) fn rename_column(&mut self, table: &str, from: &str, to: &str) {
+        if let Some(columns) = self.tables.get_mut(table) {
+            for column in [&mut columns.source, &mut columns.target] {
+                if column == from {
+                    *column = to.to_string();
+                }
+            }
+        }
+    }
+}
+
+// Directed adjacency lists, with neighbours in the order their edges were
+// inserted.
+struct Graph {
+    edges: HashMap<String, Vec<String>>,
+}
+
+impl Graph {
+    fn new<'a>(columns: &EdgeColumns, records: impl IntoIterator<Item = &'a Record>) -> Self {
+        let mut edges: HashMap<String, Vec<String>> = HashMap::new();
+        for record in records {
+            if let (Some(source), Some(target)) = (record.data.get(&columns.source), record.data.get(&columns.target)) {
+                let targets = edges.entry(source.clone()).or_default();
+                if !targets.contains(target) {
+                    targets.push(target.clone());
+                }
+            }
+        }
+        Graph { edges }
+    }
+
+    fn neighbors(&self, node: &str) -> &[String] {
+        self.edges.get(node).map_or(&[], Vec::as_slice)
+    }
+
+    // Each reachable node within `max_depth` edges, once, with the depth it
+    // was reached at; the start is at depth 0.
+    fn traverse(&self, start: &str, max_depth: usize, order: Traversal) -> Vec<(String, usize)> {
+        let mut seen: HashSet<String> = HashSet::from([start.to_string()]);
+        let mut pending = VecDeque::from([(start.to_string(), 0)]);
+        let mut visited = Vec::new();
+        loop {
+            let next = match order {
+                Traversal::BreadthFirst => pending.pop_front(),
+                Traversal::DepthFirst => pending.pop_back(),
+            };
+            let Some((node, depth)) = next else { return visited };
+            if depth < max_depth {
+                let neighbors = self.neighbors(&node).iter().filter(|n| seen.insert(n.to_string()));
+                let neighbors: Vec<_> = neighbors.map(|n| (n.clone(), depth + 1)).collect();
+                match order {
+                    Traversal::BreadthFirst => pending.extend(neighbors),
+                    // so the first neighbour is explored first
+                    Traversal::DepthFirst => pending.extend(neighbors.into_iter().rev()),
+                }
+            }
+            visited.push((node, depth));
+        }
+    }
+
+    // Fewest edges from `from` to `to`, both included.
+    fn shortest_path(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        let mut previous: HashMap<&str, &str> = HashMap::new();
+        let mut pending = VecDeque::from([from]);
+        while let Some(node) = pending.pop_front() {
+            if node == to {
+                let mut path = vec![to.to_string()];
+                let mut node = to;
+                while let Some(&before) = previous.get(node) {
+                    path.push(before.to_string());
+                    node = before;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for neighbor in self.neighbors(node) {
+                if neighbor != from && !previous.contains_key(neighbor.as_str()) {
+                    previous.insert(neighbor, node);
+                    pending.push_back(neighbor);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(feature = "sql")]
+fn node_rows(nodes: impl IntoIterator<Item = (String, usize)>) -> Vec<Record> {
+    (1..).zip(nodes)
+        .map(|(id, (node, depth))| Record { id, data: Row::from([("node".to_string(), node), ("depth".to_string(), depth.to_string())]) })
+        .collect()
+}
+
+impl Database {
+    /// Declares `table` an edge list: each row is a directed edge from the
+    /// node in `source` to the node in `target`, for the traversal methods
+    /// and `GRAPH` statements. Declarations are not saved with the
+    /// database.
+    pub fn declare_edges(&mut self, table: &str, source: &str, target: &str) -> Result<(), String> {
+        if !self.tables.contains_key(table) {
+            return Err(format!("Table '{}' not found", table));
+        }
+        self.edge_tables.tables.insert(table.to_string(), EdgeColumns { source: source.to_string(), target: target.to_string() });
+        Ok(())
+    }
+
+    /// The nodes `node` has an edge to.
+    pub fn neighbors(&self, table: &str, node: &str) -> Result<Vec<String>, String> {
+        Ok(self.graph(table)?.neighbors(node).to_vec())
+    }
+
+    /// The nodes reachable from `start` in at most `max_depth` edges, each
+    /// once, in visiting order and with the depth it was first reached at.
+    pub fn traverse(&self, table: &str, start: &str, max_depth: usize, order: Traversal) -> Result<Vec<(String, usize)>, String> {
+        Ok(self.graph(table)?.traverse(start, max_depth, order))
+    }
+
+    /// A path from `from` to `to` with the fewest edges, if there is one.
+    pub fn shortest_path(&self, table: &str, from: &str, to: &str) -> Result<Option<Vec<String>>, String> {
+        Ok(self.graph(table)?.shortest_path(from, to))
+    }
+
+    fn edge_table(&self, table_name: &str) -> Result<(&EdgeColumns, &Table), String> {
+        let columns = self.edge_tables.tables.get(table_name).ok_or(format!("Table '{}' is not an edge table", table_name))?;
+        let table = self.tables.get(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        Ok((columns, table))
+    }
+
+    // The graph of every edge.
+    fn graph(&self, table_name: &str) -> Result<Graph, String> {
+        let (columns, table) = self.edge_table(table_name)?;
+        Ok(Graph::new(columns, &table.records))
+    }
+
+    // Rows of `node` and `depth` over the edges matching `condition`: the
+    // path's steps count from 0.
+    #[cfg(feature = "sql")]
+    pub(crate) fn execute_graph(&self, table: &str, query: &GraphQuery, condition: &Option<Condition>) -> Result<Vec<Record>, String> {
+        let (columns, table) = self.edge_table(table)?;
+        let mut matched = Vec::new();
+        table.filter(table.scan(condition), condition, |record| matched.push(record));
+        interrupt::check()?;
+        let graph = Graph::new(columns, matched.iter().map(AsRef::as_ref));
+        Ok(match query {
+            GraphQuery::Neighbors(node) => node_rows(graph.neighbors(node).iter().map(|n| (n.clone(), 1))),
+            GraphQuery::Traverse(order, start, max_depth) => node_rows(graph.traverse(start, *max_depth, *order)),
+            GraphQuery::Path(from, to) => node_rows(graph.shortest_path(from, to).into_iter().flatten().zip(0..)),
+        })
+    }
+}
@@ -0,0 +1,126 @@
+use core::borrow::Borrow;
+use core::fmt;
+
+use crate::aggregate::Accumulator;
+use crate::arena::{self, ArenaVec};
+use crate::prelude::*;
+use crate::{identifier, interrupt, join_calls, Condition, Database, Record, Row};
+
+// The `GROUP BY keys [HAVING condition]` of a SELECT. The columns it
+// selects are keys, or aggregates of each group's rows such as `COUNT(*)`
+// or `SUM(total)`, named as written; HAVING compares those the same way.
+#[derive(Clone, Debug)]
+pub(crate) struct GroupBy {
+    keys: Vec<String>,
+    having: Option<Condition>,
+}
+
+impl fmt::Display for GroupBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.keys.join(", "))?;
+        match &self.having {
+            Some(having) => write!(f, " HAVING {}", having),
+            None => Ok(()),
+        }
+    }
+}
+
+impl GroupBy {
+    // The clause from the tokens after `GROUP BY`.
+    pub(crate) fn parse(db: &Database, tokens: &[&str]) -> Result<GroupBy, String> {
+        let (keys, having) = match tokens.iter().position(|t| t.eq_ignore_ascii_case("HAVING")) {
+            Some(at) => (&tokens[..at], Some(&tokens[at + 1..])),
+            None => (tokens, None),
+        };
+        let keys: Vec<String> = keys.join(" ")
+            .split(',')
+            .map(|key| identifier::unquote(key.trim()))
+            .collect();
+        if keys.iter().any(|key| key.is_empty() || key.contains(char::is_whitespace)) {
+            return Err(format!("Invalid GROUP BY clause '{}'", keys.join(", ")));
+        }
+        let having = match having {
+            Some(tokens) => {
+                let joined = join_calls(tokens);
+                let tokens: Vec<&str> = joined.iter().map(String::as_str).collect();
+                Some(db.parse_conditions(&tokens).map_err(|_| format!("Invalid HAVING clause '{}'", tokens.join(" ")))?)
+            }
+            None => None,
+        };
+        Ok(GroupBy { keys, having })
+    }
+
+    // The aggregates HAVING compares.
+    fn having_columns<'a>(condition: &'a Condition, columns: &mut Vec<&'a str>) {
+        match condition {
+            Condition::Equals(c, _) | Condition::NotEquals(c, _) | Condition::GreaterThan(c, _) | Condition::LessThan(c, _) | Condition::Between(c, _, _) | Condition::Like(c, _) | Condition::IsNull(c) | Condition::IsNotNull(c) => columns.push(c),
+            Condition::And(l, r) | Condition::Or(l, r) => {
+                Self::having_columns(l, columns);
+                Self::having_columns(r, columns);
+            }
+        }
+    }
+}
+
+impl Database {
+    // One row per distinct combination of key values among `rows`, in the
+    // order each first appears, holding the `columns` of the groups HAVING
+    // keeps. Rows without a key are grouped together, and their groups
+    // leave it out.
+    pub(crate) fn group_rows<R: Borrow<Record>>(&self, group_by: &GroupBy, columns: &[String], rows: &[R]) -> Result<Vec<Record>, String> {
+        let mut aggregates = Vec::new();
+        for column in columns {
+            if column == "*" {
+                return Err("SELECT * cannot be grouped; name the GROUP BY columns and aggregates".to_string());
+            }
+            if !group_by.keys.contains(column) {
+                let accumulator = match column.contains('(') {
+                    true => Accumulator::parse(column)?,
+                    false => return Err(format!("Column '{}' is neither grouped nor aggregated", column)),
+                };
+                aggregates.push((column.as_str(), accumulator));
+            }
+        }
+        let mut having_columns = Vec::new();
+        if let Some(having) = &group_by.having {
+            GroupBy::having_columns(having, &mut having_columns);
+        }
+        for column in having_columns {
+            if !group_by.keys.iter().any(|k| k == column) && !aggregates.iter().any(|(name, _)| *name == column) {
+                aggregates.push((column, Accumulator::parse(column).map_err(|_| format!("Column '{}' is neither grouped nor aggregated", column))?));
+            }
+        }
+        let case_sensitive = interrupt::case_sensitive();
+        Ok(arena::scoped(|arena| {
+            let mut groups: ArenaVec<(Vec<Option<&str>>, ArenaVec<&Record>)> = ArenaVec::new_in(arena);
+            let mut positions: HashMap<Vec<Option<&str>>, usize> = HashMap::new();
+            for row in rows {
+                let row = row.borrow();
+                let values: Vec<Option<&str>> = group_by.keys.iter().map(|key| row.data.get(key).map(String::as_str)).collect();
+                let position = *positions.entry(values.clone()).or_insert_with(|| {
+                    groups.push((values, ArenaVec::new_in(arena)));
+                    groups.len() - 1
+                });
+                groups[position].1.push(row);
+            }
+            (1..).zip(groups)
+                .filter_map(|(id, (values, rows))| {
+                    let mut data: Row = aggregates.iter()
+                        .filter_map(|(name, accumulator)| Some((name.to_string(), accumulator.apply(&rows[..])?)))
+                        .collect();
+                    for (key, value) in group_by.keys.iter().zip(values) {
+                        if let Some(value) = value {
+                            data.insert(key.clone(), value.to_string());
+                        }
+                    }
+                    let group = Record { id, data };
+                    if !group_by.having.as_ref().map_or(true, |having| having.matches(&group, case_sensitive, true)) {
+                        return None;
+                    }
+                    let data = group.data.into_iter().filter(|(column, _)| columns.contains(column)).collect();
+                    Some(Record { id, data })
+                })
+                .collect()
+        }))
+    }
+}
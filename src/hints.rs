@@ -0,0 +1,86 @@
+use alloc::borrow::Cow;
+
+use crate::identifier;
+use crate::prelude::*;
+use crate::Condition;
+
+// What the `/*+ ... */` comments of a SELECT ask of its scan. The only
+// access paths a scan can take besides reading every row are looking up
+// the id the WHERE clause requires and pruning the partitions it rules
+// out, which it does whenever it can, so `NO_INDEX` and `FULL(table)` turn
+// those off and `INDEX(table name)` asks for what happens anyway. Hints naming other tables, and hints this
+// planner has no use for, such as join orders, are ignored.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Hints {
+    full_scan: bool,
+}
+
+impl Hints {
+    // The hints of `text`, the bodies of a statement's hint comments, that
+    // apply to `table`.
+    pub(crate) fn parse(text: &str, table: &str) -> Hints {
+        let mut hints = Hints::default();
+        let mut rest = text.trim();
+        while !rest.is_empty() {
+            let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+            let name = &rest[..end];
+            rest = rest[end..].trim_start();
+            let mut arguments = Vec::new();
+            if let Some(inner) = rest.strip_prefix('(') {
+                let close = inner.find(')').unwrap_or(inner.len());
+                arguments = inner[..close].split(|c: char| c.is_whitespace() || c == ',').filter(|a| !a.is_empty()).collect();
+                rest = inner.get(close + 1..).unwrap_or_default().trim_start();
+            } else if end == 0 {
+                // a stray character between hints
+                rest = rest[rest.chars().next().map_or(0, char::len_utf8)..].trim_start();
+            }
+            let ours = arguments.first().map_or(true, |t| identifier::unquote(t) == table);
+            match name.to_uppercase().as_str() {
+                "NO_INDEX" | "FULL" if ours => hints.full_scan = true,
+                "INDEX" if ours => hints.full_scan = false,
+                _ => {}
+            }
+        }
+        hints
+    }
+
+    // The condition the scan may prune by.
+    pub(crate) fn scanned(self, condition: &Option<Condition>) -> &Option<Condition> {
+        match self.full_scan {
+            true => &None,
+            false => condition,
+        }
+    }
+}
+
+// `sql` without its `/* ... */` comments, and the bodies of those that are
+// hints, `/*+ ... */`, joined. Comments inside quotes are left alone.
+pub(crate) fn strip(sql: &str) -> (Cow<'_, str>, String) {
+    if !sql.contains("/*") {
+        return (Cow::Borrowed(sql), String::new());
+    }
+    let mut stripped = String::with_capacity(sql.len());
+    let mut hints = String::new();
+    let mut quote = None;
+    let mut rest = sql;
+    while let Some(c) = rest.chars().next() {
+        match quote {
+            None if rest.starts_with("/*") => {
+                let end = rest.find("*/").map_or(rest.len(), |end| end + 2);
+                if let Some(body) = rest[2..end.max(2)].strip_prefix('+') {
+                    hints.push_str(body.trim_end_matches("*/"));
+                    hints.push(' ');
+                }
+                stripped.push(' ');
+                rest = &rest[end..];
+                continue;
+            }
+            None if c == '\'' || c == '"' => quote = Some(c),
+            Some(q) if c == q => quote = None,
+            _ => {}
+        }
+        stripped.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    (Cow::Owned(stripped), hints)
+}
@@ -0,0 +1,170 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use core::cmp::Ordering;
+
+use crate::prelude::*;
+use crate::{CompareAs, Condition, Database, Record, Row, Table};
+
+// Buckets per histogram, fewer for columns with fewer values.
+const BUCKETS: usize = 32;
+
+/// The distribution of one column's values, built by
+/// [`Database::analyze`]: the values sorted as WHERE compares them and cut
+/// into buckets holding about as many rows each, so a range predicate's
+/// share of rows can be estimated from the buckets it covers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    column: String,
+    rows: usize,
+    missing: usize,
+    distinct: usize,
+    // the smallest value, then the largest of each bucket
+    bounds: Vec<String>,
+    compare: CompareAs,
+}
+
+impl Histogram {
+    fn build(table: &Table, column: &str) -> Histogram {
+        let compare = table.compare.get(column).copied().or_else(|| table.declared_compare(column)).unwrap_or_else(|| match table.proto.as_ref().is_some_and(|p| p.is_numeric(column)) {
+            true => CompareAs::Numeric,
+            false => CompareAs::Value,
+        });
+        let mut values: Vec<&String> = table.records.iter().filter_map(|r| r.data.get(column)).collect();
+        values.sort_by(|a, b| compare.sort(a, b));
+        let distinct = values.iter().collect::<BTreeSet<_>>().len();
+        let buckets = BUCKETS.min(values.len());
+        let bounds = match buckets {
+            0 => Vec::new(),
+            _ => core::iter::once(values[0])
+                .chain((1..=buckets).map(|b| values[b * values.len() / buckets - 1]))
+                .cloned()
+                .collect(),
+        };
+        Histogram { column: column.to_string(), rows: values.len(), missing: table.records.len() - values.len(), distinct, bounds, compare }
+    }
+
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    /// Rows holding a value in the column.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Rows without the column.
+    pub fn missing(&self) -> usize {
+        self.missing
+    }
+
+    pub fn distinct(&self) -> usize {
+        self.distinct
+    }
+
+    /// The smallest value, then the largest value of each bucket; each
+    /// bucket holds about `rows / (bounds.len() - 1)` rows.
+    pub fn bounds(&self) -> &[String] {
+        &self.bounds
+    }
+
+    fn total(&self) -> f64 {
+        (self.rows + self.missing) as f64
+    }
+
+    // The share of all rows whose value is `value`, assuming values are
+    // spread evenly.
+    fn equal(&self, value: &str) -> f64 {
+        match self.rows == 0 || self.compare.compare(value, value).is_none() {
+            true => 0.0,
+            false => self.rows as f64 / self.distinct as f64 / self.total(),
+        }
+    }
+
+    // The share of all rows whose value sorts before `value`: the buckets
+    // wholly below it, and half of the one it falls in.
+    fn below(&self, value: &str) -> f64 {
+        let Some((first, uppers)) = self.bounds.split_first() else { return 0.0 };
+        if self.compare.sort(value, first) != Ordering::Greater {
+            return 0.0;
+        }
+        let full = uppers.iter().take_while(|upper| self.compare.sort(upper, value) == Ordering::Less).count();
+        let buckets = match full < uppers.len() {
+            true => full as f64 + 0.5,
+            false => full as f64,
+        };
+        buckets / uppers.len() as f64 * self.rows as f64 / self.total()
+    }
+
+    fn with_value(&self) -> f64 {
+        self.rows as f64 / self.total()
+    }
+}
+
+// column -> histogram, per table
+#[derive(Clone, Default)]
+pub(crate) struct Histograms {
+    tables: HashMap<String, BTreeMap<String, Histogram>>,
+}
+
+impl Histograms {
+    // The share of `table`'s rows expected to match `condition`, from the
+    // histograms of the columns it compares; columns without one are
+    // assumed to match every row.
+    pub(crate) fn selectivity(&self, table: &str, condition: &Condition) -> f64 {
+        let histogram = |column: &str| self.tables.get(table).and_then(|columns| columns.get(column));
+        let share = match condition {
+            Condition::Equals(c, v) => histogram(c).map_or(1.0, |h| h.equal(v)),
+            Condition::NotEquals(c, v) => histogram(c).map_or(1.0, |h| 1.0 - h.equal(v)),
+            Condition::LessThan(c, v) => histogram(c).map_or(1.0, |h| h.below(v)),
+            Condition::GreaterThan(c, v) => histogram(c).map_or(1.0, |h| h.with_value() - h.below(v) - h.equal(v)),
+            Condition::Between(c, low, high) => histogram(c).map_or(1.0, |h| h.below(high) + h.equal(high) - h.below(low)),
+            // histograms know nothing of patterns
+            Condition::Like(..) => 1.0,
+            Condition::IsNull(c) => histogram(c).map_or(1.0, |h| 1.0 - h.with_value()),
+            Condition::IsNotNull(c) => histogram(c).map_or(1.0, Histogram::with_value),
+            Condition::And(l, r) => self.selectivity(table, l) * self.selectivity(table, r),
+            Condition::Or(l, r) => {
+                let (l, r) = (self.selectivity(table, l), self.selectivity(table, r));
+                l + r - l * r
+            }
+        };
+        share.clamp(0.0, 1.0)
+    }
+}
+
+impl Database {
+    /// Builds a histogram of every column of a table, for `EXPLAIN` to
+    /// estimate how many rows a WHERE clause keeps, and returns them in
+    /// column order. The histograms describe the table as it is now; run
+    /// this again after large changes. `ANALYZE table` does the same in SQL.
+    pub fn analyze(&mut self, table_name: &str) -> Result<Vec<Histogram>, String> {
+        let table = self.tables.get(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        let mut columns: BTreeSet<&String> = table.records.iter().flat_map(|r| r.data.keys()).collect();
+        let proto_columns = table.proto.as_ref().map(|p| p.columns()).unwrap_or_default();
+        columns.extend(proto_columns.iter());
+        let histograms: BTreeMap<String, Histogram> = columns.into_iter().map(|c| (c.clone(), Histogram::build(table, c))).collect();
+        let built = histograms.values().cloned().collect();
+        self.histograms.tables.insert(table_name.to_string(), histograms);
+        Ok(built)
+    }
+
+    /// The histogram [`analyze`](Self::analyze) last built of a column.
+    pub fn histogram(&self, table_name: &str, column: &str) -> Option<&Histogram> {
+        self.histograms.tables.get(table_name)?.get(column)
+    }
+
+    pub(crate) fn execute_analyze(&mut self, table_name: &str) -> Result<Vec<Record>, String> {
+        let histograms = self.analyze(table_name)?;
+        Ok(histograms.into_iter().zip(1..)
+            .map(|(h, id)| {
+                let data = Row::from([
+                    ("column".to_string(), h.column),
+                    ("rows".to_string(), h.rows.to_string()),
+                    ("missing".to_string(), h.missing.to_string()),
+                    ("distinct".to_string(), h.distinct.to_string()),
+                    ("buckets".to_string(), h.bounds.len().saturating_sub(1).to_string()),
+                ]);
+                Record { id, data }
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,148 @@
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock::unix_millis;
+use crate::prelude::*;
+use crate::{Database, Row};
+#[cfg(any(feature = "std", feature = "sql"))]
+use crate::Record;
+#[cfg(feature = "sql")]
+use crate::Table;
+
+// A row as written at a time in milliseconds since the Unix epoch. `None`
+// marks a delete.
+type Version = (u64, Option<Row>);
+
+// Every version of every row of a table, oldest first.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub(crate) struct History {
+    versions: HashMap<u64, Vec<Version>>,
+}
+
+impl History {
+    pub(crate) fn record(&mut self, id: u64, data: Option<&Row>) {
+        self.versions.entry(id).or_default().push((unix_millis(), data.cloned()));
+    }
+
+    #[cfg(any(feature = "std", feature = "sql"))]
+    fn at(&self, id: u64, millis: u64) -> Option<&Row> {
+        let versions = self.versions.get(&id)?;
+        let end = versions.partition_point(|(written, _)| *written <= millis);
+        versions[..end].last()?.1.as_ref()
+    }
+}
+
+// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+// The proleptic Gregorian date `days` after 1970-01-01, as `2024-01-01`.
+#[cfg(feature = "sql")]
+pub(crate) fn date_text(days: i64) -> String {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// Days since 1970-01-01 of a `2024-01-01` date.
+#[cfg(feature = "sql")]
+pub(crate) fn parse_date(text: &str) -> Option<i64> {
+    let mut parts = text.splitn(3, '-').map(str::parse::<i64>);
+    let (Some(Ok(year)), Some(Ok(month)), Some(Ok(day))) = (parts.next(), parts.next(), parts.next()) else {
+        return None;
+    };
+    ((1..=12).contains(&month) && (1..=31).contains(&day) && text.len() == 10).then(|| days_from_civil(year, month, day))
+}
+
+// Reads `2024-01-01`, `2024-01-01T12:30:00Z` (optionally with fractional
+// seconds) or milliseconds since the Unix epoch, in UTC.
+pub(crate) fn parse_timestamp(text: &str) -> Result<u64, String> {
+    let text = text.trim_matches(|c| c == '\'' || c == '"');
+    let invalid = || format!("Invalid timestamp '{}'", text);
+    if let Ok(millis) = text.parse::<u64>() {
+        return Ok(millis);
+    }
+    let (date, time) = text.trim_end_matches('Z').split_once('T').unwrap_or((text, "00:00:00"));
+    let date: Vec<i64> = date.split('-').map(str::parse).collect::<Result<_, _>>().map_err(|_| invalid())?;
+    let [year, month, day] = date[..] else { return Err(invalid()) };
+    let (time, fraction) = time.split_once('.').unwrap_or((time, "0"));
+    let time: Vec<i64> = time.split(':').map(str::parse).collect::<Result<_, _>>().map_err(|_| invalid())?;
+    let [hour, minute, second] = time[..] else { return Err(invalid()) };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return Err(invalid());
+    }
+    let millis_of_second: i64 = format!("{:0<3.3}", fraction).parse().map_err(|_| invalid())?;
+    let seconds = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second;
+    u64::try_from(seconds * 1000 + millis_of_second).map_err(|_| invalid())
+}
+
+#[cfg(feature = "sql")]
+impl Table {
+    // The table as it was at `millis`, ordered by id.
+    pub(crate) fn as_of(&self, millis: u64) -> Result<Table, String> {
+        let history = self.history.as_ref().ok_or(format!("Table '{}' does not keep history", self.name))?;
+        let mut records: Vec<Record> = history.versions.keys()
+            .filter_map(|&id| Some(Record { id, data: history.at(id, millis)?.clone() }))
+            .collect();
+        records.sort_by_key(|r| r.id);
+        Ok(Table {
+            name: self.name.clone(),
+            index: records.iter().enumerate().map(|(i, r)| (r.id, i)).collect(),
+            records,
+            proto: self.proto.clone(),
+            partitions: None,
+            history: None,
+            encrypted: self.encrypted.clone(),
+            generated: self.generated.clone(),
+            enums: self.enums.clone(),
+            series: self.series.clone(),
+            queue: self.queue.clone(),
+            max_rows: self.max_rows,
+            temporary: self.temporary,
+            columnar: None,
+            compare: self.compare.clone(),
+            expiry: None,
+            timestamps: false,
+            types: self.types.clone(),
+        })
+    }
+}
+
+impl Database {
+    /// Keeps every version of the table's rows from now on, so reads with
+    /// `AS OF` or [`get_as_of`](Self::get_as_of) can see past states. The
+    /// history is saved with the database.
+    pub fn enable_history(&mut self, table_name: &str) -> Result<(), String> {
+        let table = self.tables.get_mut(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        if table.history.is_none() {
+            let mut history = History::default();
+            table.records.iter().for_each(|r| history.record(r.id, Some(&r.data)));
+            table.history = Some(history);
+        }
+        Ok(())
+    }
+
+    /// The record as it was at `as_of`, or `None` if it did not exist then.
+    #[cfg(feature = "std")]
+    pub fn get_as_of(&self, table_name: &str, id: u64, as_of: SystemTime) -> Result<Option<Record>, String> {
+        let table = self.tables.get(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        let history = table.history.as_ref().ok_or(format!("Table '{}' does not keep history", table_name))?;
+        let millis = as_of.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64);
+        Ok(history.at(id, millis).map(|data| Record { id, data: data.clone() }))
+    }
+}
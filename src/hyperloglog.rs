@@ -0,0 +1,47 @@
+use core::hash::BuildHasher;
+#[cfg(feature = "std")]
+use std::hash::BuildHasherDefault;
+#[cfg(feature = "std")]
+use std::collections::hash_map::DefaultHasher;
+
+use crate::prelude::*;
+
+// A fixed hash, so the same values always give the same estimate.
+#[cfg(feature = "std")]
+type FixedHash = BuildHasherDefault<DefaultHasher>;
+#[cfg(not(feature = "std"))]
+type FixedHash = foldhash::fast::FixedState;
+
+// 2^14 registers: about 0.8% standard error in 16 KiB, however many values.
+const PRECISION: u32 = 14;
+const REGISTERS: usize = 1 << PRECISION;
+
+/// A HyperLogLog sketch estimating how many distinct values were added.
+pub(crate) struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    pub(crate) fn new() -> Self {
+        HyperLogLog { registers: vec![0; REGISTERS] }
+    }
+
+    pub(crate) fn add(&mut self, value: &str) {
+        let hash = FixedHash::default().hash_one(value);
+        let register = (hash >> (64 - PRECISION)) as usize;
+        // the position of the first set bit after the register bits
+        let rank = ((hash << PRECISION) | (1 << (PRECISION - 1))).leading_zeros() as u8 + 1;
+        self.registers[register] = self.registers[register].max(rank);
+    }
+
+    pub(crate) fn estimate(&self) -> u64 {
+        let m = REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self.registers.iter().map(|&r| 2f64.powi(-(r as i32))).sum();
+        let raw = alpha * m * m / sum;
+        let empty = self.registers.iter().filter(|&&r| r == 0).count();
+        // linear counting is more accurate for small cardinalities
+        let estimate = if raw <= 2.5 * m && empty > 0 { m * (m / empty as f64).ln() } else { raw };
+        estimate.round() as u64
+    }
+}
@@ -0,0 +1,81 @@
+use crate::prelude::*;
+#[cfg(feature = "sql")]
+use crate::precheck;
+
+// The keywords the parser reads statements by, from the lists it checks
+// them against; a table or column named one of these has to be quoted in
+// SQL. Without SQL, nothing reads names back.
+#[cfg(feature = "sql")]
+const KEYWORDS: &[&[&str]] = &[precheck::STATEMENTS, precheck::OPERAND_TAKING, precheck::COMPARISONS];
+#[cfg(not(feature = "sql"))]
+const KEYWORDS: &[&[&str]] = &[];
+
+/// Writes a table or column name as SQL reads it: as it is if it is a plain
+/// identifier (letters, digits and `_`, not starting with a digit, and not
+/// a keyword), and otherwise in double quotes, doubling any quote inside.
+/// `quote_identifier("first name")` is `"first name"`.
+pub fn quote_identifier(name: &str) -> String {
+    let plain = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !KEYWORDS.iter().copied().flatten().any(|k| k.eq_ignore_ascii_case(name));
+    match plain {
+        true => name.to_string(),
+        false => format!("\"{}\"", name.replace('"', "\"\"")),
+    }
+}
+
+// Names the Rust API accepts: anything SQL can quote, which leaves out empty
+// names, control characters and spaces at either end.
+pub(crate) fn check(kind: &str, name: &str) -> Result<(), String> {
+    if name.is_empty() || name.trim() != name || name.contains(char::is_control) {
+        return Err(format!("Invalid {} name '{}'", kind, name.escape_debug()));
+    }
+    Ok(())
+}
+
+// Splits SQL at whitespace, except inside a double-quoted name.
+#[cfg(feature = "sql")]
+pub(crate) fn split(sql: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut quoted = false;
+    for (i, c) in sql.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if let Some(start) = start.take() {
+                    tokens.push(&sql[start..i]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(i);
+    }
+    tokens.extend(start.map(|start| &sql[start..]));
+    tokens
+}
+
+// The tokens of a statement, without the `;` that may end it.
+#[cfg(feature = "sql")]
+pub(crate) fn split_statement(sql: &str) -> Vec<&str> {
+    let mut tokens = split(sql);
+    while let Some(last) = tokens.last_mut() {
+        *last = last.trim_end_matches(';');
+        if !last.is_empty() {
+            break;
+        }
+        tokens.pop();
+    }
+    tokens
+}
+
+// The name a token of SQL stands for: the text between double quotes, with
+// `""` for a quote, or the token as it is.
+#[cfg(feature = "sql")]
+pub(crate) fn unquote(token: &str) -> String {
+    match token.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+        Some(inner) => inner.replace("\"\"", "\""),
+        None => token.to_string(),
+    }
+}
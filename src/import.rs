@@ -0,0 +1,124 @@
+use std::error::Error;
+
+use crate::prelude::*;
+use crate::{generated, ColumnType, Database, Row, VirtualTable};
+
+/// How [`Database::import`] maps a source's rows onto a table, so files
+/// whose columns don't line up with it load as they are. Columns are
+/// renamed first; defaults and conversions name them as renamed.
+///
+/// ```
+/// use potatodb::{ColumnType, ImportMapping};
+///
+/// let mapping = ImportMapping::new()
+///     .rename("Unit Price", "price")
+///     .convert("price", ColumnType::Real)
+///     .default_value("currency", "EUR")
+///     .skip_invalid();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ImportMapping {
+    renames: HashMap<String, String>,
+    conversions: HashMap<String, ColumnType>,
+    defaults: HashMap<String, String>,
+    skip_invalid: bool,
+}
+
+impl ImportMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the source's column `from` as `to`.
+    pub fn rename(mut self, from: &str, to: &str) -> Self {
+        self.renames.insert(from.to_string(), to.to_string());
+        self
+    }
+
+    /// Converts a column's values as [`Database::change_column_type`] does,
+    /// so ` 3.0` loads as `3` into an `Integer` column.
+    pub fn convert(mut self, column: &str, to: ColumnType) -> Self {
+        self.conversions.insert(column.to_string(), to);
+        self
+    }
+
+    /// The value of a column for rows without one. Defaults are converted
+    /// like the source's values.
+    pub fn default_value(mut self, column: &str, value: &str) -> Self {
+        self.defaults.insert(column.to_string(), value.to_string());
+        self
+    }
+
+    /// Leaves out rows that fail to convert or that the table rejects,
+    /// listing them in the [`ImportReport`], instead of loading nothing.
+    pub fn skip_invalid(mut self) -> Self {
+        self.skip_invalid = true;
+        self
+    }
+
+    // A source row with its columns renamed, defaults filled in and values
+    // converted.
+    fn map(&self, row: Row) -> Result<Row, String> {
+        let mut data: Row = row.into_iter()
+            .map(|(column, value)| (self.renames.get(&column).cloned().unwrap_or(column), value))
+            .collect();
+        for (column, value) in &self.defaults {
+            if !data.contains_key(column) {
+                data.insert(column.clone(), value.clone());
+            }
+        }
+        for (column, to) in &self.conversions {
+            if let Some(value) = data.get(column) {
+                let converted = to.convert(value).ok_or_else(|| format!("Cannot convert '{}' in column '{}' to {:?}", value, column, to))?;
+                data.insert(column.clone(), converted);
+            }
+        }
+        Ok(data)
+    }
+}
+
+/// What [`Database::import`] loaded and left out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportReport {
+    loaded: usize,
+    rejected: Vec<(usize, String)>,
+}
+
+impl ImportReport {
+    pub fn loaded(&self) -> usize {
+        self.loaded
+    }
+
+    /// The position in the source, counting from 1, and the error of each
+    /// row left out by [`ImportMapping::skip_invalid`].
+    pub fn rejected(&self) -> &[(usize, String)] {
+        &self.rejected
+    }
+}
+
+impl Database {
+    /// Loads the rows of `source`, such as a [`CsvSource`](crate::CsvSource)
+    /// or `JsonSource`, into a table through `mapping`. Rows are checked as
+    /// [`Database::copy_in`] checks them and added in one batch; a bad row
+    /// loads nothing unless the mapping skips invalid rows.
+    pub fn import(&mut self, table_name: &str, source: &dyn VirtualTable, mapping: &ImportMapping) -> Result<ImportReport, Box<dyn Error>> {
+        let table = self.tables.get(table_name).ok_or_else(|| format!("Table '{}' not found", table_name))?;
+        let mut rows = Vec::new();
+        let mut rejected = Vec::new();
+        for (position, row) in (1..).zip(source.scan()?) {
+            let prepared = mapping.map(row).and_then(|mut data| {
+                let columns: Vec<String> = data.keys().cloned().collect();
+                generated::check_writable(&table.generated, &columns)?;
+                self.prepare_row(table, &mut data)?;
+                Ok(data)
+            });
+            match prepared {
+                Ok(data) => rows.push(data),
+                Err(e) if mapping.skip_invalid => rejected.push((position, e)),
+                Err(e) => return Err(format!("Row {}: {}", position, e).into()),
+            }
+        }
+        let loaded = self.load_rows(table_name, rows)?;
+        Ok(ImportReport { loaded, rejected })
+    }
+}
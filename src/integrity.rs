@@ -0,0 +1,99 @@
+use crate::prelude::*;
+use crate::{Database, Table};
+#[cfg(feature = "sql")]
+use crate::{Record, Row};
+
+/// Something [`Database::check_integrity`] found wrong.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IntegrityProblem {
+    table: Option<String>,
+    record: Option<u64>,
+    description: String,
+}
+
+impl IntegrityProblem {
+    fn new(table: &Table, record: Option<u64>, description: String) -> Self {
+        IntegrityProblem { table: Some(table.name.clone()), record, description }
+    }
+
+    /// The table the problem is in; `None` for problems with the file itself.
+    pub fn table(&self) -> Option<&str> {
+        self.table.as_deref()
+    }
+
+    pub fn record(&self) -> Option<u64> {
+        self.record
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl Table {
+    fn problems(&self) -> Vec<IntegrityProblem> {
+        let mut problems = Vec::new();
+        let mut seen = HashSet::new();
+        for (position, record) in self.records.iter().enumerate() {
+            if !seen.insert(record.id) {
+                problems.push(IntegrityProblem::new(self, Some(record.id), format!("duplicate record id at position {}", position)));
+            } else if !self.index.contains_key(&record.id) {
+                problems.push(IntegrityProblem::new(self, Some(record.id), "record is missing from the index".to_string()));
+            }
+            if let Some(Err(e)) = self.proto.as_ref().map(|p| p.validate(&record.data)) {
+                problems.push(IntegrityProblem::new(self, Some(record.id), e));
+            }
+        }
+        for (&id, &position) in &self.index {
+            if self.records.get(position).map(|r| r.id) != Some(id) {
+                problems.push(IntegrityProblem::new(self, Some(id), format!("index points at position {} which holds another record", position)));
+            }
+        }
+        if let Some(partitions) = &self.partitions {
+            problems.extend(partitions.problems(&self.records).into_iter()
+                .map(|(id, description)| IntegrityProblem::new(self, id, description)));
+        }
+        problems
+    }
+}
+
+impl Database {
+    /// Checks that every table's index matches its records, record ids are
+    /// unique, proto tables hold values their message allows and partitions
+    /// list the right records. An empty report means no problems were found.
+    pub fn check_integrity(&self) -> Vec<IntegrityProblem> {
+        let mut tables: Vec<&Table> = self.tables.values().collect();
+        tables.sort_by(|a, b| a.name.cmp(&b.name));
+        tables.into_iter().flat_map(Table::problems).collect()
+    }
+
+    /// Checks a saved database: its checksum, then the loaded data as in
+    /// [`check_integrity`](Self::check_integrity).
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub fn check_file_integrity(filename: &str) -> Result<Vec<IntegrityProblem>, Box<dyn std::error::Error>> {
+        let bytes = std::fs::read(filename)?;
+        if !crate::format::checksum_matches(&bytes)? {
+            let description = "file checksum does not match its contents".to_string();
+            return Ok(vec![IntegrityProblem { table: None, record: None, description }]);
+        }
+        Ok(Database::from_bytes(&bytes)?.check_integrity())
+    }
+
+    // PRAGMA integrity_check: one row per problem, or a single `ok` row.
+    #[cfg(feature = "sql")]
+    pub(crate) fn pragma_integrity_check(&self) -> Vec<Record> {
+        let problems = self.check_integrity();
+        if problems.is_empty() {
+            let data = [("integrity_check".to_string(), "ok".to_string())].into();
+            return vec![Record { id: 1, data }];
+        }
+        problems.into_iter().zip(1..)
+            .map(|(problem, id)| {
+                let mut data: Row = [("integrity_check".to_string(), problem.description)].into();
+                data.extend(problem.table.map(|t| ("table".to_string(), t)));
+                data.extend(problem.record.map(|r| ("record".to_string(), r.to_string())));
+                Record { id, data }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,244 @@
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::cell::RefCell;
+
+use crate::clock::Timer;
+use crate::prelude::*;
+use crate::settings::Settings;
+use crate::Database;
+#[cfg(feature = "sql")]
+use crate::{stats::record_bytes, Record};
+
+// Deadlines are read every this many rows, as reading the clock costs more
+// than reading the cancel flag.
+const CLOCK_EVERY: u32 = 256;
+
+/// Cancels SQL statements of the database it came from, from any thread.
+/// Get one with [`Database::query_handle`].
+#[derive(Clone, Default)]
+pub struct QueryHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl QueryHandle {
+    /// Stops the statement running when this is called, which then fails
+    /// with "Query cancelled". Does nothing if no statement is running.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Caps on the resources of each SQL statement, for running SQL that
+/// isn't trusted. A statement over a cap fails with an error naming it.
+/// `None` leaves a resource uncapped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueryLimits {
+    /// Rows a SELECT may return.
+    pub max_result_rows: Option<usize>,
+    /// Rows a statement may read while looking for the ones it wants.
+    pub max_scanned_rows: Option<usize>,
+    /// Estimated bytes of the rows a SELECT holds before returning them.
+    pub max_memory_bytes: Option<usize>,
+}
+
+// The statement limits and cancel flag of a database. Clones get their own
+// flag, so cancelling one database leaves its copies running.
+#[derive(Default)]
+pub(crate) struct Limits {
+    timeout: Option<Duration>,
+    query: QueryLimits,
+    handle: QueryHandle,
+}
+
+impl Limits {
+    pub(crate) fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+}
+
+impl Clone for Limits {
+    fn clone(&self) -> Self {
+        Limits { timeout: self.timeout, query: self.query, handle: QueryHandle::default() }
+    }
+}
+
+struct Running {
+    timer: Timer,
+    timeout: Option<Duration>,
+    limits: QueryLimits,
+    cancelled: Arc<AtomicBool>,
+    scanned: usize,
+    #[cfg(feature = "sql")]
+    kept: usize,
+    #[cfg(feature = "sql")]
+    held: usize,
+    // whether a scan looked rows up rather than reading every one
+    #[cfg(feature = "sql")]
+    indexed: bool,
+    stopped: Option<String>,
+    #[cfg(feature = "sql")]
+    settings: Settings,
+}
+
+impl Running {
+    fn stop(&mut self, reason: String) -> bool {
+        self.stopped = Some(reason);
+        false
+    }
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    // the statement running on this thread
+    static CURRENT: RefCell<Option<Running>> = const { RefCell::new(None) };
+}
+
+// Without threads of its own, a no_std build runs one statement at a time.
+#[cfg(not(feature = "std"))]
+static CURRENT: Mutex<Option<Running>> = Mutex::new(None);
+
+#[cfg(feature = "std")]
+fn with_current<R>(f: impl FnOnce(&mut Option<Running>) -> R) -> R {
+    CURRENT.with(|current| f(&mut current.borrow_mut()))
+}
+
+#[cfg(not(feature = "std"))]
+fn with_current<R>(f: impl FnOnce(&mut Option<Running>) -> R) -> R {
+    f(&mut lock(&CURRENT))
+}
+
+/// Restores the statement that was running on the thread, if any, when
+/// dropped.
+pub(crate) struct Statement(Option<Running>);
+
+impl Drop for Statement {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        with_current(|current| *current = previous);
+    }
+}
+
+// Starts the limits of a statement on this thread, with `query` in place
+// of the database's own if given, under the database's settings.
+#[cfg_attr(not(feature = "sql"), allow(unused_variables))]
+pub(crate) fn begin(limits: &Limits, settings: Settings, query: Option<QueryLimits>) -> Statement {
+    limits.handle.cancelled.store(false, Ordering::SeqCst);
+    let running = Running {
+        timer: Timer::start(),
+        timeout: limits.timeout,
+        limits: query.unwrap_or(limits.query),
+        cancelled: limits.handle.cancelled.clone(),
+        scanned: 0,
+        #[cfg(feature = "sql")]
+        kept: 0,
+        #[cfg(feature = "sql")]
+        held: 0,
+        #[cfg(feature = "sql")]
+        indexed: false,
+        stopped: None,
+        #[cfg(feature = "sql")]
+        settings,
+    };
+    Statement(with_current(|current| current.replace(running)))
+}
+
+// Whether the running statement may go on to its next row. Scans stop
+// taking rows once this is false, then report why with `check`.
+pub(crate) fn running() -> bool {
+    with_current(|current| {
+        let Some(running) = current.as_mut() else {
+            return true;
+        };
+        if running.stopped.is_some() {
+            return false;
+        }
+        if running.cancelled.load(Ordering::Relaxed) {
+            return running.stop("Query cancelled".to_string());
+        }
+        running.scanned += 1;
+        if let Some(max) = running.limits.max_scanned_rows.filter(|&max| running.scanned > max) {
+            return running.stop(format!("Query exceeded the limit of {} scanned rows", max));
+        }
+        if let Some(timeout) = running.timeout.filter(|_| running.scanned % CLOCK_EVERY as usize == 0) {
+            if running.timer.elapsed() > timeout {
+                return running.stop(format!("Query timed out after {:?}", timeout));
+            }
+        }
+        true
+    })
+}
+
+// Whether the running statement compares text by case, as it does outside
+// statements.
+#[cfg(feature = "sql")]
+pub(crate) fn case_sensitive() -> bool {
+    with_current(|current| current.as_ref().map_or(true, |r| r.settings.case_sensitive))
+}
+
+// Counts a row a SELECT keeps for its result against the limits.
+#[cfg(feature = "sql")]
+pub(crate) fn keep(record: &Record) {
+    with_current(|current| {
+        let Some(running) = current.as_mut().filter(|r| r.stopped.is_none()) else {
+            return;
+        };
+        running.kept += 1;
+        running.held += record_bytes(record);
+        if let Some(max) = running.limits.max_result_rows.filter(|&max| running.kept > max) {
+            running.stop(format!("Query exceeded the limit of {} result rows", max));
+        } else if let Some(max) = running.limits.max_memory_bytes.filter(|&max| running.held > max) {
+            running.stop(format!("Query exceeded the limit of {} bytes of memory", max));
+        }
+    })
+}
+
+// Notes that a scan of the running statement skipped rows by an index or
+// by partition.
+#[cfg(feature = "sql")]
+pub(crate) fn used_index() {
+    with_current(|current| {
+        if let Some(running) = current.as_mut() {
+            running.indexed = true;
+        }
+    })
+}
+
+// The rows the running statement has read so far, and whether it used an
+// index to skip others.
+#[cfg(feature = "sql")]
+pub(crate) fn scanned() -> (usize, bool) {
+    with_current(|current| current.as_ref().map_or((0, false), |r| (r.scanned, r.indexed)))
+}
+
+// Fails if the running statement was cancelled or ran out of time.
+pub(crate) fn check() -> Result<(), String> {
+    with_current(|current| match current.as_ref().and_then(|r| r.stopped.clone()) {
+        Some(reason) => Err(reason),
+        None => Ok(()),
+    })
+}
+
+impl Database {
+    /// Stops any SQL statement still scanning rows after `timeout`, which
+    /// then fails with "Query timed out". `None` lets statements run as
+    /// long as they need, the default. Writes are only stopped while they
+    /// look for their rows, before changing any.
+    pub fn set_statement_timeout(&mut self, timeout: Option<Duration>) {
+        self.limits.timeout = timeout;
+    }
+
+    /// Caps the resources of every later SQL statement; see
+    /// [`query_sql_with_limits`](Self::query_sql_with_limits) for one
+    /// statement.
+    pub fn set_query_limits(&mut self, limits: QueryLimits) {
+        self.limits.query = limits;
+    }
+
+    /// A handle for cancelling this database's running SQL statement from
+    /// another thread.
+    pub fn query_handle(&self) -> QueryHandle {
+        self.limits.handle.clone()
+    }
+}
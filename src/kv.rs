@@ -0,0 +1,87 @@
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Database;
+use crate::prelude::*;
+
+// namespace -> key -> value, saved with the tables; namespaces are shared
+// with copies of the database until written to
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub(crate) struct Store {
+    namespaces: BTreeMap<String, Arc<Entries>>,
+}
+
+type Entries = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// A namespace of byte keys and values kept next to the tables, from
+/// [`Database::kv`]. Keys and values can be anything that is bytes, such as
+/// `&str`. Writes are saved with the database but are not in the change
+/// log, audit log or history.
+pub struct KvNamespace<'a> {
+    store: &'a mut Store,
+    name: String,
+}
+
+impl KvNamespace<'_> {
+    fn entries(&self) -> Option<&Entries> {
+        self.store.namespaces.get(&self.name).map(|entries| &**entries)
+    }
+
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&[u8]> {
+        self.entries()?.get(key.as_ref()).map(Vec::as_slice)
+    }
+
+    /// The value of `key` as text, if it is set and valid UTF-8.
+    pub fn get_str(&self, key: impl AsRef<[u8]>) -> Option<&str> {
+        core::str::from_utf8(self.get(key)?).ok()
+    }
+
+    /// Sets `key`, returning its previous value.
+    pub fn set(&mut self, key: impl AsRef<[u8]>, value: impl AsRef<[u8]>) -> Option<Vec<u8>> {
+        let entries = Arc::make_mut(self.store.namespaces.entry(self.name.clone()).or_default());
+        entries.insert(key.as_ref().to_vec(), value.as_ref().to_vec())
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn delete(&mut self, key: impl AsRef<[u8]>) -> Option<Vec<u8>> {
+        let entries = Arc::make_mut(self.store.namespaces.get_mut(&self.name)?);
+        let value = entries.remove(key.as_ref());
+        if entries.is_empty() {
+            self.store.namespaces.remove(&self.name);
+        }
+        value
+    }
+
+    /// The entries whose keys start with `prefix`, in key order.
+    pub fn scan_prefix(&self, prefix: impl AsRef<[u8]>) -> Vec<(&[u8], &[u8])> {
+        let prefix = prefix.as_ref();
+        self.entries().into_iter()
+            .flat_map(|entries| entries.range(prefix.to_vec()..))
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, value)| (key.as_slice(), value.as_slice()))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries().map_or(0, BTreeMap::len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Database {
+    /// The key-value namespace `name`, for config-style data that doesn't
+    /// need a table. Namespaces exist once a key is set in them.
+    pub fn kv(&mut self, name: &str) -> KvNamespace<'_> {
+        KvNamespace { store: &mut self.kv, name: name.to_string() }
+    }
+
+    /// The names of the key-value namespaces holding keys.
+    pub fn kv_namespaces(&self) -> Vec<&str> {
+        self.kv.namespaces.keys().map(String::as_str).collect()
+    }
+}
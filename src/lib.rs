@@ -1,31 +1,330 @@
-use std::collections::HashMap;
-use std::fs::File; 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "sql")]
+use alloc::borrow::Cow;
+use alloc::collections::{BTreeMap, BTreeSet};
+#[cfg(any(feature = "std", feature = "sql"))]
+use alloc::sync::Arc;
+#[cfg(feature = "sql")]
+use core::cmp::Ordering;
+#[cfg(feature = "sql")]
+use core::ops::ControlFlow;
+#[cfg(feature = "sql")]
+use core::time::Duration;
 use serde::{Serialize, Deserialize};
-use bincode::{serialize_into, deserialize_from};
+
+#[cfg(feature = "sql")]
+use arena::ArenaVec;
+#[cfg(feature = "sql")]
+use clock::Timer;
+use prelude::*;
+use row::SharedNames;
+
+mod access;
+mod aggregate;
+mod alter;
+mod arena;
+mod audit;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod background_save;
+#[cfg(feature = "bench")]
+pub mod bench;
+mod cache;
+mod capped;
+#[cfg(feature = "sql")]
+mod catalog;
+mod changes;
+mod clock;
+#[cfg(feature = "std")]
+mod codec;
+mod columnar;
+mod compare;
+#[cfg(feature = "sql")]
+mod cond;
+#[cfg(feature = "std")]
+mod copy;
+mod counter;
+#[cfg(feature = "crdt")]
+pub mod crdt;
+mod cursor;
+#[cfg(feature = "std")]
+mod delta;
+mod dictionary;
+#[cfg(feature = "sql")]
+mod dry_run;
+mod encryption;
+mod enums;
+mod expiry;
+#[cfg(feature = "sql")]
+mod explain;
+#[cfg(feature = "fixtures")]
+mod fixture;
+#[cfg(feature = "sql")]
+mod group_by;
+#[cfg(all(feature = "std", feature = "sql"))]
+mod foreign;
+#[cfg(feature = "std")]
+mod format;
+mod generated;
+mod graph;
+#[cfg(feature = "sql")]
+mod hints;
+#[cfg(feature = "sql")]
+mod histogram;
+mod history;
+mod hyperloglog;
+mod identifier;
+#[cfg(all(feature = "std", feature = "sql"))]
+mod import;
+#[cfg(feature = "sql")]
+mod observer;
+mod integrity;
+mod interrupt;
+mod kv;
+#[cfg(feature = "sql")]
+mod masking;
+mod migration;
+#[cfg(feature = "sql")]
+mod order;
+#[cfg(feature = "sql")]
+mod page;
+mod prelude;
+mod proto;
+#[cfg(feature = "sql")]
+mod pseudo;
+#[cfg(feature = "sql")]
+mod querylog;
+mod queue;
+mod relation;
+#[cfg(feature = "raft")]
+pub mod raft;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod replication;
+mod row;
+mod sample;
+mod schema;
+mod sequence;
+mod settings;
+mod stats;
+#[cfg(feature = "std")]
+mod storage;
+mod stored;
+#[cfg(feature = "sql")]
+mod streaming;
+#[cfg(feature = "sql")]
+mod table_function;
+mod tables;
+#[cfg(feature = "avro")]
+mod avro;
+#[cfg(feature = "mongo")]
+mod mongo;
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+mod opfs;
+mod partition;
+#[cfg(feature = "sql")]
+mod policy;
+#[cfg(feature = "sql")]
+mod precheck;
+#[cfg(all(feature = "std", feature = "sql", not(target_arch = "wasm32")))]
+mod remote;
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+mod wasm;
+mod telemetry;
+mod temporary;
+mod timeseries;
+mod timestamps;
+mod value;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod vacuum;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "sql")]
+mod vectorized;
+#[cfg(feature = "sql")]
+mod virtual_table;
+#[cfg(feature = "xlsx")]
+mod xlsx;
+
+pub use access::{Privilege, ALL_TABLES};
+pub use aggregate::{Pipeline, SortOrder};
+pub use alter::{ColumnType, ConversionReport};
+pub use audit::{AuditEntry, AuditSink, AUDIT_TABLE};
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub use background_save::SaveHandle;
+#[cfg(feature = "sql")]
+pub use catalog::{COLUMNS_CATALOG, INDEXES_CATALOG, TABLES_CATALOG};
+pub use changes::{ChangeEvent, ChangeKind};
+#[cfg(feature = "cbor")]
+pub use codec::CborCodec;
+#[cfg(feature = "msgpack")]
+pub use codec::MessagePackCodec;
+#[cfg(feature = "std")]
+pub use codec::{BincodeCodec, Codec};
+pub use compare::CompareAs;
+#[cfg(feature = "sql")]
+pub use cond::Cond;
+#[cfg(feature = "std")]
+pub use copy::CopyFormat;
+pub use cursor::TableCursor;
+#[cfg(feature = "sql")]
+pub use dry_run::DryRun;
+#[cfg(feature = "std")]
+pub use erased_serde;
+#[cfg(all(feature = "std", feature = "sql"))]
+pub use foreign::CsvSource;
+#[cfg(feature = "json")]
+pub use foreign::JsonSource;
+pub use encryption::ColumnKey;
+pub use graph::Traversal;
+#[cfg(feature = "sql")]
+pub use histogram::Histogram;
+pub use identifier::quote_identifier;
+#[cfg(all(feature = "std", feature = "sql"))]
+pub use import::{ImportMapping, ImportReport};
+pub use integrity::IntegrityProblem;
+pub use interrupt::{QueryHandle, QueryLimits};
+pub use kv::KvNamespace;
+#[cfg(feature = "sql")]
+pub use masking::Mask;
+pub use migration::{Migration, MigrationStep, SCHEMA_VERSION_TABLE};
+#[cfg(feature = "sql")]
+pub use observer::QueryObserver;
+pub use partition::PartitionScheme;
+#[cfg(feature = "sql")]
+pub use precheck::{EMPTY_STATEMENT, UNEXPECTED_END};
+pub use proto::{ProtoMessage, ProtoType};
+#[cfg(feature = "sql")]
+pub use querylog::{ExecutionStats, QueryLogEntry, SLOW_QUERIES_TABLE};
+pub use relation::Model;
+#[cfg(all(feature = "std", feature = "sql", not(target_arch = "wasm32")))]
+pub use remote::{QueryServer, RemoteClient, IDLE_IN_TRANSACTION_TIMEOUT};
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub use replication::{Follower, ReplicationServer};
+pub use row::Row;
+pub use stats::TableStats;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub use storage::FileStorage;
+#[cfg(feature = "std")]
+pub use storage::{FaultyStorage, MemoryStorage, StorageBackend};
+#[cfg(feature = "sql")]
+pub use pseudo::ID;
+pub use timestamps::{CREATED_AT, UPDATED_AT};
+pub use value::Value;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub use vacuum::{AutoVacuum, Maintenance, VacuumReport};
+#[cfg(feature = "sql")]
+pub use virtual_table::{Predicate, VirtualTable};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Record {
     id: u64,
-    data: HashMap<String, String>,
+    data: Row,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+// Serialized through stored::StoredTable so protobuf tables are kept as bytes.
+#[derive(Clone)]
 pub struct Table {
     name: String,
     records: Vec<Record>,
     index: HashMap<u64, usize>,
+    proto: Option<ProtoMessage>,
+    partitions: Option<partition::Partitions>,
+    history: Option<history::History>,
+    // columns whose values are stored encrypted
+    encrypted: BTreeSet<String>,
+    generated: Vec<generated::GeneratedColumn>,
+    // column -> the values it allows
+    enums: BTreeMap<String, Vec<String>>,
+    series: Option<timeseries::TimeSeries>,
+    queue: Option<queue::Queue>,
+    // the oldest rows are evicted past this many
+    max_rows: Option<usize>,
+    // dropped when the session ends and never saved
+    temporary: bool,
+    columnar: Option<columnar::Columnar>,
+    // columns that don't compare as plain text
+    compare: BTreeMap<String, CompareAs>,
+    expiry: Option<expiry::Expiry>,
+    // whether writes set created_at and updated_at
+    timestamps: bool,
+    // columns whose values are stored as a type
+    types: BTreeMap<String, ColumnType>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Database {
-    tables: HashMap<String, Table>,
+    #[serde(serialize_with = "temporary::persistent_tables")]
+    tables: tables::Tables,
+    kv: kv::Store,
+    schemas: BTreeSet<String>,
+    #[serde(serialize_with = "changes::persistent_seq", deserialize_with = "changes::restored_log")]
+    changes: changes::ChangeLog,
+    #[cfg(feature = "sql")]
+    #[serde(skip)]
+    query_log: querylog::QueryLog,
+    #[serde(skip)]
+    audit: audit::AuditLog,
+    #[cfg(feature = "sql")]
+    #[serde(skip)]
+    observers: Vec<Arc<dyn QueryObserver>>,
+    #[cfg(feature = "sql")]
+    #[serde(skip)]
+    virtual_tables: HashMap<String, Arc<dyn VirtualTable>>,
+    #[serde(skip)]
+    access: access::AccessControl,
+    #[cfg(feature = "sql")]
+    #[serde(skip)]
+    policies: policy::Policies,
+    #[serde(skip)]
+    keys: encryption::Keys,
+    #[cfg(feature = "sql")]
+    #[serde(skip)]
+    masks: masking::Masks,
+    #[serde(skip)]
+    relations: relation::Relations,
+    #[serde(skip)]
+    caches: cache::Caches,
+    #[serde(skip)]
+    limits: interrupt::Limits,
+    #[serde(skip)]
+    settings: settings::Settings,
+    #[serde(skip)]
+    sequences: sequence::Sequences,
+    #[cfg(feature = "sql")]
+    #[serde(skip)]
+    histograms: histogram::Histograms,
+    #[cfg(feature = "sql")]
+    #[serde(skip)]
+    collations: order::Collations,
+    #[serde(skip)]
+    edge_tables: graph::EdgeTables,
+    // the tables reset_to_fixture restores
+    #[cfg(feature = "fixtures")]
+    #[serde(skip)]
+    fixture: Option<tables::Tables>,
+    // None saves with bincode
+    #[cfg(feature = "std")]
+    #[serde(skip)]
+    codec: Option<Arc<dyn codec::Codec>>,
 }
 
+#[cfg(feature = "sql")]
 enum SqlStatement {
     Select {
         table: String,
         columns: Vec<String>,
         condition: Option<Condition>,
+        // milliseconds since the Unix epoch, for AS OF
+        as_of: Option<u64>,
+        sample: Option<sample::Sample>,
+        hints: hints::Hints,
+        // empty leaves the rows in scan order
+        order_by: Vec<order::OrderKey>,
+        page: page::Page,
+        // None selects rows rather than groups of them
+        group_by: Option<group_by::GroupBy>,
     },
     Insert {
         table: String,
@@ -38,53 +337,336 @@ enum SqlStatement {
         value: String,
         condition: Option<Condition>,
     },
+    // UPDATE t SET column = column + delta
+    Increment {
+        table: String,
+        column: String,
+        delta: f64,
+        condition: Option<Condition>,
+    },
     Delete {
         table: String,
         condition: Option<Condition>,
     },
+    Explain {
+        analyze: bool,
+        statement: Box<SqlStatement>,
+    },
+    CreateTable {
+        table: String,
+        generated: Vec<generated::GeneratedColumn>,
+        enums: BTreeMap<String, Vec<String>>,
+        types: BTreeMap<String, ColumnType>,
+        temporary: bool,
+        max_rows: Option<usize>,
+    },
+    CreateSchema(String),
+    DropSchema(String),
+    // traversal of an edge table, limited to the edges matching `condition`
+    Graph {
+        table: String,
+        query: graph::GraphQuery,
+        condition: Option<Condition>,
+    },
+    Pragma(String),
+    // `SET name = value`, or `PRAGMA name = value`
+    Set(String, String),
+    Analyze(String),
 }
 
-#[derive(Clone)]
+#[cfg(feature = "sql")]
+impl SqlStatement {
+    fn kind(&self) -> &'static str {
+        match self {
+            SqlStatement::Select { .. } => "select",
+            SqlStatement::Insert { .. } => "insert",
+            SqlStatement::Update { .. } | SqlStatement::Increment { .. } => "update",
+            SqlStatement::Delete { .. } => "delete",
+            SqlStatement::Explain { .. } => "explain",
+            SqlStatement::CreateTable { .. } | SqlStatement::CreateSchema(_) => "create",
+            SqlStatement::DropSchema(_) => "drop",
+            SqlStatement::Graph { .. } => "graph",
+            SqlStatement::Pragma(_) => "pragma",
+            SqlStatement::Set(..) => "set",
+            SqlStatement::Analyze(_) => "analyze",
+        }
+    }
+
+    fn table(&self) -> &str {
+        match self {
+            SqlStatement::Select { table, .. }
+            | SqlStatement::Insert { table, .. }
+            | SqlStatement::Update { table, .. }
+            | SqlStatement::Increment { table, .. }
+            | SqlStatement::Delete { table, .. }
+            | SqlStatement::CreateTable { table, .. }
+            | SqlStatement::Graph { table, .. } => table,
+            SqlStatement::Explain { statement, .. } => statement.table(),
+            SqlStatement::Analyze(table) => table,
+            SqlStatement::CreateSchema(_) | SqlStatement::DropSchema(_) | SqlStatement::Pragma(_) | SqlStatement::Set(..) => "",
+        }
+    }
+}
+
+#[cfg(feature = "sql")]
+#[derive(Clone, Debug)]
 enum Condition {
     Equals(String, String),
     NotEquals(String, String),
     GreaterThan(String, String),
     LessThan(String, String),
+    // column, low, high; both ends included
+    Between(String, String, String),
+    // column, pattern; `%` matches any run of characters and `_` any one
+    Like(String, String),
+    // rows without the column, and rows with it
+    IsNull(String),
+    IsNotNull(String),
     And(Box<Condition>, Box<Condition>),
     Or(Box<Condition>, Box<Condition>),
 }
 
+#[cfg(feature = "sql")]
+impl Condition {
+    // Compares values as `Value`s, so numbers compare as numbers and values
+    // of different kinds match nothing but `!=`, unless `typed` is off;
+    // text compares as text ignoring case unless `case_sensitive`. See
+    // `vectorized` for typed numeric columns. Columns are read as
+    // `pseudo::column` reads them, so `id` is the record's.
+    fn matches(&self, record: &Record, case_sensitive: bool, typed: bool) -> bool {
+        fn folded(v: &str, case_sensitive: bool) -> Cow<'_, str> {
+            match case_sensitive {
+                true => Cow::Borrowed(v),
+                false => Cow::Owned(v.to_lowercase()),
+            }
+        }
+        let text = |a: &str, b: &str| folded(a, case_sensitive).cmp(&folded(b, case_sensitive));
+        let order = |col: &String, val: &str| {
+            let v = pseudo::column(record, col)?;
+            match typed {
+                true => value::order(&v, val, text),
+                false => Some(text(&v, val)),
+            }
+        };
+        match self {
+            Condition::Equals(col, val) => order(col, val) == Some(Ordering::Equal),
+            Condition::NotEquals(col, val) => order(col, val) != Some(Ordering::Equal),
+            Condition::GreaterThan(col, val) => order(col, val) == Some(Ordering::Greater),
+            Condition::LessThan(col, val) => order(col, val) == Some(Ordering::Less),
+            Condition::Between(col, low, high) => order(col, low).is_some_and(Ordering::is_ge) && order(col, high).is_some_and(Ordering::is_le),
+            Condition::Like(col, pattern) => pseudo::column(record, col).is_some_and(|v| cond::like(&folded(&v, case_sensitive), &folded(pattern, case_sensitive))),
+            Condition::IsNull(col) => pseudo::column(record, col).is_none(),
+            Condition::IsNotNull(col) => pseudo::column(record, col).is_some(),
+            Condition::And(left, right) => left.matches(record, case_sensitive, typed) && right.matches(record, case_sensitive, typed),
+            Condition::Or(left, right) => left.matches(record, case_sensitive, typed) || right.matches(record, case_sensitive, typed),
+        }
+    }
+}
+
+impl Record {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn data(&self) -> &Row {
+        &self.data
+    }
+
+    /// A column read as a [`Value`]; [`Value::Null`] if the record doesn't
+    /// have it.
+    pub fn value(&self, column: &str) -> Value {
+        self.data.get(column).map_or(Value::Null, |v| Value::parse(v))
+    }
+}
+
+// `SELECT COUNT(*)` returns how many rows match rather than the rows.
+#[cfg(feature = "sql")]
+fn is_count(columns: &[String]) -> bool {
+    matches!(columns, [column] if column.eq_ignore_ascii_case("COUNT(*)"))
+}
+
+// Rejoins the words of function calls split at spaces, such as
+// `COALESCE(nick,` and `name)`.
+#[cfg(feature = "sql")]
+fn join_calls(tokens: &[&str]) -> Vec<String> {
+    let mut joined: Vec<String> = Vec::new();
+    let mut depth = 0;
+    for token in tokens {
+        match joined.last_mut() {
+            Some(call) if depth > 0 => {
+                call.push(' ');
+                call.push_str(token);
+            }
+            _ => joined.push(token.to_string()),
+        }
+        depth += token.matches('(').count() as isize - token.matches(')').count() as isize;
+    }
+    joined
+}
+
+// The function calls among the columns of a SELECT.
+#[cfg(feature = "sql")]
+fn calls(columns: &[String]) -> Result<Vec<generated::GeneratedColumn>, String> {
+    columns.iter()
+        .filter(|c| c.contains('(') && !c.eq_ignore_ascii_case("COUNT(*)"))
+        .map(|c| generated::GeneratedColumn::call(c))
+        .collect()
+}
+
+// Every named column gets exactly one value, and is one the table can hold.
+#[cfg(feature = "sql")]
+fn check_insert(table: &Table, columns: &[String], values: &[String]) -> Result<(), String> {
+    let quoted = |names: &[String]| names.iter().map(|n| format!("'{}'", n)).collect::<Vec<_>>().join(", ");
+    let counted = |n: usize, noun: &str| format!("{} {}{}", n, noun, if n == 1 { "" } else { "s" });
+    match columns.len().cmp(&values.len()) {
+        Ordering::Greater => {
+            return Err(format!("INSERT into '{}' names {} but gives {}; no value for {}", table.name, counted(columns.len(), "column"), counted(values.len(), "value"), quoted(&columns[values.len()..])));
+        }
+        Ordering::Less => {
+            return Err(format!("INSERT into '{}' names {} but gives {}; no column for {}", table.name, counted(columns.len(), "column"), counted(values.len(), "value"), quoted(&values[columns.len()..])));
+        }
+        Ordering::Equal => {}
+    }
+    if let Some(column) = columns.iter().enumerate().find_map(|(i, c)| columns[..i].contains(c).then_some(c)) {
+        return Err(format!("INSERT into '{}' names column '{}' more than once", table.name, column));
+    }
+    match &table.proto {
+        Some(proto) => columns.iter().try_for_each(|c| proto.check_column(c)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(feature = "sql")]
+fn project(columns: &[String], calls: &[generated::GeneratedColumn], record: &Record) -> Record {
+    let mut data: Row = match columns[0] == "*" {
+        true if calls.is_empty() => return record.clone(),
+        true => record.data.clone(),
+        false => columns.iter().filter_map(|c| Some((c.clone(), pseudo::column(record, c)?.into_owned()))).collect(),
+    };
+    for call in calls {
+        if let Some(value) = call.value(&record.data) {
+            data.insert(call.name().to_string(), value);
+        }
+    }
+    Record { id: record.id, data }
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Database {
     pub fn new() -> Self {
         Database {
-            tables: HashMap::new(),
+            tables: tables::Tables::default(),
+            kv: kv::Store::default(),
+            schemas: BTreeSet::new(),
+            changes: changes::ChangeLog::default(),
+            #[cfg(feature = "sql")]
+            query_log: querylog::QueryLog::default(),
+            audit: audit::AuditLog::default(),
+            #[cfg(feature = "sql")]
+            observers: Vec::new(),
+            #[cfg(feature = "sql")]
+            virtual_tables: HashMap::new(),
+            access: access::AccessControl::default(),
+            #[cfg(feature = "sql")]
+            policies: policy::Policies::default(),
+            keys: encryption::Keys::default(),
+            #[cfg(feature = "sql")]
+            masks: masking::Masks::default(),
+            edge_tables: graph::EdgeTables::default(),
+            relations: relation::Relations::default(),
+            caches: cache::Caches::default(),
+            limits: interrupt::Limits::default(),
+            settings: settings::Settings::default(),
+            sequences: sequence::Sequences::default(),
+            #[cfg(feature = "sql")]
+            histograms: histogram::Histograms::default(),
+            #[cfg(feature = "sql")]
+            collations: order::Collations::default(),
+            #[cfg(feature = "fixtures")]
+            fixture: None,
+            #[cfg(feature = "std")]
+            codec: None,
         }
     }
 
     pub fn create_table(&mut self, name: String) -> Result<(), String> {
+        identifier::check("table", &name)?;
+        self.check_schema(&name)?;
+        #[cfg(feature = "sql")]
+        if self.is_virtual(&name) {
+            return Err(format!("Table '{}' already exists", name));
+        }
+        #[cfg(feature = "sql")]
+        if name.starts_with(catalog::RESERVED_PREFIX) {
+            return Err(format!("Table names starting with '{}' are reserved for the system catalog", catalog::RESERVED_PREFIX));
+        }
         if self.tables.contains_key(&name) {
-            Err(format!("Table '{}' already exists", name))
-        } else {
-            let table = Table {
-                name: name.clone(),
-                records: Vec::new(),
-                index: HashMap::new(),
-            };
-            self.tables.insert(name, table);
-            Ok(())
+            return Err(format!("Table '{}' already exists", name));
         }
+        self.tables.insert(Table {
+            name,
+            records: Vec::new(),
+            index: HashMap::new(),
+            proto: None,
+            partitions: None,
+            history: None,
+            encrypted: BTreeSet::new(),
+            generated: Vec::new(),
+            enums: BTreeMap::new(),
+            series: None,
+            queue: None,
+            max_rows: None,
+            temporary: false,
+            columnar: None,
+            compare: BTreeMap::new(),
+            expiry: None,
+            timestamps: false,
+            types: BTreeMap::new(),
+        });
+        Ok(())
     }
 
-    pub fn insert(&mut self, table_name: &str, id: u64, data: HashMap<String, String>) -> Result<(), String> {
+    pub fn insert(&mut self, table_name: &str, id: u64, data: impl Into<Row>) -> Result<(), String> {
+        let mut data = data.into();
         if let Some(table) = self.tables.get_mut(table_name) {
             if table.index.contains_key(&id) {
                 Err(format!("Record with id {} already exists in table '{}'", id, table_name))
             } else {
+                table.check_append(id)?;
+                table.stamp(None, &mut data);
+                table.convert_types(&mut data)?;
+                generated::fill(&table.generated, &mut data);
+                table.check_enums(&data)?;
+                self.keys.seal_row(table, &mut data)?;
+                if let Some(proto) = &table.proto {
+                    proto.validate(&data)?;
+                }
+                if let Some(partitions) = &mut table.partitions {
+                    partitions.place(id, &data);
+                }
+                if let Some(expiry) = &mut table.expiry {
+                    expiry.place(id, &data);
+                }
+                if let Some(newest) = table.records.last() {
+                    data.share_names(&newest.data);
+                }
                 let record = Record { id, data };
                 let index = table.records.len();
-                table.records.push(record);
+                table.records.push(record.clone());
+                table.invalidate_columns();
                 table.index.insert(id, index);
+                if let Some(history) = &mut table.history {
+                    history.record(id, Some(&record.data));
+                }
+                self.audit.record(table_name, id, ChangeKind::Insert, None, Some(&record.data));
+                self.changes.push(table_name, ChangeKind::Insert, record);
+                self.evict_over_cap(table_name);
+                self.cache_inserted(table_name, id);
                 Ok(())
             }
         } else {
@@ -108,10 +690,33 @@ impl Database {
         }
     }
 
-    pub fn update(&mut self, table_name: &str, id: u64, data: HashMap<String, String>) -> Result<(), String> {
+    pub fn update(&mut self, table_name: &str, id: u64, data: impl Into<Row>) -> Result<(), String> {
+        let mut data = data.into();
         if let Some(table) = self.tables.get_mut(table_name) {
             if let Some(&index) = table.index.get(&id) {
-                table.records[index].data = data;
+                table.stamp(Some(&table.records[index].data), &mut data);
+                table.convert_types(&mut data)?;
+                generated::fill(&table.generated, &mut data);
+                table.check_enums(&data)?;
+                self.keys.seal_row(table, &mut data)?;
+                if let Some(proto) = &table.proto {
+                    proto.validate(&data)?;
+                }
+                if let Some(partitions) = &mut table.partitions {
+                    partitions.remove(id, &table.records[index].data);
+                    partitions.place(id, &data);
+                }
+                if let Some(expiry) = &mut table.expiry {
+                    expiry.place(id, &data);
+                }
+                data.share_names(&table.records[index].data);
+                let before = core::mem::replace(&mut table.records[index].data, data);
+                table.invalidate_columns();
+                if let Some(history) = &mut table.history {
+                    history.record(id, Some(&table.records[index].data));
+                }
+                self.audit.record(table_name, id, ChangeKind::Update, Some(&before), Some(&table.records[index].data));
+                self.changes.push(table_name, ChangeKind::Update, table.records[index].clone());
                 Ok(())
             } else {
                 Err(format!("Record with id {} not found in table '{}'", id, table_name))
@@ -121,16 +726,81 @@ impl Database {
         }
     }
 
+    /// Replaces the data of many records of one table, keyed by id, as one
+    /// change: every row is checked before any is written, so if one id is
+    /// missing or one row is invalid nothing is updated. Cached column data
+    /// is rebuilt once for the batch rather than once per row.
+    pub fn update_many<R: Into<Row>>(&mut self, table_name: &str, updates: HashMap<u64, R>) -> Result<(), String> {
+        let table = self.tables.get_mut(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        let mut rows = Vec::with_capacity(updates.len());
+        for (id, data) in updates {
+            let Some(&index) = table.index.get(&id) else {
+                return Err(format!("Record with id {} not found in table '{}'", id, table_name));
+            };
+            let mut data = data.into();
+            table.stamp(Some(&table.records[index].data), &mut data);
+            table.convert_types(&mut data)?;
+            generated::fill(&table.generated, &mut data);
+            table.check_enums(&data)?;
+            self.keys.seal_row(table, &mut data)?;
+            if let Some(proto) = &table.proto {
+                proto.validate(&data)?;
+            }
+            rows.push((id, data));
+        }
+        rows.sort_unstable_by_key(|(id, _)| *id);
+        self.replace_rows(table_name, rows);
+        Ok(())
+    }
+
+    // Replaces the data of existing records with rows already checked and
+    // sealed, as updates.
+    pub(crate) fn replace_rows(&mut self, table_name: &str, rows: Vec<(u64, Row)>) {
+        let Some(table) = self.tables.get_mut(table_name) else { return };
+        // the rows usually change their columns the same way
+        let mut names = SharedNames::default();
+        for (id, mut data) in rows {
+            let Some(&index) = table.index.get(&id) else { continue };
+            names.share(&mut data);
+            if let Some(partitions) = &mut table.partitions {
+                partitions.remove(id, &table.records[index].data);
+                partitions.place(id, &data);
+            }
+            if let Some(expiry) = &mut table.expiry {
+                expiry.place(id, &data);
+            }
+            let before = core::mem::replace(&mut table.records[index].data, data);
+            if let Some(history) = &mut table.history {
+                history.record(id, Some(&table.records[index].data));
+            }
+            self.audit.record(table_name, id, ChangeKind::Update, Some(&before), Some(&table.records[index].data));
+            self.changes.push(table_name, ChangeKind::Update, table.records[index].clone());
+        }
+        table.invalidate_columns();
+    }
+
     pub fn delete(&mut self, table_name: &str, id: u64) -> Result<(), String> {
         if let Some(table) = self.tables.get_mut(table_name) {
             if let Some(index) = table.index.remove(&id) {
-                table.records.remove(index);
+                let record = table.records.remove(index);
+                table.invalidate_columns();
+                if let Some(partitions) = &mut table.partitions {
+                    partitions.remove(id, &record.data);
+                }
+                if let Some(expiry) = &mut table.expiry {
+                    expiry.remove(id);
+                }
                 // Update indices for all records after the deleted one
                 for (_, idx) in table.index.iter_mut() {
                     if *idx > index {
                         *idx -= 1;
                     }
                 }
+                if let Some(history) = &mut table.history {
+                    history.record(id, None);
+                }
+                self.audit.record(table_name, id, ChangeKind::Delete, Some(&record.data), None);
+                self.changes.push(table_name, ChangeKind::Delete, record);
                 Ok(())
             } else {
                 Err(format!("Record with id {} not found in table '{}'", id, table_name))
@@ -144,6 +814,22 @@ impl Database {
         self.tables.keys().map(AsRef::as_ref).collect()
     }
 
+    pub fn columns(&self, table_name: &str) -> Result<Vec<String>, String> {
+        if let Some(table) = self.tables.get(table_name) {
+            if let Some(proto) = &table.proto {
+                return Ok(proto.columns());
+            }
+            let mut columns: Vec<String> = table.records.iter()
+                .flat_map(|r| r.data.keys().cloned())
+                .collect();
+            columns.sort();
+            columns.dedup();
+            Ok(columns)
+        } else {
+            Err(format!("Table '{}' not found", table_name))
+        }
+    }
+
     pub fn query(&self, table_name: &str, condition: impl Fn(&Record) -> bool) -> Result<Vec<&Record>, String> {
         if let Some(table) = self.tables.get(table_name) {
             Ok(table.records.iter().filter(|r| condition(r)).collect())
@@ -152,80 +838,380 @@ impl Database {
         }
     }
 
+    // Sets `column` of each record to its checked and sealed value.
+    pub(crate) fn write_column(&mut self, table_name: &str, column: &str, writes: Vec<(u64, String)>) -> Result<Vec<Record>, String> {
+        let table = self.tables.get_mut(table_name).ok_or("Table not found")?;
+        let mut updated_records = Vec::new();
+    
+        for (id, value) in writes {
+            if let Some(&index) = table.index.get(&id) {
+                let mut data = table.records[index].data.clone();
+                data.insert(column.to_string(), value);
+                table.stamp(Some(&table.records[index].data), &mut data);
+                generated::fill(&table.generated, &mut data);
+                if let Some(partitions) = &mut table.partitions {
+                    partitions.remove(id, &table.records[index].data);
+                    partitions.place(id, &data);
+                }
+                if let Some(expiry) = &mut table.expiry {
+                    expiry.place(id, &data);
+                }
+                data.share_names(&table.records[index].data);
+                let before = core::mem::replace(&mut table.records[index].data, data);
+                table.invalidate_columns();
+                if let Some(history) = &mut table.history {
+                    history.record(id, Some(&table.records[index].data));
+                }
+                self.audit.record(table_name, id, ChangeKind::Update, Some(&before), Some(&table.records[index].data));
+                self.changes.push(table_name, ChangeKind::Update, table.records[index].clone());
+                updated_records.push(table.records[index].clone());
+            }
+        }
     
+        Ok(updated_records)
+    }
+}
+
+#[cfg(feature = "sql")]
+impl Database {
     pub fn execute_sql(&mut self, sql: &str) -> Result<Vec<Record>, String> {
-        let statement = self.parse_sql(sql)?;
+        self.execute_sql_with_stats(sql).map(|(records, _)| records)
+    }
+
+    /// Like [`execute_sql`](Self::execute_sql), also returning how long the
+    /// statement took, how many rows it read and whether it used an index.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "potatodb.query", skip(self), fields(kind, table, rows)))]
+    pub fn execute_sql_with_stats(&mut self, sql: &str) -> Result<(Vec<Record>, ExecutionStats), String> {
+        let timer = Timer::start();
+        let _statement = interrupt::begin(&self.limits, self.settings, None);
+        let (sql, statement) = self.prepare(sql);
+        let kind = statement.as_ref().map_or("invalid", SqlStatement::kind);
+        if let Ok(statement) = &statement {
+            telemetry::record("table", statement.table());
+        }
+        let result = statement.and_then(|statement| self.execute_statement(statement));
+        let stats = self.finish_query(timer, kind, &sql, &result);
+        result.map(|records| (records, stats))
+    }
+
+    fn execute_statement(&mut self, statement: SqlStatement) -> Result<Vec<Record>, String> {
+        if let SqlStatement::Insert { table, .. } | SqlStatement::Update { table, .. } | SqlStatement::Increment { table, .. } | SqlStatement::Delete { table, .. } = &statement {
+            self.check_writable(table)?;
+        }
         match statement {
-            SqlStatement::Select { table, columns, condition } => self.execute_select(&table, &columns, condition),
+            select @ SqlStatement::Select { .. } => self.execute_select(select),
             SqlStatement::Insert { table, columns, values } => self.execute_insert(&table, &columns, &values),
             SqlStatement::Update { table, column, value, condition } => self.execute_update(&table, &column, &value, condition),
+            SqlStatement::Increment { table, column, delta, condition } => self.execute_increment(&table, &column, delta, condition),
             SqlStatement::Delete { table, condition } => self.execute_delete(&table, condition),
+            SqlStatement::Explain { analyze, statement } => self.execute_explain(*statement, analyze),
+            SqlStatement::CreateTable { table, generated, enums, types, temporary, max_rows } => self.execute_create_table(table, generated, enums, types, temporary, max_rows),
+            SqlStatement::CreateSchema(name) => self.create_schema(&name).map(|()| Vec::new()),
+            SqlStatement::DropSchema(name) => self.drop_schema(&name).map(|()| Vec::new()),
+            SqlStatement::Graph { table, query, condition } => self.execute_graph(&table, &query, &condition),
+            SqlStatement::Pragma(name) => self.execute_pragma(&name),
+            SqlStatement::Set(name, value) => self.set_setting(&name, &value).map(|()| Vec::new()),
+            SqlStatement::Analyze(table) => self.execute_analyze(&table),
         }
     }
 
+    pub fn query_sql(&self, sql: &str) -> Result<Vec<Record>, String> {
+        self.query_sql_limited(sql, None).map(|(records, _)| records)
+    }
+
+    /// Runs a read-only statement under `limits` instead of the ones set
+    /// with [`set_query_limits`](Self::set_query_limits).
+    pub fn query_sql_with_limits(&self, sql: &str, limits: QueryLimits) -> Result<Vec<Record>, String> {
+        self.query_sql_limited(sql, Some(limits)).map(|(records, _)| records)
+    }
+
+    /// Like [`query_sql`](Self::query_sql), also returning how long the
+    /// statement took, how many rows it read and whether it used an index.
+    pub fn query_sql_with_stats(&self, sql: &str) -> Result<(Vec<Record>, ExecutionStats), String> {
+        self.query_sql_limited(sql, None)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "potatodb.query", skip(self, limits), fields(kind, table, rows)))]
+    fn query_sql_limited(&self, sql: &str, limits: Option<QueryLimits>) -> Result<(Vec<Record>, ExecutionStats), String> {
+        let timer = Timer::start();
+        let _statement = interrupt::begin(&self.limits, self.settings, limits);
+        let (sql, statement) = self.prepare(sql);
+        let kind = statement.as_ref().map_or("invalid", SqlStatement::kind);
+        if let Ok(statement) = &statement {
+            telemetry::record("table", statement.table());
+        }
+        let result = statement.and_then(|statement| match statement {
+            select @ SqlStatement::Select { .. } => self.execute_select(select),
+            SqlStatement::Explain { analyze, statement } => self.execute_explain(*statement, analyze),
+            SqlStatement::Graph { table, query, condition } => self.execute_graph(&table, &query, &condition),
+            SqlStatement::Pragma(name) => self.execute_pragma(&name),
+            _ => Err("Only SELECT statements can be run read-only".to_string()),
+        });
+        let stats = self.finish_query(timer, kind, &sql, &result);
+        result.map(|records| (records, stats))
+    }
+
+    fn finish_query(&self, timer: Timer, kind: &'static str, sql: &str, result: &Result<Vec<Record>, String>) -> ExecutionStats {
+        if let Ok(records) = result {
+            records.iter().for_each(|record| self.notify_row(sql, record));
+        }
+        let duration = self.finish_statement(timer, kind, sql, result.as_ref().map(Vec::len).map_err(String::as_str));
+        let (rows_scanned, index_used) = interrupt::scanned();
+        ExecutionStats { duration, rows_scanned, index_used }
+    }
+
+    // Logs and reports a statement that produced `result` rows, returning
+    // how long it took.
+    fn finish_statement(&self, timer: Timer, kind: &'static str, sql: &str, result: Result<usize, &str>) -> Duration {
+        let duration = timer.elapsed();
+        telemetry::query(kind, duration, result.is_err());
+        telemetry::record("kind", kind);
+        telemetry::record("rows", result.unwrap_or(0));
+        self.query_log.finish(duration, sql, result);
+        self.notify_executed(sql, duration, result);
+        if self.query_log.is_slow(duration) {
+            self.query_log.record_slow(sql, duration, self.describe_plan(sql));
+        }
+        duration
+    }
+
+    fn describe_plan(&self, sql: &str) -> String {
+        match self.parse_sql(sql) {
+            Ok(statement) => self.describe_statement(&statement),
+            Err(_) => "invalid statement".to_string(),
+        }
+    }
+
+    fn describe_statement(&self, statement: &SqlStatement) -> String {
+        let (table, condition) = match statement {
+            SqlStatement::Insert { table, .. } => return format!("insert into {}", table),
+            SqlStatement::Explain { statement, .. } => return self.describe_statement(statement),
+            SqlStatement::CreateTable { table, .. } => return format!("create table {}", table),
+            SqlStatement::CreateSchema(name) => return format!("create schema {}", name),
+            SqlStatement::DropSchema(name) => return format!("drop schema {}", name),
+            SqlStatement::Pragma(name) => return format!("pragma {}", name),
+            SqlStatement::Set(name, value) => return format!("set {} = {}", name, value),
+            SqlStatement::Analyze(table) => return format!("analyze {}", table),
+            SqlStatement::Select { table, condition, hints, .. } => (table, hints.scanned(condition)),
+            SqlStatement::Update { table, condition, .. }
+            | SqlStatement::Increment { table, condition, .. }
+            | SqlStatement::Delete { table, condition }
+            | SqlStatement::Graph { table, condition, .. } => (table, condition),
+        };
+        match self.readable_table(table) {
+            Ok(table) => table.describe_scan(condition),
+            Err(_) => format!("table {} not found", table),
+        }
+    }
+
+    fn select_target(&self, sql: &str) -> Result<(String, Option<Condition>), String> {
+        match self.parse_sql(sql)? {
+            // samples are drawn from every partition
+            SqlStatement::Select { table, condition, sample, hints, .. } => Ok((table, hints.scanned(&condition).clone().filter(|_| sample.is_none()))),
+            _ => Err("Only SELECT statements can be run read-only".to_string()),
+        }
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "potatodb.parse", level = "debug", skip_all))]
     fn parse_sql(&self, sql: &str) -> Result<SqlStatement, String> {
-        let tokens: Vec<&str> = sql.split_whitespace().collect();
+        let (sql, hint_text) = hints::strip(sql);
+        let tokens: Vec<&str> = identifier::split_statement(&sql);
+        precheck::check(&tokens)?;
         match tokens[0].to_uppercase().as_str() {
             "SELECT" => {
                 let from_index = tokens.iter().position(|&r| r.to_uppercase() == "FROM").ok_or("Invalid SELECT statement")?;
-                let table = tokens[from_index + 1].to_string();
-                let columns = tokens[1..from_index].iter().map(|s| s.to_string()).collect();
-                let condition = self.parse_where_clause(&tokens[from_index + 2..]);
-                Ok(SqlStatement::Select { table, columns, condition })
+                // a table function's call, split at spaces, is rejoined
+                let from = &tokens[from_index + 1..];
+                let mut depth = 0;
+                let used = from.iter()
+                    .position(|t| {
+                        depth += t.matches('(').count() as isize - t.matches(')').count() as isize;
+                        depth <= 0
+                    })
+                    .map_or(from.len(), |last| last + 1);
+                if used == 0 {
+                    return Err("Invalid SELECT statement".to_string());
+                }
+                let table = identifier::unquote(&from[..used].join(" "));
+                let columns: Vec<String> = join_calls(&tokens[1..from_index]).iter()
+                    .map(|s| identifier::unquote(s.trim_matches(',')))
+                    .filter(|s| !s.is_empty())
+                    .collect();
+                if columns.is_empty() {
+                    return Err("SELECT names no columns".to_string());
+                }
+                let mut rest = &from[used..];
+                let mut sample = None;
+                if rest.first().is_some_and(|t| t.eq_ignore_ascii_case("TABLESAMPLE")) {
+                    let end = rest.iter().position(|t| t.ends_with(')')).ok_or("Invalid TABLESAMPLE clause")?;
+                    let clause = rest[1..=end].join(" ");
+                    let inner = clause.strip_prefix('(').and_then(|c| c.strip_suffix(')')).ok_or("Invalid TABLESAMPLE clause")?;
+                    sample = Some(sample::Sample::parse(inner)?);
+                    rest = &rest[end + 1..];
+                }
+                let mut as_of = None;
+                if let [as_, of, timestamp, tail @ ..] = rest {
+                    if as_.eq_ignore_ascii_case("AS") && of.eq_ignore_ascii_case("OF") {
+                        as_of = Some(history::parse_timestamp(timestamp)?);
+                        rest = tail;
+                    }
+                }
+                let (mut rest, page) = page::Page::split(rest)?;
+                let mut order_by = Vec::new();
+                if let Some(at) = rest.windows(2).position(|w| w[0].eq_ignore_ascii_case("ORDER") && w[1].eq_ignore_ascii_case("BY")) {
+                    order_by = order::parse(&rest[at + 2..])?;
+                    rest = &rest[..at];
+                }
+                let mut group_by = None;
+                if let Some(at) = rest.windows(2).position(|w| w[0].eq_ignore_ascii_case("GROUP") && w[1].eq_ignore_ascii_case("BY")) {
+                    group_by = Some(group_by::GroupBy::parse(self, &rest[at + 2..])?);
+                    rest = &rest[..at];
+                }
+                let condition = self.parse_where_clause(rest)?;
+                let hints = hints::Hints::parse(&hint_text, &table);
+                Ok(SqlStatement::Select { table, columns, condition, as_of, sample, hints, order_by, page, group_by })
             },
             "INSERT" => { 
                 let into_index = tokens.iter().position(|&r| r.to_uppercase() == "INTO").ok_or("Invalid INSERT statement")?;
-                let values_index = tokens.iter().position(|&r| r.to_uppercase() == "VALUES").ok_or("Invalid INSERT statement")?;
-                let table = tokens[into_index + 1].to_string();
+                let values_index = tokens.iter().position(|&r| r.to_uppercase() == "VALUES").filter(|&v| v > into_index + 1).ok_or("Invalid INSERT statement")?;
+                let table = identifier::unquote(tokens[into_index + 1]);
                 let columns = tokens[into_index + 2..values_index].iter()
-                    .map(|s| s.trim_matches(|c| c == '(' || c == ',' || c == ')').to_string())
+                    .map(|s| identifier::unquote(s.trim_matches(|c| c == '(' || c == ',' || c == ')')))
+                    .filter(|s| !s.is_empty())
                     .collect();
                 let values = tokens[values_index + 1..].iter()
                     .map(|s| s.trim_matches(|c| c == '(' || c == ',' || c == ')').to_string())
+                    .filter(|s| !s.is_empty())
                     .collect();
                 Ok(SqlStatement::Insert { table, columns, values })
             },
             "UPDATE" => {
                 let set_index = tokens.iter().position(|&r| r.to_uppercase() == "SET").ok_or("Invalid UPDATE statement")?;
-                let table = tokens[1].to_string();
-                let column = tokens[set_index + 1].to_string();
-                let value = tokens[set_index + 3].to_string();
-                let condition = self.parse_where_clause(&tokens[set_index + 4..]);
+                let table = identifier::unquote(tokens[1]);
+                let column = identifier::unquote(tokens[set_index + 1]);
+                // anything after the value has to be the WHERE clause, or a
+                // misread statement would write to every row
+                let check_rest = |rest: &[&str]| match rest.first() {
+                    Some(word) if !word.eq_ignore_ascii_case("WHERE") => Err(format!("Unexpected '{}' in UPDATE statement", word)),
+                    _ => Ok(()),
+                };
+                if let Some([source, op @ ("+" | "-"), rest @ ..]) = tokens.get(set_index + 3..) {
+                    if identifier::unquote(source) != column {
+                        return Err(format!("UPDATE can only add to the column it sets, not '{}'", identifier::unquote(source)));
+                    }
+                    let delta = rest.first().ok_or(format!("Invalid increment after '{}'", op))?;
+                    let delta: f64 = delta.parse().map_err(|_| format!("Invalid increment '{}'", delta))?;
+                    let delta = if *op == "-" { -delta } else { delta };
+                    check_rest(&rest[1..])?;
+                    let condition = self.parse_where_clause(&rest[1..])?;
+                    return Ok(SqlStatement::Increment { table, column, delta, condition });
+                }
+                let value = tokens.get(set_index + 3).filter(|_| tokens[set_index + 2] == "=").ok_or("Invalid UPDATE statement")?.to_string();
+                check_rest(&tokens[set_index + 4..])?;
+                let condition = self.parse_where_clause(&tokens[set_index + 4..])?;
                 Ok(SqlStatement::Update { table, column, value, condition })
             },
+            "EXPLAIN" => {
+                let analyze = tokens.get(1).is_some_and(|t| t.to_uppercase() == "ANALYZE");
+                let mut statement = tokens[if analyze { 2 } else { 1 }..].join(" ");
+                if statement.is_empty() {
+                    return Err("Invalid EXPLAIN statement".to_string());
+                }
+                // the explained statement's hints were stripped with the
+                // comments, so they are handed back
+                if !hint_text.is_empty() {
+                    statement = format!("/*+ {} */ {}", hint_text, statement);
+                }
+                Ok(SqlStatement::Explain { analyze, statement: Box::new(self.parse_sql(&statement)?) })
+            },
+            "CREATE" if tokens.get(1).is_some_and(|t| t.eq_ignore_ascii_case("SCHEMA")) => match tokens.get(2..) {
+                Some([name]) => Ok(SqlStatement::CreateSchema(name.to_string())),
+                _ => Err("Invalid CREATE SCHEMA statement".to_string()),
+            },
+            "DROP" => match tokens.get(1..) {
+                Some([kind, name]) if kind.eq_ignore_ascii_case("SCHEMA") => Ok(SqlStatement::DropSchema(name.to_string())),
+                _ => Err("Unsupported SQL statement".to_string()),
+            },
+            "CREATE" => {
+                let temporary = tokens.get(1).is_some_and(|t| t.eq_ignore_ascii_case("TEMP") || t.eq_ignore_ascii_case("TEMPORARY"));
+                let keywords = if temporary { 3 } else { 2 };
+                if !tokens.get(keywords - 1).is_some_and(|t| t.eq_ignore_ascii_case("TABLE")) {
+                    return Err("Invalid CREATE statement".to_string());
+                }
+                let rest = tokens[..keywords].iter()
+                    .fold(sql.trim().trim_end_matches(';'), |rest, keyword| rest.trim_start()[keyword.len()..].trim_start())
+                    .trim();
+                let (rest, max_rows) = capped::split_max_rows(rest)?;
+                let (table, generated, enums, types) = match rest.split_once('(') {
+                    Some((table, body)) => {
+                        let body = body.trim_end().strip_suffix(')').ok_or("Invalid CREATE statement")?;
+                        (table.trim(), generated::parse_definitions(body)?, enums::parse_definitions(body)?, alter::parse_definitions(body))
+                    }
+                    None => (rest, Vec::new(), BTreeMap::new(), BTreeMap::new()),
+                };
+                // only a quoted name may hold spaces
+                if table.is_empty() || (table.contains(char::is_whitespace) && !table.starts_with('"')) {
+                    return Err("Invalid CREATE statement".to_string());
+                }
+                Ok(SqlStatement::CreateTable { table: identifier::unquote(table), generated, enums, types, temporary, max_rows })
+            },
+            "GRAPH" => {
+                let (query, used) = graph::GraphQuery::parse(tokens.get(2..).unwrap_or_default())?;
+                let table = identifier::unquote(tokens[1]);
+                let condition = self.parse_where_clause(&tokens[2 + used..])?;
+                Ok(SqlStatement::Graph { table, query, condition })
+            },
+            "PRAGMA" => match tokens.get(1..) {
+                Some([name]) if !name.contains('=') => Ok(SqlStatement::Pragma(name.to_lowercase())),
+                Some(rest) => settings::parse_assignment(rest).map(|(name, value)| SqlStatement::Set(name, value)).ok_or("Invalid PRAGMA statement".to_string()),
+                None => Err("Invalid PRAGMA statement".to_string()),
+            },
+            "ANALYZE" => match tokens.get(1..) {
+                Some([table]) => Ok(SqlStatement::Analyze(identifier::unquote(table))),
+                _ => Err("Invalid ANALYZE statement".to_string()),
+            },
+            "SET" => settings::parse_assignment(&tokens[1..]).map(|(name, value)| SqlStatement::Set(name, value)).ok_or("Invalid SET statement".to_string()),
             "DELETE" => {
                 let from_index = tokens.iter().position(|&r| r.to_uppercase() == "FROM").ok_or("Invalid DELETE statement")?;
-                let table = tokens[from_index + 1].to_string();
-                let condition = self.parse_where_clause(&tokens[from_index + 2..]);
+                let table = identifier::unquote(tokens.get(from_index + 1).ok_or("Invalid DELETE statement")?);
+                let condition = self.parse_where_clause(&tokens[from_index + 2..])?;
                 Ok(SqlStatement::Delete { table, condition })
             },
             _ => Err("Unsupported SQL statement".to_string()),
         }
     }
 
-    fn parse_where_clause(&self, tokens: &[&str]) -> Option<Condition> {
-        if tokens.is_empty() || tokens[0].to_uppercase() != "WHERE" {
-            return None;
+    // None when the tokens are empty; anything else has to be a WHERE
+    // clause the parser understands, so a statement is never run on more
+    // rows than it names.
+    fn parse_where_clause(&self, tokens: &[&str]) -> Result<Option<Condition>, String> {
+        match tokens.first() {
+            None => return Ok(None),
+            Some(first) if !first.eq_ignore_ascii_case("WHERE") => return Err(format!("Unexpected '{}'", first)),
+            Some(_) => {}
         }
+        let joined = join_calls(&tokens[1..]);
+        let tokens: Vec<&str> = joined.iter().map(String::as_str).collect();
+        self.parse_conditions(&tokens).map(Some)
+    }
 
+    // The comparisons after WHERE, joined by AND and OR. A group in
+    // parentheses, which `join_calls` has made one token, is parsed on its
+    // own and counts as one comparison.
+    fn parse_conditions(&self, tokens: &[&str]) -> Result<Condition, String> {
         let mut conditions = Vec::new();
-        let mut i = 1;
+        let mut i = 0;
         while i < tokens.len() {
-            if i + 2 < tokens.len() {
-                let column = tokens[i].to_string();
-                let operator = tokens[i + 1];
-                let value = tokens[i + 2].to_string();
-                let condition = match operator {
-                    "=" => Condition::Equals(column, value),
-                    "!=" => Condition::NotEquals(column, value),
-                    ">" => Condition::GreaterThan(column, value),
-                    "<" => Condition::LessThan(column, value),
-                    _ => return None, // Unsupported 
-                };
-                conditions.push(condition);
-                i += 3;
+            if let Some(group) = tokens[i].strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+                let split: Vec<&str> = group.split_whitespace().collect();
+                let joined = join_calls(&split);
+                let tokens: Vec<&str> = joined.iter().map(String::as_str).collect();
+                conditions.push(self.parse_conditions(&tokens)?);
+                i += 1;
             } else {
-                break;
+                let (condition, width) = self.parse_comparison(&tokens[i..])?;
+                conditions.push(condition);
+                i += width;
             }
 
             if i < tokens.len() {
@@ -233,67 +1219,281 @@ impl Database {
                     "AND" => i += 1,
                     "OR" => {
                         let left = conditions.pop().unwrap();
-                        let right = self.parse_where_clause(&tokens[i + 1..]).unwrap();
+                        let right = self.parse_conditions(&tokens[i + 1..])?;
                         conditions.push(Condition::Or(Box::new(left), Box::new(right)));
                         break;
                     },
-                    _ => break,
+                    _ => return Err(format!("Unexpected '{}' in WHERE clause", tokens[i])),
                 }
             }
         }
 
-        conditions.into_iter().reduce(|acc, item| Condition::And(Box::new(acc), Box::new(item)))
+        conditions.into_iter().reduce(|acc, item| Condition::And(Box::new(acc), Box::new(item))).ok_or("Empty WHERE clause".to_string())
     }
 
-    fn execute_select(&self, table: &str, columns: &[String], condition: Option<Condition>) -> Result<Vec<Record>, String> {
-        let table = self.tables.get(table).ok_or("Table not found")?;
-        let records: Vec<Record> = table.records.iter()
-            .filter(|record| self.evaluate_condition(record, &condition))
-            .cloned()
-            .collect();
+    // The comparison `tokens` start with, and how many tokens it takes.
+    fn parse_comparison(&self, tokens: &[&str]) -> Result<(Condition, usize), String> {
+        let [column, operator, value, ..] = tokens[..] else {
+            return Err(format!("Incomplete condition '{}'", tokens.join(" ")));
+        };
+        let column = identifier::unquote(column);
+        let value = value.to_string();
+        Ok(match operator.to_uppercase().as_str() {
+            "=" => (Condition::Equals(column, value), 3),
+            "!=" => (Condition::NotEquals(column, value), 3),
+            ">" => (Condition::GreaterThan(column, value), 3),
+            "<" => (Condition::LessThan(column, value), 3),
+            "LIKE" => (Condition::Like(column, value), 3),
+            "IS" => match (value.to_uppercase().as_str(), tokens.get(3)) {
+                ("NULL", _) => (Condition::IsNull(column), 3),
+                ("NOT", Some(null)) if null.eq_ignore_ascii_case("NULL") => (Condition::IsNotNull(column), 4),
+                _ => return Err(format!("Invalid IS on '{}'", column)),
+            },
+            "BETWEEN" => match tokens.get(3..5) {
+                Some([and, high]) if and.eq_ignore_ascii_case("AND") => (Condition::Between(column, value, high.to_string()), 5),
+                _ => return Err(format!("Invalid BETWEEN on '{}'", column)),
+            },
+            _ => return Err(format!("Unsupported operator '{}'", operator)),
+        })
+    }
 
-        if columns[0] == "*" {
-            Ok(records)
-        } else {
-            Ok(records.into_iter()
-                .map(|mut record| {
-                    record.data.retain(|k, _| columns.contains(k));
-                    record
-                })
-                .collect())
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "potatodb.execute", level = "debug", skip_all, fields(table = %select.table())))]
+    fn execute_select(&self, select: SqlStatement) -> Result<Vec<Record>, String> {
+        let SqlStatement::Select { table, columns, condition, as_of, sample, hints, order_by, page, group_by } = select else {
+            return Err("Only SELECT statements can be run read-only".to_string());
+        };
+        let columns = &columns[..];
+        let table = self.select_source(&table, as_of, &condition)?;
+        if let Some(group_by) = &group_by {
+            let rows = match sample {
+                Some(sample) => sample.draw(table.scan(&None)),
+                None => table.scan(hints.scanned(&condition)),
+            };
+            let mut matched = Vec::new();
+            table.filter(rows, &condition, |record| matched.push(record.into_owned()));
+            interrupt::check()?;
+            // rows are grouped and aggregated as the session may read them,
+            // so neither keys nor aggregates show more than the columns
+            self.keys.reveal(&table, &mut matched);
+            self.apply_masks(&table.name, &mut matched);
+            let mut groups: Vec<Cow<'_, Record>> = self.group_rows(group_by, columns, &matched)?.into_iter().map(Cow::Owned).collect();
+            self.sort_rows(&table, &order_by, &mut groups)?;
+            return Ok(groups.into_iter().skip(page.offset).take(page.limit.unwrap_or(usize::MAX)).map(Cow::into_owned).collect());
+        }
+        if is_count(columns) {
+            return self.count_rows(&table, &columns[0], condition, sample, hints);
+        }
+        let calls = calls(columns)?;
+        if let Some(proto) = &table.proto {
+            columns.iter().filter(|c| *c != "*" && !c.contains('(') && !pseudo::is_pseudo(c)).try_for_each(|c| proto.check_column(c))?;
+        }
+        // named columns of a whole columnar table are read column by column,
+        // unless they are sorted by columns that may not be selected, paged,
+        // or include pseudo-columns, which are not stored
+        let read = match (&condition, sample) {
+            (None, None) if calls.is_empty() && order_by.is_empty() && page.is_all() && !columns.iter().any(|c| pseudo::is_pseudo(c)) => table.select_columns(columns),
+            _ => None,
+        };
+        let records: Vec<Record> = match read {
+            Some(mut records) => {
+                self.keys.reveal(&table, &mut records);
+                self.apply_masks(&table.name, &mut records);
+                records
+            }
+            None => arena::scoped(|arena| -> Result<_, String> {
+                // a sample is drawn from the whole table, before WHERE filters it
+                let rows = match sample {
+                    Some(sample) => sample.draw(table.scan(&None)),
+                    None => table.scan(hints.scanned(&condition)),
+                };
+                let mut matched = ArenaVec::new_in(arena);
+                // unsorted rows are paged as they match, so reading stops
+                // once the page is full; sorted ones only once sorted
+                let sorted = !order_by.is_empty();
+                let mut skip = if sorted { 0 } else { page.offset };
+                let _ = table.try_filter(rows, &condition, |record| {
+                    if skip > 0 {
+                        skip -= 1;
+                        return ControlFlow::Continue(());
+                    }
+                    interrupt::keep(&record);
+                    matched.push(record);
+                    match !sorted && page.is_full(matched.len()) {
+                        true => ControlFlow::Break(()),
+                        false => ControlFlow::Continue(()),
+                    }
+                });
+                // rows are sorted as the session may read them, so neither
+                // sealed values nor the ones masks hide decide the order
+                if sorted {
+                    for record in matched.iter_mut() {
+                        if let Cow::Owned(readable) = self.readable(&table, record) {
+                            *record = Cow::Owned(readable);
+                        }
+                    }
+                }
+                self.sort_rows(&table, &order_by, &mut matched)?;
+                let skip = if sorted { page.offset } else { 0 };
+                // only the selected columns of the page are copied out
+                Ok(matched.iter()
+                    .skip(skip)
+                    .take(page.limit.unwrap_or(usize::MAX))
+                    .map(|record| match sorted {
+                        true => project(columns, &calls, record),
+                        false => project(columns, &calls, &self.readable(&table, record)),
+                    })
+                    .collect())
+            })?,
+        };
+        interrupt::check()?;
+        Ok(records)
+    }
+
+    // Takes the row count from the table when nothing is filtered, and
+    // otherwise counts the matching rows without copying them out; a
+    // condition on the partition column only reads the partitions it names.
+    fn count_rows(&self, table: &Table, column: &str, condition: Option<Condition>, sample: Option<sample::Sample>, hints: hints::Hints) -> Result<Vec<Record>, String> {
+        let count = match (&condition, sample) {
+            (None, None) => table.records.len(),
+            (_, sample) => {
+                let rows = match sample {
+                    Some(sample) => sample.draw(table.scan(&None)),
+                    None => table.scan(hints.scanned(&condition)),
+                };
+                let mut count = 0;
+                table.filter(rows, &condition, |_| count += 1);
+                interrupt::check()?;
+                count
+            }
+        };
+        Ok(vec![Record { id: 1, data: Row::from([(column.to_string(), count.to_string())]) }])
+    }
+
+    fn execute_pragma(&self, name: &str) -> Result<Vec<Record>, String> {
+        match name {
+            "integrity_check" => Ok(self.pragma_integrity_check()),
+            _ => match self.setting(name) {
+                Ok(value) => Ok(vec![Record { id: 1, data: Row::from([(name.to_string(), value)]) }]),
+                Err(_) => Err(format!("Unsupported PRAGMA '{}'", name)),
+            },
+        }
+    }
+
+    // The table a SELECT reads, as it was at `as_of` if given; of a virtual
+    // table, only the rows that may match `condition` are read.
+    fn select_source(&self, name: &str, as_of: Option<u64>, condition: &Option<Condition>) -> Result<Cow<'_, Table>, String> {
+        let table = match self.is_virtual(name) {
+            true => Cow::Owned(self.read_virtual(name, condition)?),
+            false => self.readable_table(name)?,
+        };
+        match as_of {
+            Some(millis) => Ok(Cow::Owned(table.as_of(millis)?)),
+            None => Ok(table),
         }
     }
 
-    fn execute_insert(&mut self, table: &str, columns: &[String], values: &[String]) -> Result<Vec<Record>, String> {
-        let table = self.tables.get_mut(table).ok_or("Table not found")?;
-        let id = table.records.len() as u64 + 1; 
-        let mut data = HashMap::new();
+    // A table SELECTs can read, including the system tables.
+    fn readable_table(&self, name: &str) -> Result<Cow<'_, Table>, String> {
+        match self.tables.get(name) {
+            Some(table) => Ok(Cow::Borrowed(table)),
+            None if name == SLOW_QUERIES_TABLE => Ok(Cow::Owned(self.query_log.slow_queries())),
+            None if name == AUDIT_TABLE => Ok(Cow::Owned(self.audit.table())),
+            None if self.is_virtual(name) => Ok(Cow::Owned(self.read_virtual(name, &None)?)),
+            None if name.starts_with(catalog::RESERVED_PREFIX) => self.catalog(name).map(Cow::Owned).ok_or("Table not found".to_string()),
+            None if name.contains('(') => Ok(Cow::Owned(table_function::call(name)?)),
+            None => Err("Table not found".to_string()),
+        }
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "potatodb.execute", level = "debug", skip_all, fields(table = %table_name)))]
+    fn execute_insert(&mut self, table_name: &str, columns: &[String], values: &[String]) -> Result<Vec<Record>, String> {
+        let table = self.tables.get_mut(table_name).ok_or("Table not found")?;
+        check_insert(table, columns, values)?;
+        generated::check_writable(&table.generated, columns)?;
+        // time series are keyed by when their points arrive, and a point
+        // arriving in the same millisecond as the newest, or while the
+        // clock is behind it, goes just after it
+        let id = if table.series.is_some() {
+            clock::unix_millis().max(table.records.last().map_or(0, |newest| newest.id + 1))
+        } else if table.queue.is_some() || table.max_rows.is_some() {
+            table.next_message_id().max(self.sequences.floor(table_name))
+        } else {
+            (table.records.len() as u64 + 1).max(self.sequences.floor(table_name))
+        };
+        // once rows are deleted that id may still be in use
+        let id = match table.series.is_none() && table.index.contains_key(&id) {
+            true => table.index.keys().max().map_or(1, |max| max + 1),
+            false => id,
+        };
+        table.check_append(id)?;
+        let mut data = Row::new();
         for (column, value) in columns.iter().zip(values.iter()) {
             data.insert(column.clone(), value.clone());
         }
+        table.stamp(None, &mut data);
+        table.convert_types(&mut data)?;
+        generated::fill(&table.generated, &mut data);
+        table.check_enums(&data)?;
+        self.keys.seal_row(table, &mut data)?;
+        if let Some(proto) = &table.proto {
+            proto.validate(&data)?;
+        }
+        if let Some(partitions) = &mut table.partitions {
+            partitions.place(id, &data);
+        }
+        if let Some(expiry) = &mut table.expiry {
+            expiry.place(id, &data);
+        }
+        if let Some(newest) = table.records.last() {
+            data.share_names(&newest.data);
+        }
         let record = Record { id, data };
         table.records.push(record.clone());
         table.index.insert(id, table.records.len() - 1);
+        table.invalidate_columns();
+        if let Some(history) = &mut table.history {
+            history.record(id, Some(&record.data));
+        }
+        self.audit.record(table_name, id, ChangeKind::Insert, None, Some(&record.data));
+        self.changes.push(table_name, ChangeKind::Insert, record.clone());
+        if table.series.is_some() {
+            self.apply_retention(table_name)?;
+        }
+        self.evict_over_cap(table_name);
+        self.cache_inserted(table_name, id);
         Ok(vec![record])
     }
  
-    fn execute_delete(&mut self, table: &str, condition: Option<Condition>) -> Result<Vec<Record>, String> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "potatodb.execute", level = "debug", skip_all, fields(table = %table_name)))]
+    fn execute_delete(&mut self, table_name: &str, condition: Option<Condition>) -> Result<Vec<Record>, String> {
         // 1. evaluate the condition and collect the IDs to delete
         let ids_to_delete = {
-            let table = self.tables.get(table).ok_or("Table not found")?;
-            table.records.iter()
-                .filter(|record| self.evaluate_condition(record, &condition))
-                .map(|record| record.id)
-                .collect::<Vec<_>>()
+            let table = self.tables.get(table_name).ok_or("Table not found")?;
+            let mut ids = Vec::new();
+            table.filter(table.scan(&condition), &condition, |record| ids.push(record.id));
+            ids
         };
+        interrupt::check()?;
     
         // 2. perform the deletion
-        let table = self.tables.get_mut(table).ok_or("Table not found")?;
+        let table = self.tables.get_mut(table_name).ok_or("Table not found")?;
         let mut deleted_records = Vec::new();
     
         for id in ids_to_delete {
             if let Some(index) = table.index.remove(&id) {
                 let record = table.records.remove(index);
+                table.invalidate_columns();
+                if let Some(partitions) = &mut table.partitions {
+                    partitions.remove(id, &record.data);
+                }
+                if let Some(expiry) = &mut table.expiry {
+                    expiry.remove(id);
+                }
+                if let Some(history) = &mut table.history {
+                    history.record(id, None);
+                }
+                self.audit.record(table_name, id, ChangeKind::Delete, Some(&record.data), None);
+                self.changes.push(table_name, ChangeKind::Delete, record.clone());
                 deleted_records.push(record);
                 // Update indices for all records after the deleted one
                 for (_, idx) in table.index.iter_mut() {
@@ -306,54 +1506,77 @@ impl Database {
     
         Ok(deleted_records)
     }
-    fn execute_update(&mut self, table: &str, column: &str, value: &str, condition: Option<Condition>) -> Result<Vec<Record>, String> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "potatodb.execute", level = "debug", skip_all, fields(table = %table_name)))]
+    fn execute_update(&mut self, table_name: &str, column: &str, value: &str, condition: Option<Condition>) -> Result<Vec<Record>, String> {
         // 1. evaluate the condition and collect the IDs to update
         let ids_to_update = {
-            let table = self.tables.get(table).ok_or("Table not found")?;
-            table.records.iter()
-                .filter(|record| self.evaluate_condition(record, &condition))
-                .map(|record| record.id)
-                .collect::<Vec<_>>()
+            let table = self.tables.get(table_name).ok_or("Table not found")?;
+            let mut ids = Vec::new();
+            table.filter(table.scan(&condition), &condition, |record| ids.push(record.id));
+            ids
         };
+        interrupt::check()?;
     
         // 2. perform the update
-        let table = self.tables.get_mut(table).ok_or("Table not found")?;
-        let mut updated_records = Vec::new();
-    
-        for id in ids_to_update {
-            if let Some(index) = table.index.get(&id) {
-                if let Some(data) = table.records[*index].data.get_mut(column) {
-                    *data = value.to_string();
-                    updated_records.push(table.records[*index].clone());
-                }
-            }
+        let value = self.updated_value(table_name, column, value)?;
+        let table = self.tables.get(table_name).ok_or("Table not found")?;
+        let writes = ids_to_update.into_iter()
+            .filter(|id| table.index.get(id).is_some_and(|&index| table.records[index].data.contains_key(column)))
+            .map(|id| (id, value.clone()))
+            .collect();
+        self.write_column(table_name, column, writes)
+    }
+
+    // Fails for tables that take no writes, as running or dry running a
+    // write does before it writes.
+    fn check_writable(&self, table_name: &str) -> Result<(), String> {
+        if self.is_virtual(table_name) {
+            return Err(format!("Virtual table '{}' is read-only", table_name));
         }
-    
-        Ok(updated_records)
+        if catalog::is_catalog(table_name) {
+            return Err(format!("System catalog '{}' is read-only", table_name));
+        }
+        Ok(())
     }
-    fn evaluate_condition(&self, record: &Record, condition: &Option<Condition>) -> bool {
-        match condition {
-            Some(cond) => match cond {
-                Condition::Equals(col, val) => record.data.get(col).map_or(false, |v| v == val),
-                Condition::NotEquals(col, val) => record.data.get(col).map_or(true, |v| v != val),
-                Condition::GreaterThan(col, val) => record.data.get(col).map_or(false, |v| v > val),
-                Condition::LessThan(col, val) => record.data.get(col).map_or(false, |v| v < val),
-                Condition::And(left, right) => self.evaluate_condition(record, &Some(*left.clone())) && self.evaluate_condition(record, &Some(*right.clone())),
-                Condition::Or(left, right) => self.evaluate_condition(record, &Some(*left.clone())) || self.evaluate_condition(record, &Some(*right.clone())),
-            },
-            None => true,
+
+    // The value `UPDATE table SET column = value` stores, converted, checked
+    // and sealed.
+    fn updated_value(&self, table_name: &str, column: &str, value: &str) -> Result<String, String> {
+        let table = self.tables.get(table_name).ok_or("Table not found")?;
+        let value = &table.convert_column(column, value)?;
+        if let Some(proto) = &table.proto {
+            proto.validate_value(column, value)?;
         }
+        generated::check_writable(&table.generated, &[column.to_string()])?;
+        table.check_enum_value(column, value)?;
+        self.keys.seal(table, column, value)
     }
 
+    fn evaluate_condition(&self, record: &Record, condition: &Option<Condition>) -> bool {
+        condition.as_ref().map_or(true, |condition| condition.matches(record, self.settings.case_sensitive, true))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Database {
+    // browsers have no file system; wasm builds persist through save_opfs/load_opfs
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "potatodb.save", skip(self), fields(bytes)))]
     pub fn save(&self, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let file = File::create(filename)?;
-        serialize_into(file, self)?;
-        Ok(())
+        self.save_to(&FileStorage, filename)
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "potatodb.load", fields(bytes)))]
     pub fn load(filename: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let file = File::open(filename)?;
-        let db: Database = deserialize_from(file)?;
-        Ok(db)
+        Self::load_from(&FileStorage, filename)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        format::encode(self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        format::decode(bytes)
     }
 }
@@ -1,6 +1,22 @@
+#[cfg(not(target_arch = "wasm32"))]
 use potatodb::Database;
 
+#[cfg(feature = "tui")]
+mod tui;
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
+
+#[cfg(not(target_arch = "wasm32"))]
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(feature = "tui")]
+    {
+        let args: Vec<String> = std::env::args().collect();
+        if args.get(1).map(String::as_str) == Some("tui") {
+            return tui::run(args.get(2).map(String::as_str).unwrap_or("database.bin"));
+        }
+    }
+
     let mut db = Database::new();
      
     db.create_table("users".to_string())?;
@@ -0,0 +1,90 @@
+
+use alloc::borrow::Cow;
+use core::slice;
+
+use crate::{Database, Privilege, Record, Table};
+use crate::prelude::*;
+
+/// How a masked column is shown to sessions without the `Unmask` privilege.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mask {
+    /// `****`
+    Redact,
+    /// `a****@example.com`
+    Email,
+    /// `****1234`, keeping this many trailing characters.
+    KeepLast(usize),
+}
+
+impl Mask {
+    fn apply(&self, value: &str) -> String {
+        match self {
+            Mask::Redact => "****".to_string(),
+            Mask::Email => match value.split_once('@') {
+                Some((user, domain)) => format!("{}****@{}", user.chars().next().unwrap_or_default(), domain),
+                None => "****".to_string(),
+            },
+            Mask::KeepLast(n) => {
+                let skip = value.chars().count().saturating_sub(*n);
+                format!("****{}", value.chars().skip(skip).collect::<String>())
+            }
+        }
+    }
+}
+
+// table -> column -> mask
+#[derive(Clone, Default)]
+pub(crate) struct Masks(HashMap<String, HashMap<String, Mask>>);
+
+impl Masks {
+    pub(crate) fn rename_column(&mut self, table: &str, from: &str, to: &str) {
+        if let Some(mask) = self.0.get_mut(table).and_then(|masks| masks.remove(from)) {
+            self.0.entry(table.to_string()).or_default().insert(to.to_string(), mask);
+        }
+    }
+}
+
+impl Database {
+    /// Masks a column in SELECT output for session users without
+    /// [`Privilege::Unmask`] on the table. Stored values are unchanged, and
+    /// the Rust API and statements run without a session user see them.
+    pub fn mask_column(&mut self, table_name: &str, column: &str, mask: Mask) {
+        self.masks.0.entry(table_name.to_string()).or_default().insert(column.to_string(), mask);
+    }
+
+    pub fn unmask_column(&mut self, table_name: &str, column: &str) {
+        if let Some(masks) = self.masks.0.get_mut(table_name) {
+            masks.remove(column);
+        }
+    }
+
+    // Whether SELECTs by the session see some column of the table masked.
+    pub(crate) fn masks_apply(&self, table_name: &str) -> bool {
+        self.masks.0.get(table_name).is_some_and(|masks| !masks.is_empty())
+            && !self.session_allows(table_name, Privilege::Unmask)
+    }
+
+    // A row as a SELECT may show it, with encrypted columns revealed and
+    // masked columns masked, for computing its output from: what
+    // `COALESCE(email, 'x')` or `MAX(email)` shows is no more than `email`.
+    pub(crate) fn readable<'r>(&self, table: &Table, record: &'r Record) -> Cow<'r, Record> {
+        if table.encrypted.is_empty() && !self.masks_apply(&table.name) {
+            return Cow::Borrowed(record);
+        }
+        let mut record = record.clone();
+        self.keys.reveal(table, slice::from_mut(&mut record));
+        self.apply_masks(&table.name, slice::from_mut(&mut record));
+        Cow::Owned(record)
+    }
+
+    pub(crate) fn apply_masks(&self, table_name: &str, records: &mut [Record]) {
+        if !self.masks_apply(table_name) {
+            return;
+        }
+        for (column, mask) in &self.masks.0[table_name] {
+            for value in records.iter_mut().filter_map(|r| r.data.get_mut(column)) {
+                *value = mask.apply(value);
+            }
+        }
+    }
+}
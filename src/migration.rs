@@ -0,0 +1,100 @@
+
+use crate::clock::unix_millis;
+use crate::Database;
+use crate::prelude::*;
+
+/// The table applied migrations are recorded in, one row per migration with
+/// the columns `version`, `name` and `applied_at` (milliseconds since the
+/// Unix epoch), keyed by version.
+pub const SCHEMA_VERSION_TABLE: &str = "_schema_version";
+
+/// What a migration does.
+#[derive(Clone, Copy)]
+pub enum MigrationStep {
+    /// SQL statements separated by `;`. Fails without the `sql` feature.
+    Sql(&'static str),
+    /// A function changing the database it is given, and nothing else.
+    Rust(fn(&mut Database) -> Result<(), String>),
+}
+
+/// One versioned change to a database's schema or data, for
+/// [`Database::migrate`].
+#[derive(Clone, Copy)]
+pub struct Migration {
+    pub version: u64,
+    pub name: &'static str,
+    pub step: MigrationStep,
+}
+
+impl Migration {
+    fn run(&self, db: &mut Database) -> Result<(), String> {
+        let result = match self.step {
+            #[cfg(feature = "sql")]
+            MigrationStep::Sql(sql) => sql.split(';')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .try_for_each(|statement| db.execute_sql(statement).map(|_| ())),
+            #[cfg(not(feature = "sql"))]
+            MigrationStep::Sql(_) => Err("SQL migrations need the sql feature".to_string()),
+            MigrationStep::Rust(step) => step(db),
+        };
+        result.map_err(|e| format!("Migration {} ({}) failed: {}", self.version, self.name, e))
+    }
+}
+
+impl Database {
+    /// The version of the newest migration applied, or 0.
+    pub fn schema_version(&self) -> u64 {
+        self.tables.get(SCHEMA_VERSION_TABLE)
+            .and_then(|table| table.index.keys().max().copied())
+            .unwrap_or(0)
+    }
+
+    /// Applies the migrations newer than the schema version, in order, and
+    /// returns their versions. Either all of them apply or, if one fails,
+    /// none do: they are tried on a copy first, so Rust steps run twice.
+    pub fn migrate(&mut self, migrations: &[Migration]) -> Result<Vec<u64>, String> {
+        let pending = self.migrate_dry_run(migrations)?;
+        for migration in migrations.iter().filter(|m| pending.contains(&m.version)) {
+            self.apply_migration(migration)?;
+        }
+        Ok(pending)
+    }
+
+    /// The versions [`migrate`](Self::migrate) would apply, after checking
+    /// they succeed, without changing this database.
+    pub fn migrate_dry_run(&self, migrations: &[Migration]) -> Result<Vec<u64>, String> {
+        if let Some(pair) = migrations.windows(2).find(|pair| pair[0].version >= pair[1].version) {
+            return Err(format!("Migration {} is listed after migration {}", pair[1].version, pair[0].version));
+        }
+        let current = self.schema_version();
+        let pending: Vec<&Migration> = migrations.iter().filter(|m| m.version > current).collect();
+        let mut scratch = Database {
+            tables: self.tables.clone(),
+            kv: self.kv.clone(),
+            schemas: self.schemas.clone(),
+            access: self.access.clone(),
+            #[cfg(feature = "sql")]
+            policies: self.policies.clone(),
+            keys: self.keys.clone(),
+            ..Database::new()
+        };
+        for migration in &pending {
+            scratch.apply_migration(migration)?;
+        }
+        Ok(pending.iter().map(|m| m.version).collect())
+    }
+
+    fn apply_migration(&mut self, migration: &Migration) -> Result<(), String> {
+        migration.run(self)?;
+        if !self.tables.contains_key(SCHEMA_VERSION_TABLE) {
+            self.create_table(SCHEMA_VERSION_TABLE.to_string())?;
+        }
+        let data = HashMap::from([
+            ("version".to_string(), migration.version.to_string()),
+            ("name".to_string(), migration.name.to_string()),
+            ("applied_at".to_string(), unix_millis().to_string()),
+        ]);
+        self.insert(SCHEMA_VERSION_TABLE, migration.version, data)
+    }
+}
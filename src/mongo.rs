@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use bson::{Bson, Document};
+use serde_json::Value as Json;
+
+use crate::{Database, Row};
+
+// Nested documents are flattened into dotted column names ("address.city").
+// Strings, numbers and booleans keep their plain text form; every other BSON
+// value (ObjectId, dates, arrays, binary, ...) is stored as canonical
+// Extended JSON so it can be restored on export.
+impl Database {
+    /// Imports a mongoexport file: one Extended JSON document per line, or a
+    /// single JSON array as written by `--jsonArray`. Returns the number of
+    /// documents imported.
+    pub fn import_mongo_json(&mut self, table_name: &str, path: impl AsRef<Path>) -> Result<usize, Box<dyn std::error::Error>> {
+        let text = fs::read_to_string(path)?;
+        let values: Vec<Json> = if text.trim_start().starts_with('[') {
+            serde_json::from_str(&text)?
+        } else {
+            text.lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<Result<_, _>>()?
+        };
+        let documents = values.into_iter()
+            .map(|value| match Bson::try_from(value)? {
+                Bson::Document(document) => Ok(document),
+                other => Err(format!("Expected a document, found {}", other).into()),
+            })
+            .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+        self.import_documents(table_name, documents)
+    }
+
+    /// Imports a `.bson` file of concatenated documents as written by
+    /// mongodump. Returns the number of documents imported.
+    pub fn import_bson(&mut self, table_name: &str, path: impl AsRef<Path>) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut reader = BufReader::new(fs::File::open(path)?);
+        let mut documents = Vec::new();
+        while !reader.fill_buf()?.is_empty() {
+            documents.push(Document::from_reader(&mut reader)?);
+        }
+        self.import_documents(table_name, documents)
+    }
+
+    /// Writes a table as relaxed Extended JSON, one document per line, in the
+    /// format mongoimport reads. Returns the number of documents written.
+    pub fn export_mongo_json(&self, table_name: &str, path: impl AsRef<Path>) -> Result<usize, Box<dyn std::error::Error>> {
+        let documents = self.export_documents(table_name)?;
+        let mut out = String::new();
+        for document in &documents {
+            out.push_str(&Bson::Document(document.clone()).into_relaxed_extjson().to_string());
+            out.push('\n');
+        }
+        fs::write(path, out)?;
+        Ok(documents.len())
+    }
+
+    /// Writes a table as concatenated BSON documents, the format mongorestore
+    /// reads. Returns the number of documents written.
+    pub fn export_bson(&self, table_name: &str, path: impl AsRef<Path>) -> Result<usize, Box<dyn std::error::Error>> {
+        let documents = self.export_documents(table_name)?;
+        let mut out = Vec::new();
+        for document in &documents {
+            document.to_writer(&mut out)?;
+        }
+        fs::write(path, out)?;
+        Ok(documents.len())
+    }
+
+    // Flattens every document before touching the table so a bad document
+    // leaves it unchanged.
+    fn import_documents(&mut self, table_name: &str, documents: Vec<Document>) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut rows = documents.into_iter()
+            .map(|document| {
+                let mut data = HashMap::new();
+                flatten("", document, &mut data)?;
+                Ok(Row::from(data))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let table = self.tables.get(table_name);
+        if let Some(table) = table {
+            rows.iter_mut().try_for_each(|data| table.convert_types(data))?;
+        }
+        if let Some(proto) = table.and_then(|t| t.proto.as_ref()) {
+            rows.iter().try_for_each(|data| proto.validate(data))?;
+        }
+        let first_id = table.and_then(|t| t.index.keys().max()).map_or(Some(1), |id| id.checked_add(1))
+            .map(|id| id.max(self.sequences.floor(table_name)));
+        let first_id = first_id.filter(|id| id.checked_add(rows.len() as u64).is_some())
+            .ok_or("No record ids left in the table")?;
+
+        if table.is_none() {
+            self.create_table(table_name.to_string())?;
+        }
+        let imported = rows.len();
+        for (id, data) in (first_id..).zip(rows) {
+            self.insert(table_name, id, data)?;
+        }
+        Ok(imported)
+    }
+
+    fn export_documents(&self, table_name: &str) -> Result<Vec<Document>, String> {
+        self.get_all(table_name)?
+            .into_iter()
+            .map(|record| {
+                let mut columns: Vec<_> = record.data.iter().collect();
+                // mongo tooling expects _id to lead the document
+                columns.sort_by_key(|(column, _)| (column.as_str() != "_id", *column));
+                let mut document = Document::new();
+                for (column, value) in columns {
+                    insert_path(&mut document, column, restore(value))?;
+                }
+                Ok(document)
+            })
+            .collect()
+    }
+}
+
+fn flatten(prefix: &str, document: Document, data: &mut HashMap<String, String>) -> Result<(), String> {
+    for (key, value) in document {
+        let column = if prefix.is_empty() { key } else { format!("{}.{}", prefix, key) };
+        let text = match value {
+            Bson::Document(nested) if !nested.is_empty() => {
+                flatten(&column, nested, data)?;
+                continue;
+            }
+            Bson::Null => continue,
+            Bson::String(s) => s,
+            Bson::Int32(n) => n.to_string(),
+            Bson::Int64(n) => n.to_string(),
+            Bson::Double(n) if n.is_finite() => n.to_string(),
+            Bson::Boolean(b) => b.to_string(),
+            other => other.into_canonical_extjson().to_string(),
+        };
+        if data.insert(column.clone(), text).is_some() {
+            return Err(format!("Field '{}' appears twice after flattening", column));
+        }
+    }
+    Ok(())
+}
+
+// Numbers are written as numbers only when their text form round-trips
+// exactly, so values such as "007" stay strings.
+fn restore(value: &str) -> Bson {
+    if value.starts_with(['{', '[']) {
+        if let Ok(bson) = serde_json::from_str::<Json>(value).map_err(|_| ()).and_then(|json| Bson::try_from(json).map_err(|_| ())) {
+            return bson;
+        }
+    }
+    if let Ok(n) = value.parse::<i32>() {
+        if n.to_string() == value {
+            return Bson::Int32(n);
+        }
+    }
+    if let Ok(n) = value.parse::<i64>() {
+        if n.to_string() == value {
+            return Bson::Int64(n);
+        }
+    }
+    if let Ok(n) = value.parse::<f64>() {
+        if n.is_finite() && n.to_string() == value {
+            return Bson::Double(n);
+        }
+    }
+    match value {
+        "true" => Bson::Boolean(true),
+        "false" => Bson::Boolean(false),
+        _ => Bson::String(value.to_string()),
+    }
+}
+
+fn insert_path(document: &mut Document, column: &str, value: Bson) -> Result<(), String> {
+    let Some((head, rest)) = column.split_once('.') else {
+        if document.contains_key(column) {
+            return Err(format!("Column '{}' clashes with a nested document", column));
+        }
+        document.insert(column, value);
+        return Ok(());
+    };
+    let nested = document.entry(head.to_string()).or_insert_with(|| Bson::Document(Document::new()));
+    match nested {
+        Bson::Document(nested) => insert_path(nested, rest, value),
+        _ => Err(format!("Column '{}' clashes with a nested document", column)),
+    }
+}
+
@@ -0,0 +1,67 @@
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
+use core::time::Duration;
+
+use crate::{Database, Record, SqlStatement};
+use crate::prelude::*;
+
+/// Callbacks around every statement run through [`Database::execute_sql`] or
+/// [`Database::query_sql`], for profilers, rate limiters and query rewriters.
+/// Every method does nothing by default.
+pub trait QueryObserver: Send + Sync {
+    /// Runs before a statement is parsed. Returning a statement replaces it;
+    /// returning an error rejects it with that error.
+    fn rewrite(&self, _sql: &str) -> Result<Option<String>, String> {
+        Ok(None)
+    }
+
+    /// The statement parsed; `plan` describes the scan it will do.
+    fn on_plan(&self, _sql: &str, _plan: &str) {}
+
+    /// Called for each row the statement returns, or inserts, updates or
+    /// deletes, before the caller gets them.
+    fn on_row(&self, _sql: &str, _record: &Record) {}
+
+    /// The statement finished with this many rows, or failed.
+    fn on_execute(&self, _sql: &str, _duration: Duration, _result: Result<usize, &str>) {}
+}
+
+impl Database {
+    /// Adds an observer; observers run in the order they were added.
+    pub fn add_query_observer(&mut self, observer: Arc<dyn QueryObserver>) {
+        self.observers.push(observer);
+    }
+
+    pub fn clear_query_observers(&mut self) {
+        self.observers.clear();
+    }
+
+    // Rewrites, parses and authorizes a statement and applies row policies,
+    // telling observers its plan.
+    pub(crate) fn prepare<'a>(&self, sql: &'a str) -> (Cow<'a, str>, Result<SqlStatement, String>) {
+        let mut sql = Cow::Borrowed(sql);
+        for observer in &self.observers {
+            match observer.rewrite(&sql) {
+                Ok(Some(rewritten)) => sql = Cow::Owned(rewritten),
+                Ok(None) => {}
+                Err(e) => return (sql, Err(e)),
+            }
+        }
+        let statement = self.parse_sql(&sql)
+            .and_then(|statement| self.authorize(&statement).map(|_| statement))
+            .and_then(|statement| self.apply_policies(statement));
+        if let (Ok(statement), false) = (&statement, self.observers.is_empty()) {
+            let plan = self.describe_statement(statement);
+            self.observers.iter().for_each(|o| o.on_plan(&sql, &plan));
+        }
+        (sql, statement)
+    }
+
+    pub(crate) fn notify_row(&self, sql: &str, record: &Record) {
+        self.observers.iter().for_each(|observer| observer.on_row(sql, record));
+    }
+
+    pub(crate) fn notify_executed(&self, sql: &str, duration: Duration, result: Result<usize, &str>) {
+        self.observers.iter().for_each(|observer| observer.on_execute(sql, duration, result));
+    }
+}
@@ -0,0 +1,66 @@
+use js_sys::{Promise, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    Blob, FileSystemDirectoryHandle, FileSystemFileHandle, FileSystemGetFileOptions,
+    FileSystemWritableFileStream, StorageManager, WorkerGlobalScope,
+};
+
+use crate::Database;
+
+// Browser persistence goes through the Origin Private File System, which is
+// available both on the main thread and inside workers.
+impl Database {
+    pub async fn save_opfs(&self, filename: &str) -> Result<(), String> {
+        let bytes = self.to_bytes().map_err(|e| e.to_string())?;
+        write_file(filename, &bytes).await
+    }
+
+    pub async fn load_opfs(filename: &str) -> Result<Self, String> {
+        let bytes = read_file(filename).await?;
+        Database::from_bytes(&bytes).map_err(|e| e.to_string())
+    }
+}
+
+pub(crate) async fn write_file(filename: &str, bytes: &[u8]) -> Result<(), String> {
+    let file = file_handle(filename, true).await?;
+    let stream: FileSystemWritableFileStream = resolve(file.create_writable()).await?;
+    let write = stream.write_with_u8_array(bytes).map_err(js_error)?;
+    JsFuture::from(write).await.map_err(js_error)?;
+    JsFuture::from(stream.close()).await.map_err(js_error)?;
+    Ok(())
+}
+
+pub(crate) async fn read_file(filename: &str) -> Result<Vec<u8>, String> {
+    let file = file_handle(filename, false).await?;
+    let blob: Blob = resolve(file.get_file()).await?;
+    let buffer = JsFuture::from(blob.array_buffer()).await.map_err(js_error)?;
+    Ok(Uint8Array::new(&buffer).to_vec())
+}
+
+fn storage() -> Result<StorageManager, String> {
+    let global = js_sys::global();
+    if let Some(window) = global.dyn_ref::<web_sys::Window>() {
+        Ok(window.navigator().storage())
+    } else if let Some(worker) = global.dyn_ref::<WorkerGlobalScope>() {
+        Ok(worker.navigator().storage())
+    } else {
+        Err("OPFS is not available in this environment".to_string())
+    }
+}
+
+async fn file_handle(filename: &str, create: bool) -> Result<FileSystemFileHandle, String> {
+    let root: FileSystemDirectoryHandle = resolve(storage()?.get_directory()).await?;
+    let options = FileSystemGetFileOptions::new();
+    options.set_create(create);
+    resolve(root.get_file_handle_with_options(filename, &options)).await
+}
+
+async fn resolve<T: JsCast>(promise: Promise) -> Result<T, String> {
+    let value = JsFuture::from(promise).await.map_err(js_error)?;
+    value.dyn_into::<T>().map_err(js_error)
+}
+
+fn js_error(value: JsValue) -> String {
+    value.as_string().unwrap_or_else(|| format!("{:?}", value))
+}
@@ -0,0 +1,141 @@
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
+use core::cmp::Ordering;
+use core::fmt;
+
+use crate::aggregate::compare;
+use crate::{identifier, pseudo};
+use crate::prelude::*;
+use crate::{CompareAs, Database, Record, Table};
+
+// Collations every database has, named after the ways a column can compare.
+const BUILT_IN: &[(&str, CompareAs)] = &[
+    ("string", CompareAs::String),
+    ("numeric", CompareAs::Numeric),
+    ("date", CompareAs::Date),
+    ("natural", CompareAs::Natural),
+    ("value", CompareAs::Value),
+];
+
+type Comparator = Arc<dyn Fn(&str, &str) -> Ordering + Send + Sync>;
+
+#[derive(Clone, Default)]
+pub(crate) struct Collations {
+    custom: HashMap<String, Comparator>,
+}
+
+// One key of an ORDER BY: `column [COLLATE name] [ASC | DESC] [NULLS FIRST
+// | NULLS LAST]`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct OrderKey {
+    column: String,
+    collation: Option<String>,
+    descending: bool,
+    // None leaves rows without the column where the `nulls` setting puts them
+    nulls_last: Option<bool>,
+}
+
+impl fmt::Display for OrderKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.column)?;
+        if let Some(collation) = &self.collation {
+            write!(f, " COLLATE {}", collation)?;
+        }
+        if self.descending {
+            write!(f, " DESC")?;
+        }
+        match self.nulls_last {
+            Some(true) => write!(f, " NULLS LAST"),
+            Some(false) => write!(f, " NULLS FIRST"),
+            None => Ok(()),
+        }
+    }
+}
+
+// The keys of the tokens after `ORDER BY`, separated by commas.
+pub(crate) fn parse(tokens: &[&str]) -> Result<Vec<OrderKey>, String> {
+    let text = tokens.join(" ");
+    let text = text.trim_end_matches(';');
+    text.split(',')
+        .map(|key| {
+            let invalid = || format!("Invalid ORDER BY key '{}'", key.trim());
+            let words = identifier::split(key);
+            let (column, mut rest) = words.split_first().ok_or_else(invalid)?;
+            let mut order = OrderKey { column: identifier::unquote(column), collation: None, descending: false, nulls_last: None };
+            if let [collate, name, tail @ ..] = rest {
+                if collate.eq_ignore_ascii_case("COLLATE") {
+                    order.collation = Some(name.to_lowercase());
+                    rest = tail;
+                }
+            }
+            if let [direction, tail @ ..] = rest {
+                if direction.eq_ignore_ascii_case("ASC") || direction.eq_ignore_ascii_case("DESC") {
+                    order.descending = direction.eq_ignore_ascii_case("DESC");
+                    rest = tail;
+                }
+            }
+            match rest {
+                [] => {}
+                [nulls, position] if nulls.eq_ignore_ascii_case("NULLS") && position.eq_ignore_ascii_case("FIRST") => order.nulls_last = Some(false),
+                [nulls, position] if nulls.eq_ignore_ascii_case("NULLS") && position.eq_ignore_ascii_case("LAST") => order.nulls_last = Some(true),
+                _ => return Err(invalid()),
+            }
+            Ok(order)
+        })
+        .collect()
+}
+
+impl Database {
+    /// Adds a collation ORDER BY can name with `COLLATE name`, ordering
+    /// values with `compare`, such as by version number. The collations
+    /// `string`, `numeric`, `date` and `natural` are built in and compare
+    /// as the [`CompareAs`] of the same name.
+    pub fn register_collation(&mut self, name: &str, compare: impl Fn(&str, &str) -> Ordering + Send + Sync + 'static) -> Result<(), String> {
+        let name = name.to_lowercase();
+        if BUILT_IN.iter().any(|(built_in, _)| *built_in == name) {
+            return Err(format!("Collation '{}' is built in", name));
+        }
+        self.collations.custom.insert(name, Arc::new(compare));
+        Ok(())
+    }
+
+    // Sorts rows of `table` by `keys`, each compared by its collation, or
+    // else as WHERE compares the column, with numbers in untyped columns
+    // compared as numbers.
+    pub(crate) fn sort_rows(&self, table: &Table, keys: &[OrderKey], rows: &mut [Cow<'_, Record>]) -> Result<(), String> {
+        let comparators = keys.iter()
+            .map(|key| -> Result<Comparator, String> {
+                let compare_as = match &key.collation {
+                    Some(name) => match BUILT_IN.iter().find(|(built_in, _)| built_in == name) {
+                        Some((_, compare_as)) => Some(*compare_as),
+                        None => return self.collations.custom.get(name).cloned().ok_or(format!("Collation '{}' not found", name)),
+                    },
+                    None if table.proto.as_ref().is_some_and(|p| p.is_numeric(&key.column)) => Some(CompareAs::Numeric),
+                    None => table.compare.get(&key.column).copied().or_else(|| table.declared_compare(&key.column)),
+                };
+                Ok(match compare_as {
+                    Some(compare_as) => Arc::new(move |a: &str, b: &str| compare_as.sort(a, b)),
+                    None => Arc::new(compare),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        rows.sort_by(|a, b| {
+            keys.iter().zip(&comparators)
+                .map(|(key, compare)| {
+                    let (a, b) = (pseudo::column(a, &key.column), pseudo::column(b, &key.column));
+                    let ordering = match (&a, &b) {
+                        (Some(a), Some(b)) => compare(a, b),
+                        _ => a.is_some().cmp(&b.is_some()),
+                    };
+                    match key.nulls_last.or(self.settings.nulls_last) {
+                        Some(last) if a.is_none() != b.is_none() => (a.is_some() ^ last).cmp(&(b.is_some() ^ last)),
+                        _ if key.descending => ordering.reverse(),
+                        _ => ordering,
+                    }
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use core::fmt;
+
+use crate::prelude::*;
+
+// The `LIMIT n OFFSET m` of a SELECT: of the rows it would return, in the
+// order it returns them, the first `limit` after skipping `offset`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Page {
+    // None returns every row after the offset
+    pub(crate) limit: Option<usize>,
+    pub(crate) offset: usize,
+}
+
+impl Page {
+    // Splits a trailing `LIMIT n`, `LIMIT n OFFSET m` or `OFFSET m` off
+    // `tokens`.
+    pub(crate) fn split<'a, 't>(tokens: &'a [&'t str]) -> Result<(&'a [&'t str], Page), String> {
+        let is = |token: &str, keyword: &str| token.eq_ignore_ascii_case(keyword);
+        let count = |n: &str, clause: &str| n.trim_end_matches(';').parse::<usize>().map_err(|_| format!("Invalid {} '{}'", clause, n));
+        match tokens {
+            [rest @ .., limit, n, offset, m] if is(limit, "LIMIT") && is(offset, "OFFSET") => {
+                Ok((rest, Page { limit: Some(count(n, "LIMIT")?), offset: count(m, "OFFSET")? }))
+            }
+            [rest @ .., limit, n] if is(limit, "LIMIT") => Ok((rest, Page { limit: Some(count(n, "LIMIT")?), offset: 0 })),
+            [rest @ .., offset, m] if is(offset, "OFFSET") => Ok((rest, Page { limit: None, offset: count(m, "OFFSET")? })),
+            _ => Ok((tokens, Page::default())),
+        }
+    }
+
+    pub(crate) fn is_all(self) -> bool {
+        self == Page::default()
+    }
+
+    // Whether `kept` rows after the offset fill the page.
+    pub(crate) fn is_full(self, kept: usize) -> bool {
+        self.limit.is_some_and(|limit| kept >= limit)
+    }
+
+    // The rows of the page, of `rows` that many rows read in all.
+    pub(crate) fn size(self, rows: usize) -> usize {
+        rows.saturating_sub(self.offset).min(self.limit.unwrap_or(usize::MAX))
+    }
+}
+
+impl fmt::Display for Page {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut clauses = Vec::new();
+        if let Some(limit) = self.limit {
+            clauses.push(format!("LIMIT {}", limit));
+        }
+        if self.offset > 0 {
+            clauses.push(format!("OFFSET {}", self.offset));
+        }
+        write!(f, "{}", clauses.join(" "))
+    }
+}
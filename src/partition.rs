@@ -0,0 +1,237 @@
+use alloc::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+use crate::{CompareAs, Database, Record, Row};
+#[cfg(feature = "sql")]
+use crate::{interrupt, pseudo, telemetry, Condition, Table};
+
+/// How the records of a table are split into partitions.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PartitionScheme {
+    /// Spreads records over `partitions` buckets by a hash of `column`.
+    Hash { column: String, partitions: usize },
+    /// Splits records at the given ascending `bounds` of `column`: partition
+    /// `i` holds values below `bounds[i]` and at or above `bounds[i - 1]`.
+    /// Values compare as strings, the same way WHERE clauses compare them.
+    Range { column: String, bounds: Vec<String> },
+}
+
+// Record ids per partition. The last segment holds records without the
+// partition column. Segments are rebuilt from the records when a table loads.
+#[derive(Clone)]
+pub(crate) struct Partitions {
+    scheme: PartitionScheme,
+    segments: Vec<BTreeSet<u64>>,
+}
+
+impl PartitionScheme {
+    pub(crate) fn column(&self) -> &str {
+        match self {
+            PartitionScheme::Hash { column, .. } | PartitionScheme::Range { column, .. } => column,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            PartitionScheme::Hash { partitions: 0, .. } => Err("A hash partitioning needs at least one partition".to_string()),
+            PartitionScheme::Range { bounds, .. } if bounds.windows(2).any(|w| w[0] >= w[1]) => {
+                Err("Range partition bounds must be strictly ascending".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn partition_count(&self) -> usize {
+        match self {
+            PartitionScheme::Hash { partitions, .. } => *partitions,
+            PartitionScheme::Range { bounds, .. } => bounds.len() + 1,
+        }
+    }
+
+    fn partition_of(&self, value: &str) -> usize {
+        match self {
+            PartitionScheme::Hash { partitions, .. } => (fnv1a(value.as_bytes()) % *partitions as u64) as usize,
+            PartitionScheme::Range { bounds, .. } => bounds.partition_point(|b| b.as_str() <= value),
+        }
+    }
+
+    // The partitions that can hold a record matching `condition`, or None if
+    // the condition does not narrow the search.
+    #[cfg(feature = "sql")]
+    fn candidates(&self, condition: &Condition) -> Option<BTreeSet<usize>> {
+        let column = self.column();
+        let last = self.partition_count() - 1;
+        match (self, condition) {
+            (_, Condition::Equals(c, v)) if c == column => Some(BTreeSet::from([self.partition_of(v)])),
+            (PartitionScheme::Range { .. }, Condition::GreaterThan(c, v)) if c == column => Some((self.partition_of(v)..=last).collect()),
+            (PartitionScheme::Range { .. }, Condition::LessThan(c, v)) if c == column => Some((0..=self.partition_of(v)).collect()),
+            (PartitionScheme::Range { .. }, Condition::Between(c, low, high)) if c == column => Some((self.partition_of(low)..=self.partition_of(high)).collect()),
+            (_, Condition::And(left, right)) => match (self.candidates(left), self.candidates(right)) {
+                (Some(l), Some(r)) => Some(l.intersection(&r).copied().collect()),
+                (l, r) => l.or(r),
+            },
+            (_, Condition::Or(left, right)) => {
+                let (l, r) = (self.candidates(left)?, self.candidates(right)?);
+                Some(l.union(&r).copied().collect())
+            }
+            _ => None,
+        }
+    }
+}
+
+// FNV-1a, so hash partitions are the same on every platform and release.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+impl Partitions {
+    pub(crate) fn new(scheme: PartitionScheme, records: &[Record]) -> Self {
+        let segments = vec![BTreeSet::new(); scheme.partition_count() + 1];
+        let mut partitions = Partitions { scheme, segments };
+        for record in records {
+            partitions.place(record.id, &record.data);
+        }
+        partitions
+    }
+
+    pub(crate) fn scheme(&self) -> &PartitionScheme {
+        &self.scheme
+    }
+
+    // Record ids listed across all partitions.
+    pub(crate) fn len(&self) -> usize {
+        self.segments.iter().map(BTreeSet::len).sum()
+    }
+
+    fn segment_of(&self, data: &Row) -> usize {
+        match data.get(self.scheme.column()) {
+            Some(value) => self.scheme.partition_of(value),
+            None => self.segments.len() - 1,
+        }
+    }
+
+    pub(crate) fn place(&mut self, id: u64, data: &Row) {
+        let segment = self.segment_of(data);
+        self.segments[segment].insert(id);
+    }
+
+    pub(crate) fn remove(&mut self, id: u64, data: &Row) {
+        let segment = self.segment_of(data);
+        self.segments[segment].remove(&id);
+    }
+
+    #[cfg(feature = "sql")]
+    fn candidates(&self, condition: &Option<Condition>) -> Option<BTreeSet<usize>> {
+        // values are filed by their exact text, so ignoring case reads them all
+        if !interrupt::case_sensitive() {
+            return None;
+        }
+        self.scheme.candidates(condition.as_ref()?)
+    }
+
+    // Records filed in the wrong partition, and ids of records that are gone.
+    pub(crate) fn problems(&self, records: &[Record]) -> Vec<(Option<u64>, String)> {
+        let mut problems: Vec<_> = records.iter()
+            .filter(|r| !self.segments[self.segment_of(&r.data)].contains(&r.id))
+            .map(|r| (Some(r.id), "record is missing from its partition".to_string()))
+            .collect();
+        let filed = self.len();
+        if filed != records.len() {
+            problems.push((None, format!("partitions list {} records but the table has {}", filed, records.len())));
+        }
+        problems
+    }
+}
+
+#[cfg(feature = "sql")]
+impl Table {
+    // Records that may match `condition`, in storage order: the record of
+    // the id it requires, looked up in the index, or else those of the
+    // partitions it doesn't rule out.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "potatodb.plan", level = "debug", skip_all, fields(table = %self.name, rows)))]
+    pub(crate) fn scan(&self, condition: &Option<Condition>) -> Vec<&Record> {
+        let pruned = self.partitions.as_ref().and_then(|p| Some((p, p.candidates(condition)?)));
+        let rows: Vec<&Record> = match (condition.as_ref().and_then(pseudo::required_id), pruned) {
+            (Some(id), _) => {
+                interrupt::used_index();
+                self.index.get(&id).map(|&i| &self.records[i]).into_iter().collect()
+            }
+            (None, None) => self.records.iter().collect(),
+            (None, Some((partitions, candidates))) => {
+                interrupt::used_index();
+                let mut positions: Vec<usize> = candidates.iter()
+                    .flat_map(|&s| &partitions.segments[s])
+                    .filter_map(|id| self.index.get(id).copied())
+                    .collect();
+                positions.sort_unstable();
+                positions.into_iter().map(|i| &self.records[i]).collect()
+            }
+        };
+        telemetry::rows_scanned(&self.name, rows.len());
+        telemetry::record("rows", rows.len());
+        rows
+    }
+}
+
+#[cfg(feature = "sql")]
+impl Table {
+    // How many records a scan for `condition` reads.
+    pub(crate) fn scan_estimate(&self, condition: &Option<Condition>) -> usize {
+        if let Some(id) = condition.as_ref().and_then(pseudo::required_id) {
+            return self.index.contains_key(&id) as usize;
+        }
+        match self.partitions.as_ref().and_then(|p| Some((p, p.candidates(condition)?))) {
+            Some((partitions, candidates)) => candidates.iter().map(|&s| partitions.segments[s].len()).sum(),
+            None => self.records.len(),
+        }
+    }
+
+    // What a scan for `condition` reads, for query plans.
+    pub(crate) fn describe_scan(&self, condition: &Option<Condition>) -> String {
+        if let Some(id) = condition.as_ref().and_then(pseudo::required_id) {
+            return format!("lookup of id {} in {}", id, self.name);
+        }
+        let pruned = self.partitions.as_ref().and_then(|p| Some((p, p.candidates(condition)?)));
+        match pruned {
+            Some((partitions, candidates)) => format!(
+                "scan of {} partitions {:?} of {}",
+                self.name, candidates, partitions.segments.len(),
+            ),
+            None => format!("full scan of {} ({} rows)", self.name, self.records.len()),
+        }
+    }
+}
+
+impl Database {
+    /// Partitions a table so queries filtering on the partition column only
+    /// scan the partitions that can match.
+    pub fn partition_table(&mut self, table_name: &str, scheme: PartitionScheme) -> Result<(), String> {
+        scheme.validate()?;
+        let table = self.tables.get_mut(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        if table.compare.get(scheme.column()).is_some_and(|&compare| compare != CompareAs::String) {
+            return Err(format!("Column '{}' does not compare as text, so it cannot partition a table", scheme.column()));
+        }
+        table.partitions = Some(Partitions::new(scheme, &table.records));
+        Ok(())
+    }
+
+    pub fn table_partitioning(&self, table_name: &str) -> Result<Option<&PartitionScheme>, String> {
+        let table = self.tables.get(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        Ok(table.partitions.as_ref().map(Partitions::scheme))
+    }
+
+    /// The partitions a SELECT would scan, or None if its table is not
+    /// partitioned. The last partition of a table holds records without the
+    /// partition column.
+    #[cfg(feature = "sql")]
+    pub fn scanned_partitions(&self, sql: &str) -> Result<Option<Vec<usize>>, String> {
+        let (table, condition) = self.select_target(sql)?;
+        let table = self.tables.get(&table).ok_or(format!("Table '{}' not found", table))?;
+        Ok(table.partitions.as_ref().map(|p| match p.candidates(&condition) {
+            Some(candidates) => candidates.into_iter().collect(),
+            None => (0..p.segments.len()).collect(),
+        }))
+    }
+}
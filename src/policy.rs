@@ -0,0 +1,160 @@
+
+use crate::{Condition, Database, Record, SqlStatement};
+use crate::prelude::*;
+
+#[derive(Clone, Default)]
+pub(crate) struct Policies {
+    // table -> predicates, which may name session attributes as `$name`
+    tables: HashMap<String, Vec<Condition>>,
+    attributes: HashMap<String, String>,
+}
+
+impl Condition {
+    // The condition with every `$name` value replaced by that attribute.
+    fn bind(&self, attributes: &HashMap<String, String>) -> Result<Condition, String> {
+        let value = |v: &String| match v.strip_prefix('$') {
+            Some(name) => attributes.get(name).cloned().ok_or(format!("Session attribute '{}' is not set", name)),
+            None => Ok(v.clone()),
+        };
+        Ok(match self {
+            Condition::Equals(c, v) => Condition::Equals(c.clone(), value(v)?),
+            Condition::NotEquals(c, v) => Condition::NotEquals(c.clone(), value(v)?),
+            Condition::GreaterThan(c, v) => Condition::GreaterThan(c.clone(), value(v)?),
+            Condition::LessThan(c, v) => Condition::LessThan(c.clone(), value(v)?),
+            Condition::Between(c, low, high) => Condition::Between(c.clone(), value(low)?, value(high)?),
+            Condition::Like(c, pattern) => Condition::Like(c.clone(), value(pattern)?),
+            Condition::IsNull(_) | Condition::IsNotNull(_) => self.clone(),
+            Condition::And(l, r) => Condition::And(Box::new(l.bind(attributes)?), Box::new(r.bind(attributes)?)),
+            Condition::Or(l, r) => Condition::Or(Box::new(l.bind(attributes)?), Box::new(r.bind(attributes)?)),
+        })
+    }
+
+    fn rename_column(&mut self, from: &str, to: &str) {
+        match self {
+            Condition::Equals(c, _) | Condition::NotEquals(c, _) | Condition::GreaterThan(c, _) | Condition::LessThan(c, _) | Condition::Between(c, _, _) | Condition::Like(c, _) | Condition::IsNull(c) | Condition::IsNotNull(c) => {
+                if c == from {
+                    *c = to.to_string();
+                }
+            }
+            Condition::And(l, r) | Condition::Or(l, r) => {
+                l.rename_column(from, to);
+                r.rename_column(from, to);
+            }
+        }
+    }
+
+    fn reads(&self, column: &str) -> bool {
+        match self {
+            Condition::Equals(c, _) | Condition::NotEquals(c, _) | Condition::GreaterThan(c, _) | Condition::LessThan(c, _) | Condition::Between(c, _, _) | Condition::Like(c, _) | Condition::IsNull(c) | Condition::IsNotNull(c) => c == column,
+            Condition::And(l, r) | Condition::Or(l, r) => l.reads(column) || r.reads(column),
+        }
+    }
+}
+
+impl Policies {
+    // Every policy on `table` for the current session, as one condition.
+    pub(crate) fn condition(&self, table: &str) -> Result<Option<Condition>, String> {
+        let bound = self.tables.get(table).into_iter().flatten()
+            .map(|policy| policy.bind(&self.attributes))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(bound.into_iter().reduce(|acc, c| Condition::And(Box::new(acc), Box::new(c))))
+    }
+
+    pub(crate) fn rename_column(&mut self, table: &str, from: &str, to: &str) {
+        for policy in self.tables.get_mut(table).into_iter().flatten() {
+            policy.rename_column(from, to);
+        }
+    }
+
+    pub(crate) fn restricts(&self, table: &str) -> bool {
+        self.tables.get(table).is_some_and(|policies| !policies.is_empty())
+    }
+}
+
+fn restrict(condition: &mut Option<Condition>, policy: Condition) {
+    *condition = Some(match condition.take() {
+        Some(existing) => Condition::And(Box::new(existing), Box::new(policy)),
+        None => policy,
+    });
+}
+
+impl Database {
+    /// Adds a row policy to a table: a WHERE predicate such as
+    /// `tenant = $tenant` that every SQL statement on the table is limited
+    /// to, with `$name` standing for a session attribute. SELECT, UPDATE and
+    /// DELETE only see matching rows, INSERT may only add them, and UPDATE
+    /// may not set the columns a policy reads. A row must match every policy
+    /// on its table. The Rust API is not limited, and policies are not saved
+    /// with the database.
+    pub fn add_policy(&mut self, table_name: &str, predicate: &str) -> Result<(), String> {
+        let tokens: Vec<&str> = core::iter::once("WHERE").chain(crate::identifier::split(predicate)).collect();
+        let condition = self.parse_where_clause(&tokens).ok().flatten().ok_or(format!("Invalid policy predicate '{}'", predicate))?;
+        self.policies.tables.entry(table_name.to_string()).or_default().push(condition);
+        Ok(())
+    }
+
+    pub fn clear_policies(&mut self, table_name: &str) {
+        self.policies.tables.remove(table_name);
+    }
+
+    /// Sets a value policies can refer to as `$name`. A statement on a table
+    /// whose policies name an attribute that is not set fails.
+    pub fn set_session_attribute(&mut self, name: &str, value: &str) {
+        self.policies.attributes.insert(name.to_string(), value.to_string());
+    }
+
+    pub fn clear_session_attributes(&mut self) {
+        self.policies.attributes.clear();
+    }
+
+    // Limits a statement to the rows its table's policies allow.
+    pub(crate) fn apply_policies(&self, statement: SqlStatement) -> Result<SqlStatement, String> {
+        let Some(policy) = self.policies.condition(statement.table())? else {
+            return Ok(statement);
+        };
+        Ok(match statement {
+            SqlStatement::Select { table, columns, mut condition, as_of, sample, hints, order_by, page, group_by } => {
+                restrict(&mut condition, policy);
+                SqlStatement::Select { table, columns, condition, as_of, sample, hints, order_by, page, group_by }
+            }
+            SqlStatement::Delete { table, mut condition } => {
+                restrict(&mut condition, policy);
+                SqlStatement::Delete { table, condition }
+            }
+            SqlStatement::Update { table, column, value, mut condition } => {
+                if policy.reads(&column) {
+                    return Err(format!("Cannot update column '{}' read by a row policy on '{}'", column, table));
+                }
+                restrict(&mut condition, policy);
+                SqlStatement::Update { table, column, value, condition }
+            }
+            SqlStatement::Increment { table, column, delta, mut condition } => {
+                if policy.reads(&column) {
+                    return Err(format!("Cannot update column '{}' read by a row policy on '{}'", column, table));
+                }
+                restrict(&mut condition, policy);
+                SqlStatement::Increment { table, column, delta, condition }
+            }
+            SqlStatement::Insert { table, columns, values } => {
+                let data = columns.iter().cloned().zip(values.iter().cloned()).collect();
+                let record = Record { id: 0, data };
+                let allowed = match self.tables.get(&table) {
+                    Some(target) => target.matches(&record, &policy),
+                    None => self.evaluate_condition(&record, &Some(policy)),
+                };
+                if !allowed {
+                    return Err(format!("New row violates a row policy on '{}'", table));
+                }
+                SqlStatement::Insert { table, columns, values }
+            }
+            SqlStatement::Graph { table, query, mut condition } => {
+                restrict(&mut condition, policy);
+                SqlStatement::Graph { table, query, condition }
+            }
+            SqlStatement::Explain { analyze, statement } => {
+                SqlStatement::Explain { analyze, statement: Box::new(self.apply_policies(*statement)?) }
+            }
+            statement @ (SqlStatement::CreateTable { .. } | SqlStatement::CreateSchema(_) | SqlStatement::DropSchema(_) | SqlStatement::Pragma(_) | SqlStatement::Set(..) | SqlStatement::Analyze(_)) => statement,
+        })
+    }
+}
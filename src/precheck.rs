@@ -0,0 +1,54 @@
+use crate::prelude::*;
+
+/// The error of a statement with nothing in it but whitespace, comments and
+/// semicolons.
+pub const EMPTY_STATEMENT: &str = "Empty statement";
+
+/// How the error of a statement cut off where more has to follow begins;
+/// the rest names the last word read, as in
+/// `Unexpected end of statement after 'FROM'`.
+pub const UNEXPECTED_END: &str = "Unexpected end of statement";
+
+// The words statements start with; none is a whole statement.
+pub(crate) const STATEMENTS: &[&str] = &["ANALYZE", "CREATE", "DELETE", "DROP", "EXPLAIN", "GRAPH", "INSERT", "PRAGMA", "SELECT", "SET", "UPDATE"];
+
+// Keywords and operators a statement can't end with; other words may be
+// names, but a name spelling a keyword has to be quoted, which
+// `quote_identifier` does for the words here.
+pub(crate) const OPERAND_TAKING: &[&str] = &[
+    "AND", "ANALYZE", "AS", "BETWEEN", "BY", "CREATE", "DELETE", "DROP", "EXPLAIN", "FROM", "GROUP", "HAVING", "INSERT", "INTO",
+    "IS", "LIKE", "LIMIT", "NOT", "OF", "OFFSET", "OR", "ORDER", "PRAGMA", "SCHEMA", "SELECT", "SET", "TABLE", "TABLESAMPLE", "TEMP",
+    "TEMPORARY", "UPDATE", "VALUES", "WHERE", "=", "!=", "<", ">",
+];
+
+// Words that a single word after them can't finish: a column needs a
+// comparison, and a setting a value.
+const CLAUSE_STARTING: &[&str] = &["AND", "BETWEEN", "HAVING", "OR", "SET", "WHERE"];
+
+pub(crate) const COMPARISONS: &[&str] = &["=", "!=", "<", ">", "LIKE"];
+
+fn is_one_of(token: &str, words: &[&str]) -> bool {
+    words.iter().any(|w| w.eq_ignore_ascii_case(token))
+}
+
+// Rejects a statement, split into tokens, that is empty or stops where its
+// grammar needs more, before the parser reads past its end. A value
+// compared against is taken as a value even if it spells a keyword.
+pub(crate) fn check(tokens: &[&str]) -> Result<(), String> {
+    let tokens: Vec<&str> = tokens.iter().map(|t| t.trim_end_matches(';')).filter(|t| !t.is_empty()).collect();
+    let Some((last, before)) = tokens.split_last() else {
+        return Err(EMPTY_STATEMENT.to_string());
+    };
+    let previous = before.last().copied().unwrap_or_default();
+    let compared = is_one_of(previous, COMPARISONS);
+    // `BETWEEN low AND high` ends with a value after AND
+    let high = before.len() >= 3 && is_one_of(before[before.len() - 3], &["BETWEEN"]);
+    let cut = (before.is_empty() && is_one_of(last, STATEMENTS))
+        || (!compared && !high && is_one_of(last, OPERAND_TAKING))
+        || last.ends_with([',', '('])
+        || (!high && is_one_of(previous, CLAUSE_STARTING));
+    match cut {
+        true => Err(format!("{} after '{}'", UNEXPECTED_END, last)),
+        false => Ok(()),
+    }
+}
@@ -0,0 +1,93 @@
+// What modules of the engine would get from the std prelude, taken from
+// alloc so they build without std, and what stands in for the parts of std
+// they use when it is left out: hashbrown's maps, spin's locks and libm's
+// float functions.
+pub(crate) use alloc::boxed::Box;
+pub(crate) use alloc::string::{String, ToString};
+pub(crate) use alloc::vec::Vec;
+pub(crate) use alloc::{format, vec};
+
+#[cfg(feature = "std")]
+pub(crate) use std::collections::{HashMap, HashSet};
+#[cfg(not(feature = "std"))]
+pub(crate) use hashbrown::{HashMap, HashSet};
+
+#[cfg(feature = "std")]
+pub(crate) use std::sync::{Mutex, MutexGuard, OnceLock};
+#[cfg(not(feature = "std"))]
+pub(crate) use spin::{Mutex, MutexGuard};
+
+// A poisoned lock only means another thread panicked holding it.
+#[cfg(feature = "std")]
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock()
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) struct OnceLock<T>(spin::Once<T>);
+
+#[cfg(not(feature = "std"))]
+impl<T> Default for OnceLock<T> {
+    fn default() -> Self {
+        OnceLock::new()
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<T> OnceLock<T> {
+    pub(crate) const fn new() -> Self {
+        OnceLock(spin::Once::new())
+    }
+
+    pub(crate) fn get_or_init(&self, init: impl FnOnce() -> T) -> &T {
+        self.0.call_once(init)
+    }
+}
+
+// The f64 functions std gets from the platform's libm.
+#[cfg(not(feature = "std"))]
+pub(crate) trait Float {
+    fn ceil(self) -> Self;
+    fn floor(self) -> Self;
+    fn fract(self) -> Self;
+    fn ln(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn round(self) -> Self;
+    fn trunc(self) -> Self;
+}
+
+#[cfg(not(feature = "std"))]
+impl Float for f64 {
+    fn ceil(self) -> f64 {
+        libm::ceil(self)
+    }
+
+    fn floor(self) -> f64 {
+        libm::floor(self)
+    }
+
+    fn fract(self) -> f64 {
+        self - libm::trunc(self)
+    }
+
+    fn ln(self) -> f64 {
+        libm::log(self)
+    }
+
+    fn powi(self, n: i32) -> f64 {
+        libm::pow(self, n as f64)
+    }
+
+    fn round(self) -> f64 {
+        libm::round(self)
+    }
+
+    fn trunc(self) -> f64 {
+        libm::trunc(self)
+    }
+}
@@ -0,0 +1,277 @@
+use serde::{Serialize, Deserialize};
+
+use crate::{Database, Row};
+use crate::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ProtoType {
+    Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Sint32,
+    Sint64,
+    Bool,
+    Double,
+    Float,
+    String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProtoField {
+    name: String,
+    number: u32,
+    ty: ProtoType,
+}
+
+/// A protobuf message definition whose fields map one-to-one onto table columns.
+/// Records of a table with a message are stored as encoded messages on disk.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProtoMessage {
+    name: String,
+    fields: Vec<ProtoField>,
+}
+
+const VARINT: u64 = 0;
+const FIXED64: u64 = 1;
+const LENGTH_DELIMITED: u64 = 2;
+const FIXED32: u64 = 5;
+
+const MAX_FIELD_NUMBER: u32 = (1 << 29) - 1;
+const RESERVED_FIELD_NUMBERS: core::ops::RangeInclusive<u32> = 19000..=19999;
+
+impl ProtoMessage {
+    pub fn new(name: &str) -> Self {
+        ProtoMessage { name: name.to_string(), fields: Vec::new() }
+    }
+
+    pub fn field(mut self, name: &str, number: u32, ty: ProtoType) -> Result<Self, String> {
+        if !(1..=MAX_FIELD_NUMBER).contains(&number) || RESERVED_FIELD_NUMBERS.contains(&number) {
+            return Err(format!("Field number {} of '{}' is not a valid protobuf field number", number, name));
+        }
+        if self.fields.iter().any(|f| f.name == name) {
+            return Err(format!("Message '{}' already has a field named '{}'", self.name, name));
+        }
+        if let Some(other) = self.fields.iter().find(|f| f.number == number) {
+            return Err(format!("Field number {} is already used by '{}' in message '{}'", number, other.name, self.name));
+        }
+        self.fields.push(ProtoField { name: name.to_string(), number, ty });
+        Ok(self)
+    }
+
+    pub(crate) fn columns(&self) -> Vec<String> {
+        self.fields.iter().map(|f| f.name.clone()).collect()
+    }
+
+    #[cfg(feature = "sql")]
+    pub(crate) fn check_column(&self, column: &str) -> Result<(), String> {
+        self.by_name(column).map(|_| ())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn type_of(&self, column: &str) -> Option<ProtoType> {
+        self.by_name(column).ok().map(|field| field.ty)
+    }
+
+    pub(crate) fn rename_field(&mut self, from: &str, to: &str) {
+        self.fields.iter_mut().filter(|f| f.name == from).for_each(|f| f.name = to.to_string());
+    }
+
+    pub(crate) fn retype_field(&mut self, column: &str, ty: ProtoType) {
+        self.fields.iter_mut().filter(|f| f.name == column).for_each(|f| f.ty = ty);
+    }
+
+    fn by_name(&self, column: &str) -> Result<&ProtoField, String> {
+        self.fields.iter().find(|f| f.name == column)
+            .ok_or_else(|| format!("Column '{}' is not a field of message '{}'", column, self.name))
+    }
+
+    // Whether `column` holds numbers, which WHERE compares as numbers.
+    #[cfg(feature = "sql")]
+    pub(crate) fn is_numeric(&self, column: &str) -> bool {
+        self.by_name(column).is_ok_and(|field| !matches!(field.ty, ProtoType::Bool | ProtoType::String))
+    }
+
+    pub(crate) fn validate_value(&self, column: &str, value: &str) -> Result<(), String> {
+        let field = self.by_name(column)?;
+        let valid = match field.ty {
+            ProtoType::Int32 | ProtoType::Sint32 => value.parse::<i32>().is_ok(),
+            ProtoType::Int64 | ProtoType::Sint64 => value.parse::<i64>().is_ok(),
+            ProtoType::Uint32 => value.parse::<u32>().is_ok(),
+            ProtoType::Uint64 => value.parse::<u64>().is_ok(),
+            ProtoType::Bool => value.parse::<bool>().is_ok(),
+            ProtoType::Double => value.parse::<f64>().is_ok(),
+            ProtoType::Float => value.parse::<f32>().is_ok(),
+            ProtoType::String => true,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("Value '{}' is not a valid {:?} for field '{}' of message '{}'", value, field.ty, column, self.name))
+        }
+    }
+
+    pub(crate) fn validate(&self, data: &Row) -> Result<(), String> {
+        data.iter().try_for_each(|(column, value)| self.validate_value(column, value))
+    }
+
+    pub fn encode(&self, data: &Row) -> Result<Vec<u8>, String> {
+        self.validate(data)?;
+        let mut buf = Vec::new();
+        for field in &self.fields {
+            let Some(value) = data.get(&field.name) else {
+                continue;
+            };
+            let key = (field.number as u64) << 3;
+            // validate() guarantees the parses below succeed
+            match field.ty {
+                ProtoType::Int32 | ProtoType::Int64 => {
+                    put_varint(&mut buf, key | VARINT);
+                    put_varint(&mut buf, value.parse::<i64>().unwrap() as u64);
+                }
+                ProtoType::Uint32 | ProtoType::Uint64 => {
+                    put_varint(&mut buf, key | VARINT);
+                    put_varint(&mut buf, value.parse::<u64>().unwrap());
+                }
+                ProtoType::Sint32 | ProtoType::Sint64 => {
+                    let n = value.parse::<i64>().unwrap();
+                    put_varint(&mut buf, key | VARINT);
+                    put_varint(&mut buf, ((n << 1) ^ (n >> 63)) as u64);
+                }
+                ProtoType::Bool => {
+                    put_varint(&mut buf, key | VARINT);
+                    put_varint(&mut buf, value.parse::<bool>().unwrap() as u64);
+                }
+                ProtoType::Double => {
+                    put_varint(&mut buf, key | FIXED64);
+                    buf.extend_from_slice(&value.parse::<f64>().unwrap().to_le_bytes());
+                }
+                ProtoType::Float => {
+                    put_varint(&mut buf, key | FIXED32);
+                    buf.extend_from_slice(&value.parse::<f32>().unwrap().to_le_bytes());
+                }
+                ProtoType::String => {
+                    put_varint(&mut buf, key | LENGTH_DELIMITED);
+                    put_varint(&mut buf, value.len() as u64);
+                    buf.extend_from_slice(value.as_bytes());
+                }
+            }
+        }
+        Ok(buf)
+    }
+
+    pub fn decode(&self, mut bytes: &[u8]) -> Result<Row, String> {
+        let mut data = Row::new();
+        while !bytes.is_empty() {
+            let key = take_varint(&mut bytes)?;
+            let wire_type = key & 7;
+            let payload = match wire_type {
+                VARINT => Payload::Varint(take_varint(&mut bytes)?),
+                FIXED64 => Payload::Fixed(take(&mut bytes, 8)?),
+                FIXED32 => Payload::Fixed(take(&mut bytes, 4)?),
+                LENGTH_DELIMITED => {
+                    let len = take_varint(&mut bytes)? as usize;
+                    Payload::Fixed(take(&mut bytes, len)?)
+                }
+                _ => return Err(format!("Unsupported wire type {} in message '{}'", wire_type, self.name)),
+            };
+            // unknown fields are skipped, as protobuf readers are expected to do
+            let Some(field) = self.fields.iter().find(|f| f.number as u64 == key >> 3) else {
+                continue;
+            };
+            let value = match (field.ty, payload) {
+                (ProtoType::Int32, Payload::Varint(n)) => in_range::<i32, _>(n as i64, field)?,
+                (ProtoType::Int64, Payload::Varint(n)) => (n as i64).to_string(),
+                (ProtoType::Uint32, Payload::Varint(n)) => in_range::<u32, _>(n, field)?,
+                (ProtoType::Uint64, Payload::Varint(n)) => n.to_string(),
+                (ProtoType::Sint32, Payload::Varint(n)) => in_range::<i32, _>((n >> 1) as i64 ^ -((n & 1) as i64), field)?,
+                (ProtoType::Sint64, Payload::Varint(n)) => ((n >> 1) as i64 ^ -((n & 1) as i64)).to_string(),
+                (ProtoType::Bool, Payload::Varint(n)) => (n != 0).to_string(),
+                (ProtoType::Double, Payload::Fixed(b)) if b.len() == 8 => f64::from_le_bytes(b.try_into().unwrap()).to_string(),
+                (ProtoType::Float, Payload::Fixed(b)) if b.len() == 4 => f32::from_le_bytes(b.try_into().unwrap()).to_string(),
+                (ProtoType::String, Payload::Fixed(b)) if wire_type == LENGTH_DELIMITED => {
+                    String::from_utf8(b.to_vec()).map_err(|_| format!("Field '{}' is not valid UTF-8", field.name))?
+                }
+                _ => return Err(format!("Wire type {} does not match field '{}' of message '{}'", wire_type, field.name, self.name)),
+            };
+            data.insert(field.name.clone(), value);
+        }
+        Ok(data)
+    }
+}
+
+fn in_range<T: TryFrom<N> + ToString, N: Copy + ToString>(n: N, field: &ProtoField) -> Result<String, String> {
+    T::try_from(n)
+        .map(|v| v.to_string())
+        .map_err(|_| format!("Value {} is out of range for {:?} field '{}'", n.to_string(), field.ty, field.name))
+}
+
+enum Payload<'a> {
+    Varint(u64),
+    Fixed(&'a [u8]),
+}
+
+fn put_varint(buf: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buf.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+fn take_varint(bytes: &mut &[u8]) -> Result<u64, String> {
+    let mut n = 0u64;
+    for shift in (0..64).step_by(7) {
+        let [b, rest @ ..] = *bytes else {
+            break;
+        };
+        *bytes = rest;
+        n |= ((b & 0x7f) as u64) << shift;
+        if b & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    Err("Truncated or invalid varint".to_string())
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], String> {
+    if bytes.len() < len {
+        return Err("Truncated protobuf message".to_string());
+    }
+    let (head, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(head)
+}
+
+impl Database {
+    pub fn set_table_proto(&mut self, table_name: &str, message: ProtoMessage) -> Result<(), String> {
+        let table = self.tables.get_mut(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        for record in &table.records {
+            message.validate(&record.data)
+                .map_err(|e| format!("Record {} does not match message '{}': {}", record.id, message.name, e))?;
+        }
+        table.proto = Some(message);
+        Ok(())
+    }
+
+    pub fn insert_proto(&mut self, table_name: &str, id: u64, bytes: &[u8]) -> Result<(), String> {
+        let data = self.table_proto(table_name)?.decode(bytes)?;
+        self.insert(table_name, id, data)
+    }
+
+    pub fn get_proto(&self, table_name: &str, id: u64) -> Result<Option<Vec<u8>>, String> {
+        let message = self.table_proto(table_name)?;
+        match self.get(table_name, id)? {
+            Some(record) => Ok(Some(message.encode(&record.data)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn table_proto(&self, table_name: &str) -> Result<&ProtoMessage, String> {
+        let table = self.tables.get(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        table.proto.as_ref().ok_or(format!("Table '{}' has no protobuf message declared", table_name))
+    }
+}
@@ -0,0 +1,32 @@
+use alloc::borrow::Cow;
+
+use crate::prelude::*;
+use crate::{Condition, Record};
+
+/// The pseudo-column SQL reads a record's id through: `WHERE id = 5` looks
+/// record 5 up in the table's index rather than scanning for it, and
+/// `SELECT id, name` lists it beside the stored columns. It is not stored
+/// in rows, and SQL reads it in place of a stored column named `id`.
+pub const ID: &str = "id";
+
+pub(crate) fn is_pseudo(column: &str) -> bool {
+    column == ID
+}
+
+// A column of `record` as SQL reads it, pseudo-columns included.
+pub(crate) fn column<'r>(record: &'r Record, column: &str) -> Option<Cow<'r, str>> {
+    match column {
+        ID => Some(Cow::Owned(record.id.to_string())),
+        _ => record.data.get(column).map(|v| Cow::Borrowed(v.as_str())),
+    }
+}
+
+// The id `condition` requires, if it is `id = n` or ANDs that with more,
+// so a scan need only read that record.
+pub(crate) fn required_id(condition: &Condition) -> Option<u64> {
+    match condition {
+        Condition::Equals(column, value) if column == ID => value.parse().ok(),
+        Condition::And(left, right) => required_id(left).or_else(|| required_id(right)),
+        _ => None,
+    }
+}
@@ -0,0 +1,183 @@
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+use alloc::sync::Arc;
+use core::time::Duration;
+
+use crate::clock::unix_millis;
+use crate::{Database, Record, Row, Table};
+use crate::prelude::*;
+
+/// The read-only table slow statements are kept in, with the columns
+/// `statement`, `duration_ms`, `plan` and `timestamp` (milliseconds since the
+/// Unix epoch). Values compare as text in WHERE clauses.
+pub const SLOW_QUERIES_TABLE: &str = "_slow_queries";
+
+// Only the most recent slow statements are kept.
+const SLOW_QUERY_LIMIT: usize = 1000;
+
+/// One SQL statement run through [`Database::execute_sql`] or
+/// [`Database::query_sql`].
+#[derive(Clone, Debug)]
+pub struct QueryLogEntry {
+    sql: String,
+    duration: Duration,
+    rows: usize,
+    error: Option<String>,
+}
+
+impl QueryLogEntry {
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Rows returned by a SELECT, or inserted, updated or deleted by a write.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
+
+/// How a statement run through [`Database::execute_sql_with_stats`] or
+/// [`Database::query_sql_with_stats`] went.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionStats {
+    /// From parsing the statement to having its result.
+    pub duration: Duration,
+    /// Rows read while looking for the ones the statement wanted, counted
+    /// as [`QueryLimits::max_scanned_rows`](crate::QueryLimits) counts them.
+    pub rows_scanned: usize,
+    /// Whether the scan looked its rows up by `id` or read only the
+    /// partitions the WHERE clause names, rather than every row.
+    pub index_used: bool,
+}
+
+type Sink = Arc<dyn Fn(&QueryLogEntry) + Send + Sync>;
+
+#[derive(Default)]
+pub(crate) struct QueryLog {
+    enabled: bool,
+    sink: Option<Sink>,
+    slow_threshold: Option<Duration>,
+    // queries only take &self, so slow ones are recorded behind a lock
+    slow: Mutex<SlowQueries>,
+}
+
+#[derive(Clone, Default)]
+struct SlowQueries {
+    last_id: u64,
+    records: VecDeque<Record>,
+}
+
+impl Clone for QueryLog {
+    fn clone(&self) -> Self {
+        QueryLog {
+            enabled: self.enabled,
+            sink: self.sink.clone(),
+            slow_threshold: self.slow_threshold,
+            slow: Mutex::new(lock(&self.slow).clone()),
+        }
+    }
+}
+
+impl QueryLog {
+    pub(crate) fn finish(&self, duration: Duration, sql: &str, result: Result<usize, &str>) {
+        if !self.enabled {
+            return;
+        }
+        let entry = QueryLogEntry {
+            sql: sql.to_string(),
+            duration,
+            rows: result.unwrap_or(0),
+            error: result.err().map(String::from),
+        };
+        match &self.sink {
+            Some(sink) => sink(&entry),
+            None => log_entry(&entry),
+        }
+    }
+
+    pub(crate) fn is_slow(&self, duration: Duration) -> bool {
+        self.slow_threshold.is_some_and(|threshold| duration >= threshold)
+    }
+
+    pub(crate) fn record_slow(&self, sql: &str, duration: Duration, plan: String) {
+        let timestamp = unix_millis();
+        let data = Row::from([
+            ("statement".to_string(), sql.to_string()),
+            ("duration_ms".to_string(), format!("{:.3}", duration.as_secs_f64() * 1000.0)),
+            ("plan".to_string(), plan),
+            ("timestamp".to_string(), timestamp.to_string()),
+        ]);
+        // a poisoned lock only means another query panicked mid-record
+        let mut slow = lock(&self.slow);
+        slow.last_id += 1;
+        let id = slow.last_id;
+        slow.records.push_back(Record { id, data });
+        if slow.records.len() > SLOW_QUERY_LIMIT {
+            slow.records.pop_front();
+        }
+    }
+
+    pub(crate) fn slow_queries(&self) -> Table {
+        let slow = lock(&self.slow);
+        let records: Vec<Record> = slow.records.iter().cloned().collect();
+        Table {
+            name: SLOW_QUERIES_TABLE.to_string(),
+            index: records.iter().enumerate().map(|(i, r)| (r.id, i)).collect(),
+            records,
+            proto: None,
+            partitions: None,
+            history: None,
+            encrypted: BTreeSet::new(),
+            generated: Vec::new(),
+            enums: BTreeMap::new(),
+            series: None,
+            queue: None,
+            max_rows: None,
+            temporary: false,
+            columnar: None,
+            compare: BTreeMap::new(),
+            expiry: None,
+            timestamps: false,
+            types: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+fn log_entry(entry: &QueryLogEntry) {
+    match &entry.error {
+        None => log::info!(target: "potatodb::query", "{} ({} rows in {:?})", entry.sql, entry.rows, entry.duration),
+        Some(error) => log::warn!(target: "potatodb::query", "{} failed after {:?}: {}", entry.sql, entry.duration, error),
+    }
+}
+
+#[cfg(not(feature = "log"))]
+fn log_entry(_: &QueryLogEntry) {}
+
+impl Database {
+    /// Turns query logging on or off. Statements go to the sink set with
+    /// [`set_query_sink`](Self::set_query_sink), or with the `log` feature to
+    /// the `log` crate under the `potatodb::query` target.
+    pub fn set_query_logging(&mut self, enabled: bool) {
+        self.query_log.enabled = enabled;
+    }
+
+    /// Records statements that take at least `threshold` in
+    /// [`SLOW_QUERIES_TABLE`], where SELECTs can find them. `None` turns
+    /// this off; statements already recorded stay.
+    pub fn set_slow_query_threshold(&mut self, threshold: Option<Duration>) {
+        self.query_log.slow_threshold = threshold;
+    }
+
+    /// Sends logged statements to `sink` instead of the `log` crate.
+    pub fn set_query_sink(&mut self, sink: impl Fn(&QueryLogEntry) + Send + Sync + 'static) {
+        self.query_log.sink = Some(Arc::new(sink));
+    }
+}
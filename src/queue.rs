@@ -0,0 +1,112 @@
+use alloc::collections::BTreeMap;
+use core::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock::unix_millis;
+use crate::{Database, Record, Row};
+use crate::prelude::*;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct ConsumerGroup {
+    // the first message id not yet delivered to the group
+    next: u64,
+    // delivered but not acknowledged: message id -> when it may be redelivered
+    in_flight: BTreeMap<u64, u64>,
+}
+
+/// Settings and consumer-group offsets of a queue table, whose rows are
+/// messages numbered in the order they were pushed.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Queue {
+    visibility_timeout: Duration,
+    groups: BTreeMap<String, ConsumerGroup>,
+}
+
+impl Database {
+    /// Creates a queue table. Each consumer group reads every message once,
+    /// in order: a popped message is hidden from the group for
+    /// `visibility_timeout`, then delivered again unless acknowledged.
+    /// Messages are kept after delivery, so a new group starts from the
+    /// first; offsets are saved with the database.
+    pub fn create_queue(&mut self, name: &str, visibility_timeout: Duration) -> Result<(), String> {
+        self.create_table(name.to_string())?;
+        if let Some(table) = self.tables.get_mut(name) {
+            table.queue = Some(Queue { visibility_timeout, groups: BTreeMap::new() });
+        }
+        Ok(())
+    }
+
+    /// Adds a message to a queue and returns its id.
+    pub fn push(&mut self, queue: &str, data: impl Into<Row>) -> Result<u64, String> {
+        let table = self.tables.get(queue).ok_or(format!("Table '{}' not found", queue))?;
+        if table.queue.is_none() {
+            return Err(format!("Table '{}' is not a queue", queue));
+        }
+        let id = table.next_message_id();
+        self.insert(queue, id, data)?;
+        Ok(id)
+    }
+
+    /// The next message for `group`: the oldest one whose visibility timeout
+    /// ran out unacknowledged, else the next one not yet delivered.
+    pub fn pop(&mut self, queue: &str, group: &str) -> Result<Option<Record>, String> {
+        let now = unix_millis();
+        let table = self.tables.get_mut(queue).ok_or(format!("Table '{}' not found", queue))?;
+        let settings = table.queue.as_mut().ok_or(format!("Table '{}' is not a queue", queue))?;
+        let timeout = settings.visibility_timeout.as_millis() as u64;
+        let consumers = settings.groups.entry(group.to_string()).or_default();
+        let expired: Vec<u64> = consumers.in_flight.iter().filter(|(_, &until)| until <= now).map(|(&id, _)| id).collect();
+        // messages deleted while in flight are skipped
+        let redelivered = expired.into_iter().find(|id| {
+            let found = table.index.contains_key(id);
+            if !found {
+                consumers.in_flight.remove(id);
+            }
+            found
+        });
+        let id = match redelivered {
+            Some(id) => id,
+            None => {
+                let position = table.records.partition_point(|r| r.id < consumers.next);
+                let Some(record) = table.records.get(position) else {
+                    return Ok(None);
+                };
+                consumers.next = record.id + 1;
+                record.id
+            }
+        };
+        consumers.in_flight.insert(id, now + timeout);
+        Ok(Some(table.records[table.index[&id]].clone()))
+    }
+
+    /// Marks a message popped by `group` as handled, so it is not delivered
+    /// to the group again.
+    pub fn ack(&mut self, queue: &str, group: &str, id: u64) -> Result<(), String> {
+        let table = self.tables.get_mut(queue).ok_or(format!("Table '{}' not found", queue))?;
+        let settings = table.queue.as_mut().ok_or(format!("Table '{}' is not a queue", queue))?;
+        settings.groups.get_mut(group)
+            .and_then(|consumers| consumers.in_flight.remove(&id))
+            .map(|_| ())
+            .ok_or(format!("Message {} is not awaiting acknowledgement by '{}'", id, group))
+    }
+
+    /// How many messages of a queue `group` has not acknowledged, counting
+    /// ones not yet delivered.
+    pub fn queue_backlog(&self, queue: &str, group: &str) -> Result<usize, String> {
+        let table = self.tables.get(queue).ok_or(format!("Table '{}' not found", queue))?;
+        let settings = table.queue.as_ref().ok_or(format!("Table '{}' is not a queue", queue))?;
+        let Some(consumers) = settings.groups.get(group) else {
+            return Ok(table.records.len());
+        };
+        let undelivered = table.records.len() - table.records.partition_point(|r| r.id < consumers.next);
+        Ok(undelivered + consumers.in_flight.len())
+    }
+}
+
+impl crate::Table {
+    // Messages, and rows of capped tables, are numbered after the newest one.
+    pub(crate) fn next_message_id(&self) -> u64 {
+        self.records.last().map_or(1, |r| r.id + 1)
+    }
+}
@@ -0,0 +1,440 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Database, Record};
+
+pub type NodeId = u64;
+
+const HEARTBEAT_TICKS: u32 = 3;
+const ELECTION_TICKS: u32 = 10;
+
+/// A replicated write. Commands are applied to every node's database in log order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Command {
+    Noop,
+    CreateTable(String),
+    Insert { table: String, id: u64, data: HashMap<String, String> },
+    Update { table: String, id: u64, data: HashMap<String, String> },
+    Delete { table: String, id: u64 },
+    Sql(String),
+    /// The full set of voters from this entry on, written by `add_member` and `remove_member`.
+    SetMembers(Vec<NodeId>),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Entry {
+    term: u64,
+    command: Command,
+}
+
+/// Messages exchanged between nodes. They derive serde so hosts can ship them
+/// over any transport.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Message {
+    RequestVote { term: u64, last_index: u64, last_term: u64 },
+    Vote { term: u64, granted: bool },
+    Append { term: u64, prev_index: u64, prev_term: u64, entries: Vec<Entry>, commit: u64 },
+    AppendResult { term: u64, success: bool, match_index: u64 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Follower,
+    Candidate,
+    Leader,
+}
+
+/// One member of a raft group replicating a [`Database`].
+///
+/// The node does no I/O: the host calls [`tick`](Self::tick) on a timer,
+/// delivers incoming messages with [`step`](Self::step) and sends whatever
+/// [`take_messages`](Self::take_messages) returns. Writes go through
+/// [`propose`](Self::propose) on the leader and are visible once
+/// [`applied_index`](Self::applied_index) reaches the returned index.
+/// Raft state lives in memory, so a restarted node has to rejoin as a new member.
+pub struct RaftNode {
+    id: NodeId,
+    initial_members: BTreeSet<NodeId>,
+    members: BTreeSet<NodeId>,
+    role: Role,
+    term: u64,
+    voted_for: Option<NodeId>,
+    leader: Option<NodeId>,
+    log: Vec<Entry>,
+    commit_index: u64,
+    applied_index: u64,
+    elapsed: u32,
+    election_timeout: u32,
+    votes: BTreeSet<NodeId>,
+    next_index: HashMap<NodeId, u64>,
+    match_index: HashMap<NodeId, u64>,
+    outbox: Vec<(NodeId, Message)>,
+    proposed: BTreeMap<u64, u64>,
+    results: HashMap<u64, Result<Vec<Record>, String>>,
+    db: Database,
+}
+
+impl RaftNode {
+    /// Creates a node of a group whose initial voters are `members`. A node
+    /// joining an existing group passes an empty list and is added by the
+    /// leader with [`add_member`](Self::add_member).
+    pub fn new(id: NodeId, members: &[NodeId]) -> Self {
+        let members: BTreeSet<NodeId> = members.iter().copied().collect();
+        let mut node = RaftNode {
+            id,
+            initial_members: members.clone(),
+            members,
+            role: Role::Follower,
+            term: 0,
+            voted_for: None,
+            leader: None,
+            log: Vec::new(),
+            commit_index: 0,
+            applied_index: 0,
+            elapsed: 0,
+            election_timeout: 0,
+            votes: BTreeSet::new(),
+            next_index: HashMap::new(),
+            match_index: HashMap::new(),
+            outbox: Vec::new(),
+            proposed: BTreeMap::new(),
+            results: HashMap::new(),
+            db: Database::new(),
+        };
+        node.reset_election_timer();
+        node
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    pub fn term(&self) -> u64 {
+        self.term
+    }
+
+    /// The leader this node last heard from, if any.
+    pub fn leader(&self) -> Option<NodeId> {
+        self.leader
+    }
+
+    /// The voters of the latest configuration in this node's log.
+    pub fn members(&self) -> Vec<NodeId> {
+        self.members.iter().copied().collect()
+    }
+
+    pub fn commit_index(&self) -> u64 {
+        self.commit_index
+    }
+
+    pub fn applied_index(&self) -> u64 {
+        self.applied_index
+    }
+
+    /// The replicated database. It reflects every entry up to `applied_index`.
+    pub fn database(&self) -> &Database {
+        &self.db
+    }
+
+    /// The result of applying an entry this node proposed. `None` until it is
+    /// applied, and for good if a later leader replaced the entry.
+    pub fn take_result(&mut self, index: u64) -> Option<Result<Vec<Record>, String>> {
+        self.results.remove(&index)
+    }
+
+    pub fn take_messages(&mut self) -> Vec<(NodeId, Message)> {
+        std::mem::take(&mut self.outbox)
+    }
+
+    /// Appends a command to the log and returns its index. Only the leader
+    /// accepts proposals.
+    pub fn propose(&mut self, command: Command) -> Result<u64, String> {
+        if self.role != Role::Leader {
+            return Err(match self.leader {
+                Some(leader) => format!("Node {} is not the leader; node {} is", self.id, leader),
+                None => format!("Node {} is not the leader and no leader is known", self.id),
+            });
+        }
+        if let Command::SetMembers(_) = command {
+            let pending = self.log[self.commit_index as usize..].iter()
+                .any(|e| matches!(e.command, Command::SetMembers(_)));
+            if pending {
+                return Err("Another membership change is still in progress".to_string());
+            }
+        }
+        self.log.push(Entry { term: self.term, command });
+        self.refresh_members();
+        let index = self.last_index();
+        self.proposed.insert(index, self.term);
+        self.match_index.insert(self.id, index);
+        self.broadcast_append();
+        self.advance_commit();
+        Ok(index)
+    }
+
+    pub fn add_member(&mut self, id: NodeId) -> Result<u64, String> {
+        if self.members.contains(&id) {
+            return Err(format!("Node {} is already a member", id));
+        }
+        let members = self.members.iter().copied().chain([id]).collect();
+        self.propose(Command::SetMembers(members))
+    }
+
+    pub fn remove_member(&mut self, id: NodeId) -> Result<u64, String> {
+        if !self.members.contains(&id) {
+            return Err(format!("Node {} is not a member", id));
+        }
+        let members = self.members.iter().copied().filter(|&m| m != id).collect();
+        self.propose(Command::SetMembers(members))
+    }
+
+    /// Advances the node's clock by one tick.
+    pub fn tick(&mut self) {
+        self.elapsed += 1;
+        match self.role {
+            Role::Leader => {
+                if self.elapsed >= HEARTBEAT_TICKS {
+                    self.elapsed = 0;
+                    self.broadcast_append();
+                }
+            }
+            _ => {
+                if self.elapsed >= self.election_timeout && self.members.contains(&self.id) {
+                    self.start_election();
+                }
+            }
+        }
+    }
+
+    /// Handles a message from another node.
+    pub fn step(&mut self, from: NodeId, message: Message) {
+        let term = match &message {
+            Message::RequestVote { term, .. } | Message::Vote { term, .. } | Message::Append { term, .. } | Message::AppendResult { term, .. } => *term,
+        };
+        if term > self.term {
+            self.become_follower(term, None);
+        }
+        match message {
+            Message::RequestVote { term, last_index, last_term } => {
+                let up_to_date = (last_term, last_index) >= (self.last_term(), self.last_index());
+                let granted = term == self.term && up_to_date && self.voted_for.map_or(true, |v| v == from);
+                if granted {
+                    self.voted_for = Some(from);
+                    self.elapsed = 0;
+                }
+                self.outbox.push((from, Message::Vote { term: self.term, granted }));
+            }
+            Message::Vote { term, granted } => {
+                if self.role == Role::Candidate && term == self.term && granted {
+                    self.votes.insert(from);
+                    if self.has_quorum(&self.votes) {
+                        self.become_leader();
+                    }
+                }
+            }
+            Message::Append { term, prev_index, prev_term, entries, commit } => {
+                if term < self.term {
+                    self.outbox.push((from, Message::AppendResult { term: self.term, success: false, match_index: 0 }));
+                    return;
+                }
+                self.become_follower(term, Some(from));
+                let result = self.append_entries(prev_index, prev_term, entries, commit);
+                let (success, match_index) = match result {
+                    Ok(index) => (true, index),
+                    Err(hint) => (false, hint),
+                };
+                self.outbox.push((from, Message::AppendResult { term: self.term, success, match_index }));
+            }
+            Message::AppendResult { term, success, match_index } => {
+                if self.role != Role::Leader || term != self.term {
+                    return;
+                }
+                if success {
+                    self.match_index.insert(from, match_index);
+                    self.next_index.insert(from, match_index + 1);
+                    self.advance_commit();
+                } else {
+                    let next = self.next_index.get(&from).copied().unwrap_or(1);
+                    self.next_index.insert(from, (match_index + 1).min(next.saturating_sub(1)).max(1));
+                    self.send_append(from);
+                }
+            }
+        }
+    }
+
+    fn last_index(&self) -> u64 {
+        self.log.len() as u64
+    }
+
+    fn last_term(&self) -> u64 {
+        self.term_at(self.last_index())
+    }
+
+    fn term_at(&self, index: u64) -> u64 {
+        match index {
+            0 => 0,
+            i => self.log.get(i as usize - 1).map_or(0, |e| e.term),
+        }
+    }
+
+    fn has_quorum(&self, nodes: &BTreeSet<NodeId>) -> bool {
+        nodes.intersection(&self.members).count() * 2 > self.members.len()
+    }
+
+    fn reset_election_timer(&mut self) {
+        self.elapsed = 0;
+        // spread timeouts over [ELECTION_TICKS, 2 * ELECTION_TICKS) so nodes rarely split the vote
+        let spread = self.id.wrapping_mul(0x9e37_79b9_7f4a_7c15).wrapping_add(self.term.wrapping_mul(31)) >> 32;
+        self.election_timeout = ELECTION_TICKS + (spread % ELECTION_TICKS as u64) as u32;
+    }
+
+    fn become_follower(&mut self, term: u64, leader: Option<NodeId>) {
+        if term > self.term {
+            self.term = term;
+            self.voted_for = None;
+        }
+        self.role = Role::Follower;
+        self.leader = leader;
+        self.reset_election_timer();
+    }
+
+    fn start_election(&mut self) {
+        self.term += 1;
+        self.role = Role::Candidate;
+        self.voted_for = Some(self.id);
+        self.leader = None;
+        self.votes = BTreeSet::from([self.id]);
+        self.reset_election_timer();
+        if self.has_quorum(&self.votes) {
+            self.become_leader();
+            return;
+        }
+        let (last_index, last_term) = (self.last_index(), self.last_term());
+        for &peer in self.members.iter().filter(|&&p| p != self.id) {
+            self.outbox.push((peer, Message::RequestVote { term: self.term, last_index, last_term }));
+        }
+    }
+
+    fn become_leader(&mut self) {
+        self.role = Role::Leader;
+        self.leader = Some(self.id);
+        self.elapsed = 0;
+        self.next_index.clear();
+        self.match_index.clear();
+        // a no-op from the new term lets entries from earlier terms commit
+        self.log.push(Entry { term: self.term, command: Command::Noop });
+        self.match_index.insert(self.id, self.last_index());
+        self.broadcast_append();
+        self.advance_commit();
+    }
+
+    fn broadcast_append(&mut self) {
+        let peers: Vec<NodeId> = self.members.iter().copied().filter(|&p| p != self.id).collect();
+        for peer in peers {
+            self.send_append(peer);
+        }
+    }
+
+    fn send_append(&mut self, peer: NodeId) {
+        let last_index = self.last_index();
+        let next = *self.next_index.entry(peer).or_insert(last_index + 1);
+        let prev_index = next - 1;
+        let message = Message::Append {
+            term: self.term,
+            prev_index,
+            prev_term: self.term_at(prev_index),
+            entries: self.log[prev_index as usize..].to_vec(),
+            commit: self.commit_index,
+        };
+        self.outbox.push((peer, message));
+    }
+
+    // Returns the index of the last matching entry, or a hint for the leader's
+    // next attempt when the logs diverge.
+    fn append_entries(&mut self, prev_index: u64, prev_term: u64, entries: Vec<Entry>, commit: u64) -> Result<u64, u64> {
+        if prev_index > self.last_index() || self.term_at(prev_index) != prev_term {
+            return Err(self.last_index().min(prev_index.saturating_sub(1)));
+        }
+        let mut changed = false;
+        for (offset, entry) in entries.into_iter().enumerate() {
+            let index = prev_index + 1 + offset as u64;
+            if index <= self.last_index() {
+                if self.term_at(index) == entry.term {
+                    continue;
+                }
+                // committed entries never conflict, so this only drops uncommitted ones
+                self.log.truncate(index as usize - 1);
+            }
+            self.log.push(entry);
+            changed = true;
+        }
+        if changed {
+            self.refresh_members();
+        }
+        let matched = self.last_index();
+        if commit > self.commit_index {
+            self.commit_index = commit.min(matched);
+            self.apply_committed();
+        }
+        Ok(matched)
+    }
+
+    fn advance_commit(&mut self) {
+        let mut matched: Vec<u64> = self.members.iter()
+            .map(|id| self.match_index.get(id).copied().unwrap_or(0))
+            .collect();
+        if matched.is_empty() {
+            return;
+        }
+        matched.sort_unstable_by(|a, b| b.cmp(a));
+        let quorum_index = matched[self.members.len() / 2];
+        // only entries from the current term are committed by counting replicas
+        if quorum_index > self.commit_index && self.term_at(quorum_index) == self.term {
+            self.commit_index = quorum_index;
+            self.apply_committed();
+            self.broadcast_append();
+        }
+    }
+
+    // The configuration in force is the latest one in the log, committed or not.
+    fn refresh_members(&mut self) {
+        self.members = self.log.iter().rev()
+            .find_map(|e| match &e.command {
+                Command::SetMembers(members) => Some(members.iter().copied().collect()),
+                _ => None,
+            })
+            .unwrap_or_else(|| self.initial_members.clone());
+    }
+
+    fn apply_committed(&mut self) {
+        while self.applied_index < self.commit_index {
+            self.applied_index += 1;
+            let Entry { term, command } = self.log[self.applied_index as usize - 1].clone();
+            let result = match command {
+                Command::Noop => Ok(Vec::new()),
+                Command::SetMembers(members) => {
+                    // a leader that removed itself hands over once the change commits
+                    if !members.contains(&self.id) && self.role == Role::Leader {
+                        self.role = Role::Follower;
+                        self.leader = None;
+                    }
+                    Ok(Vec::new())
+                }
+                Command::CreateTable(name) => self.db.create_table(name).map(|_| Vec::new()),
+                Command::Insert { table, id, data } => self.db.insert(&table, id, data).map(|_| Vec::new()),
+                Command::Update { table, id, data } => self.db.update(&table, id, data).map(|_| Vec::new()),
+                Command::Delete { table, id } => self.db.delete(&table, id).map(|_| Vec::new()),
+                Command::Sql(sql) => self.db.execute_sql(&sql),
+            };
+            // the same index may hold another leader's command if this node lost its term
+            if self.proposed.remove(&self.applied_index) == Some(term) {
+                self.results.insert(self.applied_index, result);
+            }
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use alloc::collections::BTreeSet;
+
+use crate::{Database, Record};
+use crate::prelude::*;
+
+/// A Rust type read from the rows of one table, for the relations API.
+pub trait Model: Sized {
+    const TABLE: &'static str;
+
+    fn from_record(record: &Record) -> Result<Self, String>;
+}
+
+#[derive(Clone)]
+struct Relation {
+    parent: String,
+    child: String,
+    // the child column holding the parent's id
+    foreign_key: String,
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Relations {
+    relations: Vec<Relation>,
+}
+
+impl Relation {
+    fn parent_id(&self, record: &Record) -> Option<u64> {
+        record.data.get(&self.foreign_key)?.parse().ok()
+    }
+}
+
+impl Relations {
+    pub(crate) fn rename_column(&mut self, table: &str, from: &str, to: &str) {
+        for relation in self.relations.iter_mut().filter(|r| r.child == table && r.foreign_key == from) {
+            relation.foreign_key = to.to_string();
+        }
+    }
+
+    fn find(&self, parent: Option<&str>, child: &str) -> Result<&Relation, String> {
+        let mut found = self.relations.iter().filter(|r| r.child == child && parent.map_or(true, |p| r.parent == p));
+        match (found.next(), found.next()) {
+            (Some(relation), None) => Ok(relation),
+            (Some(_), Some(_)) => Err(format!("'{}' belongs to more than one table; name the parent", child)),
+            (None, _) => Err(match parent {
+                Some(parent) => format!("No relation from '{}' to '{}'", parent, child),
+                None => format!("No relation to '{}'", child),
+            }),
+        }
+    }
+}
+
+impl Database {
+    /// Declares that each row of `parent` has many rows of `child`, linked
+    /// by the child column `foreign_key` holding the parent's id: `users
+    /// 1..N orders` is `has_many("users", "orders", "user_id")`. Relations
+    /// are not saved with the database.
+    pub fn has_many(&mut self, parent: &str, child: &str, foreign_key: &str) -> Result<(), String> {
+        for table in [parent, child] {
+            if !self.tables.contains_key(table) {
+                return Err(format!("Table '{}' not found", table));
+            }
+        }
+        if self.relations.relations.iter().any(|r| r.parent == parent && r.child == child) {
+            return Err(format!("'{}' already has many '{}'", parent, child));
+        }
+        self.relations.relations.push(Relation { parent: parent.to_string(), child: child.to_string(), foreign_key: foreign_key.to_string() });
+        Ok(())
+    }
+
+    /// The rows of `C`'s table that belong to the parent row `parent_id`,
+    /// through the one relation declared to that table.
+    pub fn related<C: Model>(&self, parent_id: u64) -> Result<Vec<C>, String> {
+        let relation = self.relations.find(None, C::TABLE)?;
+        self.get_all(C::TABLE)?.into_iter()
+            .filter(|record| relation.parent_id(record) == Some(parent_id))
+            .map(C::from_record)
+            .collect()
+    }
+
+    /// The parent row a row of `child` belongs to, if any.
+    pub fn parent_of<P: Model>(&self, child: &str, child_id: u64) -> Result<Option<P>, String> {
+        let relation = self.relations.find(Some(P::TABLE), child)?;
+        let record = self.get(child, child_id)?.ok_or(format!("Record {} not found in '{}'", child_id, child))?;
+        match relation.parent_id(record).map(|id| self.get(P::TABLE, id)) {
+            Some(Ok(Some(parent))) => Ok(Some(P::from_record(parent)?)),
+            Some(Err(e)) => Err(e),
+            _ => Ok(None),
+        }
+    }
+
+    /// Deletes a row together with every row that belongs to it through
+    /// the relations declared with [`has_many`](Self::has_many), and the rows
+    /// that belong to those, and so on. Returns each deleted row with its
+    /// table, the named row first. The rows to delete are all found before
+    /// any is deleted, so nothing is deleted if the row does not exist.
+    pub fn delete_cascade(&mut self, table: &str, id: u64) -> Result<Vec<(String, Record)>, String> {
+        let record = self.get(table, id)?.ok_or(format!("Record with id {} not found in table '{}'", id, table))?;
+        let mut found = vec![(table.to_string(), record.clone())];
+        let mut seen: BTreeSet<(String, u64)> = BTreeSet::from([(table.to_string(), id)]);
+        let mut next = 0;
+        while let Some((parent, record)) = found.get(next) {
+            let parent_id = record.id;
+            let mut children = Vec::new();
+            for relation in self.relations.relations.iter().filter(|r| r.parent == *parent) {
+                for child in self.get_all(&relation.child)? {
+                    if relation.parent_id(child) == Some(parent_id) && seen.insert((relation.child.clone(), child.id)) {
+                        children.push((relation.child.clone(), child.clone()));
+                    }
+                }
+            }
+            found.extend(children);
+            next += 1;
+        }
+        // children go before their parents, so no row is left pointing at a
+        // deleted one if a delete is observed midway
+        for (table, record) in found.iter().rev() {
+            self.delete(table, record.id)?;
+        }
+        Ok(found)
+    }
+
+    /// Every row of `P`'s table with its related `C` rows, loaded with one
+    /// pass over each table rather than one lookup per parent.
+    pub fn with_related<P: Model, C: Model>(&self) -> Result<Vec<(P, Vec<C>)>, String> {
+        let relation = self.relations.find(Some(P::TABLE), C::TABLE)?;
+        let mut children: HashMap<u64, Vec<C>> = HashMap::new();
+        for record in self.get_all(C::TABLE)? {
+            if let Some(parent_id) = relation.parent_id(record) {
+                children.entry(parent_id).or_default().push(C::from_record(record)?);
+            }
+        }
+        self.get_all(P::TABLE)?.into_iter()
+            .map(|record| Ok((P::from_record(record)?, children.remove(&record.id).unwrap_or_default())))
+            .collect()
+    }
+
+    /// The tables and the relations declared between them as a Graphviz
+    /// DOT graph, for `dot -Tsvg`: a node per table and an edge from each
+    /// child table to its parent, labelled with the foreign key.
+    pub fn schema_graph_dot(&self) -> String {
+        let quote = |name: &str| format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut tables: BTreeSet<&str> = self.tables.keys().map(String::as_str).collect();
+        tables.extend(self.relations.relations.iter().flat_map(|r| [r.parent.as_str(), r.child.as_str()]));
+        let mut dot = String::from("digraph schema {\n");
+        for table in tables {
+            dot.push_str(&format!("    {};\n", quote(table)));
+        }
+        for relation in &self.relations.relations {
+            dot.push_str(&format!("    {} -> {} [label={}];\n", quote(&relation.child), quote(&relation.parent), quote(&relation.foreign_key)));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::{self, BufWriter, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::replication::{accept_loop, lock, other, read_frame, write_frame};
+use crate::tables::Tables;
+use crate::{Database, Privilege, Record, SqlStatement};
+
+// Idle connections check this often whether the server was dropped.
+const IDLE_CHECK: Duration = Duration::from_millis(100);
+
+/// How long a client may leave a transaction open between requests before
+/// the server closes its connection, unless changed with
+/// [`QueryServer::set_idle_in_transaction_timeout`].
+pub const IDLE_IN_TRANSACTION_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize)]
+enum Request {
+    Query(String),
+    ListTables,
+    Columns(String),
+}
+
+#[derive(Serialize, Deserialize)]
+enum Reply {
+    Records(Vec<Record>),
+    Names(Vec<String>),
+}
+
+/// Answers read-only queries from [`RemoteClient`]s against a shared
+/// database, using the same framing as replication. Writes are refused.
+/// A client's `SET` changes a setting for its own connection only, which
+/// otherwise runs under the database's settings.
+/// Dropping the server stops accepting clients and closes idle connections.
+///
+/// A client's `BEGIN` opens a transaction on its own connection: until
+/// `COMMIT` or `ROLLBACK` its queries read the tables as they were at
+/// `BEGIN`, so they see the same rows whatever the embedding application
+/// or other clients do meanwhile. The snapshot shares the tables with the
+/// database until they are written, and queries in it run under the
+/// database's current limits, settings and privileges. As sessions only
+/// read, the two ends are the same.
+pub struct QueryServer {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    idle_in_transaction_millis: Arc<AtomicU64>,
+}
+
+impl QueryServer {
+    pub fn bind(db: Arc<Mutex<Database>>, addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Self::serve(db, addr, None)
+    }
+
+    /// Like [`bind`](Self::bind), but runs every query as `user`, so clients
+    /// can only read the tables that user was granted SELECT on.
+    pub fn bind_as(db: Arc<Mutex<Database>>, addr: impl ToSocketAddrs, user: &str) -> io::Result<Self> {
+        if !lock(&db)?.has_user(user) {
+            return Err(other(format!("User '{}' not found", user)));
+        }
+        Self::serve(db, addr, Some(user.to_string()))
+    }
+
+    fn serve(db: Arc<Mutex<Database>>, addr: impl ToSocketAddrs, user: Option<String>) -> io::Result<Self> {
+        let idle_in_transaction_millis = Arc::new(AtomicU64::new(IDLE_IN_TRANSACTION_TIMEOUT.as_millis() as u64));
+        let timeout = idle_in_transaction_millis.clone();
+        let (addr, stop) = accept_loop(addr, move |stream, stop| {
+            // a client hanging up is not an error for the server
+            let _ = serve_client(&db, user.as_deref(), stream, stop, &timeout);
+        })?;
+        Ok(QueryServer { addr, stop, idle_in_transaction_millis })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Sets how long a client may leave a transaction open between
+    /// requests, for connections open now and later, before the server
+    /// closes the connection and drops its copy of the database.
+    pub fn set_idle_in_transaction_timeout(&self, timeout: Duration) {
+        self.idle_in_transaction_millis.store(timeout.as_millis() as u64, Ordering::Relaxed);
+    }
+}
+
+enum Control {
+    Begin,
+    // COMMIT, END or ROLLBACK
+    End,
+}
+
+fn control(sql: &str) -> Option<Control> {
+    let sql = sql.trim().trim_end_matches(';').to_uppercase();
+    match sql.split_whitespace().collect::<Vec<_>>()[..] {
+        ["BEGIN"] | ["BEGIN", "TRANSACTION" | "WORK"] | ["START", "TRANSACTION"] => Some(Control::Begin),
+        ["COMMIT" | "END" | "ROLLBACK"] | ["COMMIT" | "END" | "ROLLBACK", "TRANSACTION" | "WORK"] => Some(Control::End),
+        _ => None,
+    }
+}
+
+// Settings a client changed with SET or PRAGMA, by name; it reads the
+// database's own for the rest.
+type Settings = BTreeMap<String, String>;
+
+fn answer(db: &mut Database, user: Option<&str>, settings: &mut Settings, request: Request) -> Result<Reply, String> {
+    if let Request::Query(sql) = &request {
+        if let Ok(SqlStatement::Set(name, value)) = db.parse_sql(sql) {
+            // checked by setting it on the database, which then gets its
+            // own value back
+            let default = db.setting(&name)?;
+            db.set_setting(&name, &value)?;
+            settings.insert(name.clone(), db.setting(&name)?);
+            db.set_setting(&name, &default)?;
+            return Ok(Reply::Records(Vec::new()));
+        }
+    }
+    let defaults = settings.iter()
+        .map(|(name, value)| {
+            let default = db.setting(name)?;
+            db.set_setting(name, value)?;
+            Ok((name, default))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let session = db.access.replace_session(user.map(String::from));
+    let reply = match request {
+        Request::Query(sql) => db.query_sql(&sql).map(Reply::Records),
+        // only the tables the user may SELECT from
+        Request::ListTables => {
+            let tables = db.list_tables().into_iter().filter(|t| db.session_allows(t, Privilege::Select));
+            Ok(Reply::Names(tables.map(String::from).collect()))
+        }
+        Request::Columns(table) => db.check_privilege(&table, Privilege::Select).and_then(|()| db.columns(&table)).map(Reply::Names),
+    };
+    db.access.replace_session(session);
+    for (name, default) in defaults {
+        db.set_setting(name, &default)?;
+    }
+    reply
+}
+
+impl Drop for QueryServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn serve_client(db: &Mutex<Database>, user: Option<&str>, mut stream: TcpStream, stop: &AtomicBool, idle_in_transaction_millis: &AtomicU64) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_nodelay(true)?;
+    // the tables an open transaction reads, and when its last request ended
+    let mut transaction: Option<(Tables, Instant)> = None;
+    let mut settings = Settings::new();
+    while !stop.load(Ordering::Relaxed) {
+        if let Some((_, idle_since)) = &transaction {
+            if idle_since.elapsed() >= Duration::from_millis(idle_in_transaction_millis.load(Ordering::Relaxed)) {
+                return Err(other("Connection closed after idling in a transaction"));
+            }
+        }
+        // wait for the next request without holding up shutdown
+        stream.set_read_timeout(Some(IDLE_CHECK))?;
+        match stream.peek(&mut [0]) {
+            Ok(0) => return Ok(()),
+            Ok(_) => {}
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => continue,
+            Err(e) => return Err(e),
+        }
+        stream.set_read_timeout(None)?;
+        let request = read_frame(&mut stream)?;
+        let control = match &request {
+            Request::Query(sql) => control(sql),
+            _ => None,
+        };
+        let reply = match (control, &mut transaction) {
+            (Some(Control::Begin), Some(_)) => Err("A transaction is already open".to_string()),
+            (Some(Control::Begin), None) => {
+                // the tables are shared with the database, not copied
+                transaction = Some((lock(db)?.tables.clone(), Instant::now()));
+                Ok(Reply::Records(Vec::new()))
+            }
+            (Some(Control::End), None) => Err("No transaction is open".to_string()),
+            (Some(Control::End), Some(_)) => {
+                transaction = None;
+                Ok(Reply::Records(Vec::new()))
+            }
+            (None, Some((snapshot, _))) => {
+                let mut db = lock(db)?;
+                std::mem::swap(&mut db.tables, snapshot);
+                let reply = answer(&mut db, user, &mut settings, request);
+                std::mem::swap(&mut db.tables, snapshot);
+                reply
+            }
+            (None, None) => answer(&mut *lock(db)?, user, &mut settings, request),
+        };
+        let mut out = BufWriter::new(&stream);
+        write_frame(&mut out, &reply)?;
+        out.flush()?;
+        if let Some((_, idle_since)) = &mut transaction {
+            *idle_since = Instant::now();
+        }
+    }
+    Ok(())
+}
+
+/// A connection to a [`QueryServer`], for running queries against a database
+/// on another machine without copying it.
+pub struct RemoteClient {
+    stream: TcpStream,
+}
+
+impl RemoteClient {
+    pub fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        Ok(RemoteClient { stream })
+    }
+
+    /// Runs a SELECT on the server, `BEGIN`, `COMMIT` or `ROLLBACK`, or a
+    /// `SET` for this connection. Statements that write are rejected.
+    pub fn query_sql(&mut self, sql: &str) -> Result<Vec<Record>, Box<dyn Error>> {
+        match self.call(Request::Query(sql.to_string()))? {
+            Reply::Records(records) => Ok(records),
+            Reply::Names(_) => Err(other("Unexpected reply from server").into()),
+        }
+    }
+
+    pub fn list_tables(&mut self) -> Result<Vec<String>, Box<dyn Error>> {
+        self.names(Request::ListTables)
+    }
+
+    pub fn columns(&mut self, table_name: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        self.names(Request::Columns(table_name.to_string()))
+    }
+
+    fn names(&mut self, request: Request) -> Result<Vec<String>, Box<dyn Error>> {
+        match self.call(request)? {
+            Reply::Names(names) => Ok(names),
+            Reply::Records(_) => Err(other("Unexpected reply from server").into()),
+        }
+    }
+
+    fn call(&mut self, request: Request) -> Result<Reply, Box<dyn Error>> {
+        let mut out = BufWriter::new(&self.stream);
+        write_frame(&mut out, &request)?;
+        out.flush()?;
+        drop(out);
+        let reply: Result<Reply, String> = read_frame(&mut self.stream)?;
+        Ok(reply?)
+    }
+}
@@ -0,0 +1,222 @@
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use bincode::{deserialize, serialize};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::{ChangeEvent, Database};
+
+// Leaders poll their change log this often for new events to ship.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+
+// Frames are a little-endian u32 length followed by a bincode message.
+#[derive(Serialize, Deserialize)]
+enum Message {
+    Snapshot { seq: u64, bytes: Vec<u8> },
+    Change(ChangeEvent),
+}
+
+pub(crate) fn other(e: impl ToString) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+pub(crate) fn write_frame(out: &mut impl Write, message: &impl Serialize) -> io::Result<()> {
+    let bytes = serialize(message).map_err(other)?;
+    if bytes.len() > MAX_FRAME_BYTES {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Frame of {} bytes is over the limit of {} bytes", bytes.len(), MAX_FRAME_BYTES)));
+    }
+    let len = bytes.len() as u32;
+    out.write_all(&len.to_le_bytes())?;
+    out.write_all(&bytes)
+}
+
+// The longest frame read, snapshots included. A longer length prefix is
+// taken as corrupt or hostile rather than allocated for.
+pub(crate) const MAX_FRAME_BYTES: usize = 1 << 30;
+
+pub(crate) fn read_frame<T: DeserializeOwned>(input: &mut impl Read) -> io::Result<T> {
+    let mut len = [0; 4];
+    input.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_BYTES {
+        let message = format!("Frame of {} bytes is over the limit of {} bytes", len, MAX_FRAME_BYTES);
+        return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+    }
+    // the buffer grows with the bytes that arrive, not with the length sent
+    let mut bytes = Vec::new();
+    if input.take(len as u64).read_to_end(&mut bytes)? < len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    deserialize(&bytes).map_err(other)
+}
+
+/// Streams the changes of a shared database to every follower that connects.
+/// Each follower first receives a snapshot and then the changes made after it.
+/// Dropping the server stops accepting followers and closes their streams.
+pub struct ReplicationServer {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+}
+
+impl ReplicationServer {
+    pub fn bind(db: Arc<Mutex<Database>>, addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let (addr, stop) = accept_loop(addr, move |stream, stop| {
+            // a follower hanging up is not an error for the leader
+            let _ = serve_follower(&db, stream, stop);
+        })?;
+        Ok(ReplicationServer { addr, stop })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for ReplicationServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+// Runs `handle` on its own thread for every connection until the returned
+// flag is set.
+pub(crate) fn accept_loop(
+    addr: impl ToSocketAddrs,
+    handle: impl Fn(TcpStream, &AtomicBool) + Clone + Send + 'static,
+) -> io::Result<(SocketAddr, Arc<AtomicBool>)> {
+    let listener = TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    let addr = listener.local_addr()?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let accept_stop = stop.clone();
+    thread::spawn(move || {
+        while !accept_stop.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let (handle, stop) = (handle.clone(), accept_stop.clone());
+                    thread::spawn(move || handle(stream, &stop));
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+                Err(_) => break,
+            }
+        }
+    });
+    Ok((addr, stop))
+}
+
+pub(crate) fn lock(db: &Mutex<Database>) -> io::Result<std::sync::MutexGuard<'_, Database>> {
+    db.lock().map_err(|_| other("Database lock poisoned"))
+}
+
+fn serve_follower(db: &Mutex<Database>, stream: TcpStream, stop: &AtomicBool) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_nodelay(true)?;
+    let mut out = BufWriter::new(stream);
+    let mut sent = None;
+    while !stop.load(Ordering::Relaxed) {
+        let messages = {
+            let db = lock(db)?;
+            match sent {
+                // a new follower, or one whose next changes were already discarded
+                Some(seq) if db.changes_complete_after(seq) => db.changes_since(seq).map(Message::Change).collect(),
+                _ => vec![Message::Snapshot { seq: db.last_change_seq(), bytes: db.to_bytes().map_err(other)? }],
+            }
+        };
+        for message in &messages {
+            write_frame(&mut out, message)?;
+            sent = Some(match message {
+                Message::Snapshot { seq, .. } => *seq,
+                Message::Change(event) => event.seq(),
+            });
+        }
+        out.flush()?;
+        if messages.is_empty() {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+    Ok(())
+}
+
+/// A read-only replica kept up to date by a [`ReplicationServer`]. It
+/// reconnects on its own, catching up from a fresh snapshot.
+pub struct Follower {
+    db: Arc<Mutex<Database>>,
+    applied: Arc<AtomicU64>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Follower {
+    /// Connects to a leader and waits for its initial snapshot.
+    pub fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let addr = addr.to_socket_addrs()?.next().ok_or_else(|| other("No address to connect to"))?;
+        let mut input = BufReader::new(TcpStream::connect(addr)?);
+        let Message::Snapshot { seq, bytes } = read_frame(&mut input)? else {
+            return Err(other("Leader did not start with a snapshot"));
+        };
+        let db = Arc::new(Mutex::new(Database::from_bytes(&bytes).map_err(other)?));
+        let applied = Arc::new(AtomicU64::new(seq));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let (thread_db, thread_applied, thread_stop) = (db.clone(), applied.clone(), stop.clone());
+        thread::spawn(move || {
+            let mut input = Some(input);
+            while !thread_stop.load(Ordering::Relaxed) {
+                let stream = match input.take() {
+                    Some(stream) => Ok(stream),
+                    None => TcpStream::connect(addr).map(BufReader::new),
+                };
+                if let Ok(mut stream) = stream {
+                    // returns once the connection drops
+                    let _ = follow(&thread_db, &mut stream, &thread_applied, &thread_stop);
+                }
+                thread::sleep(RECONNECT_DELAY);
+            }
+        });
+        Ok(Follower { db, applied, stop })
+    }
+
+    /// Runs `f` against the replica. Writes are not possible; they belong on the leader.
+    pub fn read<T>(&self, f: impl FnOnce(&Database) -> T) -> Result<T, String> {
+        let db = self.db.lock().map_err(|_| "Database lock poisoned".to_string())?;
+        Ok(f(&db))
+    }
+
+    #[cfg(feature = "sql")]
+    pub fn query_sql(&self, sql: &str) -> Result<Vec<crate::Record>, String> {
+        self.read(|db| db.query_sql(sql))?
+    }
+
+    /// The sequence number of the last leader change applied to the replica.
+    pub fn applied_seq(&self) -> u64 {
+        self.applied.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for Follower {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn follow(db: &Mutex<Database>, input: &mut impl Read, applied: &AtomicU64, stop: &AtomicBool) -> io::Result<()> {
+    while !stop.load(Ordering::Relaxed) {
+        let seq = match read_frame(input)? {
+            Message::Snapshot { seq, bytes } => {
+                *lock(db)? = Database::from_bytes(&bytes).map_err(other)?;
+                seq
+            }
+            Message::Change(event) => {
+                lock(db)?.apply_change(event.table(), event.kind(), event.record()).map_err(other)?;
+                event.seq()
+            }
+        };
+        applied.store(seq, Ordering::Release);
+    }
+    Ok(())
+}
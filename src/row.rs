@@ -0,0 +1,248 @@
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::fmt;
+use core::mem::size_of;
+use core::ops::Index;
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use crate::prelude::*;
+
+// A set of column names in sorted order. Rows of a table with the same
+// columns share one, so each name is stored once rather than once per row.
+#[derive(Debug)]
+struct Schema {
+    names: Box<[String]>,
+}
+
+fn schema(names: Box<[String]>) -> Arc<Schema> {
+    Arc::new(Schema { names })
+}
+
+// Gives the rows passed through it one copy of the names of each set of
+// columns. Tables share names as rows are stored in them, and when a
+// change gives many rows new columns at once.
+#[derive(Default)]
+pub(crate) struct SharedNames(HashMap<Box<[String]>, Arc<Schema>>);
+
+impl SharedNames {
+    pub(crate) fn share(&mut self, row: &mut Row) {
+        match self.0.get(&row.schema.names) {
+            Some(schema) => row.schema = schema.clone(),
+            None => {
+                self.0.insert(row.schema.names.clone(), row.schema.clone());
+            }
+        }
+    }
+}
+
+/// The columns of a record, used like a `HashMap<String, String>`: values
+/// are kept in one list, in column name order, next to a list of the
+/// names shared by every row with the same columns.
+#[derive(Clone)]
+pub struct Row {
+    schema: Arc<Schema>,
+    values: Vec<String>,
+}
+
+impl Row {
+    pub fn new() -> Row {
+        Row { schema: schema(Box::new([])), values: Vec::new() }
+    }
+
+    fn position(&self, column: &str) -> Result<usize, usize> {
+        self.schema.names.binary_search_by(|name| name.as_str().cmp(column))
+    }
+
+    pub fn get(&self, column: &str) -> Option<&String> {
+        self.position(column).ok().map(|position| &self.values[position])
+    }
+
+    pub fn get_mut(&mut self, column: &str) -> Option<&mut String> {
+        self.position(column).ok().map(|position| &mut self.values[position])
+    }
+
+    pub fn contains_key(&self, column: &str) -> bool {
+        self.position(column).is_ok()
+    }
+
+    /// Sets a column, returning its previous value.
+    pub fn insert(&mut self, column: String, value: String) -> Option<String> {
+        match self.position(&column) {
+            Ok(position) => Some(core::mem::replace(&mut self.values[position], value)),
+            Err(position) => {
+                let mut names = self.schema.names.to_vec();
+                names.insert(position, column);
+                self.schema = schema(names.into());
+                self.values.insert(position, value);
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, column: &str) -> Option<String> {
+        let position = self.position(column).ok()?;
+        let mut names = self.schema.names.to_vec();
+        names.remove(position);
+        self.schema = schema(names.into());
+        Some(self.values.remove(position))
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&String, &mut String) -> bool) {
+        let kept: Vec<bool> = self.schema.names.iter().zip(&mut self.values).map(|(name, value)| keep(name, value)).collect();
+        if kept.iter().all(|&k| k) {
+            return;
+        }
+        let mut kept_values = kept.iter();
+        self.values.retain(|_| *kept_values.next().unwrap_or(&false));
+        let names: Vec<String> = self.schema.names.iter().zip(&kept).filter(|(_, &k)| k).map(|(name, _)| name.clone()).collect();
+        self.schema = schema(names.into());
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Columns and values in column name order.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.schema.names.iter().zip(&self.values)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&String, &mut String)> {
+        self.schema.names.iter().zip(&mut self.values)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.schema.names.iter()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &String> {
+        self.values.iter()
+    }
+
+    // Takes `other`'s copy of the column names if the columns are the same.
+    pub(crate) fn share_names(&mut self, other: &Row) {
+        if !Arc::ptr_eq(&self.schema, &other.schema) && self.schema.names == other.schema.names {
+            self.schema = other.schema.clone();
+        }
+    }
+
+    // Bytes on the heap, counting this row's share of its column names.
+    pub(crate) fn heap_bytes(&self) -> usize {
+        let names: usize = self.schema.names.iter().map(|n| size_of::<String>() + n.capacity()).sum();
+        self.values.capacity() * size_of::<String>()
+            + self.values.iter().map(String::capacity).sum::<usize>()
+            + names / Arc::strong_count(&self.schema)
+    }
+
+    /// The row as a map, for APIs that take one.
+    pub fn to_map(&self) -> HashMap<String, String> {
+        self.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+    }
+}
+
+impl Default for Row {
+    fn default() -> Row {
+        Row::new()
+    }
+}
+
+impl FromIterator<(String, String)> for Row {
+    // Later values of a repeated column win, as with a map.
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(pairs: I) -> Row {
+        let sorted: BTreeMap<String, String> = pairs.into_iter().collect();
+        let (names, values): (Vec<String>, Vec<String>) = sorted.into_iter().unzip();
+        Row { schema: schema(names.into()), values }
+    }
+}
+
+impl Extend<(String, String)> for Row {
+    fn extend<I: IntoIterator<Item = (String, String)>>(&mut self, pairs: I) {
+        let mut map: BTreeMap<String, String> = core::mem::take(self).into_iter().collect();
+        map.extend(pairs);
+        *self = map.into_iter().collect();
+    }
+}
+
+impl From<HashMap<String, String>> for Row {
+    fn from(map: HashMap<String, String>) -> Row {
+        map.into_iter().collect()
+    }
+}
+
+impl<const N: usize> From<[(String, String); N]> for Row {
+    fn from(pairs: [(String, String); N]) -> Row {
+        pairs.into_iter().collect()
+    }
+}
+
+impl From<Row> for HashMap<String, String> {
+    fn from(row: Row) -> HashMap<String, String> {
+        row.into_iter().collect()
+    }
+}
+
+impl IntoIterator for Row {
+    type Item = (String, String);
+    type IntoIter = core::iter::Zip<alloc::vec::IntoIter<String>, alloc::vec::IntoIter<String>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let names = self.schema.names.to_vec();
+        names.into_iter().zip(self.values)
+    }
+}
+
+impl<'a> IntoIterator for &'a Row {
+    type Item = (&'a String, &'a String);
+    type IntoIter = core::iter::Zip<core::slice::Iter<'a, String>, core::slice::Iter<'a, String>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.schema.names.iter().zip(self.values.iter())
+    }
+}
+
+impl Index<&str> for Row {
+    type Output = String;
+
+    fn index(&self, column: &str) -> &String {
+        self.get(column).unwrap_or_else(|| panic!("Row has no column '{}'", column))
+    }
+}
+
+impl PartialEq for Row {
+    fn eq(&self, other: &Row) -> bool {
+        (Arc::ptr_eq(&self.schema, &other.schema) || self.schema.names == other.schema.names) && self.values == other.values
+    }
+}
+
+impl Eq for Row {}
+
+impl PartialEq<HashMap<String, String>> for Row {
+    fn eq(&self, other: &HashMap<String, String>) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl fmt::Debug for Row {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+// Saved as a map, as records were before rows were compact.
+impl Serialize for Row {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for Row {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(HashMap::<String, String>::deserialize(deserializer)?.into())
+    }
+}
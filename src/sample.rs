@@ -0,0 +1,120 @@
+use core::fmt;
+use core::hash::BuildHasher;
+#[cfg(feature = "std")]
+use std::collections::hash_map::RandomState;
+#[cfg(feature = "std")]
+use std::time::SystemTime;
+
+use crate::prelude::*;
+use crate::{Database, Record};
+
+/// How much of a table `TABLESAMPLE` keeps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Sample {
+    #[cfg(feature = "sql")]
+    Percent(f64),
+    Rows(usize),
+}
+
+impl Sample {
+    // The inside of `TABLESAMPLE (...)`: `5 PERCENT` or `100 ROWS`.
+    #[cfg(feature = "sql")]
+    pub(crate) fn parse(text: &str) -> Result<Sample, String> {
+        let invalid = || format!("Invalid TABLESAMPLE '{}': expected '(<n> PERCENT)' or '(<n> ROWS)'", text);
+        let [amount, unit] = text.split_whitespace().collect::<Vec<_>>()[..] else {
+            return Err(invalid());
+        };
+        match unit.to_uppercase().as_str() {
+            "PERCENT" => match amount.parse::<f64>() {
+                Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(Sample::Percent(percent)),
+                _ => Err(format!("TABLESAMPLE percentages must be between 0 and 100, not '{}'", amount)),
+            },
+            "ROWS" => amount.parse().map(Sample::Rows).map_err(|_| invalid()),
+            _ => Err(invalid()),
+        }
+    }
+
+    // How many of `total` rows the sample keeps.
+    pub(crate) fn size(&self, total: usize) -> usize {
+        match *self {
+            #[cfg(feature = "sql")]
+            Sample::Percent(percent) => (total as f64 * percent / 100.0).round() as usize,
+            Sample::Rows(n) => n.min(total),
+        }
+    }
+
+    // A uniform sample of `rows` by reservoir sampling, in table order.
+    pub(crate) fn draw<'a>(&self, rows: Vec<&'a Record>) -> Vec<&'a Record> {
+        let size = self.size(rows.len());
+        let mut random = Random::new();
+        let mut reservoir: Vec<(usize, &Record)> = Vec::with_capacity(size);
+        for (position, row) in rows.into_iter().enumerate() {
+            if reservoir.len() < size {
+                reservoir.push((position, row));
+            } else {
+                let slot = random.below(position + 1);
+                if slot < size {
+                    reservoir[slot] = (position, row);
+                }
+            }
+        }
+        reservoir.sort_unstable_by_key(|(position, _)| *position);
+        reservoir.into_iter().map(|(_, row)| row).collect()
+    }
+}
+
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(feature = "sql")]
+            Sample::Percent(percent) => write!(f, "{} PERCENT", percent),
+            Sample::Rows(n) => write!(f, "{} ROWS", n),
+        }
+    }
+}
+
+// splitmix64, seeded per sample; good enough to pick rows, not for secrets.
+pub(crate) struct Random(u64);
+
+impl Random {
+    #[cfg(feature = "std")]
+    fn new() -> Random {
+        let seed = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_nanos();
+        Random(RandomState::new().hash_one(seed))
+    }
+
+    // Without a clock, the seed comes from foldhash's per-instance seeds.
+    #[cfg(not(feature = "std"))]
+    fn new() -> Random {
+        Random(foldhash::fast::RandomState::default().hash_one(0u64))
+    }
+
+    // The same numbers on every run, for generated data.
+    #[cfg(any(feature = "bench", feature = "testing"))]
+    pub(crate) fn seeded(seed: u64) -> Random {
+        Random(seed)
+    }
+
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in `0..bound`.
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        ((self.next() as u128 * bound as u128) >> 64) as usize
+    }
+}
+
+impl Database {
+    /// `n` records of a table picked uniformly at random, in table order;
+    /// all of them if it has fewer. Each record is read once, by reservoir
+    /// sampling.
+    pub fn sample(&self, table_name: &str, n: usize) -> Result<Vec<&Record>, String> {
+        let table = self.tables.get(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        Ok(Sample::Rows(n).draw(table.records.iter().collect()))
+    }
+}
@@ -0,0 +1,67 @@
+use crate::prelude::*;
+use crate::Database;
+
+// `schema.table` names a table in a schema; other names are in none.
+pub(crate) fn schema_of(table_name: &str) -> Option<&str> {
+    table_name.split_once('.').map(|(schema, _)| schema)
+}
+
+impl Database {
+    /// Creates a schema, as `CREATE SCHEMA name` does. Tables named
+    /// `name.table` are in it; they can only be created once it exists.
+    pub fn create_schema(&mut self, name: &str) -> Result<(), String> {
+        if name.is_empty() || name.contains(|c: char| c == '.' || c.is_whitespace()) {
+            return Err(format!("Invalid schema name '{}'", name));
+        }
+        if !self.schemas.insert(name.to_string()) {
+            return Err(format!("Schema '{}' already exists", name));
+        }
+        Ok(())
+    }
+
+    /// Drops a schema and every table in it, as `DROP SCHEMA name` does.
+    pub fn drop_schema(&mut self, name: &str) -> Result<(), String> {
+        if !self.schemas.remove(name) {
+            return Err(format!("Schema '{}' not found", name));
+        }
+        self.tables.retain(|table_name, _| schema_of(table_name) != Some(name));
+        Ok(())
+    }
+
+    /// The schemas, in name order.
+    pub fn list_schemas(&self) -> Vec<&str> {
+        self.schemas.iter().map(String::as_str).collect()
+    }
+
+    /// The full `schema.table` names of the tables in a schema, in name
+    /// order.
+    pub fn list_schema_tables(&self, schema: &str) -> Result<Vec<&str>, String> {
+        if !self.schemas.contains(schema) {
+            return Err(format!("Schema '{}' not found", schema));
+        }
+        let mut tables: Vec<&str> = self.tables.keys()
+            .map(String::as_str)
+            .filter(|name| schema_of(name) == Some(schema))
+            .collect();
+        tables.sort_unstable();
+        Ok(tables)
+    }
+
+    /// A database holding only a schema and copies of its tables, to save or
+    /// hand over on its own.
+    pub fn export_schema(&self, schema: &str) -> Result<Database, String> {
+        let tables = self.list_schema_tables(schema)?.into_iter()
+            .map(|name| (name.to_string(), self.tables[name].clone()))
+            .collect();
+        let mut export = Database { tables, ..Database::new() };
+        export.schemas.insert(schema.to_string());
+        Ok(export)
+    }
+
+    pub(crate) fn check_schema(&self, table_name: &str) -> Result<(), String> {
+        match schema_of(table_name) {
+            Some(schema) if !self.schemas.contains(schema) => Err(format!("Schema '{}' not found", schema)),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use core::ops::Range;
+
+use crate::prelude::*;
+use crate::Database;
+
+// The ids handed out by reserve_ids, per table, so ids the engine picks
+// itself start after them. Reservations are not saved: once the reserved
+// rows are inserted, the ids after the largest in the table are free anyway.
+#[derive(Clone, Default)]
+pub(crate) struct Sequences {
+    // table -> the first id never reserved
+    next: HashMap<String, u64>,
+}
+
+impl Sequences {
+    // The lowest id the engine may pick for a new row of `table`.
+    pub(crate) fn floor(&self, table: &str) -> u64 {
+        self.next.get(table).copied().unwrap_or(1)
+    }
+}
+
+impl Database {
+    /// Reserves `n` ids of a table that no insert picking its own id will
+    /// use, such as SQL `INSERT` or [`copy_in`](Self::copy_in), so an
+    /// importer can assign them to rows, and point child rows at them,
+    /// before inserting any. The ids follow every id in use or reserved
+    /// before. Reservations last while the database is open.
+    pub fn reserve_ids(&mut self, table_name: &str, n: u64) -> Result<Range<u64>, String> {
+        let table = self.tables.get(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        if table.series.is_some() {
+            return Err(format!("Table '{}' is a time series, whose ids are timestamps", table_name));
+        }
+        let start = table.index.keys().max().map_or(Some(1), |id| id.checked_add(1))
+            .map(|id| id.max(self.sequences.floor(table_name)));
+        let range = start.and_then(|start| Some(start..start.checked_add(n)?)).ok_or("No record ids left in the table")?;
+        self.sequences.next.insert(table_name.to_string(), range.end);
+        Ok(range)
+    }
+}
@@ -0,0 +1,92 @@
+use core::time::Duration;
+
+use crate::prelude::*;
+use crate::Database;
+
+// Toggles that last as long as the database is open, set with `SET name =
+// value` or `PRAGMA name = value` and never saved. The statement timeout is
+// one too, but lives with the other statement limits.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Settings {
+    pub(crate) case_sensitive: bool,
+    // None sorts missing values before every other
+    pub(crate) nulls_last: Option<bool>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings { case_sensitive: true, nulls_last: None }
+    }
+}
+
+fn flag(name: &str, value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "on" | "true" | "1" => Ok(true),
+        "off" | "false" | "0" => Ok(false),
+        _ => Err(format!("Invalid value '{}' for setting '{}': expected on or off", value, name)),
+    }
+}
+
+fn on_off(on: bool) -> String {
+    if on { "on" } else { "off" }.to_string()
+}
+
+impl Database {
+    /// Changes a setting for as long as the database is open, as `SET name =
+    /// value` does:
+    ///
+    /// - `case_sensitive` (`on` or `off`, default `on`): with `off`, SQL
+    ///   compares text ignoring case, and reads every partition.
+    /// - `nulls` (`first`, `last` or `low`, the default): where
+    ///   [`Pipeline::sort`](crate::Pipeline::sort) puts rows without the
+    ///   column, whichever the direction; `low` sorts them before every
+    ///   value.
+    /// - `statement_timeout` (milliseconds, `0` for none): as
+    ///   [`set_statement_timeout`](Self::set_statement_timeout).
+    pub fn set_setting(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name.to_lowercase().as_str() {
+            "case_sensitive" => self.settings.case_sensitive = flag(name, value)?,
+            "nulls" => {
+                self.settings.nulls_last = match value.to_lowercase().as_str() {
+                    "first" => Some(false),
+                    "last" => Some(true),
+                    "low" => None,
+                    _ => return Err(format!("Invalid value '{}' for setting '{}': expected first, last or low", value, name)),
+                }
+            }
+            "statement_timeout" => {
+                let millis: u64 = value.parse().map_err(|_| format!("Invalid value '{}' for setting '{}': expected milliseconds", value, name))?;
+                self.set_statement_timeout(Some(Duration::from_millis(millis)).filter(|t| !t.is_zero()));
+            }
+            _ => return Err(format!("Unknown setting '{}'", name)),
+        }
+        Ok(())
+    }
+
+    /// The value of a setting, as `PRAGMA name` shows it.
+    pub fn setting(&self, name: &str) -> Result<String, String> {
+        match name.to_lowercase().as_str() {
+            "case_sensitive" => Ok(on_off(self.settings.case_sensitive)),
+            "nulls" => Ok(match self.settings.nulls_last {
+                Some(false) => "first",
+                Some(true) => "last",
+                None => "low",
+            }
+            .to_string()),
+            "statement_timeout" => Ok(self.limits.timeout().map_or(0, |t| t.as_millis()).to_string()),
+            _ => Err(format!("Unknown setting '{}'", name)),
+        }
+    }
+}
+
+// The name and value of `name = value`, as SET and PRAGMA take them.
+#[cfg(feature = "sql")]
+pub(crate) fn parse_assignment(tokens: &[&str]) -> Option<(String, String)> {
+    let text = tokens.join(" ");
+    let (name, value) = text.trim_end_matches(';').split_once('=')?;
+    let (name, value) = (name.trim(), value.trim().trim_matches('\''));
+    match name.is_empty() || name.contains(char::is_whitespace) || value.is_empty() {
+        true => None,
+        false => Some((name.to_lowercase(), value.to_string())),
+    }
+}
@@ -0,0 +1,85 @@
+use core::mem::size_of;
+
+use crate::{Database, Record, Table};
+use crate::prelude::*;
+
+/// Size figures for one table, from [`Database::stats`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableStats {
+    name: String,
+    records: usize,
+    memory_bytes: usize,
+    index_bytes: usize,
+    disk_bytes: u64,
+}
+
+impl TableStats {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn records(&self) -> usize {
+        self.records
+    }
+
+    /// Estimated heap and inline bytes of the records, including the index.
+    pub fn memory_bytes(&self) -> usize {
+        self.memory_bytes
+    }
+
+    /// Estimated bytes of the id index and partition lists.
+    pub fn index_bytes(&self) -> usize {
+        self.index_bytes
+    }
+
+    /// Bytes the table takes in a saved file; 0 without the `std` feature,
+    /// which saves no files.
+    pub fn disk_bytes(&self) -> u64 {
+        self.disk_bytes
+    }
+}
+
+// Hash maps store one control byte per slot on top of the entries.
+fn map_bytes<K, V>(capacity: usize) -> usize {
+    capacity * (size_of::<(K, V)>() + 1)
+}
+
+pub(crate) fn record_bytes(record: &Record) -> usize {
+    size_of::<Record>()
+        + record.data.heap_bytes()
+}
+
+#[cfg(feature = "std")]
+fn disk_bytes(table: &Table) -> Result<u64, String> {
+    bincode::serialized_size(table).map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "std"))]
+fn disk_bytes(_: &Table) -> Result<u64, String> {
+    Ok(0)
+}
+
+impl Table {
+    fn stats(&self) -> Result<TableStats, String> {
+        let partition_bytes = self.partitions.as_ref().map_or(0, |p| p.len() * size_of::<u64>());
+        let index_bytes = map_bytes::<u64, usize>(self.index.capacity()) + partition_bytes;
+        let records_bytes = self.records.iter().map(record_bytes).sum::<usize>()
+            + (self.records.capacity() - self.records.len()) * size_of::<Record>();
+        Ok(TableStats {
+            name: self.name.clone(),
+            records: self.records.len(),
+            memory_bytes: records_bytes + index_bytes,
+            index_bytes,
+            disk_bytes: disk_bytes(self)?,
+        })
+    }
+}
+
+impl Database {
+    /// Size figures for every table, largest in memory first.
+    pub fn stats(&self) -> Result<Vec<TableStats>, String> {
+        let mut stats = self.tables.values().map(Table::stats).collect::<Result<Vec<_>, _>>()?;
+        stats.sort_by(|a, b| b.memory_bytes.cmp(&a.memory_bytes).then_with(|| a.name.cmp(&b.name)));
+        Ok(stats)
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::sync::Mutex;
+
+use crate::clock::Timer;
+use crate::{telemetry, Database};
+
+// Bytes asked for at a time when reading a whole blob page by page.
+const PAGE_BYTES: usize = 64 * 1024;
+
+/// Where databases and logs are kept: named blobs that can be read a page
+/// at a time, replaced whole, or appended to. Implement it to keep them
+/// somewhere other than local files, such as object storage, an encrypted
+/// volume or a test double.
+///
+/// [`Database::save_to`] writes a new copy next to the old one, syncs it,
+/// and only then renames it into place, so a save that fails part way
+/// leaves the last saved database intact.
+pub trait StorageBackend: Send + Sync {
+    /// Up to `len` bytes of `name` from `offset`; fewer only at its end.
+    fn read_page(&self, name: &str, offset: u64, len: usize) -> io::Result<Vec<u8>>;
+    /// Writes `bytes` as the whole of `name`. A failed write may leave
+    /// some of them written.
+    fn write(&self, name: &str, bytes: &[u8]) -> io::Result<()>;
+    /// Adds `bytes` to the end of `name`, creating it if needed. A failed
+    /// append may leave some of them written.
+    fn append(&self, name: &str, bytes: &[u8]) -> io::Result<()>;
+    /// Makes what was written to `name` durable.
+    fn sync(&self, name: &str) -> io::Result<()>;
+    /// Replaces `to` with `from` in one step.
+    fn rename(&self, from: &str, to: &str) -> io::Result<()>;
+
+    /// All of `name`.
+    fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        loop {
+            let page = self.read_page(name, bytes.len() as u64, PAGE_BYTES)?;
+            bytes.extend_from_slice(&page);
+            if page.len() < PAGE_BYTES {
+                return Ok(bytes);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for dyn StorageBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StorageBackend")
+    }
+}
+
+/// Files on the local file system.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FileStorage;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl StorageBackend for FileStorage {
+    fn read_page(&self, name: &str, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        use std::io::{Read, Seek, SeekFrom};
+        let mut file = std::fs::File::open(name)?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut page = Vec::with_capacity(len);
+        file.take(len as u64).read_to_end(&mut page)?;
+        Ok(page)
+    }
+
+    fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        std::fs::read(name)
+    }
+
+    fn write(&self, name: &str, bytes: &[u8]) -> io::Result<()> {
+        std::fs::write(name, bytes)
+    }
+
+    fn append(&self, name: &str, bytes: &[u8]) -> io::Result<()> {
+        use std::io::Write;
+        std::fs::OpenOptions::new().create(true).append(true).open(name)?.write_all(bytes)
+    }
+
+    fn sync(&self, name: &str) -> io::Result<()> {
+        std::fs::File::open(name)?.sync_all()
+    }
+
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        std::fs::rename(from, to)
+    }
+}
+
+/// Blobs kept in memory, for tests and for databases that need no disk.
+/// Sync does nothing.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    blobs: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        MemoryStorage::default()
+    }
+
+    /// The names of the blobs held, in no particular order.
+    pub fn names(&self) -> Vec<String> {
+        self.blobs().keys().cloned().collect()
+    }
+
+    fn blobs(&self) -> std::sync::MutexGuard<'_, HashMap<String, Vec<u8>>> {
+        self.blobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn not_found(name: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("No blob named '{}'", name))
+}
+
+impl StorageBackend for MemoryStorage {
+    fn read_page(&self, name: &str, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let blobs = self.blobs();
+        let blob = blobs.get(name).ok_or_else(|| not_found(name))?;
+        let start = (offset as usize).min(blob.len());
+        Ok(blob[start..blob.len().min(start + len)].to_vec())
+    }
+
+    fn write(&self, name: &str, bytes: &[u8]) -> io::Result<()> {
+        self.blobs().insert(name.to_string(), bytes.to_vec());
+        Ok(())
+    }
+
+    fn append(&self, name: &str, bytes: &[u8]) -> io::Result<()> {
+        self.blobs().entry(name.to_string()).or_default().extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn sync(&self, name: &str) -> io::Result<()> {
+        self.blobs().get(name).map(|_| ()).ok_or_else(|| not_found(name))
+    }
+
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        let mut blobs = self.blobs();
+        let blob = blobs.remove(from).ok_or_else(|| not_found(from))?;
+        blobs.insert(to.to_string(), blob);
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Faults {
+    // bytes the next write or append gets through before it fails
+    short_write: Option<usize>,
+    failed_syncs: usize,
+    // bits flipped in what the next read returns
+    flipped_bits: Vec<usize>,
+}
+
+/// Storage that fails on request, for testing what survives a crash or
+/// bad media: writes cut short, syncs that fail, and bits flipped on read.
+/// Each fault happens once, on the next call it applies to.
+#[derive(Default)]
+pub struct FaultyStorage<S> {
+    inner: S,
+    faults: Mutex<Faults>,
+}
+
+impl<S: StorageBackend> FaultyStorage<S> {
+    pub fn new(inner: S) -> Self {
+        FaultyStorage { inner, faults: Mutex::default() }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The next write or append stores only the first `bytes` bytes, then
+    /// fails.
+    pub fn short_write(&self, bytes: usize) {
+        self.faults().short_write = Some(bytes);
+    }
+
+    /// The next sync fails.
+    pub fn fail_sync(&self) {
+        self.faults().failed_syncs += 1;
+    }
+
+    /// The next read returns its bytes with bit `bit` flipped, counting
+    /// from the lowest bit of the first byte. A page read flips the bit
+    /// if the page holds it.
+    pub fn flip_bit(&self, bit: usize) {
+        self.faults().flipped_bits.push(bit);
+    }
+
+    fn faults(&self) -> std::sync::MutexGuard<'_, Faults> {
+        self.faults.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<S: StorageBackend> FaultyStorage<S> {
+    fn flip(&self, offset: u64, mut bytes: Vec<u8>) -> Vec<u8> {
+        for bit in std::mem::take(&mut self.faults().flipped_bits) {
+            if let Some(byte) = (bit / 8).checked_sub(offset as usize).and_then(|at| bytes.get_mut(at)) {
+                *byte ^= 1 << (bit % 8);
+            }
+        }
+        bytes
+    }
+
+    // Runs `write` on all of `bytes`, or on the start of them and fails.
+    fn cut_short(&self, bytes: &[u8], write: impl FnOnce(&[u8]) -> io::Result<()>) -> io::Result<()> {
+        match self.faults().short_write.take() {
+            Some(written) => {
+                write(&bytes[..written.min(bytes.len())])?;
+                Err(io::Error::new(io::ErrorKind::WriteZero, "injected short write"))
+            }
+            None => write(bytes),
+        }
+    }
+}
+
+impl<S: StorageBackend> StorageBackend for FaultyStorage<S> {
+    fn read_page(&self, name: &str, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        Ok(self.flip(offset, self.inner.read_page(name, offset, len)?))
+    }
+
+    fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        Ok(self.flip(0, self.inner.read(name)?))
+    }
+
+    fn write(&self, name: &str, bytes: &[u8]) -> io::Result<()> {
+        self.cut_short(bytes, |bytes| self.inner.write(name, bytes))
+    }
+
+    fn append(&self, name: &str, bytes: &[u8]) -> io::Result<()> {
+        self.cut_short(bytes, |bytes| self.inner.append(name, bytes))
+    }
+
+    fn sync(&self, name: &str) -> io::Result<()> {
+        let mut faults = self.faults();
+        if faults.failed_syncs > 0 {
+            faults.failed_syncs -= 1;
+            return Err(io::Error::other("injected sync failure"));
+        }
+        drop(faults);
+        self.inner.sync(name)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        self.inner.rename(from, to)
+    }
+}
+
+impl Database {
+    /// Saves to `name` in `storage`, replacing what was there only once
+    /// the new copy is durable.
+    pub fn save_to(&self, storage: &dyn StorageBackend, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.write_to(storage, name).map(drop)
+    }
+
+    // Saves like `save_to`, returning how many bytes were written.
+    pub(crate) fn write_to(&self, storage: &dyn StorageBackend, name: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        let timer = Timer::start();
+        let bytes = self.to_bytes()?;
+        let staged = format!("{}.tmp", name);
+        storage.write(&staged, &bytes)?;
+        storage.sync(&staged)?;
+        storage.rename(&staged, name)?;
+        telemetry::persisted("save", timer.elapsed(), bytes.len());
+        telemetry::record("bytes", bytes.len());
+        Ok(bytes.len())
+    }
+
+    /// Loads `name` from `storage`. A damaged copy fails its checksum
+    /// rather than loading wrong data.
+    pub fn load_from(storage: &dyn StorageBackend, name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let timer = Timer::start();
+        let bytes = storage.read(name)?;
+        let db = Self::from_bytes(&bytes)?;
+        telemetry::persisted("load", timer.elapsed(), bytes.len());
+        telemetry::record("bytes", bytes.len());
+        Ok(db)
+    }
+}
@@ -0,0 +1,123 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::dictionary::{self, DictionaryColumn};
+use crate::expiry::Expiry;
+use crate::generated::GeneratedColumn;
+use crate::history::History;
+use crate::partition::Partitions;
+use crate::prelude::*;
+use crate::queue::Queue;
+use crate::row::SharedNames;
+use crate::timeseries::TimeSeries;
+use crate::{ColumnType, CompareAs, PartitionScheme, ProtoMessage, Record, Table};
+
+// Tables with a protobuf message keep their records as encoded messages at rest.
+// Other tables keep columns of repeated values apart, as dictionaries.
+#[derive(Serialize)]
+enum StoredRecordsRef<'a> {
+    Maps(&'a [Record]),
+    Proto(Vec<(u64, Vec<u8>)>),
+    Dictionary(Vec<Record>, Vec<DictionaryColumn>),
+}
+
+#[derive(Deserialize)]
+enum StoredRecords {
+    Maps(Vec<Record>),
+    Proto(Vec<(u64, Vec<u8>)>),
+    Dictionary(Vec<Record>, Vec<DictionaryColumn>),
+}
+
+#[derive(Serialize)]
+struct StoredTableRef<'a> {
+    name: &'a str,
+    records: StoredRecordsRef<'a>,
+    index: &'a HashMap<u64, usize>,
+    proto: &'a Option<ProtoMessage>,
+    partitioning: Option<&'a PartitionScheme>,
+    history: &'a Option<History>,
+    encrypted: &'a BTreeSet<String>,
+    generated: &'a [GeneratedColumn],
+    enums: &'a BTreeMap<String, Vec<String>>,
+    series: &'a Option<TimeSeries>,
+    queue: &'a Option<Queue>,
+    max_rows: Option<usize>,
+    columnar: bool,
+    compare: &'a BTreeMap<String, CompareAs>,
+    expiry: Option<&'a str>,
+    timestamps: bool,
+    types: &'a BTreeMap<String, ColumnType>,
+}
+
+// Partition segments and the expiry order are not stored; they are rebuilt
+// from the records.
+#[derive(Deserialize)]
+struct StoredTable {
+    name: String,
+    records: StoredRecords,
+    index: HashMap<u64, usize>,
+    proto: Option<ProtoMessage>,
+    partitioning: Option<PartitionScheme>,
+    history: Option<History>,
+    encrypted: BTreeSet<String>,
+    generated: Vec<GeneratedColumn>,
+    enums: BTreeMap<String, Vec<String>>,
+    series: Option<TimeSeries>,
+    queue: Option<Queue>,
+    max_rows: Option<usize>,
+    columnar: bool,
+    compare: BTreeMap<String, CompareAs>,
+    expiry: Option<String>,
+    timestamps: bool,
+    types: BTreeMap<String, ColumnType>,
+}
+
+impl StoredTable {
+    fn into_table(self) -> Result<Table, String> {
+        let mut records: Vec<Record> = match (self.records, &self.proto) {
+            (StoredRecords::Maps(records), _) => records,
+            (StoredRecords::Dictionary(mut records, columns), _) => {
+                dictionary::decode(&mut records, columns)?;
+                records
+            }
+            (StoredRecords::Proto(rows), Some(message)) => rows.into_iter()
+                .map(|(id, bytes)| Ok(Record { id, data: message.decode(&bytes)? }))
+                .collect::<Result<_, String>>()?,
+            (StoredRecords::Proto(_), None) => return Err("Protobuf records without a message definition".to_string()),
+        };
+        let mut names = SharedNames::default();
+        records.iter_mut().for_each(|r| names.share(&mut r.data));
+        let partitions = self.partitioning.map(|scheme| Partitions::new(scheme, &records));
+        let expiry = self.expiry.map(|column| Expiry::new(&column, &records));
+        Ok(Table { name: self.name, records, index: self.index, proto: self.proto, partitions, history: self.history, encrypted: self.encrypted, generated: self.generated, enums: self.enums, series: self.series, queue: self.queue, max_rows: self.max_rows, temporary: false, columnar: self.columnar.then(Default::default), compare: self.compare, expiry, timestamps: self.timestamps, types: self.types })
+    }
+}
+
+impl Serialize for Table {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let records = match &self.proto {
+            Some(message) => StoredRecordsRef::Proto(
+                self.records.iter()
+                    .map(|r| Ok((r.id, message.encode(&r.data)?)))
+                    .collect::<Result<_, String>>()
+                    .map_err(S::Error::custom)?,
+            ),
+            None => match dictionary::encode(&self.records) {
+                (_, columns) if columns.is_empty() => StoredRecordsRef::Maps(&self.records),
+                (records, columns) => StoredRecordsRef::Dictionary(records, columns),
+            },
+        };
+        let partitioning = self.partitions.as_ref().map(Partitions::scheme);
+        let expiry = self.expiry.as_ref().map(Expiry::column);
+        StoredTableRef { name: &self.name, records, index: &self.index, proto: &self.proto, partitioning, history: &self.history, encrypted: &self.encrypted, generated: &self.generated, enums: &self.enums, series: &self.series, queue: &self.queue, max_rows: self.max_rows, columnar: self.columnar.is_some(), compare: &self.compare, expiry, timestamps: self.timestamps, types: &self.types }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Table {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        StoredTable::deserialize(deserializer)?.into_table().map_err(D::Error::custom)
+    }
+}
@@ -0,0 +1,100 @@
+use core::ops::ControlFlow;
+
+use crate::clock::Timer;
+use crate::{calls, interrupt, is_count, project, pseudo, telemetry, Database, Record, SqlStatement};
+use crate::prelude::*;
+
+impl Database {
+    /// Runs a statement like [`execute_sql`](Self::execute_sql), but hands
+    /// each row it produces to `on_row` instead of collecting them, and stops
+    /// as soon as `on_row` breaks. A SELECT reads its rows one by one, so
+    /// exports never hold the whole result, and the result-size limits of
+    /// [`QueryLimits`](crate::QueryLimits) don't apply to it; other statements
+    /// hand over their rows once they finish. Returns how many rows `on_row`
+    /// was given.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "potatodb.query", skip(self, on_row), fields(kind, table, rows)))]
+    pub fn execute_sql_streaming(&mut self, sql: &str, mut on_row: impl FnMut(Record) -> ControlFlow<()>) -> Result<usize, String> {
+        let timer = Timer::start();
+        let _statement = interrupt::begin(&self.limits, self.settings, None);
+        let (sql, statement) = self.prepare(sql);
+        let kind = statement.as_ref().map_or("invalid", SqlStatement::kind);
+        if let Ok(statement) = &statement {
+            telemetry::record("table", statement.table());
+        }
+        let mut rows = 0;
+        let mut deliver = |db: &Database, record: Record| {
+            db.notify_row(&sql, &record);
+            rows += 1;
+            on_row(record)
+        };
+        let result = match statement {
+            Ok(select @ SqlStatement::Select { .. }) => self.stream_select(select, &mut |record| deliver(self, record)),
+            statement => statement.and_then(|statement| self.execute_statement(statement)).map(|records| {
+                let _ = records.into_iter().try_for_each(|record| deliver(self, record));
+            }),
+        };
+        let result = result.map(|()| rows);
+        self.finish_statement(timer, kind, &sql, result.as_ref().copied().map_err(String::as_str));
+        result
+    }
+
+    // Like `execute_select`, but hands each row to `on_row` as soon as it
+    // matches.
+    fn stream_select(&self, select: SqlStatement, on_row: &mut dyn FnMut(Record) -> ControlFlow<()>) -> Result<(), String> {
+        // sorted rows and groups are only known once every row has been read
+        if matches!(&select, SqlStatement::Select { order_by, group_by, .. } if !order_by.is_empty() || group_by.is_some()) {
+            let _ = self.execute_select(select)?.into_iter().try_for_each(on_row);
+            return Ok(());
+        }
+        let SqlStatement::Select { table, columns, condition, as_of, sample, hints, page, .. } = select else {
+            return Err("Only SELECT statements can be streamed".to_string());
+        };
+        let columns = &columns[..];
+        let table = self.select_source(&table, as_of, &condition)?;
+        if is_count(columns) {
+            let _ = self.count_rows(&table, &columns[0], condition, sample, hints)?.into_iter().try_for_each(on_row);
+            return Ok(());
+        }
+        let calls = calls(columns)?;
+        if let Some(proto) = &table.proto {
+            columns.iter().filter(|c| *c != "*" && !c.contains('(') && !pseudo::is_pseudo(c)).try_for_each(|c| proto.check_column(c))?;
+        }
+        // rows before the offset are skipped, and reading stops once the
+        // page is full
+        let (mut skip, mut left) = (page.offset, page.limit.unwrap_or(usize::MAX));
+        let mut emit = |record: Record| {
+            if left == 0 {
+                return ControlFlow::Break(());
+            }
+            if skip > 0 {
+                skip -= 1;
+                return ControlFlow::Continue(());
+            }
+            left -= 1;
+            on_row(record)?;
+            match left {
+                0 => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            }
+        };
+        let read = match (&condition, &sample) {
+            (None, None) if calls.is_empty() && page.is_all() && !columns.iter().any(|c| pseudo::is_pseudo(c)) => table.select_columns(columns),
+            _ => None,
+        };
+        let _ = match read {
+            Some(mut records) => {
+                self.keys.reveal(&table, &mut records);
+                self.apply_masks(&table.name, &mut records);
+                records.into_iter().try_for_each(&mut emit)
+            }
+            None => {
+                let rows = match sample {
+                    Some(sample) => sample.draw(table.scan(&None)),
+                    None => table.scan(hints.scanned(&condition)),
+                };
+                table.try_filter(rows, &condition, |record| emit(project(columns, &calls, &self.readable(&table, &record))))
+            }
+        };
+        interrupt::check()
+    }
+}
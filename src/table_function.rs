@@ -0,0 +1,147 @@
+#[cfg(feature = "json")]
+use serde_json::Value as Json;
+
+#[cfg(feature = "json")]
+use crate::foreign::column_value;
+
+use crate::generated::number_text;
+use crate::history::{date_text, parse_date};
+use crate::{interrupt, Record, Row, Table};
+use crate::prelude::*;
+
+// The arguments between the parentheses of a call, split at commas outside
+// quotes, with `'text'` unquoted and `''` inside it standing for a quote.
+fn arguments(inner: &str) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut argument = String::new();
+    let mut quoted = false;
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' if quoted && chars.peek() == Some(&'\'') => {
+                argument.push('\'');
+                chars.next();
+            }
+            '\'' => quoted = !quoted,
+            ',' if !quoted => arguments.push(core::mem::take(&mut argument).trim().to_string()),
+            c if quoted || !c.is_whitespace() => argument.push(c),
+            _ => {}
+        }
+    }
+    arguments.push(argument.trim().to_string());
+    arguments
+}
+
+// The most rows a series may have; longer ones are refused rather than
+// built in memory.
+const MAX_SERIES: u64 = 1_000_000;
+
+// How many steps of a series fit between its bounds, after checking that
+// it isn't too long to build.
+fn series_length(steps: f64) -> Result<u64, String> {
+    if steps > MAX_SERIES as f64 {
+        return Err(format!("generate_series would make more than {} rows", MAX_SERIES));
+    }
+    Ok(if steps < 0.0 { 0 } else { steps as u64 + 1 })
+}
+
+// `generate_series(start, stop[, step])`: numbers from `start` to `stop`,
+// both included, `step` apart, or dates `step` days apart. Whole numbers
+// and dates are stepped exactly, as integers.
+fn generate_series(arguments: &[String]) -> Result<Vec<Row>, String> {
+    let (start, stop, step) = match arguments {
+        [start, stop] => (start.as_str(), stop.as_str(), "1"),
+        [start, stop, step] => (start.as_str(), stop.as_str(), step.as_str()),
+        _ => return Err("generate_series takes a start, a stop and an optional step".to_string()),
+    };
+    let zero = || "The step of generate_series cannot be zero".to_string();
+    let integers = |start: i64, stop: i64, step: i64| -> Result<Vec<i64>, String> {
+        if step == 0 {
+            return Err(zero());
+        }
+        let (span, step) = (stop as i128 - start as i128, step as i128);
+        // a stop behind the start in the direction of the step ends the
+        // series before it begins
+        let steps = if span != 0 && (span < 0) != (step < 0) { -1 } else { span / step };
+        let length = series_length(steps as f64)?;
+        Ok((0..length as i128).map(|k| (start as i128 + k * step) as i64).collect())
+    };
+    let dates = parse_date(start).zip(parse_date(stop));
+    let values: Vec<String> = match (dates, start.parse::<i64>(), stop.parse::<i64>(), step.parse::<i64>()) {
+        (Some((start, stop)), _, _, Ok(step)) => integers(start, stop, step)?.into_iter().map(date_text).collect(),
+        (None, Ok(start), Ok(stop), Ok(step)) => integers(start, stop, step)?.iter().map(i64::to_string).collect(),
+        _ => {
+            let step: f64 = step.parse().map_err(|_| format!("Invalid step '{}' for generate_series", step))?;
+            if step == 0.0 || !step.is_finite() {
+                return Err(zero());
+            }
+            let number = |text: &str| text.parse::<f64>().map_err(|_| format!("Invalid bound '{}' for generate_series", text));
+            let (start, stop) = (number(start)?, number(stop)?);
+            (0..series_length(((stop - start) / step).floor())?)
+                .map(|k| start + k as f64 * step)
+                .take_while(|&n| if step > 0.0 { n <= stop } else { n >= stop })
+                .map(number_text)
+                .collect()
+        }
+    };
+    Ok(values.into_iter()
+        .take_while(|_| interrupt::running())
+        .map(|value| Row::from([("value".to_string(), value)]))
+        .collect())
+}
+
+// `json_each(doc)`: a row for each element of a JSON array or member of a
+// JSON object, with its `key` (the index in an array), `value` and `type`.
+#[cfg(feature = "json")]
+fn json_each(arguments: &[String]) -> Result<Vec<Row>, String> {
+    let [doc] = arguments else {
+        return Err("json_each takes one JSON document".to_string());
+    };
+    let doc: Json = serde_json::from_str(doc).map_err(|e| format!("Invalid JSON for json_each: {}", e))?;
+    let members: Vec<(String, Json)> = match doc {
+        Json::Array(elements) => elements.into_iter().enumerate().map(|(i, v)| (i.to_string(), v)).collect(),
+        Json::Object(members) => members.into_iter().collect(),
+        other => vec![(String::new(), other)],
+    };
+    Ok(members.into_iter()
+        .map(|(key, value)| {
+            let kind = match &value {
+                Json::Null => "null",
+                Json::Bool(true) => "true",
+                Json::Bool(false) => "false",
+                Json::Number(n) if n.is_f64() => "real",
+                Json::Number(_) => "integer",
+                Json::String(_) => "text",
+                Json::Array(_) => "array",
+                Json::Object(_) => "object",
+            };
+            let mut row = Row::from([("key".to_string(), key), ("type".to_string(), kind.to_string())]);
+            if let Some(value) = column_value(value) {
+                row.insert("value".to_string(), value);
+            }
+            row
+        })
+        .collect())
+}
+
+#[cfg(not(feature = "json"))]
+fn json_each(_: &[String]) -> Result<Vec<Row>, String> {
+    Err("json_each needs the json feature".to_string())
+}
+
+// The rows a table-valued function named in FROM, such as
+// `generate_series(1, 10)`, gives, as a table for one statement.
+pub(crate) fn call(text: &str) -> Result<Table, String> {
+    let (name, inner) = text.split_once('(')
+        .and_then(|(name, rest)| Some((name.trim(), rest.trim_end().strip_suffix(')')?)))
+        .ok_or(format!("Invalid table function '{}'", text))?;
+    let arguments = arguments(inner);
+    let rows = match name.to_lowercase().as_str() {
+        "generate_series" => generate_series(&arguments)?,
+        "json_each" => json_each(&arguments)?,
+        _ => return Err(format!("Unknown table function '{}'", name)),
+    };
+    interrupt::check()?;
+    let records = rows.into_iter().zip(1..).map(|(data, id)| Record { id, data }).collect();
+    Ok(Table::transient(text, records))
+}
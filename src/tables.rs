@@ -0,0 +1,81 @@
+use alloc::sync::Arc;
+use core::ops::Index;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::prelude::*;
+use crate::Table;
+
+// The tables of a database by name. Each is shared between copies of the
+// database, such as a background save's or a transaction's, until one of
+// them writes to it, which copies that table alone.
+#[derive(Clone, Default)]
+pub(crate) struct Tables(HashMap<String, Arc<Table>>);
+
+impl Tables {
+    pub(crate) fn get(&self, name: &str) -> Option<&Table> {
+        self.0.get(name).map(|table| &**table)
+    }
+
+    // Copies the table first if another copy of the database shares it.
+    pub(crate) fn get_mut(&mut self, name: &str) -> Option<&mut Table> {
+        self.0.get_mut(name).map(Arc::make_mut)
+    }
+
+    pub(crate) fn contains_key(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    pub(crate) fn insert(&mut self, table: Table) {
+        self.0.insert(table.name.clone(), Arc::new(table));
+    }
+
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(&String, &Table) -> bool) {
+        self.0.retain(|name, table| keep(name, table));
+    }
+
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &String> {
+        self.0.keys()
+    }
+
+    pub(crate) fn values(&self) -> impl Iterator<Item = &Table> {
+        self.0.values().map(|table| &**table)
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&String, &Table)> {
+        self.0.iter().map(|(name, table)| (name, &**table))
+    }
+}
+
+impl FromIterator<(String, Table)> for Tables {
+    fn from_iter<I: IntoIterator<Item = (String, Table)>>(tables: I) -> Self {
+        Tables(tables.into_iter().map(|(name, table)| (name, Arc::new(table))).collect())
+    }
+}
+
+impl From<HashMap<String, Table>> for Tables {
+    fn from(tables: HashMap<String, Table>) -> Self {
+        tables.into_iter().collect()
+    }
+}
+
+impl Index<&str> for Tables {
+    type Output = Table;
+
+    fn index(&self, name: &str) -> &Table {
+        &self.0[name]
+    }
+}
+
+// Saved as a plain map of tables, as before they were shared.
+impl Serialize for Tables {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for Tables {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(HashMap::<String, Table>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
@@ -0,0 +1,46 @@
+// Metrics go through the `metrics` facade, so the host application picks the
+// exporter, e.g. metrics-exporter-prometheus for a /metrics endpoint. Spans
+// come from `tracing::instrument` attributes on the functions themselves.
+// Queries are only timed with `sql` and saves only happen with `std`, so
+// builds without either leave some of these uncalled.
+#![cfg_attr(not(all(feature = "std", feature = "sql")), allow(dead_code))]
+
+use core::time::Duration;
+
+// Fills in a field declared on the current span.
+#[cfg(feature = "tracing")]
+pub(crate) fn record(field: &'static str, value: impl tracing::Value) {
+    tracing::Span::current().record(field, value);
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn record<T>(_: &'static str, _: T) {}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn query(kind: &'static str, duration: Duration, failed: bool) {
+    metrics::counter!("potatodb_queries_total", "kind" => kind).increment(1);
+    if failed {
+        metrics::counter!("potatodb_query_errors_total", "kind" => kind).increment(1);
+    }
+    metrics::histogram!("potatodb_query_duration_seconds", "kind" => kind).record(duration.as_secs_f64());
+}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn rows_scanned(table: &str, rows: usize) {
+    metrics::counter!("potatodb_rows_scanned_total", "table" => table.to_string()).increment(rows as u64);
+}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn persisted(operation: &'static str, duration: Duration, bytes: usize) {
+    metrics::histogram!("potatodb_persist_duration_seconds", "operation" => operation).record(duration.as_secs_f64());
+    metrics::gauge!("potatodb_file_bytes").set(bytes as f64);
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn query(_: &'static str, _: Duration, _: bool) {}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn rows_scanned(_: &str, _: usize) {}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn persisted(_: &'static str, _: Duration, _: usize) {}
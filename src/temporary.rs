@@ -0,0 +1,36 @@
+
+use serde::{Serialize, Serializer};
+
+use crate::tables::Tables;
+use crate::{Database, Table};
+use crate::prelude::*;
+
+// Saves leave temporary tables out.
+pub(crate) fn persistent_tables<S: Serializer>(tables: &Tables, serializer: S) -> Result<S::Ok, S::Error> {
+    let persistent: HashMap<&String, &Table> = tables.iter().filter(|(_, t)| !t.temporary).collect();
+    persistent.serialize(serializer)
+}
+
+impl Database {
+    /// Creates a table for the current session, as `CREATE TEMP TABLE`
+    /// does: it works like any other table but is never saved, and is
+    /// dropped when the session ends.
+    pub fn create_temp_table(&mut self, name: &str) -> Result<(), String> {
+        self.create_table(name.to_string())?;
+        if let Some(table) = self.tables.get_mut(name) {
+            table.temporary = true;
+        }
+        Ok(())
+    }
+
+    /// The temporary tables of the current session, in no particular order.
+    pub fn temp_tables(&self) -> Vec<&str> {
+        self.tables.values().filter(|t| t.temporary).map(|t| t.name.as_str()).collect()
+    }
+
+    /// Ends the session, dropping its temporary tables. Switching the
+    /// session user ends the session too.
+    pub fn end_session(&mut self) {
+        self.tables.retain(|_, table| !table.temporary);
+    }
+}
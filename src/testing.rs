@@ -0,0 +1,275 @@
+//! Randomized cross-checks of the SQL engine. [`generate`] builds a
+//! workload of statements over random tables from a seed, and [`check`]
+//! runs it against a [`Database`] and against [`Reference`], a plain model
+//! of what each statement should do, failing at the first difference.
+//!
+//! The same seed always builds the same workload, so a failure is
+//! reproduced by running its seed again:
+//!
+//! ```
+//! for seed in 0..20 {
+//!     potatodb::testing::check(seed, 100).unwrap();
+//! }
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::sample::Random;
+use crate::{Database, Record, Value};
+
+const COLUMNS: [&str; 4] = ["c0", "c1", "c2", "c3"];
+// short values, some of which order differently as text than as numbers
+const VALUES: [&str; 8] = ["a", "b", "bb", "c", "7", "10", "9", "007"];
+
+/// How a generated predicate compares a column with a value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Equals,
+    NotEquals,
+    GreaterThan,
+    LessThan,
+}
+
+/// A generated WHERE predicate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Predicate {
+    Compare(String, Comparison, String),
+    Between(String, String, String),
+    And(Box<Predicate>, Box<Predicate>),
+}
+
+/// A generated SQL statement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Statement {
+    CreateTable { table: String },
+    Insert { table: String, columns: Vec<String>, values: Vec<String> },
+    Update { table: String, column: String, value: String, filter: Option<Predicate> },
+    Delete { table: String, filter: Option<Predicate> },
+    /// No columns selects `*`.
+    Select { table: String, columns: Vec<String>, filter: Option<Predicate> },
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Predicate::Compare(column, comparison, value) => {
+                let operator = match comparison {
+                    Comparison::Equals => "=",
+                    Comparison::NotEquals => "!=",
+                    Comparison::GreaterThan => ">",
+                    Comparison::LessThan => "<",
+                };
+                write!(f, "{} {} {}", column, operator, value)
+            }
+            Predicate::Between(column, low, high) => write!(f, "{} BETWEEN {} AND {}", column, low, high),
+            Predicate::And(left, right) => write!(f, "{} AND {}", left, right),
+        }
+    }
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let filter = |filter: &Option<Predicate>| filter.as_ref().map(|p| format!(" WHERE {}", p)).unwrap_or_default();
+        match self {
+            Statement::CreateTable { table } => write!(f, "CREATE TABLE {}", table),
+            Statement::Insert { table, columns, values } => {
+                write!(f, "INSERT INTO {} ({}) VALUES ({})", table, columns.join(", "), values.join(", "))
+            }
+            Statement::Update { table, column, value, filter: p } => write!(f, "UPDATE {} SET {} = {}{}", table, column, value, filter(p)),
+            Statement::Delete { table, filter: p } => write!(f, "DELETE FROM {}{}", table, filter(p)),
+            Statement::Select { table, columns, filter: p } => {
+                let columns = if columns.is_empty() { "*".to_string() } else { columns.join(", ") };
+                write!(f, "SELECT {} FROM {}{}", columns, table, filter(p))
+            }
+        }
+    }
+}
+
+impl Predicate {
+    fn matches(&self, data: &BTreeMap<String, String>) -> bool {
+        // numbers compare as numbers and text as text; a number and a word
+        // match nothing but `!=`
+        let order = |column: &String, v: &str| {
+            let (value, v) = (Value::parse(data.get(column)?), Value::parse(v));
+            match (&value, &v) {
+                (Value::Text(a), Value::Text(b)) => Some(a.cmp(b)),
+                _ => value.compare(&v),
+            }
+        };
+        match self {
+            Predicate::Compare(column, Comparison::Equals, v) => order(column, v) == Some(Ordering::Equal),
+            Predicate::Compare(column, Comparison::NotEquals, v) => order(column, v) != Some(Ordering::Equal),
+            Predicate::Compare(column, Comparison::GreaterThan, v) => order(column, v) == Some(Ordering::Greater),
+            Predicate::Compare(column, Comparison::LessThan, v) => order(column, v) == Some(Ordering::Less),
+            Predicate::Between(column, low, high) => order(column, low).is_some_and(Ordering::is_ge) && order(column, high).is_some_and(Ordering::is_le),
+            Predicate::And(left, right) => left.matches(data) && right.matches(data),
+        }
+    }
+}
+
+/// A row as the model keeps it: its id and columns.
+pub type ModelRow = (u64, BTreeMap<String, String>);
+
+/// What each generated statement should do, kept as simple as possible:
+/// tables are lists of rows in insertion order and every value is text.
+#[derive(Clone, Debug, Default)]
+pub struct Reference {
+    tables: BTreeMap<String, Vec<ModelRow>>,
+}
+
+impl Reference {
+    /// Runs `statement`, returning the rows it selected or changed, as
+    /// `execute_sql` does.
+    pub fn apply(&mut self, statement: &Statement) -> Result<Vec<ModelRow>, String> {
+        let matches = |filter: &Option<Predicate>, data: &BTreeMap<String, String>| filter.as_ref().map_or(true, |p| p.matches(data));
+        match statement {
+            Statement::CreateTable { table } => {
+                self.tables.insert(table.clone(), Vec::new());
+                Ok(Vec::new())
+            }
+            Statement::Insert { table: name, columns, values } => {
+                let rows = self.tables.get_mut(name).ok_or("Table not found")?;
+                // the next id is one past the row count, unless a row holds it
+                let mut id = rows.len() as u64 + 1;
+                if rows.iter().any(|(existing, _)| *existing == id) {
+                    id = rows.iter().map(|(existing, _)| existing + 1).max().unwrap_or(1);
+                }
+                let data = columns.iter().cloned().zip(values.iter().cloned()).collect();
+                rows.push((id, data));
+                Ok(vec![rows.last().unwrap().clone()])
+            }
+            Statement::Update { table: name, column, value, filter } => {
+                let rows = self.tables.get_mut(name).ok_or("Table not found")?;
+                // only rows that have the column are updated
+                let mut updated = Vec::new();
+                for (id, data) in rows {
+                    if data.contains_key(column) && matches(filter, data) {
+                        data.insert(column.clone(), value.clone());
+                        updated.push((*id, data.clone()));
+                    }
+                }
+                Ok(updated)
+            }
+            Statement::Delete { table: name, filter } => {
+                let rows = self.tables.get_mut(name).ok_or("Table not found")?;
+                let (deleted, kept) = std::mem::take(rows).into_iter().partition(|(_, data)| matches(filter, data));
+                *rows = kept;
+                Ok(deleted)
+            }
+            Statement::Select { table: name, columns, filter } => {
+                let rows = self.tables.get(name).ok_or("Table not found")?;
+                Ok(rows.iter()
+                    .filter(|(_, data)| matches(filter, data))
+                    .map(|(id, data)| {
+                        let data = data.iter().filter(|(c, _)| columns.is_empty() || columns.contains(c)).map(|(c, v)| (c.clone(), v.clone())).collect();
+                        (*id, data)
+                    })
+                    .collect())
+            }
+        }
+    }
+}
+
+struct Generator {
+    random: Random,
+    tables: usize,
+}
+
+impl Generator {
+    fn pick<'a>(&mut self, choices: &[&'a str]) -> &'a str {
+        choices[self.random.below(choices.len())]
+    }
+
+    fn table(&mut self) -> String {
+        format!("t{}", self.random.below(self.tables))
+    }
+
+    fn value(&mut self) -> String {
+        self.pick(&VALUES).to_string()
+    }
+
+    fn predicate(&mut self, depth: usize) -> Predicate {
+        let column = self.pick(&COLUMNS).to_string();
+        match self.random.below(if depth > 0 { 7 } else { 6 }) {
+            0 | 1 => Predicate::Compare(column, Comparison::Equals, self.value()),
+            2 => Predicate::Compare(column, Comparison::NotEquals, self.value()),
+            3 => Predicate::Compare(column, Comparison::GreaterThan, self.value()),
+            4 => Predicate::Compare(column, Comparison::LessThan, self.value()),
+            5 => Predicate::Between(column, self.value(), self.value()),
+            _ => Predicate::And(Box::new(self.predicate(depth - 1)), Box::new(self.predicate(depth - 1))),
+        }
+    }
+
+    fn filter(&mut self) -> Option<Predicate> {
+        (self.random.below(4) > 0).then(|| self.predicate(2))
+    }
+
+    fn statement(&mut self) -> Statement {
+        let table = self.table();
+        match self.random.below(10) {
+            0..=3 => {
+                let mut columns: Vec<String> = COLUMNS.iter().filter(|_| self.random.below(3) > 0).map(|c| c.to_string()).collect();
+                if columns.is_empty() {
+                    columns.push(self.pick(&COLUMNS).to_string());
+                }
+                let values = columns.iter().map(|_| self.value()).collect();
+                Statement::Insert { table, columns, values }
+            }
+            4 => Statement::Update { table, column: self.pick(&COLUMNS).to_string(), value: self.value(), filter: self.filter() },
+            5 => Statement::Delete { table, filter: self.filter() },
+            _ => {
+                let columns = COLUMNS.iter().filter(|_| self.random.below(2) > 0).map(|c| c.to_string()).collect();
+                Statement::Select { table, columns, filter: self.filter() }
+            }
+        }
+    }
+}
+
+/// The statements of the workload for `seed`: a few tables, then `steps`
+/// inserts, updates, deletes and selects on them.
+pub fn generate(seed: u64, steps: usize) -> Vec<Statement> {
+    let mut generator = Generator { random: Random::seeded(seed), tables: 0 };
+    generator.tables = 1 + generator.random.below(3);
+    let tables = (0..generator.tables).map(|n| Statement::CreateTable { table: format!("t{}", n) });
+    tables.chain((0..steps).map(|_| generator.statement()).collect::<Vec<_>>()).collect()
+}
+
+fn rows(records: Vec<Record>) -> Vec<ModelRow> {
+    records.into_iter().map(|r| (r.id, r.data.into_iter().collect())).collect()
+}
+
+/// Runs the workload for `seed` on a new database and on [`Reference`],
+/// comparing what every statement returns and every table at the end.
+/// The error lists the statements up to the first that differed.
+pub fn check(seed: u64, steps: usize) -> Result<(), String> {
+    let statements = generate(seed, steps);
+    let mut db = Database::new();
+    let mut reference = Reference::default();
+    let replay = |upto: usize| statements[..=upto].iter().map(|s| format!("{};", s)).collect::<Vec<_>>().join("\n");
+    for (step, statement) in statements.iter().enumerate() {
+        let sql = statement.to_string();
+        let actual = match statement {
+            Statement::Select { .. } => db.query_sql(&sql),
+            _ => db.execute_sql(&sql),
+        };
+        let expected = reference.apply(statement);
+        let same = match (&actual, &expected) {
+            (Ok(actual), Ok(expected)) => rows(actual.clone()) == *expected,
+            (Err(_), Err(_)) => true,
+            _ => false,
+        };
+        if !same {
+            let actual = actual.map(rows);
+            return Err(format!("seed {}: statement {} differs\n{}\nexpected {:?}\nactual {:?}", seed, step, replay(step), expected, actual));
+        }
+    }
+    for (table, expected) in &reference.tables {
+        let actual = rows(db.query_sql(&format!("SELECT * FROM {}", table))?);
+        if actual != *expected {
+            return Err(format!("seed {}: table {} differs at the end\n{}\nexpected {:?}\nactual {:?}", seed, table, replay(statements.len() - 1), expected, actual));
+        }
+    }
+    Ok(())
+}
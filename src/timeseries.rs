@@ -0,0 +1,194 @@
+use core::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::aggregate::Accumulator;
+use crate::clock::unix_millis;
+use crate::{ChangeKind, Database, Record, Row, Table};
+use crate::prelude::*;
+
+// A job writing one row per `bucket` of a series into `target`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Rollup {
+    target: String,
+    bucket: Duration,
+    // (output column, accumulator) as in aggregation pipelines
+    accumulators: Vec<(String, String)>,
+    // buckets starting before this are written
+    done_until: u64,
+}
+
+/// Settings of a time-series table, whose rows are points keyed by their
+/// timestamp in milliseconds and kept in timestamp order.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct TimeSeries {
+    retention: Option<Duration>,
+    rollups: Vec<Rollup>,
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+impl Table {
+    // Points, and queue messages, can only be added after the newest one.
+    pub(crate) fn check_append(&self, id: u64) -> Result<(), String> {
+        match self.records.last() {
+            Some(newest) if (self.series.is_some() || self.queue.is_some()) && id <= newest.id => {
+                Err(format!("Rows must be added after the newest one in '{}' ({})", self.name, newest.id))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // The points with timestamps in `from..to`, found by binary search.
+    fn points(&self, from: u64, to: u64) -> &[Record] {
+        let start = self.records.partition_point(|r| r.id < from);
+        let end = self.records.partition_point(|r| r.id < to);
+        &self.records[start..end.max(start)]
+    }
+}
+
+impl Database {
+    /// Creates a time-series table: rows are points keyed by a timestamp in
+    /// milliseconds since the Unix epoch and only added after the newest
+    /// point, so range scans are binary searches. SQL INSERTs stamp points
+    /// with the current time. With a `retention`, points older than that
+    /// are dropped as new ones arrive.
+    pub fn create_time_series(&mut self, name: &str, retention: Option<Duration>) -> Result<(), String> {
+        self.create_table(name.to_string())?;
+        if let Some(table) = self.tables.get_mut(name) {
+            table.series = Some(TimeSeries { retention, rollups: Vec::new() });
+        }
+        Ok(())
+    }
+
+    /// Adds a point to a time series, then applies its retention.
+    pub fn append_point(&mut self, table_name: &str, timestamp: u64, data: impl Into<Row>) -> Result<(), String> {
+        self.series(table_name)?;
+        self.insert(table_name, timestamp, data)?;
+        self.apply_retention(table_name)
+    }
+
+    /// The points of a time series from `from` up to, not including, `to`.
+    pub fn points(&self, table_name: &str, from: u64, to: u64) -> Result<&[Record], String> {
+        self.series(table_name)?;
+        Ok(self.tables[table_name].points(from, to))
+    }
+
+    /// Drops the points older than the retention window of a time series.
+    /// Dropped points are recorded as deletes in the change log and history.
+    pub fn apply_retention(&mut self, table_name: &str) -> Result<(), String> {
+        let Some(retention) = self.series(table_name)?.retention else {
+            return Ok(());
+        };
+        let cutoff = unix_millis().saturating_sub(millis(retention));
+        let expired = self.tables[table_name].records.partition_point(|r| r.id < cutoff);
+        self.drop_oldest(table_name, expired);
+        Ok(())
+    }
+
+    // Removes the first `count` rows of a table, recording them as deletes
+    // in the change log and history.
+    pub(crate) fn drop_oldest(&mut self, table_name: &str, count: usize) {
+        let Some(table) = self.tables.get_mut(table_name) else {
+            return;
+        };
+        if count == 0 {
+            return;
+        }
+        table.invalidate_columns();
+        for record in table.records.drain(..count) {
+            table.index.remove(&record.id);
+            if let Some(partitions) = &mut table.partitions {
+                partitions.remove(record.id, &record.data);
+            }
+            if let Some(expiry) = &mut table.expiry {
+                expiry.remove(record.id);
+            }
+            if let Some(history) = &mut table.history {
+                history.record(record.id, None);
+            }
+            self.changes.push(table_name, ChangeKind::Delete, record);
+        }
+        table.index.values_mut().for_each(|index| *index -= count);
+    }
+
+    /// Downsamples a time series into `target` by `bucket`: one point per
+    /// bucket holding each named accumulator (as in
+    /// [`Pipeline::group`](crate::Pipeline::group)) over the bucket's
+    /// points, at the bucket's start. `target` is created as a time series
+    /// if it doesn't exist. Buckets are written by
+    /// [`run_rollups`](Self::run_rollups) once a newer point closes them.
+    pub fn add_rollup(&mut self, source: &str, target: &str, bucket: Duration, accumulators: &[(&str, &str)]) -> Result<(), String> {
+        self.series(source)?;
+        if millis(bucket) == 0 {
+            return Err("Rollup buckets must be at least a millisecond".to_string());
+        }
+        for (_, accumulator) in accumulators {
+            Accumulator::parse(accumulator)?;
+        }
+        if !self.tables.contains_key(target) {
+            self.create_time_series(target, None)?;
+        }
+        self.series(target)?;
+        let rollup = Rollup {
+            target: target.to_string(),
+            bucket,
+            accumulators: accumulators.iter().map(|(name, a)| (name.to_string(), a.to_string())).collect(),
+            done_until: 0,
+        };
+        if let Some(series) = self.tables.get_mut(source).and_then(|t| t.series.as_mut()) {
+            series.rollups.push(rollup);
+        }
+        Ok(())
+    }
+
+    /// Writes the buckets of `source`'s rollups closed since the last run
+    /// and returns how many points were written. Points dropped by
+    /// retention before their bucket is written are not counted.
+    pub fn run_rollups(&mut self, source: &str) -> Result<usize, String> {
+        let rollups = self.series(source)?.rollups.clone();
+        let table = &self.tables[source];
+        let Some(newest) = table.records.last().map(|r| r.id) else {
+            return Ok(0);
+        };
+        let mut written = Vec::new();
+        for (position, rollup) in rollups.iter().enumerate() {
+            let bucket = millis(rollup.bucket);
+            // the bucket holding the newest point may still grow
+            let open = newest / bucket * bucket;
+            let accumulators = rollup.accumulators.iter()
+                .map(|(name, a)| Ok((name, Accumulator::parse(a)?)))
+                .collect::<Result<Vec<_>, String>>()?;
+            let mut points = Vec::new();
+            let mut rest = table.points(rollup.done_until, open);
+            while let Some(first) = rest.first() {
+                let start = first.id / bucket * bucket;
+                let (rows, tail) = rest.split_at(rest.partition_point(|r| r.id < start + bucket));
+                let data: Row = accumulators.iter()
+                    .filter_map(|(name, accumulator)| Some((name.to_string(), accumulator.apply(rows)?)))
+                    .collect();
+                points.push((start, data));
+                rest = tail;
+            }
+            written.push((position, rollup.target.clone(), points, open.max(rollup.done_until)));
+        }
+        let mut count = 0;
+        for (position, target, points, done_until) in written {
+            for (timestamp, data) in points {
+                self.append_point(&target, timestamp, data)?;
+                count += 1;
+            }
+            if let Some(series) = self.tables.get_mut(source).and_then(|t| t.series.as_mut()) {
+                series.rollups[position].done_until = done_until;
+            }
+        }
+        Ok(count)
+    }
+
+    fn series(&self, table_name: &str) -> Result<&TimeSeries, String> {
+        let table = self.tables.get(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        table.series.as_ref().ok_or(format!("Table '{}' is not a time series", table_name))
+    }
+}
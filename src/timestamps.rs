@@ -0,0 +1,55 @@
+use crate::clock::unix_millis;
+use crate::prelude::*;
+use crate::{Database, Row, Table};
+
+/// The column a table keeping timestamps sets when a row is inserted, in
+/// milliseconds since the Unix epoch.
+pub const CREATED_AT: &str = "created_at";
+
+/// The column a table keeping timestamps sets whenever a row is inserted or
+/// changed, in milliseconds since the Unix epoch.
+pub const UPDATED_AT: &str = "updated_at";
+
+impl Table {
+    // Sets the timestamps of a row about to be written, if the table keeps
+    // them: both to now for a new row, and for a changed one `updated_at`
+    // to now and `created_at` to what it was, whatever the new data says.
+    pub(crate) fn stamp(&self, before: Option<&Row>, data: &mut Row) {
+        if !self.timestamps {
+            return;
+        }
+        let now = unix_millis().to_string();
+        match before {
+            None => {
+                data.insert(CREATED_AT.to_string(), now.clone());
+            }
+            Some(before) => match before.get(CREATED_AT) {
+                Some(created) => {
+                    data.insert(CREATED_AT.to_string(), created.clone());
+                }
+                None => {
+                    data.remove(CREATED_AT);
+                }
+            },
+        }
+        data.insert(UPDATED_AT.to_string(), now);
+    }
+}
+
+impl Database {
+    /// Has the engine keep [`CREATED_AT`] and [`UPDATED_AT`] on every row of
+    /// a table from now on: both are set when a row is inserted, and
+    /// `updated_at` whenever it changes, through the Rust API or SQL. Rows
+    /// already in the table are left as they are. A table with a message
+    /// needs `created_at` and `updated_at` fields for them.
+    pub fn keep_timestamps(&mut self, table_name: &str, on: bool) -> Result<(), String> {
+        let table = self.tables.get_mut(table_name).ok_or(format!("Table '{}' not found", table_name))?;
+        if let Some(proto) = table.proto.as_ref().filter(|_| on) {
+            if let Some(column) = [CREATED_AT, UPDATED_AT].into_iter().find(|c| proto.type_of(c).is_none()) {
+                return Err(format!("The message of table '{}' has no '{}' field", table_name, column));
+            }
+        }
+        table.timestamps = on;
+        Ok(())
+    }
+}
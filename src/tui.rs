@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+use std::io;
+
+use potatodb::{Database, Record};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Cell, List, ListItem, ListState, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+
+#[derive(PartialEq)]
+enum Focus {
+    Tables,
+    Records,
+}
+
+enum Input {
+    None,
+    Edit(String),
+    Query(String),
+}
+
+struct App {
+    db: Database,
+    path: String,
+    tables: Vec<String>,
+    table_state: ListState,
+    focus: Focus,
+    input: Input,
+    columns: Vec<String>,
+    rows: Vec<Record>,
+    // true while the grid shows the output of a query rather than a table
+    showing_result: bool,
+    grid_state: TableState,
+    page_size: usize,
+    status: String,
+    quit: bool,
+}
+
+pub fn run(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let db = Database::load(path)?;
+    let mut app = App::new(db, path.to_string());
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl App {
+    fn new(db: Database, path: String) -> Self {
+        let mut app = App {
+            db,
+            path,
+            tables: Vec::new(),
+            table_state: ListState::default(),
+            focus: Focus::Tables,
+            input: Input::None,
+            columns: Vec::new(),
+            rows: Vec::new(),
+            showing_result: false,
+            grid_state: TableState::default(),
+            page_size: 10,
+            status: "Tab: switch pane  Enter: edit cell  /: query  s: save  q: quit".to_string(),
+            quit: false,
+        };
+        app.reload_tables();
+        if !app.tables.is_empty() {
+            app.load_table();
+        }
+        app
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), Box<dyn std::error::Error>> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Lists the tables again, as statements create and drop them, keeping
+    // the selected one selected while it exists.
+    fn reload_tables(&mut self) {
+        let selected = self.selected_table().map(String::from);
+        self.tables = self.db.list_tables().into_iter().map(String::from).collect();
+        self.tables.sort();
+        let position = selected.and_then(|name| self.tables.iter().position(|t| *t == name));
+        self.table_state.select(position.or((!self.tables.is_empty()).then_some(0)));
+    }
+
+    fn selected_table(&self) -> Option<&str> {
+        self.table_state.selected().map(|i| self.tables[i].as_str())
+    }
+
+    fn load_table(&mut self) {
+        let Some(table) = self.selected_table().map(String::from) else {
+            return;
+        };
+        self.columns = self.db.columns(&table).unwrap_or_default();
+        self.rows = self.db.get_all(&table).unwrap_or_default().into_iter().cloned().collect();
+        self.showing_result = false;
+        self.reset_grid();
+    }
+
+    fn show_result(&mut self, rows: Vec<Record>) {
+        let mut columns: Vec<String> = rows.iter().flat_map(|r| r.data().keys().cloned()).collect();
+        columns.sort();
+        columns.dedup();
+        self.columns = columns;
+        self.rows = rows;
+        self.showing_result = true;
+        self.reset_grid();
+    }
+
+    fn reset_grid(&mut self) {
+        self.grid_state = TableState::default();
+        if !self.rows.is_empty() {
+            self.grid_state.select_cell(Some((0, 0)));
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> io::Result<()> {
+        match &mut self.input {
+            Input::Edit(buffer) | Input::Query(buffer) => match key.code {
+                KeyCode::Esc => self.input = Input::None,
+                KeyCode::Backspace => {
+                    buffer.pop();
+                }
+                KeyCode::Char(c) => buffer.push(c),
+                KeyCode::Enter => self.submit_input(),
+                _ => {}
+            },
+            Input::None => self.handle_navigation(key),
+        }
+        Ok(())
+    }
+
+    fn handle_navigation(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    Focus::Tables => Focus::Records,
+                    Focus::Records => Focus::Tables,
+                }
+            }
+            KeyCode::Char('/') | KeyCode::Char(':') => self.input = Input::Query(String::new()),
+            KeyCode::Char('s') => {
+                self.status = match self.db.save(&self.path) {
+                    Ok(()) => format!("Saved to {}", self.path),
+                    Err(e) => format!("Save failed: {}", e),
+                }
+            }
+            KeyCode::Esc if self.showing_result => self.load_table(),
+            _ if self.focus == Focus::Tables => self.handle_tables_key(key),
+            _ => self.handle_grid_key(key),
+        }
+    }
+
+    fn handle_tables_key(&mut self, key: KeyEvent) {
+        if self.tables.is_empty() {
+            return;
+        }
+        let selected = self.table_state.selected().unwrap_or(0);
+        let next = match key.code {
+            KeyCode::Up => selected.saturating_sub(1),
+            KeyCode::Down => (selected + 1).min(self.tables.len() - 1),
+            KeyCode::Enter => {
+                self.focus = Focus::Records;
+                selected
+            }
+            _ => return,
+        };
+        if next != selected || self.showing_result {
+            self.table_state.select(Some(next));
+            self.load_table();
+        }
+    }
+
+    fn handle_grid_key(&mut self, key: KeyEvent) {
+        let Some((row, column)) = self.grid_state.selected_cell() else {
+            return;
+        };
+        let last_row = self.rows.len().saturating_sub(1);
+        // column 0 is the id, data columns follow
+        let last_column = self.columns.len();
+        let (row, column) = match key.code {
+            KeyCode::Up => (row.saturating_sub(1), column),
+            KeyCode::Down => ((row + 1).min(last_row), column),
+            KeyCode::Left => (row, column.saturating_sub(1)),
+            KeyCode::Right => (row, (column + 1).min(last_column)),
+            KeyCode::PageUp => (row.saturating_sub(self.page_size), column),
+            KeyCode::PageDown => ((row + self.page_size).min(last_row), column),
+            KeyCode::Home => (0, column),
+            KeyCode::End => (last_row, column),
+            KeyCode::Enter => {
+                if self.showing_result {
+                    self.status = "Query results are read-only, press Esc to return to the table".to_string();
+                } else if column == 0 {
+                    self.status = "The id column cannot be edited".to_string();
+                } else {
+                    let current = self.rows[row].data().get(&self.columns[column - 1]).cloned().unwrap_or_default();
+                    self.input = Input::Edit(current);
+                }
+                return;
+            }
+            _ => return,
+        };
+        self.grid_state.select_cell(Some((row, column)));
+    }
+
+    fn submit_input(&mut self) {
+        match std::mem::replace(&mut self.input, Input::None) {
+            Input::Edit(value) => self.commit_edit(value),
+            Input::Query(sql) if sql.trim().is_empty() => self.status = "Empty query".to_string(),
+            Input::Query(sql) => match self.db.execute_sql(&sql) {
+                Ok(rows) => {
+                    self.status = format!("{} row(s)", rows.len());
+                    self.reload_tables();
+                    self.show_result(rows);
+                }
+                Err(e) => self.status = format!("Error: {}", e),
+            },
+            Input::None => {}
+        }
+    }
+
+    fn commit_edit(&mut self, value: String) {
+        let (Some(table), Some((row, column))) = (self.selected_table().map(String::from), self.grid_state.selected_cell()) else {
+            return;
+        };
+        let record = &self.rows[row];
+        let mut data: HashMap<String, String> = record.data().to_map();
+        data.insert(self.columns[column - 1].clone(), value);
+        match self.db.update(&table, record.id(), data) {
+            Ok(()) => {
+                self.status = format!("Updated record {}", record.id());
+                self.rows = self.db.get_all(&table).unwrap_or_default().into_iter().cloned().collect();
+            }
+            Err(e) => self.status = format!("Error: {}", e),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, input, status] = Layout::vertical([
+            Constraint::Min(3),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [tables, grid] = Layout::horizontal([Constraint::Length(24), Constraint::Min(10)]).areas(main);
+
+        self.draw_tables(frame, tables);
+        self.draw_grid(frame, grid);
+
+        let (title, text) = match &self.input {
+            Input::None => ("Input", String::new()),
+            Input::Edit(buffer) => ("Edit cell (Enter: apply, Esc: cancel)", buffer.clone()),
+            Input::Query(buffer) => ("SQL (Enter: run, Esc: cancel)", buffer.clone()),
+        };
+        frame.render_widget(Paragraph::new(text).block(Block::default().borders(Borders::ALL).title(title)), input);
+        frame.render_widget(Line::from(self.status.as_str()), status);
+    }
+
+    fn pane_block(&self, title: String, focus: Focus) -> Block<'static> {
+        let style = if self.focus == focus {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        Block::default().borders(Borders::ALL).border_style(style).title(title)
+    }
+
+    fn draw_tables(&mut self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self.tables.iter().map(|t| ListItem::new(t.as_str())).collect();
+        let list = List::new(items)
+            .block(self.pane_block("Tables".to_string(), Focus::Tables))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.table_state);
+    }
+
+    fn draw_grid(&mut self, frame: &mut Frame, area: Rect) {
+        // borders and the header row take up three lines
+        self.page_size = (area.height as usize).saturating_sub(3).max(1);
+        let title = if self.showing_result {
+            format!("Query result ({} rows, Esc: back)", self.rows.len())
+        } else {
+            format!("{} ({} rows)", self.selected_table().unwrap_or(""), self.rows.len())
+        };
+
+        let header = Row::new(
+            std::iter::once("id".to_string())
+                .chain(self.columns.iter().cloned())
+                .map(Cell::from),
+        )
+        .style(Style::default().add_modifier(Modifier::BOLD));
+        let rows = self.rows.iter().map(|record| {
+            Row::new(
+                std::iter::once(record.id().to_string())
+                    .chain(self.columns.iter().map(|c| record.data().get(c).cloned().unwrap_or_default()))
+                    .map(Cell::from),
+            )
+        });
+        let widths = std::iter::once(Constraint::Length(8))
+            .chain(self.columns.iter().map(|_| Constraint::Min(8)));
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(self.pane_block(title, Focus::Records))
+            .cell_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, area, &mut self.grid_state);
+    }
+}
@@ -0,0 +1,147 @@
+use core::mem::size_of;
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::prelude::*;
+use crate::{Database, Record, StorageBackend};
+
+/// When [`Database::vacuum`] compacts tables and checkpoints the database.
+#[derive(Clone)]
+pub struct AutoVacuum {
+    /// How long [`Maintenance`] waits between runs.
+    pub interval: Duration,
+    /// Compact a table once this share of the rows it held since it was
+    /// last compacted have been deleted.
+    pub tombstone_ratio: f64,
+    /// Checkpoint once this many changes are logged.
+    pub max_logged_changes: usize,
+    /// Where checkpoints are saved. Without one the change log only shrinks
+    /// through [`Database::discard_changes_through`].
+    pub checkpoint: Option<(Arc<dyn StorageBackend>, String)>,
+    /// A run starts no more work once it has moved this many bytes.
+    pub io_budget: usize,
+}
+
+impl Default for AutoVacuum {
+    fn default() -> Self {
+        AutoVacuum {
+            interval: Duration::from_secs(60),
+            tombstone_ratio: 0.25,
+            max_logged_changes: 10_000,
+            checkpoint: None,
+            io_budget: 64 << 20,
+        }
+    }
+}
+
+/// What one [`Database::vacuum`] run did.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VacuumReport {
+    compacted: Vec<String>,
+    checkpointed: bool,
+    bytes: usize,
+}
+
+impl VacuumReport {
+    /// The tables compacted, most deleted from first.
+    pub fn compacted(&self) -> &[String] {
+        &self.compacted
+    }
+
+    pub fn checkpointed(&self) -> bool {
+        self.checkpointed
+    }
+
+    /// Bytes moved compacting tables and written checkpointing.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+impl Database {
+    /// The share of the rows a table held since it was last compacted that
+    /// have been deleted.
+    pub fn tombstone_ratio(&self, table_name: &str) -> Result<f64, String> {
+        let table = self.tables.get(table_name).ok_or_else(|| format!("Table '{}' not found", table_name))?;
+        let deleted = self.changes.tombstones.get(table_name).copied().unwrap_or(0);
+        Ok(match deleted {
+            0 => 0.0,
+            deleted => deleted as f64 / (table.records.len() + deleted) as f64,
+        })
+    }
+
+    /// Compacts the tables over `config`'s tombstone ratio, giving their
+    /// deleted rows' space back, then, once the change log is over
+    /// `config.max_logged_changes`, saves a checkpoint and discards the
+    /// changes it holds. Followers that fall behind the log catch up from a
+    /// snapshot.
+    pub fn vacuum(&mut self, config: &AutoVacuum) -> Result<VacuumReport, Box<dyn Error>> {
+        let mut report = VacuumReport::default();
+        self.changes.tombstones.retain(|table_name, _| self.tables.contains_key(table_name));
+        let mut due: Vec<(f64, String)> = self.changes.tombstones.keys()
+            .map(|table_name| (self.tombstone_ratio(table_name).unwrap_or(0.0), table_name.clone()))
+            .filter(|(ratio, _)| *ratio >= config.tombstone_ratio)
+            .collect();
+        due.sort_by(|a, b| b.0.total_cmp(&a.0));
+        for (_, table_name) in due {
+            if report.bytes >= config.io_budget {
+                return Ok(report);
+            }
+            let table = self.tables.get_mut(&table_name).expect("tombstones are kept for existing tables");
+            table.records.shrink_to_fit();
+            table.index.shrink_to_fit();
+            self.changes.tombstones.remove(&table_name);
+            report.bytes += table.records.len() * size_of::<Record>();
+            report.compacted.push(table_name);
+        }
+        if let Some((storage, name)) = &config.checkpoint {
+            if self.changes.len() >= config.max_logged_changes && report.bytes < config.io_budget {
+                report.bytes += self.write_to(storage.as_ref(), name)?;
+                self.discard_changes_through(self.last_change_seq());
+                report.checkpointed = true;
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Runs [`Database::vacuum`] on a shared database every
+/// `config.interval` on a background thread, so long-running services stay
+/// compact without pausing for it. Dropping it stops the runs.
+pub struct Maintenance {
+    stop: Arc<AtomicBool>,
+    last: Arc<Mutex<Option<Result<VacuumReport, String>>>>,
+}
+
+impl Maintenance {
+    pub fn start(db: Arc<Mutex<Database>>, config: AutoVacuum) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let last = Arc::new(Mutex::new(None));
+        let (thread_stop, thread_last) = (stop.clone(), last.clone());
+        thread::spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                thread::sleep(config.interval);
+                let result = match db.lock() {
+                    Ok(mut db) => db.vacuum(&config).map_err(|e| e.to_string()),
+                    Err(_) => Err("Database lock poisoned".to_string()),
+                };
+                *lock(&thread_last) = Some(result);
+            }
+        });
+        Maintenance { stop, last }
+    }
+
+    /// What the latest run did, or why it failed; None before the first.
+    pub fn last_run(&self) -> Option<Result<VacuumReport, String>> {
+        lock(&self.last).clone()
+    }
+}
+
+impl Drop for Maintenance {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
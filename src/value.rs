@@ -0,0 +1,212 @@
+use core::cmp::Ordering;
+use core::fmt;
+
+use crate::generated::number_text;
+use crate::prelude::*;
+
+/// A column value read as the type it spells. Rows keep their values as
+/// text, as they are written; comparisons and arithmetic read them as
+/// values, so `10` is greater than `9` and `10.0` equals `10`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// A column the row doesn't have.
+    Null,
+    /// `true` or `false`.
+    Bool(bool),
+    /// A whole number that fits in an `i64`.
+    Int(i64),
+    /// Any other finite number, such as `2.5`.
+    Float(f64),
+    Text(String),
+    /// Bytes, written as a hex literal such as `x'cafe'`.
+    Blob(Vec<u8>),
+}
+
+// Whether `text` spells a number as the database writes them: an optional
+// `-`, digits without a leading zero, and optionally `.` and more digits.
+fn plain_number(text: &str) -> bool {
+    let digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    let unsigned = text.strip_prefix('-').unwrap_or(text);
+    let (whole, fraction) = unsigned.split_once('.').map_or((unsigned, None), |(whole, fraction)| (whole, Some(fraction)));
+    digits(whole) && (whole == "0" || !whole.starts_with('0')) && fraction.map_or(true, digits)
+}
+
+impl Value {
+    /// Reads a stored value. Only numbers written plainly, as the database
+    /// writes them, read as numbers: `-2.5` and `10` do, while `007`, `+5`
+    /// and `1e3` stay text, so codes such as zip codes match only as
+    /// written.
+    pub fn parse(text: &str) -> Value {
+        if plain_number(text) {
+            if let Ok(n) = text.parse::<i64>() {
+                return Value::Int(n);
+            }
+            if let Some(n) = text.parse::<f64>().ok().filter(|n| n.is_finite()) {
+                return Value::Float(n);
+            }
+        }
+        match text {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => blob(text).map_or_else(|| Value::Text(text.to_string()), Value::Blob),
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// The text a row stores for the value; None for [`Value::Null`].
+    pub fn to_text(&self) -> Option<String> {
+        (!self.is_null()).then(|| self.to_string())
+    }
+
+    // Where values of the kind sort among the others.
+    fn rank(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Int(_) | Value::Float(_) => 2,
+            Value::Text(_) => 3,
+            Value::Blob(_) => 4,
+        }
+    }
+
+    fn number(&self) -> Option<f64> {
+        match self {
+            Value::Int(n) => Some(*n as f64),
+            Value::Float(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// How two values of the same kind order: numbers as numbers, whole or
+    /// not, `false` before `true`, text and bytes lexicographically. None
+    /// for values of different kinds, and for NULL.
+    pub fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+            (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+            (Value::Text(a), Value::Text(b)) => Some(a.cmp(b)),
+            (Value::Blob(a), Value::Blob(b)) => Some(a.cmp(b)),
+            (a, b) => Some(a.number()?.total_cmp(&b.number()?)),
+        }
+    }
+
+    // Whole numbers stay whole unless the result doesn't fit; None unless
+    // both values are numbers, or if the result isn't finite.
+    fn arithmetic(&self, other: &Value, whole: fn(i64, i64) -> Option<i64>, real: fn(f64, f64) -> f64) -> Option<Value> {
+        if let (Value::Int(a), Value::Int(b)) = (self, other) {
+            if let Some(n) = whole(*a, *b) {
+                return Some(Value::Int(n));
+            }
+        }
+        Some(real(self.number()?, other.number()?)).filter(|n| n.is_finite()).map(Value::Float)
+    }
+
+    pub fn checked_add(&self, other: &Value) -> Option<Value> {
+        self.arithmetic(other, i64::checked_add, |a, b| a + b)
+    }
+
+    pub fn checked_sub(&self, other: &Value) -> Option<Value> {
+        self.arithmetic(other, i64::checked_sub, |a, b| a - b)
+    }
+
+    pub fn checked_mul(&self, other: &Value) -> Option<Value> {
+        self.arithmetic(other, i64::checked_mul, |a, b| a * b)
+    }
+
+    /// Whole when two whole numbers divide exactly; None when dividing by
+    /// zero.
+    pub fn checked_div(&self, other: &Value) -> Option<Value> {
+        if other.number()? == 0.0 {
+            return None;
+        }
+        self.arithmetic(other, |a, b| a.checked_rem(b).filter(|r| *r == 0).and_then(|_| a.checked_div(b)), |a, b| a / b)
+    }
+}
+
+// The bytes of `x'...'`, with an even number of hex digits.
+fn blob(text: &str) -> Option<Vec<u8>> {
+    let hex = text.strip_prefix("x'").or_else(|| text.strip_prefix("X'"))?.strip_suffix('\'')?;
+    if hex.len() % 2 != 0 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok()).collect()
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "NULL"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Float(n) => write!(f, "{}", number_text(*n)),
+            Value::Text(s) => write!(f, "{}", s),
+            Value::Blob(bytes) => {
+                write!(f, "x'")?;
+                bytes.iter().try_for_each(|b| write!(f, "{:02x}", b))?;
+                write!(f, "'")
+            }
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(n: i32) -> Self {
+        Value::Int(n.into())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Int(n)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Float(n)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Text(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Text(s)
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(bytes: Vec<u8>) -> Self {
+        Value::Blob(bytes)
+    }
+}
+
+// How two stored values order as `Value`s, two pieces of text as `text`
+// orders them; None when they are of different kinds, such as a number and
+// a word, which match no comparison but `!=`.
+pub(crate) fn order(a: &str, b: &str, text: impl Fn(&str, &str) -> Ordering) -> Option<Ordering> {
+    match (Value::parse(a), Value::parse(b)) {
+        (Value::Text(_), Value::Text(_)) => Some(text(a, b)),
+        (a, b) => a.compare(&b),
+    }
+}
+
+// Orders any two stored values: booleans, then numbers, text and bytes,
+// each kind as `Value`s of it compare.
+pub(crate) fn sort(a: &str, b: &str) -> Ordering {
+    let (a, b) = (Value::parse(a), Value::parse(b));
+    a.rank().cmp(&b.rank()).then_with(|| a.compare(&b).unwrap_or(Ordering::Equal))
+}
@@ -0,0 +1,198 @@
+use alloc::borrow::Cow;
+use core::cmp::Ordering;
+use core::ops::ControlFlow;
+
+use crate::generated::GeneratedColumn;
+use crate::{interrupt, pseudo, CompareAs, Condition, Record, Table};
+use crate::prelude::*;
+
+// Rows filtered together. A comparison on a typed numeric column reads the
+// column for the whole batch, then compares it in one loop the compiler
+// can vectorize, rather than walking the condition for every row.
+const BATCH: usize = 1024;
+
+#[derive(Clone, Copy)]
+enum Test {
+    Equals,
+    NotEquals,
+    Greater,
+    Less,
+    Between,
+}
+
+enum Node<'c> {
+    // a numeric column of a message or a typed one compared with numbers
+    Numbers { column: &'c str, test: Test, low: f64, high: f64 },
+    // a column set to compare other than as text
+    Compared { column: &'c str, test: Test, low: &'c str, high: &'c str, compare: CompareAs },
+    // a function call such as `COALESCE(a, b)`, compared as text, or as
+    // numbers if it is a number CAST
+    Call { call: GeneratedColumn, test: Test, low: &'c str, high: &'c str, case_sensitive: bool },
+    // a pseudo-column, a LIKE, or any other column; the partition column
+    // and text columns compare as text, as partitions are picked by the
+    // former
+    Text { condition: &'c Condition, case_sensitive: bool, typed: bool },
+    And(Box<Node<'c>>, Box<Node<'c>>),
+    Or(Box<Node<'c>>, Box<Node<'c>>),
+}
+
+impl<'c> Node<'c> {
+    fn new(table: &Table, condition: &'c Condition) -> Node<'c> {
+        let case_sensitive = interrupt::case_sensitive();
+        let compare = |column: &'c str, test: Test, low: &'c str, high: &'c str| {
+            if let Some(call) = Some(column).filter(|c| c.contains('(')).and_then(|c| GeneratedColumn::call(c).ok()) {
+                return Node::Call { call, test, low, high, case_sensitive };
+            }
+            if pseudo::is_pseudo(column) {
+                return Node::Text { condition, case_sensitive, typed: true };
+            }
+            if let Some(&compare) = table.compare.get(column) {
+                return Node::Compared { column, test, low, high, compare };
+            }
+            let declared = table.declared_compare(column);
+            let numeric = table.proto.as_ref().is_some_and(|proto| proto.is_numeric(column)) || declared == Some(CompareAs::Numeric);
+            let typed = table.partitions.as_ref().map_or(true, |p| p.scheme().column() != column) && declared != Some(CompareAs::String);
+            match (low.parse::<f64>(), high.parse::<f64>()) {
+                (Ok(low), Ok(high)) if numeric => Node::Numbers { column, test, low, high },
+                _ => Node::Text { condition, case_sensitive, typed },
+            }
+        };
+        match condition {
+            Condition::Equals(c, v) => compare(c, Test::Equals, v, v),
+            Condition::NotEquals(c, v) => compare(c, Test::NotEquals, v, v),
+            Condition::GreaterThan(c, v) => compare(c, Test::Greater, v, v),
+            Condition::LessThan(c, v) => compare(c, Test::Less, v, v),
+            Condition::Between(c, low, high) => compare(c, Test::Between, low, high),
+            Condition::Like(..) | Condition::IsNull(_) | Condition::IsNotNull(_) => Node::Text { condition, case_sensitive, typed: true },
+            Condition::And(l, r) => Node::And(Box::new(Node::new(table, l)), Box::new(Node::new(table, r))),
+            Condition::Or(l, r) => Node::Or(Box::new(Node::new(table, l)), Box::new(Node::new(table, r))),
+        }
+    }
+
+    // Sets `selected[i]` to whether `rows[i]` matches.
+    fn select(&self, rows: &[Cow<'_, Record>], selected: &mut [bool]) {
+        match self {
+            Node::Numbers { column, test, low, high } => {
+                // missing values are NaN, which only `!=` matches
+                let mut values = [f64::NAN; BATCH];
+                for (value, row) in values.iter_mut().zip(rows) {
+                    *value = row.data.get(column).and_then(|v| v.parse().ok()).unwrap_or(f64::NAN);
+                }
+                let (values, low, high) = (&values[..rows.len()], *low, *high);
+                match test {
+                    Test::Equals => each(values, selected, |v| v == low),
+                    Test::NotEquals => each(values, selected, |v| v != low),
+                    Test::Greater => each(values, selected, |v| v > low),
+                    Test::Less => each(values, selected, |v| v < low),
+                    Test::Between => each(values, selected, |v| low <= v && v <= high),
+                }
+            }
+            Node::Compared { column, test, low, high, compare } => {
+                for (selected, row) in selected.iter_mut().zip(rows) {
+                    let ordering = |bound: &str| row.data.get(column).and_then(|v| compare.compare(v, bound));
+                    *selected = match test {
+                        Test::Equals => ordering(low) == Some(Ordering::Equal),
+                        Test::NotEquals => ordering(low) != Some(Ordering::Equal),
+                        Test::Greater => ordering(low) == Some(Ordering::Greater),
+                        Test::Less => ordering(low) == Some(Ordering::Less),
+                        Test::Between => ordering(low).is_some_and(Ordering::is_ge) && ordering(high).is_some_and(Ordering::is_le),
+                    };
+                }
+            }
+            Node::Call { call, test, low, high, case_sensitive } => {
+                let numbers = match (low.parse::<f64>(), high.parse::<f64>()) {
+                    (Ok(low), Ok(high)) if call.is_numeric() => Some((low, high)),
+                    _ => None,
+                };
+                for (selected, row) in selected.iter_mut().zip(rows) {
+                    let value = call.value(&row.data);
+                    if let Some((low, high)) = numbers {
+                        let value = value.and_then(|v| v.parse::<f64>().ok()).unwrap_or(f64::NAN);
+                        *selected = match test {
+                            Test::Equals => value == low,
+                            Test::NotEquals => value != low,
+                            Test::Greater => value > low,
+                            Test::Less => value < low,
+                            Test::Between => low <= value && value <= high,
+                        };
+                        continue;
+                    }
+                    let fold = |v: &str| if *case_sensitive { v.to_string() } else { v.to_lowercase() };
+                    let (value, low, high) = (value.as_deref().map(fold), fold(low), fold(high));
+                    *selected = match test {
+                        Test::Equals => value == Some(low),
+                        Test::NotEquals => value != Some(low),
+                        Test::Greater => value.is_some_and(|v| v > low),
+                        Test::Less => value.is_some_and(|v| v < low),
+                        Test::Between => value.is_some_and(|v| low <= v && v <= high),
+                    };
+                }
+            }
+            Node::Text { condition, case_sensitive, typed } => {
+                for (selected, row) in selected.iter_mut().zip(rows) {
+                    *selected = condition.matches(row, *case_sensitive, *typed);
+                }
+            }
+            Node::And(left, right) | Node::Or(left, right) => {
+                let mut other = [false; BATCH];
+                left.select(rows, selected);
+                right.select(rows, &mut other[..rows.len()]);
+                let and = matches!(self, Node::And(..));
+                for (selected, other) in selected.iter_mut().zip(other) {
+                    *selected = if and { *selected & other } else { *selected | other };
+                }
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn each(values: &[f64], selected: &mut [bool], test: impl Fn(f64) -> bool) {
+    for (selected, &value) in selected.iter_mut().zip(values) {
+        *selected = test(value);
+    }
+}
+
+impl Table {
+    // Passes each of `rows`, with its virtual columns, to `keep` if it
+    // matches `condition`, until the running statement is stopped.
+    pub(crate) fn filter<'r>(&self, rows: Vec<&'r Record>, condition: &Option<Condition>, mut keep: impl FnMut(Cow<'r, Record>)) {
+        let _ = self.try_filter(rows, condition, |record| {
+            keep(record);
+            ControlFlow::Continue(())
+        });
+    }
+
+    // Like `filter`, but stops once `keep` breaks.
+    pub(crate) fn try_filter<'r>(&self, rows: Vec<&'r Record>, condition: &Option<Condition>, mut keep: impl FnMut(Cow<'r, Record>) -> ControlFlow<()>) -> ControlFlow<()> {
+        let node = condition.as_ref().map(|condition| Node::new(self, condition));
+        let mut rows = rows.into_iter();
+        let mut batch = Vec::with_capacity(BATCH);
+        let mut selected = [true; BATCH];
+        loop {
+            batch.extend(rows.by_ref().take(BATCH).take_while(|_| interrupt::running()).map(|record| self.with_virtual(record)));
+            if batch.is_empty() {
+                return ControlFlow::Continue(());
+            }
+            let full = batch.len() == BATCH;
+            if let Some(node) = &node {
+                node.select(&batch, &mut selected[..batch.len()]);
+            }
+            for (record, &selected) in batch.drain(..).zip(&selected) {
+                if selected {
+                    keep(record)?;
+                }
+            }
+            if !full {
+                return ControlFlow::Continue(());
+            }
+        }
+    }
+
+    // Whether a single record matches, by the same rules as `filter`.
+    pub(crate) fn matches(&self, record: &Record, condition: &Condition) -> bool {
+        let mut selected = [false];
+        Node::new(self, condition).select(&[self.with_virtual(record)], &mut selected);
+        selected[0]
+    }
+}
@@ -0,0 +1,129 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::sync::Arc;
+
+use crate::{Condition, Database, ProtoMessage, Record, Row, Table};
+use crate::prelude::*;
+
+/// A read-only table whose rows live outside the database, such as a file,
+/// an in-process data structure or another database, attached with
+/// [`Database::attach_virtual`] and queried with SQL like any other table.
+pub trait VirtualTable: Send + Sync {
+    /// Every row, read afresh for each statement.
+    fn scan(&self) -> Result<Vec<Row>, String>;
+
+    /// The columns and their types, if known ahead of a scan. SELECTs naming
+    /// other columns then fail, and numeric columns compare as numbers.
+    fn schema(&self) -> Option<ProtoMessage> {
+        None
+    }
+
+    /// The rows that may match all of `predicates`, the comparisons a
+    /// statement's WHERE clause requires, for sources that can filter
+    /// before handing rows over. Every row is checked against the whole
+    /// WHERE clause afterwards, so a source may return rows that do not
+    /// match, or ignore predicates it cannot use, but must not leave out
+    /// rows that do. Defaults to [`scan`](Self::scan).
+    fn scan_where(&self, predicates: &[Predicate]) -> Result<Vec<Row>, String> {
+        let _ = predicates;
+        self.scan()
+    }
+}
+
+/// A comparison of a column with values, pushed down to a
+/// [`VirtualTable`]. Values compare as numbers on columns the schema
+/// types as numeric, and as text otherwise; a row without the column only
+/// matches `NotEquals`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Predicate {
+    Equals(String, String),
+    NotEquals(String, String),
+    GreaterThan(String, String),
+    LessThan(String, String),
+    /// Column, low and high; both ends included.
+    Between(String, String, String),
+}
+
+// The comparisons on plain columns that every row matching `condition`
+// passes; the branches of an OR are not pushed down.
+fn predicates(condition: &Condition, pushed: &mut Vec<Predicate>) {
+    let plain = |column: &String| !column.contains('(');
+    match condition {
+        Condition::Equals(c, v) if plain(c) => pushed.push(Predicate::Equals(c.clone(), v.clone())),
+        Condition::NotEquals(c, v) if plain(c) => pushed.push(Predicate::NotEquals(c.clone(), v.clone())),
+        Condition::GreaterThan(c, v) if plain(c) => pushed.push(Predicate::GreaterThan(c.clone(), v.clone())),
+        Condition::LessThan(c, v) if plain(c) => pushed.push(Predicate::LessThan(c.clone(), v.clone())),
+        Condition::Between(c, low, high) if plain(c) => pushed.push(Predicate::Between(c.clone(), low.clone(), high.clone())),
+        Condition::And(left, right) => {
+            predicates(left, pushed);
+            predicates(right, pushed);
+        }
+        _ => {}
+    }
+}
+
+impl Table {
+    // A table of rows made up for one statement, such as those of a virtual
+    // table.
+    pub(crate) fn transient(name: &str, records: Vec<Record>) -> Table {
+        Table {
+            name: name.to_string(),
+            index: records.iter().enumerate().map(|(i, r)| (r.id, i)).collect(),
+            records,
+            proto: None,
+            partitions: None,
+            history: None,
+            encrypted: BTreeSet::new(),
+            generated: Vec::new(),
+            enums: BTreeMap::new(),
+            series: None,
+            queue: None,
+            max_rows: None,
+            temporary: false,
+            columnar: None,
+            compare: BTreeMap::new(),
+            expiry: None,
+            timestamps: false,
+            types: BTreeMap::new(),
+        }
+    }
+}
+
+impl Database {
+    /// Attaches `source` as a read-only table named `name`. SELECTs read its
+    /// rows from the source each time, numbering them from 1 in the order
+    /// it gives them; nothing is imported or saved with the database.
+    pub fn attach_virtual(&mut self, name: &str, source: impl VirtualTable + 'static) -> Result<(), String> {
+        if self.tables.contains_key(name) || self.virtual_tables.contains_key(name) {
+            return Err(format!("Table '{}' already exists", name));
+        }
+        if name.starts_with(crate::catalog::RESERVED_PREFIX) {
+            return Err(format!("Table names starting with '{}' are reserved for the system catalog", crate::catalog::RESERVED_PREFIX));
+        }
+        self.virtual_tables.insert(name.to_string(), Arc::new(source));
+        Ok(())
+    }
+
+    /// Detaches a virtual table, leaving its source as it is.
+    pub fn detach_virtual(&mut self, name: &str) -> Result<(), String> {
+        self.virtual_tables.remove(name).map(drop).ok_or(format!("Virtual table '{}' not found", name))
+    }
+
+    pub(crate) fn is_virtual(&self, name: &str) -> bool {
+        self.virtual_tables.contains_key(name)
+    }
+
+    // The rows of a virtual table that may match `condition`, read into a
+    // table for one statement.
+    pub(crate) fn read_virtual(&self, name: &str, condition: &Option<Condition>) -> Result<Table, String> {
+        let source = self.virtual_tables.get(name).ok_or("Table not found")?;
+        let mut pushed = Vec::new();
+        if let Some(condition) = condition {
+            predicates(condition, &mut pushed);
+        }
+        let rows = if pushed.is_empty() { source.scan()? } else { source.scan_where(&pushed)? };
+        let records = rows.into_iter().zip(1..).map(|(data, id)| Record { id, data }).collect();
+        let mut table = Table::transient(name, records);
+        table.proto = source.schema();
+        Ok(table)
+    }
+}
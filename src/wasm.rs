@@ -0,0 +1,69 @@
+use js_sys::{Array, BigInt, Object, Promise, Reflect};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+
+use crate::{opfs, Database};
+
+/// The engine as seen from JavaScript. Results are arrays of
+/// `{ id: bigint, data: { column: value } }` objects.
+#[wasm_bindgen(js_name = Database)]
+pub struct WasmDatabase {
+    db: Database,
+}
+
+#[wasm_bindgen(js_class = Database)]
+impl WasmDatabase {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmDatabase {
+        WasmDatabase { db: Database::new() }
+    }
+
+    #[wasm_bindgen(js_name = loadOpfs)]
+    pub async fn load_opfs(filename: String) -> Result<WasmDatabase, JsError> {
+        let db = Database::load_opfs(&filename).await.map_err(|e| JsError::new(&e))?;
+        Ok(WasmDatabase { db })
+    }
+
+    /// Resolves once the snapshot taken at call time has been written.
+    #[wasm_bindgen(js_name = saveOpfs)]
+    pub fn save_opfs(&self, filename: String) -> Result<Promise, JsError> {
+        let bytes = self.db.to_bytes().map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(future_to_promise(async move {
+            opfs::write_file(&filename, &bytes).await.map_err(|e| JsValue::from(JsError::new(&e)))?;
+            Ok(JsValue::UNDEFINED)
+        }))
+    }
+
+    #[wasm_bindgen(js_name = createTable)]
+    pub fn create_table(&mut self, name: String) -> Result<(), JsError> {
+        self.db.create_table(name).map_err(|e| JsError::new(&e))
+    }
+
+    pub fn tables(&self) -> Vec<String> {
+        let mut tables: Vec<String> = self.db.list_tables().into_iter().map(String::from).collect();
+        tables.sort();
+        tables
+    }
+
+    pub fn execute(&mut self, sql: &str) -> Result<Array, JsError> {
+        let records = self.db.execute_sql(sql).map_err(|e| JsError::new(&e))?;
+        let rows = Array::new();
+        for record in records {
+            let data = Object::new();
+            for (column, value) in record.data() {
+                Reflect::set(&data, &column.into(), &value.into()).map_err(|_| JsError::new("Failed to build row"))?;
+            }
+            let row = Object::new();
+            Reflect::set(&row, &"id".into(), &BigInt::from(record.id()).into()).map_err(|_| JsError::new("Failed to build row"))?;
+            Reflect::set(&row, &"data".into(), &data).map_err(|_| JsError::new("Failed to build row"))?;
+            rows.push(&row);
+        }
+        Ok(rows)
+    }
+}
+
+impl Default for WasmDatabase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+
+use crate::{Database, Record};
+
+impl Database {
+    pub fn export_xlsx(&self, query: &str, path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error>> {
+        let records = self.query_sql(query)?;
+        let mut workbook = Workbook::new();
+        write_sheet(workbook.add_worksheet(), "Result", &records.iter().collect::<Vec<_>>())?;
+        workbook.save(path)?;
+        Ok(())
+    }
+
+    pub fn export_tables_xlsx(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error>> {
+        let mut workbook = Workbook::new();
+        let mut tables = self.list_tables();
+        tables.sort();
+        let mut used = HashSet::new();
+        for table in tables {
+            write_sheet(workbook.add_worksheet(), &sheet_name(table, &mut used), &self.get_all(table)?)?;
+        }
+        workbook.save(path)?;
+        Ok(())
+    }
+}
+
+// Excel limits sheet names to 31 characters, rejects a few punctuation marks
+// and leading or trailing apostrophes, and compares names case-insensitively.
+fn sheet_name(name: &str, used: &mut HashSet<String>) -> String {
+    let base: String = name.chars()
+        .map(|c| if "[]:*?/\\".contains(c) { '_' } else { c })
+        .collect::<String>()
+        .trim_matches('\'')
+        .chars()
+        .take(31)
+        .collect::<String>()
+        .trim_end_matches('\'')
+        .to_string();
+    let base = match base.as_str() {
+        "" => "Sheet".to_string(),
+        // reserved by Excel
+        _ if base.eq_ignore_ascii_case("history") => format!("{}_", base),
+        _ => base,
+    };
+
+    let mut candidate = base.clone();
+    let mut n = 2;
+    while !used.insert(candidate.to_lowercase()) {
+        let suffix = format!("~{}", n);
+        let prefix: String = base.chars().take(31 - suffix.len()).collect();
+        candidate = format!("{}{}", prefix.trim_end_matches('\''), suffix);
+        n += 1;
+    }
+    candidate
+}
+
+fn write_sheet(sheet: &mut Worksheet, name: &str, records: &[&Record]) -> Result<(), XlsxError> {
+    sheet.set_name(name)?;
+    let mut columns: Vec<&String> = records.iter().flat_map(|r| r.data.keys()).collect();
+    columns.sort();
+    columns.dedup();
+
+    let header = Format::new().set_bold();
+    sheet.write_string_with_format(0, 0, "id", &header)?;
+    for (i, column) in columns.iter().enumerate() {
+        sheet.write_string_with_format(0, i as u16 + 1, column.as_str(), &header)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+
+    for (row, record) in records.iter().enumerate() {
+        let row = row as u32 + 1;
+        sheet.write_number(row, 0, record.id as f64)?;
+        for (i, column) in columns.iter().enumerate() {
+            if let Some(value) = record.data.get(column) {
+                write_cell(sheet, row, i as u16 + 1, value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Values are stored as strings, so pick the cell type from the contents. Only
+// values that survive the round trip through f64 unchanged become numbers, so
+// codes like `007` and long digit strings stay text.
+fn write_cell(sheet: &mut Worksheet, row: u32, column: u16, value: &str) -> Result<(), XlsxError> {
+    if let Some(number) = value.parse::<f64>().ok().filter(|n| n.is_finite() && n.to_string() == value) {
+        sheet.write_number(row, column, number)?;
+    } else if let Ok(boolean) = value.parse::<bool>() {
+        sheet.write_boolean(row, column, boolean)?;
+    } else {
+        sheet.write_string(row, column, value)?;
+    }
+    Ok(())
+}
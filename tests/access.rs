@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use potatodb::{Database, Privilege, QueryServer, RemoteClient, ALL_TABLES};
+
+fn setup() -> Database {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    db.create_table("orders".to_string()).unwrap();
+    db.insert("users", 1, HashMap::from([("name".to_string(), "Alice".to_string())])).unwrap();
+    db.create_role("reader").unwrap();
+    db.grant("reader", "users", &[Privilege::Select]).unwrap();
+    db.create_role("clerk").unwrap();
+    db.grant("clerk", "orders", &[Privilege::Select, Privilege::Insert]).unwrap();
+    db.create_role("admin").unwrap();
+    db.grant("admin", ALL_TABLES, &[Privilege::Select, Privilege::Insert, Privilege::Update, Privilege::Delete, Privilege::Ddl]).unwrap();
+    db.create_user("rita", &["reader"]).unwrap();
+    db.create_user("carl", &["reader", "clerk"]).unwrap();
+    db.create_user("ada", &["admin"]).unwrap();
+    db
+}
+
+#[test]
+fn statements_need_a_privilege_on_their_table() {
+    let mut db = setup();
+    db.set_session_user(Some("rita")).unwrap();
+    assert_eq!(db.execute_sql("SELECT * FROM users").unwrap().len(), 1);
+    assert_eq!(db.execute_sql("EXPLAIN SELECT * FROM users").unwrap().len(), 2);
+    let err = db.execute_sql("DELETE FROM users WHERE name = Alice").unwrap_err();
+    assert!(err.contains("DELETE"), "{}", err);
+    assert!(db.execute_sql("SELECT * FROM orders").is_err());
+    assert!(db.execute_sql("INSERT INTO orders (item) VALUES (pen)").is_err());
+
+    db.set_session_user(Some("carl")).unwrap();
+    db.execute_sql("INSERT INTO orders (item) VALUES (pen)").unwrap();
+    assert!(db.execute_sql("UPDATE orders SET item = ink WHERE item = pen").is_err());
+    assert!(db.execute_sql("CREATE TABLE logs").is_err());
+    assert!(db.execute_sql("PRAGMA integrity_check").is_err());
+
+    // the application itself is unrestricted
+    db.set_session_user(None).unwrap();
+    db.execute_sql("DELETE FROM users WHERE name = Alice").unwrap();
+}
+
+#[test]
+fn wildcard_grants_cover_every_table() {
+    let mut db = setup();
+    db.set_session_user(Some("ada")).unwrap();
+    db.execute_sql("CREATE TABLE logs").unwrap();
+    db.execute_sql("INSERT INTO logs (line) VALUES (hello)").unwrap();
+    db.execute_sql("UPDATE users SET name = Alicia WHERE name = Alice").unwrap();
+    assert_eq!(db.execute_sql("PRAGMA integrity_check").unwrap().len(), 1);
+    assert_eq!(db.session_user(), Some("ada"));
+}
+
+#[test]
+fn revoking_takes_privileges_back() {
+    let mut db = setup();
+    db.set_session_user(Some("rita")).unwrap();
+    db.revoke("reader", "users", &[Privilege::Select]).unwrap();
+    assert!(db.query_sql("SELECT * FROM users").is_err());
+}
+
+#[test]
+fn unknown_users_and_roles_are_rejected() {
+    let mut db = setup();
+    assert!(db.set_session_user(Some("mallory")).is_err());
+    assert!(db.create_user("mallory", &["root"]).is_err());
+    assert!(db.grant("root", "users", &[Privilege::Select]).is_err());
+    assert!(db.create_role("reader").is_err());
+}
+
+#[test]
+fn servers_can_run_queries_as_a_user() {
+    let db = Arc::new(Mutex::new(setup()));
+    let server = QueryServer::bind_as(db.clone(), "127.0.0.1:0", "rita").unwrap();
+    let mut client = RemoteClient::connect(server.local_addr()).unwrap();
+    assert_eq!(client.query_sql("SELECT * FROM users").unwrap().len(), 1);
+    assert!(client.query_sql("SELECT * FROM orders").is_err());
+    assert_eq!(client.list_tables().unwrap(), ["users"]);
+    assert!(!client.columns("users").unwrap().is_empty());
+    assert!(client.columns("orders").unwrap_err().to_string().contains("Permission denied"));
+    // the server does not leave its user on the shared database
+    assert_eq!(db.lock().unwrap().session_user(), None);
+    assert!(QueryServer::bind_as(db, "127.0.0.1:0", "mallory").is_err());
+}
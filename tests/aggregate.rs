@@ -0,0 +1,179 @@
+use potatodb::{Database, Privilege, SortOrder};
+
+mod common;
+use common::row;
+
+fn orders() -> Database {
+    let mut db = Database::new();
+    db.create_table("orders".to_string()).unwrap();
+    let orders = [("alice", "paid", "30"), ("bob", "paid", "12.5"), ("alice", "paid", "9"), ("carol", "open", "100"), ("bob", "paid", "2.5")];
+    for (id, (user, status, total)) in (1..).zip(orders) {
+        db.insert("orders", id, row(&[("user", user), ("status", status), ("total", total)])).unwrap();
+    }
+    db
+}
+
+fn column<'a>(rows: &'a [potatodb::Record], name: &str) -> Vec<&'a str> {
+    rows.iter().map(|r| r.data()[name].as_str()).collect()
+}
+
+#[test]
+fn stages_run_in_order() {
+    let db = orders();
+    let rows = db.aggregate("orders")
+        .match_("status = paid")
+        .group("user", &[("spent", "sum(total)"), ("orders", "count"), ("largest", "max(total)")])
+        .sort("spent", SortOrder::Descending)
+        .run()
+        .unwrap();
+    assert_eq!(column(&rows, "user"), vec!["alice", "bob"]);
+    assert_eq!(column(&rows, "spent"), vec!["39", "15"]);
+    assert_eq!(column(&rows, "orders"), vec!["2", "2"]);
+    // numbers compare as numbers: 9 < 30
+    assert_eq!(column(&rows, "largest"), vec!["30", "12.5"]);
+
+    let top = db.aggregate("orders").sort("total", SortOrder::Descending).limit(2).run().unwrap();
+    assert_eq!(column(&top, "total"), vec!["100", "30"]);
+}
+
+#[test]
+fn later_matches_filter_grouped_rows() {
+    let db = orders();
+    let rows = db.aggregate("orders")
+        .group("user", &[("orders", "count")])
+        .match_("orders = 2")
+        .sort("user", SortOrder::Ascending)
+        .run()
+        .unwrap();
+    assert_eq!(column(&rows, "user"), vec!["alice", "bob"]);
+
+    let totals = db.aggregate("orders").group_all(&[("n", "count"), ("average", "avg(total)"), ("least", "min(total)")]).run().unwrap();
+    assert_eq!(totals.len(), 1);
+    assert_eq!(totals[0].data()["n"], "5");
+    assert_eq!(totals[0].data()["average"], "30.8");
+    assert_eq!(totals[0].data()["least"], "2.5");
+}
+
+#[test]
+fn percentiles_interpolate() {
+    let db = orders();
+    let rows = db.aggregate("orders")
+        .group_all(&[("median", "median(total)"), ("p90", "percentile_cont(total, 0.9)"), ("low", "PERCENTILE_CONT(total, 0)")])
+        .run()
+        .unwrap();
+    assert_eq!(rows[0].data()["median"], "12.5");
+    assert_eq!(rows[0].data()["p90"], "72");
+    assert_eq!(rows[0].data()["low"], "2.5");
+
+    let rows = db.aggregate("orders").group("user", &[("median", "median(total)")]).run().unwrap();
+    assert_eq!(column(&rows, "median"), ["19.5", "7.5", "100"]);
+    assert!(db.aggregate("orders").group_all(&[("p", "percentile_cont(total, 1.5)")]).run().is_err());
+    assert!(db.aggregate("orders").group_all(&[("p", "percentile_cont(total)")]).run().is_err());
+}
+
+#[test]
+fn approx_count_distinct_estimates_closely() {
+    let db = orders();
+    let rows = db.aggregate("orders").group_all(&[("users", "approx_count_distinct(user)")]).run().unwrap();
+    assert_eq!(rows[0].data()["users"], "3");
+
+    let mut db = Database::new();
+    db.create_table("visits".to_string()).unwrap();
+    for id in 1..=20_000 {
+        let visitor = (id % 5_000).to_string();
+        db.insert("visits", id, row(&[("visitor", &visitor)])).unwrap();
+    }
+    let rows = db.aggregate("visits").group_all(&[("visitors", "approx_count_distinct(visitor)")]).run().unwrap();
+    let estimate: f64 = rows[0].data()["visitors"].parse().unwrap();
+    assert!((estimate - 5_000.0).abs() < 150.0, "{}", estimate);
+}
+
+#[test]
+fn aggregates_without_a_column_are_rejected() {
+    let db = orders();
+    for call in ["approx_count_distinct()", "sum( )", "median()"] {
+        let err = db.aggregate("orders").group_all(&[("n", call)]).run().unwrap_err();
+        assert!(err.contains("takes a column"), "{}", err);
+    }
+    let err = db.query_sql("SELECT user, approx_count_distinct() FROM orders GROUP BY user").unwrap_err();
+    assert!(err.contains("takes a column"), "{}", err);
+}
+
+#[test]
+fn pivot_makes_a_column_per_value() {
+    let db = orders();
+    let rows = db.aggregate("orders").pivot("user", "status", "sum(total)").run().unwrap();
+    assert_eq!(column(&rows, "user"), ["alice", "bob", "carol"]);
+    assert_eq!(rows[0].data()["paid"], "39");
+    assert_eq!(rows[1].data()["paid"], "15");
+    assert_eq!(rows[2].data()["open"], "100");
+    assert!(!rows[0].data().contains_key("open"));
+
+    let rows = db.aggregate("orders")
+        .match_("status = paid")
+        .pivot("status", "user", "count")
+        .run()
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].data()["alice"], "2");
+    assert_eq!(rows[0].data()["bob"], "2");
+    assert!(!rows[0].data().contains_key("carol"));
+
+    assert!(db.aggregate("orders").pivot("user", "status", "mode(total)").run().is_err());
+}
+
+#[test]
+fn invalid_stages_fail() {
+    let db = orders();
+    assert!(db.aggregate("orders").match_("status is paid").run().is_err());
+    assert!(db.aggregate("orders").group("user", &[("x", "mode(total)")]).run().is_err());
+    assert!(db.aggregate("missing").run().is_err());
+}
+
+#[test]
+fn pipelines_are_checked_like_sql() {
+    let mut db = orders();
+    db.create_role("reader").unwrap();
+    db.create_user("mallory", &[]).unwrap();
+    db.set_session_user(Some("mallory")).unwrap();
+    assert!(db.aggregate("orders").run().is_err());
+
+    db.set_session_user(None).unwrap();
+    db.grant("reader", "orders", &[Privilege::Select]).unwrap();
+    db.create_user("rita", &["reader"]).unwrap();
+    db.add_policy("orders", "user = $user").unwrap();
+    db.set_session_attribute("user", "bob");
+    db.set_session_user(Some("rita")).unwrap();
+    let rows = db.aggregate("orders").group_all(&[("spent", "sum(total)")]).run().unwrap();
+    assert_eq!(rows[0].data()["spent"], "15");
+}
+
+#[test]
+fn string_agg_joins_values() {
+    let mut db = orders();
+    for columnar in [false, true] {
+        db.set_columnar("orders", columnar).unwrap();
+        let rows = db.aggregate("orders")
+            .group("status", &[
+                ("users", "group_concat(user)"),
+                ("totals", "string_agg(total, ' | ' ORDER BY total DESC)"),
+                ("by_user", "STRING_AGG(total, \", \" order by user)"),
+            ])
+            .run()
+            .unwrap();
+        assert_eq!(column(&rows, "status"), ["paid", "open"]);
+        assert_eq!(column(&rows, "users"), ["alice,bob,alice,bob", "carol"]);
+        assert_eq!(column(&rows, "totals"), ["30 | 12.5 | 9 | 2.5", "100"]);
+        assert_eq!(column(&rows, "by_user"), ["30, 9, 12.5, 2.5", "100"]);
+    }
+}
+
+#[test]
+fn string_agg_needs_a_column_and_quoted_separator() {
+    let db = orders();
+    for accumulator in ["string_agg()", "string_agg(user, |)", "string_agg(user, ', ' ORDER user)", "group_concat(user ORDER BY total SIDEWAYS)"] {
+        assert!(db.aggregate("orders").group_all(&[("x", accumulator)]).run().is_err(), "{}", accumulator);
+    }
+    let rows = db.aggregate("orders").group_all(&[("x", "group_concat(missing)")]).run().unwrap();
+    assert!(!rows[0].data().contains_key("x"));
+}
@@ -0,0 +1,98 @@
+use potatodb::{ColumnType, CompareAs, Database, PartitionScheme, ProtoMessage, ProtoType};
+
+mod common;
+use common::row;
+
+fn shop() -> Database {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    db.create_table("orders".to_string()).unwrap();
+    db.insert("users", 1, row(&[("name", "ann")])).unwrap();
+    db.insert("orders", 1, row(&[("owner", "1"), ("price", "2.50"), ("tenant", "acme")])).unwrap();
+    db.insert("orders", 2, row(&[("owner", "1"), ("price", "10"), ("tenant", "globex")])).unwrap();
+    db.insert("orders", 3, row(&[("tenant", "acme")])).unwrap();
+    db.has_many("users", "orders", "owner").unwrap();
+    db
+}
+
+#[test]
+fn renames_rewrite_rows_and_what_depends_on_them() {
+    let mut db = shop();
+    db.add_generated_column("orders", "doubled GENERATED ALWAYS AS (price * 2)").unwrap();
+    db.partition_table("orders", PartitionScheme::Hash { column: "owner".to_string(), partitions: 2 }).unwrap();
+    db.set_compare_as("orders", "price", CompareAs::Numeric).unwrap();
+
+    db.rename_column("orders", "price", "amount").unwrap();
+    db.rename_column("orders", "owner", "user_id").unwrap();
+
+    let order = db.get("orders", 2).unwrap().unwrap();
+    assert_eq!(order.data()["amount"], "10");
+    assert!(!order.data().contains_key("price"));
+    assert_eq!(db.compare_as("orders", "amount").unwrap(), CompareAs::Numeric);
+    assert_eq!(db.query_sql("SELECT doubled FROM orders WHERE amount > 9").unwrap()[0].data()["doubled"], "20");
+    assert!(matches!(db.table_partitioning("orders").unwrap(), Some(PartitionScheme::Hash { column, .. }) if column == "user_id"));
+    assert_eq!(db.scanned_partitions("SELECT * FROM orders WHERE user_id = 1").unwrap().map(|p| p.len()), Some(1));
+    assert_eq!(db.query_sql("SELECT * FROM orders WHERE user_id = 1").unwrap().len(), 2);
+    assert_eq!(db.delete_cascade("users", 1).unwrap().len(), 3);
+}
+
+#[test]
+fn renames_follow_policies_and_messages() {
+    let mut db = shop();
+    db.add_policy("orders", "tenant = $tenant").unwrap();
+    db.set_session_attribute("tenant", "acme");
+    db.rename_column("orders", "tenant", "org").unwrap();
+    assert_eq!(db.query_sql("SELECT * FROM orders").unwrap().len(), 2);
+
+    db.create_table("points".to_string()).unwrap();
+    db.set_table_proto("points", ProtoMessage::new("Point").field("x", 1, ProtoType::Int32).unwrap()).unwrap();
+    db.insert("points", 1, row(&[("x", "4")])).unwrap();
+    db.rename_column("points", "x", "left").unwrap();
+    db.insert("points", 2, row(&[("left", "5")])).unwrap();
+    assert!(db.insert("points", 3, row(&[("x", "5")])).is_err());
+}
+
+#[test]
+fn bad_renames_are_rejected() {
+    let mut db = shop();
+    assert!(db.rename_column("orders", "missing", "b").unwrap_err().contains("not found"));
+    assert!(db.rename_column("orders", "price", "tenant").unwrap_err().contains("already has"));
+    assert!(db.rename_column("orders", "price", "").is_err());
+    assert!(db.rename_column("nope", "a", "b").is_err());
+}
+
+#[test]
+fn type_changes_convert_every_row_or_none() {
+    let mut db = shop();
+    db.insert("orders", 4, row(&[("price", "3.0")])).unwrap();
+    let report = db.change_column_type("orders", "price", ColumnType::Integer).unwrap();
+    assert!(!report.is_applied());
+    assert_eq!(report.failures(), [(1, "2.50".to_string())]);
+    assert_eq!(db.get("orders", 4).unwrap().unwrap().data()["price"], "3.0");
+
+    let report = db.change_column_type("orders", "price", ColumnType::Real).unwrap();
+    assert!(report.is_applied());
+    assert_eq!(report.converted(), 2);
+    assert_eq!(db.get("orders", 1).unwrap().unwrap().data()["price"], "2.5");
+    assert_eq!(db.get("orders", 4).unwrap().unwrap().data()["price"], "3");
+    assert_eq!(db.compare_as("orders", "price").unwrap(), CompareAs::Numeric);
+    assert_eq!(db.query_sql("SELECT * FROM orders WHERE price > 9").unwrap().len(), 1);
+    // later writes are stored as the type too
+    assert!(db.insert("orders", 5, row(&[("price", "cheap")])).is_err());
+    db.insert("orders", 5, row(&[("price", "4.50")])).unwrap();
+    assert_eq!(db.get("orders", 5).unwrap().unwrap().data()["price"], "4.5");
+
+    db.change_column_type("orders", "price", ColumnType::Text).unwrap();
+    assert_eq!(db.compare_as("orders", "price").unwrap(), CompareAs::String);
+}
+
+#[test]
+fn type_changes_retype_message_fields() {
+    let mut db = Database::new();
+    db.create_table("points".to_string()).unwrap();
+    db.set_table_proto("points", ProtoMessage::new("Point").field("x", 1, ProtoType::String).unwrap()).unwrap();
+    db.insert("points", 1, row(&[("x", "07")])).unwrap();
+    assert_eq!(db.change_column_type("points", "x", ColumnType::Integer).unwrap().converted(), 1);
+    assert_eq!(db.get("points", 1).unwrap().unwrap().data()["x"], "7");
+    assert!(db.insert("points", 2, row(&[("x", "seven")])).is_err());
+}
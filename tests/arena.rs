@@ -0,0 +1,48 @@
+use std::sync::Arc;
+use std::thread;
+
+use potatodb::Database;
+
+mod common;
+use common::row;
+
+fn sales(count: u64) -> Database {
+    let mut db = Database::new();
+    db.create_table("sales".to_string()).unwrap();
+    for id in 1..=count {
+        let region = ["north", "south", "east"][id as usize % 3];
+        db.insert("sales", id, row(&[("region", region), ("amount", &id.to_string()), ("note", "x")])).unwrap();
+    }
+    db
+}
+
+#[test]
+fn statements_reusing_arenas_return_their_own_rows() {
+    let db = sales(30_000);
+    // a large statement first, so later ones run on a grown arena
+    assert_eq!(db.query_sql("SELECT region FROM sales").unwrap().len(), 30_000);
+    for _ in 0..3 {
+        let rows = db.query_sql("SELECT amount FROM sales WHERE region = north").unwrap();
+        assert_eq!(rows.len(), 10_000);
+        assert!(rows.iter().all(|r| r.data().len() == 1 && r.data().contains_key("amount")));
+    }
+    let totals = db.aggregate("sales").group("region", &[("n", "count")]).run().unwrap();
+    assert!(totals.iter().all(|r| r.data()["n"] == "10000"));
+}
+
+#[test]
+fn threads_have_their_own_arenas() {
+    let db = Arc::new(sales(300));
+    let handles: Vec<_> = ["north", "south", "east"].into_iter()
+        .map(|region| {
+            let db = Arc::clone(&db);
+            thread::spawn(move || {
+                (0..20).all(|_| {
+                    let rows = db.query_sql(&format!("SELECT region FROM sales WHERE region = {}", region)).unwrap();
+                    rows.len() == 100 && rows.iter().all(|r| r.data()["region"] == region)
+                })
+            })
+        })
+        .collect();
+    assert!(handles.into_iter().all(|h| h.join().unwrap()));
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use potatodb::{AuditSink, ChangeKind, Database, Row, AUDIT_TABLE};
+
+mod common;
+use common::temp_path;
+
+fn row(name: &str) -> HashMap<String, String> {
+    HashMap::from([("name".to_string(), name.to_string())])
+}
+
+fn audit_rows(db: &Database) -> Vec<HashMap<String, String>> {
+    db.query_sql(&format!("SELECT * FROM {}", AUDIT_TABLE)).unwrap().iter().map(|r| r.data().to_map()).collect()
+}
+
+#[test]
+fn mutations_are_audited_to_the_audit_table() {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    db.insert("users", 1, row("before auditing")).unwrap();
+    db.enable_audit(AuditSink::Table).unwrap();
+    db.set_audit_session("alice");
+    db.update("users", 1, row("Ann")).unwrap();
+    db.set_audit_session("job:cleanup");
+    db.execute_sql("INSERT INTO users (name) VALUES (Bob)").unwrap();
+    db.execute_sql("UPDATE users SET name = Rob WHERE name = Bob").unwrap();
+    db.execute_sql("DELETE FROM users WHERE name = Ann").unwrap();
+
+    let rows = audit_rows(&db);
+    let summary: Vec<_> = rows.iter()
+        .map(|r| (r["session"].as_str(), r["operation"].as_str(), r.get("before").map(String::as_str), r.get("after").map(String::as_str)))
+        .collect();
+    assert_eq!(summary, [
+        ("alice", "update", Some("name=before auditing"), Some("name=Ann")),
+        ("job:cleanup", "insert", None, Some("name=Bob")),
+        ("job:cleanup", "update", Some("name=Bob"), Some("name=Rob")),
+        ("job:cleanup", "delete", Some("name=Ann"), None),
+    ]);
+    assert!(rows.iter().all(|r| r["table"] == "users" && r["timestamp"].parse::<u64>().unwrap() > 0));
+    assert!(db.execute_sql(&format!("DELETE FROM {}", AUDIT_TABLE)).is_err());
+
+    db.set_audit_retention(Some(2), None);
+    assert_eq!(audit_rows(&db).len(), 2);
+    db.set_audit_retention(None, Some(Duration::from_millis(200)));
+    std::thread::sleep(Duration::from_millis(250));
+    db.insert("users", 9, row("late")).unwrap();
+    assert_eq!(audit_rows(&db).len(), 1);
+
+    db.disable_audit();
+    db.delete("users", 9).unwrap();
+    assert_eq!(audit_rows(&db).len(), 1);
+}
+
+#[test]
+fn mutations_are_appended_to_an_audit_file() {
+    let path = temp_path("audit", "bin");
+    let _ = std::fs::remove_file(&path);
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    db.enable_audit(AuditSink::File(path.clone())).unwrap();
+    db.set_audit_session("alice");
+    db.insert("users", 1, row("Ann")).unwrap();
+    db.delete("users", 1).unwrap();
+
+    // reopening appends rather than truncating
+    let mut other = Database::new();
+    other.create_table("t".to_string()).unwrap();
+    other.enable_audit(AuditSink::File(path.clone())).unwrap();
+    other.insert("t", 1, row("x")).unwrap();
+
+    let entries = Database::read_audit_file(&path).unwrap();
+    let kinds: Vec<_> = entries.iter().map(|e| (e.table(), e.kind(), e.session())).collect();
+    assert_eq!(kinds, [("users", ChangeKind::Insert, "alice"), ("users", ChangeKind::Delete, "alice"), ("t", ChangeKind::Insert, "")]);
+    assert_eq!(entries[1].before().map(Row::to_map), Some(row("Ann")));
+    assert_eq!(entries[1].after(), None);
+    assert!(audit_rows(&db).is_empty());
+    assert_eq!(db.take_audit_error(), None);
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,124 @@
+#![cfg(feature = "avro")]
+
+use std::collections::HashMap;
+
+use potatodb::Database;
+
+mod common;
+use common::{row, temp_path};
+
+fn long(out: &mut Vec<u8>, n: i64) {
+    let mut n = ((n << 1) ^ (n >> 63)) as u64;
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn bytes(out: &mut Vec<u8>, b: &[u8]) {
+    long(out, b.len() as i64);
+    out.extend_from_slice(b);
+}
+
+// Builds a one-block object container file from already encoded records.
+fn container(schema: &str, count: i64, block: &[u8]) -> Vec<u8> {
+    let mut out = b"Obj\x01".to_vec();
+    long(&mut out, 1);
+    bytes(&mut out, b"avro.schema");
+    bytes(&mut out, schema.as_bytes());
+    long(&mut out, 0);
+    out.extend_from_slice(&[7; 16]);
+    long(&mut out, count);
+    long(&mut out, block.len() as i64);
+    out.extend_from_slice(block);
+    out.extend_from_slice(&[7; 16]);
+    out
+}
+
+#[test]
+fn export_then_import_round_trips() {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    db.insert("users", 3, row(&[("name", "Alice"), ("email", "a@example.com")])).unwrap();
+    db.insert("users", 9, row(&[("name", "Bob")])).unwrap();
+
+    let path = temp_path("round-trip", "avro");
+    assert_eq!(db.export_avro("users", &path).unwrap(), 2);
+
+    let mut copy = Database::new();
+    assert_eq!(copy.import_avro("users", &path).unwrap(), 2);
+    assert_eq!(copy.get("users", 3).unwrap().unwrap().data(), &row(&[("name", "Alice"), ("email", "a@example.com")]));
+    assert_eq!(copy.get("users", 9).unwrap().unwrap().data(), &row(&[("name", "Bob")]));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn export_rejects_clashing_column_names() {
+    let mut db = Database::new();
+    db.create_table("t".to_string()).unwrap();
+    db.insert("t", 1, row(&[("a-b", "1"), ("a_b", "2")])).unwrap();
+    assert!(db.export_avro("t", temp_path("clash", "avro")).is_err());
+
+    db.create_table("u".to_string()).unwrap();
+    db.insert("u", 1, row(&[("id", "x")])).unwrap();
+    assert!(db.export_avro("u", temp_path("clash-id", "avro")).is_err());
+}
+
+#[test]
+fn import_is_all_or_nothing() {
+    let mut db = Database::new();
+    db.create_table("t".to_string()).unwrap();
+    db.insert("t", 2, row(&[("v", "existing")])).unwrap();
+
+    // ids 1 and 2, the second clashes with the existing record
+    let mut block = Vec::new();
+    for id in [1, 2] {
+        long(&mut block, id);
+        bytes(&mut block, b"new");
+    }
+    let schema = r#"{"type":"record","name":"t","fields":[{"name":"id","type":"long"},{"name":"v","type":"string"}]}"#;
+    let path = temp_path("atomic", "avro");
+    std::fs::write(&path, container(schema, 2, &block)).unwrap();
+
+    assert!(db.import_avro("t", &path).is_err());
+    assert_eq!(db.get_all("t").unwrap().len(), 1);
+    assert!(db.import_avro("fresh", &path).is_ok());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn nullable_ids_are_used_or_assigned() {
+    let mut block = Vec::new();
+    // union branch 0 (long) with id 40, then branch 1 (null)
+    long(&mut block, 0);
+    long(&mut block, 40);
+    long(&mut block, 1);
+    let schema = r#"{"type":"record","name":"t","fields":[{"name":"id","type":["long","null"]}]}"#;
+    let path = temp_path("nullable", "avro");
+    std::fs::write(&path, container(schema, 2, &block)).unwrap();
+
+    let mut db = Database::new();
+    db.import_avro("t", &path).unwrap();
+    let mut ids: Vec<u64> = db.get_all("t").unwrap().iter().map(|r| r.id()).collect();
+    ids.sort();
+    assert_eq!(ids, vec![40, 41]);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn largest_id_does_not_overflow() {
+    let mut db = Database::new();
+    db.create_table("t".to_string()).unwrap();
+    db.insert("t", u64::MAX, HashMap::new()).unwrap();
+
+    let mut block = Vec::new();
+    bytes(&mut block, b"x");
+    let schema = r#"{"type":"record","name":"t","fields":[{"name":"v","type":"string"}]}"#;
+    let path = temp_path("overflow", "avro");
+    std::fs::write(&path, container(schema, 1, &block)).unwrap();
+
+    assert!(db.import_avro("t", &path).is_err());
+    assert_eq!(db.get_all("t").unwrap().len(), 1);
+    std::fs::remove_file(path).unwrap();
+}
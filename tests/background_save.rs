@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use potatodb::{Database, FaultyStorage, MemoryStorage, StorageBackend};
+
+mod common;
+use common::row;
+
+fn accounts() -> Database {
+    let mut db = Database::new();
+    db.create_table("accounts".to_string()).unwrap();
+    db.insert("accounts", 1, row(&[("owner", "ann"), ("balance", "10")])).unwrap();
+    db
+}
+
+#[test]
+fn saves_the_database_as_it_was_at_the_call() {
+    let mut db = accounts();
+    db.execute_sql("CREATE TEMP TABLE scratch").unwrap();
+    db.kv("settings").set("theme", "dark");
+    let storage = Arc::new(MemoryStorage::new());
+    let save = db.save_to_async(storage.clone(), "db.bin");
+
+    // writes after the call are not in the file
+    db.update("accounts", 1, row(&[("owner", "ann"), ("balance", "99")])).unwrap();
+    db.create_table("later".to_string()).unwrap();
+    db.kv("settings").set("theme", "light");
+    let bytes = save.wait().unwrap();
+
+    let mut saved = Database::load_from(&*storage, "db.bin").unwrap();
+    assert_eq!(storage.read("db.bin").unwrap().len(), bytes);
+    assert_eq!(saved.get("accounts", 1).unwrap().unwrap().data()["balance"], "10");
+    assert_eq!(saved.list_tables(), ["accounts"]);
+    assert_eq!(saved.kv("settings").get_str("theme"), Some("dark"));
+    assert_eq!(db.get("accounts", 1).unwrap().unwrap().data()["balance"], "99");
+}
+
+#[test]
+fn saves_to_files() {
+    let path = std::env::temp_dir().join(format!("potatodb-save-async-{}.bin", std::process::id()));
+    let path = path.to_str().unwrap();
+    let save = accounts().save_async(path);
+    save.wait().unwrap();
+    assert_eq!(Database::load(path).unwrap().get("accounts", 1).unwrap().unwrap().data()["owner"], "ann");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn failures_are_reported_by_wait() {
+    let storage = Arc::new(FaultyStorage::new(MemoryStorage::new()));
+    storage.fail_sync();
+    let save = accounts().save_to_async(storage.clone(), "db.bin");
+    while !save.is_finished() {
+        std::thread::yield_now();
+    }
+    assert!(save.wait().is_err());
+    assert!(storage.read("db.bin").is_err());
+}
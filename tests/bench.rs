@@ -0,0 +1,36 @@
+#![cfg(feature = "bench")]
+
+use potatodb::bench::{self, Dataset};
+
+fn small() -> Dataset {
+    Dataset { users: 50, orders: 400, seed: 7 }
+}
+
+#[test]
+fn datasets_are_reproducible() {
+    assert_eq!(small().orders(), small().orders());
+    assert_eq!(small().users(), small().users());
+    assert_ne!(Dataset { seed: 8, ..small() }.orders(), small().orders());
+    assert_eq!(small().users().len(), 50);
+    assert!(small().orders().iter().all(|(_, data)| data["user"].parse::<u64>().is_ok_and(|user| (1..=50).contains(&user))));
+}
+
+#[test]
+fn workloads_agree_with_the_generated_rows() {
+    let dataset = small();
+    let db = dataset.database().unwrap();
+    let orders = dataset.orders();
+    let users = dataset.users();
+
+    assert_eq!(bench::bulk_insert(orders.clone()).unwrap().query_sql("SELECT * FROM orders").unwrap().len(), 400);
+    assert_eq!(bench::point_lookup(&db, &[1, 400, 401]).unwrap(), 2);
+
+    let shipped = || orders.iter().filter(|(_, data)| data["status"] == "shipped");
+    let over = shipped().filter(|(_, data)| data["total"].parse::<f64>().unwrap() > 500.0).count();
+    assert_eq!(bench::scan_filter(&db).unwrap(), over);
+    let in_oslo = shipped()
+        .filter(|(_, data)| users[data["user"].parse::<usize>().unwrap() - 1].1["city"] == "Oslo")
+        .count();
+    assert_eq!(bench::join(&db).unwrap(), in_oslo);
+    assert!(in_oslo > 0);
+}
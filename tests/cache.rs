@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use potatodb::Database;
+
+mod common;
+use common::row;
+
+fn ids(db: &Database, table: &str) -> Vec<u64> {
+    let mut ids: Vec<u64> = db.get_all(table).unwrap().iter().map(|r| r.id()).collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn least_recently_used_records_are_evicted() {
+    let mut db = Database::new();
+    db.create_table("sessions".to_string()).unwrap();
+    db.enable_cache("sessions", 2).unwrap();
+    db.insert("sessions", 1, row(&[("user", "alice")])).unwrap();
+    db.insert("sessions", 2, row(&[("user", "bob")])).unwrap();
+    // reading 1 makes 2 the least recently used
+    assert_eq!(db.cache_get("sessions", 1).unwrap().unwrap().data()["user"], "alice");
+    db.insert("sessions", 3, row(&[("user", "carol")])).unwrap();
+    assert_eq!(ids(&db, "sessions"), [1, 3]);
+
+    // records deleted directly are not evicted twice
+    db.delete("sessions", 1).unwrap();
+    db.execute_sql("INSERT INTO sessions (user) VALUES (dave)").unwrap();
+    db.insert("sessions", 9, row(&[("user", "erin")])).unwrap();
+    assert_eq!(db.get_all("sessions").unwrap().len(), 2);
+    assert!(db.get("sessions", 9).unwrap().is_some());
+    assert!(db.get("sessions", 3).unwrap().is_none());
+}
+
+#[test]
+fn enabling_shrinks_the_table_to_capacity() {
+    let mut db = Database::new();
+    db.create_table("pages".to_string()).unwrap();
+    for id in 1..=4 {
+        db.insert("pages", id, HashMap::new()).unwrap();
+    }
+    db.enable_cache("pages", 3).unwrap();
+    assert_eq!(ids(&db, "pages"), [2, 3, 4]);
+    assert!(db.enable_cache("pages", 0).is_err());
+    assert!(db.enable_cache("missing", 1).is_err());
+    assert!(db.cache_get("missing", 1).is_err());
+}
+
+#[test]
+fn misses_read_through_the_loader() {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    db.enable_cache("users", 10).unwrap();
+    assert!(db.cache_get("users", 7).unwrap().is_none());
+
+    let loads = Arc::new(AtomicUsize::new(0));
+    let counter = loads.clone();
+    db.set_cache_loader("users", move |id| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        (id < 100).then(|| row(&[("name", &format!("user {}", id))]))
+    }).unwrap();
+
+    assert_eq!(db.cache_get("users", 7).unwrap().unwrap().data()["name"], "user 7");
+    assert_eq!(db.cache_get("users", 7).unwrap().unwrap().data()["name"], "user 7");
+    assert_eq!(loads.load(Ordering::SeqCst), 1);
+    assert!(db.cache_get("users", 500).unwrap().is_none());
+    assert_eq!(db.query_sql("SELECT * FROM users").unwrap().len(), 1);
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use potatodb::{ChangeKind, Database};
+
+mod common;
+use common::{row, temp_path};
+
+fn ids(db: &Database, table: &str) -> Vec<u64> {
+    db.get_all(table).unwrap().iter().map(|r| r.id()).collect()
+}
+
+#[test]
+fn inserts_evict_the_oldest_rows() {
+    let mut db = Database::new();
+    db.create_capped_table("feed", 3).unwrap();
+    for id in 1..=5 {
+        db.insert("feed", id, row(&[("event", "login")])).unwrap();
+    }
+    assert_eq!(ids(&db, "feed"), [3, 4, 5]);
+    assert_eq!(db.get("feed", 4).unwrap().unwrap().data()["event"], "login");
+    assert!(db.get("feed", 1).unwrap().is_none());
+
+    db.delete("feed", 4).unwrap();
+    db.insert("feed", 6, HashMap::new()).unwrap();
+    assert_eq!(ids(&db, "feed"), [3, 5, 6]);
+    assert!(db.create_capped_table("empty", 0).is_err());
+}
+
+#[test]
+fn sql_creates_capped_tables() {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE logs (level ENUM('info','error')) MAX ROWS 2").unwrap();
+    for level in ["info", "error", "info"] {
+        db.execute_sql(&format!("INSERT INTO logs (level) VALUES ({})", level)).unwrap();
+    }
+    assert_eq!(ids(&db, "logs"), [2, 3]);
+    assert!(db.execute_sql("INSERT INTO logs (level) VALUES (debug)").is_err());
+
+    db.execute_sql("create table recent max rows 1").unwrap();
+    db.execute_sql("INSERT INTO recent (page) VALUES (home)").unwrap();
+    db.execute_sql("INSERT INTO recent (page) VALUES (about)").unwrap();
+    let rows = db.query_sql("SELECT page FROM recent").unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].data()["page"], "about");
+
+    assert!(db.execute_sql("CREATE TABLE bad MAX ROWS many").is_err());
+    assert!(db.execute_sql("CREATE TABLE bad MAX ROWS 0").is_err());
+}
+
+#[test]
+fn evictions_are_changes() {
+    let mut db = Database::new();
+    db.create_capped_table("feed", 1).unwrap();
+    let changes = db.subscribe("feed", |_| true);
+    db.insert("feed", 1, HashMap::new()).unwrap();
+    db.insert("feed", 2, HashMap::new()).unwrap();
+    let kinds: Vec<ChangeKind> = changes.try_iter().map(|c| c.kind()).collect();
+    assert_eq!(kinds, [ChangeKind::Insert, ChangeKind::Insert, ChangeKind::Delete]);
+}
+
+#[test]
+fn caps_are_saved() {
+    let path = temp_path("capped", "bin");
+    let mut db = Database::new();
+    db.create_capped_table("feed", 2).unwrap();
+    db.insert("feed", 1, HashMap::new()).unwrap();
+    db.save(path.to_str().unwrap()).unwrap();
+    let mut loaded = Database::load(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    loaded.insert("feed", 2, HashMap::new()).unwrap();
+    loaded.insert("feed", 3, HashMap::new()).unwrap();
+    assert_eq!(ids(&loaded, "feed"), [2, 3]);
+}
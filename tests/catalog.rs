@@ -0,0 +1,78 @@
+use potatodb::{Database, PartitionScheme, ProtoMessage, ProtoType, COLUMNS_CATALOG, INDEXES_CATALOG, TABLES_CATALOG};
+
+mod common;
+use common::row;
+
+fn rows(db: &Database, sql: &str, columns: &[&str]) -> Vec<Vec<String>> {
+    db.query_sql(sql).unwrap().iter()
+        .map(|r| columns.iter().map(|c| r.data().get(c).cloned().unwrap_or_default()).collect())
+        .collect()
+}
+
+#[test]
+fn catalog_follows_ddl() {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE users (name_upper GENERATED ALWAYS AS (name || '!'))").unwrap();
+    db.execute_sql("INSERT INTO users (name, age) VALUES ('ann', 30)").unwrap();
+    db.execute_sql("CREATE TEMP TABLE scratch").unwrap();
+
+    let sql = format!("SELECT * FROM {}", TABLES_CATALOG);
+    assert_eq!(rows(&db, &sql, &["name", "kind", "rows"]), [["scratch", "temporary", "0"], ["users", "table", "1"]]);
+    let sql = format!("SELECT * FROM {} WHERE table = users", COLUMNS_CATALOG);
+    assert_eq!(rows(&db, &sql, &["column", "position", "type", "generated"]), [
+        ["age", "1", "text", ""],
+        ["name", "2", "text", ""],
+        ["name_upper", "3", "text", "virtual"],
+    ]);
+
+    db.create_table("points".to_string()).unwrap();
+    let sql = format!("SELECT name FROM {}", TABLES_CATALOG);
+    assert_eq!(rows(&db, &sql, &["name"]), [["points"], ["scratch"], ["users"]]);
+}
+
+#[test]
+fn catalog_lists_types_and_indexes() {
+    let mut db = Database::new();
+    db.create_table("events".to_string()).unwrap();
+    let message = ProtoMessage::new("Event").field("kind", 1, ProtoType::String).unwrap().field("at", 2, ProtoType::Int64).unwrap();
+    db.set_table_proto("events", message).unwrap();
+    db.partition_table("events", PartitionScheme::Hash { column: "kind".to_string(), partitions: 4 }).unwrap();
+    db.insert("events", 1, row(&[("kind", "click"), ("at", "5")])).unwrap();
+
+    let sql = format!("SELECT * FROM {} WHERE table = events", COLUMNS_CATALOG);
+    assert_eq!(rows(&db, &sql, &["column", "type"]), [["kind", "string"], ["at", "int64"]]);
+    let sql = format!("SELECT * FROM {}", INDEXES_CATALOG);
+    assert_eq!(rows(&db, &sql, &["table", "column", "kind"]), [["events", "id", "primary"], ["events", "kind", "hash"]]);
+}
+
+#[test]
+fn catalog_cannot_be_written() {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    let sql = format!("INSERT INTO {} (name) VALUES ('x')", TABLES_CATALOG);
+    assert!(db.execute_sql(&sql).unwrap_err().contains("read-only"));
+    let sql = format!("DELETE FROM {} WHERE name = users", TABLES_CATALOG);
+    assert!(db.execute_sql(&sql).unwrap_err().contains("read-only"));
+    assert!(db.create_table(COLUMNS_CATALOG.to_string()).unwrap_err().contains("reserved"));
+    assert!(db.execute_sql("CREATE TABLE __mine").unwrap_err().contains("reserved"));
+    assert!(db.insert(TABLES_CATALOG, 1, row(&[("name", "x")])).is_err());
+}
+
+#[test]
+fn row_counts_follow_row_policies() {
+    let mut db = Database::new();
+    db.create_table("docs".to_string()).unwrap();
+    for (id, tenant) in [(1, "a"), (2, "a"), (3, "b")] {
+        db.insert("docs", id, row(&[("tenant", tenant)])).unwrap();
+    }
+    db.add_policy("docs", "tenant = $tenant").unwrap();
+    let sql = format!("SELECT * FROM {}", TABLES_CATALOG);
+    assert_eq!(rows(&db, &sql, &["name", "rows"]), [["docs", ""]]);
+
+    db.set_session_attribute("tenant", "b");
+    assert_eq!(rows(&db, &sql, &["name", "rows"]), [["docs", "1"]]);
+    db.set_session_attribute("tenant", "a");
+    assert_eq!(rows(&db, &sql, &["name", "rows"]), [["docs", "2"]]);
+    db.clear_policies("docs");
+    assert_eq!(rows(&db, &sql, &["name", "rows"]), [["docs", "3"]]);
+}
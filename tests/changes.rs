@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use potatodb::{ChangeKind, Database};
+
+fn summary(db: &Database, since: u64) -> Vec<(u64, ChangeKind, u64)> {
+    db.changes_since(since).map(|e| (e.seq(), e.kind(), e.record().id())).collect()
+}
+
+#[test]
+fn mutations_are_logged_in_order() {
+    let mut db = Database::new();
+    db.create_table("t".to_string()).unwrap();
+    db.insert("t", 5, HashMap::from([("k".to_string(), "a".to_string())])).unwrap();
+    db.execute_sql("INSERT INTO t (k) VALUES (b)").unwrap();
+    db.execute_sql("UPDATE t SET k = c WHERE k = a").unwrap();
+    db.delete("t", 5).unwrap();
+
+    assert_eq!(summary(&db, 0), vec![
+        (1, ChangeKind::Insert, 5),
+        (2, ChangeKind::Insert, 2),
+        (3, ChangeKind::Update, 5),
+        (4, ChangeKind::Delete, 5),
+    ]);
+    assert_eq!(db.changes_since(2).next().unwrap().record().data()["k"], "c");
+    assert_eq!(db.last_change_seq(), 4);
+}
+
+#[test]
+fn failed_mutations_are_not_logged() {
+    let mut db = Database::new();
+    db.create_table("t".to_string()).unwrap();
+    db.insert("t", 1, HashMap::new()).unwrap();
+    assert!(db.insert("t", 1, HashMap::new()).is_err());
+    assert!(db.delete("t", 9).is_err());
+    assert_eq!(db.last_change_seq(), 1);
+}
+
+#[test]
+fn discarding_keeps_sequence_numbers_monotonic() {
+    let mut db = Database::new();
+    db.create_table("t".to_string()).unwrap();
+    for id in 1..=3 {
+        db.insert("t", id, HashMap::new()).unwrap();
+    }
+    db.discard_changes_through(2);
+    assert_eq!(summary(&db, 0), vec![(3, ChangeKind::Insert, 3)]);
+
+    db.insert("t", 4, HashMap::new()).unwrap();
+    assert_eq!(summary(&db, 3), vec![(4, ChangeKind::Insert, 4)]);
+}
+
+#[test]
+fn the_log_keeps_the_newest_changes() {
+    let mut db = Database::new();
+    db.create_table("t".to_string()).unwrap();
+    for id in 1..=10_002 {
+        db.insert("t", id, HashMap::new()).unwrap();
+    }
+    assert_eq!(db.changes_since(0).count(), 10_000);
+    assert_eq!(db.changes_since(0).next().unwrap().seq(), 3);
+
+    db.set_change_retention(Some(2));
+    assert_eq!(summary(&db, 0), vec![(10_001, ChangeKind::Insert, 10_001), (10_002, ChangeKind::Insert, 10_002)]);
+    db.set_change_retention(None);
+    for id in 10_003..=10_005 {
+        db.insert("t", id, HashMap::new()).unwrap();
+    }
+    assert_eq!(db.changes_since(0).count(), 5);
+    assert_eq!(db.last_change_seq(), 10_005);
+}
+
+#[test]
+fn readers_can_tell_when_changes_were_dropped() {
+    let mut db = Database::new();
+    db.create_table("t".to_string()).unwrap();
+    db.set_change_retention(Some(2));
+    for id in 1..=5 {
+        db.insert("t", id, HashMap::new()).unwrap();
+    }
+    assert!(!db.changes_complete_after(0));
+    assert!(!db.changes_complete_after(2));
+    assert!(db.changes_complete_after(3));
+    assert!(db.changes_complete_after(5));
+}
+
+#[test]
+fn sequence_numbers_carry_on_after_a_reload() {
+    let mut db = Database::new();
+    db.create_table("t".to_string()).unwrap();
+    for id in 1..=5 {
+        db.insert("t", id, HashMap::new()).unwrap();
+    }
+    let mut db = Database::from_bytes(&db.to_bytes().unwrap()).unwrap();
+    assert_eq!(db.last_change_seq(), 5);
+    assert_eq!(db.changes_since(0).count(), 0);
+
+    db.insert("t", 6, HashMap::new()).unwrap();
+    assert_eq!(db.last_change_seq(), 6);
+    assert_eq!(summary(&db, 5), vec![(6, ChangeKind::Insert, 6)]);
+}
+
+#[test]
+fn subscribers_receive_matching_events() {
+    let mut db = Database::new();
+    db.create_table("t".to_string()).unwrap();
+    db.create_table("other".to_string()).unwrap();
+    let deletes = db.subscribe("t", |e| e.kind() == ChangeKind::Delete);
+    let everything = db.subscribe("t", |_| true);
+
+    db.insert("t", 1, HashMap::new()).unwrap();
+    db.insert("other", 1, HashMap::new()).unwrap();
+    db.delete("t", 1).unwrap();
+
+    assert_eq!(deletes.try_iter().map(|e| e.seq()).collect::<Vec<_>>(), vec![3]);
+    assert_eq!(everything.try_iter().map(|e| e.seq()).collect::<Vec<_>>(), vec![1, 3]);
+
+    drop(everything);
+    db.insert("t", 2, HashMap::new()).unwrap();
+    assert!(deletes.try_recv().is_err());
+}
@@ -0,0 +1,87 @@
+use potatodb::{erased_serde, BincodeCodec, Codec, Database};
+
+mod common;
+use common::row;
+
+fn orders() -> Database {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE orders (item TEXT, status ENUM('pending', 'shipped'), double GENERATED ALWAYS AS (qty * 2) STORED)").unwrap();
+    db.insert("orders", 1, row(&[("item", "tea"), ("status", "pending"), ("qty", "3")])).unwrap();
+    db.insert("orders", 2, row(&[("item", "jam"), ("status", "shipped"), ("qty", "1")])).unwrap();
+    db.set_columnar("orders", true).unwrap();
+    db
+}
+
+fn assert_orders(db: &Database) {
+    let shipped = db.query_sql("SELECT item, double FROM orders WHERE status = shipped").unwrap();
+    assert_eq!(shipped.len(), 1);
+    assert_eq!(shipped[0].data().to_map(), row(&[("item", "jam"), ("double", "2")]));
+    assert_eq!(db.get("orders", 1).unwrap().unwrap().data()["status"], "pending");
+}
+
+// bincode with every byte inverted, as a stand-in for a codec the crate
+// does not know
+struct Inverted;
+
+impl Codec for Inverted {
+    fn name(&self) -> &str {
+        "inverted"
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Vec<u8>, String> {
+        Ok(BincodeCodec.encode(value)?.iter().map(|b| !b).collect())
+    }
+
+    fn decode(&self, bytes: &[u8], read: &mut dyn FnMut(&mut dyn erased_serde::Deserializer) -> Result<(), erased_serde::Error>) -> Result<(), String> {
+        BincodeCodec.decode(&bytes.iter().map(|b| !b).collect::<Vec<_>>(), read)
+    }
+}
+
+#[test]
+fn bincode_is_the_default() {
+    let db = orders();
+    assert_eq!(db.codec_name(), "bincode");
+    let loaded = Database::from_bytes(&db.to_bytes().unwrap()).unwrap();
+    assert_eq!(loaded.codec_name(), "bincode");
+    assert_orders(&loaded);
+}
+
+#[test]
+fn custom_codecs_are_needed_to_load_their_files() {
+    let mut db = orders();
+    db.set_codec(Inverted).unwrap();
+    let bytes = db.to_bytes().unwrap();
+    let error = Database::from_bytes(&bytes).err().unwrap().to_string();
+    assert_eq!(error, "Database was saved with codec 'inverted', which is not available");
+
+    let loaded = Database::from_bytes_with_codec(&bytes, Inverted).unwrap();
+    assert_orders(&loaded);
+    // the loaded copy saves with the codec it was loaded with
+    assert_eq!(loaded.codec_name(), "inverted");
+    let resaved = loaded.to_bytes().unwrap();
+    assert!(Database::from_bytes(&resaved).is_err());
+    assert_orders(&Database::from_bytes_with_codec(&resaved, Inverted).unwrap());
+
+    // files of other codecs still load when a custom one is given
+    assert_orders(&Database::from_bytes_with_codec(&orders().to_bytes().unwrap(), Inverted).unwrap());
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn cbor_round_trips() {
+    let mut db = orders();
+    db.set_codec(potatodb::CborCodec).unwrap();
+    let loaded = Database::from_bytes(&db.to_bytes().unwrap()).unwrap();
+    assert_eq!(loaded.codec_name(), "cbor");
+    assert_orders(&loaded);
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn message_pack_round_trips() {
+    let mut db = orders();
+    db.set_codec(potatodb::MessagePackCodec).unwrap();
+    let loaded = Database::from_bytes(&db.to_bytes().unwrap()).unwrap();
+    assert_eq!(loaded.codec_name(), "msgpack");
+    assert_orders(&loaded);
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use potatodb::{Database, Mask, Privilege, Record};
+
+mod common;
+use common::{row, temp_path};
+
+fn orders() -> Database {
+    let mut db = Database::new();
+    db.create_table("orders".to_string()).unwrap();
+    db.insert("orders", 1, row(&[("user", "ann"), ("total", "10")])).unwrap();
+    db.insert("orders", 2, row(&[("user", "bob"), ("total", "5")])).unwrap();
+    db.insert("orders", 3, row(&[("user", "ann"), ("total", "7.5")])).unwrap();
+    db.insert("orders", 4, row(&[("total", "100")])).unwrap();
+    db
+}
+
+fn spent(db: &Database) -> Vec<Record> {
+    db.aggregate("orders")
+        .group("user", &[("spent", "sum(total)"), ("orders", "count"), ("largest", "max(total)"), ("median", "median(total)")])
+        .run()
+        .unwrap()
+}
+
+fn data(rows: &[Record]) -> Vec<(u64, HashMap<String, String>)> {
+    rows.iter().map(|r| (r.id(), r.data().to_map())).collect()
+}
+
+#[test]
+fn columnar_tables_aggregate_like_row_tables() {
+    let mut db = orders();
+    let by_rows = spent(&db);
+    let all_by_rows = db.aggregate("orders").group_all(&[("n", "count"), ("average", "avg(total)")]).run().unwrap();
+    db.set_columnar("orders", true).unwrap();
+    assert_eq!(data(&spent(&db)), data(&by_rows));
+    assert_eq!(data(&db.aggregate("orders").group_all(&[("n", "count"), ("average", "avg(total)")]).run().unwrap()), data(&all_by_rows));
+    assert_eq!(spent(&db)[0].data()["spent"], "17.5");
+
+    // later stages run on the grouped rows
+    let top = db.aggregate("orders").group("user", &[("spent", "sum(total)")]).limit(1).run().unwrap();
+    assert_eq!(top.len(), 1);
+    assert!(db.set_columnar("missing", true).is_err());
+}
+
+#[test]
+fn writes_are_seen_by_the_next_read() {
+    let mut db = orders();
+    db.set_columnar("orders", true).unwrap();
+    assert_eq!(spent(&db)[1].data()["spent"], "5");
+    db.update("orders", 2, row(&[("user", "bob"), ("total", "6")])).unwrap();
+    db.execute_sql("INSERT INTO orders (user, total) VALUES (bob, 4)").unwrap();
+    db.delete("orders", 1).unwrap();
+    let rows = spent(&db);
+    assert_eq!(rows[0].data()["user"], "bob");
+    assert_eq!(rows[0].data()["spent"], "10");
+    assert_eq!(rows[1].data()["spent"], "7.5");
+}
+
+#[test]
+fn selects_of_named_columns_read_the_columns() {
+    let mut db = orders();
+    db.add_generated_column("orders", "taxed GENERATED ALWAYS AS (total * 2) VIRTUAL").unwrap();
+    let by_rows = db.query_sql("SELECT user, taxed FROM orders").unwrap();
+    db.set_columnar("orders", true).unwrap();
+    let by_columns = db.query_sql("SELECT user, taxed FROM orders").unwrap();
+    assert_eq!(data(&by_columns), data(&by_rows));
+    assert_eq!(by_columns[0].data()["taxed"], "20");
+
+    let plan = db.query_sql("EXPLAIN SELECT user FROM orders").unwrap();
+    assert_eq!(plan[0].data()["detail"], "column scan of orders (4 rows)");
+    let plan = db.query_sql("EXPLAIN SELECT user FROM orders WHERE user = ann").unwrap();
+    assert_eq!(plan[0].data()["detail"], "full scan of orders (4 rows)");
+}
+
+#[test]
+fn sessions_still_see_masked_and_filtered_rows() {
+    let mut db = orders();
+    db.set_columnar("orders", true).unwrap();
+    db.mask_column("orders", "user", Mask::Redact);
+    db.create_role("analyst").unwrap();
+    db.grant("analyst", "orders", &[Privilege::Select]).unwrap();
+    db.create_user("ada", &["analyst"]).unwrap();
+    db.set_session_user(Some("ada")).unwrap();
+    assert_eq!(db.query_sql("SELECT user FROM orders").unwrap()[0].data()["user"], "****");
+    assert_eq!(spent(&db)[0].data()["user"], "****");
+
+    db.unmask_column("orders", "user");
+    db.add_policy("orders", "user = ann").unwrap();
+    assert_eq!(db.query_sql("SELECT total FROM orders").unwrap().len(), 2);
+    assert_eq!(spent(&db).len(), 1);
+}
+
+#[test]
+fn the_setting_is_saved() {
+    let path = temp_path("columnar", "bin");
+    let mut db = orders();
+    db.set_columnar("orders", true).unwrap();
+    db.save(path.to_str().unwrap()).unwrap();
+    let loaded = Database::load(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let plan = loaded.query_sql("EXPLAIN SELECT total FROM orders").unwrap();
+    assert_eq!(plan[0].data()["detail"], "column scan of orders (4 rows)");
+    assert_eq!(spent(&loaded)[0].data()["spent"], "17.5");
+}
@@ -0,0 +1,14 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+pub fn row(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+/// A scratch file for `name`, kept apart from other test processes.
+pub fn temp_path(name: &str, ext: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("potatodb-{}-{}.{}", name, std::process::id(), ext))
+}
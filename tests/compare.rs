@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use potatodb::{CompareAs, Database, PartitionScheme, SortOrder};
+
+fn legacy() -> Database {
+    let mut db = Database::new();
+    db.create_table("files".to_string()).unwrap();
+    let rows = [
+        ("file10", "10", "2024/03/01"),
+        ("file2", "9.5", "2023-12-31 23:00:00"),
+        ("file1", "100", "2024-01-15"),
+        ("notes", "n/a", "someday"),
+    ];
+    for (id, (name, size, modified)) in (1..).zip(rows) {
+        let data = HashMap::from([
+            ("name".to_string(), name.to_string()),
+            ("size".to_string(), size.to_string()),
+            ("modified".to_string(), modified.to_string()),
+        ]);
+        db.insert("files", id, data).unwrap();
+    }
+    db
+}
+
+fn ids(db: &Database, sql: &str) -> Vec<u64> {
+    db.query_sql(sql).unwrap().iter().map(|r| r.id()).collect()
+}
+
+fn sorted(db: &Database, column: &str) -> Vec<u64> {
+    db.aggregate("files").sort(column, SortOrder::Ascending).run().unwrap().iter().map(|r| r.id()).collect()
+}
+
+#[test]
+fn columns_compare_as_values_by_default() {
+    let db = legacy();
+    assert_eq!(db.compare_as("files", "size").unwrap(), CompareAs::Value);
+    assert_eq!(ids(&db, "SELECT * FROM files WHERE modified > 2024-01-01"), [1, 3, 4]);
+    // numbers compare as numbers, and not with text
+    assert_eq!(ids(&db, "SELECT * FROM files WHERE size > 9.75"), [1, 3]);
+    assert_eq!(ids(&db, "SELECT * FROM files WHERE size != 10"), [2, 3, 4]);
+    assert_eq!(ids(&db, "SELECT * FROM files WHERE size = 10.0"), [1]);
+    assert_eq!(sorted(&db, "name"), [3, 1, 2, 4]);
+}
+
+#[test]
+fn numeric_columns_compare_as_numbers() {
+    let mut db = legacy();
+    db.set_compare_as("files", "size", CompareAs::Numeric).unwrap();
+    assert_eq!(ids(&db, "SELECT * FROM files WHERE size > 9.75"), [1, 3]);
+    assert_eq!(ids(&db, "SELECT * FROM files WHERE size = 10.0"), [1]);
+    assert_eq!(ids(&db, "SELECT * FROM files WHERE size != 10"), [2, 3, 4]);
+    assert_eq!(ids(&db, "SELECT * FROM files WHERE size BETWEEN 9 AND 10"), [1, 2]);
+    assert_eq!(sorted(&db, "size"), [2, 1, 3, 4]);
+}
+
+#[test]
+fn date_columns_compare_as_dates() {
+    let mut db = legacy();
+    db.set_compare_as("files", "modified", CompareAs::Date).unwrap();
+    assert_eq!(ids(&db, "SELECT * FROM files WHERE modified > 2024-01-01"), [1, 3]);
+    assert_eq!(ids(&db, "SELECT * FROM files WHERE modified < 2024-01-01T00:00:00Z"), [2]);
+    assert_eq!(sorted(&db, "modified"), [2, 3, 1, 4]);
+}
+
+#[test]
+fn natural_columns_compare_digits_as_numbers() {
+    let mut db = legacy();
+    db.set_compare_as("files", "name", CompareAs::Natural).unwrap();
+    assert_eq!(sorted(&db, "name"), [3, 2, 1, 4]);
+    assert_eq!(ids(&db, "SELECT * FROM files WHERE name < file3"), [2, 3]);
+}
+
+#[test]
+fn settings_are_saved_and_reset() {
+    let mut db = legacy();
+    db.set_compare_as("files", "size", CompareAs::Numeric).unwrap();
+    let mut loaded = Database::from_bytes(&db.to_bytes().unwrap()).unwrap();
+    assert_eq!(loaded.compare_as("files", "size").unwrap(), CompareAs::Numeric);
+    loaded.set_compare_as("files", "size", CompareAs::String).unwrap();
+    assert_eq!(ids(&loaded, "SELECT * FROM files WHERE size > 9.75"), [4]);
+    assert!(loaded.set_compare_as("missing", "size", CompareAs::Date).is_err());
+}
+
+#[test]
+fn partition_columns_compare_as_text() {
+    let mut db = legacy();
+    db.set_compare_as("files", "size", CompareAs::Numeric).unwrap();
+    assert!(db.partition_table("files", PartitionScheme::Hash { column: "size".to_string(), partitions: 2 }).is_err());
+    db.partition_table("files", PartitionScheme::Hash { column: "name".to_string(), partitions: 2 }).unwrap();
+    assert!(db.set_compare_as("files", "name", CompareAs::Natural).is_err());
+}
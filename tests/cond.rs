@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use potatodb::{Cond, Database, Value};
+
+fn people() -> Database {
+    let mut db = Database::new();
+    db.create_table("people".to_string()).unwrap();
+    for (id, name, age) in [(1, "Ann", "30"), (2, "Abe", "100"), (3, "Bob", "30"), (4, "Al", "9")] {
+        let data = HashMap::from([("name".to_string(), name.to_string()), ("age".to_string(), age.to_string())]);
+        db.insert("people", id, data).unwrap();
+    }
+    db
+}
+
+fn ids(records: Vec<potatodb::Record>) -> Vec<u64> {
+    records.iter().map(|r| r.id()).collect()
+}
+
+#[test]
+fn conditions_compose() {
+    let db = people();
+    let query = |cond: Cond| ids(db.query_where("people", &cond).unwrap());
+    assert_eq!(query(Cond::eq("age", 30).and(Cond::like("name", "A%"))), [1]);
+    assert_eq!(query(Cond::gt("age", 10)), [1, 2, 3]);
+    assert_eq!(query(Cond::lt("age", 30).or(Cond::eq("name", "Bob"))), [3, 4]);
+    assert_eq!(query(Cond::between("age", 9, 30).and(Cond::ne("name", "Al"))), [1, 3]);
+    assert_eq!(query(Cond::like("name", "_b_")), [2]);
+    assert_eq!(query(Cond::eq("id", 2)), [2]);
+    assert!(db.query_where("missing", &Cond::eq("age", 30)).is_err());
+}
+
+#[test]
+fn conditions_read_as_sql() {
+    let cond = Cond::eq("age", 30).and(Cond::like("name", "A%").or(Cond::gt("score", 2.5)));
+    assert_eq!(cond.to_string(), "(age = 30 AND (name LIKE A% OR score > 2.5))");
+
+    // and match what the same SQL matches
+    let db = people();
+    assert_eq!(ids(db.query_sql("SELECT * FROM people WHERE name LIKE A% AND age > 9").unwrap()), [1, 2]);
+    assert_eq!(ids(db.query_where("people", &Cond::like("name", "A%").and(Cond::gt("age", 9))).unwrap()), [1, 2]);
+
+    // the text is SQL the parser takes back
+    let cond = Cond::lt("age", 50).and(Cond::eq("name", "Bob").or(Cond::like("name", "A%")));
+    let sql = format!("SELECT * FROM people WHERE {}", cond);
+    assert_eq!(ids(db.query_sql(&sql).unwrap()), ids(db.query_where("people", &cond).unwrap()));
+    assert_eq!(ids(db.query_sql(&sql).unwrap()), [1, 3, 4]);
+}
+
+#[test]
+fn null_matches_missing_columns() {
+    let mut db = people();
+    db.insert("people", 5, HashMap::from([("name".to_string(), "Cy".to_string())])).unwrap();
+    let query = |cond: Cond| ids(db.query_where("people", &cond).unwrap());
+    assert_eq!(Cond::eq("age", Value::Null).to_string(), "age IS NULL");
+    assert_eq!(query(Cond::eq("age", Value::Null)), [5]);
+    assert_eq!(query(Cond::ne("age", Value::Null)), [1, 2, 3, 4]);
+    assert_eq!(query(Cond::gt("age", Value::Null)), [] as [u64; 0]);
+    assert_eq!(query(Cond::between("age", 1, Value::Null)), [] as [u64; 0]);
+    assert_eq!(ids(db.query_sql("SELECT * FROM people WHERE age IS NULL").unwrap()), [5]);
+    assert_eq!(ids(db.query_sql("SELECT * FROM people WHERE age IS NOT NULL AND name LIKE A%").unwrap()), [1, 2, 4]);
+
+    assert_eq!(ids(db.delete_where("people", &Cond::eq("age", Value::Null)).unwrap()), [5]);
+    assert_eq!(db.get_all("people").unwrap().len(), 4);
+}
+
+#[test]
+fn like_matches_patterns() {
+    let mut db = people();
+    let query = |db: &Database, pattern: &str| ids(db.query_where("people", &Cond::like("name", pattern)).unwrap());
+    assert_eq!(query(&db, "%"), [1, 2, 3, 4]);
+    assert_eq!(query(&db, "A%e"), [2]);
+    assert_eq!(query(&db, "%n%"), [1]);
+    assert_eq!(query(&db, "A_"), [4]);
+    assert_eq!(query(&db, "a%"), [] as [u64; 0]);
+    db.set_setting("case_sensitive", "false").unwrap();
+    assert_eq!(query(&db, "a%"), [1, 2, 4]);
+}
+
+#[test]
+fn updates_and_deletes_take_conditions() {
+    let mut db = people();
+    let updated = db.update_where("people", &Cond::like("name", "A%").and(Cond::lt("age", 50)), "age", 31).unwrap();
+    assert_eq!(ids(updated), [1, 4]);
+    assert_eq!(db.get("people", 4).unwrap().unwrap().data()["age"], "31");
+
+    assert_eq!(ids(db.delete_where("people", &Cond::eq("age", 31)).unwrap()), [1, 4]);
+    assert_eq!(db.get_all("people").unwrap().len(), 2);
+}
@@ -0,0 +1,54 @@
+use potatodb::{CopyFormat, Database};
+
+mod common;
+use common::row;
+
+fn products() -> Database {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE products (name TEXT, size ENUM('small', 'large'), double GENERATED ALWAYS AS (price * 2) STORED)").unwrap();
+    db
+}
+
+#[test]
+fn csv_rows_are_loaded_in_order() {
+    let mut db = products();
+    let csv = "name,size,price\ntea,small,3\n\"jam, \"\"fig\"\"\",large,\n\"two\nlines\",,1\n";
+    assert_eq!(db.copy_in("products", csv.as_bytes(), CopyFormat::Csv).unwrap(), 3);
+    assert_eq!(db.get("products", 1).unwrap().unwrap().data().to_map(), row(&[("name", "tea"), ("size", "small"), ("price", "3"), ("double", "6")]));
+    assert_eq!(db.get("products", 2).unwrap().unwrap().data().to_map(), row(&[("name", "jam, \"fig\""), ("size", "large")]));
+    assert_eq!(db.get("products", 3).unwrap().unwrap().data()["name"], "two\nlines");
+    assert_eq!(db.query_sql("SELECT name FROM products WHERE size = large").unwrap().len(), 1);
+}
+
+#[test]
+fn text_rows_are_unescaped() {
+    let mut db = products();
+    db.insert("products", 7, row(&[("name", "old")])).unwrap();
+    let text = "name\tsize\r\nback\\\\slash\t\\N\r\ntab\\there\tsmall\r\n";
+    assert_eq!(db.copy_in("products", text.as_bytes(), CopyFormat::Text).unwrap(), 2);
+    assert_eq!(db.get("products", 8).unwrap().unwrap().data().to_map(), row(&[("name", "back\\slash")]));
+    assert_eq!(db.get("products", 9).unwrap().unwrap().data().to_map(), row(&[("name", "tab\there"), ("size", "small")]));
+}
+
+#[test]
+fn a_bad_row_loads_nothing() {
+    let mut db = products();
+    let bad_enum = "name,size\ntea,small\njam,huge\n";
+    assert!(db.copy_in("products", bad_enum.as_bytes(), CopyFormat::Csv).is_err());
+    let short_row = "name,size\ntea,small\njam\n";
+    assert!(db.copy_in("products", short_row.as_bytes(), CopyFormat::Csv).is_err());
+    let generated = "name,double\ntea,6\n";
+    assert!(db.copy_in("products", generated.as_bytes(), CopyFormat::Csv).is_err());
+    assert!(db.copy_in("products", "name\n\"open\n".as_bytes(), CopyFormat::Csv).is_err());
+    assert!(db.copy_in("products", "name\nbad\\q\n".as_bytes(), CopyFormat::Text).is_err());
+    assert!(db.copy_in("missing", "name\n".as_bytes(), CopyFormat::Csv).is_err());
+    assert!(db.get_all("products").unwrap().is_empty());
+}
+
+#[test]
+fn loads_are_recorded_as_inserts() {
+    let mut db = products();
+    let events = db.subscribe("products", |_| true);
+    db.copy_in("products", "name\na\nb\n".as_bytes(), CopyFormat::Csv).unwrap();
+    assert_eq!(events.try_iter().count(), 2);
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "sql")]
+
+use std::collections::HashMap;
+
+use potatodb::{Database, PartitionScheme};
+
+fn events() -> Database {
+    let mut db = Database::new();
+    db.create_table("events".to_string()).unwrap();
+    for id in 1..=100u64 {
+        let day = (id % 4).to_string();
+        db.insert("events", id, HashMap::from([("day".to_string(), day)])).unwrap();
+    }
+    db
+}
+
+fn count(db: &Database, sql: &str) -> String {
+    let rows = db.query_sql(sql).unwrap();
+    assert_eq!(rows.len(), 1);
+    let value = rows[0].data().values().next().unwrap().clone();
+    value
+}
+
+fn column(rows: &[potatodb::Record], name: &str) -> Vec<String> {
+    rows.iter().map(|r| r.data().get(name).cloned().unwrap_or_default()).collect()
+}
+
+#[test]
+fn count_star_returns_one_row() {
+    let db = events();
+    let rows = db.query_sql("SELECT COUNT(*) FROM events").unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].data().to_map(), HashMap::from([("COUNT(*)".to_string(), "100".to_string())]));
+    assert_eq!(count(&db, "SELECT count(*) FROM events WHERE day = 2"), "25");
+    assert_eq!(count(&db, "SELECT COUNT(*) FROM events WHERE day = 9"), "0");
+    assert!(db.query_sql("SELECT COUNT(*) FROM missing").is_err());
+}
+
+#[test]
+fn unfiltered_counts_come_from_the_row_count() {
+    let db = events();
+    let plan = db.query_sql("EXPLAIN SELECT COUNT(*) FROM events").unwrap();
+    assert_eq!(column(&plan, "node"), ["count"]);
+    assert_eq!(column(&plan, "detail"), ["row count of events"]);
+    let plan = db.query_sql("EXPLAIN SELECT COUNT(*) FROM events WHERE day = 1").unwrap();
+    assert_eq!(column(&plan, "node"), ["scan", "filter", "count"]);
+}
+
+#[test]
+fn counts_on_the_partition_column_read_one_partition() {
+    let mut db = events();
+    db.partition_table("events", PartitionScheme::Hash { column: "day".to_string(), partitions: 4 }).unwrap();
+    assert_eq!(count(&db, "SELECT COUNT(*) FROM events WHERE day = 3"), "25");
+    let plan = db.query_sql("EXPLAIN ANALYZE SELECT COUNT(*) FROM events WHERE day = 3").unwrap();
+    assert!(plan[0].data()["detail"].starts_with("scan of events partitions"));
+    assert_eq!(column(&plan, "actual_rows").last().unwrap(), "1");
+}
+
+#[test]
+fn counts_respect_deletes_and_samples() {
+    let mut db = events();
+    db.execute_sql("DELETE FROM events WHERE day = 0").unwrap();
+    assert_eq!(count(&db, "SELECT COUNT(*) FROM events"), "75");
+    assert_eq!(count(&db, "SELECT COUNT(*) FROM events TABLESAMPLE (10 ROWS)"), "10");
+}
@@ -0,0 +1,83 @@
+use potatodb::Database;
+
+mod common;
+use common::row;
+
+fn pages() -> Database {
+    let mut db = Database::new();
+    db.create_table("pages".to_string()).unwrap();
+    db.insert("pages", 1, row(&[("path", "/"), ("hits", "41"), ("score", "1.5")])).unwrap();
+    db.insert("pages", 2, row(&[("path", "/about"), ("hits", "7")])).unwrap();
+    db.insert("pages", 3, row(&[("path", "/new")])).unwrap();
+    db
+}
+
+fn value(db: &Database, id: u64, column: &str) -> Option<String> {
+    db.get("pages", id).unwrap().unwrap().data().get(column).cloned()
+}
+
+#[test]
+fn increment_adds_in_one_step() {
+    let mut db = pages();
+    assert_eq!(db.increment("pages", 1, "hits", 1.0).unwrap(), "42");
+    assert_eq!(db.increment("pages", 1, "score", 0.25).unwrap(), "1.75");
+    assert_eq!(db.increment("pages", 2, "hits", -10.0).unwrap(), "-3");
+    // counters start from 0
+    assert_eq!(db.increment("pages", 3, "hits", 1.0).unwrap(), "1");
+    assert_eq!(value(&db, 1, "hits").as_deref(), Some("42"));
+
+    assert!(db.increment("pages", 1, "path", 1.0).is_err());
+    assert!(db.increment("pages", 9, "hits", 1.0).is_err());
+    assert!(db.increment("missing", 1, "hits", 1.0).is_err());
+}
+
+#[test]
+fn sql_updates_can_add_to_a_column() {
+    let mut db = pages();
+    let updated = db.execute_sql("UPDATE pages SET hits = hits + 1").unwrap();
+    assert_eq!(updated.len(), 2);
+    assert_eq!(value(&db, 1, "hits").as_deref(), Some("42"));
+    assert_eq!(value(&db, 2, "hits").as_deref(), Some("8"));
+    // like other updates, rows without the column are left alone
+    assert_eq!(value(&db, 3, "hits"), None);
+
+    db.execute_sql("UPDATE pages SET hits = hits - 2 WHERE path = /about").unwrap();
+    assert_eq!(value(&db, 2, "hits").as_deref(), Some("6"));
+    db.execute_sql("UPDATE pages SET score = score + 0.5 WHERE path = /").unwrap();
+    assert_eq!(value(&db, 1, "score").as_deref(), Some("2"));
+
+    assert!(db.execute_sql("UPDATE pages SET hits = hits + many").is_err());
+}
+
+#[test]
+fn misread_updates_write_nothing() {
+    let mut db = pages();
+    assert_eq!(db.execute_sql("UPDATE pages SET hits = score + 1 WHERE id = 1").unwrap_err(), "UPDATE can only add to the column it sets, not 'score'");
+    assert!(db.execute_sql("UPDATE pages SET hits = hits +").is_err());
+    assert_eq!(db.execute_sql("UPDATE pages SET hits = 5 6").unwrap_err(), "Unexpected '6' in UPDATE statement");
+    assert_eq!(db.execute_sql("UPDATE pages SET hits = hits + 1 2").unwrap_err(), "Unexpected '2' in UPDATE statement");
+    assert_eq!(value(&db, 1, "hits").as_deref(), Some("41"));
+    assert_eq!(value(&db, 2, "hits").as_deref(), Some("7"));
+}
+
+#[test]
+fn a_failing_row_leaves_every_row_unchanged() {
+    let mut db = pages();
+    db.insert("pages", 4, row(&[("path", "/old"), ("hits", "lots")])).unwrap();
+    assert!(db.execute_sql("UPDATE pages SET hits = hits + 1").is_err());
+    assert_eq!(value(&db, 1, "hits").as_deref(), Some("41"));
+    assert_eq!(value(&db, 2, "hits").as_deref(), Some("7"));
+}
+
+#[test]
+fn increments_are_checked_like_updates() {
+    let mut db = pages();
+    db.add_generated_column("pages", "double INTEGER GENERATED ALWAYS AS (hits * 2) STORED").unwrap();
+    db.increment("pages", 2, "hits", 1.0).unwrap();
+    assert_eq!(value(&db, 2, "double").as_deref(), Some("16"));
+    assert!(db.increment("pages", 2, "double", 1.0).is_err());
+
+    db.set_column_enum("pages", "hits", &["41", "42", "8"]).unwrap();
+    db.execute_sql("UPDATE pages SET hits = hits + 1 WHERE path = /").unwrap();
+    assert!(db.execute_sql("UPDATE pages SET hits = hits + 1 WHERE path = /").is_err());
+}
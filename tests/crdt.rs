@@ -0,0 +1,144 @@
+#![cfg(feature = "crdt")]
+
+use std::collections::HashMap;
+
+use potatodb::crdt::{Replica, CONFLICTS_TABLE};
+use potatodb::Database;
+
+mod common;
+use common::row;
+
+fn data(db: &Database, table: &str, id: u64) -> Option<HashMap<String, String>> {
+    db.get(table, id).unwrap().map(|r| r.data().to_map())
+}
+
+#[test]
+fn replicas_converge_after_sync() {
+    let mut a = Replica::new(1);
+    let mut b = Replica::new(2);
+    a.create_table("notes").unwrap();
+    a.insert("notes", 1, row(&[("title", "draft"), ("body", "x")])).unwrap();
+    a.sync_with(&mut b).unwrap();
+    assert_eq!(data(b.database(), "notes", 1), Some(row(&[("title", "draft"), ("body", "x")])));
+
+    // concurrent edits to different fields both survive
+    a.update("notes", 1, row(&[("title", "final"), ("body", "x")])).unwrap();
+    b.update("notes", 1, row(&[("title", "draft"), ("body", "y")])).unwrap();
+    b.insert("notes", 2, row(&[("title", "other")])).unwrap();
+    a.sync_with(&mut b).unwrap();
+
+    for replica in [&a, &b] {
+        let note = data(replica.database(), "notes", 1).unwrap();
+        assert_eq!(note["title"], "final");
+        assert_eq!(note["body"], "y");
+        assert_eq!(data(replica.database(), "notes", 2), Some(row(&[("title", "other")])));
+    }
+    assert_eq!(data(a.database(), "notes", 1), data(b.database(), "notes", 1));
+}
+
+#[test]
+fn concurrent_writes_to_one_field_pick_the_same_winner() {
+    let mut a = Replica::new(1);
+    let mut b = Replica::new(2);
+    a.create_table("t").unwrap();
+    a.insert("t", 1, row(&[("v", "0")])).unwrap();
+    a.sync_with(&mut b).unwrap();
+
+    a.update("t", 1, row(&[("v", "a")])).unwrap();
+    b.update("t", 1, row(&[("v", "b")])).unwrap();
+    let winner = if a.version()[&1] > b.version()[&2] { "a" } else { "b" };
+    b.sync_with(&mut a).unwrap();
+    assert_eq!(data(a.database(), "t", 1).unwrap()["v"], winner);
+    assert_eq!(data(b.database(), "t", 1).unwrap()["v"], winner);
+}
+
+#[test]
+fn deletes_win_over_older_writes_and_lose_to_newer_ones() {
+    let mut a = Replica::new(1);
+    let mut b = Replica::new(2);
+    a.create_table("t").unwrap();
+    a.insert("t", 1, row(&[("v", "0")])).unwrap();
+    a.sync_with(&mut b).unwrap();
+
+    a.delete("t", 1).unwrap();
+    a.sync_with(&mut b).unwrap();
+    assert!(data(b.database(), "t", 1).is_none());
+
+    b.insert("t", 1, row(&[("w", "new")])).unwrap();
+    b.sync_with(&mut a).unwrap();
+    assert_eq!(data(a.database(), "t", 1), Some(row(&[("w", "new")])));
+}
+
+#[test]
+fn ops_relay_through_a_third_replica() {
+    let mut a = Replica::new(1);
+    let mut b = Replica::new(2);
+    let mut c = Replica::new(3);
+    a.create_table("t").unwrap();
+    a.insert("t", 1, row(&[("v", "from a")])).unwrap();
+    a.sync_with(&mut b).unwrap();
+    b.sync_with(&mut c).unwrap();
+    assert_eq!(data(c.database(), "t", 1), Some(row(&[("v", "from a")])));
+
+    // replaying ops that were already merged changes nothing
+    c.apply_ops(a.ops_since(&Default::default())).unwrap();
+    assert_eq!(c.database().get_all("t").unwrap().len(), 1);
+}
+
+fn max_wins(replica: &mut Replica) {
+    replica.set_resolver("stock", |c| {
+        let value = |v: Option<&str>| v.and_then(|v| v.parse::<u64>().ok()).unwrap_or(0);
+        Some(value(c.earlier()).max(value(c.later())).to_string())
+    });
+}
+
+fn conflicts(replica: &Replica) -> Vec<HashMap<String, String>> {
+    replica.query_sql(&format!("SELECT * FROM {}", CONFLICTS_TABLE)).unwrap_or_default()
+        .iter().map(|r| r.data().to_map()).collect()
+}
+
+#[test]
+fn resolvers_decide_concurrent_writes_and_losers_are_kept() {
+    let (mut a, mut b, mut c) = (Replica::new(1), Replica::new(2), Replica::new(3));
+    for replica in [&mut a, &mut b, &mut c] {
+        max_wins(replica);
+    }
+    a.create_table("stock").unwrap();
+    a.insert("stock", 1, row(&[("count", "5"), ("name", "pen")])).unwrap();
+    a.sync_with(&mut b).unwrap();
+
+    // sequential edits are not conflicts
+    b.update("stock", 1, row(&[("count", "6"), ("name", "pen")])).unwrap();
+    b.sync_with(&mut a).unwrap();
+    assert!(conflicts(&a).is_empty());
+
+    a.update("stock", 1, row(&[("count", "9"), ("name", "pen")])).unwrap();
+    b.update("stock", 1, row(&[("count", "7"), ("name", "pen")])).unwrap();
+    a.sync_with(&mut b).unwrap();
+    b.sync_with(&mut c).unwrap();
+
+    for replica in [&a, &b, &c] {
+        assert_eq!(data(replica.database(), "stock", 1).unwrap()["count"], "9");
+    }
+    let lost = row(&[("table", "stock"), ("record", "1"), ("column", "count"), ("replica", "2"), ("value", "7")]);
+    assert_eq!(conflicts(&a), vec![lost.clone()]);
+    assert_eq!(conflicts(&b), vec![lost]);
+    assert!(a.create_table(CONFLICTS_TABLE).is_err());
+}
+
+#[test]
+fn conflicts_without_a_resolver_keep_the_later_write() {
+    let (mut a, mut b) = (Replica::new(1), Replica::new(2));
+    a.create_table("t").unwrap();
+    a.insert("t", 1, row(&[("v", "0")])).unwrap();
+    a.sync_with(&mut b).unwrap();
+
+    a.update("t", 1, row(&[])).unwrap();
+    b.update("t", 1, row(&[("v", "b")])).unwrap();
+    a.sync_with(&mut b).unwrap();
+
+    assert_eq!(data(a.database(), "t", 1), data(b.database(), "t", 1));
+    let kept = data(a.database(), "t", 1).unwrap();
+    let lost = &conflicts(&a)[0];
+    assert_eq!(kept.contains_key("v"), !lost.contains_key("value"));
+}
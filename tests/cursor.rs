@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+
+use potatodb::Database;
+
+fn row(value: &str) -> HashMap<String, String> {
+    HashMap::from([("value".to_string(), value.to_string())])
+}
+
+fn numbers() -> Database {
+    let mut db = Database::new();
+    db.create_table("numbers".to_string()).unwrap();
+    for id in 1..=4 {
+        db.insert("numbers", id, row(&id.to_string())).unwrap();
+    }
+    db
+}
+
+#[test]
+fn cursors_see_writes_made_between_steps() {
+    let mut db = numbers();
+    let mut cursor = db.cursor("numbers").unwrap();
+    assert_eq!(cursor.remaining(), 4);
+    assert_eq!(cursor.next(&db).unwrap().unwrap().id(), 1);
+
+    db.update("numbers", 2, row("two")).unwrap();
+    db.delete("numbers", 3).unwrap();
+    db.insert("numbers", 5, row("5")).unwrap();
+
+    let second = cursor.next(&db).unwrap().unwrap();
+    assert_eq!((second.id(), second.data()["value"].as_str()), (2, "two"));
+    // 3 is gone and 5 came after the cursor opened
+    assert_eq!(cursor.next(&db).unwrap().unwrap().id(), 4);
+    assert!(cursor.next(&db).unwrap().is_none());
+    assert!(cursor.next(&db).unwrap().is_none());
+    assert_eq!(cursor.remaining(), 0);
+}
+
+#[test]
+fn rows_can_be_deleted_while_walking_them() {
+    let mut db = numbers();
+    let mut cursor = db.cursor("numbers").unwrap();
+    while let Some(record) = cursor.next(&db).unwrap() {
+        db.delete("numbers", record.id()).unwrap();
+    }
+    assert!(db.get_all("numbers").unwrap().is_empty());
+}
+
+#[test]
+fn cursors_need_a_table() {
+    assert!(numbers().cursor("missing").is_err());
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use potatodb::Database;
+
+fn row(name: &str) -> HashMap<String, String> {
+    HashMap::from([("name".to_string(), name.to_string())])
+}
+
+fn rows(db: &Database, table: &str) -> Vec<(u64, HashMap<String, String>)> {
+    let sql = format!("SELECT * FROM {}", table);
+    db.query_sql(&sql).unwrap().iter().map(|r| (r.id(), r.data().to_map())).collect()
+}
+
+#[test]
+fn deltas_bring_a_copy_up_to_date() {
+    let mut source = Database::new();
+    source.create_table("users".to_string()).unwrap();
+    source.insert("users", 1, row("ann")).unwrap();
+    source.insert("users", 2, row("bob")).unwrap();
+    let mut copy = Database::from_bytes(&source.to_bytes().unwrap()).unwrap();
+    let since = source.last_change_seq();
+
+    source.update("users", 1, row("anna")).unwrap();
+    source.delete("users", 2).unwrap();
+    source.insert("users", 3, row("cy")).unwrap();
+    source.insert("users", 4, row("temp")).unwrap();
+    source.delete("users", 4).unwrap();
+    source.create_table("orders".to_string()).unwrap();
+    source.insert("orders", 1, row("book")).unwrap();
+
+    let delta = source.export_delta(since).unwrap();
+    let next = copy.apply_delta(&delta).unwrap();
+    assert_eq!(next, source.last_change_seq());
+    for table in ["users", "orders"] {
+        assert_eq!(rows(&copy, table), rows(&source, table));
+    }
+
+    copy.apply_delta(&delta).unwrap();
+    assert_eq!(rows(&copy, "users"), rows(&source, "users"));
+    assert!(copy.apply_delta(&source.export_delta(next).unwrap()).is_ok());
+}
+
+#[test]
+fn repeated_writes_collapse_to_the_final_row() {
+    let mut source = Database::new();
+    source.create_table("t".to_string()).unwrap();
+    source.insert("t", 1, row("a")).unwrap();
+    let one = source.export_delta(0).unwrap().len();
+    for name in ["b", "c", "d", "e", "f"] {
+        source.update("t", 1, row(name)).unwrap();
+    }
+    assert_eq!(source.export_delta(0).unwrap().len(), one);
+}
+
+#[test]
+fn unavailable_or_invalid_deltas_are_rejected() {
+    let mut source = Database::new();
+    source.create_table("t".to_string()).unwrap();
+    source.insert("t", 1, row("a")).unwrap();
+    source.insert("t", 2, row("b")).unwrap();
+    source.discard_changes_through(1);
+    assert!(source.export_delta(0).is_err());
+    assert!(source.export_delta(5).is_err());
+    assert!(source.export_delta(1).is_ok());
+
+    let mut copy = Database::new();
+    assert!(copy.apply_delta(b"not a delta").is_err());
+    assert!(copy.apply_delta(b"POTADLTA\xff").is_err());
+}
@@ -0,0 +1,50 @@
+use potatodb::Database;
+
+mod common;
+use common::{row, temp_path};
+
+fn saved_size(db: &Database, name: &str) -> u64 {
+    let path = temp_path(name, "bin");
+    db.save(path.to_str().unwrap()).unwrap();
+    let size = std::fs::metadata(&path).unwrap().len();
+    std::fs::remove_file(&path).unwrap();
+    size
+}
+
+fn customers(country: impl Fn(u64) -> String) -> Database {
+    let mut db = Database::new();
+    db.create_table("customers".to_string()).unwrap();
+    for id in 1..=500 {
+        db.insert("customers", id, row(&[("country", &country(id)), ("status", "active")])).unwrap();
+    }
+    db
+}
+
+#[test]
+fn repeated_values_are_saved_once() {
+    let codes = ["NL", "DE", "FR", "BE"];
+    let repeated = customers(|id| format!("{}-region", codes[id as usize % 4]));
+    let unique = customers(|id| format!("{:09}", id));
+    assert!(saved_size(&repeated, "dictionary-repeated") * 3 < saved_size(&unique, "dictionary-unique") * 2);
+}
+
+#[test]
+fn dictionary_columns_read_back_as_text() {
+    let path = temp_path("dictionary-roundtrip", "bin");
+    let mut db = Database::new();
+    db.create_table("orders".to_string()).unwrap();
+    for id in 1..=20 {
+        let mut data = row(&[("note", &format!("order {}", id))]);
+        if id % 3 != 0 {
+            data.insert("status".to_string(), if id % 2 == 0 { "paid" } else { "open" }.to_string());
+        }
+        db.insert("orders", id, data).unwrap();
+    }
+    db.save(path.to_str().unwrap()).unwrap();
+    let loaded = Database::load(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    for id in 1..=20 {
+        assert_eq!(loaded.get("orders", id).unwrap().unwrap().data(), db.get("orders", id).unwrap().unwrap().data());
+    }
+    assert_eq!(loaded.query_sql("SELECT * FROM orders WHERE status = paid").unwrap().len(), 7);
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use potatodb::{Database, QueryObserver};
+
+fn accounts() -> Database {
+    let mut db = Database::new();
+    db.create_table("accounts".to_string()).unwrap();
+    for (id, owner, balance) in [(1, "ann", Some("0")), (2, "bob", Some("25")), (3, "cy", None), (4, "di", Some("0"))] {
+        let mut data = HashMap::from([("owner".to_string(), owner.to_string())]);
+        if let Some(balance) = balance {
+            data.insert("balance".to_string(), balance.to_string());
+        }
+        db.insert("accounts", id, data).unwrap();
+    }
+    db
+}
+
+fn ids(rows: &[potatodb::Record]) -> Vec<u64> {
+    rows.iter().map(|r| r.id()).collect()
+}
+
+#[test]
+fn deletes_list_their_rows_without_deleting() {
+    let db = accounts();
+    let run = db.dry_run("DELETE FROM accounts WHERE balance = 0").unwrap();
+    assert_eq!(ids(run.rows()), [1, 4]);
+    assert_eq!(run.plan(), "full scan of accounts (4 rows)");
+    assert_eq!(db.get_all("accounts").unwrap().len(), 4);
+
+    assert_eq!(db.dry_run("DELETE FROM accounts WHERE id = 2").unwrap().plan(), "lookup of id 2 in accounts");
+}
+
+#[test]
+fn updates_list_the_rows_they_would_change_as_they_are() {
+    let db = accounts();
+    let run = db.dry_run("UPDATE accounts SET balance = 10 WHERE owner != bob").unwrap();
+    // the row without a balance is not updated
+    assert_eq!(ids(run.rows()), [1, 4]);
+    assert_eq!(run.rows()[0].data()["balance"], "0");
+
+    let run = db.dry_run("UPDATE accounts SET balance = balance + 5").unwrap();
+    assert_eq!(ids(run.rows()), [1, 2, 4]);
+    assert_eq!(db.get("accounts", 2).unwrap().unwrap().data()["balance"], "25");
+}
+
+#[test]
+fn statements_that_would_fail_fail() {
+    let mut db = accounts();
+    db.insert("accounts", 5, HashMap::from([("balance".to_string(), "lots".to_string())])).unwrap();
+    assert!(db.dry_run("UPDATE accounts SET balance = balance + 1").is_err());
+    assert!(db.dry_run("DELETE FROM missing WHERE id = 1").is_err());
+    assert_eq!(db.dry_run("SELECT * FROM accounts").unwrap_err(), "Only UPDATE and DELETE statements can be dry run");
+}
+
+struct Renamer;
+
+impl QueryObserver for Renamer {
+    fn rewrite(&self, sql: &str) -> Result<Option<String>, String> {
+        match sql.contains("DROP") {
+            true => Err("no drops".to_string()),
+            false => Ok(Some(sql.replace("old_accounts", "accounts"))),
+        }
+    }
+}
+
+#[test]
+fn observers_rewrite_dry_runs_too() {
+    let mut db = accounts();
+    db.add_query_observer(Arc::new(Renamer));
+    assert_eq!(ids(db.dry_run("DELETE FROM old_accounts WHERE balance = 0").unwrap().rows()), [1, 4]);
+    assert_eq!(db.dry_run("DELETE FROM accounts WHERE owner = DROP").unwrap_err(), "no drops");
+}
@@ -0,0 +1,105 @@
+#![cfg(feature = "encryption")]
+
+use std::collections::HashMap;
+
+use potatodb::Database;
+
+mod common;
+use common::temp_path;
+
+const KEY: [u8; 32] = [7; 32];
+
+fn setup() -> Database {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    let row = HashMap::from([("name".to_string(), "Alice".to_string()), ("email".to_string(), "alice@example.com".to_string())]);
+    db.insert("users", 1, row).unwrap();
+    db.encrypt_column("users", "email", KEY).unwrap();
+    db
+}
+
+fn email(db: &Database) -> String {
+    db.query_sql("SELECT email FROM users").unwrap()[0].data()["email"].clone()
+}
+
+#[test]
+fn values_are_encrypted_until_read_with_the_key() {
+    let mut db = setup();
+    let stored = db.get("users", 1).unwrap().unwrap().data()["email"].clone();
+    assert!(!stored.contains("alice"));
+    assert_eq!(db.get("users", 1).unwrap().unwrap().data()["name"], "Alice");
+    assert_eq!(email(&db), "alice@example.com");
+
+    db.clear_column_keys();
+    assert_eq!(email(&db), stored);
+    let err = db.execute_sql("INSERT INTO users (name, email) VALUES (Bob, bob@example.com)").unwrap_err();
+    assert!(err.contains("key"), "{}", err);
+}
+
+#[test]
+fn writes_are_encrypted() {
+    let mut db = setup();
+    db.execute_sql("UPDATE users SET email = alicia@example.com WHERE name = Alice").unwrap();
+    db.insert("users", 2, HashMap::from([("email".to_string(), "bob@example.com".to_string())])).unwrap();
+    assert!(!db.get("users", 2).unwrap().unwrap().data()["email"].contains("bob"));
+    let mut emails: Vec<String> = db.query_sql("SELECT email FROM users").unwrap().iter().map(|r| r.data()["email"].clone()).collect();
+    emails.sort();
+    assert_eq!(emails, vec!["alicia@example.com", "bob@example.com"]);
+}
+
+#[test]
+fn keys_are_not_saved() {
+    let db = setup();
+    let path = temp_path("encryption", "bin");
+    db.save(path.to_str().unwrap()).unwrap();
+    assert!(!String::from_utf8_lossy(&std::fs::read(&path).unwrap()).contains("alice@"));
+    let mut loaded = Database::load(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_ne!(email(&loaded), "alice@example.com");
+    assert!(loaded.set_column_key("users", "email", [8; 32]).is_err());
+    loaded.set_column_key("users", "email", KEY).unwrap();
+    assert_eq!(email(&loaded), "alice@example.com");
+    assert!(loaded.set_column_key("users", "name", KEY).is_err());
+    assert!(loaded.encrypt_column("users", "email", KEY).is_err());
+}
+
+#[test]
+fn rows_read_and_written_back_are_not_encrypted_twice() {
+    let mut db = setup();
+    let mut data = db.get("users", 1).unwrap().unwrap().data().to_map();
+    data.insert("name".to_string(), "Alicia".to_string());
+    db.update("users", 1, data).unwrap();
+    assert_eq!(email(&db), "alice@example.com");
+    assert_eq!(db.query_sql("SELECT name FROM users").unwrap()[0].data()["name"], "Alicia");
+}
+
+#[test]
+fn encrypted_columns_sort_by_their_values() {
+    let mut db = setup();
+    let names = ["hal", "bob", "gus", "cat", "fay", "dan", "eve"];
+    for (id, name) in (2..).zip(names) {
+        db.insert("users", id, HashMap::from([("email".to_string(), format!("{}@example.com", name))])).unwrap();
+    }
+    let emails: Vec<String> = db.query_sql("SELECT email FROM users ORDER BY email").unwrap().iter().map(|r| r.data()["email"].clone()).collect();
+    let mut sorted = emails.clone();
+    sorted.sort();
+    assert_eq!(emails, sorted);
+    assert_eq!(emails[0], "alice@example.com");
+}
+
+#[test]
+fn deltas_carry_encrypted_columns() {
+    let mut source = setup();
+    let mut copy = Database::from_bytes(&source.to_bytes().unwrap()).unwrap();
+    copy.set_column_key("users", "email", KEY).unwrap();
+    let since = source.last_change_seq();
+
+    source.execute_sql("INSERT INTO users (name, email) VALUES (Bob, bob@example.com)").unwrap();
+    source.execute_sql("UPDATE users SET email = ann@example.com WHERE name = Alice").unwrap();
+    copy.apply_delta(&source.export_delta(since).unwrap()).unwrap();
+
+    let emails = copy.query_sql("SELECT email FROM users ORDER BY email").unwrap();
+    let emails: Vec<&str> = emails.iter().map(|r| r.data()["email"].as_str()).collect();
+    assert_eq!(emails, ["ann@example.com", "bob@example.com"]);
+}
@@ -0,0 +1,89 @@
+use potatodb::Database;
+
+mod common;
+use common::{row, temp_path};
+
+fn orders() -> Database {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE orders (item TEXT, status ENUM('pending', 'shipped', 'delivered'))").unwrap();
+    db.execute_sql("INSERT INTO orders (item, status) VALUES (pen, pending)").unwrap();
+    db
+}
+
+#[test]
+fn only_listed_values_can_be_written() {
+    let mut db = orders();
+    assert!(db.execute_sql("INSERT INTO orders (item, status) VALUES (ink, lost)").is_err());
+    assert!(db.insert("orders", 5, row(&[("item", "ink"), ("status", "lost")])).is_err());
+    // rows may leave the column out
+    db.insert("orders", 5, row(&[("item", "ink")])).unwrap();
+
+    db.execute_sql("UPDATE orders SET status = shipped WHERE item = pen").unwrap();
+    assert_eq!(db.get("orders", 1).unwrap().unwrap().data()["status"], "shipped");
+    assert!(db.execute_sql("UPDATE orders SET status = lost WHERE item = pen").is_err());
+    assert!(db.update("orders", 1, row(&[("item", "pen"), ("status", "lost")])).is_err());
+    assert_eq!(db.get("orders", 1).unwrap().unwrap().data()["status"], "shipped");
+}
+
+#[test]
+fn existing_values_are_checked_when_a_column_becomes_an_enum() {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    db.insert("users", 1, row(&[("role", "admin")])).unwrap();
+    assert!(db.set_column_enum("users", "role", &["user", "guest"]).is_err());
+    db.insert("users", 2, row(&[("role", "superuser")])).unwrap();
+
+    db.set_column_enum("users", "role", &["admin", "superuser", "user"]).unwrap();
+    assert!(db.insert("users", 3, row(&[("role", "guest")])).is_err());
+    assert!(db.set_column_enum("users", "role", &["user", "user"]).is_err());
+    assert!(db.set_column_enum("users", "role", &[]).is_err());
+}
+
+#[test]
+fn create_table_rejects_bad_enum_definitions() {
+    let mut db = Database::new();
+    assert!(db.execute_sql("CREATE TABLE t (status ENUM('a', 'a'))").is_err());
+    assert!(db.execute_sql("CREATE TABLE t (status ENUM 'a')").is_err());
+    assert!(db.execute_sql("CREATE TABLE t (status ENUM())").is_err());
+    assert!(db.execute_sql("CREATE TABLE t (status ENUM( ))").is_err());
+    // ENUM is not the start of a type name
+    db.execute_sql("CREATE TABLE t (status ENUMERATED)").unwrap();
+    db.execute_sql("INSERT INTO t (status) VALUES (anything)").unwrap();
+}
+
+#[test]
+fn enum_values_survive_save_and_load() {
+    let mut db = orders();
+    db.insert("orders", 2, row(&[("item", "ink")])).unwrap();
+    db.insert("orders", 3, row(&[("item", "cap"), ("status", "delivered")])).unwrap();
+    let path = temp_path("enums", "bin");
+    db.save(path.to_str().unwrap()).unwrap();
+    let mut loaded = Database::load(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.get("orders", 1).unwrap().unwrap().data()["status"], "pending");
+    assert!(!loaded.get("orders", 2).unwrap().unwrap().data().contains_key("status"));
+    assert_eq!(loaded.get("orders", 3).unwrap().unwrap().data()["status"], "delivered");
+    assert!(loaded.execute_sql("INSERT INTO orders (item, status) VALUES (ink, lost)").is_err());
+}
+
+#[test]
+fn enum_values_are_stored_compactly() {
+    let size = |db: &mut Database, value: &dyn Fn(u64) -> String| {
+        for id in 1..=200 {
+            db.insert("orders", id, row(&[("status", &value(id))])).unwrap();
+        }
+        let path = temp_path(&format!("enums-size-{}", value(0)), "bin");
+        db.save(path.to_str().unwrap()).unwrap();
+        let size = std::fs::metadata(&path).unwrap().len();
+        std::fs::remove_file(&path).unwrap();
+        size
+    };
+    let mut unique = Database::new();
+    unique.create_table("orders".to_string()).unwrap();
+    let mut with_enum = Database::new();
+    with_enum.create_table("orders".to_string()).unwrap();
+    with_enum.set_column_enum("orders", "status", &["pending", "delivered"]).unwrap();
+    let unique = size(&mut unique, &|id| format!("{:09}", id));
+    assert!(size(&mut with_enum, &|_| "delivered".to_string()) * 3 < unique * 2);
+}
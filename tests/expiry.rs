@@ -0,0 +1,75 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use potatodb::{ChangeKind, Database};
+
+mod common;
+use common::row;
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+}
+
+fn sessions() -> Database {
+    let mut db = Database::new();
+    db.create_table("sessions".to_string()).unwrap();
+    db.set_expiry_column("sessions", "expires_at").unwrap();
+    db
+}
+
+#[test]
+fn purge_deletes_only_due_rows() {
+    let mut db = sessions();
+    let past = (now() - 1000).to_string();
+    db.insert("sessions", 1, row(&[("user", "ann"), ("expires_at", &past)])).unwrap();
+    db.insert("sessions", 2, row(&[("user", "bob"), ("expires_at", "2020-01-01")])).unwrap();
+    db.insert_with_ttl("sessions", 3, row(&[("user", "cy")]), Duration::from_secs(3600)).unwrap();
+    db.insert("sessions", 4, row(&[("user", "dee")])).unwrap();
+
+    let seq = db.last_change_seq();
+    assert_eq!(db.purge_expired().unwrap(), 2);
+    let ids: Vec<u64> = db.get_all("sessions").unwrap().iter().map(|r| r.id()).collect();
+    assert_eq!(ids, [3, 4]);
+    assert!(db.changes_since(seq).all(|e| e.kind() == ChangeKind::Delete));
+    assert_eq!(db.purge_expired().unwrap(), 0);
+}
+
+#[test]
+fn next_expiry_follows_writes() {
+    let mut db = sessions();
+    assert_eq!(db.next_expiry(), None);
+    let later = now() + 60_000;
+    db.insert("sessions", 1, row(&[("expires_at", &later.to_string())])).unwrap();
+    db.insert("sessions", 2, row(&[("expires_at", &(later + 5).to_string())])).unwrap();
+    assert_eq!(db.next_expiry(), Some(later));
+
+    // an update moves the row, a delete drops it
+    db.update("sessions", 1, row(&[("expires_at", &(later + 10).to_string())])).unwrap();
+    assert_eq!(db.next_expiry(), Some(later + 5));
+    db.delete("sessions", 2).unwrap();
+    assert_eq!(db.next_expiry(), Some(later + 10));
+    db.update("sessions", 1, row(&[("note", "kept")])).unwrap();
+    assert_eq!(db.next_expiry(), None);
+}
+
+#[test]
+fn expiry_survives_saving() {
+    let mut db = sessions();
+    db.insert("sessions", 1, row(&[("expires_at", "2020-01-01T00:00:00Z")])).unwrap();
+    let path = std::env::temp_dir().join(format!("potatodb-expiry-{}.bin", std::process::id()));
+    let path = path.to_str().unwrap();
+    db.save(path).unwrap();
+    let mut loaded = Database::load(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(loaded.next_expiry(), Some(1_577_836_800_000));
+    assert_eq!(loaded.purge_expired().unwrap(), 1);
+}
+
+#[test]
+fn ttl_needs_an_expiry_column() {
+    let mut db = sessions();
+    db.create_table("plain".to_string()).unwrap();
+    assert!(db.insert_with_ttl("plain", 1, row(&[]), Duration::from_secs(1)).unwrap_err().contains("no expiry column"));
+    db.clear_expiry_column("sessions").unwrap();
+    assert!(db.insert_with_ttl("sessions", 1, row(&[]), Duration::from_secs(1)).is_err());
+    assert!(db.set_expiry_column("missing", "at").is_err());
+}
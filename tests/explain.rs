@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+
+use potatodb::{Database, PartitionScheme};
+
+fn events() -> Database {
+    let mut db = Database::new();
+    db.create_table("events".to_string()).unwrap();
+    for (id, day) in [(1, "1"), (2, "2"), (3, "3"), (4, "3")] {
+        db.insert("events", id, HashMap::from([("day".to_string(), day.to_string())])).unwrap();
+    }
+    db
+}
+
+fn column(rows: &[potatodb::Record], name: &str) -> Vec<String> {
+    rows.iter().map(|r| r.data().get(name).cloned().unwrap_or_default()).collect()
+}
+
+#[test]
+fn explain_shows_the_plan_without_running_it() {
+    let db = events();
+    let plan = db.query_sql("EXPLAIN SELECT day FROM events WHERE day = 3").unwrap();
+    assert_eq!(column(&plan, "node"), ["scan", "filter", "project"]);
+    assert_eq!(column(&plan, "detail"), ["full scan of events (4 rows)", "day = 3", "day"]);
+    assert_eq!(column(&plan, "estimated_rows"), ["4", "4", "4"]);
+    assert!(plan.iter().all(|r| !r.data().contains_key("actual_rows")));
+}
+
+#[test]
+fn explain_analyze_reports_actual_rows_and_time() {
+    let mut db = events();
+    let scheme = PartitionScheme::Range { column: "day".to_string(), bounds: vec!["3".to_string()] };
+    db.partition_table("events", scheme).unwrap();
+
+    let plan = db.execute_sql("EXPLAIN ANALYZE SELECT * FROM events WHERE day = 3 AND day != 9").unwrap();
+    assert_eq!(column(&plan, "node"), ["scan", "filter", "project"]);
+    assert_eq!(column(&plan, "detail")[0], "scan of events partitions {1} of 3");
+    assert_eq!(column(&plan, "estimated_rows"), ["2", "2", "2"]);
+    assert_eq!(column(&plan, "actual_rows"), ["2", "2", "2"]);
+    assert_eq!(column(&plan, "loops"), ["1", "1", "1"]);
+    assert!(column(&plan, "time_ms").iter().all(|t| t.parse::<f64>().is_ok()));
+
+    let plan = db.query_sql("EXPLAIN ANALYZE SELECT * FROM events").unwrap();
+    assert_eq!(column(&plan, "node"), ["scan", "project"]);
+    assert_eq!(column(&plan, "actual_rows"), ["4", "4"]);
+
+    assert!(db.execute_sql("EXPLAIN DELETE FROM events").is_err());
+    assert!(db.query_sql("EXPLAIN").is_err());
+    assert_eq!(db.get_all("events").unwrap().len(), 4);
+}
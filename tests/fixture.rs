@@ -0,0 +1,44 @@
+#![cfg(feature = "fixtures")]
+
+use std::collections::HashMap;
+
+use potatodb::Database;
+
+#[test]
+fn builds_tables_from_toml() {
+    let db = Database::from_fixture("tests/fixtures/seed.toml").unwrap();
+    let mut tables = db.list_tables();
+    tables.sort();
+    assert_eq!(tables, vec!["orders", "users"]);
+    let alice = db.get("users", 1).unwrap().unwrap();
+    assert_eq!(alice.data()["name"], "Alice");
+    assert_eq!(alice.data()["age"], "30");
+    assert!(!alice.data().contains_key("id"));
+    assert_eq!(db.get("users", 2).unwrap().unwrap().data()["admin"], "true");
+    assert_eq!(db.get("orders", 1).unwrap().unwrap().data()["item"], "pen");
+}
+
+#[test]
+fn builds_tables_from_json() {
+    let db = Database::from_fixture("tests/fixtures/seed.json").unwrap();
+    let alice = db.get("users", 1).unwrap().unwrap();
+    assert_eq!(alice.data()["tags"], r#"["a","b"]"#);
+    assert!(!alice.data().contains_key("nickname"));
+    assert_eq!(db.get("users", 2).unwrap().unwrap().data()["name"], "Bob");
+    assert!(db.get_all("empty").unwrap().is_empty());
+}
+
+#[test]
+fn reset_restores_the_fixture() {
+    let mut db = Database::from_fixture("tests/fixtures/seed.toml").unwrap();
+    db.delete("users", 1).unwrap();
+    db.insert("orders", 9, HashMap::new()).unwrap();
+    db.create_table("scratch".to_string()).unwrap();
+    db.reset_to_fixture().unwrap();
+    assert_eq!(db.get("users", 1).unwrap().unwrap().data()["name"], "Alice");
+    assert_eq!(db.get_all("orders").unwrap().len(), 1);
+    assert!(db.get_all("scratch").is_err());
+
+    assert!(Database::new().reset_to_fixture().is_err());
+    assert!(Database::from_fixture("tests/fixtures/v0.bin").is_err());
+}
@@ -0,0 +1,86 @@
+use std::fs;
+use std::path::PathBuf;
+
+use potatodb::{CsvSource, Database};
+
+mod common;
+use common::{row, temp_path};
+
+fn with_ratings(name: &str, csv: &str) -> (Database, PathBuf) {
+    let path = temp_path(name, "csv");
+    fs::write(&path, csv).unwrap();
+    let mut db = Database::new();
+    db.attach_virtual("ratings", CsvSource::new(&path)).unwrap();
+    (db, path)
+}
+
+#[test]
+fn csv_files_are_queried_in_place() {
+    let (db, path) = with_ratings("ratings", "film,stars\nheat,5\nalien,4\n\"up, again\",3\n");
+    let rows = db.query_sql("SELECT * FROM ratings").unwrap();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[2].data().to_map(), row(&[("film", "up, again"), ("stars", "3")]));
+    assert_eq!(db.query_sql("SELECT film FROM ratings WHERE CAST(stars AS INTEGER) > 3").unwrap().len(), 2);
+    assert_eq!(db.query_sql("SELECT COUNT(*) FROM ratings").unwrap()[0].data()["COUNT(*)"], "3");
+
+    // the file is read again by each statement
+    fs::write(&path, "film,stars\nheat,5\n").unwrap();
+    assert_eq!(db.query_sql("SELECT film FROM ratings").unwrap().len(), 1);
+    fs::remove_file(path).unwrap();
+    assert!(db.query_sql("SELECT film FROM ratings").unwrap_err().contains("Cannot read"));
+}
+
+#[test]
+fn virtual_rows_join_with_real_tables() {
+    let (mut db, path) = with_ratings("join", "film,stars\nheat,5\nalien,4\n");
+    db.create_table("films".to_string()).unwrap();
+    db.insert("films", 1, row(&[("title", "heat"), ("year", "1995")])).unwrap();
+    db.insert("films", 2, row(&[("title", "alien"), ("year", "1979")])).unwrap();
+
+    let ratings = db.query_sql("SELECT film, stars FROM ratings").unwrap();
+    let mut joined: Vec<(String, String)> = db.query_sql("SELECT title, year FROM films").unwrap().iter()
+        .filter_map(|film| {
+            let rating = ratings.iter().find(|r| r.data().get("film") == film.data().get("title"))?;
+            Some((film.data()["year"].clone(), rating.data()["stars"].clone()))
+        })
+        .collect();
+    joined.sort();
+    assert_eq!(joined, [("1979".to_string(), "4".to_string()), ("1995".to_string(), "5".to_string())]);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn virtual_tables_are_read_only() {
+    let (mut db, path) = with_ratings("read-only", "film,stars\nheat,5\n");
+    assert!(db.execute_sql("INSERT INTO ratings (film) VALUES (up)").unwrap_err().contains("read-only"));
+    assert!(db.execute_sql("DELETE FROM ratings WHERE film = heat").unwrap_err().contains("read-only"));
+    assert!(db.create_table("ratings".to_string()).is_err());
+    assert!(db.attach_virtual("ratings", CsvSource::new(&path)).is_err());
+
+    db.detach_virtual("ratings").unwrap();
+    assert!(db.query_sql("SELECT film FROM ratings").is_err());
+    // the file is left alone
+    assert!(path.exists());
+    fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_files_are_queried_in_place() {
+    use potatodb::JsonSource;
+
+    let mut db = Database::new();
+    let array = temp_path("ratings", "json");
+    fs::write(&array, r#"[{"film": "heat", "stars": 5, "tags": ["crime"]}, {"film": "alien", "stars": null}]"#).unwrap();
+    let lines = temp_path("ratings", "jsonl");
+    fs::write(&lines, "{\"film\": \"heat\"}\n\n{\"film\": \"up\"}\n").unwrap();
+    db.attach_virtual("array", JsonSource::new(&array)).unwrap();
+    db.attach_virtual("lines", JsonSource::new(&lines)).unwrap();
+
+    let rows = db.query_sql("SELECT * FROM array").unwrap();
+    assert_eq!(rows[0].data().to_map(), row(&[("film", "heat"), ("stars", "5"), ("tags", "[\"crime\"]")]));
+    assert_eq!(rows[1].data().to_map(), row(&[("film", "alien")]));
+    assert_eq!(db.query_sql("SELECT film FROM lines WHERE film = up").unwrap().len(), 1);
+    fs::remove_file(array).unwrap();
+    fs::remove_file(lines).unwrap();
+}
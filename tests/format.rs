@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+
+use potatodb::Database;
+
+mod common;
+use common::temp_path;
+
+#[test]
+fn loads_files_written_before_the_format_header() {
+    let db = Database::load("tests/fixtures/v0.bin").unwrap();
+    assert_eq!(db.list_tables(), vec!["users"]);
+    let users = db.get_all("users").unwrap();
+    assert!(users.iter().any(|r| r.data().get("name").map(String::as_str) == Some("Alice")));
+}
+
+#[test]
+fn migrated_files_are_saved_in_the_current_format() {
+    let db = Database::load("tests/fixtures/v0.bin").unwrap();
+    let path = temp_path("migrated", "bin");
+    db.save(path.to_str().unwrap()).unwrap();
+    assert!(std::fs::read(&path).unwrap().starts_with(b"POTATODB"));
+
+    let reloaded = Database::load(path.to_str().unwrap()).unwrap();
+    assert_eq!(reloaded.get_all("users").unwrap().len(), db.get_all("users").unwrap().len());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn rejects_newer_format_versions() {
+    let mut bytes = Database::new().to_bytes().unwrap();
+    bytes[8..12].copy_from_slice(&99u32.to_le_bytes());
+    let err = Database::from_bytes(&bytes).err().unwrap();
+    assert!(err.to_string().contains("99"));
+}
+
+#[test]
+fn bytes_round_trip() {
+    let mut db = Database::new();
+    db.create_table("t".to_string()).unwrap();
+    db.insert("t", 7, HashMap::from([("k".to_string(), "v".to_string())])).unwrap();
+
+    let copy = Database::from_bytes(&db.to_bytes().unwrap()).unwrap();
+    assert_eq!(copy.get("t", 7).unwrap().unwrap().data()["k"], "v");
+}
@@ -0,0 +1,80 @@
+use potatodb::Database;
+
+fn users() -> Database {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE users (name TEXT, nick TEXT, score INTEGER)").unwrap();
+    db.execute_sql("INSERT INTO users (name, nick, score) VALUES (alice, ally, 0)").unwrap();
+    db.execute_sql("INSERT INTO users (name, score) VALUES (bob, 7)").unwrap();
+    db
+}
+
+fn values(db: &Database, sql: &str, column: &str) -> Vec<Option<String>> {
+    let mut rows = db.query_sql(sql).unwrap();
+    rows.sort_by_key(|r| r.id());
+    rows.iter().map(|r| r.data().get(column).cloned()).collect()
+}
+
+#[test]
+fn functions_work_out_columns_in_select() {
+    let db = users();
+    assert_eq!(values(&db, "SELECT name, COALESCE(nick, name) FROM users", "COALESCE(nick, name)"), [Some("ally".to_string()), Some("bob".to_string())]);
+    assert_eq!(values(&db, "SELECT IFNULL(nick, 'none') FROM users", "IFNULL(nick, 'none')"), [Some("ally".to_string()), Some("none".to_string())]);
+    // NULLIF gives no value when its arguments are equal
+    assert_eq!(values(&db, "SELECT NULLIF(score, 0) FROM users", "NULLIF(score, 0)"), [None, Some("7".to_string())]);
+    assert_eq!(values(&db, "SELECT COALESCE(nick, NULLIF(score, 0), 'x') FROM users", "COALESCE(nick, NULLIF(score, 0), 'x')"), [Some("ally".to_string()), Some("7".to_string())]);
+}
+
+#[test]
+fn functions_filter_rows_in_where() {
+    let db = users();
+    assert_eq!(values(&db, "SELECT name FROM users WHERE COALESCE(nick, name) = bob", "name"), [Some("bob".to_string())]);
+    assert_eq!(values(&db, "SELECT name FROM users WHERE IFNULL(nick, '') != ally", "name"), [Some("bob".to_string())]);
+    assert_eq!(values(&db, "SELECT name FROM users WHERE NULLIF(score, 0) > 5 AND name = bob", "name"), [Some("bob".to_string())]);
+}
+
+#[test]
+fn defaults_fill_absent_columns() {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE tasks (title TEXT, status TEXT DEFAULT 'open', owner TEXT DEFAULT COALESCE(team, 'nobody'))").unwrap();
+    db.execute_sql("INSERT INTO tasks (title) VALUES (write)").unwrap();
+    db.execute_sql("INSERT INTO tasks (title, status, team) VALUES (test, done, qa)").unwrap();
+    assert_eq!(values(&db, "SELECT status FROM tasks", "status"), [Some("open".to_string()), Some("done".to_string())]);
+    assert_eq!(values(&db, "SELECT owner FROM tasks", "owner"), [Some("nobody".to_string()), Some("qa".to_string())]);
+
+    db.execute_sql("UPDATE tasks SET status = closed WHERE title = write").unwrap();
+    assert_eq!(values(&db, "SELECT status FROM tasks", "status"), [Some("closed".to_string()), Some("done".to_string())]);
+}
+
+#[test]
+fn bad_calls_are_rejected() {
+    let mut db = users();
+    assert!(db.query_sql("SELECT NULLIF(nick) FROM users").unwrap_err().contains("two arguments"));
+    assert!(db.query_sql("SELECT UPPER(nick) FROM users").unwrap_err().contains("Unknown function"));
+    assert!(db.execute_sql("CREATE TABLE t (a TEXT DEFAULT COALESCE(b))").is_err());
+}
+
+#[test]
+fn cast_converts_text_columns() {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE legacy (age TEXT)").unwrap();
+    for age in ["9", "10", "12.7", "unknown"] {
+        db.execute_sql(&format!("INSERT INTO legacy (age) VALUES ({})", age)).unwrap();
+    }
+    assert_eq!(values(&db, "SELECT CAST(age AS INTEGER) FROM legacy", "CAST(age AS INTEGER)"), [Some("9".to_string()), Some("10".to_string()), Some("12".to_string()), None]);
+    assert_eq!(values(&db, "SELECT CAST(age AS REAL) FROM legacy", "CAST(age AS REAL)")[2], Some("12.7".to_string()));
+    assert_eq!(values(&db, "SELECT CAST(age AS TEXT) FROM legacy", "CAST(age AS TEXT)")[3], Some("unknown".to_string()));
+
+    // text compares "10" before "9"; the cast compares as numbers, and
+    // values that do not convert match nothing
+    assert_eq!(values(&db, "SELECT age FROM legacy WHERE age > 9", "age").len(), 1);
+    assert_eq!(values(&db, "SELECT age FROM legacy WHERE CAST(age AS INTEGER) > 9", "age"), [Some("10".to_string()), Some("12.7".to_string())]);
+    assert_eq!(values(&db, "SELECT age FROM legacy WHERE CAST(age AS INTEGER) BETWEEN 9 AND 10", "age").len(), 2);
+    assert_eq!(values(&db, "SELECT age FROM legacy WHERE COALESCE(CAST(age AS INTEGER), -1) = -1", "age"), [Some("unknown".to_string())]);
+}
+
+#[test]
+fn cast_needs_a_known_type() {
+    let db = users();
+    assert!(db.query_sql("SELECT CAST(score AS BLOB) FROM users").unwrap_err().contains("Cannot CAST"));
+    assert!(db.query_sql("SELECT CAST(score INTEGER) FROM users").unwrap_err().contains("Expected AS"));
+}
@@ -0,0 +1,77 @@
+use potatodb::{Database, PartitionScheme};
+
+mod common;
+use common::{row, temp_path};
+
+fn products() -> Database {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE products (name TEXT, price REAL, price_cents INTEGER GENERATED ALWAYS AS (price * 100) STORED, label TEXT GENERATED ALWAYS AS (name || ': ' || price) VIRTUAL)").unwrap();
+    db.execute_sql("INSERT INTO products (name, price) VALUES (pen, 1.99)").unwrap();
+    db.insert("products", 2, row(&[("name", "ink"), ("price", "4")])).unwrap();
+    db
+}
+
+#[test]
+fn stored_columns_are_written_with_the_row() {
+    let mut db = products();
+    assert_eq!(db.get("products", 1).unwrap().unwrap().data()["price_cents"], "199");
+    assert_eq!(db.get("products", 2).unwrap().unwrap().data()["price_cents"], "400");
+
+    db.execute_sql("UPDATE products SET price = 2.5 WHERE name = pen").unwrap();
+    assert_eq!(db.get("products", 1).unwrap().unwrap().data()["price_cents"], "250");
+    // values given through the Rust API are replaced
+    db.update("products", 2, row(&[("name", "ink"), ("price", "3"), ("price_cents", "1")])).unwrap();
+    assert_eq!(db.get("products", 2).unwrap().unwrap().data()["price_cents"], "300");
+}
+
+#[test]
+fn virtual_columns_are_computed_on_read() {
+    let db = products();
+    assert!(!db.get("products", 1).unwrap().unwrap().data().contains_key("label"));
+    let rows = db.query_sql("SELECT label FROM products WHERE price_cents > 300").unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].data()["label"], "ink: 4");
+}
+
+#[test]
+fn generated_columns_work_in_where_clauses_of_writes() {
+    let mut db = products();
+    db.add_generated_column("products", "pricey GENERATED ALWAYS AS (price_cents / 100)").unwrap();
+    assert_eq!(db.execute_sql("DELETE FROM products WHERE pricey = 4").unwrap().len(), 1);
+    assert_eq!(db.get_all("products").unwrap().len(), 1);
+}
+
+#[test]
+fn generated_columns_cannot_be_written_in_sql() {
+    let mut db = products();
+    assert!(db.execute_sql("INSERT INTO products (name, price_cents) VALUES (cap, 5)").is_err());
+    assert!(db.execute_sql("UPDATE products SET label = x WHERE name = pen").is_err());
+    assert!(db.add_generated_column("products", "price_cents GENERATED ALWAYS AS (1)").is_err());
+    assert!(db.add_generated_column("products", "broken GENERATED ALWAYS AS (price *)").is_err());
+    assert!(db.add_generated_column("products", "broken AS (price)").is_err());
+}
+
+#[test]
+fn stored_columns_can_partition_a_table() {
+    let mut db = Database::new();
+    db.create_table("events".to_string()).unwrap();
+    db.insert("events", 1, row(&[("ms", "5000")])).unwrap();
+    db.add_generated_column("events", "second INTEGER GENERATED ALWAYS AS (ms / 1000) STORED").unwrap();
+    db.partition_table("events", PartitionScheme::Hash { column: "second".to_string(), partitions: 4 }).unwrap();
+    db.insert("events", 2, row(&[("ms", "7000")])).unwrap();
+    assert_eq!(db.query_sql("SELECT * FROM events WHERE second = 7").unwrap()[0].id(), 2);
+    assert!(db.check_integrity().is_empty());
+}
+
+#[test]
+fn definitions_are_saved() {
+    let db = products();
+    let path = temp_path("generated", "bin");
+    db.save(path.to_str().unwrap()).unwrap();
+    let mut loaded = Database::load(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(path).unwrap();
+    loaded.execute_sql("INSERT INTO products (name, price) VALUES (cap, 1)").unwrap();
+    let rows = loaded.query_sql("SELECT label, price_cents FROM products WHERE name = cap").unwrap();
+    assert_eq!(rows[0].data()["label"], "cap: 1");
+    assert_eq!(rows[0].data()["price_cents"], "100");
+}
@@ -0,0 +1,67 @@
+use potatodb::{Database, Traversal};
+
+mod common;
+use common::row;
+
+// alice -> bob -> carol -> dave, alice -> erin -> dave
+fn follows() -> Database {
+    let mut db = Database::new();
+    db.create_table("follows".to_string()).unwrap();
+    let edges = [("alice", "bob", "friend"), ("bob", "carol", "friend"), ("carol", "dave", "work"), ("alice", "erin", "work"), ("erin", "dave", "friend")];
+    for (id, (from, to, kind)) in (1..).zip(edges) {
+        db.insert("follows", id, row(&[("follower", from), ("followee", to), ("kind", kind)])).unwrap();
+    }
+    db.declare_edges("follows", "follower", "followee").unwrap();
+    db
+}
+
+#[test]
+fn neighbors_and_traversals() {
+    let db = follows();
+    assert_eq!(db.neighbors("follows", "alice").unwrap(), vec!["bob", "erin"]);
+    assert!(db.neighbors("follows", "dave").unwrap().is_empty());
+
+    let visit = |order| db.traverse("follows", "alice", 2, order).unwrap();
+    let names = |visited: Vec<(String, usize)>| visited.into_iter().map(|(n, d)| format!("{}{}", n, d)).collect::<Vec<_>>();
+    assert_eq!(names(visit(Traversal::BreadthFirst)), vec!["alice0", "bob1", "erin1", "carol2", "dave2"]);
+    assert_eq!(names(visit(Traversal::DepthFirst)), vec!["alice0", "bob1", "carol2", "erin1", "dave2"]);
+    assert_eq!(db.traverse("follows", "alice", 0, Traversal::BreadthFirst).unwrap().len(), 1);
+}
+
+#[test]
+fn shortest_paths_take_the_fewest_edges() {
+    let db = follows();
+    assert_eq!(db.shortest_path("follows", "alice", "dave").unwrap().unwrap(), vec!["alice", "erin", "dave"]);
+    assert_eq!(db.shortest_path("follows", "bob", "bob").unwrap().unwrap(), vec!["bob"]);
+    // edges are directed
+    assert_eq!(db.shortest_path("follows", "dave", "alice").unwrap(), None);
+}
+
+#[test]
+fn graph_statements_return_nodes_and_depths() {
+    let mut db = follows();
+    let rows = db.query_sql("GRAPH follows BFS alice 3 WHERE kind = friend").unwrap();
+    let found: Vec<(&str, &str)> = rows.iter().map(|r| (r.data()["node"].as_str(), r.data()["depth"].as_str())).collect();
+    assert_eq!(found, vec![("alice", "0"), ("bob", "1"), ("carol", "2")]);
+
+    let path = db.execute_sql("GRAPH follows PATH alice dave").unwrap();
+    let steps: Vec<&str> = path.iter().map(|r| r.data()["node"].as_str()).collect();
+    assert_eq!(steps, vec!["alice", "erin", "dave"]);
+    assert_eq!(db.query_sql("GRAPH follows NEIGHBORS bob").unwrap()[0].data()["node"], "carol");
+    assert_eq!(db.query_sql("GRAPH follows DFS alice 1").unwrap().len(), 3);
+
+    assert!(db.query_sql("GRAPH follows BFS alice far").is_err());
+    assert!(db.query_sql("GRAPH follows WALK alice").is_err());
+    db.create_table("plain".to_string()).unwrap();
+    assert!(db.query_sql("GRAPH plain NEIGHBORS alice").is_err());
+}
+
+#[test]
+fn row_policies_limit_the_edges_followed() {
+    let mut db = follows();
+    db.add_policy("follows", "kind = $kind").unwrap();
+    db.set_session_attribute("kind", "work");
+    let rows = db.query_sql("GRAPH follows BFS alice 5").unwrap();
+    let found: Vec<&str> = rows.iter().map(|r| r.data()["node"].as_str()).collect();
+    assert_eq!(found, vec!["alice", "erin"]);
+}
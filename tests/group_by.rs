@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+
+use potatodb::Database;
+
+fn orders() -> Database {
+    let mut db = Database::new();
+    db.create_table("orders".to_string()).unwrap();
+    let rows = [("ann", "paid", "10"), ("bob", "paid", "5"), ("ann", "open", "7"), ("ann", "paid", "3"), ("cy", "open", "1")];
+    for (id, (user, status, total)) in (1..).zip(rows) {
+        let data = HashMap::from([
+            ("user".to_string(), user.to_string()),
+            ("status".to_string(), status.to_string()),
+            ("total".to_string(), total.to_string()),
+        ]);
+        db.insert("orders", id, data).unwrap();
+    }
+    db
+}
+
+fn column(rows: &[potatodb::Record], name: &str) -> Vec<String> {
+    rows.iter().map(|r| r.data().get(name).cloned().unwrap_or_default()).collect()
+}
+
+#[test]
+fn groups_hold_their_keys_and_aggregates() {
+    let db = orders();
+    let rows = db.query_sql("SELECT user, COUNT(*), SUM(total) FROM orders GROUP BY user").unwrap();
+    // groups keep the order their first row was read in
+    assert_eq!(column(&rows, "user"), ["ann", "bob", "cy"]);
+    assert_eq!(column(&rows, "COUNT(*)"), ["3", "1", "1"]);
+    assert_eq!(column(&rows, "SUM(total)"), ["20", "5", "1"]);
+
+    let rows = db.query_sql("SELECT user, status, max(total) FROM orders WHERE total > 2 GROUP BY user, status").unwrap();
+    assert_eq!(column(&rows, "status"), ["paid", "paid", "open"]);
+    assert_eq!(column(&rows, "max(total)"), ["10", "5", "7"]);
+}
+
+#[test]
+fn having_filters_groups_before_sorting_and_paging() {
+    let db = orders();
+    let rows = db.query_sql("SELECT user FROM orders GROUP BY user HAVING COUNT(*) > 1").unwrap();
+    assert_eq!(column(&rows, "user"), ["ann"]);
+    // aggregates HAVING compares need not be selected
+    assert!(!rows[0].data().contains_key("COUNT(*)"));
+
+    let rows = db.query_sql("SELECT status, SUM(total) FROM orders GROUP BY status HAVING SUM(total) > 5 OR status = open ORDER BY SUM(total) DESC LIMIT 1").unwrap();
+    assert_eq!(column(&rows, "status"), ["paid"]);
+}
+
+#[test]
+fn ungrouped_columns_are_rejected() {
+    let db = orders();
+    assert_eq!(db.query_sql("SELECT user, status FROM orders GROUP BY user").unwrap_err(), "Column 'status' is neither grouped nor aggregated");
+    assert!(db.query_sql("SELECT * FROM orders GROUP BY user").is_err());
+    assert!(db.query_sql("SELECT user FROM orders GROUP BY user HAVING").unwrap_err().starts_with("Unexpected end of statement"));
+}
+
+#[test]
+fn explain_and_streaming_group_too() {
+    let mut db = orders();
+    let plan = db.query_sql("EXPLAIN ANALYZE SELECT user, COUNT(*) FROM orders GROUP BY user HAVING COUNT(*) > 1").unwrap();
+    let group = plan.iter().find(|r| r.data()["node"] == "group").unwrap();
+    assert_eq!(group.data()["detail"], "user HAVING COUNT(*) > 1");
+    assert_eq!(group.data()["actual_rows"], "1");
+
+    let mut streamed = Vec::new();
+    db.execute_sql_streaming("SELECT status, COUNT(*) FROM orders GROUP BY status", |record| {
+        streamed.push(record.data()["status"].clone());
+        ControlFlow::Continue(())
+    }).unwrap();
+    assert_eq!(streamed, ["paid", "open"]);
+}
+
+#[test]
+fn aggregates_of_masked_columns_are_masked() {
+    let mut db = orders();
+    db.mask_column("orders", "user", potatodb::Mask::KeepLast(1));
+    db.create_role("support").unwrap();
+    db.grant("support", "orders", &[potatodb::Privilege::Select]).unwrap();
+    db.create_user("sam", &["support"]).unwrap();
+    db.set_session_user(Some("sam")).unwrap();
+    let rows = db.query_sql("SELECT status, MAX(user), string_agg(user, ',') FROM orders GROUP BY status").unwrap();
+    assert_eq!(column(&rows, "MAX(user)"), ["****n", "****y"]);
+    assert_eq!(column(&rows, "string_agg(user, ',')"), ["****n,****b,****n", "****n,****y"]);
+}
@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use potatodb::{Database, PartitionScheme};
+
+fn events() -> Database {
+    let mut db = Database::new();
+    db.create_table("events".to_string()).unwrap();
+    db.partition_table("events", PartitionScheme::Hash { column: "day".to_string(), partitions: 4 }).unwrap();
+    for id in 1..=20 {
+        db.insert("events", id, HashMap::from([("day".to_string(), (id % 5).to_string())])).unwrap();
+    }
+    db
+}
+
+fn scan(db: &Database, sql: &str) -> String {
+    db.query_sql(&format!("EXPLAIN {}", sql)).unwrap()[0].data()["detail"].clone()
+}
+
+#[test]
+fn no_index_turns_partition_pruning_off() {
+    let db = events();
+    assert!(scan(&db, "SELECT * FROM events WHERE day = 3").starts_with("scan of events partitions"));
+    assert!(scan(&db, "SELECT /*+ NO_INDEX */ * FROM events WHERE day = 3").starts_with("full scan of events"));
+    assert!(scan(&db, "SELECT /*+ FULL(events) */ * FROM events WHERE day = 3").starts_with("full scan of events"));
+    assert_eq!(db.scanned_partitions("SELECT /*+ NO_INDEX */ * FROM events WHERE day = 3").unwrap().unwrap().len(), 5);
+
+    // the results are the same either way
+    let count = |sql: &str| db.query_sql(sql).unwrap().len();
+    assert_eq!(count("SELECT /*+ NO_INDEX */ * FROM events WHERE day = 3"), 4);
+    assert_eq!(count("SELECT * FROM events WHERE day = 3"), 4);
+    assert_eq!(db.query_sql("SELECT /*+ NO_INDEX */ COUNT(*) FROM events WHERE day = 3").unwrap()[0].data()["COUNT(*)"], "4");
+}
+
+#[test]
+fn hints_for_other_tables_and_unknown_hints_are_ignored() {
+    let db = events();
+    assert!(scan(&db, "SELECT /*+ FULL(users) */ * FROM events WHERE day = 3").starts_with("scan of events partitions"));
+    assert!(scan(&db, "SELECT /*+ LEADING(a b) USE_HASH(a) */ * FROM events WHERE day = 3").starts_with("scan of events partitions"));
+    assert!(scan(&db, "SELECT /*+ NO_INDEX INDEX(events day) */ * FROM events WHERE day = 3").starts_with("scan of events partitions"));
+    assert!(scan(&db, "SELECT /* NO_INDEX */ * FROM events WHERE day = 3").starts_with("scan of events partitions"));
+    assert_eq!(db.query_sql("SELECT /*+ what ever ( */ * FROM events WHERE day = 1").unwrap().len(), 4);
+}
+
+#[test]
+fn comments_inside_quotes_are_kept() {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE notes").unwrap();
+    db.execute_sql("INSERT INTO notes (text) VALUES ('a/*b*/c')").unwrap();
+    assert!(db.query_sql("SELECT text FROM notes").unwrap()[0].data()["text"].contains("a/*b*/c"));
+}
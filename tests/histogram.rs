@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use potatodb::{CompareAs, Database};
+
+fn orders() -> Database {
+    let mut db = Database::new();
+    db.create_table("orders".to_string()).unwrap();
+    db.set_compare_as("orders", "amount", CompareAs::Numeric).unwrap();
+    for id in 1..=1000 {
+        let mut data = HashMap::from([("amount".to_string(), id.to_string())]);
+        if id % 10 == 0 {
+            data.insert("note".to_string(), "gift".to_string());
+        }
+        db.insert("orders", id, data).unwrap();
+    }
+    db
+}
+
+fn filter_estimate(db: &Database, predicate: &str) -> usize {
+    let plan = db.query_sql(&format!("EXPLAIN SELECT * FROM orders WHERE {}", predicate)).unwrap();
+    plan.iter().find(|r| r.data()["node"] == "filter").unwrap().data()["estimated_rows"].parse().unwrap()
+}
+
+#[test]
+fn histograms_are_equi_depth() {
+    let mut db = orders();
+    let histograms = db.analyze("orders").unwrap();
+    let columns: Vec<&str> = histograms.iter().map(|h| h.column()).collect();
+    assert_eq!(columns, ["amount", "note"]);
+
+    let amount = db.histogram("orders", "amount").unwrap();
+    assert_eq!((amount.rows(), amount.missing(), amount.distinct()), (1000, 0, 1000));
+    // sorted as numbers, not text
+    assert_eq!(amount.bounds().first().unwrap(), "1");
+    assert_eq!(amount.bounds().last().unwrap(), "1000");
+    assert_eq!(amount.bounds().len(), 33);
+    assert_eq!(amount.bounds()[16], "500");
+
+    let note = db.histogram("orders", "note").unwrap();
+    assert_eq!((note.rows(), note.missing(), note.distinct()), (100, 900, 1));
+    assert!(db.histogram("orders", "missing").is_none());
+    assert!(db.analyze("missing").is_err());
+}
+
+#[test]
+fn explain_estimates_range_predicates_from_histograms() {
+    let mut db = orders();
+    assert_eq!(filter_estimate(&db, "amount < 100"), 1000);
+
+    db.analyze("orders").unwrap();
+    let near = |estimate: usize, expected: usize| estimate.abs_diff(expected) <= 20;
+    assert!(near(filter_estimate(&db, "amount < 100"), 100));
+    assert!(near(filter_estimate(&db, "amount > 750"), 250));
+    assert!(near(filter_estimate(&db, "amount BETWEEN 200 AND 400"), 200));
+    assert_eq!(filter_estimate(&db, "amount = 7"), 1);
+    assert_eq!(filter_estimate(&db, "note = gift"), 100);
+    assert!(near(filter_estimate(&db, "amount < 500 AND note = gift"), 50));
+    // columns without a histogram keep every row
+    assert_eq!(filter_estimate(&db, "colour = red"), 1000);
+}
+
+#[test]
+fn analyze_in_sql_reports_each_column() {
+    let mut db = orders();
+    let rows = db.execute_sql("ANALYZE orders").unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[1].data()["column"], "note");
+    assert_eq!(rows[1].data()["missing"], "900");
+    assert_eq!(rows[1].data()["buckets"], "32");
+    assert!(db.histogram("orders", "amount").is_some());
+    assert!(db.execute_sql("ANALYZE").is_err());
+    assert!(db.query_sql("ANALYZE orders").is_err());
+}
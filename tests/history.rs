@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use potatodb::Database;
+
+mod common;
+use common::temp_path;
+
+fn row(name: &str) -> HashMap<String, String> {
+    HashMap::from([("name".to_string(), name.to_string())])
+}
+
+// A moment strictly between the writes before and after it.
+fn checkpoint() -> SystemTime {
+    sleep(Duration::from_millis(5));
+    let now = SystemTime::now();
+    sleep(Duration::from_millis(5));
+    now
+}
+
+fn millis(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH).unwrap().as_millis()
+}
+
+fn names(db: &Database, sql: &str) -> Vec<String> {
+    db.query_sql(sql).unwrap().iter().map(|r| r.data()["name"].clone()).collect()
+}
+
+fn history_db() -> (Database, SystemTime, SystemTime, SystemTime) {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    db.insert("users", 1, row("Alice")).unwrap();
+    db.enable_history("users").unwrap();
+    let first = checkpoint();
+    db.insert("users", 2, row("Bob")).unwrap();
+    db.update("users", 1, row("Alicia")).unwrap();
+    let second = checkpoint();
+    db.delete("users", 2).unwrap();
+    let third = checkpoint();
+    (db, first, second, third)
+}
+
+#[test]
+fn as_of_reads_past_states() {
+    let (db, first, second, third) = history_db();
+    assert_eq!(names(&db, &format!("SELECT name FROM users AS OF {}", millis(first))), vec!["Alice"]);
+    assert_eq!(names(&db, &format!("SELECT name FROM users AS OF {}", millis(second))), vec!["Alicia", "Bob"]);
+    assert_eq!(names(&db, &format!("SELECT name FROM users AS OF {}", millis(third))), vec!["Alicia"]);
+    assert_eq!(names(&db, &format!("SELECT name FROM users AS OF {} WHERE name = Bob", millis(second))), vec!["Bob"]);
+    assert!(names(&db, "SELECT name FROM users AS OF '2000-01-01'").is_empty());
+}
+
+#[test]
+fn get_as_of_reads_one_record() {
+    let (db, first, second, third) = history_db();
+    assert_eq!(db.get_as_of("users", 1, first).unwrap().unwrap().data()["name"], "Alice");
+    assert_eq!(db.get_as_of("users", 2, second).unwrap().unwrap().data()["name"], "Bob");
+    assert!(db.get_as_of("users", 2, first).unwrap().is_none());
+    assert!(db.get_as_of("users", 2, third).unwrap().is_none());
+}
+
+#[test]
+fn sql_writes_are_kept() {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    db.enable_history("users").unwrap();
+    db.execute_sql("INSERT INTO users (name) VALUES (Alice)").unwrap();
+    let before = checkpoint();
+    db.execute_sql("UPDATE users SET name = Alicia WHERE name = Alice").unwrap();
+    assert_eq!(names(&db, &format!("SELECT name FROM users AS OF {}", millis(before))), vec!["Alice"]);
+}
+
+#[test]
+fn timestamps_can_be_dates() {
+    let (db, ..) = history_db();
+    assert!(db.query_sql("SELECT * FROM users AS OF '2024-01-01T00:00:00.5Z'").unwrap().is_empty());
+    assert!(db.query_sql("SELECT * FROM users AS OF 2024-13-01").is_err());
+    assert_eq!(db.query_sql("SELECT * FROM users AS OF '2999-01-01'").unwrap().len(), 1);
+}
+
+#[test]
+fn history_must_be_enabled() {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    let err = db.query_sql("SELECT * FROM users AS OF 0").unwrap_err();
+    assert!(err.contains("history"));
+    assert!(db.get_as_of("users", 1, SystemTime::now()).is_err());
+}
+
+#[test]
+fn history_is_saved() {
+    let (db, first, ..) = history_db();
+    let path = temp_path("history", "bin");
+    db.save(path.to_str().unwrap()).unwrap();
+    let loaded = Database::load(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(loaded.get_as_of("users", 1, first).unwrap().unwrap().data()["name"], "Alice");
+}
@@ -0,0 +1,74 @@
+use potatodb::{quote_identifier, Database};
+
+mod common;
+use common::row;
+
+#[test]
+fn quoted_names_can_be_keywords_or_hold_spaces() {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE \"from\"").unwrap();
+    db.execute_sql("INSERT INTO \"from\" (\"first name\", age) VALUES ('Anne', 30)").unwrap();
+    db.execute_sql("INSERT INTO \"from\" (\"first name\", age) VALUES ('Bob', 40)").unwrap();
+    db.execute_sql("UPDATE \"from\" SET \"first name\" = Ann WHERE age = 30").unwrap();
+
+    let rows = db.query_sql("SELECT \"first name\" FROM \"from\" WHERE \"first name\" = Ann").unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].data()["first name"], "Ann");
+    db.execute_sql("DELETE FROM \"from\" WHERE \"first name\" = 'Bob'").unwrap();
+    assert_eq!(db.query_sql("SELECT * FROM \"from\"").unwrap().len(), 1);
+
+    db.execute_sql("CREATE TABLE \"my table\"").unwrap();
+    db.insert("my table", 1, row(&[("a", "1")])).unwrap();
+    assert_eq!(db.query_sql("SELECT a FROM \"my table\"").unwrap()[0].data()["a"], "1");
+    assert!(db.execute_sql("CREATE TABLE my table").is_err());
+}
+
+#[test]
+fn quote_identifier_round_trips_through_sql() {
+    assert_eq!(quote_identifier("users"), "users");
+    assert_eq!(quote_identifier("from"), "\"from\"");
+    assert_eq!(quote_identifier("first name"), "\"first name\"");
+    assert_eq!(quote_identifier("2nd"), "\"2nd\"");
+    assert_eq!(quote_identifier("say \"hi\""), "\"say \"\"hi\"\"\"");
+
+    let mut db = Database::new();
+    let table = "say \"hi\"";
+    db.create_table(table.to_string()).unwrap();
+    db.insert(table, 1, row(&[("select", "x")])).unwrap();
+    let sql = format!("SELECT {} FROM {}", quote_identifier("select"), quote_identifier(table));
+    assert_eq!(db.query_sql(&sql).unwrap()[0].data()["select"], "x");
+}
+
+#[test]
+fn every_keyword_round_trips_as_a_name() {
+    let keywords = [
+        "ANALYZE", "AND", "AS", "BETWEEN", "BY", "CREATE", "DELETE", "DROP", "EXPLAIN", "FROM", "GRAPH", "GROUP", "HAVING", "INSERT",
+        "INTO", "IS", "LIKE", "LIMIT", "NOT", "OF", "OFFSET", "OR", "ORDER", "PRAGMA", "SCHEMA", "SELECT", "SET", "TABLE",
+        "TABLESAMPLE", "TEMP", "TEMPORARY", "UPDATE", "VALUES", "WHERE",
+    ];
+    for keyword in keywords.into_iter().flat_map(|k| [k.to_string(), k.to_lowercase()]) {
+        let name = quote_identifier(&keyword);
+        assert_ne!(name, keyword);
+        let mut db = Database::new();
+        db.execute_sql(&format!("CREATE TABLE {}", name)).unwrap();
+        db.execute_sql(&format!("INSERT INTO {} ({}) VALUES (a)", name, name)).unwrap();
+        db.execute_sql(&format!("INSERT INTO {} ({}) VALUES (b)", name, name)).unwrap();
+        db.execute_sql(&format!("UPDATE {} SET {} = c WHERE {} = b", name, name, name)).unwrap();
+
+        let sql = format!("SELECT {} FROM {} WHERE {} = c OR {} LIKE a% ORDER BY {} LIMIT 5", name, name, name, name, name);
+        let values: Vec<String> = db.query_sql(&sql).unwrap().iter().map(|r| r.data()[&keyword].clone()).collect();
+        assert_eq!(values, ["a", "c"], "{}", keyword);
+        db.execute_sql(&format!("DELETE FROM {} WHERE {} = a", name, name)).unwrap();
+        assert_eq!(db.list_tables(), std::slice::from_ref(&keyword));
+        assert_eq!(db.get_all(&keyword).unwrap().len(), 1, "{}", keyword);
+    }
+}
+
+#[test]
+fn create_table_rejects_names_sql_cannot_write() {
+    let mut db = Database::new();
+    for name in ["", " padded", "tab\tbed", "line\n"] {
+        assert!(db.create_table(name.to_string()).unwrap_err().contains("Invalid table name"));
+    }
+    assert!(db.list_tables().is_empty());
+}
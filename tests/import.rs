@@ -0,0 +1,67 @@
+use std::fs;
+
+use potatodb::{ColumnType, CsvSource, Database, ImportMapping};
+
+mod common;
+use common::{row, temp_path};
+
+fn products() -> Database {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE products (name TEXT, size ENUM('small', 'large'))").unwrap();
+    db
+}
+
+fn import_csv(db: &mut Database, name: &str, csv: &str, mapping: &ImportMapping) -> Result<potatodb::ImportReport, String> {
+    let path = temp_path(name, "csv");
+    fs::write(&path, csv).unwrap();
+    let report = db.import("products", &CsvSource::new(&path), mapping).map_err(|e| e.to_string());
+    fs::remove_file(path).unwrap();
+    report
+}
+
+#[test]
+fn columns_are_renamed_converted_and_defaulted() {
+    let mut db = products();
+    let mapping = ImportMapping::new()
+        .rename("Product Name", "name")
+        .rename("Qty", "stock")
+        .convert("stock", ColumnType::Integer)
+        .default_value("size", "small");
+    let csv = "Product Name,Qty,size\ntea, 4.0 ,large\njam,12,\n";
+    let report = import_csv(&mut db, "mapped", csv, &mapping).unwrap();
+    assert_eq!((report.loaded(), report.rejected()), (2, &[][..]));
+    assert_eq!(db.get("products", 1).unwrap().unwrap().data().to_map(), row(&[("name", "tea"), ("stock", "4"), ("size", "large")]));
+    assert_eq!(db.get("products", 2).unwrap().unwrap().data().to_map(), row(&[("name", "jam"), ("stock", "12"), ("size", "small")]));
+}
+
+#[test]
+fn invalid_rows_are_skipped_and_reported() {
+    let mut db = products();
+    let csv = "name,stock,size\ntea,4,small\njam,lots,small\nfig,1,huge\noat,2,large\n";
+    let mapping = ImportMapping::new().convert("stock", ColumnType::Integer);
+    // without skipping, a bad row loads nothing
+    assert!(import_csv(&mut db, "strict", csv, &mapping).unwrap_err().starts_with("Row 2: Cannot convert 'lots'"));
+    assert!(db.get_all("products").unwrap().is_empty());
+
+    let report = import_csv(&mut db, "skipped", csv, &mapping.skip_invalid()).unwrap();
+    assert_eq!(report.loaded(), 2);
+    let rejected: Vec<usize> = report.rejected().iter().map(|(position, _)| *position).collect();
+    assert_eq!(rejected, [2, 3]);
+    assert_eq!(report.rejected()[0].1, "Cannot convert 'lots' in column 'stock' to Integer");
+    let names: Vec<String> = db.get_all("products").unwrap().iter().map(|r| r.data()["name"].clone()).collect();
+    assert_eq!(names, ["tea", "oat"]);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_sources_are_mapped_too() {
+    let mut db = products();
+    let path = temp_path("mapped", "json");
+    fs::write(&path, "{\"title\": \"tea\", \"stock\": 3.0}\n{\"title\": \"jam\", \"stock\": null}\n").unwrap();
+    let mapping = ImportMapping::new().rename("title", "name").convert("stock", ColumnType::Integer).default_value("stock", "0");
+    let report = db.import("products", &potatodb::JsonSource::new(&path), &mapping).unwrap();
+    fs::remove_file(path).unwrap();
+    assert_eq!(report.loaded(), 2);
+    assert_eq!(db.get("products", 1).unwrap().unwrap().data().to_map(), row(&[("name", "tea"), ("stock", "3")]));
+    assert_eq!(db.get("products", 2).unwrap().unwrap().data()["stock"], "0");
+}
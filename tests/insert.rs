@@ -0,0 +1,46 @@
+use potatodb::{Database, ProtoMessage, ProtoType};
+
+fn people() -> Database {
+    let mut db = Database::new();
+    db.create_table("people".to_string()).unwrap();
+    db
+}
+
+#[test]
+fn rejects_columns_and_values_of_different_counts() {
+    let mut db = people();
+    assert_eq!(
+        db.execute_sql("INSERT INTO people (name, age, city) VALUES (Ann, 30)").unwrap_err(),
+        "INSERT into 'people' names 3 columns but gives 2 values; no value for 'city'"
+    );
+    assert_eq!(
+        db.execute_sql("INSERT INTO people (name) VALUES (Ann, 30, Oslo)").unwrap_err(),
+        "INSERT into 'people' names 1 column but gives 3 values; no column for '30', 'Oslo'"
+    );
+    assert!(db.execute_sql("INSERT INTO people VALUES (Ann)").is_err());
+    assert!(db.query_sql("SELECT * FROM people").unwrap().is_empty());
+}
+
+#[test]
+fn rejects_repeated_and_unknown_columns() {
+    let mut db = people();
+    assert_eq!(
+        db.execute_sql("INSERT INTO people (name, age, name) VALUES (Ann, 30, Bo)").unwrap_err(),
+        "INSERT into 'people' names column 'name' more than once"
+    );
+    db.set_table_proto("people", ProtoMessage::new("Person").field("name", 1, ProtoType::String).unwrap()).unwrap();
+    assert_eq!(
+        db.execute_sql("INSERT INTO people (name, height) VALUES (Ann, 170)").unwrap_err(),
+        "Column 'height' is not a field of message 'Person'"
+    );
+    assert!(db.query_sql("SELECT * FROM people").unwrap().is_empty());
+}
+
+#[test]
+fn accepts_spaced_lists_and_a_trailing_semicolon() {
+    let mut db = people();
+    db.execute_sql("INSERT INTO people ( name, age ) VALUES ( Ann, 30 );").unwrap();
+    let row = &db.query_sql("SELECT * FROM people").unwrap()[0];
+    assert_eq!(row.data()["name"], "Ann");
+    assert_eq!(row.data()["age"], "30");
+}
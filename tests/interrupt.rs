@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use potatodb::Database;
+
+fn big(rows: u64) -> Database {
+    let mut db = Database::new();
+    db.create_table("events".to_string()).unwrap();
+    for id in 1..=rows {
+        db.insert("events", id, HashMap::from([("kind".to_string(), (id % 7).to_string())])).unwrap();
+    }
+    db
+}
+
+#[test]
+fn statements_time_out() {
+    let mut db = big(2_000);
+    db.set_statement_timeout(Some(Duration::ZERO));
+    let error = db.query_sql("SELECT * FROM events WHERE kind = 3").unwrap_err();
+    assert!(error.starts_with("Query timed out"), "{}", error);
+    assert!(db.execute_sql("DELETE FROM events WHERE kind = 3").is_err());
+    assert_eq!(db.get_all("events").unwrap().len(), 2_000);
+
+    db.set_statement_timeout(Some(Duration::from_secs(60)));
+    assert_eq!(db.query_sql("SELECT * FROM events WHERE kind = 3").unwrap().len(), 286);
+    db.set_statement_timeout(None);
+    assert_eq!(db.execute_sql("DELETE FROM events WHERE kind = 3").unwrap().len(), 286);
+}
+
+#[test]
+fn handles_cancel_running_statements() {
+    let db = Arc::new(big(100_000));
+    let handle = db.query_handle();
+    let done = Arc::new(AtomicBool::new(false));
+    let canceller = {
+        let done = done.clone();
+        thread::spawn(move || {
+            while !done.load(Ordering::SeqCst) {
+                handle.cancel();
+                thread::sleep(Duration::from_millis(1));
+            }
+        })
+    };
+    let result = db.query_sql("SELECT * FROM events WHERE kind != 3");
+    done.store(true, Ordering::SeqCst);
+    canceller.join().unwrap();
+    assert_eq!(result.unwrap_err(), "Query cancelled");
+
+    // a cancel only stops the statement running at the time
+    assert_eq!(db.query_sql("SELECT * FROM events WHERE kind = 3").unwrap().len(), 14_286);
+}
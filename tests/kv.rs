@@ -0,0 +1,54 @@
+use potatodb::Database;
+
+mod common;
+use common::temp_path;
+
+#[test]
+fn get_set_and_delete() {
+    let mut db = Database::new();
+    let mut settings = db.kv("settings");
+    assert!(settings.is_empty());
+    assert_eq!(settings.set("theme", "dark"), None);
+    assert_eq!(settings.set("theme", "light"), Some(b"dark".to_vec()));
+    settings.set([0xff, 0x00], [0xff, 0xfe]);
+    assert_eq!(settings.get_str("theme"), Some("light"));
+    assert_eq!(settings.get([0xff, 0x00]), Some([0xff, 0xfe].as_slice()));
+    assert_eq!(settings.get_str([0xff, 0x00]), None);
+    assert_eq!(settings.len(), 2);
+
+    assert_eq!(settings.delete("theme"), Some(b"light".to_vec()));
+    assert_eq!(settings.delete("theme"), None);
+    assert_eq!(settings.get("theme"), None);
+
+    // namespaces are separate
+    assert_eq!(db.kv("other").get([0xff, 0x00]), None);
+    assert_eq!(db.kv_namespaces(), vec!["settings"]);
+    db.kv("settings").delete([0xff, 0x00]);
+    assert!(db.kv_namespaces().is_empty());
+}
+
+#[test]
+fn prefix_scans_are_in_key_order() {
+    let mut db = Database::new();
+    let mut flags = db.kv("flags");
+    for key in ["user:2", "user:10", "team:1", "user:1", "user"] {
+        flags.set(key, key.to_uppercase());
+    }
+    let keys: Vec<&[u8]> = flags.scan_prefix("user:").into_iter().map(|(k, _)| k).collect();
+    assert_eq!(keys, vec![b"user:1".as_slice(), b"user:10", b"user:2"]);
+    assert_eq!(flags.scan_prefix("").len(), 5);
+    assert!(flags.scan_prefix("zzz").is_empty());
+}
+
+#[test]
+fn namespaces_are_saved_with_the_tables() {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    db.kv("settings").set("theme", "dark");
+    let path = temp_path("kv", "bin");
+    db.save(path.to_str().unwrap()).unwrap();
+    let mut loaded = Database::load(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.kv("settings").get_str("theme"), Some("dark"));
+    assert_eq!(loaded.list_tables(), vec!["users"]);
+}
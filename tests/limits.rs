@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+use potatodb::{Database, QueryLimits};
+
+fn users(count: u64) -> Database {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    for id in 1..=count {
+        let plan = if id % 10 == 0 { "pro" } else { "free" };
+        db.insert("users", id, HashMap::from([("plan".to_string(), plan.to_string())])).unwrap();
+    }
+    db
+}
+
+#[test]
+fn result_rows_are_capped() {
+    let mut db = users(100);
+    db.set_query_limits(QueryLimits { max_result_rows: Some(10), ..QueryLimits::default() });
+    assert_eq!(db.query_sql("SELECT * FROM users WHERE plan = pro").unwrap().len(), 10);
+    let error = db.query_sql("SELECT * FROM users").unwrap_err();
+    assert_eq!(error, "Query exceeded the limit of 10 result rows");
+    // writes aren't results
+    assert_eq!(db.execute_sql("UPDATE users SET plan = team WHERE plan = free").unwrap().len(), 90);
+}
+
+#[test]
+fn scanned_rows_are_capped() {
+    let mut db = users(100);
+    db.set_query_limits(QueryLimits { max_scanned_rows: Some(50), ..QueryLimits::default() });
+    let error = db.query_sql("SELECT * FROM users WHERE plan = pro").unwrap_err();
+    assert_eq!(error, "Query exceeded the limit of 50 scanned rows");
+    assert!(db.execute_sql("DELETE FROM users WHERE plan = pro").is_err());
+    assert_eq!(db.get_all("users").unwrap().len(), 100);
+
+    db.set_query_limits(QueryLimits::default());
+    assert_eq!(db.query_sql("SELECT * FROM users WHERE plan = pro").unwrap().len(), 10);
+}
+
+#[test]
+fn held_memory_is_capped() {
+    let db = users(100);
+    let limits = QueryLimits { max_memory_bytes: Some(5_000), ..QueryLimits::default() };
+    let error = db.query_sql_with_limits("SELECT * FROM users", limits).unwrap_err();
+    assert_eq!(error, "Query exceeded the limit of 5000 bytes of memory");
+    assert_eq!(db.query_sql_with_limits("SELECT * FROM users WHERE plan = pro", limits).unwrap().len(), 10);
+}
+
+#[test]
+fn statement_limits_replace_the_database_ones() {
+    let mut db = users(100);
+    db.set_query_limits(QueryLimits { max_result_rows: Some(1), ..QueryLimits::default() });
+    let roomy = QueryLimits { max_result_rows: Some(1_000), ..QueryLimits::default() };
+    assert_eq!(db.query_sql_with_limits("SELECT * FROM users", roomy).unwrap().len(), 100);
+    assert!(db.query_sql("SELECT * FROM users").is_err());
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use potatodb::{Database, Mask, Privilege};
+
+fn setup() -> Database {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    let row = HashMap::from([
+        ("name".to_string(), "Alice".to_string()),
+        ("email".to_string(), "alice@example.com".to_string()),
+        ("card".to_string(), "4111111111111111".to_string()),
+        ("token".to_string(), "s3cr3t".to_string()),
+    ]);
+    db.insert("users", 1, row).unwrap();
+    db.mask_column("users", "email", Mask::Email);
+    db.mask_column("users", "card", Mask::KeepLast(4));
+    db.mask_column("users", "token", Mask::Redact);
+    db.create_role("support").unwrap();
+    db.grant("support", "users", &[Privilege::Select]).unwrap();
+    db.create_role("billing").unwrap();
+    db.grant("billing", "users", &[Privilege::Select, Privilege::Unmask]).unwrap();
+    db.create_user("sam", &["support"]).unwrap();
+    db.create_user("bea", &["billing"]).unwrap();
+    db
+}
+
+fn row(db: &Database) -> HashMap<String, String> {
+    db.query_sql("SELECT * FROM users").unwrap()[0].data().to_map()
+}
+
+#[test]
+fn unprivileged_sessions_see_masked_values() {
+    let mut db = setup();
+    db.set_session_user(Some("sam")).unwrap();
+    let row = row(&db);
+    assert_eq!(row["email"], "a****@example.com");
+    assert_eq!(row["card"], "****1111");
+    assert_eq!(row["token"], "****");
+    assert_eq!(row["name"], "Alice");
+    // stored values are unchanged
+    assert_eq!(db.get("users", 1).unwrap().unwrap().data()["token"], "s3cr3t");
+}
+
+#[test]
+fn unmask_privilege_and_the_application_see_stored_values() {
+    let mut db = setup();
+    db.set_session_user(Some("bea")).unwrap();
+    assert_eq!(row(&db)["token"], "s3cr3t");
+    db.set_session_user(None).unwrap();
+    assert_eq!(row(&db)["email"], "alice@example.com");
+
+    db.set_session_user(Some("sam")).unwrap();
+    db.unmask_column("users", "token");
+    assert_eq!(row(&db)["token"], "s3cr3t");
+}
+
+#[test]
+fn values_computed_from_masked_columns_are_masked() {
+    let mut db = setup();
+    db.set_session_user(Some("sam")).unwrap();
+    let rows = db.query_sql("SELECT COALESCE(email, 'x'), CAST(token AS TEXT) FROM users").unwrap();
+    assert_eq!(rows[0].data()["COALESCE(email, 'x')"], "a****@example.com");
+    assert_eq!(rows[0].data()["CAST(token AS TEXT)"], "****");
+
+    let rows = db.aggregate("users").group_all(&[("top", "max(email)"), ("all", "string_agg(card, ',')")]).run().unwrap();
+    assert_eq!(rows[0].data()["top"], "a****@example.com");
+    assert_eq!(rows[0].data()["all"], "****1111");
+}
+
+#[test]
+fn masked_columns_sort_by_what_the_session_sees() {
+    let mut db = setup();
+    for (id, token) in [(2, "a1"), (3, "m2")] {
+        db.insert("users", id, HashMap::from([("token".to_string(), token.to_string())])).unwrap();
+    }
+    let ids = |db: &Database| db.query_sql("SELECT * FROM users ORDER BY token").unwrap().iter().map(|r| r.id()).collect::<Vec<_>>();
+    assert_eq!(ids(&db), [2, 3, 1]);
+    // every token reads as `****`, so the real order does not show
+    db.set_session_user(Some("sam")).unwrap();
+    assert_eq!(ids(&db), [1, 2, 3]);
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "metrics")]
+
+use std::collections::HashMap;
+
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+use potatodb::Database;
+
+fn read<'a>(snapshot: &'a [(String, Vec<String>, DebugValue)], name: &str, label: &str) -> Option<&'a DebugValue> {
+    snapshot.iter().find(|(n, labels, _)| n == name && labels.iter().any(|l| l == label)).map(|(_, _, v)| v)
+}
+
+#[test]
+fn queries_and_scans_are_counted() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    metrics::with_local_recorder(&recorder, || {
+        let mut db = Database::new();
+        db.create_table("users".to_string()).unwrap();
+        for id in 1..=3 {
+            db.insert("users", id, HashMap::from([("name".to_string(), id.to_string())])).unwrap();
+        }
+        db.query_sql("SELECT * FROM users WHERE name = 2").unwrap();
+        db.execute_sql("DELETE FROM users WHERE name = 3").unwrap();
+        assert!(db.query_sql("SELECT * FROM missing").is_err());
+    });
+
+    let snapshot: Vec<_> = snapshotter.snapshot().into_vec().into_iter()
+        .map(|(key, _, _, value)| {
+            let labels = key.key().labels().map(|l| format!("{}={}", l.key(), l.value())).collect();
+            (key.key().name().to_string(), labels, value)
+        })
+        .collect();
+    assert_eq!(read(&snapshot, "potatodb_queries_total", "kind=select"), Some(&DebugValue::Counter(2)));
+    assert_eq!(read(&snapshot, "potatodb_queries_total", "kind=delete"), Some(&DebugValue::Counter(1)));
+    assert_eq!(read(&snapshot, "potatodb_query_errors_total", "kind=select"), Some(&DebugValue::Counter(1)));
+    assert_eq!(read(&snapshot, "potatodb_rows_scanned_total", "table=users"), Some(&DebugValue::Counter(6)));
+    assert!(matches!(read(&snapshot, "potatodb_query_duration_seconds", "kind=select"), Some(DebugValue::Histogram(h)) if h.len() == 2));
+}
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+
+use potatodb::{Database, Migration, MigrationStep, SCHEMA_VERSION_TABLE};
+
+fn add_admin(db: &mut Database) -> Result<(), String> {
+    db.insert("users", 100, HashMap::from([("name".to_string(), "admin".to_string())]))
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, name: "create users", step: MigrationStep::Sql("CREATE TABLE users; INSERT INTO users (name) VALUES (Alice)") },
+    Migration { version: 2, name: "add admin", step: MigrationStep::Rust(add_admin) },
+];
+
+#[test]
+fn pending_migrations_are_applied_once() {
+    let mut db = Database::new();
+    assert_eq!(db.schema_version(), 0);
+    assert_eq!(db.migrate(MIGRATIONS).unwrap(), vec![1, 2]);
+    assert_eq!(db.schema_version(), 2);
+    assert_eq!(db.get_all("users").unwrap().len(), 2);
+    assert_eq!(db.get(SCHEMA_VERSION_TABLE, 2).unwrap().unwrap().data()["name"], "add admin");
+
+    assert!(db.migrate(MIGRATIONS).unwrap().is_empty());
+    assert_eq!(db.get_all("users").unwrap().len(), 2);
+}
+
+#[test]
+fn failing_migrations_change_nothing() {
+    let mut db = Database::new();
+    db.migrate(&MIGRATIONS[..1]).unwrap();
+    let broken = [
+        MIGRATIONS[0],
+        MIGRATIONS[1],
+        Migration { version: 3, name: "broken", step: MigrationStep::Sql("INSERT INTO missing (a) VALUES (b)") },
+    ];
+    let err = db.migrate(&broken).unwrap_err();
+    assert!(err.contains("Migration 3"), "{}", err);
+    assert_eq!(db.schema_version(), 1);
+    assert_eq!(db.get_all("users").unwrap().len(), 1);
+}
+
+#[test]
+fn dry_runs_report_without_applying() {
+    let mut db = Database::new();
+    db.migrate(&MIGRATIONS[..1]).unwrap();
+    assert_eq!(db.migrate_dry_run(MIGRATIONS).unwrap(), vec![2]);
+    assert_eq!(db.schema_version(), 1);
+    assert!(db.get("users", 100).unwrap().is_none());
+}
+
+#[test]
+fn migrations_must_be_in_order() {
+    let mut db = Database::new();
+    let reversed = [MIGRATIONS[1], MIGRATIONS[0]];
+    assert!(db.migrate(&reversed).is_err());
+    assert_eq!(db.schema_version(), 0);
+}
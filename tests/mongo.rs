@@ -0,0 +1,63 @@
+#![cfg(feature = "mongo")]
+
+use potatodb::Database;
+
+mod common;
+use common::temp_path;
+
+const EXPORT: &str = r#"{"_id":{"$oid":"5f1d7f0b9d3e2a0017c1a001"},"name":"Alice","age":30,"address":{"city":"Oslo","zip":"0150"},"tags":["a","b"]}
+{"_id":{"$oid":"5f1d7f0b9d3e2a0017c1a002"},"name":"Bob","active":true,"missing":null}
+"#;
+
+#[test]
+fn imports_mongoexport_json_with_flattened_documents() {
+    let path = temp_path("mongo-import", "json");
+    std::fs::write(&path, EXPORT).unwrap();
+
+    let mut db = Database::new();
+    assert_eq!(db.import_mongo_json("people", &path).unwrap(), 2);
+    let alice = db.get("people", 1).unwrap().unwrap().data();
+    assert_eq!(alice["address.city"], "Oslo");
+    assert_eq!(alice["address.zip"], "0150");
+    assert_eq!(alice["age"], "30");
+    assert_eq!(alice["_id"], r#"{"$oid":"5f1d7f0b9d3e2a0017c1a001"}"#);
+    let bob = db.get("people", 2).unwrap().unwrap().data();
+    assert!(!bob.contains_key("missing"));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn bson_round_trip_restores_types_and_nesting() {
+    let source = temp_path("mongo-source", "json");
+    std::fs::write(&source, EXPORT).unwrap();
+    let mut db = Database::new();
+    db.import_mongo_json("people", &source).unwrap();
+
+    let dump = temp_path("mongo-dump", "bson");
+    assert_eq!(db.export_bson("people", &dump).unwrap(), 2);
+    let mut copy = Database::new();
+    copy.import_bson("people", &dump).unwrap();
+    assert_eq!(copy.get("people", 1).unwrap().unwrap().data(), db.get("people", 1).unwrap().unwrap().data());
+
+    let json = temp_path("mongo-export", "json");
+    copy.export_mongo_json("people", &json).unwrap();
+    let first_line = std::fs::read_to_string(&json).unwrap().lines().next().unwrap().to_string();
+    assert!(first_line.starts_with(r#"{"_id":{"$oid":"5f1d7f0b9d3e2a0017c1a001"}"#));
+    assert!(first_line.contains(r#""address":{"city":"Oslo","zip":"0150"}"#));
+    assert!(first_line.contains(r#""age":30"#));
+    assert!(first_line.contains(r#""tags":["a","b"]"#));
+    for path in [source, dump, json] {
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn failed_import_leaves_the_table_untouched() {
+    let path = temp_path("mongo-clash", "json");
+    std::fs::write(&path, "{\"a\":1}\n{\"a\":{\"b\":1},\"a.b\":2}\n").unwrap();
+
+    let mut db = Database::new();
+    assert!(db.import_mongo_json("t", &path).is_err());
+    assert!(db.list_tables().is_empty());
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use potatodb::{Database, QueryObserver, Record};
+
+#[derive(Default)]
+struct Profiler {
+    events: Mutex<Vec<String>>,
+}
+
+impl QueryObserver for Profiler {
+    fn on_plan(&self, sql: &str, plan: &str) {
+        self.events.lock().unwrap().push(format!("plan {}: {}", sql, plan));
+    }
+
+    fn on_row(&self, _sql: &str, record: &Record) {
+        self.events.lock().unwrap().push(format!("row {}", record.id()));
+    }
+
+    fn on_execute(&self, _sql: &str, _duration: Duration, result: Result<usize, &str>) {
+        self.events.lock().unwrap().push(format!("done {:?}", result));
+    }
+}
+
+// Allows a fixed number of statements, and points queries at a renamed table.
+struct Limiter {
+    remaining: AtomicUsize,
+}
+
+impl QueryObserver for Limiter {
+    fn rewrite(&self, sql: &str) -> Result<Option<String>, String> {
+        if self.remaining.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_err() {
+            return Err("rate limit exceeded".to_string());
+        }
+        Ok(sql.contains("old_users").then(|| sql.replace("old_users", "users")))
+    }
+}
+
+fn users() -> Database {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    for (id, name) in [(1, "Alice"), (2, "Bob")] {
+        db.insert("users", id, HashMap::from([("name".to_string(), name.to_string())])).unwrap();
+    }
+    db
+}
+
+#[test]
+fn observers_see_each_stage() {
+    let mut db = users();
+    let profiler = Arc::new(Profiler::default());
+    db.add_query_observer(profiler.clone());
+
+    db.query_sql("SELECT * FROM users").unwrap();
+    assert!(db.execute_sql("DELETE FROM missing").is_err());
+
+    assert_eq!(*profiler.events.lock().unwrap(), [
+        "plan SELECT * FROM users: full scan of users (2 rows)",
+        "row 1",
+        "row 2",
+        "done Ok(2)",
+        "plan DELETE FROM missing: table missing not found",
+        "done Err(\"Table not found\")",
+    ]);
+
+    db.clear_query_observers();
+    db.query_sql("SELECT * FROM users").unwrap();
+    assert_eq!(profiler.events.lock().unwrap().len(), 6);
+}
+
+#[test]
+fn observers_can_rewrite_and_reject_statements() {
+    let mut db = users();
+    db.add_query_observer(Arc::new(Limiter { remaining: AtomicUsize::new(2) }));
+
+    assert_eq!(db.query_sql("SELECT * FROM old_users WHERE name = Bob").unwrap()[0].id(), 2);
+    db.execute_sql("DELETE FROM old_users WHERE name = Alice").unwrap();
+    assert_eq!(db.query_sql("SELECT * FROM users").unwrap_err(), "rate limit exceeded");
+    assert_eq!(db.get_all("users").unwrap().len(), 1);
+}
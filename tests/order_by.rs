@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use potatodb::Database;
+
+fn releases() -> Database {
+    let mut db = Database::new();
+    db.create_table("releases".to_string()).unwrap();
+    let rows: [&[(&str, &str)]; 5] = [
+        &[("channel", "stable"), ("version", "1.10.0"), ("downloads", "9")],
+        &[("channel", "beta"), ("version", "1.9.2"), ("downloads", "100")],
+        &[("channel", "stable"), ("version", "1.9.0"), ("downloads", "40")],
+        &[("channel", "beta"), ("version", "2.0.0")],
+        &[("channel", "stable"), ("version", "1.2.0"), ("downloads", "40")],
+    ];
+    for (id, row) in (1..).zip(rows) {
+        db.insert("releases", id, row.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<HashMap<_, _>>()).unwrap();
+    }
+    db
+}
+
+fn ids(db: &Database, sql: &str) -> Vec<u64> {
+    db.query_sql(sql).unwrap().iter().map(|r| r.id()).collect()
+}
+
+#[test]
+fn sorts_by_compound_keys() {
+    let db = releases();
+    assert_eq!(ids(&db, "SELECT * FROM releases ORDER BY downloads"), vec![4, 1, 3, 5, 2]);
+    assert_eq!(ids(&db, "SELECT version FROM releases WHERE channel = stable ORDER BY downloads DESC, version"), vec![5, 3, 1]);
+    assert_eq!(ids(&db, "SELECT * FROM releases ORDER BY channel, downloads DESC;"), vec![2, 4, 3, 5, 1]);
+}
+
+#[test]
+fn places_missing_values_as_asked() {
+    let mut db = releases();
+    assert_eq!(ids(&db, "SELECT * FROM releases ORDER BY downloads DESC"), vec![2, 3, 5, 1, 4]);
+    assert_eq!(ids(&db, "SELECT * FROM releases ORDER BY downloads NULLS LAST"), vec![1, 3, 5, 2, 4]);
+    assert_eq!(ids(&db, "SELECT * FROM releases ORDER BY downloads DESC NULLS FIRST"), vec![4, 2, 3, 5, 1]);
+
+    db.set_setting("nulls", "last").unwrap();
+    assert_eq!(ids(&db, "SELECT * FROM releases ORDER BY downloads DESC"), vec![2, 3, 5, 1, 4]);
+    assert_eq!(ids(&db, "SELECT * FROM releases ORDER BY downloads NULLS FIRST"), vec![4, 1, 3, 5, 2]);
+}
+
+#[test]
+fn sorts_with_collations() {
+    let mut db = releases();
+    assert_eq!(ids(&db, "SELECT * FROM releases ORDER BY version"), vec![1, 5, 3, 2, 4]);
+    assert_eq!(ids(&db, "SELECT * FROM releases ORDER BY version COLLATE natural"), vec![5, 3, 2, 1, 4]);
+
+    db.register_collation("semver_desc", |a, b| {
+        let parts = |v: &str| v.split('.').map(|p| p.parse::<u64>().unwrap_or(0)).collect::<Vec<_>>();
+        parts(b).cmp(&parts(a))
+    })
+    .unwrap();
+    assert_eq!(ids(&db, "SELECT * FROM releases ORDER BY version COLLATE semver_desc"), vec![4, 1, 2, 3, 5]);
+    assert_eq!(ids(&db, "SELECT * FROM releases ORDER BY channel DESC, version COLLATE semver_desc ASC"), vec![1, 3, 5, 4, 2]);
+
+    assert!(db.register_collation("natural", |a: &str, b: &str| a.cmp(b)).is_err());
+    assert_eq!(db.query_sql("SELECT * FROM releases ORDER BY version COLLATE missing").unwrap_err(), "Collation 'missing' not found");
+}
+
+#[test]
+fn explains_the_sort() {
+    let db = releases();
+    let plan = db.query_sql("EXPLAIN ANALYZE SELECT * FROM releases WHERE channel = beta ORDER BY version DESC NULLS LAST").unwrap();
+    let sort = plan.iter().find(|r| r.data()["node"] == "sort").unwrap();
+    assert_eq!(sort.data()["detail"], "version DESC NULLS LAST");
+    assert!(db.query_sql("SELECT * FROM releases ORDER BY version sideways").is_err());
+}
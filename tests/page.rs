@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+
+use potatodb::Database;
+
+fn numbers(count: u64) -> Database {
+    let mut db = Database::new();
+    db.create_table("numbers".to_string()).unwrap();
+    for id in 1..=count {
+        let parity = if id % 2 == 0 { "even" } else { "odd" };
+        let data = HashMap::from([("n".to_string(), id.to_string()), ("parity".to_string(), parity.to_string())]);
+        db.insert("numbers", id, data).unwrap();
+    }
+    db
+}
+
+fn ids(db: &Database, sql: &str) -> Vec<u64> {
+    db.query_sql(sql).unwrap().iter().map(|r| r.id()).collect()
+}
+
+#[test]
+fn limit_and_offset_page_through_rows() {
+    let db = numbers(10);
+    assert_eq!(ids(&db, "SELECT * FROM numbers LIMIT 3"), [1, 2, 3]);
+    assert_eq!(ids(&db, "SELECT * FROM numbers LIMIT 3 OFFSET 3"), [4, 5, 6]);
+    assert_eq!(ids(&db, "SELECT * FROM numbers LIMIT 3 OFFSET 9"), [10]);
+    assert_eq!(ids(&db, "SELECT * FROM numbers OFFSET 8"), [9, 10]);
+    assert_eq!(ids(&db, "select n from numbers limit 0"), [] as [u64; 0]);
+    assert_eq!(ids(&db, "SELECT * FROM numbers WHERE parity = odd LIMIT 2 OFFSET 1"), [3, 5]);
+    // sorted rows are paged after sorting
+    assert_eq!(ids(&db, "SELECT * FROM numbers ORDER BY n DESC LIMIT 2 OFFSET 1"), [9, 8]);
+    assert_eq!(ids(&db, "SELECT * FROM numbers WHERE parity = even ORDER BY n DESC LIMIT 2;"), [10, 8]);
+}
+
+#[test]
+fn unsorted_pages_stop_reading_once_full() {
+    let db = numbers(5000);
+    let (records, stats) = db.query_sql_with_stats("SELECT * FROM numbers LIMIT 10 OFFSET 20").unwrap();
+    assert_eq!(records.len(), 10);
+    assert_eq!(records[0].id(), 21);
+    assert!(stats.rows_scanned < 5000, "scanned {} rows", stats.rows_scanned);
+}
+
+#[test]
+fn invalid_limits_are_rejected() {
+    let db = numbers(3);
+    assert_eq!(db.query_sql("SELECT * FROM numbers LIMIT ten").unwrap_err(), "Invalid LIMIT 'ten'");
+    assert_eq!(db.query_sql("SELECT * FROM numbers LIMIT 1 OFFSET -1").unwrap_err(), "Invalid OFFSET '-1'");
+    assert!(db.query_sql("SELECT * FROM numbers LIMIT").unwrap_err().starts_with("Unexpected end of statement"));
+}
+
+#[test]
+fn explain_and_streaming_page_too() {
+    let mut db = numbers(10);
+    let plan = db.query_sql("EXPLAIN SELECT * FROM numbers WHERE parity = odd LIMIT 2 OFFSET 1").unwrap();
+    let limit = plan.iter().find(|r| r.data()["node"] == "limit").unwrap();
+    assert_eq!(limit.data()["detail"], "LIMIT 2 OFFSET 1");
+    assert_eq!(limit.data()["estimated_rows"], "2");
+
+    let mut streamed = Vec::new();
+    let count = db.execute_sql_streaming("SELECT * FROM numbers LIMIT 3 OFFSET 4", |record| {
+        streamed.push(record.id());
+        ControlFlow::Continue(())
+    }).unwrap();
+    assert_eq!((count, streamed), (3, vec![5, 6, 7]));
+}
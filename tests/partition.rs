@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use potatodb::{Database, PartitionScheme};
+
+fn events() -> Database {
+    let mut db = Database::new();
+    db.create_table("events".to_string()).unwrap();
+    for (id, date) in [(1, "2024-01-05"), (2, "2024-02-10"), (3, "2024-03-15"), (4, "2024-02-28")] {
+        db.insert("events", id, HashMap::from([("date".to_string(), date.to_string())])).unwrap();
+    }
+    db.insert("events", 5, HashMap::new()).unwrap();
+    let bounds = vec!["2024-02-01".to_string(), "2024-03-01".to_string()];
+    db.partition_table("events", PartitionScheme::Range { column: "date".to_string(), bounds }).unwrap();
+    db
+}
+
+fn ids(db: &Database, sql: &str) -> Vec<u64> {
+    db.query_sql(sql).unwrap().iter().map(|r| r.id()).collect()
+}
+
+#[test]
+fn range_partitions_are_pruned_by_where_clauses() {
+    let db = events();
+    assert_eq!(db.scanned_partitions("SELECT * FROM events WHERE date = 2024-02-10").unwrap(), Some(vec![1]));
+    assert_eq!(db.scanned_partitions("SELECT * FROM events WHERE date > 2024-02-15").unwrap(), Some(vec![1, 2]));
+    assert_eq!(db.scanned_partitions("SELECT * FROM events WHERE date < 2024-01-31").unwrap(), Some(vec![0]));
+    assert_eq!(db.scanned_partitions("SELECT * FROM events WHERE date != 2024-01-05").unwrap(), Some(vec![0, 1, 2, 3]));
+
+    assert_eq!(ids(&db, "SELECT * FROM events WHERE date > 2024-02-15"), vec![3, 4]);
+    assert_eq!(ids(&db, "SELECT * FROM events WHERE date != 2024-01-05"), vec![2, 3, 4, 5]);
+}
+
+#[test]
+fn writes_move_records_between_partitions() {
+    let mut db = events();
+    db.execute_sql("UPDATE events SET date = 2024-03-20 WHERE date = 2024-01-05").unwrap();
+    assert_eq!(ids(&db, "SELECT * FROM events WHERE date > 2024-03-01"), vec![1, 3]);
+    assert!(ids(&db, "SELECT * FROM events WHERE date < 2024-02-01").is_empty());
+
+    db.execute_sql("DELETE FROM events WHERE date = 2024-03-15").unwrap();
+    db.execute_sql("INSERT INTO events (date) VALUES (2024-01-01)").unwrap();
+    assert_eq!(ids(&db, "SELECT * FROM events WHERE date > 2024-03-01"), vec![1]);
+    assert_eq!(ids(&db, "SELECT * FROM events WHERE date < 2024-02-01").len(), 1);
+}
+
+#[test]
+fn hash_partitions_prune_equality_and_survive_a_reload() {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    db.partition_table("users", PartitionScheme::Hash { column: "name".to_string(), partitions: 4 }).unwrap();
+    for (id, name) in [(1, "ann"), (2, "bob"), (3, "cy")] {
+        db.insert("users", id, HashMap::from([("name".to_string(), name.to_string())])).unwrap();
+    }
+
+    let copy = Database::from_bytes(&db.to_bytes().unwrap()).unwrap();
+    assert_eq!(copy.scanned_partitions("SELECT * FROM users WHERE name = bob").unwrap().unwrap().len(), 1);
+    assert_eq!(ids(&copy, "SELECT * FROM users WHERE name = bob"), vec![2]);
+    assert!(db.partition_table("users", PartitionScheme::Hash { column: "name".to_string(), partitions: 0 }).is_err());
+}
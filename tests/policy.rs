@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+use potatodb::Database;
+
+fn order(tenant: &str, item: &str) -> HashMap<String, String> {
+    HashMap::from([("tenant".to_string(), tenant.to_string()), ("item".to_string(), item.to_string())])
+}
+
+fn setup() -> Database {
+    let mut db = Database::new();
+    db.create_table("orders".to_string()).unwrap();
+    db.insert("orders", 1, order("acme", "pen")).unwrap();
+    db.insert("orders", 2, order("acme", "ink")).unwrap();
+    db.insert("orders", 3, order("globex", "pen")).unwrap();
+    db.add_policy("orders", "tenant = $tenant").unwrap();
+    db
+}
+
+fn items(db: &Database, sql: &str) -> Vec<String> {
+    let mut items: Vec<String> = db.query_sql(sql).unwrap().iter().map(|r| r.data()["item"].clone()).collect();
+    items.sort();
+    items
+}
+
+#[test]
+fn selects_only_see_the_sessions_rows() {
+    let mut db = setup();
+    db.set_session_attribute("tenant", "acme");
+    assert_eq!(items(&db, "SELECT * FROM orders"), vec!["ink", "pen"]);
+    assert_eq!(items(&db, "SELECT * FROM orders WHERE item = pen"), vec!["pen"]);
+    assert!(db.query_sql("EXPLAIN SELECT * FROM orders").unwrap()[1].data()["detail"].contains("acme"));
+
+    db.set_session_attribute("tenant", "globex");
+    assert_eq!(items(&db, "SELECT * FROM orders"), vec!["pen"]);
+}
+
+#[test]
+fn writes_are_limited_to_the_sessions_rows() {
+    let mut db = setup();
+    db.set_session_attribute("tenant", "globex");
+    assert_eq!(db.execute_sql("DELETE FROM orders WHERE item = pen").unwrap().len(), 1);
+    assert_eq!(db.execute_sql("UPDATE orders SET item = nib WHERE item = ink").unwrap().len(), 0);
+    assert!(db.execute_sql("UPDATE orders SET tenant = globex WHERE item = ink").is_err());
+
+    db.execute_sql("INSERT INTO orders (tenant, item) VALUES (globex, cap)").unwrap();
+    let err = db.execute_sql("INSERT INTO orders (tenant, item) VALUES (acme, cap)").unwrap_err();
+    assert!(err.contains("policy"), "{}", err);
+    assert!(db.execute_sql("INSERT INTO orders (item) VALUES (cap)").is_err());
+
+    // the Rust API is not limited
+    assert_eq!(db.get_all("orders").unwrap().len(), 3);
+}
+
+#[test]
+fn missing_attributes_fail_closed() {
+    let mut db = setup();
+    let err = db.query_sql("SELECT * FROM orders").unwrap_err();
+    assert!(err.contains("tenant"), "{}", err);
+    db.set_session_attribute("tenant", "acme");
+    db.clear_session_attributes();
+    assert!(db.query_sql("SELECT * FROM orders").is_err());
+}
+
+#[test]
+fn rows_must_match_every_policy() {
+    let mut db = setup();
+    db.add_policy("orders", "item != ink").unwrap();
+    db.set_session_attribute("tenant", "acme");
+    assert_eq!(items(&db, "SELECT * FROM orders"), vec!["pen"]);
+
+    db.clear_policies("orders");
+    assert_eq!(items(&db, "SELECT * FROM orders").len(), 3);
+    assert!(db.add_policy("orders", "tenant ~ acme").is_err());
+}
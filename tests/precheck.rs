@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use potatodb::{Database, EMPTY_STATEMENT, UNEXPECTED_END};
+
+fn users() -> Database {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    db.insert("users", 1, HashMap::from([("name".to_string(), "Alice".to_string())])).unwrap();
+    db
+}
+
+#[test]
+fn empty_statements_are_errors() {
+    let mut db = users();
+    for sql in ["", "   ", "\n\t", ";", " ; ", "/* nothing */"] {
+        assert_eq!(db.query_sql(sql).unwrap_err(), EMPTY_STATEMENT);
+        assert_eq!(db.execute_sql(sql).unwrap_err(), EMPTY_STATEMENT);
+    }
+    assert_eq!(db.query_sql("EXPLAIN").unwrap_err(), format!("{} after 'EXPLAIN'", UNEXPECTED_END));
+}
+
+#[test]
+fn truncated_statements_name_where_they_stop() {
+    let mut db = users();
+    let cases = [
+        ("SELECT", "SELECT"),
+        ("SELECT * FROM", "FROM"),
+        ("SELECT name,", "name,"),
+        ("SELECT * FROM users WHERE", "WHERE"),
+        ("SELECT * FROM users WHERE name", "name"),
+        ("SELECT * FROM users WHERE name =", "="),
+        ("SELECT * FROM users WHERE name = Alice AND", "AND"),
+        ("SELECT * FROM users WHERE name BETWEEN a", "a"),
+        ("SELECT * FROM users ORDER BY", "BY"),
+        ("INSERT INTO", "INTO"),
+        ("INSERT INTO users (name) VALUES", "VALUES"),
+        ("UPDATE users SET name", "name"),
+        ("UPDATE users SET name = Bob WHERE", "WHERE"),
+        ("DELETE FROM users WHERE;", "WHERE"),
+        ("GRAPH", "GRAPH"),
+        ("CREATE TABLE", "TABLE"),
+    ];
+    for (sql, last) in cases {
+        assert_eq!(db.execute_sql(sql).unwrap_err(), format!("{} after '{}'", UNEXPECTED_END, last), "{}", sql);
+    }
+    assert_eq!(db.query_sql("SELECT * FROM users").unwrap().len(), 1);
+}
+
+#[test]
+fn keywords_compared_against_are_values() {
+    let mut db = users();
+    db.execute_sql("UPDATE users SET name = from WHERE name = Alice").unwrap();
+    assert_eq!(db.query_sql("SELECT * FROM users WHERE name = from").unwrap().len(), 1);
+    assert_eq!(db.query_sql("SELECT * FROM users WHERE name BETWEEN a AND g").unwrap().len(), 1);
+    assert!(db.execute_sql("INSERT INTO VALUES (1)").is_err());
+    assert!(db.execute_sql("UPDATE users SET name Bob").is_err());
+}
+
+#[test]
+fn where_clauses_that_cannot_be_read_are_errors() {
+    let mut db = users();
+    assert_eq!(db.execute_sql("DELETE FROM users WHERE name ~ Alice").unwrap_err(), "Unsupported operator '~'");
+    assert_eq!(db.execute_sql("DELETE FROM users WHERE name = Alice id = 1").unwrap_err(), "Unexpected 'id' in WHERE clause");
+    assert_eq!(db.execute_sql("UPDATE users SET name = Bob WHERE name Alice").unwrap_err(), "Incomplete condition 'name Alice'");
+    assert_eq!(db.query_sql("SELECT * FROM users name = Alice").unwrap_err(), "Unexpected 'name'");
+    assert!(db.query_sql("SELECT * FROM users WHERE id BETWEEN 1 2").is_err());
+    assert_eq!(db.get_all("users").unwrap()[0].data()["name"], "Alice");
+}
+
+#[test]
+fn a_trailing_semicolon_ends_the_statement() {
+    let mut db = users();
+    db.insert("users", 2, HashMap::from([("name".to_string(), "Bob".to_string())])).unwrap();
+    assert_eq!(db.query_sql("SELECT * FROM users WHERE name = Alice;").unwrap().len(), 1);
+    assert_eq!(db.query_sql("SELECT * FROM users WHERE name = Alice ;").unwrap().len(), 1);
+    assert_eq!(db.execute_sql("UPDATE users SET name = Cy WHERE name = Alice;").unwrap().len(), 1);
+    assert_eq!(db.get("users", 1).unwrap().unwrap().data()["name"], "Cy");
+    assert_eq!(db.execute_sql("DELETE FROM users WHERE name = Bob;").unwrap().len(), 1);
+    assert_eq!(db.get_all("users").unwrap().len(), 1);
+}
+
+#[test]
+fn selects_without_columns_are_errors() {
+    let db = users();
+    assert_eq!(db.query_sql("SELECT FROM users").unwrap_err(), "SELECT names no columns");
+    assert_eq!(db.query_sql("SELECT , FROM users").unwrap_err(), "SELECT names no columns");
+}
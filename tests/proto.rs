@@ -0,0 +1,76 @@
+use potatodb::{Database, ProtoMessage, ProtoType, Row};
+
+mod common;
+use common::row;
+
+fn person() -> ProtoMessage {
+    ProtoMessage::new("Person")
+        .field("name", 1, ProtoType::String).unwrap()
+        .field("age", 2, ProtoType::Int32).unwrap()
+        .field("rank", 3, ProtoType::Sint64).unwrap()
+}
+
+#[test]
+fn encodes_to_standard_protobuf_bytes() {
+    let bytes = person().encode(&row(&[("name", "Alice"), ("age", "30"), ("rank", "-2")]).into()).unwrap();
+    assert_eq!(bytes, [0x0a, 5, b'A', b'l', b'i', b'c', b'e', 0x10, 0x1e, 0x18, 0x03]);
+}
+
+#[test]
+fn every_type_round_trips() {
+    let message = ProtoMessage::new("All")
+        .field("i32", 1, ProtoType::Int32).unwrap()
+        .field("i64", 2, ProtoType::Int64).unwrap()
+        .field("u32", 3, ProtoType::Uint32).unwrap()
+        .field("u64", 4, ProtoType::Uint64).unwrap()
+        .field("s32", 5, ProtoType::Sint32).unwrap()
+        .field("s64", 6, ProtoType::Sint64).unwrap()
+        .field("b", 7, ProtoType::Bool).unwrap()
+        .field("d", 8, ProtoType::Double).unwrap()
+        .field("f", 9, ProtoType::Float).unwrap()
+        .field("s", 10, ProtoType::String).unwrap();
+    let data: Row = row(&[
+        ("i32", "-5"), ("i64", "-9223372036854775808"), ("u32", "4294967295"),
+        ("u64", "18446744073709551615"), ("s32", "-2147483648"), ("s64", "9223372036854775807"),
+        ("b", "true"), ("d", "1.5"), ("f", "-0.25"), ("s", "héllo"),
+    ]).into();
+    assert_eq!(message.decode(&message.encode(&data).unwrap()).unwrap(), data);
+}
+
+#[test]
+fn rejects_invalid_field_definitions() {
+    assert!(ProtoMessage::new("M").field("a", 0, ProtoType::Int32).is_err());
+    assert!(ProtoMessage::new("M").field("a", 1 << 29, ProtoType::Int32).is_err());
+    assert!(ProtoMessage::new("M").field("a", 19500, ProtoType::Int32).is_err());
+    let m = ProtoMessage::new("M").field("a", 1, ProtoType::Int32).unwrap();
+    assert!(m.clone().field("a", 2, ProtoType::Int32).is_err());
+    assert!(m.field("b", 1, ProtoType::Int32).is_err());
+}
+
+#[test]
+fn decode_rejects_out_of_range_values() {
+    let u32_field = ProtoMessage::new("M").field("n", 1, ProtoType::Uint32).unwrap();
+    // field 1, varint 2^32
+    assert!(u32_field.decode(&[0x08, 0x80, 0x80, 0x80, 0x80, 0x10]).is_err());
+
+    let i32_field = ProtoMessage::new("M").field("n", 1, ProtoType::Int32).unwrap();
+    // field 1, varint 2^31
+    assert!(i32_field.decode(&[0x08, 0x80, 0x80, 0x80, 0x80, 0x08]).is_err());
+}
+
+#[test]
+fn proto_tables_persist_and_map_to_sql_columns() {
+    let mut db = Database::new();
+    db.create_table("people".to_string()).unwrap();
+    db.set_table_proto("people", person()).unwrap();
+    db.execute_sql("INSERT INTO people (name, age) VALUES (Bob, 41)").unwrap();
+    assert!(db.execute_sql("INSERT INTO people (name, age) VALUES (Eve, old)").is_err());
+    assert!(db.execute_sql("SELECT height FROM people").is_err());
+    assert_eq!(db.columns("people").unwrap(), vec!["name", "age", "rank"]);
+
+    let copy = Database::from_bytes(&db.to_bytes().unwrap()).unwrap();
+    let rows = copy.query_sql("SELECT name, age FROM people").unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].data()["age"], "41");
+    assert!(copy.get_proto("people", rows[0].id()).unwrap().is_some());
+}
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use potatodb::{Database, ID};
+
+fn people() -> Database {
+    let mut db = Database::new();
+    db.create_table("people".to_string()).unwrap();
+    for (id, name) in [(3, "Ann"), (10, "Bob"), (5, "Cy")] {
+        db.insert("people", id, HashMap::from([("name".to_string(), name.to_string())])).unwrap();
+    }
+    db
+}
+
+fn ids(db: &Database, sql: &str) -> Vec<u64> {
+    db.query_sql(sql).unwrap().iter().map(|r| r.id()).collect()
+}
+
+#[test]
+fn where_reads_the_record_id() {
+    let db = people();
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE id = 5"), [5]);
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE id = 4"), [] as [u64; 0]);
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE id > 4"), [10, 5]);
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE id != 5 AND name = Bob"), [10]);
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE id BETWEEN 4 AND 10 ORDER BY id"), [5, 10]);
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE name = Ann OR id = 10"), [3, 10]);
+    assert_eq!(db.query_sql("SELECT COUNT(*) FROM people WHERE id < 10").unwrap()[0].data()["COUNT(*)"], "2");
+}
+
+#[test]
+fn equality_on_id_looks_the_record_up() {
+    let db = people();
+    let detail = |sql: &str| db.query_sql(&format!("EXPLAIN {}", sql)).unwrap()[0].data()["detail"].clone();
+    assert_eq!(detail("SELECT * FROM people WHERE id = 5"), "lookup of id 5 in people");
+    assert_eq!(detail("SELECT * FROM people WHERE name = Cy AND id = 5"), "lookup of id 5 in people");
+    assert!(detail("SELECT * FROM people WHERE id > 5").starts_with("full scan of people"));
+    assert!(detail("SELECT /*+ NO_INDEX */ * FROM people WHERE id = 5").starts_with("full scan of people"));
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE name = Ann AND id = 5"), [] as [u64; 0]);
+}
+
+#[test]
+fn select_lists_the_id() {
+    let db = people();
+    let rows = db.query_sql("SELECT id, name FROM people WHERE name = Bob").unwrap();
+    assert_eq!(rows[0].data()[ID], "10");
+    assert_eq!(rows[0].data()["name"], "Bob");
+    // it is not stored, so * leaves it out
+    assert!(!db.query_sql("SELECT * FROM people").unwrap()[0].data().contains_key(ID));
+}
+
+#[test]
+fn updates_and_deletes_find_rows_by_id() {
+    let mut db = people();
+    db.execute_sql("UPDATE people SET name = Bea WHERE id = 10").unwrap();
+    assert_eq!(db.get("people", 10).unwrap().unwrap().data()["name"], "Bea");
+    assert_eq!(db.execute_sql("DELETE FROM people WHERE id = 3").unwrap().len(), 1);
+    assert_eq!(ids(&db, "SELECT * FROM people"), [10, 5]);
+}
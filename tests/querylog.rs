@@ -0,0 +1,54 @@
+use std::sync::{Arc, Mutex};
+
+use potatodb::Database;
+
+#[test]
+fn statements_are_logged_while_logging_is_on() {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    let entries = Arc::new(Mutex::new(Vec::new()));
+    let sink = entries.clone();
+    db.set_query_sink(move |e| sink.lock().unwrap().push((e.sql().to_string(), e.rows(), e.error().map(String::from))));
+
+    db.execute_sql("INSERT INTO users (name) VALUES (Alice)").unwrap();
+    db.set_query_logging(true);
+    db.execute_sql("INSERT INTO users (name) VALUES (Bob)").unwrap();
+    db.query_sql("SELECT * FROM users").unwrap();
+    db.execute_sql("UPDATE users SET name = Al WHERE name = Alice").unwrap();
+    assert!(db.query_sql("SELECT * FROM missing").is_err());
+    db.set_query_logging(false);
+    db.query_sql("SELECT * FROM users").unwrap();
+
+    let entries = entries.lock().unwrap();
+    assert_eq!(*entries, vec![
+        ("INSERT INTO users (name) VALUES (Bob)".to_string(), 1, None),
+        ("SELECT * FROM users".to_string(), 2, None),
+        ("UPDATE users SET name = Al WHERE name = Alice".to_string(), 1, None),
+        ("SELECT * FROM missing".to_string(), 0, Some("Table not found".to_string())),
+    ]);
+}
+
+#[test]
+fn results_report_how_the_statement_ran() {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    for name in ["Ann", "Bob", "Cy", "Di"] {
+        db.execute_sql(&format!("INSERT INTO users (name) VALUES ({})", name)).unwrap();
+    }
+
+    let (records, stats) = db.query_sql_with_stats("SELECT * FROM users WHERE name > B").unwrap();
+    assert_eq!(records.len(), 3);
+    assert_eq!(stats.rows_scanned, 4);
+    assert!(!stats.index_used);
+
+    let (records, stats) = db.query_sql_with_stats("SELECT * FROM users WHERE id = 2").unwrap();
+    assert_eq!(records[0].data()["name"], "Bob");
+    assert_eq!(stats.rows_scanned, 1);
+    assert!(stats.index_used);
+
+    let (records, stats) = db.execute_sql_with_stats("DELETE FROM users WHERE id = 3").unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!((stats.rows_scanned, stats.index_used), (1, true));
+
+    assert!(db.query_sql_with_stats("SELECT * FROM missing").is_err());
+}
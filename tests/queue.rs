@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::thread::sleep;
+use std::time::Duration;
+
+use potatodb::Database;
+
+mod common;
+use common::temp_path;
+
+fn job(name: &str) -> HashMap<String, String> {
+    HashMap::from([("job".to_string(), name.to_string())])
+}
+
+fn jobs(timeout: Duration) -> Database {
+    let mut db = Database::new();
+    db.create_queue("jobs", timeout).unwrap();
+    for name in ["resize", "email", "report"] {
+        db.push("jobs", job(name)).unwrap();
+    }
+    db
+}
+
+fn popped(db: &mut Database, group: &str) -> Option<(u64, String)> {
+    db.pop("jobs", group).unwrap().map(|m| (m.id(), m.data()["job"].clone()))
+}
+
+#[test]
+fn groups_read_every_message_in_order() {
+    let mut db = jobs(Duration::from_secs(60));
+    assert_eq!(popped(&mut db, "workers"), Some((1, "resize".to_string())));
+    assert_eq!(popped(&mut db, "workers"), Some((2, "email".to_string())));
+    // each group has its own offset
+    assert_eq!(popped(&mut db, "audit"), Some((1, "resize".to_string())));
+    db.ack("jobs", "workers", 1).unwrap();
+    db.ack("jobs", "workers", 2).unwrap();
+    assert!(db.ack("jobs", "workers", 2).is_err());
+    assert!(db.ack("jobs", "workers", 3).is_err());
+
+    assert_eq!(popped(&mut db, "workers"), Some((3, "report".to_string())));
+    assert_eq!(popped(&mut db, "workers"), None);
+    assert_eq!(db.queue_backlog("jobs", "workers").unwrap(), 1);
+    assert_eq!(db.queue_backlog("jobs", "audit").unwrap(), 3);
+    assert_eq!(db.queue_backlog("jobs", "new").unwrap(), 3);
+
+    assert_eq!(db.push("jobs", job("cleanup")).unwrap(), 4);
+    db.execute_sql("INSERT INTO jobs (job) VALUES (backup)").unwrap();
+    assert_eq!(popped(&mut db, "workers"), Some((4, "cleanup".to_string())));
+    assert_eq!(popped(&mut db, "workers"), Some((5, "backup".to_string())));
+}
+
+#[test]
+fn unacknowledged_messages_come_back_after_the_timeout() {
+    let mut db = jobs(Duration::from_millis(200));
+    assert_eq!(popped(&mut db, "workers").unwrap().0, 1);
+    assert_eq!(popped(&mut db, "workers").unwrap().0, 2);
+    db.ack("jobs", "workers", 2).unwrap();
+    sleep(Duration::from_millis(300));
+    assert_eq!(popped(&mut db, "workers").unwrap().0, 1);
+    assert_eq!(popped(&mut db, "workers").unwrap().0, 3);
+    db.ack("jobs", "workers", 1).unwrap();
+
+    // a message deleted while in flight is not redelivered
+    db.delete("jobs", 3).unwrap();
+    sleep(Duration::from_millis(300));
+    assert_eq!(popped(&mut db, "workers"), None);
+}
+
+#[test]
+fn offsets_are_saved() {
+    let mut db = jobs(Duration::from_secs(60));
+    popped(&mut db, "workers");
+    db.ack("jobs", "workers", 1).unwrap();
+    popped(&mut db, "workers");
+    let path = temp_path("queue", "bin");
+    db.save(path.to_str().unwrap()).unwrap();
+    let mut loaded = Database::load(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    loaded.ack("jobs", "workers", 2).unwrap();
+    assert_eq!(popped(&mut loaded, "workers"), Some((3, "report".to_string())));
+}
+
+#[test]
+fn queues_only_take_new_messages() {
+    let mut db = jobs(Duration::from_secs(60));
+    assert!(db.insert("jobs", 2, job("late")).is_err());
+    db.create_table("plain".to_string()).unwrap();
+    assert!(db.push("plain", job("x")).is_err());
+    assert!(db.pop("plain", "workers").is_err());
+}
@@ -0,0 +1,135 @@
+#![cfg(feature = "raft")]
+
+use std::collections::{BTreeMap, HashSet};
+
+use potatodb::raft::{Command, NodeId, RaftNode, Role};
+
+// An in-memory network that delivers every message instantly unless one end
+// is cut off.
+struct Cluster {
+    nodes: BTreeMap<NodeId, RaftNode>,
+    isolated: HashSet<NodeId>,
+}
+
+impl Cluster {
+    fn new(ids: &[NodeId]) -> Self {
+        Cluster { nodes: ids.iter().map(|&id| (id, RaftNode::new(id, ids))).collect(), isolated: HashSet::new() }
+    }
+
+    fn run(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.nodes.values_mut().for_each(RaftNode::tick);
+            self.deliver();
+        }
+    }
+
+    fn deliver(&mut self) {
+        loop {
+            let mut messages = Vec::new();
+            for (&from, node) in &mut self.nodes {
+                messages.extend(node.take_messages().into_iter().map(|(to, m)| (from, to, m)));
+            }
+            if messages.is_empty() {
+                return;
+            }
+            for (from, to, message) in messages {
+                if self.isolated.contains(&from) || self.isolated.contains(&to) {
+                    continue;
+                }
+                if let Some(node) = self.nodes.get_mut(&to) {
+                    node.step(from, message);
+                }
+            }
+        }
+    }
+
+    fn leader(&self) -> NodeId {
+        let leaders: Vec<NodeId> = self.nodes.values()
+            .filter(|n| n.role() == Role::Leader && !self.isolated.contains(&n.id()))
+            .map(RaftNode::id)
+            .collect();
+        assert_eq!(leaders.len(), 1, "expected one leader, found {:?}", leaders);
+        leaders[0]
+    }
+
+    fn propose(&mut self, command: Command) -> (NodeId, u64) {
+        let leader = self.leader();
+        let index = self.nodes.get_mut(&leader).unwrap().propose(command).unwrap();
+        self.deliver();
+        (leader, index)
+    }
+
+    fn rows(&self, id: NodeId, table: &str) -> usize {
+        self.nodes[&id].database().get_all(table).map_or(0, |rows| rows.len())
+    }
+}
+
+#[test]
+fn elects_a_leader_and_replicates_commits() {
+    let mut cluster = Cluster::new(&[1, 2, 3]);
+    cluster.run(40);
+    cluster.propose(Command::CreateTable("users".to_string()));
+    let (leader, index) = cluster.propose(Command::Sql("INSERT INTO users (name) VALUES (Alice)".to_string()));
+    cluster.run(5);
+
+    assert_eq!(cluster.nodes.get_mut(&leader).unwrap().take_result(index).unwrap().unwrap().len(), 1);
+    for id in [1, 2, 3] {
+        assert_eq!(cluster.rows(id, "users"), 1, "node {}", id);
+    }
+}
+
+#[test]
+fn followers_reject_writes() {
+    let mut cluster = Cluster::new(&[1, 2, 3]);
+    cluster.run(40);
+    let leader = cluster.leader();
+    let follower = [1, 2, 3].into_iter().find(|&id| id != leader).unwrap();
+    let err = cluster.nodes.get_mut(&follower).unwrap().propose(Command::Noop).unwrap_err();
+    assert!(err.contains(&format!("node {} is", leader)));
+}
+
+#[test]
+fn a_partitioned_leader_is_replaced_and_its_uncommitted_writes_dropped() {
+    let mut cluster = Cluster::new(&[1, 2, 3]);
+    cluster.run(40);
+    cluster.propose(Command::CreateTable("t".to_string()));
+    cluster.run(5);
+
+    let old = cluster.leader();
+    cluster.isolated.insert(old);
+    cluster.nodes.get_mut(&old).unwrap().propose(Command::Sql("INSERT INTO t (v) VALUES (lost)".to_string())).unwrap();
+    cluster.run(60);
+    let new = cluster.leader();
+    assert_ne!(new, old);
+    cluster.propose(Command::Sql("INSERT INTO t (v) VALUES (kept)".to_string()));
+
+    cluster.isolated.clear();
+    cluster.run(20);
+    assert_eq!(cluster.leader(), new);
+    for id in [1, 2, 3] {
+        let rows = cluster.nodes[&id].database().query_sql("SELECT * FROM t").unwrap();
+        assert_eq!(rows.len(), 1, "node {}", id);
+        assert_eq!(rows[0].data()["v"], "kept");
+    }
+}
+
+#[test]
+fn members_can_join_and_leave() {
+    let mut cluster = Cluster::new(&[1, 2, 3]);
+    cluster.run(40);
+    cluster.propose(Command::CreateTable("t".to_string()));
+
+    cluster.nodes.insert(4, RaftNode::new(4, &[]));
+    let leader = cluster.leader();
+    cluster.nodes.get_mut(&leader).unwrap().add_member(4).unwrap();
+    cluster.run(10);
+    assert_eq!(cluster.nodes[&4].members(), vec![1, 2, 3, 4]);
+    assert!(cluster.nodes[&4].database().list_tables().contains(&"t"));
+
+    let leader = cluster.leader();
+    cluster.nodes.get_mut(&leader).unwrap().remove_member(leader).unwrap();
+    cluster.run(60);
+    let next = cluster.leader();
+    assert_ne!(next, leader);
+    assert_eq!(cluster.nodes[&next].members().len(), 3);
+}
@@ -0,0 +1,136 @@
+use potatodb::{Database, Model, Record};
+
+mod common;
+use common::row;
+
+#[derive(Debug, PartialEq)]
+struct User {
+    id: u64,
+    name: String,
+}
+
+#[derive(Debug, PartialEq)]
+struct Order {
+    id: u64,
+    item: String,
+}
+
+impl Model for User {
+    const TABLE: &'static str = "users";
+
+    fn from_record(record: &Record) -> Result<Self, String> {
+        let name = record.data().get("name").ok_or("User without a name")?;
+        Ok(User { id: record.id(), name: name.clone() })
+    }
+}
+
+impl Model for Order {
+    const TABLE: &'static str = "orders";
+
+    fn from_record(record: &Record) -> Result<Self, String> {
+        let item = record.data().get("item").ok_or("Order without an item")?;
+        Ok(Order { id: record.id(), item: item.clone() })
+    }
+}
+
+fn shop() -> Database {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    db.create_table("orders".to_string()).unwrap();
+    db.insert("users", 1, row(&[("name", "Alice")])).unwrap();
+    db.insert("users", 2, row(&[("name", "Bob")])).unwrap();
+    db.insert("users", 3, row(&[("name", "Carol")])).unwrap();
+    db.insert("orders", 1, row(&[("item", "pen"), ("user_id", "1")])).unwrap();
+    db.insert("orders", 2, row(&[("item", "ink"), ("user_id", "2")])).unwrap();
+    db.insert("orders", 3, row(&[("item", "cap"), ("user_id", "1")])).unwrap();
+    db.insert("orders", 4, row(&[("item", "pad")])).unwrap();
+    db.has_many("users", "orders", "user_id").unwrap();
+    db
+}
+
+#[test]
+fn related_rows_follow_the_foreign_key() {
+    let db = shop();
+    let mut orders: Vec<Order> = db.related(1).unwrap();
+    orders.sort_by_key(|o| o.id);
+    assert_eq!(orders, vec![Order { id: 1, item: "pen".to_string() }, Order { id: 3, item: "cap".to_string() }]);
+    assert!(db.related::<Order>(3).unwrap().is_empty());
+
+    assert_eq!(db.parent_of::<User>("orders", 2).unwrap(), Some(User { id: 2, name: "Bob".to_string() }));
+    assert_eq!(db.parent_of::<User>("orders", 4).unwrap(), None);
+    assert!(db.parent_of::<User>("orders", 9).is_err());
+}
+
+#[test]
+fn eager_loading_groups_children_by_parent() {
+    let db = shop();
+    let mut loaded = db.with_related::<User, Order>().unwrap();
+    loaded.sort_by_key(|(user, _)| user.id);
+    let counts: Vec<(&str, usize)> = loaded.iter().map(|(u, orders)| (u.name.as_str(), orders.len())).collect();
+    assert_eq!(counts, vec![("Alice", 2), ("Bob", 1), ("Carol", 0)]);
+}
+
+#[test]
+fn relations_must_be_declared_between_existing_tables() {
+    let mut db = shop();
+    assert!(db.has_many("users", "orders", "user_id").is_err());
+    assert!(db.has_many("users", "missing", "user_id").is_err());
+    assert!(db.related::<User>(1).is_err());
+
+    // a table owned by two parents needs the parent named
+    db.create_table("shops".to_string()).unwrap();
+    db.has_many("shops", "orders", "shop_id").unwrap();
+    assert!(db.related::<Order>(1).is_err());
+    assert_eq!(db.with_related::<User, Order>().unwrap().len(), 3);
+}
+
+#[test]
+fn delete_cascade_removes_dependent_rows_across_tables() {
+    let mut db = shop();
+    db.create_table("lines".to_string()).unwrap();
+    db.insert("lines", 1, row(&[("order_id", "1"), ("qty", "2")])).unwrap();
+    db.insert("lines", 2, row(&[("order_id", "2"), ("qty", "1")])).unwrap();
+    db.insert("lines", 3, row(&[("order_id", "3"), ("qty", "5")])).unwrap();
+    db.has_many("orders", "lines", "order_id").unwrap();
+
+    let deleted: Vec<(String, u64)> = db.delete_cascade("users", 1).unwrap().into_iter().map(|(t, r)| (t, r.id())).collect();
+    assert_eq!(deleted, [
+        ("users".to_string(), 1),
+        ("orders".to_string(), 1),
+        ("orders".to_string(), 3),
+        ("lines".to_string(), 1),
+        ("lines".to_string(), 3),
+    ]);
+    assert!(db.get("users", 1).unwrap().is_none());
+    let ids = |db: &Database, table: &str| db.get_all(table).unwrap().iter().map(|r| r.id()).collect::<Vec<_>>();
+    assert_eq!(ids(&db, "orders"), [2, 4]);
+    assert_eq!(ids(&db, "lines"), [2]);
+}
+
+#[test]
+fn delete_cascade_of_a_missing_row_deletes_nothing() {
+    let mut db = shop();
+    assert!(db.delete_cascade("users", 9).unwrap_err().contains("not found"));
+    assert!(db.delete_cascade("nope", 1).is_err());
+    assert_eq!(db.get_all("orders").unwrap().len(), 4);
+
+    // a row without children is deleted alone
+    let deleted = db.delete_cascade("users", 3).unwrap();
+    assert_eq!(deleted.len(), 1);
+    assert_eq!(deleted[0].1.data()["name"], "Carol");
+}
+
+#[test]
+fn schema_graph_dot_draws_tables_and_foreign_keys() {
+    let mut db = shop();
+    db.create_table("say \"hi\"".to_string()).unwrap();
+    assert_eq!(db.schema_graph_dot(), concat!(
+        "digraph schema {\n",
+        "    \"orders\";\n",
+        "    \"say \\\"hi\\\"\";\n",
+        "    \"users\";\n",
+        "    \"orders\" -> \"users\" [label=\"user_id\"];\n",
+        "}\n",
+    ));
+    assert_eq!(Database::new().schema_graph_dot(), "digraph schema {\n}\n");
+}
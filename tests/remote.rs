@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use potatodb::{Database, QueryLimits, QueryServer, RemoteClient};
+
+#[test]
+fn clients_query_a_remote_database() {
+    let db = Arc::new(Mutex::new(Database::new()));
+    {
+        let mut db = db.lock().unwrap();
+        db.create_table("users".to_string()).unwrap();
+        db.insert("users", 1, HashMap::from([("name".to_string(), "Alice".to_string())])).unwrap();
+    }
+    let server = QueryServer::bind(db.clone(), "127.0.0.1:0").unwrap();
+    let mut client = RemoteClient::connect(server.local_addr()).unwrap();
+
+    assert_eq!(client.list_tables().unwrap(), vec!["users"]);
+    assert_eq!(client.columns("users").unwrap(), vec!["name"]);
+    let rows = client.query_sql("SELECT * FROM users WHERE name = Alice").unwrap();
+    assert_eq!(rows[0].id(), 1);
+
+    // later writes on the server are visible to the next query
+    db.lock().unwrap().insert("users", 2, HashMap::from([("name".to_string(), "Bob".to_string())])).unwrap();
+    assert_eq!(client.query_sql("SELECT * FROM users").unwrap().len(), 2);
+
+    assert!(client.query_sql("DELETE FROM users WHERE name = Bob").is_err());
+    assert!(client.query_sql("SELECT * FROM missing").is_err());
+    assert_eq!(db.lock().unwrap().get_all("users").unwrap().len(), 2);
+    // the connection survives failed queries
+    assert_eq!(client.query_sql("SELECT * FROM users").unwrap().len(), 2);
+}
+
+fn users(names: &[&str]) -> Arc<Mutex<Database>> {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    for (id, name) in (1..).zip(names) {
+        db.insert("users", id, HashMap::from([("name".to_string(), name.to_string())])).unwrap();
+    }
+    Arc::new(Mutex::new(db))
+}
+
+#[test]
+fn transactions_read_a_snapshot_per_session() {
+    let db = users(&["Alice"]);
+    let server = QueryServer::bind(db.clone(), "127.0.0.1:0").unwrap();
+    let mut reader = RemoteClient::connect(server.local_addr()).unwrap();
+    let mut other = RemoteClient::connect(server.local_addr()).unwrap();
+
+    reader.query_sql("BEGIN").unwrap();
+    assert!(reader.query_sql("BEGIN TRANSACTION").unwrap_err().to_string().contains("already open"));
+    db.lock().unwrap().insert("users", 2, HashMap::from([("name".to_string(), "Bob".to_string())])).unwrap();
+    // the open transaction keeps reading what was there at BEGIN
+    assert_eq!(reader.query_sql("SELECT * FROM users").unwrap().len(), 1);
+    assert_eq!(other.query_sql("SELECT * FROM users").unwrap().len(), 2);
+
+    reader.query_sql("COMMIT;").unwrap();
+    assert_eq!(reader.query_sql("SELECT * FROM users").unwrap().len(), 2);
+    assert!(reader.query_sql("ROLLBACK").unwrap_err().to_string().contains("No transaction"));
+}
+
+#[test]
+fn transactions_keep_the_query_limits() {
+    let db = users(&["Alice", "Bob", "Carol"]);
+    db.lock().unwrap().set_query_limits(QueryLimits { max_result_rows: Some(2), ..QueryLimits::default() });
+    let server = QueryServer::bind(db, "127.0.0.1:0").unwrap();
+    let mut client = RemoteClient::connect(server.local_addr()).unwrap();
+
+    client.query_sql("BEGIN").unwrap();
+    let error = client.query_sql("SELECT * FROM users").unwrap_err();
+    assert!(error.to_string().contains("limit of 2 result rows"));
+    assert_eq!(client.query_sql("SELECT * FROM users WHERE name = Bob").unwrap().len(), 1);
+    client.query_sql("COMMIT").unwrap();
+}
+
+#[test]
+fn transactions_share_all_but_the_rows_with_the_database() {
+    let db = users(&["Alice"]);
+    let server = QueryServer::bind(db.clone(), "127.0.0.1:0").unwrap();
+    let mut client = RemoteClient::connect(server.local_addr()).unwrap();
+
+    client.query_sql("BEGIN").unwrap();
+    db.lock().unwrap().insert("users", 2, HashMap::from([("name".to_string(), "Bob".to_string())])).unwrap();
+    db.lock().unwrap().set_query_limits(QueryLimits { max_result_rows: Some(0), ..QueryLimits::default() });
+    assert!(client.query_sql("SELECT * FROM users").unwrap_err().to_string().contains("limit of 0 result rows"));
+    db.lock().unwrap().set_query_limits(QueryLimits::default());
+    assert_eq!(client.query_sql("SELECT * FROM users").unwrap().len(), 1);
+    // the database reads its own rows between the transaction's queries
+    assert_eq!(db.lock().unwrap().get_all("users").unwrap().len(), 2);
+    client.query_sql("COMMIT").unwrap();
+    assert_eq!(client.query_sql("SELECT * FROM users").unwrap().len(), 2);
+}
+
+#[test]
+fn settings_belong_to_the_connection() {
+    let db = users(&["Alice"]);
+    let server = QueryServer::bind(db.clone(), "127.0.0.1:0").unwrap();
+    let mut relaxed = RemoteClient::connect(server.local_addr()).unwrap();
+    let mut strict = RemoteClient::connect(server.local_addr()).unwrap();
+
+    relaxed.query_sql("SET case_sensitive = off").unwrap();
+    assert!(relaxed.query_sql("SET case_sensitive = maybe").is_err());
+    assert_eq!(relaxed.query_sql("SELECT * FROM users WHERE name = alice").unwrap().len(), 1);
+    assert!(strict.query_sql("SELECT * FROM users WHERE name = alice").unwrap().is_empty());
+    assert_eq!(db.lock().unwrap().setting("case_sensitive").unwrap(), "on");
+
+    // settings a connection didn't change follow the database's
+    db.lock().unwrap().set_setting("case_sensitive", "off").unwrap();
+    assert_eq!(strict.query_sql("SELECT * FROM users WHERE name = alice").unwrap().len(), 1);
+    strict.query_sql("PRAGMA case_sensitive = on").unwrap();
+    assert!(strict.query_sql("SELECT * FROM users WHERE name = alice").unwrap().is_empty());
+    assert_eq!(db.lock().unwrap().setting("case_sensitive").unwrap(), "off");
+}
+
+#[test]
+fn idle_transactions_are_closed() {
+    let db = users(&["Alice"]);
+    let server = QueryServer::bind(db, "127.0.0.1:0").unwrap();
+    server.set_idle_in_transaction_timeout(std::time::Duration::from_millis(200));
+    let mut idle = RemoteClient::connect(server.local_addr()).unwrap();
+    let mut busy = RemoteClient::connect(server.local_addr()).unwrap();
+
+    idle.query_sql("BEGIN").unwrap();
+    busy.query_sql("BEGIN").unwrap();
+    for _ in 0..8 {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        busy.query_sql("SELECT * FROM users").unwrap();
+    }
+    assert!(idle.query_sql("SELECT * FROM users").is_err());
+    busy.query_sql("COMMIT").unwrap();
+}
+
+#[test]
+fn oversized_frames_close_the_connection() {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::time::Duration;
+
+    let server = QueryServer::bind(users(&["Alice"]), "127.0.0.1:0").unwrap();
+    let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    // a length prefix of 4 GiB, with no body behind it
+    stream.write_all(&u32::MAX.to_le_bytes()).unwrap();
+    let closed = match stream.read(&mut [0; 16]) {
+        Ok(read) => read == 0,
+        Err(e) => e.kind() != std::io::ErrorKind::WouldBlock && e.kind() != std::io::ErrorKind::TimedOut,
+    };
+    assert!(closed);
+
+    let mut client = RemoteClient::connect(server.local_addr()).unwrap();
+    assert_eq!(client.query_sql("SELECT * FROM users").unwrap().len(), 1);
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use potatodb::{Database, Follower, ReplicationServer};
+
+fn wait_for(follower: &Follower, seq: u64) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while follower.applied_seq() < seq {
+        assert!(Instant::now() < deadline, "follower stuck at {}", follower.applied_seq());
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+fn row(name: &str) -> HashMap<String, String> {
+    HashMap::from([("name".to_string(), name.to_string())])
+}
+
+#[test]
+fn follower_starts_from_a_snapshot_and_applies_changes() {
+    let leader = Arc::new(Mutex::new(Database::new()));
+    {
+        let mut db = leader.lock().unwrap();
+        db.create_table("users".to_string()).unwrap();
+        db.insert("users", 1, row("Alice")).unwrap();
+    }
+    let server = ReplicationServer::bind(leader.clone(), "127.0.0.1:0").unwrap();
+    let follower = Follower::connect(server.local_addr()).unwrap();
+    assert_eq!(follower.query_sql("SELECT * FROM users").unwrap().len(), 1);
+
+    let seq = {
+        let mut db = leader.lock().unwrap();
+        db.insert("users", 2, row("Bob")).unwrap();
+        db.update("users", 1, row("Alicia")).unwrap();
+        db.delete("users", 2).unwrap();
+        db.create_table("later".to_string()).unwrap();
+        db.insert("later", 7, row("x")).unwrap();
+        db.last_change_seq()
+    };
+    wait_for(&follower, seq);
+
+    let names = follower.read(|db| {
+        db.get_all("users").unwrap().iter().map(|r| r.data()["name"].clone()).collect::<Vec<_>>()
+    }).unwrap();
+    assert_eq!(names, vec!["Alicia"]);
+    assert!(follower.read(|db| db.get("later", 7).unwrap().is_some()).unwrap());
+}
+
+#[test]
+fn follower_catches_up_after_the_log_is_discarded() {
+    let leader = Arc::new(Mutex::new(Database::new()));
+    leader.lock().unwrap().create_table("t".to_string()).unwrap();
+    let server = ReplicationServer::bind(leader.clone(), "127.0.0.1:0").unwrap();
+    let follower = Follower::connect(server.local_addr()).unwrap();
+
+    // hold the lock so the leader cannot ship the inserts before they are discarded
+    let seq = {
+        let mut db = leader.lock().unwrap();
+        for id in 1..=3 {
+            db.insert("t", id, HashMap::new()).unwrap();
+        }
+        let seq = db.last_change_seq();
+        db.discard_changes_through(seq);
+        seq
+    };
+    wait_for(&follower, seq);
+    assert_eq!(follower.read(|db| db.get_all("t").unwrap().len()).unwrap(), 3);
+}
@@ -0,0 +1,38 @@
+use potatodb::{CopyFormat, Database};
+
+mod common;
+use common::row;
+
+#[test]
+fn reserved_ids_follow_the_ids_in_use() {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    db.insert("users", 1, row(&[("name", "ann")])).unwrap();
+    db.insert("users", 7, row(&[("name", "bob")])).unwrap();
+    assert_eq!(db.reserve_ids("users", 3).unwrap(), 8..11);
+    assert_eq!(db.reserve_ids("users", 2).unwrap(), 11..13);
+    assert_eq!(db.reserve_ids("users", 0).unwrap(), 13..13);
+    assert!(db.reserve_ids("missing", 1).is_err());
+    assert!(db.reserve_ids("users", u64::MAX).is_err());
+}
+
+#[test]
+fn importers_can_point_children_at_reserved_ids() {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    db.create_table("orders".to_string()).unwrap();
+    let ids = db.reserve_ids("users", 2).unwrap();
+    for (order, user) in ids.clone().enumerate() {
+        db.insert("orders", order as u64 + 1, row(&[("user_id", &user.to_string())])).unwrap();
+    }
+    // rows the engine numbers itself skip the reservation
+    db.execute_sql("INSERT INTO users (name) VALUES (carol)").unwrap();
+    db.copy_in("users", "name\ndave\n".as_bytes(), CopyFormat::Csv).unwrap();
+    let numbered: Vec<u64> = db.get_all("users").unwrap().iter().map(|r| r.id()).collect();
+    assert_eq!(numbered, [3, 4]);
+
+    for user in ids {
+        db.insert("users", user, row(&[("name", "imported")])).unwrap();
+    }
+    assert_eq!(db.get_all("users").unwrap().len(), 4);
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use potatodb::{Database, Row};
+
+mod common;
+use common::row;
+
+#[test]
+fn rows_are_used_like_maps() {
+    let mut data = Row::from(row(&[("name", "Ann"), ("age", "31")]));
+    assert_eq!(data["name"], "Ann");
+    assert_eq!(data.get("age").map(String::as_str), Some("31"));
+    assert!(data.get("email").is_none() && !data.contains_key("email"));
+
+    assert_eq!(data.insert("age".to_string(), "32".to_string()).as_deref(), Some("31"));
+    assert_eq!(data.insert("email".to_string(), "ann@example.com".to_string()), None);
+    data.get_mut("name").unwrap().push('a');
+    assert_eq!(data.remove("age").as_deref(), Some("32"));
+    assert_eq!(data.remove("age"), None);
+    assert_eq!(data, row(&[("name", "Anna"), ("email", "ann@example.com")]));
+
+    // columns come out in name order
+    let columns: Vec<&String> = data.keys().collect();
+    assert_eq!(columns, ["email", "name"]);
+    data.retain(|column, _| column != "email");
+    assert_eq!(data.to_map(), row(&[("name", "Anna")]));
+    assert_eq!(data.len(), 1);
+}
+
+#[test]
+fn records_share_column_names() {
+    let mut db = Database::new();
+    db.create_table("short".to_string()).unwrap();
+    db.create_table("long".to_string()).unwrap();
+    let short: Vec<String> = (0..20).map(|n| format!("c{}", n)).collect();
+    let long: Vec<String> = (0..20).map(|n| format!("a_rather_long_column_name_{}", n)).collect();
+    for id in 1..=200 {
+        db.insert("short", id, short.iter().map(|c| (c.clone(), id.to_string())).collect::<HashMap<_, _>>()).unwrap();
+        db.insert("long", id, long.iter().map(|c| (c.clone(), id.to_string())).collect::<HashMap<_, _>>()).unwrap();
+    }
+    let stats = db.stats().unwrap();
+    let bytes = |name: &str| stats.iter().find(|s| s.name() == name).unwrap().memory_bytes();
+    // the longer names are stored once, not once per record
+    let names: usize = long.iter().map(String::len).sum();
+    assert!(bytes("long") < bytes("short") + 2 * names, "{} vs {}", bytes("long"), bytes("short"));
+    assert_eq!(db.get("long", 7).unwrap().unwrap().data()["a_rather_long_column_name_3"], "7");
+}
+
+#[test]
+fn column_names_stay_shared_after_renames_and_reloads() {
+    let mut db = Database::new();
+    db.create_table("long".to_string()).unwrap();
+    let long: Vec<String> = (0..20).map(|n| format!("a_rather_long_column_name_{}", n)).collect();
+    for id in 1..=200 {
+        db.insert("long", id, long.iter().map(|c| (c.clone(), id.to_string())).collect::<HashMap<_, _>>()).unwrap();
+    }
+    let bytes = |db: &Database| db.stats().unwrap()[0].memory_bytes();
+    let stored = bytes(&db);
+    // every row holding its own copy of the names would add this much
+    let names: usize = 200 * long.iter().map(String::len).sum::<usize>();
+
+    db.rename_column("long", "a_rather_long_column_name_0", "a_rather_long_column_name_x").unwrap();
+    assert!(bytes(&db) < stored + names / 2, "{} vs {}", bytes(&db), stored);
+    let db = Database::from_bytes(&db.to_bytes().unwrap()).unwrap();
+    assert!(bytes(&db) < stored + names / 2, "{} vs {}", bytes(&db), stored);
+    assert_eq!(db.get("long", 7).unwrap().unwrap().data()["a_rather_long_column_name_x"], "7");
+}
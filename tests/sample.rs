@@ -0,0 +1,72 @@
+use potatodb::Database;
+
+mod common;
+use common::row;
+
+fn users(count: u64) -> Database {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    for id in 1..=count {
+        let plan = if id % 2 == 0 { "pro" } else { "free" };
+        db.insert("users", id, row(&[("plan", plan)])).unwrap();
+    }
+    db
+}
+
+#[test]
+fn sample_picks_distinct_records_in_table_order() {
+    let db = users(100);
+    let ids: Vec<u64> = db.sample("users", 10).unwrap().iter().map(|r| r.id()).collect();
+    assert_eq!(ids.len(), 10);
+    assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+
+    assert_eq!(db.sample("users", 500).unwrap().len(), 100);
+    assert!(db.sample("users", 0).unwrap().is_empty());
+    assert!(db.sample("missing", 1).is_err());
+}
+
+#[test]
+fn sample_is_uniform() {
+    let db = users(4);
+    let mut picked = [0; 4];
+    for _ in 0..2000 {
+        let id = db.sample("users", 1).unwrap()[0].id();
+        picked[id as usize - 1] += 1;
+    }
+    // each record is expected 500 times
+    assert!(picked.iter().all(|&n| n > 350), "{:?}", picked);
+}
+
+#[test]
+fn tablesample_keeps_a_share_of_the_table() {
+    let db = users(200);
+    assert_eq!(db.query_sql("SELECT * FROM users TABLESAMPLE (5 PERCENT)").unwrap().len(), 10);
+    assert_eq!(db.query_sql("SELECT * FROM users TABLESAMPLE ( 12 ROWS )").unwrap().len(), 12);
+    assert_eq!(db.query_sql("SELECT * FROM users tablesample (100 percent)").unwrap().len(), 200);
+    assert!(db.query_sql("SELECT * FROM users TABLESAMPLE (0 PERCENT)").unwrap().is_empty());
+
+    // WHERE filters the sample
+    let rows = db.query_sql("SELECT plan FROM users TABLESAMPLE (50 PERCENT) WHERE plan = pro").unwrap();
+    assert!(rows.len() <= 100);
+    assert!(rows.iter().all(|r| r.data()["plan"] == "pro"));
+}
+
+#[test]
+fn tablesample_rejects_bad_amounts() {
+    let db = users(3);
+    assert!(db.query_sql("SELECT * FROM users TABLESAMPLE (150 PERCENT)").is_err());
+    assert!(db.query_sql("SELECT * FROM users TABLESAMPLE (some PERCENT)").is_err());
+    assert!(db.query_sql("SELECT * FROM users TABLESAMPLE (5 BLOCKS)").is_err());
+    assert!(db.query_sql("SELECT * FROM users TABLESAMPLE 5 PERCENT").is_err());
+}
+
+#[test]
+fn explain_shows_the_sample() {
+    let db = users(40);
+    let plan = db.query_sql("EXPLAIN ANALYZE SELECT * FROM users TABLESAMPLE (25 PERCENT) WHERE plan = free").unwrap();
+    let nodes: Vec<&str> = plan.iter().map(|r| r.data()["node"].as_str()).collect();
+    assert_eq!(nodes, ["scan", "sample", "filter", "project"]);
+    assert_eq!(plan[1].data()["detail"], "25 PERCENT");
+    assert_eq!(plan[1].data()["estimated_rows"], "10");
+    assert_eq!(plan[1].data()["actual_rows"], "10");
+}
@@ -0,0 +1,67 @@
+use potatodb::Database;
+
+mod common;
+use common::row;
+
+fn tenants() -> Database {
+    let mut db = Database::new();
+    db.execute_sql("CREATE SCHEMA acme").unwrap();
+    db.execute_sql("CREATE SCHEMA globex").unwrap();
+    db.execute_sql("CREATE TABLE acme.users (name TEXT)").unwrap();
+    db.execute_sql("CREATE TABLE acme.orders (item TEXT)").unwrap();
+    db.execute_sql("CREATE TABLE globex.users (name TEXT)").unwrap();
+    db.insert("acme.users", 1, row(&[("name", "ann")])).unwrap();
+    db.insert("globex.users", 1, row(&[("name", "bob")])).unwrap();
+    db
+}
+
+#[test]
+fn tables_are_namespaced_by_schema() {
+    let db = tenants();
+    assert_eq!(db.list_schemas(), ["acme", "globex"]);
+    assert_eq!(db.list_schema_tables("acme").unwrap(), ["acme.orders", "acme.users"]);
+    assert_eq!(db.list_schema_tables("globex").unwrap(), ["globex.users"]);
+    let users = db.query_sql("SELECT name FROM acme.users").unwrap();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].data()["name"], "ann");
+    assert!(db.list_schema_tables("initech").is_err());
+}
+
+#[test]
+fn schemas_must_exist_before_their_tables() {
+    let mut db = Database::new();
+    assert!(db.execute_sql("CREATE TABLE acme.users (name TEXT)").is_err());
+    assert!(db.create_schema("acme").is_ok());
+    assert!(db.create_schema("acme").is_err());
+    assert!(db.create_schema("a.b").is_err());
+    assert!(db.create_table("acme.users".to_string()).is_ok());
+    assert!(db.create_table("plain".to_string()).is_ok());
+}
+
+#[test]
+fn dropping_a_schema_drops_its_tables() {
+    let mut db = tenants();
+    db.execute_sql("DROP SCHEMA acme").unwrap();
+    assert_eq!(db.list_schemas(), ["globex"]);
+    assert_eq!(db.list_tables(), ["globex.users"]);
+    assert!(db.execute_sql("DROP SCHEMA acme").is_err());
+    assert!(db.execute_sql("DROP TABLE globex.users").is_err());
+}
+
+#[test]
+fn a_schema_exports_on_its_own() {
+    let db = tenants();
+    let export = Database::from_bytes(&db.export_schema("acme").unwrap().to_bytes().unwrap()).unwrap();
+    assert_eq!(export.list_schemas(), ["acme"]);
+    assert_eq!(export.list_schema_tables("acme").unwrap(), ["acme.orders", "acme.users"]);
+    assert_eq!(export.get("acme.users", 1).unwrap().unwrap().data()["name"], "ann");
+    assert!(export.get("globex.users", 1).is_err());
+}
+
+#[test]
+fn schemas_persist() {
+    let db = tenants();
+    let loaded = Database::from_bytes(&db.to_bytes().unwrap()).unwrap();
+    assert_eq!(loaded.list_schemas(), ["acme", "globex"]);
+    assert_eq!(loaded.list_schema_tables("globex").unwrap(), ["globex.users"]);
+}
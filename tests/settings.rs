@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use potatodb::{Database, PartitionScheme, SortOrder};
+
+fn people() -> Database {
+    let mut db = Database::new();
+    db.create_table("people".to_string()).unwrap();
+    for (id, name) in [(1, "Ann"), (2, "bob"), (3, "ANN")] {
+        db.insert("people", id, HashMap::from([("name".to_string(), name.to_string())])).unwrap();
+    }
+    db
+}
+
+fn ids(db: &Database, sql: &str) -> Vec<u64> {
+    db.query_sql(sql).unwrap().iter().map(|r| r.id()).collect()
+}
+
+#[test]
+fn case_sensitivity_is_a_session_setting() {
+    let mut db = people();
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE name = ann"), Vec::<u64>::new());
+    assert_eq!(db.query_sql("PRAGMA case_sensitive").unwrap()[0].data()["case_sensitive"], "on");
+
+    db.execute_sql("SET case_sensitive = off").unwrap();
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE name = ann"), vec![1, 3]);
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE name > b"), vec![2]);
+    assert_eq!(db.setting("case_sensitive").unwrap(), "off");
+
+    db.execute_sql("UPDATE people SET name = x WHERE name = BOB").unwrap();
+    assert_eq!(db.get("people", 2).unwrap().unwrap().data()["name"], "x");
+
+    db.execute_sql("PRAGMA case_sensitive = on").unwrap();
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE name = ann"), Vec::<u64>::new());
+}
+
+#[test]
+fn ignoring_case_reads_every_partition() {
+    let mut db = people();
+    db.partition_table("people", PartitionScheme::Hash { column: "name".to_string(), partitions: 4 }).unwrap();
+    db.set_setting("case_sensitive", "off").unwrap();
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE name = ann"), vec![1, 3]);
+}
+
+#[test]
+fn nulls_setting_places_rows_without_the_column() {
+    let mut db = people();
+    db.insert("people", 4, HashMap::from([("nickname".to_string(), "d".to_string())])).unwrap();
+    let sorted = |db: &Database, order| db.aggregate("people").sort("name", order).run().unwrap().iter().map(|r| r.id()).collect::<Vec<_>>();
+    assert_eq!(sorted(&db, SortOrder::Ascending)[0], 4);
+    assert_eq!(sorted(&db, SortOrder::Descending)[3], 4);
+
+    db.execute_sql("SET nulls = last").unwrap();
+    assert_eq!(sorted(&db, SortOrder::Ascending)[3], 4);
+    assert_eq!(sorted(&db, SortOrder::Descending)[3], 4);
+    db.execute_sql("SET nulls = first").unwrap();
+    assert_eq!(sorted(&db, SortOrder::Descending)[0], 4);
+    assert_eq!(db.query_sql("PRAGMA nulls").unwrap()[0].data()["nulls"], "first");
+}
+
+#[test]
+fn statement_timeout_is_a_setting() {
+    let mut db = people();
+    assert_eq!(db.setting("statement_timeout").unwrap(), "0");
+    db.execute_sql("SET statement_timeout = 250").unwrap();
+    assert_eq!(db.setting("statement_timeout").unwrap(), "250");
+    db.execute_sql("SET statement_timeout = 0").unwrap();
+    assert_eq!(db.setting("statement_timeout").unwrap(), "0");
+}
+
+#[test]
+fn bad_settings_are_rejected() {
+    let mut db = people();
+    assert!(db.execute_sql("SET colour = blue").unwrap_err().contains("Unknown setting 'colour'"));
+    assert!(db.execute_sql("SET case_sensitive = maybe").is_err());
+    assert!(db.execute_sql("SET nulls = middle").is_err());
+    assert!(db.execute_sql("SET statement_timeout = soon").is_err());
+    assert!(db.execute_sql("SET case_sensitive").is_err());
+    assert!(db.query_sql("PRAGMA colour").is_err());
+    // settings change the session, which a read-only query can't
+    assert!(db.query_sql("SET case_sensitive = off").is_err());
+}
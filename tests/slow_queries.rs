@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use potatodb::{Database, PartitionScheme, SLOW_QUERIES_TABLE};
+
+fn slow(db: &Database) -> Vec<HashMap<String, String>> {
+    db.query_sql(&format!("SELECT * FROM {}", SLOW_QUERIES_TABLE)).unwrap()
+        .iter().map(|r| r.data().to_map()).collect()
+}
+
+#[test]
+fn statements_over_the_threshold_are_queryable() {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    db.create_table("events".to_string()).unwrap();
+    let scheme = PartitionScheme::Range { column: "day".to_string(), bounds: vec!["2".to_string()] };
+    db.partition_table("events", scheme).unwrap();
+    db.execute_sql("INSERT INTO users (name) VALUES (Alice)").unwrap();
+    assert!(slow(&db).is_empty());
+
+    db.set_slow_query_threshold(Some(Duration::ZERO));
+    db.query_sql("SELECT * FROM users WHERE name = Alice").unwrap();
+    db.query_sql("SELECT * FROM events WHERE day = 3").unwrap();
+    db.set_slow_query_threshold(Some(Duration::from_secs(3600)));
+    db.query_sql("SELECT * FROM users").unwrap();
+
+    let entries = slow(&db);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["statement"], "SELECT * FROM users WHERE name = Alice");
+    assert_eq!(entries[0]["plan"], "full scan of users (1 rows)");
+    assert_eq!(entries[1]["plan"], "scan of events partitions {1} of 3");
+    assert!(entries[0]["duration_ms"].parse::<f64>().is_ok());
+    assert!(entries[0]["timestamp"].parse::<u64>().unwrap() > 0);
+
+    let plans = db.query_sql(&format!("SELECT plan FROM {} WHERE statement > SELECT", SLOW_QUERIES_TABLE)).unwrap();
+    assert_eq!(plans.len(), 2);
+    assert!(db.execute_sql(&format!("DELETE FROM {}", SLOW_QUERIES_TABLE)).is_err());
+}
@@ -0,0 +1,28 @@
+use std::collections::HashMap;
+
+use potatodb::Database;
+
+#[test]
+fn stats_report_sizes_largest_first() {
+    let mut db = Database::new();
+    db.create_table("small".to_string()).unwrap();
+    db.create_table("big".to_string()).unwrap();
+    db.create_table("empty".to_string()).unwrap();
+    db.insert("small", 1, HashMap::from([("k".to_string(), "v".to_string())])).unwrap();
+    for id in 1..=50 {
+        db.insert("big", id, HashMap::from([("text".to_string(), format!("{:0100}", id))])).unwrap();
+    }
+
+    let stats = db.stats().unwrap();
+    let names: Vec<&str> = stats.iter().map(|s| s.name()).collect();
+    assert_eq!(names, ["big", "small", "empty"]);
+    let big = &stats[0];
+    assert_eq!(big.records(), 50);
+    assert!(big.memory_bytes() > 50 * 100);
+    assert!(big.index_bytes() > 0 && big.index_bytes() < big.memory_bytes());
+    assert!(big.disk_bytes() > 50 * 100);
+    assert_eq!(stats[2].records(), 0);
+
+    let total: u64 = stats.iter().map(|s| s.disk_bytes()).sum();
+    assert!(total <= db.to_bytes().unwrap().len() as u64);
+}
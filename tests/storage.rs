@@ -0,0 +1,139 @@
+use std::io;
+use std::sync::Arc;
+
+use potatodb::{AuditSink, ChangeKind, Database, FaultyStorage, FileStorage, MemoryStorage, StorageBackend};
+
+mod common;
+use common::{row, temp_path};
+
+fn accounts(balance: &str) -> Database {
+    let mut db = Database::new();
+    db.create_table("accounts".to_string()).unwrap();
+    db.insert("accounts", 1, row(&[("owner", "ann"), ("balance", balance)])).unwrap();
+    db
+}
+
+fn balance(db: &Database) -> String {
+    db.get("accounts", 1).unwrap().unwrap().data()["balance"].clone()
+}
+
+fn cleanup(path: &str) {
+    let _ = std::fs::remove_file(path);
+    let _ = std::fs::remove_file(format!("{}.tmp", path));
+}
+
+#[test]
+fn failed_saves_keep_the_last_saved_database() {
+    let path = temp_path("storage-crash", "bin");
+    let path = path.to_str().unwrap();
+    let storage = FaultyStorage::new(FileStorage);
+    accounts("100").save_to(&storage, path).unwrap();
+
+    // a crash part way through writing
+    storage.short_write(20);
+    assert!(accounts("250").save_to(&storage, path).is_err());
+    assert_eq!(balance(&Database::load_from(&storage, path).unwrap()), "100");
+
+    // the new copy could not be made durable
+    storage.fail_sync();
+    assert!(accounts("250").save_to(&storage, path).is_err());
+    assert_eq!(balance(&Database::load(path).unwrap()), "100");
+
+    accounts("250").save_to(&storage, path).unwrap();
+    assert_eq!(balance(&Database::load_from(&storage, path).unwrap()), "250");
+    assert!(!std::path::Path::new(&format!("{}.tmp", path)).exists());
+    cleanup(path);
+}
+
+#[test]
+fn flipped_bits_fail_to_load() {
+    let path = temp_path("storage-flip", "bin");
+    let path = path.to_str().unwrap();
+    let storage = FaultyStorage::new(FileStorage);
+    accounts("100").save(path).unwrap();
+    let bits = std::fs::metadata(path).unwrap().len() as usize * 8;
+
+    // past the magic bytes, every bit is covered by the checksum
+    for bit in (64..bits).step_by(7) {
+        storage.flip_bit(bit);
+        let error = Database::load_from(&storage, path).err().unwrap_or_else(|| panic!("bit {} went unnoticed", bit));
+        assert!(!error.to_string().is_empty());
+    }
+    assert_eq!(balance(&Database::load_from(&storage, path).unwrap()), "100");
+    cleanup(path);
+}
+
+#[test]
+fn memory_storage_holds_saved_databases() {
+    let storage = MemoryStorage::new();
+    accounts("100").save_to(&storage, "bank").unwrap();
+    assert_eq!(storage.names(), ["bank"]);
+    assert_eq!(balance(&Database::load_from(&storage, "bank").unwrap()), "100");
+
+    storage.write("blob", b"0123456789").unwrap();
+    storage.append("blob", b"ab").unwrap();
+    assert_eq!(storage.read_page("blob", 8, 3).unwrap(), b"89a");
+    assert_eq!(storage.read_page("blob", 11, 3).unwrap(), b"b");
+    assert!(storage.read("missing").is_err());
+    assert!(Database::load_from(&storage, "missing").is_err());
+}
+
+// A backend of the kind an embedder would write: every byte is stored
+// scrambled, and only whole blobs are kept.
+#[derive(Default)]
+struct Scrambled(MemoryStorage);
+
+fn scramble(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().map(|b| b ^ 0x5a).collect()
+}
+
+impl StorageBackend for Scrambled {
+    fn read_page(&self, name: &str, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        Ok(scramble(&self.0.read_page(name, offset, len)?))
+    }
+
+    fn write(&self, name: &str, bytes: &[u8]) -> io::Result<()> {
+        self.0.write(name, &scramble(bytes))
+    }
+
+    fn append(&self, name: &str, bytes: &[u8]) -> io::Result<()> {
+        self.0.append(name, &scramble(bytes))
+    }
+
+    fn sync(&self, name: &str) -> io::Result<()> {
+        self.0.sync(name)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        self.0.rename(from, to)
+    }
+}
+
+#[test]
+fn custom_backends_hold_databases_and_audit_logs() {
+    let backend = Arc::new(Scrambled::default());
+    let mut db = accounts("100");
+    db.enable_audit(AuditSink::Backend(backend.clone(), "audit".to_string())).unwrap();
+    db.update("accounts", 1, row(&[("owner", "ann"), ("balance", "90")])).unwrap();
+    db.save_to(backend.as_ref(), "bank").unwrap();
+
+    assert!(!backend.0.read("bank").unwrap().windows(8).any(|w| w == b"POTATODB"));
+    assert_eq!(balance(&Database::load_from(backend.as_ref(), "bank").unwrap()), "90");
+    let entries = Database::read_audit_log(backend.as_ref(), "audit").unwrap();
+    assert_eq!(entries.iter().map(|e| e.kind()).collect::<Vec<_>>(), [ChangeKind::Update]);
+}
+
+#[test]
+fn audit_entries_cut_short_are_left_out() {
+    let storage = Arc::new(FaultyStorage::new(MemoryStorage::new()));
+    let mut db = accounts("100");
+    db.enable_audit(AuditSink::Backend(storage.clone(), "audit".to_string())).unwrap();
+    db.insert("accounts", 2, row(&[("owner", "bob")])).unwrap();
+    storage.short_write(6);
+    db.insert("accounts", 3, row(&[("owner", "cat")])).unwrap();
+    assert!(db.take_audit_error().is_some_and(|e| e.contains("short write")));
+
+    let entries = Database::read_audit_log(storage.as_ref(), "audit").unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].record(), 2);
+}
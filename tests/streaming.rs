@@ -0,0 +1,65 @@
+#![cfg(feature = "sql")]
+
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+
+use potatodb::{Database, QueryLimits};
+
+fn numbers(count: u64) -> Database {
+    let mut db = Database::new();
+    db.create_table("numbers".to_string()).unwrap();
+    for id in 1..=count {
+        let parity = if id % 2 == 0 { "even" } else { "odd" };
+        let data: HashMap<String, String> = [("n", id.to_string()), ("parity", parity.to_string())]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+        db.insert("numbers", id, data).unwrap();
+    }
+    db
+}
+
+#[test]
+fn rows_are_handed_over_one_by_one() {
+    let mut db = numbers(10);
+    let mut seen = Vec::new();
+    let rows = db.execute_sql_streaming("SELECT n FROM numbers WHERE parity = even", |record| {
+        seen.push(record.data()["n"].clone());
+        ControlFlow::Continue(())
+    }).unwrap();
+    assert_eq!(rows, 5);
+    assert_eq!(seen, ["2", "4", "6", "8", "10"]);
+}
+
+#[test]
+fn breaking_stops_the_scan() {
+    let mut db = numbers(5_000);
+    let mut ids = Vec::new();
+    let rows = db.execute_sql_streaming("SELECT * FROM numbers", |record| {
+        ids.push(record.id());
+        if ids.len() == 3 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+    }).unwrap();
+    assert_eq!(rows, 3);
+    assert_eq!(ids, [1, 2, 3]);
+}
+
+#[test]
+fn result_limits_do_not_apply() {
+    let mut db = numbers(100);
+    db.set_query_limits(QueryLimits { max_result_rows: Some(10), ..QueryLimits::default() });
+    assert!(db.execute_sql("SELECT * FROM numbers").is_err());
+    assert_eq!(db.execute_sql_streaming("SELECT * FROM numbers", |_| ControlFlow::Continue(())).unwrap(), 100);
+}
+
+#[test]
+fn other_statements_hand_over_their_rows() {
+    let mut db = numbers(4);
+    let mut updated = Vec::new();
+    let rows = db.execute_sql_streaming("UPDATE numbers SET parity = odd WHERE parity = even", |record| {
+        updated.push(record.id());
+        ControlFlow::Continue(())
+    }).unwrap();
+    assert_eq!(rows, 2);
+    assert_eq!(updated, [2, 4]);
+    assert!(db.execute_sql_streaming("SELECT * FROM missing", |_| ControlFlow::Continue(())).is_err());
+}
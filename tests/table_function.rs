@@ -0,0 +1,67 @@
+use potatodb::Database;
+
+fn values(db: &Database, sql: &str, column: &str) -> Vec<String> {
+    db.query_sql(sql).unwrap().iter().map(|r| r.data()[column].clone()).collect()
+}
+
+#[test]
+fn generate_series_counts_between_bounds() {
+    let db = Database::new();
+    assert_eq!(values(&db, "SELECT value FROM generate_series(1, 5)", "value"), ["1", "2", "3", "4", "5"]);
+    assert_eq!(values(&db, "SELECT value FROM generate_series(10, 1, -4)", "value"), ["10", "6", "2"]);
+    assert_eq!(values(&db, "SELECT value FROM generate_series(0, 1, 0.25) WHERE value > 0.5", "value"), ["0.75", "1"]);
+    assert_eq!(db.query_sql("SELECT COUNT(*) FROM generate_series(1, 100)").unwrap()[0].data()["COUNT(*)"], "100");
+    assert!(db.query_sql("SELECT value FROM generate_series(5, 1)").unwrap().is_empty());
+    assert!(db.query_sql("SELECT value FROM generate_series(1, 0, 2)").unwrap().is_empty());
+}
+
+#[test]
+fn integer_series_step_exactly() {
+    let db = Database::new();
+    let sql = "SELECT value FROM generate_series(9007199254740993, 9007199254740995)";
+    assert_eq!(values(&db, sql, "value"), ["9007199254740993", "9007199254740994", "9007199254740995"]);
+    let sql = "SELECT value FROM generate_series(9223372036854775806, 9223372036854775807)";
+    assert_eq!(values(&db, sql, "value"), ["9223372036854775806", "9223372036854775807"]);
+}
+
+#[test]
+fn long_series_are_refused() {
+    let db = Database::new();
+    let err = db.query_sql("SELECT COUNT(*) FROM generate_series(1, 100000000000)").unwrap_err();
+    assert!(err.contains("more than 1000000 rows"), "{}", err);
+    assert!(db.query_sql("SELECT COUNT(*) FROM generate_series(0, 1e11, 0.5)").is_err());
+}
+
+#[test]
+fn generate_series_builds_date_spines() {
+    let db = Database::new();
+    assert_eq!(values(&db, "SELECT value FROM generate_series('2024-02-27', '2024-03-02')", "value"), ["2024-02-27", "2024-02-28", "2024-02-29", "2024-03-01", "2024-03-02"]);
+    assert_eq!(values(&db, "SELECT value FROM generate_series('2023-12-25', '2024-01-08', 7)", "value"), ["2023-12-25", "2024-01-01", "2024-01-08"]);
+}
+
+#[test]
+fn bad_table_functions_are_rejected() {
+    let db = Database::new();
+    assert!(db.query_sql("SELECT value FROM generate_series(1, 5, 0)").unwrap_err().contains("cannot be zero"));
+    assert!(db.query_sql("SELECT value FROM generate_series(1)").unwrap_err().contains("takes a start"));
+    assert!(db.query_sql("SELECT value FROM generate_series(a, b)").unwrap_err().contains("Invalid bound"));
+    assert!(db.query_sql("SELECT value FROM unnest(1, 2)").unwrap_err().contains("Unknown table function"));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_each_explodes_arrays_and_objects() {
+    let db = Database::new();
+    let rows = db.query_sql("SELECT * FROM json_each('[\"a\", 2, null, [3]]')").unwrap();
+    let rows: Vec<(String, Option<String>, String)> = rows.iter()
+        .map(|r| (r.data()["key"].clone(), r.data().get("value").cloned(), r.data()["type"].clone()))
+        .collect();
+    assert_eq!(rows, [
+        ("0".to_string(), Some("a".to_string()), "text".to_string()),
+        ("1".to_string(), Some("2".to_string()), "integer".to_string()),
+        ("2".to_string(), None, "null".to_string()),
+        ("3".to_string(), Some("[3]".to_string()), "array".to_string()),
+    ]);
+    assert_eq!(values(&db, "SELECT key FROM json_each('{\"it''s\": 1, \"b\": 2}') WHERE value = 2", "key"), ["b"]);
+    assert!(db.query_sql("SELECT key FROM json_each('[1,')").unwrap_err().contains("Invalid JSON"));
+}
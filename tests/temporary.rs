@@ -0,0 +1,62 @@
+use potatodb::{Database, Privilege};
+
+mod common;
+use common::{row, temp_path};
+
+#[test]
+fn temp_tables_work_like_tables() {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TEMP TABLE staging").unwrap();
+    db.execute_sql("INSERT INTO staging (total) VALUES (12)").unwrap();
+    db.execute_sql("create temporary table scratch (status ENUM('new','done'))").unwrap();
+    db.execute_sql("INSERT INTO scratch (status) VALUES (new)").unwrap();
+    assert!(db.execute_sql("INSERT INTO scratch (status) VALUES (lost)").is_err());
+
+    let rows = db.query_sql("SELECT total FROM staging").unwrap();
+    assert_eq!(rows[0].data()["total"], "12");
+    let mut tables = db.temp_tables();
+    tables.sort();
+    assert_eq!(tables, ["scratch", "staging"]);
+
+    assert!(db.execute_sql("CREATE TEMP staging").is_err());
+    assert!(db.create_temp_table("staging").is_err());
+}
+
+#[test]
+fn temp_tables_are_not_saved() {
+    let path = temp_path("temporary", "bin");
+    let mut db = Database::new();
+    db.create_table("orders".to_string()).unwrap();
+    db.insert("orders", 1, row(&[("total", "5")])).unwrap();
+    db.create_temp_table("staging").unwrap();
+    db.insert("staging", 1, row(&[("total", "5")])).unwrap();
+    db.save(path.to_str().unwrap()).unwrap();
+
+    let loaded = Database::load(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.get_all("orders").unwrap().len(), 1);
+    assert!(loaded.get_all("staging").is_err());
+    // the session's own tables are untouched by saving
+    assert_eq!(db.get_all("staging").unwrap().len(), 1);
+}
+
+#[test]
+fn ending_the_session_drops_temp_tables() {
+    let mut db = Database::new();
+    db.create_table("orders".to_string()).unwrap();
+    db.create_temp_table("staging").unwrap();
+    db.end_session();
+    assert!(db.get_all("staging").is_err());
+    assert!(db.get_all("orders").is_ok());
+
+    db.create_role("jobs").unwrap();
+    db.grant("jobs", "staging", &[Privilege::Ddl, Privilege::Insert, Privilege::Select]).unwrap();
+    db.create_user("etl", &["jobs"]).unwrap();
+    db.set_session_user(Some("etl")).unwrap();
+    db.execute_sql("CREATE TEMP TABLE staging").unwrap();
+    // setting the same user keeps the session
+    db.set_session_user(Some("etl")).unwrap();
+    assert!(db.query_sql("SELECT * FROM staging").is_ok());
+    db.set_session_user(None).unwrap();
+    assert!(db.temp_tables().is_empty());
+}
@@ -0,0 +1,34 @@
+#![cfg(feature = "testing")]
+
+use potatodb::testing::{self, Predicate, Reference, Statement};
+
+#[test]
+fn workloads_are_reproducible() {
+    assert_eq!(testing::generate(3, 50), testing::generate(3, 50));
+    assert_ne!(testing::generate(3, 50), testing::generate(4, 50));
+    let statements = testing::generate(3, 50);
+    assert!(matches!(statements[0], Statement::CreateTable { .. }));
+    assert!(statements.iter().any(|s| matches!(s, Statement::Select { filter: Some(Predicate::And(..)), .. })));
+}
+
+#[test]
+fn the_engine_agrees_with_the_reference() {
+    for seed in 0..200 {
+        testing::check(seed, 150).unwrap();
+    }
+}
+
+#[test]
+fn the_reference_reuses_no_ids() {
+    let mut reference = Reference::default();
+    let run = |reference: &mut Reference, sql: Statement| reference.apply(&sql).unwrap();
+    run(&mut reference, Statement::CreateTable { table: "t".to_string() });
+    for value in ["a", "b"] {
+        run(&mut reference, Statement::Insert { table: "t".to_string(), columns: vec!["c0".to_string()], values: vec![value.to_string()] });
+    }
+    let filter = Some(Predicate::Compare("c0".to_string(), testing::Comparison::Equals, "a".to_string()));
+    assert_eq!(run(&mut reference, Statement::Delete { table: "t".to_string(), filter }).len(), 1);
+    let inserted = run(&mut reference, Statement::Insert { table: "t".to_string(), columns: vec!["c0".to_string()], values: vec!["x".to_string()] });
+    assert_eq!(inserted[0].0, 3);
+    assert!(reference.apply(&Statement::Delete { table: "missing".to_string(), filter: None }).is_err());
+}
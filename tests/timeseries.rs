@@ -0,0 +1,106 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use potatodb::Database;
+
+mod common;
+use common::{row, temp_path};
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+}
+
+fn readings() -> Database {
+    let mut db = Database::new();
+    db.create_time_series("cpu", None).unwrap();
+    // one reading every 20 seconds for three minutes
+    for (i, t) in (0..180_000).step_by(20_000).enumerate() {
+        db.append_point("cpu", t, row(&[("load", &(i * 10).to_string())])).unwrap();
+    }
+    db
+}
+
+#[test]
+fn points_are_appended_in_order_and_scanned_by_range() {
+    let mut db = readings();
+    let points = db.points("cpu", 40_000, 100_000).unwrap();
+    let times: Vec<u64> = points.iter().map(|p| p.id()).collect();
+    assert_eq!(times, vec![40_000, 60_000, 80_000]);
+    assert!(db.points("cpu", 500_000, 600_000).unwrap().is_empty());
+    assert!(db.points("cpu", 100_000, 40_000).unwrap().is_empty());
+
+    assert!(db.append_point("cpu", 160_000, row(&[("load", "1")])).is_err());
+    assert!(db.insert("cpu", 10, row(&[("load", "1")])).is_err());
+    // SQL inserts are stamped with the current time
+    db.execute_sql("INSERT INTO cpu (load) VALUES (5)").unwrap();
+    assert_eq!(db.points("cpu", now() - 60_000, now() + 1).unwrap().len(), 1);
+
+    db.create_table("plain".to_string()).unwrap();
+    assert!(db.points("plain", 0, 10).is_err());
+}
+
+#[test]
+fn sql_inserts_back_to_back_all_land() {
+    let mut db = Database::new();
+    db.create_time_series("cpu", None).unwrap();
+    for i in 0..200 {
+        db.execute_sql(&format!("INSERT INTO cpu (load) VALUES ({})", i)).unwrap();
+    }
+    let points = db.points("cpu", 0, u64::MAX).unwrap();
+    assert_eq!(points.len(), 200);
+    assert!(points.windows(2).all(|w| w[0].id() < w[1].id()));
+
+    // a clock behind the newest point still adds after it
+    let ahead = now() + 3_600_000;
+    db.append_point("cpu", ahead, row(&[("load", "1")])).unwrap();
+    db.execute_sql("INSERT INTO cpu (load) VALUES (2)").unwrap();
+    assert_eq!(db.points("cpu", ahead, u64::MAX).unwrap().iter().map(|p| p.id()).collect::<Vec<_>>(), [ahead, ahead + 1]);
+}
+
+#[test]
+fn old_points_fall_out_of_the_retention_window() {
+    let mut db = Database::new();
+    db.create_time_series("events", Some(Duration::from_secs(3600))).unwrap();
+    let now = now();
+    db.append_point("events", now - 7_200_000, row(&[("kind", "old")])).unwrap();
+    db.append_point("events", now - 1_800_000, row(&[("kind", "recent")])).unwrap();
+    db.append_point("events", now, row(&[("kind", "new")])).unwrap();
+    let kinds: Vec<&str> = db.get_all("events").unwrap().iter().map(|r| r.data()["kind"].as_str()).collect();
+    assert_eq!(kinds, vec!["recent", "new"]);
+    assert_eq!(db.get("events", now).unwrap().unwrap().data()["kind"], "new");
+    assert!(db.check_integrity().is_empty());
+}
+
+#[test]
+fn rollups_write_closed_buckets_once() {
+    let mut db = readings();
+    db.add_rollup("cpu", "cpu_1m", Duration::from_secs(60), &[("avg_load", "avg(load)"), ("samples", "count")]).unwrap();
+    // the bucket at 120s holds the newest point and stays open
+    assert_eq!(db.run_rollups("cpu").unwrap(), 2);
+    let minutes = db.points("cpu_1m", 0, u64::MAX).unwrap();
+    let averages: Vec<(u64, &str, &str)> = minutes.iter().map(|p| (p.id(), p.data()["avg_load"].as_str(), p.data()["samples"].as_str())).collect();
+    assert_eq!(averages, vec![(0, "10", "3"), (60_000, "40", "3")]);
+    assert_eq!(db.run_rollups("cpu").unwrap(), 0);
+
+    db.append_point("cpu", 200_000, row(&[("load", "100")])).unwrap();
+    assert_eq!(db.run_rollups("cpu").unwrap(), 1);
+    assert_eq!(db.get("cpu_1m", 120_000).unwrap().unwrap().data()["avg_load"], "70");
+
+    assert!(db.add_rollup("cpu", "cpu_x", Duration::ZERO, &[("n", "count")]).is_err());
+    assert!(db.add_rollup("cpu", "cpu_x", Duration::from_secs(1), &[("n", "mode(load)")]).is_err());
+}
+
+#[test]
+fn series_settings_are_saved() {
+    let mut db = readings();
+    db.add_rollup("cpu", "cpu_1m", Duration::from_secs(60), &[("peak", "max(load)")]).unwrap();
+    db.run_rollups("cpu").unwrap();
+    let path = temp_path("timeseries", "bin");
+    db.save(path.to_str().unwrap()).unwrap();
+    let mut loaded = Database::load(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(loaded.append_point("cpu", 0, row(&[("load", "1")])).is_err());
+    loaded.append_point("cpu", 200_000, row(&[("load", "1")])).unwrap();
+    assert_eq!(loaded.run_rollups("cpu").unwrap(), 1);
+    assert_eq!(loaded.get("cpu_1m", 120_000).unwrap().unwrap().data()["peak"], "80");
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::thread::sleep;
+use std::time::Duration;
+
+use potatodb::{Database, ProtoMessage, ProtoType, CREATED_AT, UPDATED_AT};
+
+mod common;
+use common::row;
+
+fn stamps(db: &Database, id: u64) -> (u64, u64) {
+    let data = db.get("notes", id).unwrap().unwrap().data();
+    (data[CREATED_AT].parse().unwrap(), data[UPDATED_AT].parse().unwrap())
+}
+
+fn notes() -> Database {
+    let mut db = Database::new();
+    db.create_table("notes".to_string()).unwrap();
+    db.keep_timestamps("notes", true).unwrap();
+    db
+}
+
+#[test]
+fn inserts_and_updates_are_stamped() {
+    let mut db = notes();
+    db.insert("notes", 1, row(&[("text", "a"), (CREATED_AT, "5")])).unwrap();
+    let (created, updated) = stamps(&db, 1);
+    assert!(created > 1_600_000_000_000);
+    assert_eq!(created, updated);
+
+    sleep(Duration::from_millis(5));
+    db.update("notes", 1, row(&[("text", "b"), (CREATED_AT, "5")])).unwrap();
+    let (still_created, updated) = stamps(&db, 1);
+    assert_eq!(still_created, created);
+    assert!(updated > created);
+
+    sleep(Duration::from_millis(5));
+    db.update_many("notes", HashMap::from([(1, row(&[("text", "c")]))])).unwrap();
+    let (still_created, last) = stamps(&db, 1);
+    assert_eq!(still_created, created);
+    assert!(last > updated);
+}
+
+#[test]
+fn sql_writes_are_stamped_and_stamps_can_be_queried() {
+    let mut db = notes();
+    db.execute_sql("INSERT INTO notes (text) VALUES (a)").unwrap();
+    let (created, _) = stamps(&db, 1);
+    sleep(Duration::from_millis(5));
+    db.execute_sql("UPDATE notes SET text = b WHERE text = a").unwrap();
+    let (still_created, updated) = stamps(&db, 1);
+    assert_eq!(still_created, created);
+    assert!(updated > created);
+
+    let rows = db.query_sql(&format!("SELECT text, updated_at FROM notes WHERE updated_at > {}", created)).unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].data()[UPDATED_AT], updated.to_string());
+}
+
+#[test]
+fn tables_without_timestamps_are_left_alone() {
+    let mut db = notes();
+    db.insert("notes", 1, row(&[("text", "a")])).unwrap();
+    db.keep_timestamps("notes", false).unwrap();
+    db.insert("notes", 2, row(&[("text", "b")])).unwrap();
+    assert!(!db.get("notes", 2).unwrap().unwrap().data().contains_key(UPDATED_AT));
+
+    // rows from before timestamps were kept get no created_at
+    db.keep_timestamps("notes", true).unwrap();
+    db.update("notes", 2, row(&[("text", "c"), (CREATED_AT, "5")])).unwrap();
+    let data = db.get("notes", 2).unwrap().unwrap().data();
+    assert!(!data.contains_key(CREATED_AT));
+    assert!(data.contains_key(UPDATED_AT));
+    assert!(db.keep_timestamps("missing", true).is_err());
+}
+
+#[test]
+fn typed_tables_need_timestamp_fields() {
+    let mut db = Database::new();
+    db.create_table("events".to_string()).unwrap();
+    db.set_table_proto("events", ProtoMessage::new("Event").field("kind", 1, ProtoType::String).unwrap()).unwrap();
+    assert!(db.keep_timestamps("events", true).unwrap_err().contains("created_at"));
+
+    let message = ProtoMessage::new("Event").field("kind", 1, ProtoType::String).unwrap()
+        .field(CREATED_AT, 2, ProtoType::Uint64).unwrap()
+        .field(UPDATED_AT, 3, ProtoType::Uint64).unwrap();
+    db.set_table_proto("events", message).unwrap();
+    db.keep_timestamps("events", true).unwrap();
+    db.insert("events", 1, row(&[("kind", "click")])).unwrap();
+    assert!(db.get("events", 1).unwrap().unwrap().data().contains_key(CREATED_AT));
+}
+
+#[test]
+fn timestamps_are_saved_with_the_table() {
+    let db = notes();
+    let mut loaded = Database::from_bytes(&db.to_bytes().unwrap()).unwrap();
+    loaded.insert("notes", 1, row(&[("text", "a")])).unwrap();
+    assert!(loaded.get("notes", 1).unwrap().unwrap().data().contains_key(CREATED_AT));
+}
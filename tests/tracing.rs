@@ -0,0 +1,69 @@
+#![cfg(feature = "tracing")]
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use potatodb::Database;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{Layer, Registry};
+
+type Spans = Arc<Mutex<Vec<(String, HashMap<String, String>)>>>;
+
+// Keeps every span with the fields recorded on it, in creation order.
+struct Recorder(Spans);
+
+struct Fields<'a>(&'a mut HashMap<String, String>);
+
+impl Visit for Fields<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.insert(field.name().to_string(), format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Recorder {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut spans = self.0.lock().unwrap();
+        let mut fields = HashMap::new();
+        attrs.record(&mut Fields(&mut fields));
+        ctx.span(id).unwrap().extensions_mut().insert(spans.len());
+        spans.push((attrs.metadata().name().to_string(), fields));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let position = *ctx.span(id).unwrap().extensions().get::<usize>().unwrap();
+        values.record(&mut Fields(&mut self.0.lock().unwrap()[position].1));
+    }
+}
+
+#[test]
+fn queries_open_spans_for_each_phase() {
+    let spans = Spans::default();
+    tracing::subscriber::with_default(Registry::default().with(Recorder(spans.clone())), || {
+        let mut db = Database::new();
+        db.create_table("users".to_string()).unwrap();
+        db.execute_sql("INSERT INTO users (name) VALUES (Alice)").unwrap();
+        db.query_sql("SELECT * FROM users WHERE name = Alice").unwrap();
+    });
+
+    let spans = spans.lock().unwrap();
+    let names: Vec<&str> = spans.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, [
+        "potatodb.query", "potatodb.parse", "potatodb.execute",
+        "potatodb.query", "potatodb.parse", "potatodb.execute", "potatodb.plan",
+    ]);
+    let select = &spans[3].1;
+    assert_eq!(select["kind"], "select");
+    assert_eq!(select["table"], "users");
+    assert_eq!(select["rows"], "1");
+    assert_eq!(spans[5].1["table"], "users");
+    assert_eq!(spans[6].1["rows"], "1");
+}
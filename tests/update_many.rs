@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+
+use potatodb::{ChangeKind, Database, ProtoMessage, ProtoType};
+
+mod common;
+use common::row;
+
+fn stock() -> Database {
+    let mut db = Database::new();
+    db.create_table("stock".to_string()).unwrap();
+    for id in 1..=1000 {
+        db.insert("stock", id, row(&[("qty", "0")])).unwrap();
+    }
+    db
+}
+
+#[test]
+fn updates_every_row_of_the_batch() {
+    let mut db = stock();
+    let seq = db.last_change_seq();
+    let updates: HashMap<u64, HashMap<String, String>> = (1..=1000).step_by(2).map(|id| (id, row(&[("qty", &id.to_string())]))).collect();
+    db.update_many("stock", updates).unwrap();
+
+    assert_eq!(db.get("stock", 7).unwrap().unwrap().data()["qty"], "7");
+    assert_eq!(db.get("stock", 8).unwrap().unwrap().data()["qty"], "0");
+    assert_eq!(db.query_sql("SELECT COUNT(*) FROM stock WHERE qty = 0").unwrap()[0].data()["COUNT(*)"], "500");
+    let changes: Vec<(ChangeKind, u64)> = db.changes_since(seq).map(|e| (e.kind(), e.record().id())).collect();
+    assert_eq!(changes.len(), 500);
+    assert_eq!(changes[..2], [(ChangeKind::Update, 1), (ChangeKind::Update, 3)]);
+}
+
+#[test]
+fn a_bad_row_leaves_the_table_unchanged() {
+    let mut db = stock();
+    let updates = HashMap::from([(1, row(&[("qty", "5")])), (5000, row(&[("qty", "5")]))]);
+    assert!(db.update_many("stock", updates).unwrap_err().contains("5000 not found"));
+    assert_eq!(db.get("stock", 1).unwrap().unwrap().data()["qty"], "0");
+
+    let message = ProtoMessage::new("Stock").field("qty", 1, ProtoType::Int32).unwrap();
+    db.set_table_proto("stock", message).unwrap();
+    let updates = HashMap::from([(1, row(&[("qty", "5")])), (2, row(&[("qty", "many")]))]);
+    assert!(db.update_many("stock", updates).is_err());
+    assert_eq!(db.get("stock", 1).unwrap().unwrap().data()["qty"], "0");
+    assert!(db.update_many("missing", HashMap::<u64, HashMap<String, String>>::new()).is_err());
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use potatodb::{AutoVacuum, Database, Maintenance, MemoryStorage};
+
+fn numbers(count: u64) -> Database {
+    let mut db = Database::new();
+    db.create_table("numbers".to_string()).unwrap();
+    db.create_table("letters".to_string()).unwrap();
+    for id in 1..=count {
+        db.insert("numbers", id, HashMap::from([("n".to_string(), id.to_string())])).unwrap();
+    }
+    db
+}
+
+#[test]
+fn tables_over_the_tombstone_ratio_are_compacted() {
+    let mut db = numbers(100);
+    for id in 1..=20 {
+        db.delete("numbers", id).unwrap();
+    }
+    assert_eq!(db.tombstone_ratio("numbers").unwrap(), 0.2);
+    assert_eq!(db.tombstone_ratio("letters").unwrap(), 0.0);
+    assert!(db.tombstone_ratio("missing").is_err());
+
+    let config = AutoVacuum { tombstone_ratio: 0.25, ..AutoVacuum::default() };
+    assert!(db.vacuum(&config).unwrap().compacted().is_empty());
+    db.delete("numbers", 21).unwrap();
+    db.delete("numbers", 22).unwrap();
+    db.delete("numbers", 23).unwrap();
+    db.delete("numbers", 24).unwrap();
+    db.delete("numbers", 25).unwrap();
+    let report = db.vacuum(&config).unwrap();
+    assert_eq!(report.compacted(), ["numbers"]);
+    assert!(!report.checkpointed());
+    assert_eq!(db.tombstone_ratio("numbers").unwrap(), 0.0);
+    assert_eq!(db.get_all("numbers").unwrap().len(), 75);
+}
+
+#[test]
+fn a_long_change_log_is_checkpointed() {
+    let mut db = numbers(50);
+    let storage = Arc::new(MemoryStorage::new());
+    let config = AutoVacuum {
+        max_logged_changes: 40,
+        checkpoint: Some((storage.clone(), "db".to_string())),
+        ..AutoVacuum::default()
+    };
+    let report = db.vacuum(&config).unwrap();
+    assert!(report.checkpointed());
+    assert!(report.bytes() > 0);
+    assert_eq!(db.changes_since(0).count(), 0);
+    assert_eq!(db.last_change_seq(), 50);
+    let saved = Database::load_from(storage.as_ref(), "db").unwrap();
+    assert_eq!(saved.get_all("numbers").unwrap().len(), 50);
+
+    db.delete("numbers", 1).unwrap();
+    assert!(!db.vacuum(&config).unwrap().checkpointed());
+}
+
+#[test]
+fn work_stops_at_the_io_budget() {
+    let mut db = numbers(10);
+    for id in 1..=10 {
+        db.insert("letters", id, HashMap::from([("c".to_string(), "x".to_string())])).unwrap();
+    }
+    for id in 1..=9 {
+        db.delete("numbers", id).unwrap();
+        db.delete("letters", id).unwrap();
+    }
+    let config = AutoVacuum { io_budget: 1, checkpoint: Some((Arc::new(MemoryStorage::new()), "db".to_string())), max_logged_changes: 0, ..AutoVacuum::default() };
+    let report = db.vacuum(&config).unwrap();
+    assert_eq!(report.compacted().len(), 1);
+    assert!(!report.checkpointed());
+    assert_eq!(db.vacuum(&config).unwrap().compacted().len(), 1);
+    assert!(db.vacuum(&config).unwrap().checkpointed());
+}
+
+#[test]
+fn maintenance_runs_in_the_background() {
+    let db = Arc::new(Mutex::new(numbers(10)));
+    for id in 1..=5 {
+        db.lock().unwrap().delete("numbers", id).unwrap();
+    }
+    let maintenance = Maintenance::start(db.clone(), AutoVacuum { interval: Duration::from_millis(10), ..AutoVacuum::default() });
+    for _ in 0..500 {
+        if maintenance.last_run().is_some() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(maintenance.last_run().unwrap().is_ok());
+    assert_eq!(db.lock().unwrap().tombstone_ratio("numbers").unwrap(), 0.0);
+}
@@ -0,0 +1,134 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use potatodb::{ColumnType, Database, Value};
+
+fn people() -> Database {
+    let mut db = Database::new();
+    db.create_table("people".to_string()).unwrap();
+    for (id, name, age) in [(1, "Ann", "30"), (2, "Bob", "100"), (3, "Cy", "7.5"), (4, "Di", "unknown")] {
+        let data = HashMap::from([("name".to_string(), name.to_string()), ("age".to_string(), age.to_string())]);
+        db.insert("people", id, data).unwrap();
+    }
+    db
+}
+
+fn ids(db: &Database, sql: &str) -> Vec<u64> {
+    db.query_sql(sql).unwrap().iter().map(|r| r.id()).collect()
+}
+
+#[test]
+fn stored_text_reads_as_values() {
+    assert_eq!(Value::parse("42"), Value::Int(42));
+    assert_eq!(Value::parse("-2.5"), Value::Float(-2.5));
+    assert_eq!(Value::parse("0.5"), Value::Float(0.5));
+    for text in ["007", "1e3", "+5", ".5", "5.", "-", "1.2.3"] {
+        assert_eq!(Value::parse(text), Value::Text(text.to_string()));
+    }
+    assert_eq!(Value::parse("true"), Value::Bool(true));
+    assert_eq!(Value::parse("x'cafe'"), Value::Blob(vec![0xca, 0xfe]));
+    assert_eq!(Value::parse("x'caf'"), Value::Text("x'caf'".to_string()));
+    assert_eq!(Value::parse("inf"), Value::Text("inf".to_string()));
+    assert_eq!(Value::parse("Ann"), Value::from("Ann"));
+
+    for text in ["42", "-2.5", "true", "x'cafe'", "Ann"] {
+        assert_eq!(Value::parse(text).to_text().unwrap(), text);
+    }
+    assert_eq!(Value::Null.to_text(), None);
+
+    let record = &people().query_sql("SELECT * FROM people WHERE name = Ann").unwrap()[0];
+    assert_eq!(record.value("age"), Value::Int(30));
+    assert!(record.value("email").is_null());
+}
+
+#[test]
+fn values_compare_and_compute_by_type() {
+    assert_eq!(Value::Int(30).compare(&Value::Int(100)), Some(Ordering::Less));
+    assert_eq!(Value::Int(2).compare(&Value::Float(2.0)), Some(Ordering::Equal));
+    assert_eq!(Value::from("30").compare(&Value::from("100")), Some(Ordering::Greater));
+    assert_eq!(Value::Int(1).compare(&Value::from("1")), None);
+    assert_eq!(Value::Null.compare(&Value::Null), None);
+
+    assert_eq!(Value::Int(2).checked_add(&Value::Int(3)), Some(Value::Int(5)));
+    assert_eq!(Value::Int(i64::MAX).checked_add(&Value::Int(1)), Some(Value::Float(i64::MAX as f64 + 1.0)));
+    assert_eq!(Value::Int(7).checked_div(&Value::Int(2)), Some(Value::Float(3.5)));
+    assert_eq!(Value::Int(8).checked_div(&Value::Int(2)), Some(Value::Int(4)));
+    assert_eq!(Value::Int(8).checked_div(&Value::Int(0)), None);
+    assert_eq!(Value::Float(1.5).checked_mul(&Value::Int(2)), Some(Value::Float(3.0)));
+    assert_eq!(Value::from("a").checked_sub(&Value::Int(1)), None);
+}
+
+#[test]
+fn where_compares_numbers_as_numbers() {
+    let mut db = people();
+    // "unknown" is not a number, so no comparison with one matches it
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE age > 5"), vec![1, 2, 3]);
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE age > 30"), vec![2]);
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE age != 30"), vec![2, 3, 4]);
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE age = unknown"), vec![4]);
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE age < 30"), vec![3]);
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE age = 30.0"), vec![1]);
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE age BETWEEN 8 AND 100"), vec![1, 2]);
+    assert_eq!(ids(&db, "SELECT * FROM people ORDER BY age"), vec![3, 1, 2, 4]);
+
+    db.execute_sql("UPDATE people SET age = age + 1 WHERE age < 50").unwrap();
+    assert_eq!(db.get("people", 1).unwrap().unwrap().data()["age"], "31");
+    assert_eq!(db.get("people", 3).unwrap().unwrap().data()["age"], "8.5");
+}
+
+#[test]
+fn generated_columns_keep_whole_numbers_whole() {
+    let mut db = people();
+    db.add_generated_column("people", "half GENERATED ALWAYS AS (age / 2) STORED").unwrap();
+    db.add_generated_column("people", "next GENERATED ALWAYS AS (age + 1) STORED").unwrap();
+    let row = |id| db.get("people", id).unwrap().unwrap();
+    assert_eq!(row(1).data()["half"], "15");
+    assert_eq!(row(3).data()["half"], "3.75");
+    assert_eq!(row(2).data()["next"], "101");
+    assert!(!row(4).data().contains_key("next"));
+}
+
+#[test]
+fn typed_columns_store_values_as_their_type() {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE people (name VARCHAR(40), age INTEGER, height REAL, active BOOLEAN, avatar BLOB, mood ENUM('ok', 'meh'))").unwrap();
+    assert_eq!(db.column_type("people", "age").unwrap(), Some(ColumnType::Integer));
+    assert_eq!(db.column_type("people", "name").unwrap(), Some(ColumnType::Text));
+    assert_eq!(db.column_type("people", "mood").unwrap(), None);
+
+    let row = |pairs: &[(&str, &str)]| pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<HashMap<_, _>>();
+    db.insert("people", 1, row(&[("name", "Ann"), ("age", "007"), ("height", "1.70"), ("active", "TRUE"), ("avatar", "X'CAFE'")])).unwrap();
+    let ann = db.get("people", 1).unwrap().unwrap();
+    assert_eq!(ann.data()["age"], "7");
+    assert_eq!(ann.data()["height"], "1.7");
+    assert_eq!(ann.value("active"), Value::Bool(true));
+    assert_eq!(ann.value("avatar"), Value::Blob(vec![0xca, 0xfe]));
+
+    // values that aren't of the column's type are refused
+    let error = db.execute_sql("INSERT INTO people (name, age) VALUES (Bob, unknown)").unwrap_err();
+    assert_eq!(error, "Cannot convert 'unknown' in column 'age' to Integer");
+    assert!(db.execute_sql("UPDATE people SET age = 7.5").is_err());
+    assert!(db.execute_sql("UPDATE people SET age = age + 0.5").is_err());
+    assert!(db.update("people", 1, row(&[("active", "maybe")])).is_err());
+    assert_eq!(db.get("people", 1).unwrap().unwrap().data()["age"], "7");
+
+    // text columns compare as text, so "10" comes before "9"
+    db.execute_sql("INSERT INTO people (name, age) VALUES (10, 30)").unwrap();
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE name > 9"), vec![1]);
+    assert_eq!(ids(&db, "SELECT * FROM people WHERE age > 9"), vec![2]);
+
+    let loaded = Database::from_bytes(&db.to_bytes().unwrap()).unwrap();
+    assert_eq!(loaded.column_type("people", "active").unwrap(), Some(ColumnType::Boolean));
+}
+
+#[test]
+fn only_plain_numbers_compare_as_numbers() {
+    let mut db = Database::new();
+    db.execute_sql("CREATE TABLE places").unwrap();
+    db.execute_sql("INSERT INTO places (zip, code) VALUES (00501, 1e3)").unwrap();
+    db.execute_sql("INSERT INTO places (zip, code) VALUES (501, 1000)").unwrap();
+    assert_eq!(ids(&db, "SELECT * FROM places WHERE zip = 00501"), [1]);
+    assert_eq!(ids(&db, "SELECT * FROM places WHERE zip = 501"), [2]);
+    assert_eq!(ids(&db, "SELECT * FROM places WHERE code = 1000"), [2]);
+    assert_eq!(ids(&db, "SELECT * FROM places WHERE code = 1e3"), [1]);
+}
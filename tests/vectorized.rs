@@ -0,0 +1,70 @@
+use potatodb::{Database, ProtoMessage, ProtoType, Record};
+
+mod common;
+use common::row;
+
+fn readings(count: u64) -> Database {
+    let mut db = Database::new();
+    db.create_table("readings".to_string()).unwrap();
+    let message = ProtoMessage::new("Reading")
+        .field("sensor", 1, ProtoType::String).unwrap()
+        .field("celsius", 2, ProtoType::Double).unwrap()
+        .field("seq", 3, ProtoType::Int64).unwrap();
+    db.set_table_proto("readings", message).unwrap();
+    for id in 1..=count {
+        let sensor = if id % 2 == 0 { "north" } else { "south" };
+        let celsius = format!("{}", (id % 50) as f64 - 10.5);
+        db.insert("readings", id, row(&[("sensor", sensor), ("celsius", &celsius), ("seq", &id.to_string())])).unwrap();
+    }
+    db
+}
+
+fn ids(records: &[Record]) -> Vec<u64> {
+    records.iter().map(Record::id).collect()
+}
+
+#[test]
+fn typed_numeric_columns_compare_as_numbers() {
+    let db = readings(12);
+    // 9 < 10 < 11 as numbers, though "10" < "9" as text
+    assert_eq!(ids(&db.query_sql("SELECT * FROM readings WHERE seq > 9").unwrap()), [10, 11, 12]);
+    assert_eq!(ids(&db.query_sql("SELECT * FROM readings WHERE celsius < -8").unwrap()), [1, 2]);
+    assert_eq!(ids(&db.query_sql("SELECT * FROM readings WHERE seq = 7.0").unwrap()), [7]);
+    assert_eq!(db.query_sql("SELECT * FROM readings WHERE seq != 7").unwrap().len(), 11);
+    // text columns still compare as text
+    assert_eq!(db.query_sql("SELECT * FROM readings WHERE sensor > o").unwrap().len(), 6);
+}
+
+#[test]
+fn between_includes_both_ends() {
+    let mut db = readings(12);
+    assert_eq!(ids(&db.query_sql("SELECT * FROM readings WHERE seq BETWEEN 4 AND 10").unwrap()), [4, 5, 6, 7, 8, 9, 10]);
+    assert_eq!(ids(&db.query_sql("SELECT * FROM readings WHERE sensor = north AND seq between 3 and 6").unwrap()), [4, 6]);
+    assert_eq!(ids(&db.query_sql("SELECT * FROM readings WHERE seq BETWEEN 11 AND 1").unwrap()), Vec::<u64>::new());
+    let plan = db.query_sql("EXPLAIN SELECT * FROM readings WHERE seq BETWEEN 4 AND 10").unwrap();
+    assert_eq!(plan[1].data()["detail"], "seq BETWEEN 4 AND 10");
+
+    db.create_table("names".to_string()).unwrap();
+    for (id, name) in [(1, "ann"), (2, "bob"), (3, "cat")] {
+        db.insert("names", id, row(&[("name", name)])).unwrap();
+    }
+    assert_eq!(ids(&db.query_sql("SELECT * FROM names WHERE name BETWEEN b AND cat").unwrap()), [2, 3]);
+}
+
+#[test]
+fn large_scans_match_row_by_row_evaluation() {
+    let mut db = readings(5000);
+    let queries = [
+        ("seq > 4095", 905),
+        ("celsius BETWEEN 0 AND 9.5 AND sensor = north", 500),
+        ("celsius = 38.5 AND seq != 49", 99),
+    ];
+    for (predicate, expected) in queries {
+        let rows = db.query_sql(&format!("SELECT seq, celsius, sensor FROM readings WHERE {}", predicate)).unwrap();
+        assert_eq!(rows.len(), expected, "{}", predicate);
+    }
+
+    assert_eq!(db.execute_sql("UPDATE readings SET sensor = east WHERE seq BETWEEN 1001 AND 3000").unwrap().len(), 2000);
+    assert_eq!(db.execute_sql("DELETE FROM readings WHERE seq > 999").unwrap().len(), 4001);
+    assert_eq!(db.query_sql("SELECT * FROM readings").unwrap().len(), 999);
+}
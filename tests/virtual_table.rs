@@ -0,0 +1,79 @@
+use std::sync::{Arc, Mutex};
+
+use potatodb::{Database, Predicate, ProtoMessage, ProtoType, Row, VirtualTable};
+
+// Sensor readings kept in a Vec, filtering on `sensor = ...` itself.
+struct Readings {
+    readings: Vec<(&'static str, f64)>,
+    pushed: Arc<Mutex<Vec<Predicate>>>,
+}
+
+impl VirtualTable for Readings {
+    fn scan(&self) -> Result<Vec<Row>, String> {
+        self.scan_where(&[])
+    }
+
+    fn schema(&self) -> Option<ProtoMessage> {
+        ProtoMessage::new("Reading")
+            .field("sensor", 1, ProtoType::String).unwrap()
+            .field("celsius", 2, ProtoType::Double).ok()
+    }
+
+    fn scan_where(&self, predicates: &[Predicate]) -> Result<Vec<Row>, String> {
+        *self.pushed.lock().unwrap() = predicates.to_vec();
+        let sensor = predicates.iter().find_map(|p| match p {
+            Predicate::Equals(column, value) if column == "sensor" => Some(value.as_str()),
+            _ => None,
+        });
+        Ok(self.readings.iter()
+            .filter(|(name, _)| sensor.map_or(true, |s| s == *name))
+            .map(|(name, celsius)| Row::from([("sensor".to_string(), name.to_string()), ("celsius".to_string(), celsius.to_string())]))
+            .collect())
+    }
+}
+
+fn readings() -> (Database, Arc<Mutex<Vec<Predicate>>>) {
+    let pushed = Arc::new(Mutex::new(Vec::new()));
+    let source = Readings { readings: vec![("attic", 9.5), ("attic", 31.0), ("cellar", 12.0)], pushed: pushed.clone() };
+    let mut db = Database::new();
+    db.attach_virtual("readings", source).unwrap();
+    (db, pushed)
+}
+
+#[test]
+fn and_ed_comparisons_are_pushed_down() {
+    let (db, pushed) = readings();
+    let rows = db.query_sql("SELECT celsius FROM readings WHERE sensor = attic AND celsius > 10").unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].data()["celsius"], "31");
+    assert_eq!(*pushed.lock().unwrap(), [
+        Predicate::Equals("sensor".to_string(), "attic".to_string()),
+        Predicate::GreaterThan("celsius".to_string(), "10".to_string()),
+    ]);
+
+    // either side of an OR may match, so neither is pushed down
+    assert_eq!(db.query_sql("SELECT sensor FROM readings WHERE sensor = attic OR sensor = cellar").unwrap().len(), 3);
+    assert!(pushed.lock().unwrap().is_empty());
+}
+
+#[test]
+fn the_schema_types_columns() {
+    let (db, _) = readings();
+    // celsius is a double, so 9.5 is less than 10 rather than greater
+    assert_eq!(db.query_sql("SELECT sensor FROM readings WHERE celsius > 10").unwrap().len(), 2);
+    assert!(db.query_sql("SELECT humidity FROM readings").unwrap_err().contains("humidity"));
+}
+
+#[test]
+fn sources_without_pushdown_are_filtered_by_the_engine() {
+    struct Numbers;
+    impl VirtualTable for Numbers {
+        fn scan(&self) -> Result<Vec<Row>, String> {
+            Ok((1..=5).map(|n| Row::from([("n".to_string(), n.to_string())])).collect())
+        }
+    }
+    let mut db = Database::new();
+    db.attach_virtual("numbers", Numbers).unwrap();
+    assert_eq!(db.query_sql("SELECT n FROM numbers WHERE n BETWEEN 2 AND 4").unwrap().len(), 3);
+    assert_eq!(db.query_sql("EXPLAIN SELECT n FROM numbers").unwrap().len(), 2);
+}
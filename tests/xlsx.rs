@@ -0,0 +1,65 @@
+#![cfg(feature = "xlsx")]
+
+use std::io::Read;
+use std::path::PathBuf;
+
+use potatodb::Database;
+
+mod common;
+use common::temp_path;
+
+fn read_entry(path: &PathBuf, entry: &str) -> String {
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(path).unwrap()).unwrap();
+    let mut contents = String::new();
+    archive.by_name(entry).unwrap().read_to_string(&mut contents).unwrap();
+    contents
+}
+
+#[test]
+fn export_rejects_statements_other_than_select() {
+    let mut db = Database::new();
+    db.create_table("users".to_string()).unwrap();
+    db.execute_sql("INSERT INTO users (name) VALUES (Alice)").unwrap();
+
+    let path = temp_path("reject", "xlsx");
+    assert!(db.export_xlsx("DELETE FROM users", &path).is_err());
+    assert_eq!(db.get_all("users").unwrap().len(), 1);
+    assert!(!path.exists());
+}
+
+#[test]
+fn only_exact_numbers_become_numeric_cells() {
+    let mut db = Database::new();
+    db.create_table("codes".to_string()).unwrap();
+    db.execute_sql("INSERT INTO codes (a, b, c) VALUES (30, 007, 12345678901234567)").unwrap();
+
+    let path = temp_path("cells", "xlsx");
+    db.export_xlsx("SELECT * FROM codes", &path).unwrap();
+    let sheet = read_entry(&path, "xl/worksheets/sheet1.xml");
+    let strings = read_entry(&path, "xl/sharedStrings.xml");
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(sheet.contains(r#"<c r="B2"><v>30</v></c>"#));
+    assert!(strings.contains("<t>007</t>"));
+    assert!(strings.contains("<t>12345678901234567</t>"));
+}
+
+#[test]
+fn sheet_names_are_sanitized_and_unique() {
+    let mut db = Database::new();
+    let long = "a".repeat(40);
+    for name in [format!("{}1", long), format!("{}2", long), "x'".to_string(), "X'".to_string(), "a/b".to_string()] {
+        db.create_table(name).unwrap();
+    }
+
+    let path = temp_path("sheets", "xlsx");
+    db.export_tables_xlsx(&path).unwrap();
+    let workbook = read_entry(&path, "xl/workbook.xml");
+    std::fs::remove_file(&path).unwrap();
+
+    let a31 = "a".repeat(31);
+    let a29 = "a".repeat(29);
+    for name in [a31, format!("{}~2", a29), "X".to_string(), "x~2".to_string(), "a_b".to_string()] {
+        assert!(workbook.contains(&format!(r#"name="{}""#, name)), "missing sheet {}", name);
+    }
+}